@@ -0,0 +1,96 @@
+//! Procedural macros for eventbus-rust
+//!
+//! Use them through `eventbus_rust` with the `macros` feature rather than
+//! depending on this crate directly; the generated code refers to
+//! `::eventbus_rust` paths.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{DeriveInput, Error, LitInt, LitStr};
+
+/// Implement `eventbus_rust::core::Event` for a payload type
+///
+/// ```ignore
+/// use eventbus_rust::core::Event;
+///
+/// #[derive(Serialize, Deserialize, Event)]
+/// #[event(topic = "orders.created", schema_version = 2)]
+/// struct OrderCreated {
+///     order_id: String,
+///     total_cents: u64,
+/// }
+/// ```
+///
+/// - `topic` is required and must be a concrete topic, without wildcards
+/// - `schema_version` defaults to 1, the version of events that declare none
+///
+/// The type must also implement `Serialize` and `Deserialize`.
+#[proc_macro_derive(Event, attributes(event))]
+pub fn derive_event(item: TokenStream) -> TokenStream {
+    let input = syn::parse_macro_input!(item as DeriveInput);
+    expand(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// Arguments of `#[event(...)]`
+struct EventArgs {
+    topic: LitStr,
+    schema_version: Option<LitInt>,
+}
+
+fn event_args(input: &DeriveInput) -> syn::Result<EventArgs> {
+    let mut topic: Option<LitStr> = None;
+    let mut schema_version: Option<LitInt> = None;
+
+    for attr in input.attrs.iter().filter(|attr| attr.path().is_ident("event")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("topic") {
+                topic = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("schema_version") {
+                schema_version = Some(meta.value()?.parse()?);
+            } else {
+                let name = meta.path.get_ident().map(|ident| ident.to_string()).unwrap_or_default();
+                return Err(meta.error(format!("unknown event argument `{}`", name)));
+            }
+            Ok(())
+        })?;
+    }
+
+    let topic = topic.ok_or_else(|| {
+        Error::new_spanned(&input.ident, "expected the topic, as in #[event(topic = \"orders.created\")]")
+    })?;
+    let name = topic.value();
+    if name.is_empty() {
+        return Err(Error::new(topic.span(), "topic cannot be empty"));
+    }
+    if name.contains('*') {
+        return Err(Error::new(topic.span(), "event topics cannot contain wildcards"));
+    }
+    if let Some(version) = &schema_version {
+        if version.base10_parse::<u32>()? == 0 {
+            return Err(Error::new(version.span(), "schema versions start at 1"));
+        }
+    }
+
+    Ok(EventArgs { topic, schema_version })
+}
+
+fn expand(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let args = event_args(&input)?;
+    let name = &input.ident;
+    let (impl_generics, type_generics, where_clause) = input.generics.split_for_impl();
+    let topic = &args.topic;
+    let schema_version = match &args.schema_version {
+        Some(version) => quote!(#version),
+        None => quote!(::eventbus_rust::core::DEFAULT_SCHEMA_VERSION),
+    };
+
+    Ok(quote! {
+        impl #impl_generics ::eventbus_rust::core::Event for #name #type_generics #where_clause {
+            const TOPIC: &'static str = #topic;
+            const SCHEMA_VERSION: u32 = #schema_version;
+        }
+    })
+}
@@ -0,0 +1,76 @@
+//! Emit throughput with and without a persistent backend
+//!
+//! Run with `cargo bench --features benchmarks --bench emit`.
+
+use std::sync::Arc;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use eventbus_rust::core::{EventBus, EventEnvelope};
+use eventbus_rust::service::{EventBusService, ServiceConfig};
+use eventbus_rust::storage::{MemoryStorage, SqliteStorage};
+use serde_json::json;
+
+fn runtime() -> tokio::runtime::Runtime {
+    tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap()
+}
+
+fn config() -> ServiceConfig {
+    ServiceConfig {
+        max_memory_events: 100_000,
+        max_concurrent_emits: 10_000,
+        ..Default::default()
+    }
+}
+
+fn events(count: usize) -> Vec<EventEnvelope> {
+    (0..count)
+        .map(|i| EventEnvelope::new(format!("bench.topic{}", i % 8), json!({ "seq": i, "body": "x".repeat(256) })))
+        .collect()
+}
+
+/// A bus per backend; each is dropped before the next is measured, so
+/// events piling up in one do not slow down the others
+async fn bus(backend: &str, dir: &std::path::Path) -> EventBusService {
+    let service = EventBusService::new(config());
+    let service = match backend {
+        "memory" => service,
+        "persistent_memory" => service.with_storage(Arc::new(MemoryStorage::new())),
+        _ => {
+            let path = dir.join(format!("{}.db", backend));
+            service.with_storage(Arc::new(SqliteStorage::new(&path.to_string_lossy()).await.unwrap()))
+        }
+    };
+    service.start().await.unwrap();
+    service
+}
+
+fn emitting(c: &mut Criterion) {
+    let runtime = runtime();
+    let dir = tempfile::tempdir().unwrap();
+
+    let mut group = c.benchmark_group("emit");
+    group.sample_size(20);
+    for backend in ["memory", "persistent_memory", "sqlite"] {
+        let bus = runtime.block_on(bus(backend, dir.path()));
+        for batch in [1, 100] {
+            group.throughput(Throughput::Elements(batch as u64));
+            group.bench_with_input(BenchmarkId::new(backend, batch), &batch, |b, &batch| {
+                b.iter_batched(
+                    || events(batch),
+                    |events| runtime.block_on(async {
+                        if batch == 1 {
+                            bus.emit(events.into_iter().next().unwrap()).await.unwrap();
+                        } else {
+                            bus.emit_batch(events).await.unwrap();
+                        }
+                    }),
+                    criterion::BatchSize::SmallInput,
+                )
+            });
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, emitting);
+criterion_main!(benches);
@@ -79,7 +79,7 @@ fn create_custom_config() -> MultiBusConfig {
         "users".to_string(),
         ServiceConfig {
             instance_id: "users".to_string(),
-            storage: StorageConfig::Memory,
+            storage: StorageConfig::memory(),
             max_concurrent_emits: 100,
             max_events_per_second: Some(1000),
             event_buffer_size: 10000,
@@ -96,7 +96,7 @@ fn create_custom_config() -> MultiBusConfig {
         "system".to_string(),
         ServiceConfig {
             instance_id: "system".to_string(),
-            storage: StorageConfig::Memory, // Fallback to memory
+            storage: StorageConfig::memory(), // Fallback to memory
             max_concurrent_emits: 25,
             max_events_per_second: Some(200),
             event_buffer_size: 2000,
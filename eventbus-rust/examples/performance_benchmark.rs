@@ -122,6 +122,7 @@ async fn run_postgres_benchmark(postgres_url: &str) -> std::result::Result<(), B
         storage: StorageConfig::Postgres { 
             url: postgres_url.to_string(),
             pool_size: 20,
+            enable_partitioning: false,
         },
         enable_metrics: true,
         ..ServiceConfig::default()
@@ -165,7 +166,7 @@ async fn run_storage_comparison_test() -> std::result::Result<(), Box<dyn std::e
     // Test memory storage
     let memory_config = ServiceConfig {
         instance_id: "memory-comparison".to_string(),
-        storage: StorageConfig::Memory,
+        storage: StorageConfig::memory(),
         enable_metrics: true,
         ..ServiceConfig::default()
     };
@@ -225,7 +226,7 @@ async fn run_stress_test() -> std::result::Result<(), Box<dyn std::error::Error
     let config = ServiceConfig {
         instance_id: "stress-test".to_string(),
         max_concurrent_emits: 200,
-        storage: StorageConfig::Memory,
+        storage: StorageConfig::memory(),
         enable_metrics: true,
         ..ServiceConfig::default()
     };
@@ -290,7 +291,7 @@ async fn run_stress_test() -> std::result::Result<(), Box<dyn std::error::Error
 async fn run_latency_tests() -> std::result::Result<(), Box<dyn std::error::Error + Send + Sync>> {
     let config = ServiceConfig {
         instance_id: "latency-test".to_string(),
-        storage: StorageConfig::Memory,
+        storage: StorageConfig::memory(),
         enable_metrics: true,
         ..ServiceConfig::default()
     };
@@ -51,7 +51,7 @@ async fn main() -> std::result::Result<(), Box<dyn std::error::Error + Send + Sy
     tokio::select! {
         result = rpc_server.start(listen_addr) => {
             match result {
-                Ok(_) => println!("✅ EventBus JSON-RPC server started successfully"),
+                Ok(_) => println!("✅ EventBus JSON-RPC server stopped"),
                 Err(e) => {
                     eprintln!("❌ Failed to start EventBus JSON-RPC server: {}", e);
                     process::exit(1);
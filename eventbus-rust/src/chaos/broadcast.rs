@@ -0,0 +1,112 @@
+//! Fault hooks for subscriber delivery
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use parking_lot::RwLock;
+use tokio::sync::broadcast;
+
+use crate::core::EventEnvelope;
+use super::FaultRng;
+
+/// Faults applied when events are broadcast to subscribers
+#[derive(Debug, Clone, Default)]
+pub struct BroadcastFaultConfig {
+    /// Probability (0.0 - 1.0) that an event is not delivered
+    pub drop_rate: f64,
+    /// Probability that an event is delivered twice
+    pub duplicate_rate: f64,
+    /// Probability that delivery is delayed by `delay`
+    pub delay_rate: f64,
+    /// Delay applied to delayed events (they may arrive out of order)
+    pub delay: Duration,
+}
+
+/// Broadcast fault hooks attached to an event bus service
+///
+/// Storage is unaffected; only delivery to live subscribers is disturbed.
+#[derive(Debug)]
+pub struct BroadcastFaults {
+    config: RwLock<BroadcastFaultConfig>,
+    rng: FaultRng,
+    dropped: AtomicU64,
+    duplicated: AtomicU64,
+    delayed: AtomicU64,
+}
+
+impl BroadcastFaults {
+    /// Create broadcast fault hooks
+    pub fn new(config: BroadcastFaultConfig) -> Self {
+        Self {
+            config: RwLock::new(config),
+            rng: FaultRng::from_entropy(),
+            dropped: AtomicU64::new(0),
+            duplicated: AtomicU64::new(0),
+            delayed: AtomicU64::new(0),
+        }
+    }
+
+    /// Use a deterministic random source
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = FaultRng::seeded(seed);
+        self
+    }
+
+    /// Replace the fault configuration at runtime
+    pub fn set_config(&self, config: BroadcastFaultConfig) {
+        *self.config.write() = config;
+    }
+
+    /// Current fault configuration
+    pub fn config(&self) -> BroadcastFaultConfig {
+        self.config.read().clone()
+    }
+
+    /// Number of events dropped
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    /// Number of events delivered twice
+    pub fn duplicated(&self) -> u64 {
+        self.duplicated.load(Ordering::Relaxed)
+    }
+
+    /// Number of events delivered late
+    pub fn delayed(&self) -> u64 {
+        self.delayed.load(Ordering::Relaxed)
+    }
+
+    /// Deliver an event through the configured faults
+    pub(crate) fn deliver(&self, sender: &broadcast::Sender<EventEnvelope>, event: EventEnvelope) {
+        let config = self.config();
+
+        if self.rng.roll(config.drop_rate) {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+            return;
+        }
+
+        let copies = if self.rng.roll(config.duplicate_rate) {
+            self.duplicated.fetch_add(1, Ordering::Relaxed);
+            2
+        } else {
+            1
+        };
+
+        if self.rng.roll(config.delay_rate) {
+            self.delayed.fetch_add(1, Ordering::Relaxed);
+            let sender = sender.clone();
+            tokio::spawn(async move {
+                tokio::time::sleep(config.delay).await;
+                for _ in 0..copies {
+                    let _ = sender.send(event.clone());
+                }
+            });
+            return;
+        }
+
+        for _ in 0..copies {
+            let _ = sender.send(event.clone());
+        }
+    }
+}
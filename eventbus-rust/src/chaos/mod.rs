@@ -0,0 +1,162 @@
+//! Failure injection for storage and broadcast
+//!
+//! Available with the `chaos` feature. [`FaultInjectingStorage`] wraps any
+//! [`EventStorage`](crate::core::traits::EventStorage) backend and
+//! [`BroadcastFaults`] can be attached to an
+//! [`EventBusService`](crate::service::EventBusService) to disturb delivery
+//! to subscribers, so producers and consumers can be exercised against
+//! outages before they happen in production.
+//!
+//! All faults are drawn from a seedable random source, making a failing run
+//! reproducible with the same seed.
+
+mod storage;
+mod broadcast;
+
+pub use storage::{FaultConfig, FaultInjectingStorage};
+pub use broadcast::{BroadcastFaultConfig, BroadcastFaults};
+
+use std::time::Duration;
+
+use parking_lot::Mutex;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+/// Shared random source for fault decisions
+#[derive(Debug)]
+pub(crate) struct FaultRng {
+    rng: Mutex<StdRng>,
+}
+
+impl FaultRng {
+    /// Random source seeded from the OS
+    pub(crate) fn from_entropy() -> Self {
+        Self {
+            rng: Mutex::new(StdRng::from_entropy()),
+        }
+    }
+
+    /// Deterministic random source
+    pub(crate) fn seeded(seed: u64) -> Self {
+        Self {
+            rng: Mutex::new(StdRng::seed_from_u64(seed)),
+        }
+    }
+
+    /// Returns true with the given probability
+    pub(crate) fn roll(&self, rate: f64) -> bool {
+        if rate <= 0.0 {
+            return false;
+        }
+        self.rng.lock().gen_bool(rate.min(1.0))
+    }
+
+    /// Uniform index in `0..len`
+    pub(crate) fn index(&self, len: usize) -> usize {
+        self.rng.lock().gen_range(0..len)
+    }
+
+    /// Base latency plus a uniform jitter of up to `jitter`
+    pub(crate) fn latency(&self, base: Duration, jitter: Duration) -> Duration {
+        if jitter.is_zero() {
+            return base;
+        }
+        let extra = self.rng.lock().gen_range(0..=jitter.as_millis() as u64);
+        base + Duration::from_millis(extra)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use futures::StreamExt;
+    use serde_json::json;
+
+    use crate::core::traits::{EventBus, EventStorage};
+    use crate::core::{EventEnvelope, EventQuery};
+    use crate::service::{EventBusService, ServiceConfig};
+    use crate::storage::MemoryStorage;
+
+    fn storage_with(config: FaultConfig) -> (Arc<MemoryStorage>, FaultInjectingStorage) {
+        let inner = Arc::new(MemoryStorage::new());
+        let storage = FaultInjectingStorage::new(inner.clone())
+            .with_config(config)
+            .with_seed(7);
+        (inner, storage)
+    }
+
+    #[tokio::test]
+    async fn test_outage_and_heal() {
+        let (_, storage) = storage_with(FaultConfig::default());
+        let event = EventEnvelope::new("chaos.test", json!({}));
+
+        assert!(storage.store(&event).await.is_ok());
+
+        storage.outage();
+        let err = storage.store(&event).await.unwrap_err();
+        assert!(err.is_retryable());
+        assert!(storage.query(&EventQuery::new()).await.is_err());
+        assert_eq!(storage.injected_faults(), 2);
+
+        storage.heal();
+        assert!(storage.store(&event).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_partial_batch_failure() {
+        let (inner, storage) = storage_with(FaultConfig::default().with_partial_batch_rate(1.0));
+        let events: Vec<_> = (0..10)
+            .map(|i| EventEnvelope::new("chaos.batch", json!({ "index": i })))
+            .collect();
+
+        assert!(storage.store_batch(&events).await.is_err());
+
+        let stored = inner.query(&EventQuery::new().with_topic("chaos.batch")).await.unwrap();
+        assert!(stored.len() < events.len());
+    }
+
+    #[tokio::test]
+    async fn test_latency_injection() {
+        let (_, storage) = storage_with(FaultConfig::default().with_latency(Duration::from_millis(30)));
+        let started = std::time::Instant::now();
+
+        storage.store(&EventEnvelope::new("chaos.slow", json!({}))).await.unwrap();
+        assert!(started.elapsed() >= Duration::from_millis(30));
+    }
+
+    #[tokio::test]
+    async fn test_seeded_faults_are_reproducible() {
+        let decisions = |seed| {
+            let rng = FaultRng::seeded(seed);
+            (0..32).map(|_| rng.roll(0.5)).collect::<Vec<_>>()
+        };
+        assert_eq!(decisions(42), decisions(42));
+    }
+
+    #[tokio::test]
+    async fn test_broadcast_drop_and_duplicate() {
+        let faults = Arc::new(BroadcastFaults::new(BroadcastFaultConfig {
+            drop_rate: 1.0,
+            ..Default::default()
+        }).with_seed(1));
+        let service = EventBusService::new(ServiceConfig::default())
+            .with_broadcast_faults(faults.clone());
+        let mut stream = service.subscribe("chaos.*").await.unwrap();
+
+        service.emit(EventEnvelope::new("chaos.dropped", json!({}))).await.unwrap();
+        assert_eq!(faults.dropped(), 1);
+
+        faults.set_config(BroadcastFaultConfig {
+            duplicate_rate: 1.0,
+            ..Default::default()
+        });
+        service.emit(EventEnvelope::new("chaos.duplicated", json!({}))).await.unwrap();
+
+        let first = stream.next().await.unwrap();
+        let second = stream.next().await.unwrap();
+        assert_eq!(first.topic, "chaos.duplicated");
+        assert_eq!(first.event_id, second.event_id);
+        assert_eq!(faults.duplicated(), 1);
+    }
+}
@@ -0,0 +1,204 @@
+//! Fault-injecting storage wrapper
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use parking_lot::RwLock;
+
+use crate::core::traits::{EventStorage, IdempotencyStorage, StorageStats};
+use crate::core::{EventBusError, EventBusResult, EventEnvelope, EventQuery};
+use super::FaultRng;
+
+/// Faults applied to storage operations
+#[derive(Debug, Clone, Default)]
+pub struct FaultConfig {
+    /// Probability (0.0 - 1.0) that an operation fails
+    pub error_rate: f64,
+    /// Latency added before every operation
+    pub latency: Duration,
+    /// Upper bound of random latency added on top of `latency`
+    pub latency_jitter: Duration,
+    /// Probability that a batch store fails after storing only part of the batch
+    pub partial_batch_rate: f64,
+}
+
+impl FaultConfig {
+    /// Every operation fails
+    pub fn outage() -> Self {
+        Self {
+            error_rate: 1.0,
+            ..Default::default()
+        }
+    }
+
+    /// Set the operation error rate
+    pub fn with_error_rate(mut self, rate: f64) -> Self {
+        self.error_rate = rate;
+        self
+    }
+
+    /// Set the latency added before every operation
+    pub fn with_latency(mut self, latency: Duration) -> Self {
+        self.latency = latency;
+        self
+    }
+
+    /// Set the random latency jitter
+    pub fn with_latency_jitter(mut self, jitter: Duration) -> Self {
+        self.latency_jitter = jitter;
+        self
+    }
+
+    /// Set the partial batch failure rate
+    pub fn with_partial_batch_rate(mut self, rate: f64) -> Self {
+        self.partial_batch_rate = rate;
+        self
+    }
+}
+
+/// Storage wrapper that injects errors, latency and partial batch failures
+///
+/// Injected errors are [`EventBusError::Storage`] and therefore retryable,
+/// like a real backend outage. `initialize` is never disturbed.
+pub struct FaultInjectingStorage {
+    inner: Arc<dyn EventStorage>,
+    config: RwLock<FaultConfig>,
+    rng: FaultRng,
+    injected: AtomicU64,
+}
+
+impl FaultInjectingStorage {
+    /// Wrap a storage backend with no faults configured
+    pub fn new(inner: Arc<dyn EventStorage>) -> Self {
+        Self {
+            inner,
+            config: RwLock::new(FaultConfig::default()),
+            rng: FaultRng::from_entropy(),
+            injected: AtomicU64::new(0),
+        }
+    }
+
+    /// Set the initial fault configuration
+    pub fn with_config(self, config: FaultConfig) -> Self {
+        *self.config.write() = config;
+        self
+    }
+
+    /// Use a deterministic random source
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = FaultRng::seeded(seed);
+        self
+    }
+
+    /// Replace the fault configuration at runtime
+    pub fn set_config(&self, config: FaultConfig) {
+        *self.config.write() = config;
+    }
+
+    /// Current fault configuration
+    pub fn config(&self) -> FaultConfig {
+        self.config.read().clone()
+    }
+
+    /// Fail every operation until [`heal`](Self::heal) is called
+    pub fn outage(&self) {
+        self.set_config(FaultConfig::outage());
+    }
+
+    /// Remove all faults
+    pub fn heal(&self) {
+        self.set_config(FaultConfig::default());
+    }
+
+    /// Number of faults injected so far
+    pub fn injected_faults(&self) -> u64 {
+        self.injected.load(Ordering::Relaxed)
+    }
+
+    /// Wrapped storage backend
+    pub fn inner(&self) -> &Arc<dyn EventStorage> {
+        &self.inner
+    }
+
+    /// Apply latency and possibly fail before an operation
+    async fn disturb(&self, operation: &str) -> EventBusResult<()> {
+        let config = self.config();
+
+        let delay = self.rng.latency(config.latency, config.latency_jitter);
+        if !delay.is_zero() {
+            tokio::time::sleep(delay).await;
+        }
+
+        if self.rng.roll(config.error_rate) {
+            self.injected.fetch_add(1, Ordering::Relaxed);
+            return Err(EventBusError::storage(format!("Injected fault: {} failed", operation)));
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl EventStorage for FaultInjectingStorage {
+    fn backend_name(&self) -> &str {
+        self.inner.backend_name()
+    }
+    
+    fn idempotency_storage(self: Arc<Self>) -> Option<Arc<dyn IdempotencyStorage>> {
+        self.inner.clone().idempotency_storage()
+    }
+    
+    async fn initialize(&self) -> EventBusResult<()> {
+        self.inner.initialize().await
+    }
+
+    async fn store(&self, event: &EventEnvelope) -> EventBusResult<()> {
+        self.disturb("store").await?;
+        self.inner.store(event).await
+    }
+
+    async fn store_batch(&self, events: &[EventEnvelope]) -> EventBusResult<()> {
+        self.disturb("store_batch").await?;
+
+        let partial_rate = self.config.read().partial_batch_rate;
+        if !events.is_empty() && self.rng.roll(partial_rate) {
+            let stored = self.rng.index(events.len());
+            self.inner.store_batch(&events[..stored]).await?;
+            self.injected.fetch_add(1, Ordering::Relaxed);
+            return Err(EventBusError::storage(format!(
+                "Injected fault: store_batch failed after {} of {} events",
+                stored,
+                events.len()
+            )));
+        }
+
+        self.inner.store_batch(events).await
+    }
+
+    async fn query(&self, query: &EventQuery) -> EventBusResult<Vec<EventEnvelope>> {
+        self.disturb("query").await?;
+        self.inner.query(query).await
+    }
+
+    async fn get_stats(&self) -> EventBusResult<StorageStats> {
+        self.disturb("get_stats").await?;
+        self.inner.get_stats().await
+    }
+
+    async fn cleanup(&self, before_timestamp: i64) -> EventBusResult<u64> {
+        self.disturb("cleanup").await?;
+        self.inner.cleanup(before_timestamp).await
+    }
+    
+    async fn cleanup_topic(&self, topic: &str, before_timestamp: i64) -> EventBusResult<u64> {
+        self.disturb("cleanup_topic").await?;
+        self.inner.cleanup_topic(topic, before_timestamp).await
+    }
+    
+    async fn purge_expired(&self, now: i64) -> EventBusResult<u64> {
+        self.disturb("purge_expired").await?;
+        self.inner.purge_expired(now).await
+    }
+}
@@ -5,8 +5,13 @@ use std::net::SocketAddr;
 use serde::{Deserialize, Serialize};
 
 use crate::core::EventBusError;
+use crate::utils::topic_matches_pattern;
 
 /// Configuration for a single event bus instance
+/// 
+/// The per-instance entry of the `MultiInstanceConfig` file format. Buses run
+/// with `service::ServiceConfig`, which this converts into with `From`;
+/// `service::MultiBusConfig::from_file` migrates whole files.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EventBusConfig {
     /// Unique identifier for this instance
@@ -83,7 +88,8 @@ impl EventBusConfig {
         self.persist = true;
         self.storage = Some(StorageConfig::Postgres {
             url: url.into(),
-            pool_size: 10,
+            pool_size: default_pool_size(),
+            enable_partitioning: false,
         });
         self
     }
@@ -109,35 +115,69 @@ impl EventBusConfig {
 }
 
 /// Storage backend configuration
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// 
+/// The single storage type used by `ServiceConfig`, `EventBusConfig` and
+/// `create_storage`. Deserializing also accepts the field names of the former
+/// `storage::StorageConfig` (`database_url`, `max_connections`).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(tag = "type")]
 pub enum StorageConfig {
     /// SQLite storage
     Sqlite {
         /// Database file path
+        #[serde(alias = "database_url")]
         path: String,
     },
     
     /// PostgreSQL storage
     Postgres {
         /// Database connection URL
+        #[serde(alias = "database_url")]
         url: String,
         /// Connection pool size
-        #[serde(default = "default_pool_size")]
+        #[serde(default = "default_pool_size", alias = "max_connections")]
         pool_size: u32,
+        /// Whether to partition the events table by time
+        #[serde(default)]
+        enable_partitioning: bool,
     },
     
-
-    
-    /// In-memory storage (for testing)
-    Memory,
+    /// In-memory storage (for testing); nothing survives a restart
+    Memory {
+        /// Maximum number of events kept per topic; the oldest are evicted
+        /// past it (0 for no limit)
+        #[serde(default = "default_memory_max_events")]
+        max_events: usize,
+    },
 }
 
 fn default_pool_size() -> u32 {
     10
 }
 
+fn default_memory_max_events() -> usize {
+    10000
+}
+
+impl Default for StorageConfig {
+    fn default() -> Self {
+        Self::memory()
+    }
+}
 
+impl StorageConfig {
+    /// In-memory storage with the default per-topic limit
+    pub fn memory() -> Self {
+        StorageConfig::Memory {
+            max_events: default_memory_max_events(),
+        }
+    }
+    
+    /// Whether events outlive the process
+    pub fn is_persistent(&self) -> bool {
+        !matches!(self, StorageConfig::Memory { .. })
+    }
+}
 
 /// Rule engine configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -205,6 +245,31 @@ pub struct RetentionConfig {
     /// How often to run cleanup in seconds
     #[serde(default = "default_cleanup_interval")]
     pub cleanup_interval_seconds: u64,
+    
+    /// Per-topic overrides of `max_age_seconds`, checked in order
+    #[serde(default)]
+    pub topic_overrides: Vec<TopicRetention>,
+}
+
+/// Retention override for topics matching a pattern
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TopicRetention {
+    /// Topic pattern (`*` and `**` wildcards)
+    pub pattern: String,
+    
+    /// Maximum age of events in seconds (0 = no limit)
+    pub max_age_seconds: u64,
+}
+
+/// Retention applied to a single topic
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EffectiveRetention {
+    /// Maximum age of events in seconds (0 = no limit)
+    pub max_age_seconds: u64,
+    
+    /// Pattern of the override in effect, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pattern: Option<String>,
 }
 
 fn default_cleanup_interval() -> u64 {
@@ -217,10 +282,82 @@ impl Default for RetentionConfig {
             max_age_seconds: 0, // No limit by default
             max_events: 0,      // No limit by default
             cleanup_interval_seconds: default_cleanup_interval(),
+            topic_overrides: Vec::new(),
         }
     }
 }
 
+impl RetentionConfig {
+    /// Override the maximum age for topics matching a pattern
+    /// 
+    /// Overrides are checked in the order they were added; the first match wins.
+    pub fn with_topic_override(mut self, pattern: impl Into<String>, max_age_seconds: u64) -> Self {
+        self.topic_overrides.push(TopicRetention {
+            pattern: pattern.into(),
+            max_age_seconds,
+        });
+        self
+    }
+    
+    /// Retention for a topic: the first matching override, else the bus default
+    pub fn for_topic(&self, topic: &str) -> EffectiveRetention {
+        self.topic_overrides
+            .iter()
+            .find(|rule| topic_matches_pattern(topic, &rule.pattern))
+            .map(|rule| EffectiveRetention {
+                max_age_seconds: rule.max_age_seconds,
+                pattern: Some(rule.pattern.clone()),
+            })
+            .unwrap_or(EffectiveRetention {
+                max_age_seconds: self.max_age_seconds,
+                pattern: None,
+            })
+    }
+}
+
+/// Event checksum and hash chain configuration (see `core::integrity`)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IntegrityConfig {
+    /// Stamp a content checksum on every stored event
+    #[serde(default)]
+    pub checksums: bool,
+    
+    /// Topic patterns whose events are also linked in a hash chain per topic
+    /// (checksummed even when `checksums` is off)
+    #[serde(default)]
+    pub chained_topics: Vec<String>,
+    
+    /// Check the checksums of polled events, failing the poll on a mismatch
+    #[serde(default = "default_verify_on_read")]
+    pub verify_on_read: bool,
+}
+
+fn default_verify_on_read() -> bool {
+    true
+}
+
+impl Default for IntegrityConfig {
+    fn default() -> Self {
+        Self {
+            checksums: false,
+            chained_topics: Vec::new(),
+            verify_on_read: default_verify_on_read(),
+        }
+    }
+}
+
+impl IntegrityConfig {
+    /// Whether any event gets a checksum
+    pub fn is_enabled(&self) -> bool {
+        self.checksums || !self.chained_topics.is_empty()
+    }
+    
+    /// Whether events of a topic are hash-chained
+    pub fn is_chained(&self, topic: &str) -> bool {
+        self.chained_topics.iter().any(|pattern| topic_matches_pattern(topic, pattern))
+    }
+}
+
 /// Transport layer configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TransportConfig {
@@ -243,6 +380,12 @@ pub struct TransportConfig {
     /// Maximum number of concurrent connections
     #[serde(default = "default_max_connections")]
     pub max_connections: u32,
+    
+    /// Token callers pass as the `admin_token` parameter of admin methods
+    /// (`set_mode`, `promote`); without it, admin methods are only served to
+    /// callers an auth layer gave the `admin` role
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub admin_token: Option<String>,
 }
 
 fn default_connect_timeout() -> u64 {
@@ -273,10 +416,38 @@ impl Default for TransportConfig {
             write_timeout_ms: default_write_timeout(),
             max_message_size: default_max_message_size(),
             max_connections: default_max_connections(),
+            admin_token: None,
         }
     }
 }
 
+/// Policy deciding which topics a bus accepts events for
+/// 
+/// Topics created through the admin API are always accepted.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum TopicPolicy {
+    /// Any topic is accepted without validation
+    #[default]
+    Open,
+    
+    /// Unknown topics are created on first emit if their name is already
+    /// in normalized form (see `normalize_topic`)
+    AutoCreate,
+    
+    /// Only the listed topics are accepted
+    AllowList {
+        /// Accepted topic names
+        topics: Vec<String>,
+    },
+    
+    /// Topics matching one of the patterns are accepted (`*` and `**` wildcards)
+    Patterns {
+        /// Accepted topic patterns
+        patterns: Vec<String>,
+    },
+}
+
 /// Event bus instance that combines configuration with runtime state
 #[derive(Debug)]
 pub struct EventBusInstance {
@@ -512,4 +683,53 @@ mod tests {
         
         assert!(config.validate().is_err());
     }
+    
+    #[test]
+    fn test_storage_config_formats() {
+        let memory: StorageConfig = serde_json::from_value(serde_json::json!({ "type": "Memory" })).unwrap();
+        assert_eq!(memory, StorageConfig::memory());
+        assert!(!memory.is_persistent());
+        
+        // Field names of the former storage::StorageConfig are accepted
+        let postgres: StorageConfig = serde_json::from_value(serde_json::json!({
+            "type": "Postgres",
+            "database_url": "postgres://localhost/events",
+            "max_connections": 4
+        })).unwrap();
+        assert_eq!(postgres, StorageConfig::Postgres {
+            url: "postgres://localhost/events".to_string(),
+            pool_size: 4,
+            enable_partitioning: false,
+        });
+        
+        let sqlite = StorageConfig::Sqlite { path: "events.db".to_string() };
+        let value = serde_json::to_value(&sqlite).unwrap();
+        assert_eq!(value, serde_json::json!({ "type": "Sqlite", "path": "events.db" }));
+        assert!(serde_json::from_value::<StorageConfig>(value).unwrap().is_persistent());
+    }
+    
+    #[test]
+    fn test_retention_topic_overrides() {
+        let retention = RetentionConfig {
+            max_age_seconds: 7 * 86400,
+            ..Default::default()
+        }
+        .with_topic_override("audit.**", 365 * 86400)
+        .with_topic_override("telemetry.*", 86400)
+        .with_topic_override("telemetry.**", 0);
+        
+        assert_eq!(retention.for_topic("audit.login.failed").max_age_seconds, 365 * 86400);
+        assert_eq!(retention.for_topic("telemetry.cpu").pattern.as_deref(), Some("telemetry.*"));
+        assert_eq!(retention.for_topic("orders.created"), EffectiveRetention {
+            max_age_seconds: 7 * 86400,
+            pattern: None,
+        });
+        
+        let parsed: RetentionConfig = serde_json::from_value(serde_json::json!({
+            "max_age_seconds": 60,
+            "topic_overrides": [{ "pattern": "audit.*", "max_age_seconds": 0 }]
+        })).unwrap();
+        assert_eq!(parsed.for_topic("audit.login").max_age_seconds, 0);
+        assert_eq!(parsed.cleanup_interval_seconds, 3600);
+    }
 } 
\ No newline at end of file
@@ -84,6 +84,18 @@ pub enum EventBusError {
     /// Rate limiting errors
     #[error("Rate limited: {message}")]
     RateLimited { message: String },
+    
+    /// Topic rejected by the bus topic policy
+    #[error("Topic not allowed: '{topic}' ({reason})")]
+    TopicNotAllowed { topic: String, reason: String },
+    
+    /// Operation rejected by the bus mode (read-only or maintenance)
+    #[error("Unavailable: {message}")]
+    Unavailable { message: String },
+    
+    /// Stored event no longer matches its checksum
+    #[error("Integrity violation: {message}")]
+    IntegrityViolation { message: String },
 }
 
 impl EventBusError {
@@ -201,6 +213,28 @@ impl EventBusError {
         }
     }
     
+    /// Create a topic not allowed error
+    pub fn topic_not_allowed(topic: impl Into<String>, reason: impl Into<String>) -> Self {
+        Self::TopicNotAllowed {
+            topic: topic.into(),
+            reason: reason.into(),
+        }
+    }
+    
+    /// Create an unavailable error
+    pub fn unavailable(message: impl Into<String>) -> Self {
+        Self::Unavailable {
+            message: message.into(),
+        }
+    }
+    
+    /// Create an integrity violation error
+    pub fn integrity(message: impl Into<String>) -> Self {
+        Self::IntegrityViolation {
+            message: message.into(),
+        }
+    }
+    
     /// Check if this error is retryable
     pub fn is_retryable(&self) -> bool {
         match self {
@@ -231,6 +265,9 @@ impl EventBusError {
             Self::ResourceLimit { .. } => "resource_limit",
             Self::Validation { .. } => "validation",
             Self::RateLimited { .. } => "rate_limited",
+            Self::TopicNotAllowed { .. } => "topic_not_allowed",
+            Self::Unavailable { .. } => "unavailable",
+            Self::IntegrityViolation { .. } => "integrity",
         }
     }
 }
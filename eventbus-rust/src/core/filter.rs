@@ -0,0 +1,485 @@
+//! Filter expressions over events
+//!
+//! A small boolean language used by rule conditions and filtered
+//! subscriptions, for example `payload.priority == "high" && priority >= 100`.
+//!
+//! - Paths name envelope fields (`topic`, `event_id`, `timestamp`, `priority`,
+//!   `source_trn`, `target_trn`, `correlation_id`, `sequence_number`) or walk
+//!   into `payload.*` and `metadata.*`. Any other first segment is looked up in
+//!   the payload, as rule `match_fields` do.
+//! - Literals are JSON strings, numbers, `true`, `false` and `null`.
+//! - Comparisons are `==`, `!=`, `<`, `<=`, `>`, `>=`; ordering applies to
+//!   numbers and strings only. A bare path is true when the value is present
+//!   and neither `false` nor `null`.
+//! - `!`, `&&`, `||` and parentheses combine conditions.
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::Value;
+
+use crate::core::{EventBusError, EventBusResult, EventEnvelope};
+
+/// Longest expression accepted, in bytes
+const MAX_EXPRESSION_LEN: usize = 4096;
+
+/// Deepest nesting of `!` and parentheses accepted
+const MAX_NESTING: usize = 32;
+
+/// A parsed filter expression, serialized as its source text
+#[derive(Clone)]
+pub struct EventFilter {
+    source: String,
+    expr: Expr,
+}
+
+impl EventFilter {
+    /// Parse a filter expression
+    pub fn parse(source: impl Into<String>) -> EventBusResult<Self> {
+        let source = source.into();
+        if source.len() > MAX_EXPRESSION_LEN {
+            return Err(EventBusError::validation(format!(
+                "Filter expression is longer than {} bytes",
+                MAX_EXPRESSION_LEN
+            )));
+        }
+        let tokens = tokenize(&source)?;
+        let mut parser = Parser { tokens, pos: 0, depth: 0 };
+        let expr = parser.parse_or()?;
+        if let Some(token) = parser.peek() {
+            return Err(EventBusError::validation(format!(
+                "Unexpected {} in filter expression",
+                token
+            )));
+        }
+        Ok(Self { source, expr })
+    }
+
+    /// Expression source text
+    pub fn as_str(&self) -> &str {
+        &self.source
+    }
+
+    /// Check whether the event satisfies the expression
+    pub fn matches(&self, event: &EventEnvelope) -> bool {
+        self.expr.eval(event)
+    }
+}
+
+impl fmt::Debug for EventFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("EventFilter").field(&self.source).finish()
+    }
+}
+
+impl fmt::Display for EventFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+impl PartialEq for EventFilter {
+    fn eq(&self, other: &Self) -> bool {
+        self.source == other.source
+    }
+}
+
+impl FromStr for EventFilter {
+    type Err = EventBusError;
+
+    fn from_str(s: &str) -> EventBusResult<Self> {
+        Self::parse(s)
+    }
+}
+
+impl Serialize for EventFilter {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.source)
+    }
+}
+
+impl<'de> Deserialize<'de> for EventFilter {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let source = String::deserialize(deserializer)?;
+        Self::parse(source).map_err(serde::de::Error::custom)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CompareOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Debug, Clone)]
+enum Expr {
+    Compare(Vec<String>, CompareOp, Value),
+    Truthy(Vec<String>),
+    Not(Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+}
+
+impl Expr {
+    fn eval(&self, event: &EventEnvelope) -> bool {
+        match self {
+            Expr::Compare(path, op, expected) => {
+                let actual = resolve(event, path);
+                compare(actual.as_ref(), *op, expected)
+            }
+            Expr::Truthy(path) => !matches!(
+                resolve(event, path),
+                None | Some(Value::Null) | Some(Value::Bool(false))
+            ),
+            Expr::Not(inner) => !inner.eval(event),
+            Expr::And(left, right) => left.eval(event) && right.eval(event),
+            Expr::Or(left, right) => left.eval(event) || right.eval(event),
+        }
+    }
+}
+
+/// Look up a dotted path on the event
+fn resolve(event: &EventEnvelope, path: &[String]) -> Option<Value> {
+    let (head, rest) = path.split_first()?;
+    let root = match head.as_str() {
+        "topic" => Value::String(event.topic.clone()),
+        "event_id" => Value::String(event.event_id.clone()),
+        "timestamp" => Value::from(event.timestamp),
+        "priority" => Value::from(event.priority),
+        "source_trn" => Value::String(event.source_trn.clone()?),
+        "target_trn" => Value::String(event.target_trn.clone()?),
+        "correlation_id" => Value::String(event.correlation_id.clone()?),
+        "sequence_number" => Value::from(event.sequence_number?),
+        "payload" => return descend(&event.payload, rest).cloned(),
+        "metadata" => return descend(event.metadata.as_ref()?, rest).cloned(),
+        _ => return descend(&event.payload, path).cloned(),
+    };
+    descend(&root, rest).cloned()
+}
+
+fn descend<'a>(mut value: &'a Value, path: &[String]) -> Option<&'a Value> {
+    for segment in path {
+        value = match value {
+            Value::Object(map) => map.get(segment)?,
+            Value::Array(items) => items.get(segment.parse::<usize>().ok()?)?,
+            _ => return None,
+        };
+    }
+    Some(value)
+}
+
+fn compare(actual: Option<&Value>, op: CompareOp, expected: &Value) -> bool {
+    let actual = actual.unwrap_or(&Value::Null);
+    match op {
+        CompareOp::Eq => values_equal(actual, expected),
+        CompareOp::Ne => !values_equal(actual, expected),
+        _ => {
+            let ordering = match (actual, expected) {
+                (Value::Number(a), Value::Number(b)) => {
+                    a.as_f64().zip(b.as_f64()).and_then(|(a, b)| a.partial_cmp(&b))
+                }
+                (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+                _ => None,
+            };
+            match ordering {
+                Some(ordering) => match op {
+                    CompareOp::Lt => ordering.is_lt(),
+                    CompareOp::Le => ordering.is_le(),
+                    CompareOp::Gt => ordering.is_gt(),
+                    CompareOp::Ge => ordering.is_ge(),
+                    CompareOp::Eq | CompareOp::Ne => unreachable!(),
+                },
+                None => false,
+            }
+        }
+    }
+}
+
+/// JSON equality that treats `1` and `1.0` as the same number
+fn values_equal(a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (Value::Number(a), Value::Number(b)) => a == b || a.as_f64() == b.as_f64(),
+        _ => a == b,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Path(Vec<String>),
+    Literal(Value),
+    Op(CompareOp),
+    And,
+    Or,
+    Not,
+    Open,
+    Close,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Path(path) => write!(f, "'{}'", path.join(".")),
+            Token::Literal(value) => write!(f, "{}", value),
+            Token::Op(op) => write!(f, "'{:?}'", op),
+            Token::And => f.write_str("'&&'"),
+            Token::Or => f.write_str("'||'"),
+            Token::Not => f.write_str("'!'"),
+            Token::Open => f.write_str("'('"),
+            Token::Close => f.write_str("')'"),
+        }
+    }
+}
+
+fn tokenize(source: &str) -> EventBusResult<Vec<Token>> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+        match c {
+            c if c.is_whitespace() => i += 1,
+            '(' => {
+                tokens.push(Token::Open);
+                i += 1;
+            }
+            ')' => {
+                tokens.push(Token::Close);
+                i += 1;
+            }
+            '&' if next == Some('&') => {
+                tokens.push(Token::And);
+                i += 2;
+            }
+            '|' if next == Some('|') => {
+                tokens.push(Token::Or);
+                i += 2;
+            }
+            '=' if next == Some('=') => {
+                tokens.push(Token::Op(CompareOp::Eq));
+                i += 2;
+            }
+            '!' if next == Some('=') => {
+                tokens.push(Token::Op(CompareOp::Ne));
+                i += 2;
+            }
+            '!' => {
+                tokens.push(Token::Not);
+                i += 1;
+            }
+            '<' | '>' => {
+                let inclusive = next == Some('=');
+                tokens.push(Token::Op(match (c, inclusive) {
+                    ('<', false) => CompareOp::Lt,
+                    ('<', true) => CompareOp::Le,
+                    ('>', false) => CompareOp::Gt,
+                    _ => CompareOp::Ge,
+                }));
+                i += if inclusive { 2 } else { 1 };
+            }
+            '"' => {
+                // Find the closing quote, skipping escaped characters, and let
+                // serde_json handle the escapes
+                let start = i;
+                i += 1;
+                while i < chars.len() && chars[i] != '"' {
+                    i += if chars[i] == '\\' { 2 } else { 1 };
+                }
+                if i >= chars.len() {
+                    return Err(EventBusError::validation("Unterminated string in filter expression"));
+                }
+                i += 1;
+                let text: String = chars[start..i].iter().collect();
+                let value: Value = serde_json::from_str(&text).map_err(|e| {
+                    EventBusError::validation(format!("Invalid string {} in filter expression: {}", text, e))
+                })?;
+                tokens.push(Token::Literal(value));
+            }
+            c if c.is_ascii_digit() || c == '-' => {
+                let start = i;
+                i += 1;
+                while i < chars.len() && (chars[i].is_ascii_alphanumeric() || matches!(chars[i], '.' | '-' | '+')) {
+                    i += 1;
+                }
+                let text: String = chars[start..i].iter().collect();
+                let value: serde_json::Number = text.parse().map_err(|_| {
+                    EventBusError::validation(format!("Invalid number '{}' in filter expression", text))
+                })?;
+                tokens.push(Token::Literal(Value::Number(value)));
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let start = i;
+                while i < chars.len() && (chars[i].is_alphanumeric() || matches!(chars[i], '_' | '.')) {
+                    i += 1;
+                }
+                let text: String = chars[start..i].iter().collect();
+                tokens.push(match text.as_str() {
+                    "true" => Token::Literal(Value::Bool(true)),
+                    "false" => Token::Literal(Value::Bool(false)),
+                    "null" => Token::Literal(Value::Null),
+                    _ => {
+                        let path: Vec<String> = text.split('.').map(str::to_string).collect();
+                        if path.iter().any(|segment| segment.is_empty()) {
+                            return Err(EventBusError::validation(format!(
+                                "Invalid path '{}' in filter expression",
+                                text
+                            )));
+                        }
+                        Token::Path(path)
+                    }
+                });
+            }
+            _ => {
+                return Err(EventBusError::validation(format!(
+                    "Unexpected character '{}' in filter expression",
+                    c
+                )))
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    depth: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn parse_or(&mut self) -> EventBusResult<Expr> {
+        let mut expr = self.parse_and()?;
+        while self.peek() == Some(&Token::Or) {
+            self.pos += 1;
+            expr = Expr::Or(Box::new(expr), Box::new(self.parse_and()?));
+        }
+        Ok(expr)
+    }
+
+    fn parse_and(&mut self) -> EventBusResult<Expr> {
+        let mut expr = self.parse_unary()?;
+        while self.peek() == Some(&Token::And) {
+            self.pos += 1;
+            expr = Expr::And(Box::new(expr), Box::new(self.parse_unary()?));
+        }
+        Ok(expr)
+    }
+
+    fn parse_unary(&mut self) -> EventBusResult<Expr> {
+        match self.next() {
+            Some(Token::Not) => {
+                self.enter()?;
+                let inner = self.parse_unary()?;
+                self.depth -= 1;
+                Ok(Expr::Not(Box::new(inner)))
+            }
+            Some(Token::Open) => {
+                self.enter()?;
+                let inner = self.parse_or()?;
+                self.depth -= 1;
+                match self.next() {
+                    Some(Token::Close) => Ok(inner),
+                    _ => Err(EventBusError::validation("Missing ')' in filter expression")),
+                }
+            }
+            Some(Token::Path(path)) => match self.peek() {
+                Some(Token::Op(op)) => {
+                    let op = *op;
+                    self.pos += 1;
+                    match self.next() {
+                        Some(Token::Literal(value)) => Ok(Expr::Compare(path, op, value)),
+                        _ => Err(EventBusError::validation(format!(
+                            "Expected a literal after '{}' in filter expression",
+                            path.join(".")
+                        ))),
+                    }
+                }
+                _ => Ok(Expr::Truthy(path)),
+            },
+            Some(token) => Err(EventBusError::validation(format!(
+                "Unexpected {} in filter expression",
+                token
+            ))),
+            None => Err(EventBusError::validation("Incomplete filter expression")),
+        }
+    }
+
+    fn enter(&mut self) -> EventBusResult<()> {
+        self.depth += 1;
+        if self.depth > MAX_NESTING {
+            return Err(EventBusError::validation("Filter expression is nested too deeply"));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn event() -> EventEnvelope {
+        EventEnvelope::new("orders.created", json!({
+            "priority": "high",
+            "amount": 250,
+            "customer": {"tier": "gold", "tags": ["vip"]},
+            "test": false,
+        }))
+        .set_trn(Some("trn:user:alice:tool:api:v1".to_string()), None)
+    }
+
+    #[test]
+    fn test_filter_expressions_match_events() {
+        let event = event();
+        let cases = [
+            (r#"payload.priority == "high""#, true),
+            ("amount == 250", true),
+            ("payload.amount > 100 && payload.amount <= 250", true),
+            ("payload.amount >= 250.0", true),
+            (r#"payload.customer.tier != "gold" || topic == "orders.created""#, true),
+            (r#"payload.customer.tags.0 == "vip""#, true),
+            ("!(payload.amount < 300)", false),
+            ("payload.test", false),
+            ("payload.customer", true),
+            ("payload.missing == null", true),
+            ("payload.missing > 1", false),
+            (r#"source_trn == "trn:user:alice:tool:api:v1""#, true),
+            ("correlation_id", false),
+            ("priority == 100", true),
+        ];
+
+        for (source, expected) in cases {
+            let filter = EventFilter::parse(source).unwrap();
+            assert_eq!(filter.matches(&event), expected, "{}", source);
+        }
+    }
+
+    #[test]
+    fn test_filter_rejects_malformed_expressions() {
+        for source in ["", "payload.a ==", "(payload.a", "payload.a == 1 &&", "payload..a", r#""x" == payload.a"#, "a = 1"] {
+            assert!(EventFilter::parse(source).is_err(), "{}", source);
+        }
+        assert!(EventFilter::parse("!".repeat(MAX_NESTING + 1) + "a").is_err());
+
+        let filter: EventFilter = serde_json::from_value(json!("payload.a == 1")).unwrap();
+        assert_eq!(serde_json::to_value(&filter).unwrap(), json!("payload.a == 1"));
+        assert!(serde_json::from_value::<EventFilter>(json!("payload.a ==")).is_err());
+    }
+}
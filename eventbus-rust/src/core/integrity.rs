@@ -0,0 +1,435 @@
+//! Event checksums and per-topic hash chains
+//!
+//! With `IntegrityConfig::checksums`, every event is stamped with the
+//! SHA-256 of its content in `metadata.integrity` just before it is stored.
+//! Events of `chained_topics` also carry their position in a hash chain per
+//! topic and the hash of the event before them, which their own hash covers:
+//! rewriting, removing or reordering a stored event breaks the chain after
+//! it, even when the rewritten event's hash is recomputed.
+//!
+//! The content hashed is every envelope field, as canonical JSON (object
+//! keys sorted), minus the integrity section itself. Polls check the
+//! checksums of the events they return when `verify_on_read` is set, and
+//! `EventBusService::verify_topic_integrity` checks a whole topic, chain
+//! included. The bus stamps the integrity section itself: one sent with an
+//! event is removed, or replaced by the bus's own.
+//!
+//! Events of chained topics cannot expire (`expires_at`): a purged event
+//! would leave a gap in its chain, and the head reloaded after a restart
+//! would step back.
+//!
+//! Checksums are unkeyed: they catch changes made behind the bus's back, but
+//! whoever can rewrite the database can also rebuild a consistent chain from
+//! the altered event on. Keep the chain head reported by a verification
+//! outside the database to detect that.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use tokio::sync::{Mutex, OwnedMutexGuard};
+
+use crate::config::IntegrityConfig;
+use crate::core::traits::{EventBusResult, EventStorage};
+use crate::core::{EventBusError, EventEnvelope, EventQuery, IntegrityMetadata, INTEGRITY_METADATA_KEY};
+
+/// Refuse an event that sets `expires_at` on a chained topic
+pub fn check_expiry(config: &IntegrityConfig, event: &EventEnvelope) -> EventBusResult<()> {
+    if event.expires_at.is_some() && config.is_chained(&event.topic) {
+        return Err(EventBusError::validation(format!(
+            "Events of hash-chained topic '{}' cannot expire",
+            event.topic
+        )));
+    }
+    Ok(())
+}
+
+/// Newest event of a hash chain
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChainHead {
+    /// Position of the event in the chain
+    pub position: u64,
+    /// Hash of the event
+    pub hash: String,
+}
+
+/// Hash of an event's content, linked at `position` after `prev_hash` for
+/// chained topics
+///
+/// Differences storage backends introduce (no metadata or an empty object,
+/// no sequence number or 0) hash the same.
+pub fn compute_hash(event: &EventEnvelope, position: Option<u64>, prev_hash: Option<&str>) -> String {
+    let mut content = String::new();
+    write_canonical(&content_of(event), &mut content);
+
+    let mut hasher = Sha256::new();
+    hasher.update(content.as_bytes());
+    if let Some(position) = position {
+        hasher.update(format!("\n{}\n{}", position, prev_hash.unwrap_or_default()).as_bytes());
+    }
+    hasher.finalize().iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Stamp an unchained checksum on an event
+pub fn seal(event: EventEnvelope) -> EventEnvelope {
+    let hash = compute_hash(&event, None, None);
+    event.with_integrity(&IntegrityMetadata {
+        hash,
+        position: None,
+        prev_hash: None,
+    })
+}
+
+/// Check an event against its checksum
+///
+/// Events without integrity metadata pass.
+pub fn verify(event: &EventEnvelope) -> EventBusResult<()> {
+    let Some(integrity) = event.integrity() else {
+        return Ok(());
+    };
+    if compute_hash(event, integrity.position, integrity.prev_hash.as_deref()) != integrity.hash {
+        return Err(EventBusError::integrity(format!(
+            "Event {} on topic '{}' does not match its checksum",
+            event.event_id, event.topic
+        )));
+    }
+    Ok(())
+}
+
+/// The fields covered by the checksum
+fn content_of(event: &EventEnvelope) -> Value {
+    let metadata = match &event.metadata {
+        Some(Value::Object(metadata)) => {
+            let mut metadata = metadata.clone();
+            metadata.remove(INTEGRITY_METADATA_KEY);
+            if metadata.is_empty() { Value::Null } else { Value::Object(metadata) }
+        }
+        Some(other) => other.clone(),
+        None => Value::Null,
+    };
+    serde_json::json!({
+        "event_id": event.event_id,
+        "topic": event.topic,
+        "payload": event.payload,
+        "timestamp": event.timestamp,
+        "metadata": metadata,
+        "source_trn": event.source_trn,
+        "target_trn": event.target_trn,
+        "correlation_id": event.correlation_id,
+        "sequence_number": event.sequence_number.filter(|number| *number != 0),
+        "priority": event.priority,
+        "expires_at": event.expires_at,
+    })
+}
+
+/// Serialize JSON with object keys sorted, whatever the map implementation
+fn write_canonical(value: &Value, out: &mut String) {
+    match value {
+        Value::Object(map) => {
+            let mut keys: Vec<&String> = map.keys().collect();
+            keys.sort();
+            out.push('{');
+            for (index, key) in keys.into_iter().enumerate() {
+                if index > 0 {
+                    out.push(',');
+                }
+                out.push_str(&Value::String(key.clone()).to_string());
+                out.push(':');
+                write_canonical(&map[key], out);
+            }
+            out.push('}');
+        }
+        Value::Array(items) => {
+            out.push('[');
+            for (index, item) in items.iter().enumerate() {
+                if index > 0 {
+                    out.push(',');
+                }
+                write_canonical(item, out);
+            }
+            out.push(']');
+        }
+        other => out.push_str(&other.to_string()),
+    }
+}
+
+/// Heads of the hash chains of a bus
+///
+/// A chain is locked from the moment its events are sealed until they are
+/// stored, so concurrent emits never link to the same head. Heads are loaded
+/// from storage the first time a chain is used.
+#[derive(Debug, Default)]
+pub struct HashChains {
+    chains: dashmap::DashMap<String, Arc<Mutex<ChainState>>>,
+}
+
+#[derive(Debug, Default)]
+struct ChainState {
+    loaded: bool,
+    head: Option<ChainHead>,
+}
+
+impl HashChains {
+    /// Create an empty set of chains
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Lock the chains of the chained topics among `topics`, for sealing
+    /// events about to be stored
+    pub async fn lock<'a>(
+        &self,
+        config: &IntegrityConfig,
+        topics: impl IntoIterator<Item = &'a str>,
+        storage: &dyn EventStorage,
+    ) -> EventBusResult<ChainLocks> {
+        let mut locks = ChainLocks {
+            checksums: config.checksums,
+            chains: BTreeMap::new(),
+        };
+        // Locked in name order, so batches spanning several chains cannot deadlock
+        let chained: BTreeSet<&str> = topics.into_iter().filter(|topic| config.is_chained(topic)).collect();
+        for topic in chained {
+            let chain = self.chains.entry(topic.to_string()).or_default().clone();
+            let mut state = chain.lock_owned().await;
+            if !state.loaded {
+                state.head = load_head(topic, storage).await?;
+                state.loaded = true;
+            }
+            let sealed = state.head.clone();
+            locks.chains.insert(topic.to_string(), LockedChain { state, sealed });
+        }
+        Ok(locks)
+    }
+
+    /// Forget the heads loaded so far, so they are loaded from storage again
+    ///
+    /// For events stored without sealing them here, such as replicated ones.
+    pub fn reset(&self) {
+        self.chains.clear();
+    }
+
+    /// Head of a topic's chain, if this bus has used the chain
+    pub async fn head(&self, topic: &str) -> Option<ChainHead> {
+        let chain = self.chains.get(topic)?.clone();
+        let state = chain.lock().await;
+        state.head.clone()
+    }
+}
+
+/// Newest chained event of a topic in storage
+async fn load_head(topic: &str, storage: &dyn EventStorage) -> EventBusResult<Option<ChainHead>> {
+    let events = storage.query(&EventQuery::new().with_topic(topic)).await?;
+    Ok(events
+        .iter()
+        .filter_map(|event| event.integrity())
+        .filter_map(|integrity| Some(ChainHead { position: integrity.position?, hash: integrity.hash }))
+        .max_by_key(|head| head.position))
+}
+
+/// Chains locked while their events are sealed and stored
+///
+/// Dropping the locks releases the chains; heads only advance past events
+/// passed to `record`.
+pub struct ChainLocks {
+    checksums: bool,
+    chains: BTreeMap<String, LockedChain>,
+}
+
+struct LockedChain {
+    state: OwnedMutexGuard<ChainState>,
+    /// Last event sealed, not necessarily stored yet
+    sealed: Option<ChainHead>,
+}
+
+impl ChainLocks {
+    /// Stamp an event's checksum, linking it to its chain if its topic is chained
+    ///
+    /// Without checksums, an integrity section sent with the event is removed.
+    pub fn seal(&mut self, event: EventEnvelope) -> EventEnvelope {
+        let Some(chain) = self.chains.get_mut(&event.topic) else {
+            return if self.checksums { seal(event) } else { event.without_integrity() };
+        };
+        let position = chain.sealed.as_ref().map_or(1, |head| head.position + 1);
+        let prev_hash = chain.sealed.as_ref().map(|head| head.hash.clone());
+        let hash = compute_hash(&event, Some(position), prev_hash.as_deref());
+        chain.sealed = Some(ChainHead { position, hash: hash.clone() });
+        event.with_integrity(&IntegrityMetadata {
+            hash,
+            position: Some(position),
+            prev_hash,
+        })
+    }
+
+    /// Advance the chain of a sealed event to it, once it is stored
+    pub fn record(&mut self, event: &EventEnvelope) {
+        let Some(chain) = self.chains.get_mut(&event.topic) else {
+            return;
+        };
+        if let Some(IntegrityMetadata { hash, position: Some(position), .. }) = event.integrity() {
+            chain.state.head = Some(ChainHead { position, hash });
+        }
+    }
+}
+
+/// Chain positions missing between two events found
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PositionGap {
+    /// Last position before the gap (0 when the chain's start is missing)
+    pub after: u64,
+    /// First position after the gap (one past the chain head when the
+    /// newest events are missing)
+    pub next: u64,
+}
+
+/// Result of checking the stored events of a topic
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct IntegrityReport {
+    /// Topic checked
+    pub topic: String,
+    /// Whether the topic is hash-chained
+    pub chained: bool,
+    /// Number of events checked
+    pub events_checked: u64,
+    /// Events without a checksum (stored before checksums were enabled, or
+    /// stripped of it)
+    pub unsealed: Vec<String>,
+    /// Events that no longer match their checksum
+    pub tampered: Vec<String>,
+    /// Chain positions with no event
+    pub missing: Vec<PositionGap>,
+    /// Events not linked to the event before them, or sharing their position
+    pub broken_links: Vec<String>,
+    /// Newest chained event found; keep it to detect a rebuilt chain later
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub head: Option<ChainHead>,
+}
+
+impl IntegrityReport {
+    /// Whether nothing was altered, removed or (on chained topics) left unsealed
+    pub fn is_intact(&self) -> bool {
+        self.tampered.is_empty()
+            && self.missing.is_empty()
+            && self.broken_links.is_empty()
+            && (!self.chained || self.unsealed.is_empty())
+    }
+}
+
+/// Check events of one topic against their checksums and hash chain
+///
+/// Chain positions are checked between the events given; the first one is
+/// not checked against events before it.
+pub fn inspect_topic(topic: &str, events: &[EventEnvelope], chained: bool) -> IntegrityReport {
+    let mut report = IntegrityReport {
+        topic: topic.to_string(),
+        chained,
+        events_checked: events.len() as u64,
+        ..Default::default()
+    };
+
+    let mut links: BTreeMap<u64, Vec<(&str, IntegrityMetadata)>> = BTreeMap::new();
+    for event in events {
+        let Some(integrity) = event.integrity() else {
+            report.unsealed.push(event.event_id.clone());
+            continue;
+        };
+        if verify(event).is_err() {
+            report.tampered.push(event.event_id.clone());
+        }
+        if let Some(position) = integrity.position {
+            links.entry(position).or_default().push((event.event_id.as_str(), integrity));
+        }
+    }
+
+    let mut previous: Option<(u64, &Vec<(&str, IntegrityMetadata)>)> = None;
+    for (&position, entries) in &links {
+        if entries.len() > 1 {
+            report.broken_links.extend(entries.iter().map(|(event_id, _)| event_id.to_string()));
+        } else if let Some((previous_position, previous_entries)) = previous {
+            let (event_id, integrity) = &entries[0];
+            if position > previous_position + 1 {
+                report.missing.push(PositionGap { after: previous_position, next: position });
+            } else if previous_entries.len() == 1
+                && integrity.prev_hash.as_deref() != Some(previous_entries[0].1.hash.as_str())
+            {
+                report.broken_links.push(event_id.to_string());
+            }
+        } else if position == 1 && entries[0].1.prev_hash.is_some() {
+            report.broken_links.push(entries[0].0.to_string());
+        }
+        previous = Some((position, entries));
+    }
+
+    report.head = links.iter().next_back().map(|(&position, entries)| ChainHead {
+        position,
+        hash: entries[0].1.hash.clone(),
+    });
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn chain(count: usize) -> Vec<EventEnvelope> {
+        let mut sealed: Option<ChainHead> = None;
+        (0..count)
+            .map(|index| {
+                let event = EventEnvelope::new("audit.log", json!({ "index": index }));
+                let position = sealed.as_ref().map_or(1, |head| head.position + 1);
+                let prev_hash = sealed.as_ref().map(|head| head.hash.clone());
+                let hash = compute_hash(&event, Some(position), prev_hash.as_deref());
+                sealed = Some(ChainHead { position, hash: hash.clone() });
+                event.with_integrity(&IntegrityMetadata { hash, position: Some(position), prev_hash })
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_checksum_ignores_key_order_and_backend_normalization() {
+        let event = seal(EventEnvelope::new("orders.created", json!({ "b": 1, "a": [1, { "y": 2, "x": 3 }] })));
+        verify(&event).unwrap();
+
+        // Read back from a backend that stores metadata as `{}` and sequence as 0
+        let mut stored = EventEnvelope::new("orders.created", json!({}));
+        stored.sequence_number = Some(0);
+        let integrity = seal(stored.clone()).integrity().unwrap();
+        stored.sequence_number = None;
+        verify(&stored.with_integrity(&integrity)).unwrap();
+
+        let mut altered = event.clone();
+        altered.payload["b"] = json!(2);
+        assert!(matches!(verify(&altered), Err(EventBusError::IntegrityViolation { .. })));
+        verify(&EventEnvelope::new("orders.created", json!({}))).unwrap();
+    }
+
+    #[test]
+    fn test_inspect_chain_finds_tampering_gaps_and_relinks() {
+        let events = chain(5);
+        let report = inspect_topic("audit.log", &events, true);
+        assert!(report.is_intact());
+        assert_eq!(report.head.as_ref().map(|head| head.position), Some(5));
+
+        // Rewriting an event and its hash breaks the link of the next one
+        let mut rewritten = events.clone();
+        rewritten[1].payload = json!({ "index": 99 });
+        let integrity = rewritten[1].integrity().unwrap();
+        let hash = compute_hash(&rewritten[1], integrity.position, integrity.prev_hash.as_deref());
+        rewritten[1] = rewritten[1].clone().with_integrity(&IntegrityMetadata { hash, ..integrity });
+        let report = inspect_topic("audit.log", &rewritten, true);
+        assert!(report.tampered.is_empty());
+        assert_eq!(report.broken_links, vec![events[2].event_id.clone()]);
+
+        let mut removed = events.clone();
+        removed.remove(2);
+        removed.push(EventEnvelope::new("audit.log", json!({})));
+        let report = inspect_topic("audit.log", &removed, true);
+        assert_eq!(report.missing, vec![PositionGap { after: 2, next: 4 }]);
+        assert_eq!(report.unsealed.len(), 1);
+        assert!(!report.is_intact());
+        assert!(inspect_topic("audit.log", &removed[4..], false).is_intact());
+    }
+}
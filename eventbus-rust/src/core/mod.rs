@@ -6,8 +6,21 @@
 pub mod types;
 pub mod traits;
 pub mod error;
+pub mod upcast;
+pub mod filter;
+pub mod plugin;
+pub mod typed;
+pub mod integrity;
 
 // Re-export all public items
 pub use types::*;
 pub use traits::*;
-pub use error::*; 
\ No newline at end of file
+pub use error::*;
+pub use upcast::*;
+pub use filter::*;
+pub use plugin::*;
+pub use typed::*;
+
+/// Derive `Event` for a payload type (see `typed`)
+#[cfg(feature = "macros")]
+pub use eventbus_macros::Event;
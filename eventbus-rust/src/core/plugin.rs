@@ -0,0 +1,35 @@
+//! Emit pipeline plugins
+//!
+//! Plugins registered on the service see every emitted event at three points:
+//! before it is validated and stored, after it is stored, and after it is
+//! broadcast to subscribers. They run in registration order, so a plugin can
+//! rely on the changes made by the plugins registered before it.
+
+use async_trait::async_trait;
+
+use crate::core::{EventBusResult, EventEnvelope};
+
+/// Hooks into the emit pipeline of an event bus
+///
+/// All hooks default to no-ops, so a plugin only implements the points it
+/// cares about. Only `before_emit` can change or reject an event; the later
+/// hooks observe events that are already committed.
+#[async_trait]
+pub trait BusPlugin: Send + Sync {
+    /// Name used in logs
+    fn name(&self) -> &str;
+
+    /// Called before the event is validated and stored
+    ///
+    /// The event may be modified in place (e.g. enriched or encrypted).
+    /// Returning an error rejects the emit.
+    async fn before_emit(&self, _event: &mut EventEnvelope) -> EventBusResult<()> {
+        Ok(())
+    }
+
+    /// Called once the event has been stored
+    async fn after_store(&self, _event: &EventEnvelope) {}
+
+    /// Called once the event has been broadcast to subscribers
+    async fn after_broadcast(&self, _event: &EventEnvelope) {}
+}
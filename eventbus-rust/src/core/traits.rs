@@ -4,8 +4,9 @@ use async_trait::async_trait;
 use std::pin::Pin;
 use futures::Stream;
 use std::collections::HashMap;
+use std::sync::Arc;
 
-use crate::core::{EventEnvelope, EventQuery, EventTriggerRule, ToolInvocation};
+use crate::core::{EventEnvelope, EventFilter, EventQuery, EventTriggerRule, IdempotencyRecord, ToolInvocation};
 use crate::core::error::EventBusError;
 
 /// Result type for event bus operations
@@ -26,6 +27,69 @@ pub trait EventBus: Send + Sync {
     /// Subscribe to a topic and receive events as a stream
     async fn subscribe(&self, topic: &str) -> EventBusResult<Pin<Box<dyn Stream<Item = EventEnvelope> + Send>>>;
     
+    /// Subscribe to events whose source or target TRN matches a pattern,
+    /// such as `trn:org:acme:*:*:*`, whatever their topic
+    async fn subscribe_trn(&self, pattern: &str) -> EventBusResult<Pin<Box<dyn Stream<Item = EventEnvelope> + Send>>> {
+        use futures::StreamExt;
+        
+        let patterns = trn_rust::TrnPatternSet::new([pattern])
+            .map_err(|e| EventBusError::validation(format!("Invalid TRN pattern '{}': {}", pattern, e)))?;
+        let events = self.subscribe("*").await?;
+        
+        Ok(Box::pin(events.filter(move |event| {
+            futures::future::ready(crate::utils::event_matches_trn_patterns(event, &patterns))
+        })))
+    }
+    
+    /// Subscribe to a topic, receiving only events that satisfy a filter
+    /// expression such as `payload.priority == "high"`
+    async fn subscribe_filtered(&self, topic: &str, filter: EventFilter) -> EventBusResult<Pin<Box<dyn Stream<Item = EventEnvelope> + Send>>> {
+        use futures::StreamExt;
+        
+        let events = self.subscribe(topic).await?;
+        
+        Ok(Box::pin(events.filter(move |event| futures::future::ready(filter.matches(event)))))
+    }
+    
+    /// Long-poll: query stored events, waiting up to `timeout` for a matching
+    /// event to arrive when none are stored yet
+    ///
+    /// The live subscription is opened before the storage query so nothing
+    /// emitted in between is missed. Returns an empty list on timeout. Live
+    /// events are returned in arrival order; pagination applies to the stored
+    /// results only, except that `limit` also caps the live batch.
+    async fn poll_wait(&self, query: EventQuery, timeout: std::time::Duration) -> EventBusResult<Vec<EventEnvelope>> {
+        use futures::{FutureExt, StreamExt};
+        
+        let mut live = self.subscribe(query.topic.as_deref().unwrap_or("*")).await?;
+        
+        let stored = self.poll(query.clone()).await?;
+        if !stored.is_empty() {
+            return Ok(stored);
+        }
+        
+        let limit = query.limit.map_or(usize::MAX, |limit| limit.max(1) as usize);
+        let deadline = tokio::time::Instant::now() + timeout;
+        let mut events = Vec::new();
+        
+        // Wait for the first match, then take whatever else is already buffered
+        while let Ok(Some(event)) = tokio::time::timeout_at(deadline, live.next()).await {
+            if query.matches(&event) {
+                events.push(event);
+                break;
+            }
+        }
+        while events.len() < limit {
+            match live.next().now_or_never() {
+                Some(Some(event)) if query.matches(&event) => events.push(event),
+                Some(Some(_)) => {}
+                _ => break,
+            }
+        }
+        
+        Ok(events)
+    }
+    
     /// Get list of all available topics
     async fn list_topics(&self) -> EventBusResult<Vec<String>>;
     
@@ -64,6 +128,19 @@ pub trait EventBus: Send + Sync {
 /// - Cleanup operations should not interfere with ongoing queries
 #[async_trait]
 pub trait EventStorage: Send + Sync {
+    /// Short name of the backend (e.g. "memory", "sqlite"), used in diagnostics
+    fn backend_name(&self) -> &str {
+        "custom"
+    }
+    
+    /// The backend's own store for emit idempotency keys, if it has one
+    /// 
+    /// Persistent backends return themselves, so keys survive restarts along
+    /// with the events.
+    fn idempotency_storage(self: Arc<Self>) -> Option<Arc<dyn IdempotencyStorage>> {
+        None
+    }
+    
     /// Initialize the storage backend
     /// 
     /// This method should create necessary tables, indexes, and perform
@@ -103,6 +180,18 @@ pub trait EventStorage: Send + Sync {
     /// Returns the number of events that were deleted.
     async fn cleanup(&self, before_timestamp: i64) -> EventBusResult<u64>;
     
+    /// Cleanup old events of a single topic
+    /// 
+    /// Like `cleanup`, restricted to events whose topic equals `topic`.
+    /// Used to apply per-topic retention overrides.
+    async fn cleanup_topic(&self, topic: &str, before_timestamp: i64) -> EventBusResult<u64>;
+    
+    /// Delete events whose `expires_at` is at or before `now`
+    /// 
+    /// Queries already exclude expired events; this reclaims their space.
+    /// Returns the number of events that were deleted.
+    async fn purge_expired(&self, now: i64) -> EventBusResult<u64>;
+    
     /// Get events for a topic since a given timestamp
     /// 
     /// This is a convenience method for real-time subscriptions and polling.
@@ -249,6 +338,22 @@ pub trait RuleStorage: Send + Sync {
     async fn count_rules(&self, enabled_only: bool) -> Result<u64, Box<dyn std::error::Error + Send + Sync>>;
 }
 
+/// Storage for emit idempotency keys
+/// 
+/// Records outlive connections (and, for persistent backends, restarts), so a
+/// client retrying an emit after reconnecting receives the original response.
+#[async_trait]
+pub trait IdempotencyStorage: Send + Sync {
+    /// Get the record for a key, ignoring records expired at `now`
+    async fn get_idempotency_record(&self, key: &str, now: i64) -> EventBusResult<Option<IdempotencyRecord>>;
+    
+    /// Store a record, replacing any previous record for the same key
+    async fn put_idempotency_record(&self, record: &IdempotencyRecord) -> EventBusResult<()>;
+    
+    /// Remove records expired at `now`, returning how many were removed
+    async fn cleanup_idempotency_records(&self, now: i64) -> EventBusResult<u64>;
+}
+
 /// Tool execution trait for handling various tool types
 /// 
 /// This trait provides a unified interface for executing different types of tools:
@@ -0,0 +1,151 @@
+//! Typed events
+//!
+//! An [`Event`] type carries its topic and schema version, so producers and
+//! consumers exchange Rust values instead of topic strings and raw JSON.
+//! Implement it with `#[derive(Event)]` (the `macros` feature) or by hand,
+//! and use it through [`TypedEventBus`]:
+//!
+//! ```ignore
+//! #[derive(Serialize, Deserialize, Event)]
+//! #[event(topic = "orders.created")]
+//! struct OrderCreated { order_id: String }
+//!
+//! let bus = TypedEventBus::new(service);
+//! let mut orders = bus.subscribe_typed::<OrderCreated>().await?;
+//! bus.emit_typed(&OrderCreated { order_id: "42".into() }).await?;
+//! ```
+//!
+//! Subscribers receive events upcast to the latest schema version (see
+//! `UpcasterRegistry`); an event whose version still differs from the
+//! type's is reported as an error rather than decoded.
+
+use std::pin::Pin;
+use std::sync::Arc;
+
+use futures::{Stream, StreamExt};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::core::traits::{EventBus, EventBusResult};
+use crate::core::{EventBusError, EventEnvelope, DEFAULT_SCHEMA_VERSION};
+
+/// An event payload type bound to a topic and schema version
+pub trait Event: Serialize + DeserializeOwned + Send + 'static {
+    /// Topic events of this type are emitted to
+    const TOPIC: &'static str;
+
+    /// Schema version of this payload shape
+    const SCHEMA_VERSION: u32 = DEFAULT_SCHEMA_VERSION;
+
+    /// Wrap the value in an envelope for its topic and schema version
+    fn to_envelope(&self) -> EventBusResult<EventEnvelope> {
+        let payload = serde_json::to_value(self)?;
+        Ok(EventEnvelope::new(Self::TOPIC, payload).with_schema_version(Self::SCHEMA_VERSION))
+    }
+
+    /// Decode the payload of an envelope of this type
+    fn from_envelope(envelope: &EventEnvelope) -> EventBusResult<Self> {
+        if envelope.topic != Self::TOPIC {
+            return Err(EventBusError::validation(format!(
+                "Event {} is on topic '{}', expected '{}'",
+                envelope.event_id, envelope.topic, Self::TOPIC
+            )));
+        }
+        let version = envelope.schema_version();
+        if version != Self::SCHEMA_VERSION {
+            return Err(EventBusError::validation(format!(
+                "Event {} has schema version {}, expected {}",
+                envelope.event_id, version, Self::SCHEMA_VERSION
+            )));
+        }
+        Ok(serde_json::from_value(envelope.payload.clone())?)
+    }
+}
+
+/// Typed view of an event bus
+#[derive(Clone)]
+pub struct TypedEventBus {
+    bus: Arc<dyn EventBus>,
+}
+
+impl TypedEventBus {
+    /// Wrap an event bus
+    pub fn new(bus: Arc<dyn EventBus>) -> Self {
+        Self { bus }
+    }
+
+    /// The wrapped bus, for untyped operations
+    pub fn inner(&self) -> &Arc<dyn EventBus> {
+        &self.bus
+    }
+
+    /// Emit a typed event, returning the ID of its envelope
+    pub async fn emit_typed<T: Event>(&self, event: &T) -> EventBusResult<String> {
+        let envelope = event.to_envelope()?;
+        let event_id = envelope.event_id.clone();
+        self.bus.emit(envelope).await?;
+        Ok(event_id)
+    }
+
+    /// Subscribe to the events of a type
+    ///
+    /// Events that fail to decode are yielded as errors so the stream keeps
+    /// going.
+    pub async fn subscribe_typed<T: Event>(
+        &self,
+    ) -> EventBusResult<Pin<Box<dyn Stream<Item = EventBusResult<T>> + Send>>> {
+        let events = self.bus.subscribe(T::TOPIC).await?;
+        Ok(Box::pin(events.map(|envelope| T::from_envelope(&envelope))))
+    }
+}
+
+#[cfg(all(test, feature = "macros"))]
+mod tests {
+    use super::*;
+    use crate::core::{EventQuery, UpcasterRegistry};
+    use crate::Event;
+    use crate::service::{EventBusService, ServiceConfig};
+    use serde::Deserialize;
+    use serde_json::json;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize, Event)]
+    #[event(topic = "orders.created", schema_version = 2)]
+    struct OrderCreated {
+        order_id: String,
+        total_cents: u64,
+    }
+
+    #[derive(Debug, Serialize, Deserialize, Event)]
+    #[event(topic = "orders.cancelled")]
+    struct OrderCancelled {
+        order_id: String,
+    }
+
+    #[tokio::test]
+    async fn test_typed_emit_and_subscribe() {
+        let mut upcasters = UpcasterRegistry::new();
+        upcasters.register_fn("orders.created", 1, |mut payload| {
+            payload["total_cents"] = json!(payload["total"].as_u64().unwrap_or(0) * 100);
+            Ok(payload)
+        }).unwrap();
+        let service = Arc::new(EventBusService::new(ServiceConfig::default()).with_upcasters(upcasters));
+        let bus = TypedEventBus::new(service.clone());
+        assert_eq!(OrderCancelled::SCHEMA_VERSION, 1);
+
+        let mut orders = bus.subscribe_typed::<OrderCreated>().await.unwrap();
+        let order = OrderCreated { order_id: "42".to_string(), total_cents: 1250 };
+        let event_id = bus.emit_typed(&order).await.unwrap();
+        assert_eq!(orders.next().await.unwrap().unwrap(), order);
+
+        let stored = service.poll(EventQuery::new().with_topic("orders.created")).await.unwrap();
+        assert_eq!(stored[0].event_id, event_id);
+        assert_eq!(stored[0].schema_version(), 2);
+
+        // Version 1 events are upcast before they are decoded
+        service.emit(EventEnvelope::new("orders.created", json!({"order_id": "7", "total": 3}))).await.unwrap();
+        assert_eq!(orders.next().await.unwrap().unwrap().total_cents, 300);
+
+        let err = OrderCancelled::from_envelope(&stored[0]).unwrap_err();
+        assert!(err.to_string().contains("expected 'orders.cancelled'"));
+    }
+}
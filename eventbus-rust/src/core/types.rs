@@ -5,6 +5,9 @@ use std::time::{SystemTime, UNIX_EPOCH};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+/// Scheduling priority of rule actions, shared with jsonrpc-rust's executor
+pub use jsonrpc_rust::core::future::Priority;
+
 /// Event priority levels
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 pub enum EventPriority {
@@ -67,12 +70,81 @@ pub struct EventEnvelope {
     /// Event priority (higher number = higher priority)
     #[serde(default = "default_priority")]
     pub priority: u32,
+    
+    /// Unix timestamp after which the event is no longer delivered by
+    /// queries and is purged by the retention task
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<i64>,
 }
 
 fn default_priority() -> u32 {
     100 // Normal priority
 }
 
+/// Metadata key holding the [`IngestMetadata`] stamped by the JSON-RPC server
+pub const INGEST_METADATA_KEY: &str = "ingest";
+
+/// Who sent an event, recorded when it arrives over JSON-RPC
+/// 
+/// Stamped by the server from the call's `ServiceContext`; a client-supplied
+/// `ingest` section is always replaced.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct IngestMetadata {
+    /// ID of the request that carried the event
+    pub request_id: String,
+    /// Unix timestamp when the request was received
+    pub received_at: i64,
+    /// Authenticated user, when the call was authenticated
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auth_user: Option<String>,
+    /// Authentication method used
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auth_method: Option<String>,
+    /// Remote address of the client
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_addr: Option<String>,
+    /// Client identifier reported by the transport
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_id: Option<String>,
+    /// TRN of the caller
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trn: Option<String>,
+}
+
+/// Metadata key holding the [`BatchMetadata`] stamped by `emit_batch`
+pub const BATCH_METADATA_KEY: &str = "batch";
+
+/// Position of an event within a multi-event batch
+/// 
+/// Lets startup recovery spot batches that were only partly written.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct BatchMetadata {
+    /// ID shared by every event of the batch
+    pub id: String,
+    /// Zero-based position of the event within the batch
+    pub index: u32,
+    /// Number of events in the batch
+    pub size: u32,
+}
+
+/// Metadata key holding the [`IntegrityMetadata`] stamped when an event is stored
+pub const INTEGRITY_METADATA_KEY: &str = "integrity";
+
+/// Checksum of an event, and its link in the hash chain of its topic
+/// 
+/// See `core::integrity` for what is hashed.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct IntegrityMetadata {
+    /// Hex SHA-256 of the event content and chain link
+    pub hash: String,
+    /// Position in the topic's hash chain, starting at 1, for chained topics
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub position: Option<u64>,
+    /// Hash of the event before it in the chain (none for the first)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prev_hash: Option<String>,
+}
+
 impl EventEnvelope {
     /// Create a new event envelope
     pub fn new(topic: impl Into<String>, payload: serde_json::Value) -> Self {
@@ -90,6 +162,7 @@ impl EventEnvelope {
             correlation_id: None,
             sequence_number: None,
             priority: default_priority(),
+            expires_at: None,
         }
     }
     
@@ -137,6 +210,114 @@ impl EventEnvelope {
         self
     }
     
+    /// Set the Unix timestamp after which the event expires
+    pub fn with_expires_at(mut self, expires_at: i64) -> Self {
+        self.expires_at = Some(expires_at);
+        self
+    }
+    
+    /// Expire the event `ttl` after its timestamp (rounded up to whole seconds)
+    pub fn with_ttl(self, ttl: std::time::Duration) -> Self {
+        let ttl_secs = ttl.as_secs() + u64::from(ttl.subsec_nanos() > 0);
+        let expires_at = self.timestamp.saturating_add(ttl_secs.min(i64::MAX as u64) as i64);
+        self.with_expires_at(expires_at)
+    }
+    
+    /// Whether the event has expired at the given Unix timestamp
+    pub fn is_expired(&self, now: i64) -> bool {
+        self.expires_at.is_some_and(|expires_at| now >= expires_at)
+    }
+    
+    /// Payload schema version (from `metadata.schema_version`, defaults to 1)
+    pub fn schema_version(&self) -> u32 {
+        self.metadata
+            .as_ref()
+            .and_then(|metadata| metadata.get(crate::core::upcast::SCHEMA_VERSION_KEY))
+            .and_then(|version| version.as_u64())
+            .map(|version| version as u32)
+            .unwrap_or(crate::core::upcast::DEFAULT_SCHEMA_VERSION)
+    }
+    
+    /// Set the payload schema version, keeping other metadata
+    pub fn with_schema_version(mut self, version: u32) -> Self {
+        let key = crate::core::upcast::SCHEMA_VERSION_KEY.to_string();
+        match self.metadata {
+            Some(serde_json::Value::Object(ref mut metadata)) => {
+                metadata.insert(key, version.into());
+            }
+            _ => self.metadata = Some(serde_json::json!({ key: version })),
+        }
+        self
+    }
+    
+    /// Ingest metadata stamped by the JSON-RPC server, if any
+    pub fn ingest(&self) -> Option<IngestMetadata> {
+        self.metadata
+            .as_ref()
+            .and_then(|metadata| metadata.get(INGEST_METADATA_KEY))
+            .and_then(|ingest| serde_json::from_value(ingest.clone()).ok())
+    }
+    
+    /// Set the ingest metadata, replacing any previous section and keeping other metadata
+    pub fn with_ingest(mut self, ingest: &IngestMetadata) -> Self {
+        let value = serde_json::to_value(ingest).unwrap_or(serde_json::Value::Null);
+        match self.metadata {
+            Some(serde_json::Value::Object(ref mut metadata)) => {
+                metadata.insert(INGEST_METADATA_KEY.to_string(), value);
+            }
+            _ => self.metadata = Some(serde_json::json!({ INGEST_METADATA_KEY: value })),
+        }
+        self
+    }
+    
+    /// Batch metadata stamped by `emit_batch`, if any
+    pub fn batch(&self) -> Option<BatchMetadata> {
+        self.metadata
+            .as_ref()
+            .and_then(|metadata| metadata.get(BATCH_METADATA_KEY))
+            .and_then(|batch| serde_json::from_value(batch.clone()).ok())
+    }
+    
+    /// Set the batch metadata, replacing any previous section and keeping other metadata
+    pub fn with_batch(mut self, batch: &BatchMetadata) -> Self {
+        let value = serde_json::to_value(batch).unwrap_or(serde_json::Value::Null);
+        match self.metadata {
+            Some(serde_json::Value::Object(ref mut metadata)) => {
+                metadata.insert(BATCH_METADATA_KEY.to_string(), value);
+            }
+            _ => self.metadata = Some(serde_json::json!({ BATCH_METADATA_KEY: value })),
+        }
+        self
+    }
+    
+    /// Integrity metadata stamped when the event was stored, if any
+    pub fn integrity(&self) -> Option<IntegrityMetadata> {
+        self.metadata
+            .as_ref()
+            .and_then(|metadata| metadata.get(INTEGRITY_METADATA_KEY))
+            .and_then(|integrity| serde_json::from_value(integrity.clone()).ok())
+    }
+    
+    /// Set the integrity metadata, replacing any previous section and keeping other metadata
+    pub fn with_integrity(mut self, integrity: &IntegrityMetadata) -> Self {
+        let value = serde_json::to_value(integrity).unwrap_or(serde_json::Value::Null);
+        match self.metadata {
+            Some(serde_json::Value::Object(ref mut metadata)) => {
+                metadata.insert(INTEGRITY_METADATA_KEY.to_string(), value);
+            }
+            _ => self.metadata = Some(serde_json::json!({ INTEGRITY_METADATA_KEY: value })),
+        }
+        self
+    }
+    
+    /// Remove the integrity metadata, keeping other metadata
+    pub fn without_integrity(mut self) -> Self {
+        if let Some(serde_json::Value::Object(ref mut metadata)) = self.metadata {
+            metadata.remove(INTEGRITY_METADATA_KEY);
+        }
+        self
+    }
+    
     /// Check if event matches topic pattern
     pub fn matches_topic(&self, pattern: &str) -> bool {
         if pattern == "*" {
@@ -171,6 +352,10 @@ pub struct ToolInvocation {
     /// Timeout for tool execution
     #[serde(skip_serializing_if = "Option::is_none")]
     pub timeout_ms: Option<u64>,
+    
+    /// Scheduling priority, taken from the rule that triggered the invocation
+    #[serde(default)]
+    pub priority: Priority,
 }
 
 impl ToolInvocation {
@@ -181,6 +366,7 @@ impl ToolInvocation {
             input,
             context: None,
             timeout_ms: None,
+            priority: Priority::default(),
         }
     }
     
@@ -195,6 +381,12 @@ impl ToolInvocation {
         self.timeout_ms = Some(timeout_ms);
         self
     }
+    
+    /// Set scheduling priority
+    pub fn with_priority(mut self, priority: Priority) -> Self {
+        self.priority = priority;
+        self
+    }
 }
 
 /// Event trigger rule for automated responses
@@ -209,6 +401,10 @@ pub struct EventTriggerRule {
     /// Field matching criteria (simple key-value for now)
     pub match_fields: HashMap<String, serde_json::Value>,
     
+    /// Filter expression the event must also satisfy
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub condition: Option<crate::core::EventFilter>,
+    
     /// Action to take when rule matches
     pub action: RuleAction,
     
@@ -216,6 +412,11 @@ pub struct EventTriggerRule {
     #[serde(default = "default_priority")]
     pub priority: u32,
     
+    /// Priority the rule's actions are executed with; actions of higher
+    /// priority rules are started before queued lower priority ones
+    #[serde(default)]
+    pub action_priority: Priority,
+    
     /// Whether the rule is enabled
     #[serde(default = "default_enabled")]
     pub enabled: bool,
@@ -236,8 +437,10 @@ impl EventTriggerRule {
             id: id.into(),
             topic: topic.into(),
             match_fields: HashMap::new(),
+            condition: None,
             action,
             priority: default_priority(),
+            action_priority: Priority::default(),
             enabled: true,
         }
     }
@@ -252,12 +455,24 @@ impl EventTriggerRule {
         self
     }
     
+    /// Require events to satisfy a filter expression
+    pub fn with_condition(mut self, condition: crate::core::EventFilter) -> Self {
+        self.condition = Some(condition);
+        self
+    }
+    
     /// Set rule priority
     pub fn with_priority(mut self, priority: u32) -> Self {
         self.priority = priority;
         self
     }
     
+    /// Set the priority the rule's actions are executed with
+    pub fn with_action_priority(mut self, priority: Priority) -> Self {
+        self.action_priority = priority;
+        self
+    }
+    
     /// Check if this rule matches the given event
     pub fn matches(&self, event: &EventEnvelope) -> bool {
         if !self.enabled {
@@ -287,7 +502,7 @@ impl EventTriggerRule {
             }
         }
         
-        true
+        self.condition.as_ref().is_none_or(|condition| condition.matches(event))
     }
 }
 
@@ -420,6 +635,19 @@ impl EventQuery {
         self.offset = Some(offset);
         self
     }
+    
+    /// Check whether an event passes the query filters (pagination is not applied)
+    /// 
+    /// Expired events never match.
+    pub fn matches(&self, event: &EventEnvelope) -> bool {
+        !event.is_expired(chrono::Utc::now().timestamp())
+            && self.topic.as_ref().is_none_or(|pattern| event.matches_topic(pattern))
+            && self.since.is_none_or(|since| event.timestamp >= since)
+            && self.until.is_none_or(|until| event.timestamp < until)
+            && self.source_trn.as_ref().is_none_or(|trn| event.source_trn.as_ref() == Some(trn))
+            && self.target_trn.as_ref().is_none_or(|trn| event.target_trn.as_ref() == Some(trn))
+            && self.correlation_id.as_ref().is_none_or(|id| event.correlation_id.as_ref() == Some(id))
+    }
 }
 
 impl Default for EventQuery {
@@ -521,6 +749,7 @@ impl Rule {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::core::EventFilter;
     use serde_json::json;
     
     #[test]
@@ -574,6 +803,14 @@ mod tests {
         .with_match_field("user_id", json!("456"));
         
         assert!(!rule2.matches(&event));
+        
+        // Conditions use the filter expression language
+        let rule3 = rule.clone().with_condition(EventFilter::parse(r#"source_trn == "trn:user:alice""#).unwrap());
+        assert!(rule3.matches(&event));
+        let rule4 = rule.with_condition(EventFilter::parse("user_id != \"123\"").unwrap());
+        assert!(!rule4.matches(&event));
+        let rule4: EventTriggerRule = serde_json::from_value(serde_json::to_value(&rule4).unwrap()).unwrap();
+        assert_eq!(rule4.condition.unwrap().as_str(), r#"user_id != "123""#);
 
     }
 } 
@@ -593,6 +830,7 @@ pub struct EventEnvelopeBuilder {
     sequence_number: Option<u64>,
     priority: EventPriority,
     timestamp: Option<i64>,
+    expires_at: Option<i64>,
 }
 
 impl EventEnvelopeBuilder {
@@ -608,6 +846,7 @@ impl EventEnvelopeBuilder {
             sequence_number: None,
             priority: EventPriority::Normal,
             timestamp: None,
+            expires_at: None,
         }
     }
 
@@ -700,6 +939,12 @@ impl EventEnvelopeBuilder {
         self
     }
 
+    /// Set the Unix timestamp after which the event expires
+    pub fn expires_at(mut self, expires_at: i64) -> Self {
+        self.expires_at = Some(expires_at);
+        self
+    }
+
     /// Set timestamp to now
     pub fn now(mut self) -> Self {
         self.timestamp = Some(chrono::Utc::now().timestamp());
@@ -746,6 +991,7 @@ impl EventEnvelopeBuilder {
         event.correlation_id = self.correlation_id;
         event.sequence_number = self.sequence_number;
         event.priority = self.priority as u32;
+        event.expires_at = self.expires_at;
         
         if let Some(timestamp) = self.timestamp {
             event.timestamp = timestamp;
@@ -822,4 +1068,50 @@ impl EventEnvelopeBuilder {
             .metadata_kv("event_type", "metric")
             .priority(EventPriority::Low))
     }
-} 
\ No newline at end of file
+} 
+/// Recorded outcome of an emit carrying an idempotency key
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct IdempotencyRecord {
+    /// Idempotency key (scoped to the bus instance)
+    pub key: String,
+    /// ID of the event emitted for the first request
+    pub event_id: String,
+    /// Response returned to the first request
+    pub response: serde_json::Value,
+    /// Unix timestamp when the key was recorded
+    pub created_at: i64,
+    /// Unix timestamp after which the key may be reused
+    pub expires_at: i64,
+}
+
+impl IdempotencyRecord {
+    /// Whether the record has expired at the given Unix timestamp
+    pub fn is_expired(&self, now: i64) -> bool {
+        now >= self.expires_at
+    }
+}
+
+/// Settings attached to a registered topic
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct TopicSettings {
+    /// Human readable description
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Free-form topic metadata
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub metadata: HashMap<String, serde_json::Value>,
+}
+
+/// Topic known to a bus
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TopicInfo {
+    /// Topic name
+    pub name: String,
+    /// Topic settings
+    #[serde(default)]
+    pub settings: TopicSettings,
+    /// Unix timestamp when the topic was registered
+    pub created_at: i64,
+    /// Whether the topic was created implicitly by an emit
+    pub auto_created: bool,
+}
@@ -0,0 +1,205 @@
+//! Event schema versioning and upcasting
+//!
+//! Events carry their schema version in `metadata.schema_version` (events
+//! without one are version 1). Upcasters registered for a topic and version
+//! migrate an event one version forward; the registry chains them so readers
+//! always receive the latest shape, while storage keeps events as emitted.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::core::{EventBusError, EventBusResult, EventEnvelope};
+
+/// Metadata key holding the event schema version
+pub const SCHEMA_VERSION_KEY: &str = "schema_version";
+
+/// Schema version of events that do not declare one
+pub const DEFAULT_SCHEMA_VERSION: u32 = 1;
+
+/// Migrates events of one topic from one schema version to the next
+pub trait EventUpcaster: Send + Sync {
+    /// Topic this upcaster applies to
+    fn topic(&self) -> &str;
+
+    /// Schema version this upcaster reads; it produces `source_version() + 1`
+    fn source_version(&self) -> u32;
+
+    /// Transform the event to the next schema version
+    ///
+    /// The registry updates the schema version afterwards.
+    fn upcast(&self, event: EventEnvelope) -> EventBusResult<EventEnvelope>;
+}
+
+/// Upcaster backed by a payload transformation closure
+pub struct FnUpcaster<F> {
+    topic: String,
+    source_version: u32,
+    transform: F,
+}
+
+impl<F> FnUpcaster<F>
+where
+    F: Fn(serde_json::Value) -> EventBusResult<serde_json::Value> + Send + Sync,
+{
+    /// Create an upcaster transforming the payload of `topic` events at `source_version`
+    pub fn new(topic: impl Into<String>, source_version: u32, transform: F) -> Self {
+        Self {
+            topic: topic.into(),
+            source_version,
+            transform,
+        }
+    }
+}
+
+impl<F> EventUpcaster for FnUpcaster<F>
+where
+    F: Fn(serde_json::Value) -> EventBusResult<serde_json::Value> + Send + Sync,
+{
+    fn topic(&self) -> &str {
+        &self.topic
+    }
+
+    fn source_version(&self) -> u32 {
+        self.source_version
+    }
+
+    fn upcast(&self, mut event: EventEnvelope) -> EventBusResult<EventEnvelope> {
+        event.payload = (self.transform)(event.payload)?;
+        Ok(event)
+    }
+}
+
+/// Registry of upcasters keyed by topic and schema version
+#[derive(Default, Clone)]
+pub struct UpcasterRegistry {
+    upcasters: HashMap<(String, u32), Arc<dyn EventUpcaster>>,
+}
+
+impl UpcasterRegistry {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register an upcaster
+    ///
+    /// Fails if an upcaster is already registered for the same topic and version.
+    pub fn register(&mut self, upcaster: Arc<dyn EventUpcaster>) -> EventBusResult<()> {
+        let key = (upcaster.topic().to_string(), upcaster.source_version());
+        if self.upcasters.contains_key(&key) {
+            return Err(EventBusError::already_exists(format!(
+                "upcaster for topic '{}' version {}",
+                key.0, key.1
+            )));
+        }
+        self.upcasters.insert(key, upcaster);
+        Ok(())
+    }
+
+    /// Register a payload transformation for `topic` events at `source_version`
+    pub fn register_fn<F>(&mut self, topic: impl Into<String>, source_version: u32, transform: F) -> EventBusResult<()>
+    where
+        F: Fn(serde_json::Value) -> EventBusResult<serde_json::Value> + Send + Sync + 'static,
+    {
+        self.register(Arc::new(FnUpcaster::new(topic, source_version, transform)))
+    }
+
+    /// Whether no upcasters are registered
+    pub fn is_empty(&self) -> bool {
+        self.upcasters.is_empty()
+    }
+
+    /// Latest schema version known for a topic
+    pub fn latest_version(&self, topic: &str) -> u32 {
+        self.upcasters
+            .keys()
+            .filter(|(t, _)| t == topic)
+            .map(|(_, version)| version + 1)
+            .max()
+            .unwrap_or(DEFAULT_SCHEMA_VERSION)
+    }
+
+    /// Bring an event to the latest schema version of its topic
+    pub fn upcast(&self, mut event: EventEnvelope) -> EventBusResult<EventEnvelope> {
+        // Each step advances the version, so this is bounded by the registry size
+        for _ in 0..=self.upcasters.len() {
+            let version = event.schema_version();
+            let Some(upcaster) = self.upcasters.get(&(event.topic.clone(), version)) else {
+                return Ok(event);
+            };
+            event = upcaster.upcast(event)?.with_schema_version(version + 1);
+        }
+        Err(EventBusError::internal(format!(
+            "Upcaster chain for topic '{}' did not terminate",
+            event.topic
+        )))
+    }
+
+    /// Upcast a batch of events
+    pub fn upcast_all(&self, events: Vec<EventEnvelope>) -> EventBusResult<Vec<EventEnvelope>> {
+        if self.is_empty() {
+            return Ok(events);
+        }
+        events.into_iter().map(|event| self.upcast(event)).collect()
+    }
+}
+
+impl std::fmt::Debug for UpcasterRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("UpcasterRegistry")
+            .field("upcasters", &self.upcasters.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn registry() -> UpcasterRegistry {
+        let mut registry = UpcasterRegistry::new();
+        // v1 -> v2: split "name" into first/last
+        registry.register_fn("user.created", 1, |payload| {
+            let name = payload["name"].as_str().unwrap_or_default().to_string();
+            let (first, last) = name.split_once(' ').unwrap_or((&name, ""));
+            Ok(json!({ "first_name": first, "last_name": last }))
+        }).unwrap();
+        // v2 -> v3: add default locale
+        registry.register_fn("user.created", 2, |mut payload| {
+            payload["locale"] = json!("en");
+            Ok(payload)
+        }).unwrap();
+        registry
+    }
+
+    #[test]
+    fn test_upcast_chain() {
+        let registry = registry();
+        assert_eq!(registry.latest_version("user.created"), 3);
+
+        let event = EventEnvelope::new("user.created", json!({ "name": "Ada Lovelace" }));
+        let upcast = registry.upcast(event).unwrap();
+
+        assert_eq!(upcast.schema_version(), 3);
+        assert_eq!(upcast.payload, json!({ "first_name": "Ada", "last_name": "Lovelace", "locale": "en" }));
+    }
+
+    #[test]
+    fn test_upcast_skips_current_and_unknown() {
+        let registry = registry();
+
+        let current = EventEnvelope::new("user.created", json!({ "first_name": "Ada" }))
+            .with_schema_version(3);
+        assert_eq!(registry.upcast(current.clone()).unwrap(), current);
+
+        let other = EventEnvelope::new("order.created", json!({}));
+        assert_eq!(registry.upcast(other.clone()).unwrap(), other);
+    }
+
+    #[test]
+    fn test_duplicate_registration() {
+        let mut registry = registry();
+        assert!(registry.register_fn("user.created", 1, Ok).is_err());
+    }
+}
@@ -0,0 +1,107 @@
+//! In-process event bus with no configuration
+//!
+//! ```rust
+//! # async fn demo() -> eventbus_rust::EventBusResult<()> {
+//! use eventbus_rust::EmbeddedBus;
+//! use futures::StreamExt;
+//!
+//! let bus = EmbeddedBus::new();
+//! let mut orders = bus.subscribe("orders.*").await?;
+//! bus.emit("orders.created", serde_json::json!({ "order_id": 42 })).await?;
+//! # assert_eq!(orders.next().await.unwrap().topic, "orders.created");
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Events live in memory and rules are off. For anything else, build an
+//! `EventBusService` from a `ServiceConfig`; `service()` gives access to the
+//! one behind an embedded bus.
+
+use std::pin::Pin;
+use std::sync::Arc;
+
+use futures::Stream;
+
+use crate::core::{EventBus, EventBusResult, EventEnvelope, EventQuery, TypedEventBus};
+use crate::service::{EventBusService, ServiceConfig};
+
+/// In-memory event bus for use inside a single process
+///
+/// Cloning is cheap; clones share the same bus.
+#[derive(Clone)]
+pub struct EmbeddedBus {
+    service: Arc<EventBusService>,
+}
+
+impl EmbeddedBus {
+    /// Create a bus with in-memory storage and no rules
+    pub fn new() -> Self {
+        let config = ServiceConfig {
+            instance_id: "embedded".to_string(),
+            ..Default::default()
+        };
+        Self {
+            service: Arc::new(EventBusService::new(config)),
+        }
+    }
+
+    /// Emit a payload to a topic, returning the new event's ID
+    pub async fn emit(&self, topic: impl Into<String>, payload: serde_json::Value) -> EventBusResult<String> {
+        let event = EventEnvelope::new(topic, payload);
+        let event_id = event.event_id.clone();
+        self.service.emit(event).await?;
+        Ok(event_id)
+    }
+
+    /// Receive the events emitted from now on to a topic, or to every topic
+    /// starting with a prefix when it ends in `*` (as in `orders.*`)
+    pub async fn subscribe(&self, topic: &str) -> EventBusResult<Pin<Box<dyn Stream<Item = EventEnvelope> + Send>>> {
+        self.service.subscribe(topic).await
+    }
+
+    /// Events already emitted to a topic (or prefix, as for `subscribe`),
+    /// newest first
+    pub async fn poll(&self, topic: &str) -> EventBusResult<Vec<EventEnvelope>> {
+        self.service.poll(EventQuery::new().with_topic(topic)).await
+    }
+
+    /// Typed view of this bus (see `core::typed`)
+    pub fn typed(&self) -> TypedEventBus {
+        TypedEventBus::new(self.service.clone())
+    }
+
+    /// The service behind this bus, for everything else
+    pub fn service(&self) -> &Arc<EventBusService> {
+        &self.service
+    }
+}
+
+impl Default for EmbeddedBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::StreamExt;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_embedded_emit_subscribe_poll() {
+        let bus = EmbeddedBus::new();
+        let mut orders = bus.subscribe("orders.*").await.unwrap();
+
+        let event_id = bus.emit("orders.created", json!({ "order_id": 42 })).await.unwrap();
+        bus.clone().emit("users.created", json!({})).await.unwrap();
+
+        let event = orders.next().await.unwrap();
+        assert_eq!((event.event_id.as_str(), event.payload["order_id"].as_i64()), (event_id.as_str(), Some(42)));
+
+        let stored = bus.poll("orders.*").await.unwrap();
+        assert_eq!(stored.len(), 1);
+        assert_eq!(bus.poll("*").await.unwrap().len(), 2);
+        assert!(!bus.service().config().enable_rules);
+    }
+}
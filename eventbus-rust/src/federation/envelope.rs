@@ -0,0 +1,93 @@
+//! Sealed events as they travel between buses
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use ed25519_dalek::Signature;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+
+use crate::core::{EventBusError, EventBusResult, EventEnvelope};
+
+/// Event signed by its origin bus for one destination bus
+///
+/// The signature covers both bus IDs, the event and the encrypted payload,
+/// so a sealed event cannot be altered or replayed to a different bus.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SealedEvent {
+    /// Bus that sealed the event
+    pub origin_bus: String,
+    /// Bus the event is addressed to
+    pub destination_bus: String,
+    /// The event; its payload is null when `encrypted_payload` is set
+    pub event: EventEnvelope,
+    /// Payload encrypted with the key shared by both buses
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub encrypted_payload: Option<EncryptedPayload>,
+    /// Base64 ed25519 signature of the origin bus
+    pub signature: String,
+}
+
+/// ChaCha20-Poly1305 encrypted event payload
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EncryptedPayload {
+    /// Base64 96-bit nonce
+    pub nonce: String,
+    /// Base64 ciphertext including the authentication tag
+    pub ciphertext: String,
+}
+
+impl SealedEvent {
+    /// Bytes covered by the signature
+    pub(crate) fn signed_bytes(&self) -> EventBusResult<Vec<u8>> {
+        serde_json::to_vec(&(
+            &self.origin_bus,
+            &self.destination_bus,
+            &self.event,
+            &self.encrypted_payload,
+        ))
+        .map_err(EventBusError::from)
+    }
+
+    pub(crate) fn decode_signature(&self) -> EventBusResult<Signature> {
+        let bytes = BASE64
+            .decode(&self.signature)
+            .map_err(|_| EventBusError::permission_denied("Malformed event signature"))?;
+        Signature::from_slice(&bytes)
+            .map_err(|_| EventBusError::permission_denied("Malformed event signature"))
+    }
+}
+
+impl EncryptedPayload {
+    /// Encrypt a payload, binding it to the event and the origin bus
+    pub(crate) fn seal(key: &[u8; 32], payload: &serde_json::Value, aad: &[u8]) -> EventBusResult<Self> {
+        let mut nonce = [0u8; 12];
+        rand::thread_rng().fill_bytes(&mut nonce);
+
+        let plaintext = serde_json::to_vec(payload)?;
+        let ciphertext = ChaCha20Poly1305::new(Key::from_slice(key))
+            .encrypt(Nonce::from_slice(&nonce), Payload { msg: &plaintext, aad })
+            .map_err(|_| EventBusError::internal("Failed to encrypt event payload"))?;
+
+        Ok(Self {
+            nonce: BASE64.encode(nonce),
+            ciphertext: BASE64.encode(ciphertext),
+        })
+    }
+
+    /// Decrypt the payload, failing if it was tampered with
+    pub(crate) fn open(&self, key: &[u8; 32], aad: &[u8]) -> EventBusResult<serde_json::Value> {
+        let malformed = || EventBusError::permission_denied("Malformed encrypted payload");
+        let nonce = BASE64.decode(&self.nonce).map_err(|_| malformed())?;
+        if nonce.len() != 12 {
+            return Err(malformed());
+        }
+        let ciphertext = BASE64.decode(&self.ciphertext).map_err(|_| malformed())?;
+
+        let plaintext = ChaCha20Poly1305::new(Key::from_slice(key))
+            .decrypt(Nonce::from_slice(&nonce), Payload { msg: &ciphertext, aad })
+            .map_err(|_| EventBusError::permission_denied("Encrypted payload failed authentication"))?;
+        Ok(serde_json::from_slice(&plaintext)?)
+    }
+}
@@ -0,0 +1,118 @@
+//! Bus identities and per-peer keys
+
+use std::fmt;
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use ed25519_dalek::{Signature, Signer, SigningKey, VerifyingKey};
+
+use crate::core::{EventBusError, EventBusResult};
+
+/// Signing identity of the local bus
+pub struct BusIdentity {
+    bus_id: String,
+    signing_key: SigningKey,
+}
+
+impl BusIdentity {
+    /// Create an identity with a freshly generated key
+    pub fn generate(bus_id: impl Into<String>) -> Self {
+        Self {
+            bus_id: bus_id.into(),
+            signing_key: SigningKey::generate(&mut rand::rngs::OsRng),
+        }
+    }
+
+    /// Create an identity from a stored 32-byte ed25519 secret key
+    pub fn from_secret_key(bus_id: impl Into<String>, secret_key: &[u8; 32]) -> Self {
+        Self {
+            bus_id: bus_id.into(),
+            signing_key: SigningKey::from_bytes(secret_key),
+        }
+    }
+
+    /// ID peers know this bus by
+    pub fn bus_id(&self) -> &str {
+        &self.bus_id
+    }
+
+    /// Public key to hand out to peers
+    pub fn public_key(&self) -> VerifyingKey {
+        self.signing_key.verifying_key()
+    }
+
+    /// Public key encoded as base64, as accepted by [`PeerKeys::from_base64`]
+    pub fn public_key_base64(&self) -> String {
+        BASE64.encode(self.public_key().as_bytes())
+    }
+
+    pub(crate) fn sign(&self, message: &[u8]) -> Signature {
+        self.signing_key.sign(message)
+    }
+}
+
+impl fmt::Debug for BusIdentity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BusIdentity")
+            .field("bus_id", &self.bus_id)
+            .field("public_key", &self.public_key_base64())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Keys shared with one remote bus
+///
+/// The verifying key checks that events really come from the peer. The
+/// optional encryption key is a secret both buses configure for each other
+/// and is required for encrypted forwarding in either direction.
+#[derive(Clone)]
+pub struct PeerKeys {
+    verifying_key: VerifyingKey,
+    encryption_key: Option<[u8; 32]>,
+}
+
+impl PeerKeys {
+    /// Keys for a peer that only signs its events
+    pub fn new(verifying_key: VerifyingKey) -> Self {
+        Self {
+            verifying_key,
+            encryption_key: None,
+        }
+    }
+
+    /// Keys from a base64-encoded ed25519 public key
+    pub fn from_base64(public_key: &str) -> EventBusResult<Self> {
+        let bytes = BASE64
+            .decode(public_key)
+            .map_err(|e| EventBusError::configuration(format!("Invalid peer public key: {}", e)))?;
+        let bytes: [u8; 32] = bytes
+            .try_into()
+            .map_err(|_| EventBusError::configuration("Peer public key must be 32 bytes"))?;
+        let verifying_key = VerifyingKey::from_bytes(&bytes)
+            .map_err(|e| EventBusError::configuration(format!("Invalid peer public key: {}", e)))?;
+        Ok(Self::new(verifying_key))
+    }
+
+    /// Set the symmetric key used to encrypt payloads exchanged with the peer
+    pub fn with_encryption_key(mut self, key: [u8; 32]) -> Self {
+        self.encryption_key = Some(key);
+        self
+    }
+
+    pub(crate) fn encryption_key(&self) -> Option<&[u8; 32]> {
+        self.encryption_key.as_ref()
+    }
+
+    pub(crate) fn verify(&self, message: &[u8], signature: &Signature) -> bool {
+        self.verifying_key.verify_strict(message, signature).is_ok()
+    }
+}
+
+impl fmt::Debug for PeerKeys {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PeerKeys")
+            .field("public_key", &BASE64.encode(self.verifying_key.as_bytes()))
+            .field("encryption", &self.encryption_key.is_some())
+            .finish()
+    }
+}
@@ -0,0 +1,258 @@
+//! Signed and encrypted forwarding between federated buses
+//!
+//! Available with the `federation` feature. Each bus holds an ed25519
+//! [`BusIdentity`] and the [`PeerKeys`] of the buses it exchanges events
+//! with. Outgoing events matching a [`FederationRule`] are sealed for the
+//! rule's peer: always signed, and with the payload encrypted under the key
+//! shared with that peer when the rule asks for it. The receiving bus opens
+//! a [`SealedEvent`] only if it comes from a known peer, is addressed to it
+//! and carries a valid signature, so tampered or spoofed events are rejected.
+//!
+//! The gateway only seals and opens events; moving sealed events over the
+//! network is left to the bridge carrying them.
+
+mod envelope;
+mod keys;
+
+pub use envelope::{EncryptedPayload, SealedEvent};
+pub use keys::{BusIdentity, PeerKeys};
+
+use std::collections::HashMap;
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+
+use crate::core::{EventBusError, EventBusResult, EventEnvelope};
+use crate::utils::topic_matches_pattern;
+
+/// Protection applied to events forwarded by a rule
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TransitSecurity {
+    /// Signed by the origin bus; the payload travels in clear
+    #[default]
+    Signed,
+    /// Signed, with the payload encrypted for the destination bus
+    Encrypted,
+}
+
+/// Forwards events on matching topics to a peer bus
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FederationRule {
+    /// Topic pattern of events to forward
+    pub topic: String,
+    /// Bus ID of the destination peer
+    pub peer: String,
+    /// Protection applied in transit
+    #[serde(default)]
+    pub security: TransitSecurity,
+}
+
+impl FederationRule {
+    /// Create a rule forwarding signed events
+    pub fn new(topic: impl Into<String>, peer: impl Into<String>) -> Self {
+        Self {
+            topic: topic.into(),
+            peer: peer.into(),
+            security: TransitSecurity::default(),
+        }
+    }
+
+    /// Set the protection applied in transit
+    pub fn with_security(mut self, security: TransitSecurity) -> Self {
+        self.security = security;
+        self
+    }
+}
+
+/// Seals outgoing and opens incoming federated events
+#[derive(Debug)]
+pub struct FederationGateway {
+    identity: BusIdentity,
+    peers: HashMap<String, PeerKeys>,
+    rules: Vec<FederationRule>,
+}
+
+impl FederationGateway {
+    /// Create a gateway for the local bus identity
+    pub fn new(identity: BusIdentity) -> Self {
+        Self {
+            identity,
+            peers: HashMap::new(),
+            rules: Vec::new(),
+        }
+    }
+
+    /// Trust a peer bus
+    pub fn with_peer(mut self, bus_id: impl Into<String>, keys: PeerKeys) -> Self {
+        self.peers.insert(bus_id.into(), keys);
+        self
+    }
+
+    /// Add a forwarding rule
+    pub fn with_rule(mut self, rule: FederationRule) -> Self {
+        self.rules.push(rule);
+        self
+    }
+
+    /// Local bus identity
+    pub fn identity(&self) -> &BusIdentity {
+        &self.identity
+    }
+
+    /// Seal an event for every peer whose rule matches its topic
+    ///
+    /// A peer matched by several rules receives the event once, with the
+    /// strongest protection any of them asks for.
+    pub fn seal_for_peers(&self, event: &EventEnvelope) -> EventBusResult<Vec<SealedEvent>> {
+        let mut targets: Vec<(&str, TransitSecurity)> = Vec::new();
+        for rule in self.rules.iter().filter(|rule| topic_matches_pattern(&event.topic, &rule.topic)) {
+            match targets.iter_mut().find(|(peer, _)| *peer == rule.peer) {
+                Some((_, security)) if rule.security == TransitSecurity::Encrypted => *security = rule.security,
+                Some(_) => {}
+                None => targets.push((&rule.peer, rule.security)),
+            }
+        }
+
+        targets
+            .into_iter()
+            .map(|(peer, security)| self.seal(event, peer, security))
+            .collect()
+    }
+
+    /// Seal an event for one peer
+    pub fn seal(&self, event: &EventEnvelope, peer: &str, security: TransitSecurity) -> EventBusResult<SealedEvent> {
+        let keys = self
+            .peers
+            .get(peer)
+            .ok_or_else(|| EventBusError::configuration(format!("Unknown federation peer: {}", peer)))?;
+
+        let mut event = event.clone();
+        let encrypted_payload = match security {
+            TransitSecurity::Signed => None,
+            TransitSecurity::Encrypted => {
+                let key = keys.encryption_key().ok_or_else(|| {
+                    EventBusError::configuration(format!("No encryption key configured for peer: {}", peer))
+                })?;
+                let aad = payload_aad(self.identity.bus_id(), &event.event_id);
+                let payload = std::mem::take(&mut event.payload);
+                Some(EncryptedPayload::seal(key, &payload, &aad)?)
+            }
+        };
+
+        let mut sealed = SealedEvent {
+            origin_bus: self.identity.bus_id().to_string(),
+            destination_bus: peer.to_string(),
+            event,
+            encrypted_payload,
+            signature: String::new(),
+        };
+        let signature = self.identity.sign(&sealed.signed_bytes()?);
+        sealed.signature = BASE64.encode(signature.to_bytes());
+        Ok(sealed)
+    }
+
+    /// Verify a sealed event from a peer and recover the original event
+    pub fn open(&self, sealed: SealedEvent) -> EventBusResult<EventEnvelope> {
+        if sealed.destination_bus != self.identity.bus_id() {
+            return Err(EventBusError::permission_denied(format!(
+                "Event addressed to bus {}, not {}",
+                sealed.destination_bus,
+                self.identity.bus_id()
+            )));
+        }
+
+        let keys = self.peers.get(&sealed.origin_bus).ok_or_else(|| {
+            EventBusError::permission_denied(format!("Unknown federation peer: {}", sealed.origin_bus))
+        })?;
+        let signature = sealed.decode_signature()?;
+        if !keys.verify(&sealed.signed_bytes()?, &signature) {
+            return Err(EventBusError::permission_denied(format!(
+                "Invalid signature on event {} from bus {}",
+                sealed.event.event_id, sealed.origin_bus
+            )));
+        }
+
+        let SealedEvent { origin_bus, mut event, encrypted_payload, .. } = sealed;
+        if let Some(encrypted) = encrypted_payload {
+            let key = keys.encryption_key().ok_or_else(|| {
+                EventBusError::configuration(format!("No encryption key configured for peer: {}", origin_bus))
+            })?;
+            event.payload = encrypted.open(key, &payload_aad(&origin_bus, &event.event_id))?;
+        }
+        Ok(event)
+    }
+}
+
+/// Associated data binding an encrypted payload to its event and origin
+fn payload_aad(origin_bus: &str, event_id: &str) -> Vec<u8> {
+    format!("{}\n{}", origin_bus, event_id).into_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const SHARED_KEY: [u8; 32] = [7; 32];
+
+    fn gateways() -> (FederationGateway, FederationGateway) {
+        let east = BusIdentity::generate("east");
+        let west = BusIdentity::generate("west");
+        let east_keys = PeerKeys::new(east.public_key()).with_encryption_key(SHARED_KEY);
+        let west_keys = PeerKeys::from_base64(&west.public_key_base64())
+            .unwrap()
+            .with_encryption_key(SHARED_KEY);
+
+        let east = FederationGateway::new(east)
+            .with_peer("west", west_keys)
+            .with_rule(FederationRule::new("orders.*", "west"))
+            .with_rule(FederationRule::new("payments.*", "west").with_security(TransitSecurity::Encrypted));
+        let west = FederationGateway::new(west).with_peer("east", east_keys);
+        (east, west)
+    }
+
+    #[test]
+    fn test_signed_and_encrypted_round_trip() {
+        let (east, west) = gateways();
+
+        let order = EventEnvelope::new("orders.created", json!({ "id": 1 }));
+        let sealed = east.seal_for_peers(&order).unwrap();
+        assert_eq!(sealed.len(), 1);
+        assert!(sealed[0].encrypted_payload.is_none());
+        assert_eq!(west.open(sealed[0].clone()).unwrap(), order);
+
+        let payment = EventEnvelope::new("payments.settled", json!({ "amount": 42 }));
+        let sealed = east.seal_for_peers(&payment).unwrap().remove(0);
+        assert_eq!(sealed.event.payload, serde_json::Value::Null);
+        assert!(sealed.encrypted_payload.is_some());
+        assert_eq!(west.open(sealed).unwrap(), payment);
+
+        assert!(east.seal_for_peers(&EventEnvelope::new("audit.log", json!({}))).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_rejects_tampered_and_misaddressed_events() {
+        let (east, west) = gateways();
+        let event = EventEnvelope::new("orders.created", json!({ "amount": 10 }));
+
+        let mut tampered = east.seal(&event, "west", TransitSecurity::Signed).unwrap();
+        tampered.event.payload = json!({ "amount": 10_000 });
+        assert_eq!(west.open(tampered).unwrap_err().category(), "permission");
+
+        let mut spoofed = east.seal(&event, "west", TransitSecurity::Signed).unwrap();
+        spoofed.origin_bus = "north".to_string();
+        assert!(west.open(spoofed).is_err());
+
+        let sealed = east.seal(&event, "west", TransitSecurity::Encrypted).unwrap();
+        assert!(east.open(sealed).is_err());
+
+        let mut ciphertext_swapped = east.seal(&event, "west", TransitSecurity::Encrypted).unwrap();
+        let other = east
+            .seal(&EventEnvelope::new("orders.created", json!({})), "west", TransitSecurity::Encrypted)
+            .unwrap();
+        ciphertext_swapped.encrypted_payload = other.encrypted_payload;
+        assert!(west.open(ciphertext_swapped).is_err());
+    }
+}
@@ -16,6 +16,7 @@ use jsonrpc_rust::transport::tcp::TcpTransport;
 type ClientResult<T> = std::result::Result<T, Box<dyn std::error::Error + Send + Sync>>;
 
 use crate::core::{EventEnvelope, EventQuery};
+use crate::queue::Delivery;
 use crate::jsonrpc::methods::*;
 
 /// EventBus JSON-RPC client
@@ -52,7 +53,21 @@ impl EventBusRpcClient {
 
     /// Emit a single event
     pub async fn emit(&self, event: EventEnvelope) -> ClientResult<bool> {
-        let params = EmitParams { event };
+        self.emit_with_params(EmitParams { event, idempotency_key: None, queue_timeout_ms: None }).await
+    }
+
+    /// Emit a single event with an idempotency key
+    /// 
+    /// Retrying with the same key (e.g. after a reconnect) never emits the event twice.
+    pub async fn emit_idempotent(&self, event: EventEnvelope, idempotency_key: impl Into<String>) -> ClientResult<bool> {
+        self.emit_with_params(EmitParams {
+            event,
+            idempotency_key: Some(idempotency_key.into()),
+            queue_timeout_ms: None,
+        }).await
+    }
+
+    async fn emit_with_params(&self, params: EmitParams) -> ClientResult<bool> {
         let request = JsonRpcRequest::new(method_names::EMIT, Some(serde_json::to_value(params)?));
         
         let response = self.send_request(request).await?;
@@ -113,11 +128,108 @@ impl EventBusRpcClient {
         }
     }
 
+    /// Query events, waiting up to `timeout` on the server for a matching event
+    /// when none are stored yet
+    pub async fn poll_wait(&self, query: EventQuery, timeout: Duration) -> ClientResult<Vec<EventEnvelope>> {
+        let params = PollWaitParams { query, timeout_ms: timeout.as_millis() as u64 };
+        let request = JsonRpcRequest::new(method_names::POLL_WAIT, Some(serde_json::to_value(params)?));
+        
+        let response = self.send_request(request).await?;
+        
+        match response.result {
+            Some(result) => {
+                let poll_response: PollResponse = serde_json::from_value(result)?;
+                Ok(poll_response.events)
+            },
+            None => {
+                if let Some(error) = response.error {
+                    return Err(format!("RPC error: {}", error.message).into());
+                }
+                Err("No result or error in response".into())
+            }
+        }
+    }
+
+    /// Lease up to `max_events` events from a consumer group, waiting up to
+    /// `timeout` on the server when none is ready
+    pub async fn queue_fetch(
+        &self,
+        group: &str,
+        consumer_id: &str,
+        max_events: usize,
+        timeout: Duration,
+    ) -> ClientResult<Vec<Delivery>> {
+        let params = QueueFetchParams {
+            group: group.to_string(),
+            consumer_id: consumer_id.to_string(),
+            max_events,
+            timeout_ms: timeout.as_millis() as u64,
+        };
+        let request = JsonRpcRequest::new(method_names::QUEUE_FETCH, Some(serde_json::to_value(params)?));
+        
+        let response = self.send_request(request).await?;
+        
+        match response.result {
+            Some(result) => {
+                let fetch_response: QueueFetchResponse = serde_json::from_value(result)?;
+                Ok(fetch_response.deliveries)
+            },
+            None => {
+                if let Some(error) = response.error {
+                    return Err(format!("RPC error: {}", error.message).into());
+                }
+                Err("No result or error in response".into())
+            }
+        }
+    }
+
+    /// Acknowledge a delivery leased from a consumer group
+    pub async fn queue_ack(&self, group: &str, delivery_id: &str) -> ClientResult<bool> {
+        let params = QueueAckParams { group: group.to_string(), delivery_id: delivery_id.to_string() };
+        let request = JsonRpcRequest::new(method_names::QUEUE_ACK, Some(serde_json::to_value(params)?));
+        self.send_ack(request).await
+    }
+
+    /// Reject a delivery, redelivering it (`requeue`) or dead-lettering it
+    pub async fn queue_nack(&self, group: &str, delivery_id: &str, requeue: bool) -> ClientResult<bool> {
+        let params = QueueNackParams { group: group.to_string(), delivery_id: delivery_id.to_string(), requeue };
+        let request = JsonRpcRequest::new(method_names::QUEUE_NACK, Some(serde_json::to_value(params)?));
+        self.send_ack(request).await
+    }
+
+    async fn send_ack(&self, request: JsonRpcRequest) -> ClientResult<bool> {
+        let response = self.send_request(request).await?;
+        
+        match response.result {
+            Some(result) => {
+                let ack_response: QueueAckResponse = serde_json::from_value(result)?;
+                Ok(ack_response.success)
+            },
+            None => {
+                if let Some(error) = response.error {
+                    return Err(format!("RPC error: {}", error.message).into());
+                }
+                Err("No result or error in response".into())
+            }
+        }
+    }
+
     /// Subscribe to a topic
     pub async fn subscribe(&self, topic: &str, client_id: Option<String>) -> ClientResult<SubscriptionHandle> {
+        self.open_subscription(topic, None, client_id).await
+    }
+
+    /// Subscribe to a topic, receiving only events that satisfy a filter
+    /// expression evaluated by the server
+    pub async fn subscribe_filtered(&self, topic: &str, filter: &str, client_id: Option<String>) -> ClientResult<SubscriptionHandle> {
+        self.open_subscription(topic, Some(filter.to_string()), client_id).await
+    }
+
+    async fn open_subscription(&self, topic: &str, filter: Option<String>, client_id: Option<String>) -> ClientResult<SubscriptionHandle> {
         let params = SubscribeParams { 
             topic: topic.to_string(),
             client_id,
+            filter,
         };
         let request = JsonRpcRequest::new(method_names::SUBSCRIBE, Some(serde_json::to_value(params)?));
         
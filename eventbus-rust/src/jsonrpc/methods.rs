@@ -5,7 +5,10 @@
 
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use crate::core::{EventEnvelope, EventQuery, BusStats};
+use crate::core::{EventEnvelope, EventQuery, EventTriggerRule, BusStats, TopicInfo, TopicSettings};
+use crate::service::{BusMode, ModeStatus, RateLimitStatus};
+use crate::queue::{Delivery, WorkQueueConfig, WorkQueueStats};
+use crate::replication::ReplicationStatus;
 
 /// JSON-RPC method names for EventBus operations
 pub mod method_names {
@@ -18,6 +21,9 @@ pub mod method_names {
     /// Query events based on criteria
     pub const POLL: &str = "eventbus.poll";
     
+    /// Query events, waiting for a matching event when none are stored (long-polling)
+    pub const POLL_WAIT: &str = "eventbus.poll_wait";
+    
     /// Subscribe to a topic (returns subscription ID)
     pub const SUBSCRIBE: &str = "eventbus.subscribe";
     
@@ -32,6 +38,77 @@ pub mod method_names {
     
     /// Get next events from subscription (for polling-based clients)
     pub const GET_SUBSCRIPTION_EVENTS: &str = "eventbus.get_subscription_events";
+    
+    /// Register an event trigger rule
+    pub const REGISTER_RULE: &str = "eventbus.register_rule";
+    
+    /// Pre-create a topic with settings (admin)
+    pub const CREATE_TOPIC: &str = "eventbus.create_topic";
+    
+    /// Remove a registered topic (admin)
+    pub const DELETE_TOPIC: &str = "eventbus.delete_topic";
+    
+    /// Describe a topic's registration and effective retention
+    pub const DESCRIBE_TOPIC: &str = "eventbus.describe_topic";
+    
+    /// Create a competing-consumer group on a topic pattern (admin)
+    pub const CREATE_QUEUE: &str = "eventbus.create_queue";
+    
+    /// Remove a consumer group and its queued events (admin)
+    pub const DELETE_QUEUE: &str = "eventbus.delete_queue";
+    
+    /// Lease events from a consumer group, waiting for work when none is ready
+    pub const QUEUE_FETCH: &str = "eventbus.queue_fetch";
+    
+    /// Acknowledge a leased event
+    pub const QUEUE_ACK: &str = "eventbus.queue_ack";
+    
+    /// Reject a leased event, redelivering or dead-lettering it
+    pub const QUEUE_NACK: &str = "eventbus.queue_nack";
+    
+    /// Get the state of a consumer group
+    pub const QUEUE_STATS: &str = "eventbus.queue_stats";
+    
+    /// Switch the bus to normal, read-only or maintenance mode (admin)
+    pub const SET_MODE: &str = "eventbus.set_mode";
+    
+    /// Get the current bus mode
+    pub const GET_MODE: &str = "eventbus.get_mode";
+    
+    /// Report bus health (served in every mode)
+    pub const HEALTH: &str = "eventbus.health";
+    
+    /// Read the replication log of a leader, from an offset
+    pub const REPLICATION_FETCH: &str = "eventbus.replication_fetch";
+    
+    /// Get the replication role, position and lag of the bus
+    pub const REPLICATION_STATUS: &str = "eventbus.replication_status";
+    
+    /// Stop following the leader and accept writes (admin, served in every mode)
+    pub const PROMOTE: &str = "eventbus.promote";
+    
+    /// Check the stored events of a topic against their checksums and hash chain
+    pub const VERIFY_TOPIC_INTEGRITY: &str = "eventbus.verify_topic_integrity";
+    
+    /// All methods served by the EventBus JSON-RPC server
+    pub const ALL: &[&str] = &[
+        EMIT, EMIT_BATCH, POLL, POLL_WAIT, SUBSCRIBE, UNSUBSCRIBE, LIST_TOPICS, GET_STATS,
+        GET_SUBSCRIPTION_EVENTS, REGISTER_RULE, CREATE_TOPIC, DELETE_TOPIC, DESCRIBE_TOPIC,
+        CREATE_QUEUE, DELETE_QUEUE, QUEUE_FETCH, QUEUE_ACK, QUEUE_NACK, QUEUE_STATS,
+        SET_MODE, GET_MODE, HEALTH, REPLICATION_FETCH, REPLICATION_STATUS, PROMOTE,
+        VERIFY_TOPIC_INTEGRITY,
+    ];
+    
+    /// Methods accepted in every bus mode
+    pub const ALWAYS_AVAILABLE: &[&str] = &[SET_MODE, GET_MODE, HEALTH, PROMOTE];
+    
+    /// Methods served only with the admin token or to callers with the `admin` role
+    pub const ADMIN: &[&str] = &[SET_MODE, PROMOTE];
+    
+    /// Methods that change the bus, rejected unless it is in normal mode
+    pub const WRITES: &[&str] = &[
+        EMIT, EMIT_BATCH, REGISTER_RULE, CREATE_TOPIC, DELETE_TOPIC, CREATE_QUEUE, DELETE_QUEUE,
+    ];
 }
 
 /// Parameters for emit method
@@ -39,6 +116,12 @@ pub mod method_names {
 pub struct EmitParams {
     /// Event to emit
     pub event: EventEnvelope,
+    /// Key making retries of this request safe (replays return the original response)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub idempotency_key: Option<String>,
+    /// Wait up to this long for rate limit capacity instead of being rejected
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub queue_timeout_ms: Option<u64>,
 }
 
 /// Parameters for emit_batch method
@@ -55,6 +138,15 @@ pub struct PollParams {
     pub query: EventQuery,
 }
 
+/// Parameters for poll_wait method
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PollWaitParams {
+    /// Query criteria
+    pub query: EventQuery,
+    /// How long to wait for a matching event, capped by the server
+    pub timeout_ms: u64,
+}
+
 /// Parameters for subscribe method
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SubscribeParams {
@@ -62,6 +154,9 @@ pub struct SubscribeParams {
     pub topic: String,
     /// Optional client ID for tracking
     pub client_id: Option<String>,
+    /// Filter expression evaluated before delivery, e.g. `payload.priority == "high"`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub filter: Option<String>,
 }
 
 /// Parameters for unsubscribe method
@@ -82,11 +177,144 @@ pub struct GetSubscriptionEventsParams {
     pub timeout_ms: Option<u64>,
 }
 
+/// Parameters for register_rule method
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RegisterRuleParams {
+    /// Rule to register
+    pub rule: EventTriggerRule,
+}
+
+/// Parameters for create_topic method
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateTopicParams {
+    /// Topic name
+    pub topic: String,
+    /// Topic settings
+    #[serde(default)]
+    pub settings: TopicSettings,
+}
+
+/// Parameters for delete_topic method
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeleteTopicParams {
+    /// Topic name
+    pub topic: String,
+}
+
+/// Parameters for create_queue method
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateQueueParams {
+    /// Group configuration
+    #[serde(flatten)]
+    pub config: WorkQueueConfig,
+}
+
+/// Parameters for delete_queue and queue_stats methods
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueueParams {
+    /// Group name
+    pub group: String,
+}
+
+/// Parameters for queue_fetch method
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueueFetchParams {
+    /// Group name
+    pub group: String,
+    /// Consumer fetching the events
+    pub consumer_id: String,
+    /// Maximum number of events to lease
+    #[serde(default = "default_fetch_max")]
+    pub max_events: usize,
+    /// How long to wait for an event when none is ready, capped by the server
+    #[serde(default)]
+    pub timeout_ms: u64,
+}
+
+fn default_fetch_max() -> usize {
+    1
+}
+
+/// Parameters for queue_ack method
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueueAckParams {
+    /// Group name
+    pub group: String,
+    /// Delivery to acknowledge
+    pub delivery_id: String,
+}
+
+/// Parameters for queue_nack method
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueueNackParams {
+    /// Group name
+    pub group: String,
+    /// Delivery to reject
+    pub delivery_id: String,
+    /// Redeliver the event (true) or dead-letter it (false)
+    #[serde(default = "default_requeue")]
+    pub requeue: bool,
+}
+
+fn default_requeue() -> bool {
+    true
+}
+
+/// Parameters for set_mode method
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SetModeParams {
+    /// Mode to switch to
+    pub mode: BusMode,
+    /// Why the mode is set, reported in rejections
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+/// Parameters for describe_topic method
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DescribeTopicParams {
+    /// Topic name
+    pub topic: String,
+}
+
+/// Parameters for verify_topic_integrity method
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VerifyTopicIntegrityParams {
+    /// Topic name
+    pub topic: String,
+    /// Minimum event timestamp (inclusive)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub since: Option<i64>,
+    /// Maximum event timestamp (exclusive); leave out to check the chain head too
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub until: Option<i64>,
+}
+
+/// Parameters for replication_fetch method
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplicationFetchParams {
+    /// Offset of the last event the follower applied (0 for none)
+    pub after: u64,
+    /// Most events to return
+    #[serde(default = "default_replication_limit")]
+    pub limit: u32,
+}
+
+fn default_replication_limit() -> u32 {
+    500
+}
+
 /// Response for emit method
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EmitResponse {
     /// Success indicator
     pub success: bool,
+    /// ID of the emitted event (the original one for idempotent replays)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub event_id: Option<String>,
+    /// Rate limit state after this emit
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rate_limit: Option<RateLimitStatus>,
 }
 
 /// Response for emit_batch method
@@ -96,6 +324,9 @@ pub struct EmitBatchResponse {
     pub success: bool,
     /// Number of events processed
     pub processed_count: usize,
+    /// Rate limit state after this batch
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rate_limit: Option<RateLimitStatus>,
 }
 
 /// Response for poll method
@@ -123,6 +354,56 @@ pub struct UnsubscribeResponse {
     pub success: bool,
 }
 
+/// Response for register_rule method
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RegisterRuleResponse {
+    /// Success indicator
+    pub success: bool,
+}
+
+/// Response for create_topic and delete_topic methods
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TopicResponse {
+    /// Affected topic
+    pub topic: TopicInfo,
+}
+
+/// Response for create_queue, delete_queue and queue_stats methods
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueueResponse {
+    /// Affected group
+    pub queue: WorkQueueStats,
+}
+
+/// Response for queue_fetch method
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueueFetchResponse {
+    /// Leased events
+    pub deliveries: Vec<Delivery>,
+}
+
+/// Response for queue_ack and queue_nack methods
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueueAckResponse {
+    /// Success indicator
+    pub success: bool,
+}
+
+/// Response for set_mode and get_mode methods
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModeResponse {
+    /// Mode in effect
+    #[serde(flatten)]
+    pub status: ModeStatus,
+}
+
+/// Response for replication_status and promote methods
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplicationStatusResponse {
+    /// Replication state, or none when the bus neither leads nor follows
+    pub replication: Option<ReplicationStatus>,
+}
+
 /// Response for list_topics method
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ListTopicsResponse {
@@ -207,4 +488,22 @@ pub mod error_codes {
     
     /// Rate limit exceeded
     pub const RATE_LIMIT_EXCEEDED: i32 = -32005;
+    
+    /// Topic rejected by the bus topic policy
+    pub const TOPIC_NOT_ALLOWED: i32 = -32006;
+    
+    /// Resource already exists
+    pub const ALREADY_EXISTS: i32 = -32007;
+    
+    /// Work queue not found
+    pub const QUEUE_NOT_FOUND: i32 = -32008;
+    
+    /// Delivery not leased (unknown, already acknowledged or expired)
+    pub const DELIVERY_NOT_FOUND: i32 = -32009;
+    
+    /// Stored event does not match its checksum
+    pub const INTEGRITY_VIOLATION: i32 = -32010;
+    
+    /// Admin method called without the admin token or role
+    pub const UNAUTHORIZED: i32 = -32011;
 } 
\ No newline at end of file
@@ -4,20 +4,43 @@
 //! over the network using the jsonrpc-rust framework.
 
 use std::collections::HashMap;
+use std::net::SocketAddr;
 use std::sync::Arc;
-use std::time::{SystemTime, UNIX_EPOCH};
-use tokio::sync::{RwLock, broadcast};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use async_trait::async_trait;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{RwLock, Semaphore, broadcast};
+use tokio::task::JoinHandle;
+use tokio_util::codec::{Framed, LengthDelimitedCodec};
+use futures::{SinkExt, StreamExt};
+use serde::{de::DeserializeOwned, Serialize};
 use uuid::Uuid;
-use serde_json::{json, Value};
+use serde_json::Value;
 
 use jsonrpc_rust::prelude::*;
-use jsonrpc_rust::transport::tcp::TcpTransport;
+use jsonrpc_rust::core::types::ClientInfo;
+use jsonrpc_rust::transport::tcp::{TcpConfig, TcpTransport};
+use jsonrpc_rust::transport::abstraction::{ConnectionLimits, TimeoutConfig};
 
-use crate::core::traits::{EventBus, BusStats};
-use crate::core::{EventEnvelope, EventQuery};
+use crate::config::TransportConfig;
+use crate::core::traits::EventBus;
+use crate::core::{EventBusError, EventEnvelope, EventFilter, IngestMetadata};
+use crate::core::integrity::IntegrityReport;
+use crate::replication::ReplicationBatch;
 use crate::service::EventBusService;
 use crate::jsonrpc::methods::*;
 
+type ServerResult<T> = std::result::Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+/// Longest time a poll_wait request may hold its connection
+const MAX_POLL_WAIT: Duration = Duration::from_secs(60);
+
+/// Pause before accepting again after a failed accept
+/// 
+/// Errors such as running out of file descriptors last until connections
+/// close, so retrying at once would only spin.
+const ACCEPT_ERROR_BACKOFF: Duration = Duration::from_millis(100);
+
 /// Subscription information for managing client subscriptions
 #[derive(Debug, Clone)]
 struct SubscriptionInfo {
@@ -28,6 +51,7 @@ struct SubscriptionInfo {
 }
 
 /// EventBus JSON-RPC server
+#[derive(Clone)]
 pub struct EventBusRpcServer {
     /// The underlying EventBus service
     bus_service: Arc<EventBusService>,
@@ -35,36 +59,355 @@ pub struct EventBusRpcServer {
     subscriptions: Arc<RwLock<HashMap<String, SubscriptionInfo>>>,
     /// Server start time
     start_time: SystemTime,
+    /// Limits enforced on accepted connections
+    transport_config: TransportConfig,
+}
+
+/// Handle to a JSON-RPC server running in the background
+pub struct RpcServerHandle {
+    /// Address the listener is bound to
+    local_addr: SocketAddr,
+    /// Signals the accept loop and open connections to stop
+    shutdown_tx: broadcast::Sender<()>,
+    /// Accept loop task
+    task: JoinHandle<()>,
+}
+
+impl RpcServerHandle {
+    /// Get the address the server is listening on
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Stop accepting connections, close open ones and wait for the server to exit
+    pub async fn shutdown(self) {
+        let _ = self.shutdown_tx.send(());
+        let _ = self.task.await;
+    }
 }
 
 impl EventBusRpcServer {
     /// Create a new EventBus JSON-RPC server
+    ///
+    /// Transport limits are taken from the service configuration.
     pub fn new(bus_service: Arc<EventBusService>) -> Self {
+        let transport_config = bus_service.config().transport.clone();
         Self {
             bus_service,
             subscriptions: Arc::new(RwLock::new(HashMap::new())),
             start_time: SystemTime::now(),
+            transport_config,
         }
     }
 
+    /// Override the transport limits
+    pub fn with_transport_config(mut self, transport_config: TransportConfig) -> Self {
+        self.transport_config = transport_config;
+        self
+    }
+
     /// Start the JSON-RPC server on the specified address
-    pub async fn start(&self, addr: &str) -> std::result::Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        println!("Starting EventBus JSON-RPC server on {}", addr);
-        
-        // 这里会在实际的jsonrpc-rust实现完成后再完善
-        // 目前先提供基本的结构
-        
+    ///
+    /// Serves requests until the listener fails; use [`spawn`](Self::spawn)
+    /// to run the server in the background with a shutdown handle.
+    pub async fn start(&self, addr: &str) -> ServerResult<()> {
+        let addr = tokio::net::lookup_host(addr).await?
+            .next()
+            .ok_or_else(|| format!("Could not resolve address: {}", addr))?;
+        let listener = self.bind(addr).await?;
+        tracing::info!("EventBus JSON-RPC server listening on {}", listener.local_addr()?);
+
+        // Keep the sender alive so the server only stops on error
+        let (_shutdown_tx, shutdown_rx) = broadcast::channel(1);
+        self.serve(listener, shutdown_rx).await;
         Ok(())
     }
 
+    /// Bind to the specified address and serve requests in a background task
+    pub async fn spawn(&self, addr: SocketAddr) -> ServerResult<RpcServerHandle> {
+        let listener = self.bind(addr).await?;
+        let local_addr = listener.local_addr()?;
+        tracing::info!("EventBus JSON-RPC server listening on {}", local_addr);
+
+        let (shutdown_tx, shutdown_rx) = broadcast::channel(1);
+        let server = self.clone();
+        let task = tokio::spawn(async move {
+            server.serve(listener, shutdown_rx).await;
+        });
+
+        Ok(RpcServerHandle {
+            local_addr,
+            shutdown_tx,
+            task,
+        })
+    }
+
+    /// Bind a listener through the jsonrpc-rust TCP transport
+    async fn bind(&self, addr: SocketAddr) -> ServerResult<TcpListener> {
+        let transport = TcpTransport::new(self.tcp_config(addr)).await?;
+        Ok(transport.listen().await?)
+    }
+
+    /// Build the TCP transport configuration from the transport limits
+    fn tcp_config(&self, addr: SocketAddr) -> TcpConfig {
+        let config = &self.transport_config;
+        TcpConfig {
+            bind_address: Some(addr),
+            timeouts: TimeoutConfig {
+                connect_timeout: Duration::from_millis(config.connect_timeout_ms),
+                read_timeout: Duration::from_millis(config.read_timeout_ms),
+                write_timeout: Duration::from_millis(config.write_timeout_ms),
+                ..TimeoutConfig::default()
+            },
+            connection_limits: ConnectionLimits {
+                max_connections: config.max_connections as usize,
+                max_message_size: config.max_message_size,
+                ..ConnectionLimits::default()
+            },
+            ..TcpConfig::default()
+        }
+    }
+
+    /// Accept connections until a shutdown signal is received
+    async fn serve(&self, listener: TcpListener, mut shutdown: broadcast::Receiver<()>) {
+        let connection_slots = Arc::new(Semaphore::new(self.transport_config.max_connections as usize));
+
+        loop {
+            tokio::select! {
+                _ = shutdown.recv() => break,
+                accepted = listener.accept() => {
+                    let (stream, peer) = match accepted {
+                        Ok(accepted) => accepted,
+                        Err(e) => {
+                            tracing::warn!("Failed to accept connection: {}", e);
+                            tokio::select! {
+                                _ = shutdown.recv() => break,
+                                _ = tokio::time::sleep(ACCEPT_ERROR_BACKOFF) => continue,
+                            }
+                        }
+                    };
+
+                    let permit = match Arc::clone(&connection_slots).try_acquire_owned() {
+                        Ok(permit) => permit,
+                        Err(_) => {
+                            tracing::warn!("Rejecting connection from {}: connection limit reached", peer);
+                            continue;
+                        }
+                    };
+
+                    let server = self.clone();
+                    let shutdown = shutdown.resubscribe();
+                    tokio::spawn(async move {
+                        let _permit = permit;
+                        if let Err(e) = server.handle_connection(stream, peer, shutdown).await {
+                            tracing::debug!("Connection from {} closed with error: {}", peer, e);
+                        }
+                    });
+                }
+            }
+        }
+    }
+
+    /// Serve length-prefixed JSON-RPC messages on a single connection
+    async fn handle_connection(
+        &self,
+        stream: TcpStream,
+        peer: SocketAddr,
+        mut shutdown: broadcast::Receiver<()>,
+    ) -> ServerResult<()> {
+        stream.set_nodelay(true)?;
+
+        let read_timeout = Duration::from_millis(self.transport_config.read_timeout_ms);
+        let write_timeout = Duration::from_millis(self.transport_config.write_timeout_ms);
+        let codec = LengthDelimitedCodec::builder()
+            .max_frame_length(self.transport_config.max_message_size)
+            .new_codec();
+        let mut framed = Framed::new(stream, codec);
+
+        loop {
+            let frame = tokio::select! {
+                _ = shutdown.recv() => return Ok(()),
+                frame = tokio::time::timeout(read_timeout, framed.next()) => frame,
+            };
+
+            let bytes = match frame {
+                // Idle past the read timeout
+                Err(_) => return Ok(()),
+                Ok(None) => return Ok(()),
+                Ok(Some(frame)) => frame?,
+            };
+
+            if let Some(response) = self.handle_frame(&bytes, Some(peer)).await {
+                let payload = serde_json::to_vec(&response)?;
+                tokio::time::timeout(write_timeout, framed.send(payload.into())).await
+                    .map_err(|_| "Write timed out")??;
+            }
+        }
+    }
+
+    /// Handle a raw JSON-RPC message, returning the response to send (if any)
+    pub async fn handle_message(&self, message: &[u8]) -> Option<JsonRpcResponse> {
+        self.handle_frame(message, None).await
+    }
+
+    /// Handle a raw JSON-RPC message received from `peer`
+    async fn handle_frame(&self, message: &[u8], peer: Option<SocketAddr>) -> Option<JsonRpcResponse> {
+        match serde_json::from_slice::<JsonRpcRequest>(message) {
+            Ok(request) => {
+                let context = request_context(&request, peer);
+                self.handle_request_with_context(request, &context).await
+            }
+            Err(e) => Some(JsonRpcResponse::error(
+                Value::Null,
+                JsonRpcError::parse_error(format!("Invalid JSON-RPC request: {}", e)),
+            )),
+        }
+    }
+
+    /// Dispatch a JSON-RPC request to its handler
+    ///
+    /// Notifications are executed but produce no response.
+    pub async fn handle_request(&self, request: JsonRpcRequest) -> Option<JsonRpcResponse> {
+        let context = request_context(&request, None);
+        self.handle_request_with_context(request, &context).await
+    }
+
+    /// Dispatch a JSON-RPC request with the context of the call
+    ///
+    /// Emitted events are stamped with ingest metadata taken from `context`
+    /// (request ID, authenticated user, client address and TRN).
+    pub async fn handle_request_with_context(
+        &self,
+        request: JsonRpcRequest,
+        context: &ServiceContext,
+    ) -> Option<JsonRpcResponse> {
+        let JsonRpcRequest { jsonrpc, method, params, id, .. } = request;
+
+        let result = if jsonrpc != jsonrpc_rust::JSONRPC_VERSION {
+            Err(JsonRpcError::invalid_request(format!("Unsupported JSON-RPC version: {}", jsonrpc)))
+        } else {
+            self.dispatch(&method, params, context).await
+        };
+
+        let id = id?;
+        Some(match result {
+            Ok(result) => JsonRpcResponse::success(id, result),
+            Err(error) => JsonRpcResponse::error(id, error),
+        })
+    }
+
+    /// Route a method call to the matching handler
+    async fn dispatch(
+        &self,
+        method: &str,
+        params: Option<Value>,
+        context: &ServiceContext,
+    ) -> std::result::Result<Value, JsonRpcError> {
+        self.check_admin(method, params.as_ref(), context)?;
+        self.check_mode(method)?;
+        
+        match method {
+            method_names::EMIT => {
+                let mut params: EmitParams = parse_params(params)?;
+                params.event = params.event.with_ingest(&ingest_metadata(context));
+                to_result(self.handle_emit(params).await?)
+            }
+            method_names::EMIT_BATCH => {
+                let mut params: EmitBatchParams = parse_params(params)?;
+                let ingest = ingest_metadata(context);
+                params.events = params.events.into_iter().map(|event| event.with_ingest(&ingest)).collect();
+                to_result(self.handle_emit_batch(params).await?)
+            }
+            method_names::POLL => to_result(self.handle_poll(parse_params(params)?).await?),
+            method_names::POLL_WAIT => to_result(self.handle_poll_wait(parse_params(params)?).await?),
+            method_names::SUBSCRIBE => to_result(self.handle_subscribe(parse_params(params)?).await?),
+            method_names::UNSUBSCRIBE => to_result(self.handle_unsubscribe(parse_params(params)?).await?),
+            method_names::LIST_TOPICS => to_result(self.handle_list_topics().await?),
+            method_names::GET_STATS => to_result(self.handle_get_stats().await?),
+            method_names::GET_SUBSCRIPTION_EVENTS => {
+                to_result(self.handle_get_subscription_events(parse_params(params)?).await?)
+            }
+            method_names::REGISTER_RULE => to_result(self.handle_register_rule(parse_params(params)?).await?),
+            method_names::CREATE_TOPIC => to_result(self.handle_create_topic(parse_params(params)?).await?),
+            method_names::DELETE_TOPIC => to_result(self.handle_delete_topic(parse_params(params)?).await?),
+            method_names::DESCRIBE_TOPIC => to_result(self.handle_describe_topic(parse_params(params)?).await?),
+            method_names::CREATE_QUEUE => to_result(self.handle_create_queue(parse_params(params)?).await?),
+            method_names::DELETE_QUEUE => to_result(self.handle_delete_queue(parse_params(params)?).await?),
+            method_names::QUEUE_FETCH => to_result(self.handle_queue_fetch(parse_params(params)?).await?),
+            method_names::QUEUE_ACK => to_result(self.handle_queue_ack(parse_params(params)?).await?),
+            method_names::QUEUE_NACK => to_result(self.handle_queue_nack(parse_params(params)?).await?),
+            method_names::QUEUE_STATS => to_result(self.handle_queue_stats(parse_params(params)?).await?),
+            method_names::SET_MODE => to_result(self.handle_set_mode(parse_params(params)?).await?),
+            method_names::GET_MODE => to_result(self.handle_get_mode().await?),
+            method_names::HEALTH => to_result(self.handle_health().await?),
+            method_names::REPLICATION_FETCH => to_result(self.handle_replication_fetch(parse_params(params)?).await?),
+            method_names::REPLICATION_STATUS => to_result(self.handle_replication_status().await?),
+            method_names::PROMOTE => to_result(self.handle_promote().await?),
+            method_names::VERIFY_TOPIC_INTEGRITY => {
+                to_result(self.handle_verify_topic_integrity(parse_params(params)?).await?)
+            }
+            _ => Err(JsonRpcError::method_not_found(method)),
+        }
+    }
+
+    /// Reject admin methods unless the caller passes the admin token or has the `admin` role
+    fn check_admin(
+        &self,
+        method: &str,
+        params: Option<&Value>,
+        context: &ServiceContext,
+    ) -> std::result::Result<(), JsonRpcError> {
+        if !method_names::ADMIN.contains(&method) {
+            return Ok(());
+        }
+        let is_admin = context.auth_context.as_ref().is_some_and(|auth| auth.roles.iter().any(|role| role == "admin"));
+        let token = params.and_then(|params| params.get("admin_token")).and_then(Value::as_str);
+        let has_token = match (&self.transport_config.admin_token, token) {
+            (Some(expected), Some(token)) => expected == token,
+            _ => false,
+        };
+        if is_admin || has_token {
+            return Ok(());
+        }
+        Err(JsonRpcError::new(
+            JsonRpcErrorCode::ServerError(error_codes::UNAUTHORIZED),
+            format!("{} requires the admin token or the admin role", method),
+        ))
+    }
+
+    /// Reject methods the current bus mode does not accept
+    /// 
+    /// Methods not listed in `method_names` are checked as reads.
+    fn check_mode(&self, method: &str) -> std::result::Result<(), JsonRpcError> {
+        let result = if method_names::ALWAYS_AVAILABLE.contains(&method) {
+            Ok(())
+        } else if method_names::WRITES.contains(&method) {
+            self.bus_service.check_writable(method)
+        } else {
+            self.bus_service.check_readable(method)
+        };
+        result.map_err(|e| JsonRpcError::new(
+            JsonRpcErrorCode::ServerError(error_codes::SERVICE_UNAVAILABLE),
+            e.to_string(),
+        ))
+    }
+
     /// Handle emit method
     pub async fn handle_emit(&self, params: EmitParams) -> std::result::Result<EmitResponse, JsonRpcError> {
-        match self.bus_service.emit(params.event).await {
-            Ok(_) => Ok(EmitResponse { success: true }),
-            Err(e) => Err(JsonRpcError::new(
-                JsonRpcErrorCode::ServerError(error_codes::STORAGE_ERROR),
-                format!("Failed to emit event: {}", e),
-            )),
+        if let Some(timeout_ms) = params.queue_timeout_ms {
+            self.bus_service
+                .wait_for_capacity(Duration::from_millis(timeout_ms))
+                .await
+                .map_err(|e| self.emit_error(&e, "Failed to emit event"))?;
+        }
+        
+        match self.bus_service.handle_emit_event(params.event, params.idempotency_key.as_deref()).await {
+            Ok(result) => Ok(EmitResponse {
+                success: true,
+                event_id: result.get("event_id").and_then(|id| id.as_str()).map(str::to_string),
+                rate_limit: self.bus_service.rate_limit_status(),
+            }),
+            Err(e) => Err(self.emit_error(&e, "Failed to emit event")),
         }
     }
 
@@ -74,12 +417,25 @@ impl EventBusRpcServer {
         match self.bus_service.emit_batch(params.events).await {
             Ok(_) => Ok(EmitBatchResponse { 
                 success: true, 
-                processed_count: count 
+                processed_count: count,
+                rate_limit: self.bus_service.rate_limit_status(),
             }),
-            Err(e) => Err(JsonRpcError::new(
-                JsonRpcErrorCode::ServerError(error_codes::STORAGE_ERROR),
-                format!("Failed to emit batch: {}", e),
-            )),
+            Err(e) => Err(self.emit_error(&e, "Failed to emit batch")),
+        }
+    }
+    
+    /// Map an emit failure to a JSON-RPC error, attaching the rate limit state when limited
+    fn emit_error(&self, error: &EventBusError, context: &str) -> JsonRpcError {
+        let rpc_error = JsonRpcError::new(
+            JsonRpcErrorCode::ServerError(error_code(error, error_codes::STORAGE_ERROR)),
+            format!("{}: {}", context, error),
+        );
+        
+        match (error, self.bus_service.rate_limit_status()) {
+            (EventBusError::RateLimited { .. }, Some(status)) => {
+                rpc_error.with_data(serde_json::json!({ "rate_limit": status }))
+            }
+            _ => rpc_error,
         }
     }
 
@@ -91,7 +447,22 @@ impl EventBusRpcServer {
                 Ok(PollResponse { events, total_count })
             },
             Err(e) => Err(JsonRpcError::new(
-                JsonRpcErrorCode::ServerError(error_codes::STORAGE_ERROR),
+                JsonRpcErrorCode::ServerError(error_code(&e, error_codes::STORAGE_ERROR)),
+                format!("Failed to poll events: {}", e),
+            )),
+        }
+    }
+
+    /// Handle poll_wait method
+    pub async fn handle_poll_wait(&self, params: PollWaitParams) -> std::result::Result<PollResponse, JsonRpcError> {
+        let timeout = Duration::from_millis(params.timeout_ms).min(MAX_POLL_WAIT);
+        match self.bus_service.poll_wait(params.query, timeout).await {
+            Ok(events) => {
+                let total_count = events.len();
+                Ok(PollResponse { events, total_count })
+            },
+            Err(e) => Err(JsonRpcError::new(
+                JsonRpcErrorCode::ServerError(error_code(&e, error_codes::STORAGE_ERROR)),
                 format!("Failed to poll events: {}", e),
             )),
         }
@@ -99,6 +470,11 @@ impl EventBusRpcServer {
 
     /// Handle subscribe method
     pub async fn handle_subscribe(&self, params: SubscribeParams) -> std::result::Result<SubscribeResponse, JsonRpcError> {
+        // Reject a bad filter before registering anything
+        let filter = params.filter.as_deref()
+            .map(EventFilter::parse)
+            .transpose()
+            .map_err(|e| JsonRpcError::invalid_params(format!("Invalid filter: {}", e)))?;
         let subscription_id = Uuid::new_v4().to_string();
         let (sender, _receiver) = broadcast::channel(1000);
 
@@ -122,7 +498,11 @@ impl EventBusRpcServer {
         let subscriptions = Arc::clone(&self.subscriptions);
         
         tokio::spawn(async move {
-            match bus_service.subscribe(&topic).await {
+            let stream = match filter {
+                Some(filter) => bus_service.subscribe_filtered(&topic, filter).await,
+                None => bus_service.subscribe(&topic).await,
+            };
+            match stream {
                 Ok(mut stream) => {
                     use futures::StreamExt;
                     while let Some(event) = stream.next().await {
@@ -157,6 +537,170 @@ impl EventBusRpcServer {
         Ok(UnsubscribeResponse { success })
     }
 
+    /// Handle register_rule method
+    pub async fn handle_register_rule(&self, params: RegisterRuleParams) -> std::result::Result<RegisterRuleResponse, JsonRpcError> {
+        match self.bus_service.handle_register_rule(params.rule).await {
+            Ok(_) => Ok(RegisterRuleResponse { success: true }),
+            Err(e) => Err(JsonRpcError::new(
+                JsonRpcErrorCode::ServerError(error_codes::SERVICE_UNAVAILABLE),
+                format!("Failed to register rule: {}", e),
+            )),
+        }
+    }
+
+    /// Handle create_topic method
+    pub async fn handle_create_topic(&self, params: CreateTopicParams) -> std::result::Result<TopicResponse, JsonRpcError> {
+        match self.bus_service.create_topic(&params.topic, params.settings) {
+            Ok(topic) => Ok(TopicResponse { topic }),
+            Err(e) => Err(JsonRpcError::new(
+                JsonRpcErrorCode::ServerError(error_code(&e, error_codes::INVALID_PARAMS)),
+                format!("Failed to create topic: {}", e),
+            )),
+        }
+    }
+
+    /// Handle delete_topic method
+    pub async fn handle_delete_topic(&self, params: DeleteTopicParams) -> std::result::Result<TopicResponse, JsonRpcError> {
+        match self.bus_service.delete_topic(&params.topic) {
+            Ok(topic) => Ok(TopicResponse { topic }),
+            Err(e) => Err(JsonRpcError::new(
+                JsonRpcErrorCode::ServerError(error_codes::TOPIC_NOT_FOUND),
+                format!("Failed to delete topic: {}", e),
+            )),
+        }
+    }
+
+    /// Handle set_mode method
+    pub async fn handle_set_mode(&self, params: SetModeParams) -> std::result::Result<ModeResponse, JsonRpcError> {
+        Ok(ModeResponse { status: self.bus_service.set_mode(params.mode, params.reason) })
+    }
+
+    /// Handle get_mode method
+    pub async fn handle_get_mode(&self) -> std::result::Result<ModeResponse, JsonRpcError> {
+        Ok(ModeResponse { status: self.bus_service.mode() })
+    }
+
+    /// Handle health method
+    pub async fn handle_health(&self) -> std::result::Result<Value, JsonRpcError> {
+        Ok(self.bus_service.health().await)
+    }
+
+    /// Handle replication_fetch method
+    pub async fn handle_replication_fetch(
+        &self,
+        params: ReplicationFetchParams,
+    ) -> std::result::Result<ReplicationBatch, JsonRpcError> {
+        self.bus_service.replication_fetch(params.after, params.limit).map_err(|e| JsonRpcError::new(
+            JsonRpcErrorCode::ServerError(error_code(&e, error_codes::SERVICE_UNAVAILABLE)),
+            format!("Failed to fetch replication log: {}", e),
+        ))
+    }
+
+    /// Handle replication_status method
+    pub async fn handle_replication_status(&self) -> std::result::Result<ReplicationStatusResponse, JsonRpcError> {
+        Ok(ReplicationStatusResponse { replication: self.bus_service.replication_status() })
+    }
+
+    /// Handle promote method
+    pub async fn handle_promote(&self) -> std::result::Result<ReplicationStatusResponse, JsonRpcError> {
+        match self.bus_service.promote().await {
+            Ok(status) => Ok(ReplicationStatusResponse { replication: Some(status) }),
+            Err(e) => Err(JsonRpcError::new(
+                JsonRpcErrorCode::ServerError(error_code(&e, error_codes::INVALID_PARAMS)),
+                format!("Failed to promote: {}", e),
+            )),
+        }
+    }
+
+    /// Handle verify_topic_integrity method
+    pub async fn handle_verify_topic_integrity(
+        &self,
+        params: VerifyTopicIntegrityParams,
+    ) -> std::result::Result<IntegrityReport, JsonRpcError> {
+        use std::ops::Bound;
+
+        let range = (
+            params.since.map_or(Bound::Unbounded, Bound::Included),
+            params.until.map_or(Bound::Unbounded, Bound::Excluded),
+        );
+        self.bus_service.verify_topic_integrity(&params.topic, range).await.map_err(|e| JsonRpcError::new(
+            JsonRpcErrorCode::ServerError(error_code(&e, error_codes::STORAGE_ERROR)),
+            format!("Failed to verify topic integrity: {}", e),
+        ))
+    }
+
+    /// Handle describe_topic method
+    pub async fn handle_describe_topic(&self, params: DescribeTopicParams) -> std::result::Result<serde_json::Value, JsonRpcError> {
+        Ok(self.bus_service.describe_topic(&params.topic))
+    }
+
+    /// Handle create_queue method
+    pub async fn handle_create_queue(&self, params: CreateQueueParams) -> std::result::Result<QueueResponse, JsonRpcError> {
+        match self.bus_service.work_queues().create(params.config) {
+            Ok(queue) => Ok(QueueResponse { queue }),
+            Err(e) => Err(JsonRpcError::new(
+                JsonRpcErrorCode::ServerError(error_code(&e, error_codes::INVALID_PARAMS)),
+                format!("Failed to create queue: {}", e),
+            )),
+        }
+    }
+
+    /// Handle delete_queue method
+    pub async fn handle_delete_queue(&self, params: QueueParams) -> std::result::Result<QueueResponse, JsonRpcError> {
+        match self.bus_service.work_queues().delete(&params.group) {
+            Ok(queue) => Ok(QueueResponse { queue }),
+            Err(e) => Err(JsonRpcError::new(
+                JsonRpcErrorCode::ServerError(error_codes::QUEUE_NOT_FOUND),
+                format!("Failed to delete queue: {}", e),
+            )),
+        }
+    }
+
+    /// Handle queue_fetch method
+    pub async fn handle_queue_fetch(&self, params: QueueFetchParams) -> std::result::Result<QueueFetchResponse, JsonRpcError> {
+        let timeout = Duration::from_millis(params.timeout_ms).min(MAX_POLL_WAIT);
+        match self.bus_service.fetch_work(&params.group, &params.consumer_id, params.max_events, timeout).await {
+            Ok(deliveries) => Ok(QueueFetchResponse { deliveries }),
+            Err(e) => Err(JsonRpcError::new(
+                JsonRpcErrorCode::ServerError(error_codes::QUEUE_NOT_FOUND),
+                format!("Failed to fetch from queue: {}", e),
+            )),
+        }
+    }
+
+    /// Handle queue_ack method
+    pub async fn handle_queue_ack(&self, params: QueueAckParams) -> std::result::Result<QueueAckResponse, JsonRpcError> {
+        match self.bus_service.work_queues().ack(&params.group, &params.delivery_id) {
+            Ok(()) => Ok(QueueAckResponse { success: true }),
+            Err(e) => Err(JsonRpcError::new(
+                JsonRpcErrorCode::ServerError(error_codes::DELIVERY_NOT_FOUND),
+                format!("Failed to acknowledge delivery: {}", e),
+            )),
+        }
+    }
+
+    /// Handle queue_nack method
+    pub async fn handle_queue_nack(&self, params: QueueNackParams) -> std::result::Result<QueueAckResponse, JsonRpcError> {
+        match self.bus_service.work_queues().nack(&params.group, &params.delivery_id, params.requeue) {
+            Ok(()) => Ok(QueueAckResponse { success: true }),
+            Err(e) => Err(JsonRpcError::new(
+                JsonRpcErrorCode::ServerError(error_codes::DELIVERY_NOT_FOUND),
+                format!("Failed to reject delivery: {}", e),
+            )),
+        }
+    }
+
+    /// Handle queue_stats method
+    pub async fn handle_queue_stats(&self, params: QueueParams) -> std::result::Result<QueueResponse, JsonRpcError> {
+        match self.bus_service.work_queues().stats(&params.group) {
+            Ok(queue) => Ok(QueueResponse { queue }),
+            Err(e) => Err(JsonRpcError::new(
+                JsonRpcErrorCode::ServerError(error_codes::QUEUE_NOT_FOUND),
+                format!("Failed to get queue stats: {}", e),
+            )),
+        }
+    }
+
     /// Handle list_topics method
     pub async fn handle_list_topics(&self) -> std::result::Result<ListTopicsResponse, JsonRpcError> {
         match self.bus_service.list_topics().await {
@@ -226,4 +770,92 @@ impl EventBusRpcServer {
             )),
         }
     }
-} 
\ No newline at end of file
+}
+
+/// Serves the bus methods behind any jsonrpc-rust server or layer stack
+///
+/// The caller's `ServiceContext` (for example one authenticated by
+/// `AuthLayer`) is used for the ingest metadata of emitted events.
+#[async_trait]
+impl MethodHandler for EventBusRpcServer {
+    async fn handle_method(&self, request: &JsonRpcRequest, context: &ServiceContext) -> jsonrpc_rust::Result<JsonRpcResponse> {
+        let response = self.handle_request_with_context(request.clone(), context).await;
+        // Notifications have no response to return
+        Ok(response.unwrap_or_else(|| JsonRpcResponse::success(Value::Null, Value::Null)))
+    }
+
+    fn supported_methods(&self) -> Vec<String> {
+        method_names::ALL.iter().map(|method| method.to_string()).collect()
+    }
+}
+
+/// Context for a request read off a connection
+///
+/// The JSON-RPC ID doubles as the request ID; notifications get a fresh one.
+fn request_context(request: &JsonRpcRequest, peer: Option<SocketAddr>) -> ServiceContext {
+    let request_id = match request.id() {
+        Some(Value::String(id)) => id.clone(),
+        Some(id) if !id.is_null() => id.to_string(),
+        _ => Uuid::new_v4().to_string(),
+    };
+    let context = ServiceContext::new(request_id);
+
+    match peer {
+        Some(peer) => context.with_client_info(ClientInfo {
+            client_id: None,
+            remote_addr: Some(peer.to_string()),
+            user_agent: None,
+            version: None,
+            metadata: HashMap::new(),
+        }),
+        None => context,
+    }
+}
+
+/// Ingest metadata describing the sender of a call
+fn ingest_metadata(context: &ServiceContext) -> IngestMetadata {
+    let received_at = context.received_at
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs() as i64)
+        .unwrap_or_default();
+    let client = context.client_info.as_ref();
+
+    #[cfg(feature = "trn-integration")]
+    let trn = context.trn_context.as_ref().map(|trn| trn.to_trn_string());
+    #[cfg(not(feature = "trn-integration"))]
+    let trn = None;
+
+    IngestMetadata {
+        request_id: context.request_id.clone(),
+        received_at,
+        auth_user: context.auth_context.as_ref().map(|auth| auth.user_id.clone()),
+        auth_method: context.auth_context.as_ref().map(|auth| auth.auth_method.clone()),
+        client_addr: client.and_then(|client| client.remote_addr.clone()),
+        client_id: client.and_then(|client| client.client_id.clone()),
+        trn,
+    }
+}
+
+/// Pick the error code for a bus error, falling back to `default`
+fn error_code(error: &EventBusError, default: i32) -> i32 {
+    match error {
+        EventBusError::RateLimited { .. } => error_codes::RATE_LIMIT_EXCEEDED,
+        EventBusError::TopicNotAllowed { .. } => error_codes::TOPIC_NOT_ALLOWED,
+        EventBusError::AlreadyExists { .. } => error_codes::ALREADY_EXISTS,
+        EventBusError::Unavailable { .. } => error_codes::SERVICE_UNAVAILABLE,
+        EventBusError::IntegrityViolation { .. } => error_codes::INTEGRITY_VIOLATION,
+        _ => default,
+    }
+}
+
+/// Deserialize method parameters, mapping failures to an invalid params error
+fn parse_params<T: DeserializeOwned>(params: Option<Value>) -> std::result::Result<T, JsonRpcError> {
+    serde_json::from_value(params.unwrap_or(Value::Null))
+        .map_err(|e| JsonRpcError::invalid_params(format!("Invalid parameters: {}", e)))
+}
+
+/// Serialize a handler response into a JSON-RPC result
+fn to_result<T: Serialize>(response: T) -> std::result::Result<Value, JsonRpcError> {
+    serde_json::to_value(response)
+        .map_err(|e| JsonRpcError::internal_error(format!("Failed to serialize response: {}", e)))
+}
@@ -35,6 +35,31 @@ pub mod utils;
 /// JSON-RPC server and client implementations
 pub mod jsonrpc;
 
+/// Competing-consumer work queues
+pub mod queue;
+
+/// In-process event bus with no configuration
+pub mod embedded;
+
+/// Asynchronous leader → follower replication of selected topics
+pub mod replication;
+
+/// In-process harness and helpers for testing event-driven code
+#[cfg(feature = "test-utils")]
+pub mod test_support;
+
+/// Failure injection for storage and broadcast
+#[cfg(feature = "chaos")]
+pub mod chaos;
+
+/// Signed and encrypted forwarding between federated buses
+#[cfg(feature = "federation")]
+pub mod federation;
+
+// Lets code generated by `#[derive(Event)]` name this crate from inside it
+#[cfg(feature = "macros")]
+extern crate self as eventbus_rust;
+
 /// Prelude module for convenient imports
 pub mod prelude {
     // Core types
@@ -42,6 +67,7 @@ pub mod prelude {
     
     // Service types
     pub use crate::service::EventBusService;
+    pub use crate::embedded::EmbeddedBus;
     
     // Storage types
     // EventStorage is re-exported from core
@@ -70,8 +96,14 @@ pub use core::{
     types::*,
     traits::*,
     error::*,
+    plugin::*,
+    typed::*,
 };
 
+// Trait and derive macro under one name, like serde's
+#[cfg(feature = "macros")]
+pub use core::Event;
+
 // Storage implementations
 pub use storage::{
     create_storage,
@@ -81,11 +113,19 @@ pub use storage::{
 // Configuration
 pub use config::{
     StorageConfig,
+    TopicPolicy,
+    RetentionConfig,
+    TopicRetention,
+    IntegrityConfig,
 };
 
 // Service types
 pub use service::{
     EventBusService,
+    TopicSnapshot,
+    BusMode,
+    ModeStatus,
+    RecoveryReport,
     ServiceConfig,
     ServiceMetrics,
     MultiBusConfig,
@@ -95,6 +135,20 @@ pub use service::{
     MetricsConfig,
     LoggingConfig,
     CombinedMetrics,
+    RateLimitMode,
+    RateLimitStatus,
+};
+
+// Embedded mode
+pub use embedded::EmbeddedBus;
+
+// Work queues
+pub use queue::{
+    Delivery,
+    DispatchStrategy,
+    WorkQueueConfig,
+    WorkQueueStats,
+    WorkQueues,
 };
 
 // Utility functions
@@ -244,7 +298,7 @@ pub async fn create_single_event_bus() -> Result<service::EventBusService, Box<d
 /// #[tokio::main]
 /// async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
 ///     let config = ServiceConfig {
-///         storage: StorageConfig::Memory,
+///         storage: StorageConfig::memory(),
 ///         max_concurrent_emits: 50,
 ///         max_events_per_second: Some(500),
 ///         ..ServiceConfig::default()
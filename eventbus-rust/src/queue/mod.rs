@@ -0,0 +1,713 @@
+//! Competing-consumer work queues
+//!
+//! Subscriptions broadcast every event to every subscriber. A work queue
+//! instead hands each event on its topic pattern to exactly one consumer of
+//! its group, so a pool of workers can share the load.
+//!
+//! Consumers pull with [`WorkQueues::fetch`]. An event fetched by a consumer
+//! is leased to it until it is acknowledged; a lease that is not acknowledged
+//! within `ack_timeout_ms` (or is negatively acknowledged) puts the event
+//! back at the front of the queue for redelivery. After `max_deliveries`
+//! failed deliveries the event is moved to the group's dead letters.
+//!
+//! When several consumers are waiting for work, new events are spread across
+//! them according to the group's [`DispatchStrategy`]. Each consumer holds at
+//! most `max_in_flight` unacknowledged events.
+
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use tokio::sync::oneshot;
+use tokio::time::{Duration, Instant};
+use uuid::Uuid;
+
+use crate::core::{EventBusError, EventBusResult, EventEnvelope};
+use crate::utils::topic_matches_pattern;
+
+/// Number of dead letters kept per group
+const MAX_DEAD_LETTERS: usize = 1000;
+
+/// How events are spread across waiting consumers
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DispatchStrategy {
+    /// Take turns in consumer ID order
+    #[default]
+    RoundRobin,
+    /// Prefer the consumer with the fewest unacknowledged events
+    LeastLoaded,
+}
+
+/// Configuration of a consumer group
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WorkQueueConfig {
+    /// Group name
+    pub group: String,
+
+    /// Topic pattern feeding the queue (`*` and `**` wildcards)
+    pub topic: String,
+
+    /// How events are spread across waiting consumers
+    #[serde(default)]
+    pub strategy: DispatchStrategy,
+
+    /// How long a consumer may hold an event before it is redelivered
+    #[serde(default = "default_ack_timeout_ms")]
+    pub ack_timeout_ms: u64,
+
+    /// Deliveries before an event is dead-lettered (0 = no limit)
+    #[serde(default = "default_max_deliveries")]
+    pub max_deliveries: u32,
+
+    /// Unacknowledged events a single consumer may hold
+    #[serde(default = "default_max_in_flight")]
+    pub max_in_flight: usize,
+}
+
+fn default_ack_timeout_ms() -> u64 {
+    30_000
+}
+
+fn default_max_deliveries() -> u32 {
+    5
+}
+
+fn default_max_in_flight() -> usize {
+    10
+}
+
+impl WorkQueueConfig {
+    /// Create a group fed by a topic pattern, with default limits
+    pub fn new(group: impl Into<String>, topic: impl Into<String>) -> Self {
+        Self {
+            group: group.into(),
+            topic: topic.into(),
+            strategy: DispatchStrategy::default(),
+            ack_timeout_ms: default_ack_timeout_ms(),
+            max_deliveries: default_max_deliveries(),
+            max_in_flight: default_max_in_flight(),
+        }
+    }
+
+    /// Set the dispatch strategy
+    pub fn with_strategy(mut self, strategy: DispatchStrategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    /// Set the acknowledgement timeout
+    pub fn with_ack_timeout(mut self, ack_timeout: Duration) -> Self {
+        self.ack_timeout_ms = ack_timeout.as_millis() as u64;
+        self
+    }
+
+    /// Set the number of deliveries before an event is dead-lettered
+    pub fn with_max_deliveries(mut self, max_deliveries: u32) -> Self {
+        self.max_deliveries = max_deliveries;
+        self
+    }
+
+    /// Set the unacknowledged events a single consumer may hold
+    pub fn with_max_in_flight(mut self, max_in_flight: usize) -> Self {
+        self.max_in_flight = max_in_flight;
+        self
+    }
+}
+
+/// Event leased to a consumer
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Delivery {
+    /// ID to acknowledge the delivery with
+    pub delivery_id: String,
+    /// The event
+    pub event: EventEnvelope,
+    /// Delivery attempt, starting at 1
+    pub attempt: u32,
+}
+
+/// State of one consumer of a group
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConsumerStats {
+    /// Consumer ID
+    pub consumer_id: String,
+    /// Unacknowledged events held
+    pub in_flight: usize,
+    /// Events delivered
+    pub delivered: u64,
+    /// Events acknowledged
+    pub acked: u64,
+}
+
+/// State of a consumer group
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WorkQueueStats {
+    /// Group configuration
+    pub config: WorkQueueConfig,
+    /// Events waiting for a consumer
+    pub pending: usize,
+    /// Events leased to consumers
+    pub in_flight: usize,
+    /// Events acknowledged
+    pub acked: u64,
+    /// Deliveries that were retries
+    pub redelivered: u64,
+    /// Events that ran out of deliveries
+    pub dead_lettered: u64,
+    /// Consumers that have fetched from the group
+    pub consumers: Vec<ConsumerStats>,
+}
+
+#[derive(Debug)]
+struct Pending {
+    event: EventEnvelope,
+    /// Deliveries so far
+    attempts: u32,
+}
+
+#[derive(Debug)]
+struct Lease {
+    event: EventEnvelope,
+    attempts: u32,
+    consumer: String,
+    deadline: Instant,
+}
+
+#[derive(Debug, Default)]
+struct Consumer {
+    in_flight: usize,
+    delivered: u64,
+    acked: u64,
+}
+
+#[derive(Debug)]
+struct Waiter {
+    id: u64,
+    consumer: String,
+    max: usize,
+    sender: oneshot::Sender<Vec<Delivery>>,
+}
+
+#[derive(Debug)]
+struct GroupState {
+    config: WorkQueueConfig,
+    ready: VecDeque<Pending>,
+    leases: HashMap<String, Lease>,
+    /// Ordered by ID for round-robin dispatch
+    consumers: BTreeMap<String, Consumer>,
+    waiters: Vec<Waiter>,
+    next_waiter_id: u64,
+    /// Consumer that received the last round-robin event
+    last_consumer: Option<String>,
+    acked: u64,
+    redelivered: u64,
+    dead_lettered: u64,
+    dead_letters: VecDeque<EventEnvelope>,
+}
+
+impl GroupState {
+    fn new(config: WorkQueueConfig) -> Self {
+        Self {
+            config,
+            ready: VecDeque::new(),
+            leases: HashMap::new(),
+            consumers: BTreeMap::new(),
+            waiters: Vec::new(),
+            next_waiter_id: 0,
+            last_consumer: None,
+            acked: 0,
+            redelivered: 0,
+            dead_lettered: 0,
+            dead_letters: VecDeque::new(),
+        }
+    }
+
+    /// Free lease slots of a consumer
+    fn capacity(&self, consumer: &str) -> usize {
+        let in_flight = self.consumers.get(consumer).map_or(0, |consumer| consumer.in_flight);
+        self.config.max_in_flight.saturating_sub(in_flight)
+    }
+
+    /// Lease an event to a consumer
+    fn lease(&mut self, consumer: &str, pending: Pending, now: Instant) -> Delivery {
+        let delivery = Delivery {
+            delivery_id: Uuid::new_v4().to_string(),
+            event: pending.event.clone(),
+            attempt: pending.attempts + 1,
+        };
+        if pending.attempts > 0 {
+            self.redelivered += 1;
+        }
+
+        let state = self.consumers.entry(consumer.to_string()).or_default();
+        state.in_flight += 1;
+        state.delivered += 1;
+
+        self.leases.insert(delivery.delivery_id.clone(), Lease {
+            event: pending.event,
+            attempts: delivery.attempt,
+            consumer: consumer.to_string(),
+            deadline: now + Duration::from_millis(self.config.ack_timeout_ms),
+        });
+        delivery
+    }
+
+    /// Undo a lease that never reached its consumer
+    fn unlease(&mut self, delivery_id: &str) {
+        let Some(lease) = self.release(delivery_id) else {
+            return;
+        };
+        if let Some(consumer) = self.consumers.get_mut(&lease.consumer) {
+            consumer.delivered = consumer.delivered.saturating_sub(1);
+        }
+        if lease.attempts > 1 {
+            self.redelivered = self.redelivered.saturating_sub(1);
+        }
+        self.ready.push_front(Pending { event: lease.event, attempts: lease.attempts - 1 });
+    }
+
+    /// End a lease, freeing the consumer's slot
+    fn release(&mut self, delivery_id: &str) -> Option<Lease> {
+        let lease = self.leases.remove(delivery_id)?;
+        if let Some(consumer) = self.consumers.get_mut(&lease.consumer) {
+            consumer.in_flight = consumer.in_flight.saturating_sub(1);
+        }
+        Some(lease)
+    }
+
+    /// Queue a released event for redelivery, or dead-letter it
+    fn retry(&mut self, lease: Lease) {
+        let max_deliveries = self.config.max_deliveries;
+        if max_deliveries > 0 && lease.attempts >= max_deliveries {
+            tracing::warn!(
+                "Work queue {}: event {} dead-lettered after {} deliveries",
+                self.config.group, lease.event.event_id, lease.attempts
+            );
+            self.dead_letter(lease.event);
+        } else {
+            self.ready.push_front(Pending { event: lease.event, attempts: lease.attempts });
+        }
+    }
+
+    fn dead_letter(&mut self, event: EventEnvelope) {
+        self.dead_lettered += 1;
+        if self.dead_letters.len() == MAX_DEAD_LETTERS {
+            self.dead_letters.pop_front();
+        }
+        self.dead_letters.push_back(event);
+    }
+
+    /// Redeliver events whose lease has run out
+    fn expire(&mut self, now: Instant) {
+        let expired: Vec<String> = self.leases
+            .iter()
+            .filter(|(_, lease)| lease.deadline <= now)
+            .map(|(id, _)| id.clone())
+            .collect();
+
+        for delivery_id in expired {
+            if let Some(lease) = self.release(&delivery_id) {
+                tracing::debug!("Work queue {}: lease {} expired", self.config.group, delivery_id);
+                self.retry(lease);
+            }
+        }
+    }
+
+    fn next_expiry(&self) -> Option<Instant> {
+        self.leases.values().map(|lease| lease.deadline).min()
+    }
+
+    /// Lease up to `max` ready events to a consumer
+    fn take(&mut self, consumer: &str, max: usize, now: Instant) -> Vec<Delivery> {
+        let count = max.min(self.capacity(consumer)).min(self.ready.len());
+        let pending: Vec<Pending> = self.ready.drain(..count).collect();
+        pending.into_iter().map(|pending| self.lease(consumer, pending, now)).collect()
+    }
+
+    /// Waiter to receive the next event, if any can take one
+    fn pick_waiter(&mut self, batches: &[Vec<Delivery>]) -> Option<usize> {
+        let candidates = self.waiters
+            .iter()
+            .enumerate()
+            .filter(|(index, waiter)| batches[*index].len() < waiter.max && self.capacity(&waiter.consumer) > 0);
+
+        let index = match self.config.strategy {
+            DispatchStrategy::LeastLoaded => candidates
+                .min_by_key(|(_, waiter)| self.consumers.get(&waiter.consumer).map_or(0, |consumer| consumer.in_flight))
+                .map(|(index, _)| index),
+            DispatchStrategy::RoundRobin => {
+                // The first consumer after the last one served, wrapping around
+                let last = self.last_consumer.as_deref();
+                let candidates: Vec<(usize, &Waiter)> = candidates.collect();
+                candidates.iter()
+                    .filter(|(_, waiter)| last.is_none_or(|last| waiter.consumer.as_str() > last))
+                    .min_by(|a, b| a.1.consumer.cmp(&b.1.consumer))
+                    .or_else(|| candidates.iter().min_by(|a, b| a.1.consumer.cmp(&b.1.consumer)))
+                    .map(|(index, _)| *index)
+            }
+        }?;
+
+        self.last_consumer = Some(self.waiters[index].consumer.clone());
+        Some(index)
+    }
+
+    /// Hand ready events to waiting consumers
+    fn dispatch(&mut self, now: Instant) {
+        while !self.ready.is_empty() && !self.waiters.is_empty() {
+            let mut batches: Vec<Vec<Delivery>> = self.waiters.iter().map(|_| Vec::new()).collect();
+            while !self.ready.is_empty() {
+                let Some(index) = self.pick_waiter(&batches) else {
+                    break;
+                };
+                let pending = self.ready.pop_front().expect("checked above");
+                let consumer = self.waiters[index].consumer.clone();
+                batches[index].push(self.lease(&consumer, pending, now));
+            }
+
+            // Complete the served waiters; leases of waiters that gave up go back
+            let mut abandoned = false;
+            for index in (0..batches.len()).rev() {
+                if batches[index].is_empty() {
+                    continue;
+                }
+                let waiter = self.waiters.remove(index);
+                let batch = std::mem::take(&mut batches[index]);
+                if let Err(batch) = waiter.sender.send(batch) {
+                    abandoned = true;
+                    for delivery in batch.into_iter().rev() {
+                        self.unlease(&delivery.delivery_id);
+                    }
+                }
+            }
+            if !abandoned {
+                break;
+            }
+        }
+    }
+
+    fn stats(&self) -> WorkQueueStats {
+        WorkQueueStats {
+            config: self.config.clone(),
+            pending: self.ready.len(),
+            in_flight: self.leases.len(),
+            acked: self.acked,
+            redelivered: self.redelivered,
+            dead_lettered: self.dead_lettered,
+            consumers: self.consumers
+                .iter()
+                .map(|(id, consumer)| ConsumerStats {
+                    consumer_id: id.clone(),
+                    in_flight: consumer.in_flight,
+                    delivered: consumer.delivered,
+                    acked: consumer.acked,
+                })
+                .collect(),
+        }
+    }
+}
+
+type Group = Arc<Mutex<GroupState>>;
+
+/// Consumer groups of a bus
+#[derive(Debug, Default)]
+pub struct WorkQueues {
+    groups: dashmap::DashMap<String, Group>,
+}
+
+impl WorkQueues {
+    /// Create an empty set of groups
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn group(&self, name: &str) -> EventBusResult<Group> {
+        self.groups
+            .get(name)
+            .map(|group| Arc::clone(&group))
+            .ok_or_else(|| EventBusError::not_found(format!("work queue '{}'", name)))
+    }
+
+    /// Create a consumer group
+    ///
+    /// Only events emitted after the group is created are queued.
+    pub fn create(&self, config: WorkQueueConfig) -> EventBusResult<WorkQueueStats> {
+        if config.group.is_empty() || config.topic.is_empty() {
+            return Err(EventBusError::invalid_input("work queue group and topic must not be empty"));
+        }
+        if config.max_in_flight == 0 {
+            return Err(EventBusError::invalid_input("work queue max_in_flight must be at least 1"));
+        }
+
+        match self.groups.entry(config.group.clone()) {
+            dashmap::mapref::entry::Entry::Occupied(_) => {
+                Err(EventBusError::already_exists(format!("work queue '{}'", config.group)))
+            }
+            dashmap::mapref::entry::Entry::Vacant(entry) => {
+                let state = GroupState::new(config);
+                let stats = state.stats();
+                entry.insert(Arc::new(Mutex::new(state)));
+                Ok(stats)
+            }
+        }
+    }
+
+    /// Delete a consumer group, dropping its queued and leased events
+    ///
+    /// Consumers waiting in `fetch` return empty-handed.
+    pub fn delete(&self, group: &str) -> EventBusResult<WorkQueueStats> {
+        let (_, group) = self.groups
+            .remove(group)
+            .ok_or_else(|| EventBusError::not_found(format!("work queue '{}'", group)))?;
+        let mut state = group.lock();
+        state.waiters.clear();
+        Ok(state.stats())
+    }
+
+    /// Queue an event on every group whose topic pattern matches it
+    pub fn offer(&self, event: &EventEnvelope) {
+        let now = Instant::now();
+        for group in self.groups.iter() {
+            let mut state = group.lock();
+            if !topic_matches_pattern(&event.topic, &state.config.topic) {
+                continue;
+            }
+            state.ready.push_back(Pending { event: event.clone(), attempts: 0 });
+            state.expire(now);
+            state.dispatch(now);
+        }
+    }
+
+    /// Lease up to `max` events to a consumer
+    ///
+    /// Waits up to `wait` for an event when none is ready; returns an empty
+    /// list if none arrives, or if the consumer already holds
+    /// `max_in_flight` unacknowledged events.
+    pub async fn fetch(&self, group: &str, consumer: &str, max: usize, wait: Duration) -> EventBusResult<Vec<Delivery>> {
+        let deadline = Instant::now() + wait;
+        let max = max.max(1);
+
+        loop {
+            let group = self.group(group)?;
+            let (waiter_id, mut receiver, wake_at) = {
+                let mut state = group.lock();
+                let now = Instant::now();
+                state.expire(now);
+                // Consumers already waiting are served first
+                state.dispatch(now);
+                state.consumers.entry(consumer.to_string()).or_default();
+
+                let batch = state.take(consumer, max, now);
+                if !batch.is_empty() || now >= deadline {
+                    return Ok(batch);
+                }
+
+                let (sender, receiver) = oneshot::channel();
+                let waiter_id = state.next_waiter_id;
+                state.next_waiter_id += 1;
+                state.waiters.push(Waiter { id: waiter_id, consumer: consumer.to_string(), max, sender });
+
+                // Wake up for the next lease expiry so its event is redelivered
+                let wake_at = state.next_expiry().map_or(deadline, |expiry| expiry.min(deadline));
+                (waiter_id, receiver, wake_at)
+            };
+
+            match tokio::time::timeout_at(wake_at, &mut receiver).await {
+                Ok(Ok(batch)) => return Ok(batch),
+                // The group was deleted
+                Ok(Err(_)) => return Ok(Vec::new()),
+                Err(_) => {
+                    group.lock().waiters.retain(|waiter| waiter.id != waiter_id);
+                    // Served between the timeout and taking the lock
+                    if let Ok(batch) = receiver.try_recv() {
+                        return Ok(batch);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Acknowledge a delivery, removing the event from the queue
+    ///
+    /// Fails when the lease has already expired; the event has then been
+    /// (or will be) redelivered.
+    pub fn ack(&self, group: &str, delivery_id: &str) -> EventBusResult<()> {
+        let group = self.group(group)?;
+        let mut state = group.lock();
+        let lease = state.release(delivery_id)
+            .ok_or_else(|| EventBusError::not_found(format!("delivery '{}'", delivery_id)))?;
+
+        state.acked += 1;
+        if let Some(consumer) = state.consumers.get_mut(&lease.consumer) {
+            consumer.acked += 1;
+        }
+        state.dispatch(Instant::now());
+        Ok(())
+    }
+
+    /// Reject a delivery
+    ///
+    /// With `requeue` the event is redelivered right away (subject to
+    /// `max_deliveries`); otherwise it is dead-lettered.
+    pub fn nack(&self, group: &str, delivery_id: &str, requeue: bool) -> EventBusResult<()> {
+        let group = self.group(group)?;
+        let mut state = group.lock();
+        let lease = state.release(delivery_id)
+            .ok_or_else(|| EventBusError::not_found(format!("delivery '{}'", delivery_id)))?;
+
+        if requeue {
+            state.retry(lease);
+        } else {
+            state.dead_letter(lease.event);
+        }
+        state.dispatch(Instant::now());
+        Ok(())
+    }
+
+    /// Remove a consumer, redelivering the events it holds
+    pub fn leave(&self, group: &str, consumer: &str) -> EventBusResult<()> {
+        let group = self.group(group)?;
+        let mut state = group.lock();
+        let held: Vec<String> = state.leases
+            .iter()
+            .filter(|(_, lease)| lease.consumer == consumer)
+            .map(|(id, _)| id.clone())
+            .collect();
+
+        for delivery_id in held {
+            if let Some(lease) = state.release(&delivery_id) {
+                state.retry(lease);
+            }
+        }
+        state.consumers.remove(consumer);
+        state.dispatch(Instant::now());
+        Ok(())
+    }
+
+    /// State of a consumer group
+    pub fn stats(&self, group: &str) -> EventBusResult<WorkQueueStats> {
+        let group = self.group(group)?;
+        let mut state = group.lock();
+        state.expire(Instant::now());
+        Ok(state.stats())
+    }
+
+    /// State of all consumer groups, sorted by name
+    pub fn list(&self) -> Vec<WorkQueueStats> {
+        let mut groups: Vec<WorkQueueStats> = self.groups.iter().map(|group| group.lock().stats()).collect();
+        groups.sort_by(|a, b| a.config.group.cmp(&b.config.group));
+        groups
+    }
+
+    /// Most recent events that ran out of deliveries, oldest first
+    pub fn dead_letters(&self, group: &str) -> EventBusResult<Vec<EventEnvelope>> {
+        let group = self.group(group)?;
+        let state = group.lock();
+        Ok(state.dead_letters.iter().cloned().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn event(n: u32) -> EventEnvelope {
+        EventEnvelope::new("tasks.build", json!({ "n": n }))
+    }
+
+    #[tokio::test]
+    async fn test_consumers_receive_disjoint_events() {
+        let queues = WorkQueues::new();
+        queues.create(WorkQueueConfig::new("builders", "tasks.*")).unwrap();
+        for n in 0..6 {
+            queues.offer(&event(n));
+        }
+        queues.offer(&EventEnvelope::new("other.topic", json!({})));
+
+        let first = queues.fetch("builders", "worker-a", 4, Duration::ZERO).await.unwrap();
+        let second = queues.fetch("builders", "worker-b", 4, Duration::ZERO).await.unwrap();
+        assert_eq!((first.len(), second.len()), (4, 2));
+
+        let mut seen: Vec<u64> = first.iter().chain(&second)
+            .map(|delivery| delivery.event.payload["n"].as_u64().unwrap())
+            .collect();
+        seen.sort();
+        assert_eq!(seen, vec![0, 1, 2, 3, 4, 5]);
+
+        for delivery in first.iter().chain(&second) {
+            queues.ack("builders", &delivery.delivery_id).unwrap();
+        }
+        let stats = queues.stats("builders").unwrap();
+        assert_eq!((stats.pending, stats.in_flight, stats.acked), (0, 0, 6));
+        assert!(queues.ack("builders", &first[0].delivery_id).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_dispatch_strategies_pick_waiting_consumer() {
+        for (strategy, served) in [(DispatchStrategy::RoundRobin, "a"), (DispatchStrategy::LeastLoaded, "b")] {
+            let queues = Arc::new(WorkQueues::new());
+            queues.create(WorkQueueConfig::new("pool", "tasks.*").with_strategy(strategy)).unwrap();
+
+            // Consumer "a" already holds two unacknowledged events
+            queues.offer(&event(0));
+            queues.offer(&event(1));
+            assert_eq!(queues.fetch("pool", "a", 2, Duration::ZERO).await.unwrap().len(), 2);
+
+            let waiting: Vec<_> = ["a", "b"].into_iter().map(|consumer| {
+                let queues = Arc::clone(&queues);
+                tokio::spawn(async move {
+                    queues.fetch("pool", consumer, 10, Duration::from_secs(5)).await.unwrap()
+                })
+            }).collect();
+            tokio::time::sleep(Duration::from_millis(50)).await;
+
+            // One event wakes exactly one waiter
+            queues.offer(&event(2));
+            let delivered: HashMap<String, u64> = queues.stats("pool").unwrap().consumers
+                .into_iter()
+                .map(|consumer| (consumer.consumer_id, consumer.delivered))
+                .collect();
+            let expected = if served == "a" { (3, 0) } else { (2, 1) };
+            assert_eq!((delivered["a"], delivered["b"]), expected, "{:?}", strategy);
+
+            queues.offer(&event(3));
+            for handle in waiting {
+                assert_eq!(handle.await.unwrap().len(), 1);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_unacked_events_are_redelivered_then_dead_lettered() {
+        let queues = WorkQueues::new();
+        let config = WorkQueueConfig::new("retry", "tasks.*")
+            .with_ack_timeout(Duration::from_millis(30))
+            .with_max_deliveries(2)
+            .with_max_in_flight(1);
+        queues.create(config).unwrap();
+        queues.offer(&event(1));
+
+        let first = queues.fetch("retry", "crashy", 5, Duration::ZERO).await.unwrap();
+        assert_eq!(first.len(), 1);
+        assert_eq!(first[0].attempt, 1);
+
+        // The lease runs out while another consumer waits, which gets the retry
+        let retried = queues.fetch("retry", "healthy", 1, Duration::from_secs(1)).await.unwrap();
+        assert_eq!(retried[0].event.event_id, first[0].event.event_id);
+        assert_eq!(retried[0].attempt, 2);
+        assert!(queues.ack("retry", &first[0].delivery_id).is_err());
+
+        // At its in-flight limit the consumer gets nothing more
+        queues.offer(&event(2));
+        assert!(queues.fetch("retry", "healthy", 5, Duration::ZERO).await.unwrap().is_empty());
+
+        queues.nack("retry", &retried[0].delivery_id, true).unwrap();
+        let stats = queues.stats("retry").unwrap();
+        assert_eq!((stats.redelivered, stats.dead_lettered), (1, 1));
+        assert_eq!(queues.dead_letters("retry").unwrap()[0].event_id, first[0].event.event_id);
+
+        let next = queues.fetch("retry", "healthy", 1, Duration::ZERO).await.unwrap();
+        assert_eq!(next[0].event.payload["n"], 2);
+    }
+}
@@ -0,0 +1,541 @@
+//! Asynchronous leader → follower replication
+//!
+//! A leader bus records the events of selected topics in a [`ReplicationLog`]
+//! as they are stored, numbering them with consecutive offsets. A follower
+//! bus pulls the log from the leader (`eventbus.replication_fetch`), appends
+//! what it has not applied yet and remembers the last offset it applied:
+//!
+//! - events keep their IDs, and events at or below the last applied offset
+//!   of the same epoch are skipped, so refetching a range after a failure
+//!   never duplicates anything. The applied offset is kept in memory only,
+//!   so a restarted follower starts over with the first event still logged;
+//!   events already stored are then ignored by ID;
+//! - followers are read-only: only replicated events are appended, rules and
+//!   plugins do not run for them (they ran on the leader);
+//! - lag is reported as events behind the leader's newest offset and as the
+//!   seconds between that event and the last applied one.
+//!
+//! The log lives in memory and keeps the last `log_capacity` events. Each log
+//! has an epoch, new every time the leader starts; when the epoch changes, or
+//! the follower fell further behind than the log reaches back, the follower
+//! starts over from the oldest event still logged and counts a resync.
+//! Events dropped from the log in between are not replicated.
+//!
+//! For disaster recovery, `EventBusService::promote` turns a follower into a
+//! writable bus after a last attempt to catch up. Events the follower had not
+//! fetched when the leader was lost stay on the leader.
+
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use futures::{SinkExt, StreamExt};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use tokio::net::TcpStream;
+use tokio_util::codec::{Framed, LengthDelimitedCodec};
+
+use crate::core::{BusPlugin, EventBusError, EventBusResult, EventEnvelope};
+use crate::jsonrpc::methods::{method_names, ReplicationFetchParams};
+use crate::service::EventBusService;
+use crate::utils::topic_matches_pattern;
+
+/// Longest time a follower waits for the leader to answer a fetch
+const LEADER_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Replication settings of a bus
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ReplicationConfig {
+    /// Topic patterns logged for followers; the bus keeps a replication log
+    /// when this is not empty
+    pub topics: Vec<String>,
+
+    /// Most events kept in the replication log
+    pub log_capacity: usize,
+
+    /// JSON-RPC address of the leader; when set, the bus starts read-only as
+    /// its follower
+    pub leader: Option<String>,
+
+    /// Time between fetches from the leader, in milliseconds
+    pub poll_interval_ms: u64,
+
+    /// Most events fetched at once
+    pub batch_size: u32,
+}
+
+impl Default for ReplicationConfig {
+    fn default() -> Self {
+        Self {
+            topics: Vec::new(),
+            log_capacity: 100_000,
+            leader: None,
+            poll_interval_ms: 500,
+            batch_size: 500,
+        }
+    }
+}
+
+/// An event in the replication log
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReplicatedEvent {
+    /// Position in the log, starting at 1
+    pub offset: u64,
+    /// Unix timestamp when the leader logged the event
+    pub logged_at: i64,
+    /// The event as stored on the leader
+    pub event: EventEnvelope,
+}
+
+/// Events read from a replication log
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReplicationBatch {
+    /// Epoch of the log
+    pub epoch: String,
+    /// Oldest offset still in the log (`head + 1` when the log is empty)
+    pub first_offset: u64,
+    /// Newest offset of the log (0 before the first event)
+    pub head: u64,
+    /// When the newest event was logged
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub head_logged_at: Option<i64>,
+    /// Events after the requested offset, oldest first
+    pub events: Vec<ReplicatedEvent>,
+}
+
+/// Log of the replicated topics kept by a leader
+///
+/// Registered as a plugin, so events are logged once they are stored.
+#[derive(Debug)]
+pub struct ReplicationLog {
+    epoch: String,
+    topics: Vec<String>,
+    capacity: usize,
+    entries: Mutex<LogEntries>,
+}
+
+#[derive(Debug, Default)]
+struct LogEntries {
+    events: VecDeque<ReplicatedEvent>,
+    head: u64,
+}
+
+impl ReplicationLog {
+    /// Create an empty log of the topics matching `topics`, with a new epoch
+    pub fn new(topics: Vec<String>, capacity: usize) -> Self {
+        Self {
+            epoch: uuid::Uuid::new_v4().to_string(),
+            topics,
+            capacity: capacity.max(1),
+            entries: Mutex::new(LogEntries::default()),
+        }
+    }
+
+    /// Epoch of this log
+    pub fn epoch(&self) -> &str {
+        &self.epoch
+    }
+
+    /// Topic patterns this log records
+    pub fn topics(&self) -> &[String] {
+        &self.topics
+    }
+
+    /// Offset of the newest event (0 before the first)
+    pub fn head(&self) -> u64 {
+        self.entries.lock().head
+    }
+
+    /// Whether events of a topic are logged
+    pub fn records(&self, topic: &str) -> bool {
+        self.topics.iter().any(|pattern| topic_matches_pattern(topic, pattern))
+    }
+
+    /// Log an event if its topic is replicated, returning its offset
+    pub fn append(&self, event: &EventEnvelope, now: i64) -> Option<u64> {
+        if !self.records(&event.topic) {
+            return None;
+        }
+        let mut entries = self.entries.lock();
+        entries.head += 1;
+        let offset = entries.head;
+        if entries.events.len() >= self.capacity {
+            entries.events.pop_front();
+        }
+        entries.events.push_back(ReplicatedEvent {
+            offset,
+            logged_at: now,
+            event: event.clone(),
+        });
+        Some(offset)
+    }
+
+    /// Read up to `limit` events logged after offset `after`
+    pub fn fetch(&self, after: u64, limit: u32) -> ReplicationBatch {
+        let entries = self.entries.lock();
+        let first_offset = entries.events.front().map_or(entries.head + 1, |entry| entry.offset);
+        let skip = (after + 1).saturating_sub(first_offset) as usize;
+        ReplicationBatch {
+            epoch: self.epoch.clone(),
+            first_offset,
+            head: entries.head,
+            head_logged_at: entries.events.back().map(|entry| entry.logged_at),
+            events: entries.events.iter().skip(skip).take(limit as usize).cloned().collect(),
+        }
+    }
+}
+
+#[async_trait]
+impl BusPlugin for ReplicationLog {
+    fn name(&self) -> &str {
+        "replication"
+    }
+
+    async fn after_store(&self, event: &EventEnvelope) {
+        self.append(event, chrono::Utc::now().timestamp());
+    }
+}
+
+/// Where a follower reads the leader's log from
+#[async_trait]
+pub trait ReplicationSource: Send + Sync {
+    /// Read up to `limit` events logged after offset `after`
+    async fn fetch(&self, after: u64, limit: u32) -> EventBusResult<ReplicationBatch>;
+}
+
+#[async_trait]
+impl ReplicationSource for ReplicationLog {
+    async fn fetch(&self, after: u64, limit: u32) -> EventBusResult<ReplicationBatch> {
+        Ok(ReplicationLog::fetch(self, after, limit))
+    }
+}
+
+/// Leader reached over JSON-RPC (TCP, length-delimited frames)
+///
+/// Connects on first use and again after a failed fetch.
+pub struct LeaderConnection {
+    addr: String,
+    connection: tokio::sync::Mutex<Option<Framed<TcpStream, LengthDelimitedCodec>>>,
+}
+
+impl LeaderConnection {
+    /// Create a connection to the leader's JSON-RPC address
+    pub fn new(addr: impl Into<String>) -> Self {
+        Self {
+            addr: addr.into(),
+            connection: tokio::sync::Mutex::new(None),
+        }
+    }
+
+    /// Address of the leader
+    pub fn addr(&self) -> &str {
+        &self.addr
+    }
+
+    async fn call(
+        framed: &mut Framed<TcpStream, LengthDelimitedCodec>,
+        after: u64,
+        limit: u32,
+    ) -> EventBusResult<ReplicationBatch> {
+        let id = uuid::Uuid::new_v4().to_string();
+        let request = serde_json::json!({
+            "jsonrpc": "2.0",
+            "method": method_names::REPLICATION_FETCH,
+            "params": ReplicationFetchParams { after, limit },
+            "id": id,
+        });
+        framed
+            .send(serde_json::to_vec(&request)?.into())
+            .await
+            .map_err(|e| EventBusError::transport(format!("Failed to send to leader: {}", e)))?;
+
+        loop {
+            let frame = framed
+                .next()
+                .await
+                .ok_or_else(|| EventBusError::transport("Leader closed the connection"))?
+                .map_err(|e| EventBusError::transport(format!("Failed to read from leader: {}", e)))?;
+            let mut response: serde_json::Value = serde_json::from_slice(&frame)?;
+            if response["id"] != id {
+                continue;
+            }
+            if let Some(error) = response.get("error") {
+                return Err(EventBusError::unavailable(format!("Leader rejected replication fetch: {}", error["message"])));
+            }
+            return Ok(serde_json::from_value(response["result"].take())?);
+        }
+    }
+}
+
+#[async_trait]
+impl ReplicationSource for LeaderConnection {
+    async fn fetch(&self, after: u64, limit: u32) -> EventBusResult<ReplicationBatch> {
+        let mut connection = self.connection.lock().await;
+        let result = tokio::time::timeout(LEADER_TIMEOUT, async {
+            let framed = match connection.as_mut() {
+                Some(framed) => framed,
+                None => {
+                    let stream = TcpStream::connect(&self.addr).await.map_err(|e| {
+                        EventBusError::transport(format!("Cannot reach leader {}: {}", self.addr, e))
+                    })?;
+                    connection.insert(Framed::new(stream, LengthDelimitedCodec::new()))
+                }
+            };
+            Self::call(framed, after, limit).await
+        })
+        .await
+        .unwrap_or_else(|_| Err(EventBusError::timeout(format!("replication fetch from {}", self.addr))));
+
+        // Start from a fresh connection next time rather than read a late answer
+        if result.is_err() {
+            *connection = None;
+        }
+        result
+    }
+}
+
+/// Replication role of a bus
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReplicationRole {
+    /// Keeps a replication log for followers
+    Leader,
+    /// Appends the events of a leader
+    Follower,
+    /// Former follower, now writable
+    Promoted,
+}
+
+/// Replication state of a bus
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReplicationStatus {
+    /// Leader, follower or promoted follower
+    pub role: ReplicationRole,
+    /// Epoch of the log (for followers, the one last fetched)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub epoch: Option<String>,
+    /// Newest offset logged (leader) or applied (follower)
+    pub position: u64,
+    /// Newest offset of the leader's log, as last seen
+    pub leader_head: u64,
+    /// Events the follower is behind the leader
+    pub lag_events: u64,
+    /// Seconds between the leader logging its newest event and the last
+    /// applied one
+    pub lag_seconds: u64,
+    /// Events appended from the leader
+    pub applied: u64,
+    /// Events fetched again and skipped
+    pub skipped: u64,
+    /// Restarts from the oldest logged event after a new epoch or a gap
+    pub resyncs: u64,
+    /// Unix timestamp of the last successful fetch
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_fetch_at: Option<i64>,
+    /// Why the last fetch failed, until one succeeds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+}
+
+impl ReplicationStatus {
+    fn new(role: ReplicationRole) -> Self {
+        Self {
+            role,
+            epoch: None,
+            position: 0,
+            leader_head: 0,
+            lag_events: 0,
+            lag_seconds: 0,
+            applied: 0,
+            skipped: 0,
+            resyncs: 0,
+            last_fetch_at: None,
+            last_error: None,
+        }
+    }
+
+    /// Status of a leader's log
+    pub fn leader(log: &ReplicationLog) -> Self {
+        let head = log.head();
+        Self {
+            epoch: Some(log.epoch().to_string()),
+            position: head,
+            leader_head: head,
+            ..Self::new(ReplicationRole::Leader)
+        }
+    }
+}
+
+/// Follower side of replication: position in the leader's log and lag
+pub struct ReplicationFollower {
+    source: Arc<dyn ReplicationSource>,
+    batch_size: u32,
+    poll_interval: Duration,
+    state: Mutex<FollowerState>,
+    /// Held for a whole sync, so concurrent syncs never apply a range twice
+    sync: tokio::sync::Mutex<()>,
+}
+
+struct FollowerState {
+    status: ReplicationStatus,
+    /// When the leader logged the last applied event
+    applied_logged_at: Option<i64>,
+}
+
+impl ReplicationFollower {
+    /// Create a follower reading from `source` with the fetch settings of `config`
+    pub fn new(source: Arc<dyn ReplicationSource>, config: &ReplicationConfig) -> Self {
+        Self {
+            source,
+            batch_size: config.batch_size.max(1),
+            poll_interval: Duration::from_millis(config.poll_interval_ms.max(1)),
+            state: Mutex::new(FollowerState {
+                status: ReplicationStatus::new(ReplicationRole::Follower),
+                applied_logged_at: None,
+            }),
+            sync: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    /// Time between fetches of the replication task
+    pub fn poll_interval(&self) -> Duration {
+        self.poll_interval
+    }
+
+    /// Current position and lag
+    pub fn status(&self) -> ReplicationStatus {
+        self.state.lock().status.clone()
+    }
+
+    /// Fetch one batch from the leader and append it to `service`
+    ///
+    /// Returns the number of events appended.
+    pub async fn sync(&self, service: &EventBusService) -> EventBusResult<usize> {
+        let _running = self.sync.lock().await;
+        let (epoch, position) = {
+            let state = self.state.lock();
+            if state.status.role == ReplicationRole::Promoted {
+                return Err(EventBusError::unavailable("Follower was promoted; replication stopped"));
+            }
+            (state.status.epoch.clone(), state.status.position)
+        };
+
+        let mut batch = self.fetch(position).await?;
+        let mut after = position;
+        let mut resync = false;
+        if epoch.is_some_and(|epoch| epoch != batch.epoch) {
+            // The leader restarted with a new log, whose offsets start over
+            resync = true;
+            after = 0;
+            batch = self.fetch(0).await?;
+        }
+        if batch.first_offset > after + 1 {
+            // Events between were dropped from the log before being fetched
+            resync = true;
+        }
+
+        let mut applied = 0;
+        let mut skipped = 0;
+        let mut applied_logged_at = None;
+        let mut failure = None;
+        for entry in batch.events {
+            if entry.offset <= after {
+                skipped += 1;
+                continue;
+            }
+            if let Err(e) = service.apply_replicated(entry.event).await {
+                failure = Some(e);
+                break;
+            }
+            applied += 1;
+            after = entry.offset;
+            applied_logged_at = Some(entry.logged_at);
+        }
+
+        let mut state = self.state.lock();
+        let applied_logged_at = applied_logged_at.or(state.applied_logged_at);
+        state.applied_logged_at = applied_logged_at;
+        let status = &mut state.status;
+        status.epoch = Some(batch.epoch);
+        status.position = after;
+        status.leader_head = batch.head;
+        status.lag_events = batch.head.saturating_sub(after);
+        status.applied += applied as u64;
+        status.skipped += skipped;
+        status.resyncs += resync as u64;
+        status.last_fetch_at = Some(chrono::Utc::now().timestamp());
+        status.last_error = failure.as_ref().map(|e| e.to_string());
+        status.lag_seconds = match (status.lag_events, batch.head_logged_at, applied_logged_at) {
+            (0, _, _) => 0,
+            (_, Some(head), Some(applied)) => (head - applied).max(0) as u64,
+            _ => 0,
+        };
+
+        match failure {
+            Some(e) => Err(e),
+            None => Ok(applied),
+        }
+    }
+
+    async fn fetch(&self, after: u64) -> EventBusResult<ReplicationBatch> {
+        self.source.fetch(after, self.batch_size).await.inspect_err(|e| {
+            self.state.lock().status.last_error = Some(e.to_string());
+        })
+    }
+
+    /// Carry on from the position another follower reached
+    ///
+    /// Used when the bus switches leaders: a source with the same epoch is
+    /// the same log, and replication resumes after the last applied offset
+    /// instead of applying the log again. Counters carry on as well.
+    pub(crate) fn resume_from(self, previous: &ReplicationFollower) -> Self {
+        {
+            let previous = previous.state.lock();
+            let mut state = self.state.lock();
+            state.status = ReplicationStatus {
+                role: ReplicationRole::Follower,
+                last_error: None,
+                ..previous.status.clone()
+            };
+            state.applied_logged_at = previous.applied_logged_at;
+        }
+        self
+    }
+
+    /// Stop following, returning the final status
+    pub(crate) fn promote(&self) -> ReplicationStatus {
+        let mut state = self.state.lock();
+        state.status.role = ReplicationRole::Promoted;
+        state.status.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_log_offsets_and_capacity() {
+        let log = ReplicationLog::new(vec!["orders.*".to_string()], 3);
+        assert_eq!(log.fetch(0, 10).first_offset, 1);
+
+        assert_eq!(log.append(&EventEnvelope::new("users.created", json!({})), 100), None);
+        for index in 0..5 {
+            let event = EventEnvelope::new("orders.created", json!({ "index": index }));
+            assert_eq!(log.append(&event, 100 + index), Some(index as u64 + 1));
+        }
+
+        // Only the last three events are kept
+        let batch = log.fetch(0, 10);
+        assert_eq!((batch.first_offset, batch.head, batch.head_logged_at), (3, 5, Some(104)));
+        assert_eq!(batch.events.iter().map(|entry| entry.offset).collect::<Vec<_>>(), vec![3, 4, 5]);
+
+        let batch = log.fetch(3, 1);
+        assert_eq!((batch.events.len(), batch.events[0].offset), (1, 4));
+        assert!(log.fetch(5, 10).events.is_empty());
+        assert_eq!(batch.epoch, log.epoch());
+    }
+}
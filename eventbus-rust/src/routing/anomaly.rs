@@ -0,0 +1,418 @@
+//! Event rate anomaly detection
+//!
+//! An [`AnomalyRule`] keeps a rolling baseline of how many events reach a
+//! topic pattern per window: the mean count of the last `baseline_windows`
+//! windows. Each window is compared with the baseline when it closes:
+//!
+//! - more than `factor` times the baseline, and at least `min_events`, is a
+//!   spike;
+//! - less than the baseline divided by `factor`, when the baseline is at
+//!   least `min_events`, is a silence.
+//!
+//! Nothing is judged until the baseline spans `baseline_windows` windows.
+//! Independently of the baseline, `silence_secs` reports a pattern that
+//! received no event for that long ("no heartbeat in 5 minutes").
+//!
+//! The service emits every finding as an `anomaly.spike` or `anomaly.silence`
+//! event, so ordinary rules and subscribers can react to it; events on
+//! `anomaly.*` topics are never counted. A silence is reported once, until
+//! events arrive again.
+
+use std::collections::VecDeque;
+
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+
+use crate::core::{EventBusError, EventBusResult, EventEnvelope};
+
+/// Prefix of the topics anomalies are emitted to
+pub const ANOMALY_TOPIC_PREFIX: &str = "anomaly.";
+
+/// Rule watching the event rate of a topic pattern
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AnomalyRule {
+    /// Unique rule identifier
+    pub id: String,
+
+    /// Topic pattern whose events are counted
+    pub topic: String,
+
+    /// Length of a counting window, in seconds
+    #[serde(default = "default_window_secs")]
+    pub window_secs: u64,
+
+    /// Number of past windows averaged into the baseline
+    #[serde(default = "default_baseline_windows")]
+    pub baseline_windows: usize,
+
+    /// Ratio to the baseline beyond which a window is anomalous
+    #[serde(default = "default_factor")]
+    pub factor: f64,
+
+    /// Fewest events per window worth judging: a spike window needs this
+    /// many, and so does the baseline before a drop counts as a silence
+    #[serde(default = "default_min_events")]
+    pub min_events: u64,
+
+    /// Report a silence after this many seconds without events
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub silence_secs: Option<u64>,
+}
+
+fn default_window_secs() -> u64 {
+    60
+}
+
+fn default_baseline_windows() -> usize {
+    10
+}
+
+fn default_factor() -> f64 {
+    3.0
+}
+
+fn default_min_events() -> u64 {
+    5
+}
+
+impl AnomalyRule {
+    /// Create a rule with one-minute windows, a ten-window baseline and a
+    /// factor of 3
+    pub fn new(id: impl Into<String>, topic: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            topic: topic.into(),
+            window_secs: default_window_secs(),
+            baseline_windows: default_baseline_windows(),
+            factor: default_factor(),
+            min_events: default_min_events(),
+            silence_secs: None,
+        }
+    }
+
+    /// Set the window length in seconds
+    pub fn with_window(mut self, window_secs: u64) -> Self {
+        self.window_secs = window_secs;
+        self
+    }
+
+    /// Set the number of windows averaged into the baseline
+    pub fn with_baseline_windows(mut self, windows: usize) -> Self {
+        self.baseline_windows = windows;
+        self
+    }
+
+    /// Set the deviation ratio
+    pub fn with_factor(mut self, factor: f64) -> Self {
+        self.factor = factor;
+        self
+    }
+
+    /// Set the fewest events per window worth judging
+    pub fn with_min_events(mut self, min_events: u64) -> Self {
+        self.min_events = min_events;
+        self
+    }
+
+    /// Report a silence after the given number of seconds without events
+    pub fn with_silence_after(mut self, silence_secs: u64) -> Self {
+        self.silence_secs = Some(silence_secs);
+        self
+    }
+
+    /// Check that the rule can be evaluated
+    pub fn validate(&self) -> EventBusResult<()> {
+        let problem = if self.id.is_empty() {
+            "id must not be empty"
+        } else if self.window_secs == 0 {
+            "window_secs must be positive"
+        } else if self.baseline_windows == 0 {
+            "baseline_windows must be positive"
+        } else if self.factor.is_nan() || self.factor <= 1.0 {
+            "factor must be greater than 1"
+        } else if self.silence_secs == Some(0) {
+            "silence_secs must be positive"
+        } else if self.topic.starts_with(ANOMALY_TOPIC_PREFIX) {
+            "anomaly topics cannot be watched"
+        } else {
+            return Ok(());
+        };
+        Err(EventBusError::validation(format!("Invalid anomaly rule '{}': {}", self.id, problem)))
+    }
+}
+
+/// Direction of an anomaly
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AnomalyKind {
+    /// Far more events than usual
+    Spike,
+    /// Far fewer events than usual, or none for `silence_secs`
+    Silence,
+}
+
+impl AnomalyKind {
+    /// Topic anomalies of this kind are emitted to
+    pub fn topic(self) -> &'static str {
+        match self {
+            AnomalyKind::Spike => "anomaly.spike",
+            AnomalyKind::Silence => "anomaly.silence",
+        }
+    }
+}
+
+/// An anomaly found by a rule
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Anomaly {
+    /// Rule that found it
+    pub rule_id: String,
+    /// Topic pattern of the rule
+    pub topic: String,
+    /// Spike or silence
+    pub kind: AnomalyKind,
+    /// Events counted in the window that closed (0 when `silence_secs` elapsed)
+    pub observed: u64,
+    /// Mean events per window, if the finding compares against the baseline
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub baseline: Option<f64>,
+    /// Window length of the rule, in seconds
+    pub window_secs: u64,
+    /// Seconds since the last counted event, for silences
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub silent_secs: Option<u64>,
+    /// Unix timestamp of the detection
+    pub detected_at: i64,
+}
+
+impl Anomaly {
+    /// Event announcing the anomaly on `anomaly.<kind>`
+    pub fn to_event(&self) -> EventBusResult<EventEnvelope> {
+        Ok(EventEnvelope::new(self.kind.topic(), serde_json::to_value(self)?))
+    }
+}
+
+/// Counting state of one rule
+#[derive(Debug)]
+struct RuleState {
+    rule: AnomalyRule,
+    window_start: i64,
+    count: u64,
+    history: VecDeque<u64>,
+    last_event_at: i64,
+    silent: bool,
+}
+
+impl RuleState {
+    fn new(rule: AnomalyRule, now: i64) -> Self {
+        Self {
+            history: VecDeque::with_capacity(rule.baseline_windows),
+            rule,
+            window_start: now,
+            count: 0,
+            last_event_at: now,
+            silent: false,
+        }
+    }
+
+    /// Close the windows that ended by `now`
+    fn roll(&mut self, now: i64, found: &mut Vec<Anomaly>) {
+        let window = self.rule.window_secs as i64;
+        let windows = (now - self.window_start) / window;
+        if windows <= 0 {
+            return;
+        }
+
+        // Past a full baseline of empty windows the history is all zeros,
+        // so longer gaps need not be replayed window by window
+        let replayed = windows.min(self.rule.baseline_windows as i64 + 1);
+        for index in 0..replayed {
+            let count = if index == 0 { std::mem::take(&mut self.count) } else { 0 };
+            self.close_window(count, self.window_start + (index + 1) * window, found);
+        }
+        self.window_start += windows * window;
+    }
+
+    fn close_window(&mut self, count: u64, closed_at: i64, found: &mut Vec<Anomaly>) {
+        if self.history.len() >= self.rule.baseline_windows {
+            let baseline = self.history.iter().sum::<u64>() as f64 / self.history.len() as f64;
+            let observed = count as f64;
+            if count >= self.rule.min_events && observed > baseline * self.rule.factor {
+                found.push(self.anomaly(AnomalyKind::Spike, count, Some(baseline), closed_at));
+            } else if !self.silent
+                && baseline >= self.rule.min_events as f64
+                && observed < baseline / self.rule.factor
+            {
+                self.silent = true;
+                found.push(self.anomaly(AnomalyKind::Silence, count, Some(baseline), closed_at));
+            }
+            self.history.pop_front();
+        }
+        self.history.push_back(count);
+    }
+
+    fn anomaly(&self, kind: AnomalyKind, observed: u64, baseline: Option<f64>, at: i64) -> Anomaly {
+        Anomaly {
+            rule_id: self.rule.id.clone(),
+            topic: self.rule.topic.clone(),
+            kind,
+            observed,
+            baseline,
+            window_secs: self.rule.window_secs,
+            silent_secs: (kind == AnomalyKind::Silence).then(|| (at - self.last_event_at).max(0) as u64),
+            detected_at: at,
+        }
+    }
+}
+
+/// Tracks event rates for a set of anomaly rules
+///
+/// Times are Unix timestamps in seconds, passed in by the caller.
+#[derive(Debug, Default)]
+pub struct AnomalyDetector {
+    state: Mutex<DetectorState>,
+}
+
+#[derive(Debug, Default)]
+struct DetectorState {
+    rules: Vec<RuleState>,
+    /// Found while counting events, reported by the next `evaluate`
+    pending: Vec<Anomaly>,
+}
+
+impl AnomalyDetector {
+    /// Create a detector without rules
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a rule, replacing the rule with the same ID
+    ///
+    /// Counting starts at `now` with an empty baseline.
+    pub fn register(&self, rule: AnomalyRule, now: i64) -> EventBusResult<()> {
+        rule.validate()?;
+        let mut state = self.state.lock();
+        state.rules.retain(|existing| existing.rule.id != rule.id);
+        state.rules.push(RuleState::new(rule, now));
+        Ok(())
+    }
+
+    /// Remove a rule
+    pub fn remove(&self, rule_id: &str) -> EventBusResult<()> {
+        let mut state = self.state.lock();
+        let before = state.rules.len();
+        state.rules.retain(|existing| existing.rule.id != rule_id);
+        if state.rules.len() == before {
+            return Err(EventBusError::not_found(format!("anomaly rule: {}", rule_id)));
+        }
+        Ok(())
+    }
+
+    /// Registered rules
+    pub fn rules(&self) -> Vec<AnomalyRule> {
+        self.state.lock().rules.iter().map(|state| state.rule.clone()).collect()
+    }
+
+    /// Count an event stored at `now`
+    pub fn observe(&self, event: &EventEnvelope, now: i64) {
+        if event.topic.starts_with(ANOMALY_TOPIC_PREFIX) {
+            return;
+        }
+        let mut state = self.state.lock();
+        let DetectorState { rules, pending } = &mut *state;
+        for rule in rules.iter_mut().filter(|rule| event.matches_topic(&rule.rule.topic)) {
+            rule.roll(now, pending);
+            rule.count += 1;
+            rule.last_event_at = now;
+            rule.silent = false;
+        }
+    }
+
+    /// Close the windows that ended by `now` and return the anomalies found
+    /// since the last call
+    pub fn evaluate(&self, now: i64) -> Vec<Anomaly> {
+        let mut state = self.state.lock();
+        let DetectorState { rules, pending } = &mut *state;
+        let mut found = std::mem::take(pending);
+        for rule in rules.iter_mut() {
+            rule.roll(now, &mut found);
+            if let Some(silence_secs) = rule.rule.silence_secs {
+                if !rule.silent && now - rule.last_event_at >= silence_secs as i64 {
+                    rule.silent = true;
+                    found.push(rule.anomaly(AnomalyKind::Silence, 0, None, now));
+                }
+            }
+        }
+        found
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn observe_n(detector: &AnomalyDetector, topic: &str, count: usize, at: i64) {
+        for _ in 0..count {
+            detector.observe(&EventEnvelope::new(topic, json!({})), at);
+        }
+    }
+
+    #[test]
+    fn test_rate_spike_and_silence() {
+        let detector = AnomalyDetector::new();
+        let rule = AnomalyRule::new("orders-rate", "orders.*")
+            .with_window(10)
+            .with_baseline_windows(3)
+            .with_min_events(5);
+        detector.register(rule, 0).unwrap();
+
+        // Three windows of 4 events build the baseline
+        for window in 0..3 {
+            observe_n(&detector, "orders.created", 4, window * 10 + 1);
+        }
+        observe_n(&detector, "users.created", 50, 25);
+        assert!(detector.evaluate(30).is_empty());
+
+        observe_n(&detector, "orders.created", 20, 35);
+        let found = detector.evaluate(40);
+        assert_eq!(found.len(), 1);
+        assert_eq!((found[0].kind, found[0].observed, found[0].baseline), (AnomalyKind::Spike, 20, Some(4.0)));
+        assert_eq!(found[0].to_event().unwrap().topic, "anomaly.spike");
+
+        // A quiet window is a silence, reported once
+        let found = detector.evaluate(50);
+        assert_eq!((found[0].kind, found[0].observed, found[0].silent_secs), (AnomalyKind::Silence, 0, Some(15)));
+        assert!(detector.evaluate(90).is_empty());
+
+        // Anomaly events are not counted
+        observe_n(&detector, "anomaly.spike", 10, 95);
+        assert!(detector.evaluate(100).is_empty());
+    }
+
+    #[test]
+    fn test_silence_after_and_rule_validation() {
+        let detector = AnomalyDetector::new();
+        detector.register(AnomalyRule::new("heartbeat", "agents.heartbeat").with_silence_after(300), 0).unwrap();
+
+        assert!(detector.evaluate(299).is_empty());
+        let found = detector.evaluate(300);
+        assert_eq!((found[0].kind, found[0].silent_secs, found[0].baseline), (AnomalyKind::Silence, Some(300), None));
+        assert!(detector.evaluate(400).is_empty());
+
+        observe_n(&detector, "agents.heartbeat", 1, 401);
+        assert!(detector.evaluate(700).is_empty());
+        assert_eq!(detector.evaluate(701).len(), 1);
+
+        for rule in [
+            AnomalyRule::new("a", "x").with_factor(1.0),
+            AnomalyRule::new("b", "x").with_window(0),
+            AnomalyRule::new("c", "x").with_silence_after(0),
+            AnomalyRule::new("d", "anomaly.*"),
+        ] {
+            assert!(detector.register(rule, 0).is_err());
+        }
+        detector.remove("heartbeat").unwrap();
+        assert!(detector.remove("heartbeat").is_err());
+        assert!(detector.rules().is_empty());
+    }
+}
@@ -1,8 +1,10 @@
 //! Event routing and rule engine implementations
 
+pub mod anomaly;
 pub mod memory_router;
 pub mod rule_engine;
 
+pub use anomaly::{Anomaly, AnomalyDetector, AnomalyKind, AnomalyRule};
 pub use memory_router::MemoryEventRouter;
 pub use rule_engine::MemoryRuleEngine;
 
@@ -69,7 +69,10 @@ impl RuleEngine for MemoryRuleEngine {
             if rule.matches(event) {
                 match &rule.action {
                     crate::core::RuleAction::InvokeTool { tool_id, input } => {
-                        invocations.push(ToolInvocation::new(tool_id.clone(), input.clone()));
+                        invocations.push(
+                            ToolInvocation::new(tool_id.clone(), input.clone())
+                                .with_priority(rule.action_priority)
+                        );
                     }
                     crate::core::RuleAction::EmitEvent { .. } => {
                         // TODO: Handle event emission
@@ -0,0 +1,77 @@
+//! Prioritized execution of rule actions
+//!
+//! Tool invocations produced by rules run on jsonrpc-rust's
+//! `PriorityExecutor`. They wait in one queue per `Priority`, and a free
+//! worker always starts the oldest invocation of the highest priority, so
+//! actions of critical rules (alerts, say) overtake a backlog of bulk ones
+//! instead of waiting behind it. Running invocations are never interrupted.
+//!
+//! Invocations are fire-and-forget: failures are logged, and timeouts (the
+//! invocation's `timeout_ms`, or the executor's default of 30 seconds) are
+//! counted in `stats`.
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use jsonrpc_rust::core::executor::{ExecutorConfig, ExecutorStats, PriorityExecutor, TaskHandle};
+use jsonrpc_rust::core::future::{JsonRpcFuture, SpawnPolicy};
+use jsonrpc_rust::core::types::JsonRpcResponse;
+
+use crate::core::{Priority, ToolInvocation, ToolInvoker};
+
+/// Worker pool running rule actions by priority
+#[derive(Clone)]
+pub struct ActionExecutor {
+    executor: PriorityExecutor,
+    invoker: Arc<dyn ToolInvoker>,
+}
+
+impl ActionExecutor {
+    /// Create a pool running at most `max_concurrent` invocations at once
+    pub fn new(invoker: Arc<dyn ToolInvoker>, max_concurrent: usize) -> Self {
+        Self {
+            executor: PriorityExecutor::new(ExecutorConfig::new().with_max_concurrent_tasks(max_concurrent)),
+            invoker,
+        }
+    }
+
+    /// Queue an invocation at its priority
+    ///
+    /// The returned handle yields the tool's result; dropping it does not
+    /// stop the invocation.
+    pub fn submit(&self, invocation: ToolInvocation) -> TaskHandle {
+        let mut policy = SpawnPolicy::new().with_priority(invocation.priority);
+        if let Some(timeout_ms) = invocation.timeout_ms {
+            policy = policy.with_timeout(Duration::from_millis(timeout_ms));
+        }
+
+        let invoker = self.invoker.clone();
+        let future = JsonRpcFuture::with_policy(
+            async move {
+                match invoker.invoke_tool(&invocation).await {
+                    Ok(result) => Ok(JsonRpcResponse::success(serde_json::Value::Null, result)),
+                    Err(e) => {
+                        tracing::warn!("Rule action {} failed: {}", invocation.tool_id, e);
+                        Err(jsonrpc_rust::Error::service(e.to_string()))
+                    }
+                }
+            },
+            policy,
+        );
+        self.executor.submit(future)
+    }
+
+    /// Invocations waiting for a worker, per priority
+    pub fn queue_depths(&self) -> BTreeMap<Priority, usize> {
+        Priority::all()
+            .iter()
+            .map(|&priority| (priority, self.executor.queued(priority)))
+            .collect()
+    }
+
+    /// Invocations queued, running, completed and failed
+    pub fn stats(&self) -> ExecutorStats {
+        self.executor.stats()
+    }
+}
@@ -7,14 +7,30 @@ use tokio::time::{Duration, Instant};
 use std::sync::atomic::{AtomicU64, Ordering};
 
 use serde::{Serialize, Deserialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 use crate::core::{
-    EventEnvelope, EventQuery, EventTriggerRule,
-    traits::{EventBus, EventStorage, RuleEngine, EventBusResult},
+    BatchMetadata, EventEnvelope, EventQuery, EventTriggerRule, IdempotencyRecord, Priority, ToolInvocation,
+    TopicInfo, TopicSettings, UpcasterRegistry, BusPlugin,
+    traits::{EventBus, EventStorage, IdempotencyStorage, RuleEngine, ToolInvoker, EventBusResult},
     EventBusError
 };
 use crate::storage::MemoryStorage;
+use crate::routing::{Anomaly, AnomalyDetector, AnomalyRule};
+use crate::queue::{Delivery, WorkQueues};
+use crate::replication::{
+    LeaderConnection, ReplicationBatch, ReplicationConfig, ReplicationFollower, ReplicationLog, ReplicationRole,
+    ReplicationSource, ReplicationStatus,
+};
+use crate::config::{IntegrityConfig, TopicPolicy};
+use crate::core::integrity::{self, HashChains, IntegrityReport};
+use crate::utils::{normalize_topic, topic_matches_pattern};
+
+pub mod actions;
+pub mod recovery;
+
+pub use actions::ActionExecutor;
+pub use recovery::RecoveryReport;
 
 /// Main event bus service that implements JSON-RPC interface
 pub struct EventBusService {
@@ -24,7 +40,23 @@ pub struct EventBusService {
     /// Rule engine for automated responses
     rule_engine: Option<Arc<dyn RuleEngine>>,
     
-    /// In-memory event distribution (for subscriptions)
+    /// Runs the tool invocations produced by rules, by priority
+    actions: Option<ActionExecutor>,
+    
+    /// Event rate tracking for anomaly rules
+    anomalies: AnomalyDetector,
+    
+    /// Heads of the hash chains of chained topics
+    chains: HashChains,
+    
+    /// Log of the replicated topics, kept for followers
+    replication_log: Option<Arc<ReplicationLog>>,
+    
+    /// Leader this bus replicates from, kept after promotion for its status
+    follower: parking_lot::RwLock<Option<Arc<ReplicationFollower>>>,
+    
+    /// In-memory event store, the primary storage when no persistent backend
+    /// is set; subscribers are fed from the broadcast channel instead
     memory_storage: Arc<MemoryStorage>,
     
     /// Service configuration
@@ -38,10 +70,92 @@ pub struct EventBusService {
     
     /// Performance metrics
     metrics: ServiceMetrics,
+    
+    /// Recorded responses for emits carrying an idempotency key
+    idempotency_storage: Arc<dyn IdempotencyStorage>,
+    
+    /// Serializes concurrent emits sharing an idempotency key
+    idempotency_locks: dashmap::DashMap<String, Arc<tokio::sync::Mutex<()>>>,
+    
+    /// Registered topics (pre-created or auto-created under the topic policy)
+    topics: dashmap::DashMap<String, TopicInfo>,
+    
+    /// Schema upcasters applied to events before delivery
+    upcasters: Arc<UpcasterRegistry>,
+    
+    /// Emit pipeline plugins, in registration order
+    plugins: Vec<Arc<dyn BusPlugin>>,
+    
+    /// Competing-consumer groups fed by emitted events
+    work_queues: WorkQueues,
+    
+    /// Held shared by emits from storing to broadcasting, and exclusively
+    /// while a snapshot is taken, so snapshots never split an emit
+    publish_gate: tokio::sync::RwLock<()>,
+    
+    /// Runtime mode restricting which operations are accepted
+    mode: parking_lot::RwLock<ModeStatus>,
+    
+    /// Report of the last startup recovery, if it ran
+    last_recovery: parking_lot::Mutex<Option<RecoveryReport>>,
+    
+    /// Fault hooks applied to subscriber delivery
+    #[cfg(feature = "chaos")]
+    broadcast_faults: Option<Arc<crate::chaos::BroadcastFaults>>,
+}
+
+/// Stored events of a topic together with a subscription to the events
+/// emitted after them (see `EventBusService::subscribe_with_snapshot`)
+pub struct TopicSnapshot {
+    /// Latest stored events, newest first
+    pub events: Vec<EventEnvelope>,
+    /// Events emitted after the snapshot, in broadcast order
+    pub live: std::pin::Pin<Box<dyn futures::Stream<Item = EventEnvelope> + Send>>,
+}
+
+/// Runtime mode of a bus, for migrations and incident response
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BusMode {
+    /// Every operation is accepted
+    #[default]
+    Normal,
+    /// Reads (poll, subscribe, queue consumption) are accepted; emits and
+    /// admin changes are rejected
+    ReadOnly,
+    /// Everything except health checks and mode changes is rejected
+    Maintenance,
+}
+
+impl std::fmt::Display for BusMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            BusMode::Normal => "normal",
+            BusMode::ReadOnly => "read-only",
+            BusMode::Maintenance => "in maintenance",
+        })
+    }
+}
+
+/// Current mode of a bus
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModeStatus {
+    /// Mode in effect
+    pub mode: BusMode,
+    /// Why the mode was set, reported in rejections
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    /// Unix timestamp when the mode was set
+    pub since: i64,
 }
 
 /// Configuration for the event bus service
+/// 
+/// The configuration every bus runs with. Fields missing when deserializing
+/// take their `Default` values, so config files only list what they change.
+/// The older `config::EventBusConfig` format converts into it with `From`.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct ServiceConfig {
     /// Service instance ID
     pub instance_id: String,
@@ -58,6 +172,9 @@ pub struct ServiceConfig {
     /// Maximum concurrent emit operations
     pub max_concurrent_emits: usize,
     
+    /// Maximum number of rule actions executing at once
+    pub max_concurrent_actions: usize,
+    
     /// Rate limiting: max events per second
     pub max_events_per_second: Option<u32>,
     
@@ -68,7 +185,7 @@ pub struct ServiceConfig {
     #[serde(with = "duration_serde")]
     pub shutdown_grace_period: Duration,
     
-    /// Storage configuration
+    /// Storage backend, created by `EventBusService::with_config`
     pub storage: crate::config::StorageConfig,
     
     /// Event buffer size for processing
@@ -85,6 +202,75 @@ pub struct ServiceConfig {
     
     /// Shutdown timeout in seconds
     pub shutdown_timeout_secs: u64,
+    
+    /// Address to serve JSON-RPC on (no listener is started when unset)
+    #[serde(default)]
+    pub listen: Option<std::net::SocketAddr>,
+    
+    /// Transport limits applied to the JSON-RPC listener
+    #[serde(default)]
+    pub transport: crate::config::TransportConfig,
+    
+    /// How long an emit idempotency key is remembered, in seconds
+    #[serde(default = "default_idempotency_ttl_secs")]
+    pub idempotency_ttl_secs: u64,
+    
+    /// Which topics events may be emitted to
+    #[serde(default)]
+    pub topic_policy: TopicPolicy,
+    
+    /// What emits do when `max_events_per_second` is reached
+    #[serde(default)]
+    pub rate_limit_mode: RateLimitMode,
+    
+    /// Event retention, with per-topic overrides
+    #[serde(default)]
+    pub retention: crate::config::RetentionConfig,
+    
+    /// Event rate anomaly rules, checked by `spawn_anomaly_task`
+    #[serde(default)]
+    pub anomaly_rules: Vec<AnomalyRule>,
+    
+    /// Topics logged for followers, and the leader to follow
+    #[serde(default)]
+    pub replication: ReplicationConfig,
+    
+    /// Event checksums and hash-chained topics
+    #[serde(default)]
+    pub integrity: IntegrityConfig,
+}
+
+/// Behaviour of emits once the rate limit is reached
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum RateLimitMode {
+    /// Fail immediately with a rate limited error
+    #[default]
+    Reject,
+    
+    /// Wait for capacity up to `max_wait_ms`, then fail
+    Queue {
+        /// Longest time an emit waits for capacity
+        max_wait_ms: u64,
+    },
+}
+
+/// Rate limit state reported to producers
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RateLimitStatus {
+    /// Events allowed per second
+    pub limit: u32,
+    /// Events that can still be emitted in the current window
+    pub remaining: u32,
+    /// Milliseconds until the next event slot frees up (0 when `remaining` > 0)
+    pub reset_after_ms: u64,
+}
+
+/// Mode reason of a bus following a replication leader
+const FOLLOWER_MODE_REASON: &str = "replication follower";
+
+fn default_idempotency_ttl_secs() -> u64 {
+    24 * 60 * 60 // 1 day
 }
 
 // Helper module for Duration serialization
@@ -116,15 +302,40 @@ impl Default for ServiceConfig {
             enable_rules: false,
             allowed_sources: vec!["*".to_string()],
             max_concurrent_emits: 100,
+            max_concurrent_actions: 16,
             max_events_per_second: None,
             batch_size: 50,
             shutdown_grace_period: Duration::from_secs(30),
-            storage: crate::config::StorageConfig::Memory,
+            storage: crate::config::StorageConfig::memory(),
             event_buffer_size: 10000,
             subscriber_buffer_size: 1000,
             enable_metrics: true,
             enable_graceful_shutdown: true,
             shutdown_timeout_secs: 30,
+            listen: None,
+            transport: crate::config::TransportConfig::default(),
+            idempotency_ttl_secs: default_idempotency_ttl_secs(),
+            topic_policy: TopicPolicy::default(),
+            rate_limit_mode: RateLimitMode::default(),
+            retention: crate::config::RetentionConfig::default(),
+            anomaly_rules: Vec::new(),
+            replication: ReplicationConfig::default(),
+            integrity: IntegrityConfig::default(),
+        }
+    }
+}
+
+impl From<&crate::config::EventBusConfig> for ServiceConfig {
+    fn from(config: &crate::config::EventBusConfig) -> Self {
+        Self {
+            instance_id: config.id.clone(),
+            enable_rules: config.enable_rules,
+            allowed_sources: config.allowed_sources.clone(),
+            storage: config.storage.clone().unwrap_or_default(),
+            listen: Some(config.listen),
+            transport: config.transport.clone(),
+            retention: config.retention.clone(),
+            ..Default::default()
         }
     }
 }
@@ -151,9 +362,12 @@ pub struct ServiceMetrics {
     #[serde(skip)]
     error_count: AtomicU64,
     
-    /// Non-atomic fields for serialization
+    /// Times of the events recorded in the last second, oldest first
     #[serde(skip)]
-    events_last_second: parking_lot::RwLock<Vec<Instant>>,
+    events_last_second: parking_lot::RwLock<std::collections::VecDeque<Instant>>,
+    
+    /// Rule actions waiting for a worker, per priority - snapshot value for serialization
+    action_queue_depths: BTreeMap<Priority, usize>,
 }
 
 impl Default for ServiceMetrics {
@@ -164,7 +378,8 @@ impl Default for ServiceMetrics {
             active_subscriptions: AtomicU64::new(0),
             current_operations: AtomicU64::new(0),
             error_count: AtomicU64::new(0),
-            events_last_second: parking_lot::RwLock::new(Vec::new()),
+            events_last_second: parking_lot::RwLock::new(std::collections::VecDeque::new()),
+            action_queue_depths: BTreeMap::new(),
         }
     }
 }
@@ -177,15 +392,25 @@ impl ServiceMetrics {
         let now = Instant::now();
         let mut last_second = self.events_last_second.write();
         
-        // Remove events older than 1 second
-        last_second.retain(|&instant| now.duration_since(instant) < Duration::from_secs(1));
-        last_second.push(now);
+        // Remove events older than 1 second; entries are in time order, so
+        // only the front needs checking
+        while last_second.front().is_some_and(|&instant| now.duration_since(instant) >= Duration::from_secs(1)) {
+            last_second.pop_front();
+        }
+        last_second.push_back(now);
     }
     
     /// Get events per second
     fn get_events_per_second(&self) -> f64 {
+        self.rate_window().0 as f64
+    }
+    
+    /// Events recorded within the last second and the oldest of them
+    fn rate_window(&self) -> (usize, Option<Instant>) {
+        let now = Instant::now();
         let last_second = self.events_last_second.read();
-        last_second.len() as f64
+        let start = last_second.partition_point(|&instant| now.duration_since(instant) >= Duration::from_secs(1));
+        (last_second.len() - start, last_second.get(start).copied())
     }
     
     /// Record an error
@@ -227,31 +452,116 @@ impl ServiceMetrics {
     pub fn error_count(&self) -> u64 {
         self.error_count.load(Ordering::Relaxed)
     }
+    
+    /// Get the number of rule actions waiting for a worker, per priority
+    pub fn action_queue_depths(&self) -> &BTreeMap<Priority, usize> {
+        &self.action_queue_depths
+    }
 }
 
 impl EventBusService {
     /// Create a new event bus service
     pub fn new(config: ServiceConfig) -> Self {
         let (event_sender, _) = broadcast::channel(config.max_memory_events);
+        let memory_storage = Arc::new(match config.storage {
+            crate::config::StorageConfig::Memory { max_events } => MemoryStorage::with_limits(max_events),
+            _ => MemoryStorage::new(),
+        });
+        
+        let anomalies = AnomalyDetector::new();
+        let now = chrono::Utc::now().timestamp();
+        for rule in &config.anomaly_rules {
+            if let Err(e) = anomalies.register(rule.clone(), now) {
+                tracing::warn!("Skipping anomaly rule of bus {}: {}", config.instance_id, e);
+            }
+        }
+        
+        let replication_log = (!config.replication.topics.is_empty()).then(|| {
+            Arc::new(ReplicationLog::new(config.replication.topics.clone(), config.replication.log_capacity))
+        });
+        let follower = config.replication.leader.as_ref().map(|leader| {
+            let source = Arc::new(LeaderConnection::new(leader.clone()));
+            Arc::new(ReplicationFollower::new(source, &config.replication))
+        });
+        
+        let topics = dashmap::DashMap::new();
+        if let TopicPolicy::AllowList { topics: allowed } = &config.topic_policy {
+            let now = chrono::Utc::now().timestamp();
+            for name in allowed {
+                topics.insert(name.clone(), TopicInfo {
+                    name: name.clone(),
+                    settings: TopicSettings::default(),
+                    created_at: now,
+                    auto_created: false,
+                });
+            }
+        }
         
         Self {
             storage: None,
             rule_engine: None,
-            memory_storage: Arc::new(MemoryStorage::new()),
+            actions: None,
+            anomalies,
+            chains: HashChains::new(),
+            follower: parking_lot::RwLock::new(follower.clone()),
+            idempotency_storage: memory_storage.clone(),
+            idempotency_locks: dashmap::DashMap::new(),
+            topics,
+            upcasters: Arc::new(UpcasterRegistry::new()),
+            plugins: replication_log.iter().map(|log| log.clone() as Arc<dyn BusPlugin>).collect(),
+            replication_log,
+            work_queues: WorkQueues::new(),
+            publish_gate: tokio::sync::RwLock::new(()),
+            mode: parking_lot::RwLock::new(ModeStatus {
+                mode: if follower.is_some() { BusMode::ReadOnly } else { BusMode::Normal },
+                reason: follower.map(|_| FOLLOWER_MODE_REASON.to_string()),
+                since: chrono::Utc::now().timestamp(),
+            }),
+            last_recovery: parking_lot::Mutex::new(None),
+            memory_storage,
             emit_semaphore: Arc::new(Semaphore::new(config.max_concurrent_emits)),
             event_sender,
             metrics: ServiceMetrics::default(),
             config,
+            #[cfg(feature = "chaos")]
+            broadcast_faults: None,
         }
     }
     
     /// Create a new event bus service with async initialization
+    /// 
+    /// Unlike `new`, connects the persistent storage backend named by
+    /// `config.storage`, and keeps idempotency keys there when the backend
+    /// can store them.
     pub async fn with_config(config: ServiceConfig) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
-        Ok(Self::new(config))
+        let storage = if config.storage.is_persistent() {
+            Some(crate::storage::create_storage(&config.storage).await?)
+        } else {
+            None
+        };
+        let mut service = Self::new(config);
+        if let Some(storage) = storage {
+            service = service.with_storage(storage);
+        }
+        Ok(service)
     }
     
     /// Set the storage backend
+    /// 
+    /// Unless an idempotency store was set, emit idempotency keys go to the
+    /// backend as well when it can store them.
     pub fn with_storage(mut self, storage: Arc<dyn EventStorage>) -> Self {
+        // Still the in-memory default, not a store set with `with_idempotency_storage`
+        if std::ptr::addr_eq(Arc::as_ptr(&self.idempotency_storage), Arc::as_ptr(&self.memory_storage)) {
+            match storage.clone().idempotency_storage() {
+                Some(idempotency) => self.idempotency_storage = idempotency,
+                None if storage.backend_name() != "memory" => tracing::warn!(
+                    "Storage backend '{}' of bus {} keeps no idempotency keys; they stay in memory and are lost on restart",
+                    storage.backend_name(), self.config.instance_id
+                ),
+                None => {}
+            }
+        }
         self.storage = Some(storage);
         self
     }
@@ -263,18 +573,166 @@ impl EventBusService {
         self
     }
     
+    /// Set the invoker that executes the tool invocations produced by rules
+    /// 
+    /// Without one, rules match but their actions are dropped. Invocations
+    /// run on a pool of `max_concurrent_actions` workers, highest
+    /// `action_priority` first (see `actions`).
+    pub fn with_tool_invoker(mut self, invoker: Arc<dyn ToolInvoker>) -> Self {
+        self.actions = Some(ActionExecutor::new(invoker, self.config.max_concurrent_actions));
+        self
+    }
+    
+    /// Set the upcasters that bring polled and delivered events to the latest schema
+    pub fn with_upcasters(mut self, upcasters: UpcasterRegistry) -> Self {
+        self.upcasters = Arc::new(upcasters);
+        self
+    }
+    
+    /// Register a plugin hooking into the emit pipeline
+    /// 
+    /// Plugins run in the order they are registered.
+    pub fn with_plugin(mut self, plugin: Arc<dyn BusPlugin>) -> Self {
+        self.plugins.push(plugin);
+        self
+    }
+    
+    /// Set the storage for emit idempotency keys
+    /// 
+    /// Defaults to in-memory storage; use a persistent backend so retried
+    /// emits stay deduplicated across restarts.
+    pub fn with_idempotency_storage(mut self, storage: Arc<dyn IdempotencyStorage>) -> Self {
+        self.idempotency_storage = storage;
+        self
+    }
+    
+    /// Get the service configuration
+    pub fn config(&self) -> &ServiceConfig {
+        &self.config
+    }
+    
+    /// Current runtime mode
+    pub fn mode(&self) -> ModeStatus {
+        self.mode.read().clone()
+    }
+    
+    /// Switch the runtime mode, returning the new status
+    /// 
+    /// Emits already past the mode check complete normally.
+    pub fn set_mode(&self, mode: BusMode, reason: Option<String>) -> ModeStatus {
+        let status = ModeStatus {
+            mode,
+            reason,
+            since: chrono::Utc::now().timestamp(),
+        };
+        tracing::info!("Bus {} is now {} (reason: {:?})", self.config.instance_id, mode, status.reason);
+        *self.mode.write() = status.clone();
+        status
+    }
+    
+    /// Reject `operation` unless the bus accepts writes
+    pub fn check_writable(&self, operation: &str) -> EventBusResult<()> {
+        let status = self.mode.read();
+        match status.mode {
+            BusMode::Normal => Ok(()),
+            _ => Err(self.mode_error(&status, operation)),
+        }
+    }
+    
+    /// Reject `operation` when the bus is in maintenance
+    pub fn check_readable(&self, operation: &str) -> EventBusResult<()> {
+        let status = self.mode.read();
+        match status.mode {
+            BusMode::Maintenance => Err(self.mode_error(&status, operation)),
+            _ => Ok(()),
+        }
+    }
+    
+    fn mode_error(&self, status: &ModeStatus, operation: &str) -> EventBusError {
+        let reason = status.reason.as_deref().map(|r| format!(" ({})", r)).unwrap_or_default();
+        EventBusError::unavailable(format!(
+            "Bus {} is {}{}: {} rejected",
+            self.config.instance_id, status.mode, reason, operation
+        ))
+    }
+    
+    /// Disturb delivery to subscribers with the given fault hooks
+    #[cfg(feature = "chaos")]
+    pub fn with_broadcast_faults(mut self, faults: Arc<crate::chaos::BroadcastFaults>) -> Self {
+        self.broadcast_faults = Some(faults);
+        self
+    }
+    
+    /// Deliver an event to live subscribers and work queues
+    fn broadcast(&self, event: EventEnvelope) {
+        self.work_queues.offer(&event);
+        
+        #[cfg(feature = "chaos")]
+        if let Some(ref faults) = self.broadcast_faults {
+            faults.deliver(&self.event_sender, event);
+            return;
+        }
+        
+        let _ = self.event_sender.send(event);
+    }
+    
     /// Start the event bus service
     pub async fn start(&self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
         // Initialize storage if configured
         if let Some(storage) = &self.storage {
             storage.initialize().await?;
         }
+        self.recover().await?;
         Ok(())
     }
     
+    /// Check the primary storage for crash damage and rebuild the topic catalog
+    /// 
+    /// Runs on `start`; see the `recovery` module for the checks. Problems
+    /// are reported, not repaired.
+    pub async fn recover(&self) -> EventBusResult<RecoveryReport> {
+        let started = Instant::now();
+        let started_at = chrono::Utc::now().timestamp();
+        
+        let events = self.primary_storage().query(&EventQuery::new()).await?;
+        let mut report = recovery::inspect_events(&events);
+        report.started_at = started_at;
+        
+        let stored_topics: std::collections::BTreeSet<&str> =
+            events.iter().map(|event| event.topic.as_str()).collect();
+        for topic in stored_topics {
+            if !self.topics.contains_key(topic) {
+                self.register_topic(topic, TopicSettings::default(), true);
+                report.topics_rebuilt.push(topic.to_string());
+            }
+        }
+        report.duration_ms = started.elapsed().as_millis() as u64;
+        
+        if report.is_consistent() {
+            tracing::info!(
+                "Bus {} recovered: {} events scanned, {} topics rebuilt",
+                self.config.instance_id, report.events_scanned, report.topics_rebuilt.len()
+            );
+        } else {
+            tracing::warn!(
+                "Bus {} recovered with inconsistencies: {} events scanned, {} sequence gaps, {} duplicate sequences, {} partial batches",
+                self.config.instance_id, report.events_scanned, report.sequence_gaps.len(),
+                report.duplicate_sequences.len(), report.partial_batches.len()
+            );
+        }
+        
+        *self.last_recovery.lock() = Some(report.clone());
+        Ok(report)
+    }
+    
+    /// Report of the last startup recovery, if it ran
+    pub fn last_recovery(&self) -> Option<RecoveryReport> {
+        self.last_recovery.lock().clone()
+    }
+    
     /// Emit a single event (wrapper around handle_emit_event)
     pub async fn emit_event(&self, event: EventEnvelope) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        self.handle_emit_event(event).await.map(|_| ()).map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send + Sync>)
+        self.handle_emit_event(event, None).await.map(|_| ()).map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send + Sync>)
     }
     
     /// Get service metrics
@@ -298,10 +756,32 @@ impl EventBusService {
             active_subscriptions: AtomicU64::new(active_subscriptions),
             current_operations: AtomicU64::new(current_operations),
             error_count: AtomicU64::new(error_count),
-            events_last_second: parking_lot::RwLock::new(Vec::new()),
+            events_last_second: parking_lot::RwLock::new(std::collections::VecDeque::new()),
+            action_queue_depths: self.action_queue_depths(),
         })
     }
     
+    /// Rule actions waiting for a worker, per priority
+    pub fn action_queue_depths(&self) -> BTreeMap<Priority, usize> {
+        match &self.actions {
+            Some(actions) => actions.queue_depths(),
+            None => Priority::all().iter().map(|&priority| (priority, 0)).collect(),
+        }
+    }
+    
+    /// Queue the tool invocations produced by rules for an event
+    fn execute_actions(&self, invocations: Vec<ToolInvocation>) {
+        let Some(actions) = &self.actions else {
+            if !invocations.is_empty() {
+                tracing::debug!("No tool invoker set; dropping {} rule actions", invocations.len());
+            }
+            return;
+        };
+        for invocation in invocations {
+            actions.submit(invocation);
+        }
+    }
+    
     /// Check if source TRN is allowed
     fn is_source_allowed(&self, source_trn: Option<&String>) -> bool {
         // If no restrictions, allow all
@@ -325,290 +805,1916 @@ impl EventBusService {
         false
     }
     
-    /// Check rate limiting
-    async fn check_rate_limit(&self) -> EventBusResult<()> {
-        if let Some(max_eps) = self.config.max_events_per_second {
-            let current_eps = self.metrics.get_events_per_second();
-            if current_eps >= max_eps as f64 {
-                return Err(EventBusError::rate_limited(
-                    format!("Rate limit exceeded: {:.1} EPS", current_eps)
-                ));
+    /// Check a topic against the topic policy
+    /// 
+    /// Returns true when the topic is accepted but not registered yet and
+    /// should be auto-created once the event is stored.
+    fn check_topic(&self, topic: &str) -> EventBusResult<bool> {
+        if self.topics.contains_key(topic) {
+            return Ok(false);
+        }
+        
+        match &self.config.topic_policy {
+            TopicPolicy::Open => Ok(false),
+            TopicPolicy::AutoCreate => {
+                let normalized = normalize_topic(topic)
+                    .map_err(|e| EventBusError::topic_not_allowed(topic, e.to_string()))?;
+                if normalized != topic {
+                    return Err(EventBusError::topic_not_allowed(
+                        topic,
+                        format!("topic names must be normalized, use '{}'", normalized),
+                    ));
+                }
+                Ok(true)
+            }
+            TopicPolicy::AllowList { .. } => Err(EventBusError::topic_not_allowed(
+                topic,
+                "topic is not in the allow-list",
+            )),
+            TopicPolicy::Patterns { patterns } => {
+                if patterns.iter().any(|pattern| topic_matches_pattern(topic, pattern)) {
+                    Ok(true)
+                } else {
+                    Err(EventBusError::topic_not_allowed(
+                        topic,
+                        "topic does not match any allowed pattern",
+                    ))
+                }
             }
         }
-        Ok(())
     }
     
-    /// Emit multiple events in batch
-    pub async fn emit_batch(&self, events: Vec<EventEnvelope>) -> EventBusResult<()> {
-        // Check rate limiting for batch
-        self.check_rate_limit().await?;
+    /// Storage events are written to and read from: the persistent backend
+    /// when set, else the in-memory store
+    fn primary_storage(&self) -> &dyn EventStorage {
+        match self.storage {
+            Some(ref storage) => storage.as_ref(),
+            None => self.memory_storage.as_ref(),
+        }
+    }
+    
+    /// Add a topic to the registry unless it is already present
+    fn register_topic(&self, name: &str, settings: TopicSettings, auto_created: bool) -> TopicInfo {
+        self.topics
+            .entry(name.to_string())
+            .or_insert_with(|| TopicInfo {
+                name: name.to_string(),
+                settings,
+                created_at: chrono::Utc::now().timestamp(),
+                auto_created,
+            })
+            .clone()
+    }
+    
+    /// Pre-create a topic with settings
+    /// 
+    /// The name is normalized with `normalize_topic`. Pre-created topics are
+    /// accepted by every topic policy.
+    pub fn create_topic(&self, name: &str, settings: TopicSettings) -> EventBusResult<TopicInfo> {
+        self.check_writable("create_topic")?;
+        let name = normalize_topic(name)?;
+        if self.topics.contains_key(&name) {
+            return Err(EventBusError::already_exists(format!("topic '{}'", name)));
+        }
+        Ok(self.register_topic(&name, settings, false))
+    }
+    
+    /// Replace the settings of a registered topic
+    /// 
+    /// The name is normalized with `normalize_topic`.
+    pub fn update_topic(&self, name: &str, settings: TopicSettings) -> EventBusResult<TopicInfo> {
+        self.check_writable("update_topic")?;
+        let name = normalize_topic(name)?;
+        let mut topic = self.topics.get_mut(&name)
+            .ok_or_else(|| EventBusError::not_found(format!("topic '{}'", name)))?;
+        topic.settings = settings;
+        Ok(topic.clone())
+    }
+    
+    /// Remove a topic from the registry
+    /// 
+    /// Stored events are kept; under a restrictive policy new events for the
+    /// topic are rejected again.
+    pub fn delete_topic(&self, name: &str) -> EventBusResult<TopicInfo> {
+        self.check_writable("delete_topic")?;
+        self.topics.remove(name)
+            .map(|(_, topic)| topic)
+            .ok_or_else(|| EventBusError::not_found(format!("topic '{}'", name)))
+    }
+    
+    /// Get a registered topic
+    pub fn get_topic(&self, name: &str) -> Option<TopicInfo> {
+        self.topics.get(name).map(|topic| topic.clone())
+    }
+    
+    /// List registered topics, sorted by name
+    pub fn registered_topics(&self) -> Vec<TopicInfo> {
+        let mut topics: Vec<TopicInfo> = self.topics.iter().map(|topic| topic.clone()).collect();
+        topics.sort_by(|a, b| a.name.cmp(&b.name));
+        topics
+    }
+    
+    /// Consumer groups of this bus
+    pub fn work_queues(&self) -> &WorkQueues {
+        &self.work_queues
+    }
+    
+    /// Lease events from a consumer group, upcast to the latest schema
+    /// 
+    /// See `WorkQueues::fetch`. Acknowledge the deliveries through
+    /// `work_queues()`.
+    pub async fn fetch_work(&self, group: &str, consumer: &str, max: usize, wait: Duration) -> EventBusResult<Vec<Delivery>> {
+        self.check_readable("fetch_work")?;
+        let deliveries = self.work_queues.fetch(group, consumer, max, wait).await?;
+        deliveries
+            .into_iter()
+            .map(|delivery| {
+                let event = self.upcasters.upcast(delivery.event)?;
+                Ok(Delivery { event, ..delivery })
+            })
+            .collect()
+    }
+    
+    /// Take the latest stored events of a topic and subscribe to what follows
+    /// 
+    /// Up to `limit` matching events are returned newest first, as `poll`
+    /// returns them. No emit is in progress while the snapshot is taken, so
+    /// the live stream starts with the first event stored after it: nothing
+    /// is missed and nothing in the snapshot is delivered again.
+    pub async fn subscribe_with_snapshot(&self, topic: &str, limit: u32) -> EventBusResult<TopicSnapshot> {
+        let _gate = self.publish_gate.write().await;
+        let live = self.subscribe(topic).await?;
+        let events = self.poll(EventQuery::new().with_topic(topic).with_pagination(limit, 0)).await?;
+        Ok(TopicSnapshot { events, live })
+    }
+    
+    /// Describe a topic: its registration and the retention applied to it
+    /// 
+    /// Works for unregistered topics too, since retention overrides match by
+    /// pattern; `registered` is null for those.
+    pub fn describe_topic(&self, name: &str) -> serde_json::Value {
+        serde_json::json!({
+            "name": name,
+            "registered": self.get_topic(name),
+            "retention": self.config.retention.for_topic(name),
+        })
+    }
+    
+    /// Delete expired events and events older than the retention of their topic
+    /// 
+    /// Events past their `expires_at` are purged first, whatever their topic.
+    /// Each known topic is then cleaned up with its effective max age (see
+    /// `RetentionConfig::for_topic`); topics without a limit are skipped.
+    /// Returns the number of events removed.
+    pub async fn apply_retention(&self) -> EventBusResult<u64> {
+        self.check_writable("retention")?;
+        let now = chrono::Utc::now().timestamp();
         
-        // Acquire semaphore permits for batch
-        let _permits = self.emit_semaphore.acquire_many(events.len() as u32).await
-            .map_err(|_| EventBusError::internal("Failed to acquire semaphore permits"))?;
+        let storage = self.primary_storage();
+        let mut removed = storage.purge_expired(now).await?;
         
-        self.metrics.start_operation();
+        for topic in self.list_topics().await? {
+            let retention = self.config.retention.for_topic(&topic);
+            if retention.max_age_seconds == 0 {
+                continue;
+            }
+            let before = now.saturating_sub(retention.max_age_seconds as i64);
+            
+            removed += storage.cleanup_topic(&topic, before).await?;
+        }
         
-        let result = async {
-            // Validate all events first
-            for event in &events {
-                if !self.is_source_allowed(event.source_trn.as_ref()) {
-                    return Err(EventBusError::permission_denied(
-                        format!("Source TRN not allowed: {:?}", event.source_trn)
-                    ));
+        if removed > 0 {
+            tracing::debug!("Retention removed {} events from bus {}", removed, self.config.instance_id);
+        }
+        Ok(removed)
+    }
+    
+    /// Delete idempotency keys whose `idempotency_ttl_secs` has passed
+    /// 
+    /// Returns the number of keys removed.
+    pub async fn cleanup_idempotency_keys(&self) -> EventBusResult<u64> {
+        let now = chrono::Utc::now().timestamp();
+        let removed = self.idempotency_storage.cleanup_idempotency_records(now).await?;
+        if removed > 0 {
+            tracing::debug!("Removed {} expired idempotency keys from bus {}", removed, self.config.instance_id);
+        }
+        Ok(removed)
+    }
+    
+    /// Run `cleanup_idempotency_keys` and `apply_retention` every
+    /// `cleanup_interval_seconds` until shutdown
+    /// 
+    /// The task also stops once the service is dropped. Returns None when
+    /// the cleanup interval is 0.
+    pub fn spawn_retention_task(
+        self: &Arc<Self>,
+        mut shutdown: broadcast::Receiver<()>,
+    ) -> Option<tokio::task::JoinHandle<()>> {
+        let interval_secs = self.config.retention.cleanup_interval_seconds;
+        if interval_secs == 0 {
+            return None;
+        }
+        
+        let service = Arc::downgrade(self);
+        Some(tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(interval_secs));
+            loop {
+                tokio::select! {
+                    _ = interval.tick() => {}
+                    _ = shutdown.recv() => break,
+                }
+                let Some(service) = service.upgrade() else {
+                    break;
+                };
+                if let Err(e) = service.cleanup_idempotency_keys().await {
+                    tracing::warn!("Idempotency key cleanup failed for bus {}: {}", service.config.instance_id, e);
+                }
+                // Events are left untouched while the bus is read-only or in maintenance
+                if service.mode().mode != BusMode::Normal {
+                    continue;
+                }
+                if let Err(e) = service.apply_retention().await {
+                    tracing::warn!("Retention failed for bus {}: {}", service.config.instance_id, e);
                 }
             }
-            
-            // Store in persistent storage if available (batch operation)
-            if let Some(ref storage) = self.storage {
-                // TODO: Implement batch store method
-                for event in &events {
-                    storage.store(event).await?;
+        }))
+    }
+    
+    /// Check the stored events of a topic against their checksums and hash chain
+    /// 
+    /// `range` selects events by timestamp (`..` for the whole topic). Chain
+    /// positions are checked between the events found, so when producers set
+    /// timestamps out of order, events just outside the range can show up as
+    /// missing. With an open end, the events are also checked against the
+    /// chain head this bus last stored, which catches removals from the end.
+    pub async fn verify_topic_integrity(
+        &self,
+        topic: &str,
+        range: impl std::ops::RangeBounds<i64>,
+    ) -> EventBusResult<IntegrityReport> {
+        use std::ops::Bound;
+        
+        self.check_readable("verify_topic_integrity")?;
+        if topic.contains('*') {
+            return Err(EventBusError::validation(format!("Cannot verify a topic pattern: '{}'", topic)));
+        }
+        let since = match range.start_bound() {
+            Bound::Included(&since) => Some(since),
+            Bound::Excluded(&since) => Some(since + 1),
+            Bound::Unbounded => None,
+        };
+        let until = match range.end_bound() {
+            Bound::Included(&until) => Some(until + 1),
+            Bound::Excluded(&until) => Some(until),
+            Bound::Unbounded => None,
+        };
+        
+        let query = EventQuery::new().with_topic(topic).with_time_range(since, until);
+        let events = self.primary_storage().query(&query).await?;
+        let chained = self.config.integrity.is_chained(topic);
+        let mut report = integrity::inspect_topic(topic, &events, chained);
+        
+        if chained && until.is_none() {
+            if let Some(known) = self.chains.head(topic).await {
+                let found = report.head.as_ref().map_or(0, |head| head.position);
+                if found < known.position {
+                    report.missing.push(integrity::PositionGap { after: found, next: known.position + 1 });
+                } else if found == known.position && report.head.as_ref() != Some(&known) {
+                    let rewritten = events.iter().filter(|event| {
+                        event.integrity().and_then(|integrity| integrity.position) == Some(found)
+                    });
+                    report.broken_links.extend(rewritten.map(|event| event.event_id.clone()));
                 }
             }
-            
-            // Store in memory for real-time subscriptions
-            for event in &events {
-                self.memory_storage.store(event).await?;
-                
-                // Broadcast to subscribers
-                let _ = self.event_sender.send(event.clone());
-                
-                // Record metrics
-                self.metrics.record_event();
+        }
+        
+        if !report.is_intact() {
+            tracing::warn!(
+                "Integrity check of topic {} on bus {} failed: {} tampered, {} gaps, {} broken links",
+                topic, self.config.instance_id, report.tampered.len(), report.missing.len(), report.broken_links.len()
+            );
+        }
+        Ok(report)
+    }
+    
+    /// Add an event rate anomaly rule, replacing the one with the same ID
+    /// 
+    /// The rule's baseline starts empty (see `routing::anomaly`).
+    pub fn register_anomaly_rule(&self, rule: AnomalyRule) -> EventBusResult<()> {
+        self.anomalies.register(rule, chrono::Utc::now().timestamp())
+    }
+    
+    /// Remove an anomaly rule
+    pub fn remove_anomaly_rule(&self, rule_id: &str) -> EventBusResult<()> {
+        self.anomalies.remove(rule_id)
+    }
+    
+    /// Registered anomaly rules
+    pub fn anomaly_rules(&self) -> Vec<AnomalyRule> {
+        self.anomalies.rules()
+    }
+    
+    /// Evaluate the anomaly rules and emit what they found
+    /// 
+    /// Each anomaly is emitted as an `anomaly.spike` or `anomaly.silence`
+    /// event and returned. Anomalies whose event cannot be emitted are
+    /// logged and still returned.
+    pub async fn check_anomalies(&self) -> Vec<Anomaly> {
+        self.check_anomalies_at(chrono::Utc::now().timestamp()).await
+    }
+    
+    async fn check_anomalies_at(&self, now: i64) -> Vec<Anomaly> {
+        let anomalies = self.anomalies.evaluate(now);
+        for anomaly in &anomalies {
+            tracing::info!(
+                "Bus {} detected a {:?} on {} (rule {})",
+                self.config.instance_id, anomaly.kind, anomaly.topic, anomaly.rule_id
+            );
+            let result = match anomaly.to_event() {
+                Ok(event) => self.emit(event).await,
+                Err(e) => Err(e),
+            };
+            if let Err(e) = result {
+                tracing::warn!("Failed to emit anomaly of bus {}: {}", self.config.instance_id, e);
             }
-            
-            // Process rules if enabled
-            if self.config.enable_rules {
-                if let Some(ref rule_engine) = self.rule_engine {
-                    for event in &events {
-                        let _invocations = rule_engine.process_event(event).await?;
-                        // TODO: Execute tool invocations
-                    }
+        }
+        anomalies
+    }
+    
+    /// Run `check_anomalies` every second until shutdown
+    /// 
+    /// The task also stops once the service is dropped. Nothing is checked
+    /// while the bus is not in normal mode.
+    pub fn spawn_anomaly_task(
+        self: &Arc<Self>,
+        mut shutdown: broadcast::Receiver<()>,
+    ) -> tokio::task::JoinHandle<()> {
+        let service = Arc::downgrade(self);
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(1));
+            loop {
+                tokio::select! {
+                    _ = interval.tick() => {}
+                    _ = shutdown.recv() => break,
+                }
+                let Some(service) = service.upgrade() else {
+                    break;
+                };
+                if service.mode().mode != BusMode::Normal {
+                    continue;
                 }
+                service.check_anomalies().await;
             }
-            
-            Ok(())
-        }.await;
+        })
+    }
+    
+    /// Log of the replicated topics, if this bus keeps one
+    pub fn replication_log(&self) -> Option<&Arc<ReplicationLog>> {
+        self.replication_log.as_ref()
+    }
+    
+    /// Read the replication log after offset `after`, for followers
+    pub fn replication_fetch(&self, after: u64, limit: u32) -> EventBusResult<ReplicationBatch> {
+        let log = self.replication_log.as_ref().ok_or_else(|| {
+            EventBusError::unavailable(format!("Bus {} keeps no replication log", self.config.instance_id))
+        })?;
+        Ok(log.fetch(after, limit))
+    }
+    
+    /// Replicate the events of a leader, switching the bus to read-only
+    /// 
+    /// Events are appended by `sync_replication`, or by the task started
+    /// with `spawn_replication_task`, until the bus is promoted. Replaces the
+    /// leader followed so far: a leader with the same log epoch resumes from
+    /// the last applied offset, any other starts from its first logged event.
+    pub fn follow(&self, source: Arc<dyn ReplicationSource>) -> Arc<ReplicationFollower> {
+        let mut follower = ReplicationFollower::new(source, &self.config.replication);
+        if let Some(previous) = self.follower.read().as_ref() {
+            follower = follower.resume_from(previous);
+        }
+        let follower = Arc::new(follower);
+        *self.follower.write() = Some(follower.clone());
+        self.set_mode(BusMode::ReadOnly, Some(FOLLOWER_MODE_REASON.to_string()));
+        follower
+    }
+    
+    /// Fetch one batch from the leader and append it, returning the number
+    /// of events appended
+    pub async fn sync_replication(&self) -> EventBusResult<usize> {
+        self.follower()?.sync(self).await
+    }
+    
+    /// Replication role, position and lag, or None when the bus neither
+    /// keeps a replication log nor follows a leader
+    pub fn replication_status(&self) -> Option<ReplicationStatus> {
+        if let Some(follower) = self.follower.read().as_ref() {
+            return Some(follower.status());
+        }
+        self.replication_log.as_deref().map(ReplicationStatus::leader)
+    }
+    
+    /// Stop following the leader and accept writes, for disaster recovery
+    /// 
+    /// A last sync catches up with the leader first; when it fails (the
+    /// leader is down, typically) the bus is promoted with what it has.
+    /// Promoting a promoted bus only returns its status. Promotion is refused
+    /// in maintenance, and only lifts the read-only mode `follow` set: a mode
+    /// set by an operator since then stays in effect.
+    pub async fn promote(&self) -> EventBusResult<ReplicationStatus> {
+        let follower = self.follower()?;
+        if follower.status().role == ReplicationRole::Promoted {
+            return Ok(follower.status());
+        }
+        self.check_readable("promote")?;
+        if let Err(e) = follower.sync(self).await {
+            tracing::warn!("Bus {} promoted without catching up with its leader: {}", self.config.instance_id, e);
+        }
+        let status = follower.promote();
+        // Replicated events extended the chains behind the cached heads
+        self.chains.reset();
+        let mode = self.mode();
+        if mode.mode == BusMode::ReadOnly && mode.reason.as_deref() == Some(FOLLOWER_MODE_REASON) {
+            self.set_mode(BusMode::Normal, Some("promoted from replication follower".to_string()));
+        }
+        Ok(status)
+    }
+    
+    fn follower(&self) -> EventBusResult<Arc<ReplicationFollower>> {
+        self.follower.read().clone().ok_or_else(|| {
+            EventBusError::validation(format!("Bus {} is not a replication follower", self.config.instance_id))
+        })
+    }
+    
+    /// Append an event replicated from a leader
+    /// 
+    /// The mode check, plugins, topic policy and rules are skipped: they ran
+    /// on the leader. Events applied before are skipped by the follower,
+    /// which tracks the leader offsets it applied. That position is not
+    /// persisted: a restarted follower fetches the log from the start again,
+    /// and storage ignores the events whose ID it already holds.
+    pub(crate) async fn apply_replicated(&self, event: EventEnvelope) -> EventBusResult<()> {
+        let gate = self.publish_gate.read().await;
+        self.primary_storage().store(&event).await?;
+        self.broadcast(event.clone());
+        drop(gate);
+        
+        self.metrics.record_event();
+        self.anomalies.observe(&event, chrono::Utc::now().timestamp());
+        self.register_topic(&event.topic, TopicSettings::default(), true);
+        Ok(())
+    }
+    
+    /// Sync with the leader every `poll_interval_ms` until shutdown or promotion
+    /// 
+    /// While the leader is ahead, batches are fetched back to back. The task
+    /// also stops once the service is dropped. Returns None when the bus
+    /// follows no leader.
+    pub fn spawn_replication_task(
+        self: &Arc<Self>,
+        mut shutdown: broadcast::Receiver<()>,
+    ) -> Option<tokio::task::JoinHandle<()>> {
+        let follower = self.follower.read().clone()?;
+        let service = Arc::downgrade(self);
+        Some(tokio::spawn(async move {
+            let mut interval = tokio::time::interval(follower.poll_interval());
+            loop {
+                tokio::select! {
+                    _ = interval.tick() => {}
+                    _ = shutdown.recv() => break,
+                }
+                let Some(service) = service.upgrade() else {
+                    break;
+                };
+                if follower.status().role == ReplicationRole::Promoted {
+                    break;
+                }
+                loop {
+                    let before = follower.status().position;
+                    if let Err(e) = follower.sync(&service).await {
+                        tracing::warn!("Replication failed for bus {}: {}", service.config.instance_id, e);
+                        break;
+                    }
+                    let status = follower.status();
+                    if status.lag_events == 0 || status.position == before {
+                        break;
+                    }
+                }
+            }
+        }))
+    }
+    
+    /// Run the `before_emit` hooks, stopping at the first plugin rejecting the event
+    async fn run_before_emit(&self, event: &mut EventEnvelope) -> EventBusResult<()> {
+        for plugin in &self.plugins {
+            plugin.before_emit(event).await.map_err(|e| {
+                tracing::debug!("Plugin '{}' rejected event {}: {}", plugin.name(), event.event_id, e);
+                e
+            })?;
+        }
+        Ok(())
+    }
+    
+    /// Run the `after_store` hooks of every plugin
+    async fn run_after_store(&self, event: &EventEnvelope) {
+        for plugin in &self.plugins {
+            plugin.after_store(event).await;
+        }
+    }
+    
+    /// Run the `after_broadcast` hooks of every plugin
+    async fn run_after_broadcast(&self, event: &EventEnvelope) {
+        for plugin in &self.plugins {
+            plugin.after_broadcast(event).await;
+        }
+    }
+    
+    /// Check rate limiting for `count` events
+    async fn check_rate_limit(&self, count: usize) -> EventBusResult<()> {
+        let max_wait = match self.config.rate_limit_mode {
+            RateLimitMode::Reject => Duration::ZERO,
+            RateLimitMode::Queue { max_wait_ms } => Duration::from_millis(max_wait_ms),
+        };
+        self.wait_for_slots(count, max_wait).await.map(|_| ())
+    }
+    
+    /// Current rate limit state, or `None` when no limit is configured
+    pub fn rate_limit_status(&self) -> Option<RateLimitStatus> {
+        let limit = self.config.max_events_per_second?;
+        let (count, oldest) = self.metrics.rate_window();
+        let remaining = limit.saturating_sub(count as u32);
+        
+        let reset_after_ms = match oldest {
+            Some(oldest) if remaining == 0 => Duration::from_secs(1)
+                .saturating_sub(oldest.elapsed())
+                .as_millis() as u64,
+            _ => 0,
+        };
+        
+        Some(RateLimitStatus { limit, remaining, reset_after_ms })
+    }
+    
+    /// Wait up to `max_wait` for rate limit capacity
+    /// 
+    /// Lets well-behaved producers queue behind the limit instead of being
+    /// rejected. Fails with a rate limited error once the wait would exceed
+    /// `max_wait`; a zero wait checks capacity once.
+    pub async fn wait_for_capacity(&self, max_wait: Duration) -> EventBusResult<Option<RateLimitStatus>> {
+        self.wait_for_slots(1, max_wait).await
+    }
+    
+    /// Wait up to `max_wait` until `count` events fit in the rate limit
+    /// 
+    /// Each event of a batch takes a slot of its own; a batch larger than
+    /// the limit never fits.
+    async fn wait_for_slots(&self, count: usize, max_wait: Duration) -> EventBusResult<Option<RateLimitStatus>> {
+        let deadline = Instant::now() + max_wait;
+        
+        loop {
+            let status = match self.rate_limit_status() {
+                Some(status) => status,
+                None => return Ok(None),
+            };
+            if status.remaining as usize >= count.max(1) {
+                return Ok(Some(status));
+            }
+            if count > status.limit as usize {
+                return Err(EventBusError::rate_limited(format!(
+                    "Rate limit exceeded: a batch of {} events is larger than the limit of {} events per second",
+                    count, status.limit
+                )));
+            }
+            
+            // The oldest slot frees up at the latest one window from now
+            let reset_after = self.metrics.rate_window().1
+                .map_or(Duration::ZERO, |oldest| Duration::from_secs(1).saturating_sub(oldest.elapsed()))
+                .max(Duration::from_millis(1));
+            if Instant::now() + reset_after > deadline {
+                return Err(EventBusError::rate_limited(format!(
+                    "Rate limit exceeded: {} events per second, retry after {} ms",
+                    status.limit, reset_after.as_millis()
+                )));
+            }
+            tokio::time::sleep(reset_after).await;
+        }
+    }
+    
+    /// Emit multiple events in batch
+    pub async fn emit_batch(&self, mut events: Vec<EventEnvelope>) -> EventBusResult<()> {
+        self.check_writable("emit_batch")?;
+        
+        for event in &mut events {
+            self.run_before_emit(event).await?;
+        }
+        
+        // Tag multi-event batches so startup recovery can detect partial writes
+        if events.len() > 1 {
+            let batch_id = uuid::Uuid::new_v4().to_string();
+            let size = events.len() as u32;
+            events = events
+                .into_iter()
+                .enumerate()
+                .map(|(index, event)| event.with_batch(&BatchMetadata {
+                    id: batch_id.clone(),
+                    index: index as u32,
+                    size,
+                }))
+                .collect();
+        }
+        
+        // Check rate limiting for batch, one slot per event
+        self.check_rate_limit(events.len()).await?;
+        
+        // Acquire semaphore permits for batch
+        let _permits = self.emit_semaphore.acquire_many(events.len() as u32).await
+            .map_err(|_| EventBusError::internal("Failed to acquire semaphore permits"))?;
+        
+        self.metrics.start_operation();
+        
+        let result = async {
+            // Validate all events first
+            let mut new_topics = Vec::new();
+            for event in &events {
+                if !self.is_source_allowed(event.source_trn.as_ref()) {
+                    return Err(EventBusError::permission_denied(
+                        format!("Source TRN not allowed: {:?}", event.source_trn)
+                    ));
+                }
+                if self.check_topic(&event.topic)? {
+                    new_topics.push(event.topic.clone());
+                }
+                integrity::check_expiry(&self.config.integrity, event)?;
+            }
+            
+            // Checksums are stamped last so they cover what plugins changed;
+            // chains stay locked until their events are stored
+            let storage = self.primary_storage();
+            let mut chains = self.chains
+                .lock(&self.config.integrity, events.iter().map(|event| event.topic.as_str()), storage)
+                .await?;
+            let events: Vec<EventEnvelope> = events.into_iter().map(|event| chains.seal(event)).collect();
+            
+            let gate = self.publish_gate.read().await;
+            
+            if let Err(e) = storage.store_batch(&events).await {
+                // Part of the batch may be stored; chains reload their heads from storage
+                drop(chains);
+                self.chains.reset();
+                return Err(e);
+            }
+            for event in &events {
+                chains.record(event);
+            }
+            drop(chains);
+            
+            let now = chrono::Utc::now().timestamp();
+            for event in &events {
+                self.run_after_store(event).await;
+                
+                // Broadcast to subscribers
+                self.broadcast(event.clone());
+                self.run_after_broadcast(event).await;
+                
+                // Record metrics
+                self.metrics.record_event();
+                self.anomalies.observe(event, now);
+            }
+            drop(gate);
+            
+            for topic in new_topics {
+                self.register_topic(&topic, TopicSettings::default(), true);
+            }
+            
+            // Process rules if enabled
+            if self.config.enable_rules {
+                if let Some(ref rule_engine) = self.rule_engine {
+                    for event in &events {
+                        let invocations = rule_engine.process_event(event).await?;
+                        self.execute_actions(invocations);
+                    }
+                }
+            }
+            
+            Ok(())
+        }.await;
+        
+        self.metrics.end_operation();
+        
+        if result.is_err() {
+            self.metrics.record_error();
+        }
+        
+        result
+    }
+    
+    /// Graceful shutdown
+    pub async fn shutdown(&self) -> EventBusResult<()> {
+        // Wait for ongoing operations to complete
+        let start = Instant::now();
+        while self.metrics.current_operations.load(Ordering::Relaxed) > 0 {
+            if start.elapsed() > self.config.shutdown_grace_period {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        
+        // Close broadcast channel
+        // Note: broadcast channels don't have explicit close
+        
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl EventBus for EventBusService {
+    async fn emit(&self, mut event: EventEnvelope) -> EventBusResult<()> {
+        self.check_writable("emit")?;
+        self.run_before_emit(&mut event).await?;
+        
+        // Validate source TRN
+        if !self.is_source_allowed(event.source_trn.as_ref()) {
+            return Err(EventBusError::permission_denied(
+                format!("Source TRN not allowed: {:?}", event.source_trn)
+            ));
+        }
+        
+        // Validate topic against the topic policy
+        let new_topic = self.check_topic(&event.topic)?;
+        integrity::check_expiry(&self.config.integrity, &event)?;
+        
+        // Check rate limiting for single emit
+        self.check_rate_limit(1).await?;
+        
+        // Acquire semaphore permit for single emit
+        let _permit = self.emit_semaphore.acquire().await
+            .map_err(|_| EventBusError::internal("Failed to acquire semaphore permit"))?;
+        
+        self.metrics.start_operation();
+        
+        let result = async {
+            // Checksums are stamped last so they cover what plugins changed;
+            // the topic's chain stays locked until the event is stored
+            let storage = self.primary_storage();
+            let mut chains = self.chains.lock(&self.config.integrity, [event.topic.as_str()], storage).await?;
+            let event = chains.seal(event);
+            
+            let gate = self.publish_gate.read().await;
+            
+            // Store once; subscribers are fed from the broadcast below
+            storage.store(&event).await?;
+            chains.record(&event);
+            drop(chains);
+            self.run_after_store(&event).await;
+            
+            // Broadcast to subscribers
+            self.broadcast(event.clone());
+            drop(gate);
+            self.run_after_broadcast(&event).await;
+            
+            // Record metrics
+            self.metrics.record_event();
+            self.anomalies.observe(&event, chrono::Utc::now().timestamp());
+            
+            if new_topic {
+                self.register_topic(&event.topic, TopicSettings::default(), true);
+            }
+            
+            // Process rules if enabled
+            if self.config.enable_rules {
+                if let Some(ref rule_engine) = self.rule_engine {
+                    let invocations = rule_engine.process_event(&event).await?;
+                    self.execute_actions(invocations);
+                }
+            }
+            
+            Ok(())
+        }.await;
+        
+        self.metrics.end_operation();
+        
+        if result.is_err() {
+            self.metrics.record_error();
+        }
+        
+        result
+    }
+    
+    async fn poll(&self, query: EventQuery) -> EventBusResult<Vec<EventEnvelope>> {
+        self.check_readable("poll")?;
+        
+        let events = self.primary_storage().query(&query).await?;
+        if self.config.integrity.verify_on_read {
+            for event in &events {
+                integrity::verify(event)?;
+            }
+        }
+        
+        self.upcasters.upcast_all(events)
+    }
+    
+    async fn subscribe(&self, topic: &str) -> EventBusResult<std::pin::Pin<Box<dyn futures::Stream<Item = EventEnvelope> + Send>>> {
+        use futures::stream::StreamExt;
+        use tokio_stream::wrappers::BroadcastStream;
+        
+        self.check_readable("subscribe")?;
+        let receiver = self.event_sender.subscribe();
+        let topic_filter = topic.to_string();
+        
+        // Increment subscription counter
+        self.metrics.active_subscriptions.fetch_add(1, Ordering::Relaxed);
+        
+        let upcasters = Arc::clone(&self.upcasters);
+        
+        let stream = BroadcastStream::new(receiver)
+            .filter_map(move |result| {
+                let topic_filter = topic_filter.clone();
+                let upcasters = Arc::clone(&upcasters);
+                async move {
+                    match result {
+                        Ok(event) => {
+                            // Filter by topic (support wildcards)
+                            if topic_filter == "*" || event.topic == topic_filter || 
+                               (topic_filter.ends_with('*') && 
+                                event.topic.starts_with(topic_filter.trim_end_matches('*'))) {
+                                // Deliver only the latest schema; skip events that cannot be upcast
+                                match upcasters.upcast(event) {
+                                    Ok(event) => Some(event),
+                                    Err(e) => {
+                                        tracing::warn!("Dropping event that failed to upcast: {}", e);
+                                        None
+                                    }
+                                }
+                            } else {
+                                None
+                            }
+                        }
+                        Err(_) => None, // Skip broadcast errors
+                    }
+                }
+            });
+        
+        Ok(Box::pin(stream))
+    }
+    
+    async fn list_topics(&self) -> EventBusResult<Vec<String>> {
+        self.check_readable("list_topics")?;
+        
+        // Query all events to extract topics
+        let query = EventQuery::new();
+        let events = self.primary_storage().query(&query).await?;
+        
+        let mut topics: Vec<String> = events
+            .into_iter()
+            .map(|e| e.topic)
+            .collect();
+        
+        // Include registered topics that have no events yet
+        topics.extend(self.topics.iter().map(|topic| topic.key().clone()));
+        
+        topics.sort();
+        topics.dedup();
+        
+        Ok(topics)
+    }
+    
+    async fn get_stats(&self) -> EventBusResult<crate::core::traits::BusStats> {
+        let storage_stats = self.primary_storage().get_stats().await?;
+        
+        Ok(crate::core::traits::BusStats {
+            events_processed: self.metrics.events_processed.load(Ordering::Relaxed),
+            active_subscriptions: self.metrics.active_subscriptions.load(Ordering::Relaxed) as u32,
+            topic_count: storage_stats.topics_count,
+            events_per_second: self.metrics.get_events_per_second(),
+        })
+    }
+}
+
+/// JSON-RPC method implementations
+impl EventBusService {
+    /// Handle emit_event method
+    /// 
+    /// When an idempotency key is given, repeating the request with the same
+    /// key within `idempotency_ttl_secs` returns the original response instead
+    /// of emitting a duplicate event. Failed emits are not recorded.
+    pub async fn handle_emit_event(
+        &self,
+        event: EventEnvelope,
+        idempotency_key: Option<&str>,
+    ) -> EventBusResult<serde_json::Value> {
+        let Some(key) = idempotency_key else {
+            let event_id = event.event_id.clone();
+            self.emit(event).await?;
+            return Ok(serde_json::json!({"status": "success", "event_id": event_id}));
+        };
+        
+        let key = format!("{}:{}", self.config.instance_id, key);
+        let lock = self.idempotency_locks.entry(key.clone()).or_default().clone();
+        
+        let result = async {
+            let _guard = lock.lock().await;
+            
+            let now = chrono::Utc::now().timestamp();
+            if let Some(record) = self.idempotency_storage.get_idempotency_record(&key, now).await? {
+                return Ok(record.response);
+            }
+            
+            let event_id = event.event_id.clone();
+            self.emit(event).await?;
+            let response = serde_json::json!({"status": "success", "event_id": event_id});
+            
+            let record = IdempotencyRecord {
+                key: key.clone(),
+                event_id,
+                response: response.clone(),
+                created_at: now,
+                expires_at: now + self.config.idempotency_ttl_secs as i64,
+            };
+            // The event is already emitted; failing here would invite a duplicate retry
+            if let Err(e) = self.idempotency_storage.put_idempotency_record(&record).await {
+                tracing::warn!("Failed to record idempotency key {}: {}", key, e);
+            }
+            
+            Ok(response)
+        }.await;
+        
+        // Drop the lock entry unless another request is waiting on it
+        self.idempotency_locks.remove_if(&key, |_, entry| Arc::strong_count(entry) <= 2);
+        
+        result
+    }
+    
+    /// Handle poll_events method
+    pub async fn handle_poll_events(&self, query: EventQuery) -> EventBusResult<Vec<EventEnvelope>> {
+        self.poll(query).await
+    }
+    
+    /// Handle register_rule method
+    pub async fn handle_register_rule(&self, rule: EventTriggerRule) -> EventBusResult<serde_json::Value> {
+        self.check_writable("register_rule")?;
+        if let Some(ref rule_engine) = self.rule_engine {
+            rule_engine.register_rule(rule).await?;
+            Ok(serde_json::json!({"status": "success"}))
+        } else {
+            Err(EventBusError::configuration("Rule engine not enabled"))
+        }
+    }
+    
+    /// Handle list_topics method
+    pub async fn handle_list_topics(&self) -> EventBusResult<Vec<String>> {
+        self.list_topics().await
+    }
+    
+    /// Handle get_stats method (for monitoring)
+    pub async fn handle_get_stats(&self) -> EventBusResult<serde_json::Value> {
+        let stats = self.get_stats().await?;
+        Ok(serde_json::json!({
+            "events_processed": stats.events_processed,
+            "active_subscriptions": stats.active_subscriptions,
+            "topic_count": stats.topic_count,
+            "events_per_second": stats.events_per_second
+        }))
+    }
+    
+    /// Describe this bus for topology introspection
+    /// 
+    /// Reports the storage backend, topic policy, forwarding rules, health
+    /// (see `health`) and the last startup recovery.
+    pub async fn describe(&self) -> serde_json::Value {
+        let storage = serde_json::json!({
+            "backend": self.primary_storage().backend_name(),
+            "persistent": self.storage.is_some(),
+        });
+        
+        let mut forwarding_rules = Vec::new();
+        if let Some(ref rule_engine) = self.rule_engine {
+            match rule_engine.list_rules().await {
+                Ok(rules) => {
+                    for rule in rules {
+                        for (target_topic, transform) in forward_targets(&rule.action) {
+                            forwarding_rules.push(serde_json::json!({
+                                "rule_id": rule.id,
+                                "topic": rule.topic,
+                                "target_topic": target_topic,
+                                "transformed": transform.is_some(),
+                                "enabled": rule.enabled,
+                            }));
+                        }
+                    }
+                }
+                Err(e) => tracing::warn!("Failed to list rules for bus {}: {}", self.config.instance_id, e),
+            }
+        }
+        
+        serde_json::json!({
+            "instance_id": self.config.instance_id,
+            "storage": storage,
+            "topic_policy": self.config.topic_policy,
+            "rules_enabled": self.config.enable_rules,
+            "forwarding_rules": forwarding_rules,
+            "anomaly_rules": self.anomaly_rules(),
+            "health": self.health().await,
+            "recovery": self.last_recovery(),
+        })
+    }
+    
+    /// Report bus health; available in every mode
+    /// 
+    /// A bus whose storage cannot report statistics is unhealthy.
+    pub async fn health(&self) -> serde_json::Value {
+        let (status, error) = match self.primary_storage().get_stats().await {
+            Ok(_) => ("healthy", None),
+            Err(e) => ("unhealthy", Some(e.to_string())),
+        };
+        
+        serde_json::json!({
+            "status": status,
+            "error": error,
+            "mode": self.mode(),
+            "events_processed": self.metrics.events_processed(),
+            "events_per_second": self.metrics.get_events_per_second(),
+            "active_subscriptions": self.metrics.active_subscriptions(),
+            "error_count": self.metrics.error_count(),
+            "action_queue_depths": self.action_queue_depths(),
+            "replication": self.replication_status(),
+        })
+    }
+}
+
+/// Forward targets of a rule action, including those nested in sequences
+fn forward_targets(action: &crate::core::RuleAction) -> Vec<(&str, Option<&serde_json::Value>)> {
+    use crate::core::RuleAction;
+    
+    match action {
+        RuleAction::Forward { target_topic, transform } => vec![(target_topic.as_str(), transform.as_ref())],
+        RuleAction::Sequence { actions } => actions.iter().flat_map(forward_targets).collect(),
+        _ => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    
+    #[tokio::test]
+    async fn test_event_bus_service_basic() {
+        let config = ServiceConfig::default();
+        let service = EventBusService::new(config);
+        
+        // Test emitting an event
+        let event = EventEnvelope::new("test.topic", json!({"message": "hello"}));
+        assert!(service.emit(event).await.is_ok());
+        
+        // Test polling events
+        let query = EventQuery::new().with_topic("test.topic");
+        let events = service.poll(query).await.unwrap();
+        assert_eq!(events.len(), 1);
+        
+        // Test listing topics
+        let topics = service.list_topics().await.unwrap();
+        assert!(topics.contains(&"test.topic".to_string()));
+    }
+    
+    #[tokio::test]
+    async fn test_emit_idempotency_key() {
+        let service = EventBusService::new(ServiceConfig::default());
+        
+        let first = EventEnvelope::new("orders.created", json!({"id": 1}));
+        let original = service.handle_emit_event(first.clone(), Some("order-1")).await.unwrap();
+        assert_eq!(original["event_id"], first.event_id);
+        
+        // A retry with a fresh envelope returns the original response
+        let retry = EventEnvelope::new("orders.created", json!({"id": 1}));
+        let replayed = service.handle_emit_event(retry, Some("order-1")).await.unwrap();
+        assert_eq!(replayed, original);
+        
+        let other = EventEnvelope::new("orders.created", json!({"id": 2}));
+        service.handle_emit_event(other, Some("order-2")).await.unwrap();
+        
+        let events = service.poll(EventQuery::new().with_topic("orders.created")).await.unwrap();
+        assert_eq!(events.len(), 2);
+    }
+    
+    #[tokio::test]
+    async fn test_emit_idempotency_key_expires() {
+        let config = ServiceConfig {
+            idempotency_ttl_secs: 0,
+            ..Default::default()
+        };
+        let service = EventBusService::new(config);
+        
+        for _ in 0..2 {
+            let event = EventEnvelope::new("orders.created", json!({}));
+            service.handle_emit_event(event, Some("order-1")).await.unwrap();
+        }
+        
+        let events = service.poll(EventQuery::new().with_topic("orders.created")).await.unwrap();
+        assert_eq!(events.len(), 2);
+        
+        // The periodic cleanup drops the expired key
+        assert_eq!(service.cleanup_idempotency_keys().await.unwrap(), 1);
+        assert_eq!(service.cleanup_idempotency_keys().await.unwrap(), 0);
+    }
+    
+    #[tokio::test]
+    async fn test_emit_idempotency_key_survives_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = format!("sqlite://{}", dir.path().join("events.db").display());
+        let config = ServiceConfig {
+            storage: crate::config::StorageConfig::Sqlite { path: path.clone() },
+            ..Default::default()
+        };
+        
+        let service = EventBusService::with_config(config.clone()).await.unwrap();
+        let first = EventEnvelope::new("orders.created", json!({"id": 1}));
+        let original = service.handle_emit_event(first, Some("order-1")).await.unwrap();
+        drop(service);
+        
+        // The key is kept in the database, not in the restarted service's memory
+        let restarted = EventBusService::with_config(config).await.unwrap();
+        let retry = EventEnvelope::new("orders.created", json!({"id": 1}));
+        let replayed = restarted.handle_emit_event(retry, Some("order-1")).await.unwrap();
+        assert_eq!(replayed, original);
+        
+        let events = restarted.poll(EventQuery::new()).await.unwrap();
+        assert_eq!(events.len(), 1);
+        drop(restarted);
+        
+        // A backend passed to `with_storage` keeps the keys as well
+        let storage = Arc::new(crate::storage::SqliteStorage::new(&path).await.unwrap());
+        let rebuilt = EventBusService::new(ServiceConfig::default()).with_storage(storage);
+        let retry = EventEnvelope::new("orders.created", json!({"id": 1}));
+        assert_eq!(rebuilt.handle_emit_event(retry, Some("order-1")).await.unwrap(), original);
+    }
+    
+    #[tokio::test]
+    async fn test_topic_policy_allow_list() {
+        let config = ServiceConfig {
+            topic_policy: TopicPolicy::AllowList { topics: vec!["orders.created".to_string()] },
+            ..Default::default()
+        };
+        let service = EventBusService::new(config);
+        
+        assert!(service.emit(EventEnvelope::new("orders.created", json!({}))).await.is_ok());
+        let err = service.emit(EventEnvelope::new("orders.deleted", json!({}))).await.unwrap_err();
+        assert_eq!(err.category(), "topic_not_allowed");
+        
+        // Pre-created topics are accepted and listed before any event
+        service.create_topic("Orders.Deleted", TopicSettings::default()).unwrap();
+        assert!(service.create_topic("orders.deleted", TopicSettings::default()).is_err());
+        assert!(service.update_topic("Orders.Deleted", TopicSettings::default()).is_ok());
+        assert!(service.list_topics().await.unwrap().contains(&"orders.deleted".to_string()));
+        assert!(service.emit(EventEnvelope::new("orders.deleted", json!({}))).await.is_ok());
+    }
+    
+    #[tokio::test]
+    async fn test_topic_policy_auto_create_and_patterns() {
+        let service = EventBusService::new(ServiceConfig {
+            topic_policy: TopicPolicy::AutoCreate,
+            ..Default::default()
+        });
+        assert!(service.emit(EventEnvelope::new("users.created", json!({}))).await.is_ok());
+        assert!(service.get_topic("users.created").unwrap().auto_created);
+        assert!(service.emit(EventEnvelope::new("Users Created", json!({}))).await.is_err());
+        
+        let service = EventBusService::new(ServiceConfig {
+            topic_policy: TopicPolicy::Patterns { patterns: vec!["workflow.*".to_string()] },
+            ..Default::default()
+        });
+        assert!(service.emit(EventEnvelope::new("workflow.started", json!({}))).await.is_ok());
+        assert!(service.emit(EventEnvelope::new("billing.charged", json!({}))).await.is_err());
+        
+        let batch = vec![
+            EventEnvelope::new("workflow.finished", json!({})),
+            EventEnvelope::new("billing.charged", json!({})),
+        ];
+        assert!(service.emit_batch(batch).await.is_err());
+        assert!(service.get_topic("workflow.finished").is_none());
+    }
+    
+    #[tokio::test]
+    async fn test_upcasters_apply_on_poll_and_subscribe() {
+        use futures::StreamExt;
+        
+        let mut upcasters = UpcasterRegistry::new();
+        upcasters.register_fn("user.created", 1, |payload| {
+            Ok(json!({ "full_name": payload["name"] }))
+        }).unwrap();
+        let service = EventBusService::new(ServiceConfig::default()).with_upcasters(upcasters);
+        let mut stream = service.subscribe("user.created").await.unwrap();
+        
+        service.emit(EventEnvelope::new("user.created", json!({"name": "Ada"}))).await.unwrap();
+        
+        let delivered = stream.next().await.unwrap();
+        assert_eq!(delivered.payload, json!({"full_name": "Ada"}));
+        assert_eq!(delivered.schema_version(), 2);
+        
+        let polled = service.poll(EventQuery::new().with_topic("user.created")).await.unwrap();
+        assert_eq!(polled[0].payload, json!({"full_name": "Ada"}));
+    }
+    
+    #[tokio::test]
+    async fn test_subscribe_with_snapshot_has_no_gap_or_duplicate() {
+        use futures::StreamExt;
+        
+        let service = Arc::new(EventBusService::new(ServiceConfig::default()));
+        let emitters: Vec<_> = (0..4)
+            .map(|task| {
+                let service = Arc::clone(&service);
+                tokio::spawn(async move {
+                    for n in 0..50 {
+                        service.emit(EventEnvelope::new("feed.item", json!({"task": task, "n": n}))).await.unwrap();
+                        tokio::task::yield_now().await;
+                    }
+                })
+            })
+            .collect();
+        
+        tokio::time::sleep(Duration::from_millis(1)).await;
+        let mut snapshot = service.subscribe_with_snapshot("feed.*", 1000).await.unwrap();
+        for emitter in emitters {
+            emitter.await.unwrap();
+        }
+        
+        // Every event is either in the snapshot or on the live stream, once
+        let mut seen: std::collections::HashSet<String> =
+            snapshot.events.iter().map(|e| e.event_id.clone()).collect();
+        assert_eq!(seen.len(), snapshot.events.len());
+        while seen.len() < 200 {
+            let event = tokio::time::timeout(Duration::from_secs(1), snapshot.live.next())
+                .await
+                .expect("live stream missed an event")
+                .unwrap();
+            assert!(seen.insert(event.event_id), "event delivered twice");
+        }
+        
+        let limited = service.subscribe_with_snapshot("feed.*", 5).await.unwrap();
+        assert_eq!(limited.events.len(), 5);
+    }
+    
+    #[tokio::test]
+    async fn test_subscribe_trn_filters_by_source_and_target() {
+        use futures::StreamExt;
+        
+        let service = EventBusService::new(ServiceConfig::default());
+        let mut stream = service.subscribe_trn("trn:org:acme:*:*:*").await.unwrap();
+        assert!(service.subscribe_trn("trn:org:acme").await.is_err());
+        
+        let acme_tool = Some("trn:org:acme:tool:billing:v1.0".to_string());
+        let other_tool = Some("trn:org:globex:tool:billing:v1.0".to_string());
+        for (topic, source, target) in [
+            ("billing.charged", acme_tool.clone(), None),
+            ("billing.charged", other_tool.clone(), None),
+            ("audit.logged", None, None),
+            ("user.notified", other_tool, acme_tool),
+        ] {
+            service.emit(EventEnvelope::with_trn(topic, json!({}), source, target)).await.unwrap();
+        }
+        
+        assert_eq!(stream.next().await.unwrap().topic, "billing.charged");
+        let delivered = stream.next().await.unwrap();
+        assert_eq!(delivered.topic, "user.notified");
+        assert_eq!(delivered.target_trn.as_deref(), Some("trn:org:acme:tool:billing:v1.0"));
+    }
+    
+    #[tokio::test]
+    async fn test_poll_wait_long_polls_for_matching_events() {
+        use std::time::Duration;
+        
+        let service = Arc::new(EventBusService::new(ServiceConfig::default()));
+        
+        // Nothing arrives: an empty result after the timeout
+        let started = tokio::time::Instant::now();
+        let events = service.poll_wait(EventQuery::new().with_topic("orders.*"), Duration::from_millis(50)).await.unwrap();
+        assert!(events.is_empty());
+        assert!(started.elapsed() >= Duration::from_millis(50));
+        
+        // Waits past non-matching events for the first matching one
+        let waiter = {
+            let service = Arc::clone(&service);
+            tokio::spawn(async move {
+                service.poll_wait(EventQuery::new().with_topic("orders.*"), Duration::from_secs(5)).await
+            })
+        };
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        service.emit(EventEnvelope::new("users.created", json!({}))).await.unwrap();
+        service.emit(EventEnvelope::new("orders.created", json!({"id": 1}))).await.unwrap();
+        let events = waiter.await.unwrap().unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].payload["id"], 1);
+        
+        // Stored matches are returned immediately
+        let started = tokio::time::Instant::now();
+        let events = service.poll_wait(EventQuery::new().with_topic("orders.*"), Duration::from_secs(5)).await.unwrap();
+        assert_eq!(events.len(), 1);
+        assert!(started.elapsed() < Duration::from_secs(1));
+    }
+    
+    #[tokio::test]
+    async fn test_plugins_hook_emit_pipeline() {
+        struct AuditPlugin {
+            calls: parking_lot::Mutex<Vec<String>>,
+        }
+        
+        #[async_trait]
+        impl BusPlugin for AuditPlugin {
+            fn name(&self) -> &str {
+                "audit"
+            }
+            
+            async fn before_emit(&self, event: &mut EventEnvelope) -> EventBusResult<()> {
+                if event.topic == "forbidden" {
+                    return Err(EventBusError::validation("forbidden topic"));
+                }
+                event.metadata = Some(json!({ "audited": true }));
+                self.calls.lock().push(format!("before:{}", event.topic));
+                Ok(())
+            }
+            
+            async fn after_store(&self, event: &EventEnvelope) {
+                self.calls.lock().push(format!("store:{}", event.topic));
+            }
+            
+            async fn after_broadcast(&self, event: &EventEnvelope) {
+                self.calls.lock().push(format!("broadcast:{}", event.topic));
+            }
+        }
+        
+        let plugin = Arc::new(AuditPlugin { calls: parking_lot::Mutex::new(Vec::new()) });
+        let service = EventBusService::new(ServiceConfig::default()).with_plugin(plugin.clone());
+        
+        service.emit(EventEnvelope::new("orders", json!({}))).await.unwrap();
+        assert!(service.emit(EventEnvelope::new("forbidden", json!({}))).await.is_err());
+        
+        assert_eq!(*plugin.calls.lock(), vec!["before:orders", "store:orders", "broadcast:orders"]);
+        
+        let stored = service.poll(EventQuery::new().with_topic("orders")).await.unwrap();
+        assert_eq!(stored[0].metadata, Some(json!({ "audited": true })));
+        assert!(service.poll(EventQuery::new().with_topic("forbidden")).await.unwrap().is_empty());
+    }
+    
+    #[tokio::test]
+    async fn test_retention_applies_topic_overrides() {
+        let config = ServiceConfig {
+            retention: crate::config::RetentionConfig {
+                max_age_seconds: 7 * 86400,
+                ..Default::default()
+            }
+            .with_topic_override("audit.*", 365 * 86400)
+            .with_topic_override("telemetry.*", 86400),
+            ..Default::default()
+        };
+        let service = EventBusService::new(config);
+        
+        let now = chrono::Utc::now().timestamp();
+        for topic in ["audit.login", "telemetry.cpu", "orders.created"] {
+            for age_days in [0, 2, 30] {
+                let mut event = EventEnvelope::new(topic, json!({"age_days": age_days}));
+                event.timestamp = now - age_days * 86400;
+                service.emit(event).await.unwrap();
+            }
+        }
+        
+        // telemetry keeps 1 day, orders the 7 day default, audit everything
+        assert_eq!(service.apply_retention().await.unwrap(), 3);
+        for (topic, kept) in [("audit.login", 3), ("telemetry.cpu", 1), ("orders.created", 2)] {
+            let events = service.poll(EventQuery::new().with_topic(topic)).await.unwrap();
+            assert_eq!(events.len(), kept, "{}", topic);
+        }
+        
+        let description = service.describe_topic("telemetry.cpu");
+        assert_eq!(description["retention"], json!({"max_age_seconds": 86400, "pattern": "telemetry.*"}));
+        assert!(description["registered"].is_null());
+        assert_eq!(service.describe_topic("orders.created")["retention"], json!({"max_age_seconds": 7 * 86400}));
+    }
+    
+    #[tokio::test]
+    async fn test_expired_events_are_hidden_then_purged() {
+        let service = EventBusService::new(ServiceConfig::default());
+        let now = chrono::Utc::now().timestamp();
+        
+        service.emit(EventEnvelope::new("presence.alice", json!({"online": true})).with_expires_at(now - 1)).await.unwrap();
+        service.emit(EventEnvelope::new("presence.bob", json!({"online": true})).with_ttl(Duration::from_secs(60))).await.unwrap();
+        service.emit(EventEnvelope::new("presence.carol", json!({"online": true}))).await.unwrap();
+        
+        let events = service.poll(EventQuery::new().with_topic("presence.*")).await.unwrap();
+        let mut topics: Vec<_> = events.iter().map(|e| e.topic.as_str()).collect();
+        topics.sort();
+        assert_eq!(topics, vec!["presence.bob", "presence.carol"]);
+        assert!(events.iter().any(|e| e.expires_at.is_some_and(|at| at >= now + 60)));
         
-        self.metrics.end_operation();
+        // No max age is configured, but expired events are still purged
+        assert_eq!(service.apply_retention().await.unwrap(), 1);
+        assert_eq!(service.memory_storage.purge_expired(now + 3600).await.unwrap(), 1);
+    }
+    
+    #[tokio::test]
+    async fn test_bus_modes_restrict_operations() {
+        let service = EventBusService::new(ServiceConfig::default());
+        service.emit(EventEnvelope::new("orders.created", json!({}))).await.unwrap();
         
-        if result.is_err() {
-            self.metrics.record_error();
-        }
+        service.set_mode(BusMode::ReadOnly, None);
+        let err = service.emit(EventEnvelope::new("orders.created", json!({}))).await.unwrap_err();
+        assert!(matches!(err, EventBusError::Unavailable { .. }));
+        assert!(service.create_topic("orders.updated", TopicSettings::default()).is_err());
+        let err = service.update_topic("orders.created", TopicSettings::default()).unwrap_err();
+        assert!(matches!(err, EventBusError::Unavailable { .. }));
+        assert!(service.apply_retention().await.is_err());
+        assert_eq!(service.poll(EventQuery::new()).await.unwrap().len(), 1);
+        assert!(service.subscribe("orders.*").await.is_ok());
         
-        result
+        service.set_mode(BusMode::Maintenance, Some("incident 42".to_string()));
+        let err = service.poll(EventQuery::new()).await.unwrap_err();
+        assert!(err.to_string().contains("in maintenance (incident 42): poll rejected"));
+        assert!(service.subscribe("orders.*").await.is_err());
+        assert_eq!(service.health().await["mode"]["mode"], "maintenance");
+        
+        service.set_mode(BusMode::Normal, None);
+        service.emit(EventEnvelope::new("orders.created", json!({}))).await.unwrap();
     }
     
-    /// Graceful shutdown
-    pub async fn shutdown(&self) -> EventBusResult<()> {
-        // Wait for ongoing operations to complete
-        let start = Instant::now();
-        while self.metrics.current_operations.load(Ordering::Relaxed) > 0 {
-            if start.elapsed() > self.config.shutdown_grace_period {
-                break;
-            }
-            tokio::time::sleep(Duration::from_millis(100)).await;
+    #[tokio::test]
+    async fn test_recovery_reports_crash_damage_and_rebuilds_topics() {
+        // A previous run stored two of a three-event batch before crashing
+        let storage = Arc::new(MemoryStorage::new());
+        let writer = EventBusService::new(ServiceConfig::default()).with_storage(storage.clone());
+        writer.emit_batch(vec![
+            EventEnvelope::new("orders.created", json!({})),
+            EventEnvelope::new("orders.created", json!({})),
+            EventEnvelope::new("orders.created", json!({})),
+        ]).await.unwrap();
+        let mut stored = storage.query(&EventQuery::new()).await.unwrap();
+        let batch = stored[0].batch().unwrap();
+        assert_eq!(batch.size, 3);
+        stored.retain(|event| event.batch().unwrap().index != 1);
+        
+        let storage = Arc::new(MemoryStorage::new());
+        for event in &stored {
+            storage.store(event).await.unwrap();
+        }
+        for sequence_number in [1, 2, 4] {
+            let event = EventEnvelope::new("billing.charged", json!({})).with_sequence(sequence_number);
+            storage.store(&event).await.unwrap();
         }
         
-        // Close broadcast channel
-        // Note: broadcast channels don't have explicit close
+        let service = EventBusService::new(ServiceConfig::default()).with_storage(storage);
+        assert!(service.last_recovery().is_none());
+        service.start().await.unwrap();
         
-        Ok(())
+        let report = service.last_recovery().unwrap();
+        assert_eq!(report.events_scanned, 5);
+        assert_eq!(report.topics_rebuilt, vec!["billing.charged", "orders.created"]);
+        assert_eq!(report.partial_batches, vec![recovery::PartialBatch {
+            batch_id: batch.id,
+            expected: 3,
+            found: 2,
+            missing: vec![1],
+        }]);
+        assert_eq!(report.sequence_gaps.len(), 1);
+        assert_eq!((report.sequence_gaps[0].after, report.sequence_gaps[0].next), (2, 4));
+        assert!(!report.is_consistent());
+        assert!(service.get_topic("billing.charged").unwrap().auto_created);
     }
-}
-
-#[async_trait]
-impl EventBus for EventBusService {
-    async fn emit(&self, event: EventEnvelope) -> EventBusResult<()> {
-        // Validate source TRN
-        if !self.is_source_allowed(event.source_trn.as_ref()) {
-            return Err(EventBusError::permission_denied(
-                format!("Source TRN not allowed: {:?}", event.source_trn)
-            ));
-        }
+    
+    #[tokio::test]
+    async fn test_config_layers_and_legacy_migration() {
+        // Partial configs fill the rest from defaults
+        let config: ServiceConfig = serde_json::from_value(json!({
+            "instance_id": "orders",
+            "storage": { "type": "Sqlite", "path": ":memory:" }
+        })).unwrap();
+        assert_eq!(config.max_concurrent_emits, ServiceConfig::default().max_concurrent_emits);
         
-        // Check rate limiting for single emit
-        self.check_rate_limit().await?;
+        let service = EventBusService::with_config(config).await.unwrap();
+        assert_eq!(service.describe().await["storage"]["backend"], "sqlite");
         
-        // Acquire semaphore permit for single emit
-        let _permit = self.emit_semaphore.acquire().await
-            .map_err(|_| EventBusError::internal("Failed to acquire semaphore permit"))?;
+        let config = MultiBusConfig::from_value(json!({
+            "instances": [
+                { "id": "orders", "listen": "127.0.0.1:0", "storage": { "type": "Sqlite", "path": "orders.db" } },
+                { "id": "audit", "listen": "127.0.0.1:1" }
+            ],
+            "global": { "log_level": "debug" }
+        })).unwrap();
+        assert_eq!(config.default_bus.as_deref(), Some("orders"));
+        assert_eq!(config.buses["orders"].instance_id, "orders");
+        assert!(config.buses["orders"].storage.is_persistent());
+        assert_eq!(config.buses["audit"].storage, crate::config::StorageConfig::memory());
+        assert_eq!(config.global.logging.unwrap().level, "debug");
         
-        self.metrics.start_operation();
+        let err = MultiBusConfig::from_value(json!({
+            "instances": [
+                { "id": "orders", "listen": "127.0.0.1:0" },
+                { "id": "orders", "listen": "127.0.0.1:1" }
+            ]
+        })).unwrap_err();
+        assert!(err.to_string().contains("Duplicate instance ID"));
+    }
+    
+    #[tokio::test]
+    async fn test_emit_stores_events_once() {
+        use futures::StreamExt;
         
-        let result = async {
-            // Store in persistent storage if available
-            if let Some(ref storage) = self.storage {
-                storage.store(&event).await?;
+        let storage = Arc::new(MemoryStorage::new());
+        let service = EventBusService::new(ServiceConfig::default()).with_storage(storage.clone());
+        let mut live = service.subscribe("orders.*").await.unwrap();
+        
+        service.emit(EventEnvelope::new("orders.created", json!({}))).await.unwrap();
+        service.emit_batch(vec![
+            EventEnvelope::new("orders.updated", json!({})),
+            EventEnvelope::new("orders.updated", json!({})),
+        ]).await.unwrap();
+        
+        assert_eq!(storage.event_count().await, 3);
+        assert_eq!(service.memory_storage.event_count().await, 0);
+        assert_eq!(live.next().await.unwrap().topic, "orders.created");
+        assert_eq!(service.poll(EventQuery::new()).await.unwrap().len(), 3);
+    }
+    
+    #[tokio::test]
+    async fn test_describe_reports_forwarding_rules() {
+        use crate::core::RuleAction;
+        use crate::routing::rule_engine::MemoryRuleEngine;
+        
+        let rule_engine = Arc::new(MemoryRuleEngine::new());
+        rule_engine.register_rule(EventTriggerRule::new(
+            "fwd",
+            "orders.*",
+            RuleAction::Sequence {
+                actions: vec![RuleAction::Forward { target_topic: "audit.orders".to_string(), transform: None }],
+            },
+        )).await.unwrap();
+        let service = EventBusService::new(ServiceConfig::default()).with_rule_engine(rule_engine);
+        
+        let description = service.describe().await;
+        assert_eq!(description["storage"], json!({ "backend": "memory", "persistent": false }));
+        assert_eq!(description["forwarding_rules"][0]["rule_id"], "fwd");
+        assert_eq!(description["forwarding_rules"][0]["target_topic"], "audit.orders");
+        assert_eq!(description["health"]["status"], "healthy");
+    }
+    
+    #[tokio::test]
+    async fn test_critical_rule_actions_preempt_bulk_actions() {
+        use crate::core::{RuleAction, ToolMetadata};
+        use crate::routing::rule_engine::MemoryRuleEngine;
+        use std::sync::atomic::AtomicBool;
+        
+        /// Records invocations in start order; the first one waits for the gate
+        #[derive(Default)]
+        struct GatedInvoker {
+            gate: tokio::sync::Notify,
+            blocked: AtomicBool,
+            started: parking_lot::Mutex<Vec<String>>,
+        }
+        
+        #[async_trait]
+        impl ToolInvoker for GatedInvoker {
+            async fn invoke_tool(&self, invocation: &ToolInvocation) -> EventBusResult<serde_json::Value> {
+                self.started.lock().push(invocation.tool_id.clone());
+                if !self.blocked.swap(true, Ordering::SeqCst) {
+                    self.gate.notified().await;
+                }
+                Ok(json!(null))
             }
             
-            // Store in memory for real-time subscriptions
-            self.memory_storage.store(&event).await?;
-            
-            // Broadcast to subscribers
-            let _ = self.event_sender.send(event.clone());
-            
-            // Record metrics
-            self.metrics.record_event();
-            
-            // Process rules if enabled
-            if self.config.enable_rules {
-                if let Some(ref rule_engine) = self.rule_engine {
-                    let _invocations = rule_engine.process_event(&event).await?;
-                    // TODO: Execute tool invocations
-                }
+            async fn tool_exists(&self, _tool_id: &str) -> EventBusResult<bool> {
+                Ok(true)
             }
             
-            Ok(())
-        }.await;
+            async fn get_tool_metadata(&self, tool_id: &str) -> EventBusResult<ToolMetadata> {
+                Err(EventBusError::not_found(tool_id))
+            }
+        }
         
-        self.metrics.end_operation();
+        let rule_engine = Arc::new(MemoryRuleEngine::new());
+        let invoke = |tool_id: &str| RuleAction::InvokeTool { tool_id: tool_id.to_string(), input: json!({}) };
+        rule_engine.register_rule(
+            EventTriggerRule::new("bulk", "reports.*", invoke("export")).with_action_priority(Priority::Low),
+        ).await.unwrap();
+        rule_engine.register_rule(
+            EventTriggerRule::new("alert", "alerts.*", invoke("page")).with_action_priority(Priority::Critical),
+        ).await.unwrap();
         
-        if result.is_err() {
-            self.metrics.record_error();
+        let invoker = Arc::new(GatedInvoker::default());
+        let service = EventBusService::new(ServiceConfig { max_concurrent_actions: 1, ..Default::default() })
+            .with_rule_engine(rule_engine)
+            .with_tool_invoker(invoker.clone());
+        
+        for _ in 0..3 {
+            service.emit(EventEnvelope::new("reports.daily", json!({}))).await.unwrap();
         }
+        service.emit(EventEnvelope::new("alerts.disk_full", json!({}))).await.unwrap();
         
-        result
+        // The first export holds the only worker; the rest wait by priority
+        let depths = service.get_metrics().await.unwrap().action_queue_depths().clone();
+        assert_eq!((depths[&Priority::Low], depths[&Priority::Critical], depths[&Priority::Normal]), (2, 1, 0));
+        assert_eq!(service.health().await["action_queue_depths"]["Critical"], 1);
+        
+        invoker.gate.notify_one();
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while invoker.started.lock().len() < 4 || service.action_queue_depths().values().sum::<usize>() > 0 {
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+        }).await.unwrap();
+        assert_eq!(*invoker.started.lock(), vec!["export", "page", "export", "export"]);
     }
     
-    async fn poll(&self, query: EventQuery) -> EventBusResult<Vec<EventEnvelope>> {
-        // Query persistent storage first, fall back to memory
-        if let Some(ref storage) = self.storage {
-            storage.query(&query).await
-        } else {
-            self.memory_storage.query(&query).await
+    #[tokio::test]
+    async fn test_anomaly_rules_emit_anomaly_events() {
+        use futures::StreamExt;
+        
+        let service = EventBusService::new(ServiceConfig {
+            anomaly_rules: vec![AnomalyRule::new("heartbeat", "agents.heartbeat").with_silence_after(300)],
+            ..Default::default()
+        });
+        assert!(service.register_anomaly_rule(AnomalyRule::new("bad", "x").with_factor(0.5)).is_err());
+        assert_eq!(service.describe().await["anomaly_rules"][0]["id"], "heartbeat");
+        let mut anomalies = service.subscribe("anomaly.*").await.unwrap();
+        
+        let now = chrono::Utc::now().timestamp();
+        service.emit(EventEnvelope::new("agents.heartbeat", json!({}))).await.unwrap();
+        assert!(service.check_anomalies_at(now + 299).await.is_empty());
+        assert_eq!(service.check_anomalies_at(now + 301).await.len(), 1);
+        let event = anomalies.next().await.unwrap();
+        assert_eq!((event.topic.as_str(), &event.payload["rule_id"]), ("anomaly.silence", &json!("heartbeat")));
+        
+        service.remove_anomaly_rule("heartbeat").unwrap();
+        assert!(service.check_anomalies_at(now + 10_000).await.is_empty());
+    }
+    
+    /// Leader log fetched from its first offset whatever was asked for
+    struct ReplayFromStart(Arc<ReplicationLog>);
+    
+    #[async_trait::async_trait]
+    impl ReplicationSource for ReplayFromStart {
+        async fn fetch(&self, _after: u64, limit: u32) -> EventBusResult<ReplicationBatch> {
+            Ok(self.0.fetch(0, limit))
         }
     }
     
-    async fn subscribe(&self, topic: &str) -> EventBusResult<std::pin::Pin<Box<dyn futures::Stream<Item = EventEnvelope> + Send>>> {
-        use futures::stream::StreamExt;
-        use tokio_stream::wrappers::BroadcastStream;
+    #[tokio::test]
+    async fn test_follower_replicates_leader_topics_and_promotes() {
+        let leader = EventBusService::new(ServiceConfig {
+            instance_id: "leader".to_string(),
+            replication: ReplicationConfig {
+                topics: vec!["orders.*".to_string()],
+                log_capacity: 3,
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+        let follower = EventBusService::new(ServiceConfig {
+            instance_id: "follower".to_string(),
+            replication: ReplicationConfig { batch_size: 2, ..Default::default() },
+            ..Default::default()
+        });
+        assert!(follower.replication_status().is_none());
+        follower.follow(leader.replication_log().unwrap().clone());
+        assert_eq!(follower.mode().mode, BusMode::ReadOnly);
         
-        let receiver = self.event_sender.subscribe();
-        let topic_filter = topic.to_string();
+        for index in 0..3 {
+            leader.emit(EventEnvelope::new("orders.created", json!({ "index": index }))).await.unwrap();
+        }
+        leader.emit(EventEnvelope::new("users.created", json!({}))).await.unwrap();
+        assert_eq!(leader.replication_status().unwrap().position, 3);
         
-        // Increment subscription counter
-        self.metrics.active_subscriptions.fetch_add(1, Ordering::Relaxed);
+        // Two events per fetch leave the follower one behind
+        assert_eq!(follower.sync_replication().await.unwrap(), 2);
+        let status = follower.replication_status().unwrap();
+        assert_eq!((status.role, status.position, status.lag_events), (ReplicationRole::Follower, 2, 1));
+        assert_eq!(follower.sync_replication().await.unwrap(), 1);
+        assert_eq!(follower.replication_status().unwrap().lag_events, 0);
         
-        let stream = BroadcastStream::new(receiver)
-            .filter_map(move |result| {
-                let topic_filter = topic_filter.clone();
-                async move {
-                    match result {
-                        Ok(event) => {
-                            // Filter by topic (support wildcards)
-                            if topic_filter == "*" || event.topic == topic_filter || 
-                               (topic_filter.ends_with('*') && 
-                                event.topic.starts_with(topic_filter.trim_end_matches('*'))) {
-                                Some(event)
-                            } else {
-                                None
-                            }
-                        }
-                        Err(_) => None, // Skip broadcast errors
-                    }
-                }
-            });
+        // Events applied before are skipped when fetched again, and following
+        // the same log again resumes where the follower stopped
+        follower.follow(Arc::new(ReplayFromStart(leader.replication_log().unwrap().clone())));
+        assert_eq!(follower.sync_replication().await.unwrap(), 0);
+        assert_eq!(follower.replication_status().unwrap().skipped, 2);
+        follower.follow(leader.replication_log().unwrap().clone());
+        assert_eq!(follower.sync_replication().await.unwrap(), 0);
+        assert_eq!(follower.poll(EventQuery::new()).await.unwrap().len(), 3);
+        assert!(follower.emit(EventEnvelope::new("orders.created", json!({}))).await.is_err());
         
-        Ok(Box::pin(stream))
+        // Offset 4 leaves the log before the follower fetches it
+        for index in 3..7 {
+            leader.emit(EventEnvelope::new("orders.created", json!({ "index": index }))).await.unwrap();
+        }
+        assert_eq!(follower.sync_replication().await.unwrap(), 2);
+        let status = follower.replication_status().unwrap();
+        assert_eq!((status.position, status.lag_events, status.resyncs), (6, 1, 1));
+        
+        // Promotion waits for maintenance to end, and catches up first
+        follower.set_mode(BusMode::Maintenance, Some("upgrade".to_string()));
+        assert!(matches!(follower.promote().await, Err(EventBusError::Unavailable { .. })));
+        assert_eq!(follower.mode().mode, BusMode::Maintenance);
+        follower.set_mode(BusMode::ReadOnly, Some(FOLLOWER_MODE_REASON.to_string()));
+        let status = follower.promote().await.unwrap();
+        assert_eq!((status.role, status.position, status.applied), (ReplicationRole::Promoted, 7, 6));
+        assert_eq!(follower.mode().mode, BusMode::Normal);
+        follower.emit(EventEnvelope::new("orders.created", json!({}))).await.unwrap();
+        assert!(follower.sync_replication().await.is_err());
+        assert!(leader.promote().await.is_err());
+        
+        let replicated = follower.poll(EventQuery::new()).await.unwrap();
+        assert_eq!(replicated.len(), 7);
+        assert!(replicated.iter().all(|event| event.topic == "orders.created"));
     }
     
-    async fn list_topics(&self) -> EventBusResult<Vec<String>> {
-        // Get topics from storage or memory
-        let storage: &dyn EventStorage = self.storage.as_ref()
-            .map(|s| s.as_ref())
-            .unwrap_or(self.memory_storage.as_ref());
-        
-        // Query all events to extract topics
-        let query = EventQuery::new();
-        let events = storage.query(&query).await?;
+    #[tokio::test]
+    async fn test_restarted_follower_replays_without_duplicates() {
+        let leader = EventBusService::new(ServiceConfig {
+            replication: ReplicationConfig { topics: vec!["orders.*".to_string()], ..Default::default() },
+            ..Default::default()
+        });
+        let dir = tempfile::tempdir().unwrap();
+        let follower_config = ServiceConfig {
+            storage: crate::config::StorageConfig::Sqlite {
+                path: format!("sqlite://{}", dir.path().join("follower.db").display()),
+            },
+            ..Default::default()
+        };
+        let log = leader.replication_log().unwrap().clone();
         
-        let mut topics: Vec<String> = events
-            .into_iter()
-            .map(|e| e.topic)
-            .collect();
+        for index in 0..3 {
+            leader.emit(EventEnvelope::new("orders.created", json!({ "index": index }))).await.unwrap();
+        }
+        let follower = EventBusService::with_config(follower_config.clone()).await.unwrap();
+        follower.follow(log.clone());
+        assert_eq!(follower.sync_replication().await.unwrap(), 3);
+        drop(follower);
         
-        topics.sort();
-        topics.dedup();
+        // The restarted follower fetches the log from the start again
+        leader.emit(EventEnvelope::new("orders.created", json!({ "index": 3 }))).await.unwrap();
+        let follower = EventBusService::with_config(follower_config).await.unwrap();
+        follower.follow(log);
+        follower.sync_replication().await.unwrap();
+        assert_eq!(follower.replication_status().unwrap().position, 4);
+        assert_eq!(follower.poll(EventQuery::new()).await.unwrap().len(), 4);
         
-        Ok(topics)
+        leader.emit(EventEnvelope::new("orders.created", json!({ "index": 4 }))).await.unwrap();
+        assert_eq!(follower.sync_replication().await.unwrap(), 1);
+        assert_eq!(follower.replication_status().unwrap().position, 5);
+        assert_eq!(follower.poll(EventQuery::new()).await.unwrap().len(), 5);
     }
     
-    async fn get_stats(&self) -> EventBusResult<crate::core::traits::BusStats> {
-        let memory_stats = self.memory_storage.get_stats().await?;
+    #[tokio::test]
+    async fn test_promoted_follower_extends_replicated_chain() {
+        let integrity = IntegrityConfig {
+            chained_topics: vec!["audit.*".to_string()],
+            ..Default::default()
+        };
+        let leader = EventBusService::new(ServiceConfig {
+            replication: ReplicationConfig { topics: vec!["audit.*".to_string()], ..Default::default() },
+            integrity: integrity.clone(),
+            ..Default::default()
+        });
+        let follower = EventBusService::new(ServiceConfig { integrity, ..Default::default() });
         
-        Ok(crate::core::traits::BusStats {
-            events_processed: self.metrics.events_processed.load(Ordering::Relaxed),
-            active_subscriptions: self.metrics.active_subscriptions.load(Ordering::Relaxed) as u32,
-            topic_count: memory_stats.topics_count,
-            events_per_second: self.metrics.get_events_per_second(),
-        })
-    }
-}
-
-/// JSON-RPC method implementations
-impl EventBusService {
-    /// Handle emit_event method
-    pub async fn handle_emit_event(&self, event: EventEnvelope) -> EventBusResult<serde_json::Value> {
-        self.emit(event).await?;
-        Ok(serde_json::json!({"status": "success"}))
-    }
-    
-    /// Handle poll_events method
-    pub async fn handle_poll_events(&self, query: EventQuery) -> EventBusResult<Vec<EventEnvelope>> {
-        self.poll(query).await
-    }
-    
-    /// Handle register_rule method
-    pub async fn handle_register_rule(&self, rule: EventTriggerRule) -> EventBusResult<serde_json::Value> {
-        if let Some(ref rule_engine) = self.rule_engine {
-            rule_engine.register_rule(rule).await?;
-            Ok(serde_json::json!({"status": "success"}))
-        } else {
-            Err(EventBusError::configuration("Rule engine not enabled"))
+        // The follower loads the (empty) chain before following
+        drop(follower.chains.lock(&follower.config.integrity, ["audit.log"], follower.primary_storage()).await.unwrap());
+        follower.follow(leader.replication_log().unwrap().clone());
+        for index in 0..2 {
+            leader.emit(EventEnvelope::new("audit.log", json!({ "index": index }))).await.unwrap();
         }
+        follower.promote().await.unwrap();
+        follower.emit(EventEnvelope::new("audit.log", json!({ "index": 2 }))).await.unwrap();
+        
+        let report = follower.verify_topic_integrity("audit.log", ..).await.unwrap();
+        assert!(report.is_intact());
+        assert_eq!(report.head.unwrap().position, 3);
     }
     
-    /// Handle list_topics method
-    pub async fn handle_list_topics(&self) -> EventBusResult<Vec<String>> {
-        self.list_topics().await
+    #[tokio::test]
+    async fn test_integrity_checksums_and_topic_chain() {
+        let storage = Arc::new(MemoryStorage::new());
+        let service = EventBusService::new(ServiceConfig {
+            integrity: IntegrityConfig {
+                checksums: true,
+                chained_topics: vec!["audit.*".to_string()],
+                ..Default::default()
+            },
+            ..Default::default()
+        }).with_storage(storage.clone());
+        
+        for index in 0..2 {
+            service.emit(EventEnvelope::new("audit.log", json!({ "index": index }))).await.unwrap();
+        }
+        service.emit_batch(vec![
+            EventEnvelope::new("audit.log", json!({ "index": 2 })),
+            EventEnvelope::new("audit.log", json!({ "index": 3 })),
+        ]).await.unwrap();
+        service.emit(EventEnvelope::new("orders.created", json!({}))).await.unwrap();
+        
+        let report = service.verify_topic_integrity("audit.log", ..).await.unwrap();
+        assert!(report.is_intact());
+        assert_eq!((report.events_checked, report.head.unwrap().position), (4, 4));
+        let report = service.verify_topic_integrity("orders.created", ..).await.unwrap();
+        assert!(report.is_intact() && !report.chained && report.unsealed.is_empty());
+        assert!(service.verify_topic_integrity("audit.*", ..).await.is_err());
+        assert_eq!(service.poll(EventQuery::new()).await.unwrap().len(), 5);
+        
+        // Rewrite one event and drop the newest one behind the bus's back
+        let stored = storage.query(&EventQuery::new()).await.unwrap();
+        let position = |event: &EventEnvelope| event.integrity().unwrap().position;
+        storage.clear().await;
+        let mut altered_id = String::new();
+        for mut event in stored {
+            match position(&event) {
+                Some(2) => {
+                    event.payload["index"] = json!(20);
+                    altered_id = event.event_id.clone();
+                }
+                Some(4) => continue,
+                _ => {}
+            }
+            storage.store(&event).await.unwrap();
+        }
+        
+        let result = service.poll(EventQuery::new().with_topic("audit.log")).await;
+        assert!(matches!(result, Err(EventBusError::IntegrityViolation { .. })));
+        service.poll(EventQuery::new().with_topic("orders.created")).await.unwrap();
+        
+        let report = service.verify_topic_integrity("audit.log", ..).await.unwrap();
+        assert!(!report.is_intact());
+        assert_eq!(report.tampered, vec![altered_id]);
+        assert_eq!(report.missing, vec![integrity::PositionGap { after: 3, next: 5 }]);
     }
     
-    /// Handle get_stats method (for monitoring)
-    pub async fn handle_get_stats(&self) -> EventBusResult<serde_json::Value> {
-        let stats = self.get_stats().await?;
-        Ok(serde_json::json!({
-            "events_processed": stats.events_processed,
-            "active_subscriptions": stats.active_subscriptions,
-            "topic_count": stats.topic_count,
-            "events_per_second": stats.events_per_second
-        }))
+    #[tokio::test]
+    async fn test_integrity_is_stamped_by_the_bus_only() {
+        let service = EventBusService::new(ServiceConfig {
+            integrity: IntegrityConfig {
+                chained_topics: vec!["audit.*".to_string()],
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+        let forged = crate::core::IntegrityMetadata {
+            hash: "forged".to_string(),
+            position: Some(7),
+            prev_hash: None,
+        };
+        
+        // A forged section is dropped without checksums, replaced on chained topics
+        service.emit(EventEnvelope::new("orders.created", json!({})).with_integrity(&forged)).await.unwrap();
+        let events = service.poll(EventQuery::new().with_topic("orders.created")).await.unwrap();
+        assert!(events[0].integrity().is_none());
+        service.emit(EventEnvelope::new("audit.log", json!({ "index": 0 })).with_integrity(&forged)).await.unwrap();
+        
+        // Chained events cannot expire, so no purge can open a gap in the chain
+        let now = chrono::Utc::now().timestamp();
+        let expiring = EventEnvelope::new("audit.log", json!({ "index": 1 })).with_expires_at(now - 1);
+        assert!(matches!(service.emit(expiring.clone()).await, Err(EventBusError::Validation { .. })));
+        assert!(matches!(service.emit_batch(vec![expiring]).await, Err(EventBusError::Validation { .. })));
+        service.apply_retention().await.unwrap();
+        service.emit(EventEnvelope::new("audit.log", json!({ "index": 1 }))).await.unwrap();
+        
+        let report = service.verify_topic_integrity("audit.log", ..).await.unwrap();
+        assert!(report.is_intact());
+        assert_eq!((report.events_checked, report.head.unwrap().position), (2, 2));
+        assert_eq!(service.poll(EventQuery::new()).await.unwrap().len(), 3);
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use serde_json::json;
     
     #[tokio::test]
-    async fn test_event_bus_service_basic() {
-        let config = ServiceConfig::default();
-        let service = EventBusService::new(config);
+    async fn test_rate_limit_status_and_queue_mode() {
+        let service = EventBusService::new(ServiceConfig {
+            max_events_per_second: Some(2),
+            ..Default::default()
+        });
         
-        // Test emitting an event
-        let event = EventEnvelope::new("test.topic", json!({"message": "hello"}));
-        assert!(service.emit(event).await.is_ok());
+        assert_eq!(service.rate_limit_status().unwrap().remaining, 2);
+        service.emit(EventEnvelope::new("rate.test", json!({}))).await.unwrap();
+        service.emit(EventEnvelope::new("rate.test", json!({}))).await.unwrap();
         
-        // Test polling events
-        let query = EventQuery::new().with_topic("test.topic");
-        let events = service.poll(query).await.unwrap();
-        assert_eq!(events.len(), 1);
+        let status = service.rate_limit_status().unwrap();
+        assert_eq!(status.remaining, 0);
+        assert!(status.reset_after_ms > 0 && status.reset_after_ms <= 1000);
         
-        // Test listing topics
-        let topics = service.list_topics().await.unwrap();
-        assert!(topics.contains(&"test.topic".to_string()));
+        let err = service.emit(EventEnvelope::new("rate.test", json!({}))).await.unwrap_err();
+        assert_eq!(err.category(), "rate_limited");
+        
+        // Every event of a batch takes a slot
+        let service = EventBusService::new(ServiceConfig {
+            max_events_per_second: Some(3),
+            ..Default::default()
+        });
+        let batch = || vec![EventEnvelope::new("rate.test", json!({})), EventEnvelope::new("rate.test", json!({}))];
+        service.emit_batch(batch()).await.unwrap();
+        assert_eq!(service.rate_limit_status().unwrap().remaining, 1);
+        let err = service.emit_batch(batch()).await.unwrap_err();
+        assert_eq!(err.category(), "rate_limited");
+        assert_eq!(service.rate_limit_status().unwrap().remaining, 1);
+        assert!(service.emit_batch([batch(), batch()].concat()).await.is_err());
+        service.emit(EventEnvelope::new("rate.test", json!({}))).await.unwrap();
+        
+        // Queued emits wait for the window to move instead of failing
+        let service = EventBusService::new(ServiceConfig {
+            max_events_per_second: Some(1),
+            rate_limit_mode: RateLimitMode::Queue { max_wait_ms: 2000 },
+            ..Default::default()
+        });
+        service.emit(EventEnvelope::new("rate.test", json!({}))).await.unwrap();
+        let started = Instant::now();
+        service.emit(EventEnvelope::new("rate.test", json!({}))).await.unwrap();
+        assert!(started.elapsed() > Duration::from_millis(500));
     }
     
     #[tokio::test]
@@ -635,11 +2741,61 @@ pub struct MultiBusConfig {
     /// Individual bus configurations
     pub buses: HashMap<String, ServiceConfig>,
     /// Global settings that apply to all buses
+    #[serde(default)]
     pub global: GlobalConfig,
     /// Default bus name to use when none specified
+    #[serde(default)]
     pub default_bus: Option<String>,
 }
 
+impl MultiBusConfig {
+    /// Load configuration from a JSON file
+    /// 
+    /// Files in the older `config::MultiInstanceConfig` format (an
+    /// `instances` list) are validated and migrated.
+    pub fn from_file(path: impl AsRef<std::path::Path>) -> EventBusResult<Self> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| EventBusError::configuration(format!("Failed to read config file: {}", e)))?;
+        let value: serde_json::Value = serde_json::from_str(&content)
+            .map_err(|e| EventBusError::configuration(format!("Failed to parse config: {}", e)))?;
+        Self::from_value(value)
+    }
+    
+    /// Parse configuration, migrating the `config::MultiInstanceConfig` format
+    pub fn from_value(value: serde_json::Value) -> EventBusResult<Self> {
+        if value.get("instances").is_some() {
+            let legacy: crate::config::MultiInstanceConfig = serde_json::from_value(value)
+                .map_err(|e| EventBusError::configuration(format!("Failed to parse config: {}", e)))?;
+            legacy.validate()?;
+            return Ok(Self::from(&legacy));
+        }
+        serde_json::from_value(value)
+            .map_err(|e| EventBusError::configuration(format!("Failed to parse config: {}", e)))
+    }
+}
+
+impl From<&crate::config::MultiInstanceConfig> for MultiBusConfig {
+    fn from(config: &crate::config::MultiInstanceConfig) -> Self {
+        let buses = config.instances
+            .iter()
+            .map(|instance| (instance.id.clone(), ServiceConfig::from(instance)))
+            .collect();
+        let global = GlobalConfig {
+            logging: Some(LoggingConfig {
+                level: config.global.log_level.clone(),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        
+        Self {
+            buses,
+            global,
+            default_bus: config.instances.first().map(|instance| instance.id.clone()),
+        }
+    }
+}
+
 /// Global configuration shared across all event bus instances
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GlobalConfig {
@@ -698,7 +2854,7 @@ impl Default for MultiBusConfig {
         buses.insert(
             "workflows".to_string(),
             ServiceConfig {
-                storage: crate::config::StorageConfig::Memory,
+                storage: crate::config::StorageConfig::memory(),
                 max_concurrent_emits: 100,
                 max_events_per_second: Some(1000),
                 ..Default::default()
@@ -709,7 +2865,7 @@ impl Default for MultiBusConfig {
         buses.insert(
             "global".to_string(),
             ServiceConfig {
-                storage: crate::config::StorageConfig::Memory,
+                storage: crate::config::StorageConfig::memory(),
                 max_concurrent_emits: 200,
                 max_events_per_second: Some(2000),
                 ..Default::default()
@@ -770,11 +2926,13 @@ impl Default for LoggingConfig {
 /// Multi-bus manager for handling multiple EventBus instances
 pub struct MultiBusManager {
     /// Individual bus services
-    buses: HashMap<String, EventBusService>,
+    buses: HashMap<String, Arc<EventBusService>>,
     /// Configuration
     config: MultiBusConfig,
     /// Shutdown signal
     shutdown_tx: Option<tokio::sync::broadcast::Sender<()>>,
+    /// Running JSON-RPC servers, keyed by bus name
+    servers: parking_lot::Mutex<HashMap<String, crate::jsonrpc::RpcServerHandle>>,
 }
 
 impl MultiBusManager {
@@ -784,13 +2942,14 @@ impl MultiBusManager {
         
         for (name, bus_config) in &config.buses {
             let service = EventBusService::with_config(bus_config.clone()).await?;
-            buses.insert(name.clone(), service);
+            buses.insert(name.clone(), Arc::new(service));
         }
         
         Ok(Self {
             buses,
             config,
             shutdown_tx: None,
+            servers: parking_lot::Mutex::new(HashMap::new()),
         })
     }
 
@@ -802,6 +2961,17 @@ impl MultiBusManager {
         for (name, bus) in &self.buses {
             tracing::info!("Starting event bus: {}", name);
             bus.start().await?;
+            bus.spawn_retention_task(shutdown_tx.subscribe());
+            bus.spawn_anomaly_task(shutdown_tx.subscribe());
+            bus.spawn_replication_task(shutdown_tx.subscribe());
+
+            if let Some(listen) = bus.config().listen {
+                let server = crate::jsonrpc::EventBusRpcServer::new(Arc::clone(bus));
+                let handle = server.spawn(listen).await
+                    .map_err(|e| format!("Failed to start JSON-RPC server for bus {}: {}", name, e))?;
+                tracing::info!("Event bus {} serving JSON-RPC on {}", name, handle.local_addr());
+                self.servers.lock().insert(name.clone(), handle);
+            }
         }
 
         tracing::info!("All event buses started successfully");
@@ -815,6 +2985,14 @@ impl MultiBusManager {
         }
 
         let timeout = std::time::Duration::from_secs(self.config.global.shutdown_timeout_secs);
+
+        // Stop accepting requests before the buses go away
+        let servers: Vec<_> = self.servers.lock().drain().collect();
+        for (name, server) in servers {
+            tracing::info!("Stopping JSON-RPC server for bus: {}", name);
+            tokio::time::timeout(timeout, server.shutdown()).await
+                .map_err(|_| format!("Timeout stopping JSON-RPC server for bus: {}", name))?;
+        }
         
         for (name, bus) in &self.buses {
             tracing::info!("Stopping event bus: {}", name);
@@ -829,13 +3007,92 @@ impl MultiBusManager {
 
     /// Get a specific bus by name
     pub fn get_bus(&self, name: &str) -> Option<&EventBusService> {
-        self.buses.get(name)
+        self.buses.get(name).map(|bus| bus.as_ref())
     }
 
     /// Get the default bus
     pub fn get_default_bus(&self) -> Option<&EventBusService> {
         let default_name = self.config.default_bus.as_ref()?;
-        self.buses.get(default_name)
+        self.get_bus(default_name)
+    }
+
+    /// Get the address a bus is serving JSON-RPC on, if its server is running
+    pub fn listen_addr(&self, name: &str) -> Option<std::net::SocketAddr> {
+        self.servers.lock().get(name).map(|server| server.local_addr())
+    }
+
+    /// Describe the whole event mesh as JSON
+    /// 
+    /// Lists every bus with its storage backend, forwarding rules, health and
+    /// listener. The JSON-RPC server is currently the only connector a bus
+    /// can have; buses without a running server report none.
+    pub async fn describe_topology(&self) -> serde_json::Value {
+        let mut names: Vec<&String> = self.buses.keys().collect();
+        names.sort();
+        
+        let mut buses = Vec::with_capacity(names.len());
+        for name in names {
+            let bus = &self.buses[name];
+            let mut description = bus.describe().await;
+            
+            let transport = &bus.config().transport;
+            let bound = self.listen_addr(name);
+            let connectors: Vec<serde_json::Value> = bound
+                .map(|addr| serde_json::json!({
+                    "kind": "jsonrpc",
+                    "transport": "tcp",
+                    "address": addr,
+                    "max_connections": transport.max_connections,
+                    "max_message_size": transport.max_message_size,
+                }))
+                .into_iter()
+                .collect();
+            
+            if let Some(object) = description.as_object_mut() {
+                object.insert("name".to_string(), serde_json::json!(name));
+                object.insert("default".to_string(), serde_json::json!(self.config.default_bus.as_ref() == Some(name)));
+                object.insert("listener".to_string(), serde_json::json!({
+                    "configured": bus.config().listen,
+                    "bound": bound,
+                }));
+                object.insert("connectors".to_string(), serde_json::json!(connectors));
+            }
+            buses.push(description);
+        }
+        
+        serde_json::json!({
+            "default_bus": self.config.default_bus,
+            "bus_count": buses.len(),
+            "buses": buses,
+        })
+    }
+
+    /// Switch every bus to `mode`, returning the new status of each
+    /// 
+    /// Buses are switched together, so a migration or incident response
+    /// covers the whole mesh.
+    pub fn set_mode(&self, mode: BusMode, reason: Option<String>) -> HashMap<String, ModeStatus> {
+        self.buses
+            .iter()
+            .map(|(name, bus)| (name.clone(), bus.set_mode(mode, reason.clone())))
+            .collect()
+    }
+    
+    /// Switch a single bus to `mode`
+    pub fn set_bus_mode(
+        &self,
+        bus_name: &str,
+        mode: BusMode,
+        reason: Option<String>,
+    ) -> Result<ModeStatus, Box<dyn std::error::Error + Send + Sync>> {
+        let bus = self.buses.get(bus_name)
+            .ok_or_else(|| format!("Bus '{}' not found", bus_name))?;
+        Ok(bus.set_mode(mode, reason))
+    }
+    
+    /// Current mode of every bus
+    pub fn modes(&self) -> HashMap<String, ModeStatus> {
+        self.buses.iter().map(|(name, bus)| (name.clone(), bus.mode())).collect()
     }
 
     /// Get all bus names
@@ -939,7 +3196,8 @@ impl CombinedMetrics {
             active_subscriptions: AtomicU64::new(metrics.active_subscriptions.load(Ordering::Relaxed)),
             current_operations: AtomicU64::new(metrics.current_operations.load(Ordering::Relaxed)),
             error_count: AtomicU64::new(metrics.error_count.load(Ordering::Relaxed)),
-            events_last_second: parking_lot::RwLock::new(Vec::new()),
+            events_last_second: parking_lot::RwLock::new(std::collections::VecDeque::new()),
+            action_queue_depths: metrics.action_queue_depths.clone(),
         };
         self.buses.insert(bus_name, serializable_metrics);
         
@@ -948,6 +3206,9 @@ impl CombinedMetrics {
         self.totals.events_last_second_count += metrics.events_last_second_count;
         self.totals.active_subscriptions.fetch_add(metrics.active_subscriptions.load(Ordering::Relaxed), Ordering::Relaxed);
         self.totals.error_count.fetch_add(metrics.error_count.load(Ordering::Relaxed), Ordering::Relaxed);
+        for (priority, depth) in &metrics.action_queue_depths {
+            *self.totals.action_queue_depths.entry(*priority).or_default() += depth;
+        }
         
         // Update timestamp
         self.collected_at = chrono::Utc::now();
@@ -7,8 +7,8 @@ use tokio::sync::RwLock;
 use chrono::{DateTime, Utc};
 
 use crate::core::{
-    traits::{EventStorage, RuleStorage, EventBusResult},
-    types::{EventEnvelope, Rule, EventQuery},
+    traits::{EventStorage, RuleStorage, IdempotencyStorage, EventBusResult},
+    types::{EventEnvelope, Rule, EventQuery, IdempotencyRecord},
 };
 use crate::StorageStats;
 
@@ -17,7 +17,8 @@ use crate::StorageStats;
 pub struct MemoryStorage {
     events: Arc<RwLock<HashMap<String, Vec<EventEnvelope>>>>,
     rules: Arc<RwLock<HashMap<String, Rule>>>,
-    #[allow(dead_code)]
+    idempotency: Arc<RwLock<HashMap<String, IdempotencyRecord>>>,
+    /// Events kept per topic before the oldest are evicted (0 for no limit)
     max_events_per_topic: usize,
 }
 
@@ -28,10 +29,14 @@ impl MemoryStorage {
     }
 
     /// Create new memory storage with custom limits
+    /// 
+    /// Once a topic holds `max_events_per_topic` events, storing another one
+    /// evicts the oldest stored; 0 keeps every event.
     pub fn with_limits(max_events_per_topic: usize) -> Self {
         Self {
             events: Arc::new(RwLock::new(HashMap::new())),
             rules: Arc::new(RwLock::new(HashMap::new())),
+            idempotency: Arc::new(RwLock::new(HashMap::new())),
             max_events_per_topic,
         }
     }
@@ -82,15 +87,24 @@ impl Default for MemoryStorage {
 
 #[async_trait]
 impl EventStorage for MemoryStorage {
+    fn backend_name(&self) -> &str {
+        "memory"
+    }
+    
     async fn store(&self, event: &EventEnvelope) -> EventBusResult<()> {
         // Store in topic-specific collection
         {
-                    let mut events = self.events.write().await;
-            
-            events
-                .entry(event.topic.clone())
-                .or_insert_with(Vec::new)
-                .push(event.clone());
+            let mut events = self.events.write().await;
+            let topic_events = events.entry(event.topic.clone()).or_default();
+            // An event whose ID is already stored is ignored
+            if topic_events.iter().any(|stored| stored.event_id == event.event_id) {
+                return Ok(());
+            }
+            topic_events.push(event.clone());
+            if self.max_events_per_topic > 0 && topic_events.len() > self.max_events_per_topic {
+                let excess = topic_events.len() - self.max_events_per_topic;
+                topic_events.drain(..excess);
+            }
         }
         
         // Events are already stored in topic-specific collections above
@@ -106,50 +120,7 @@ impl EventStorage for MemoryStorage {
         
         let mut filtered_events: Vec<EventEnvelope> = all_events
             .iter()
-            .filter(|&event| {
-                // Filter by topic if specified
-                if let Some(ref topic_pattern) = query.topic {
-                    if !event.matches_topic(topic_pattern) {
-                        return false;
-                    }
-                }
-                
-                // Filter by timestamp range
-                if let Some(since) = query.since {
-                    if event.timestamp < since {
-                        return false;
-                    }
-                }
-                
-                if let Some(until) = query.until {
-                    if event.timestamp >= until {
-                        return false;
-                    }
-                }
-                
-                // Filter by source TRN
-                if let Some(ref source_trn) = query.source_trn {
-                    if event.source_trn.as_ref() != Some(source_trn) {
-                        return false;
-                    }
-                }
-                
-                // Filter by target TRN
-                if let Some(ref target_trn) = query.target_trn {
-                    if event.target_trn.as_ref() != Some(target_trn) {
-                        return false;
-                    }
-                }
-                
-                // Filter by correlation ID
-                if let Some(ref correlation_id) = query.correlation_id {
-                    if event.correlation_id.as_ref() != Some(correlation_id) {
-                        return false;
-                    }
-                }
-                
-                true
-            })
+            .filter(|&event| query.matches(event))
             .map(|&event| event.clone())
             .collect();
         
@@ -230,6 +201,58 @@ impl EventStorage for MemoryStorage {
         
         Ok(removed_count)
     }
+    
+    async fn cleanup_topic(&self, topic: &str, before_timestamp: i64) -> EventBusResult<u64> {
+        let mut events = self.events.write().await;
+        let Some(topic_events) = events.get_mut(topic) else {
+            return Ok(0);
+        };
+        
+        let initial_len = topic_events.len();
+        topic_events.retain(|event| event.timestamp >= before_timestamp);
+        let removed_count = (initial_len - topic_events.len()) as u64;
+        
+        if topic_events.is_empty() {
+            events.remove(topic);
+        }
+        
+        Ok(removed_count)
+    }
+    
+    async fn purge_expired(&self, now: i64) -> EventBusResult<u64> {
+        let mut removed_count = 0;
+        let mut events = self.events.write().await;
+        
+        for topic_events in events.values_mut() {
+            let initial_len = topic_events.len();
+            topic_events.retain(|event| !event.is_expired(now));
+            removed_count += (initial_len - topic_events.len()) as u64;
+        }
+        events.retain(|_, topic_events| !topic_events.is_empty());
+        
+        Ok(removed_count)
+    }
+}
+
+#[async_trait]
+impl IdempotencyStorage for MemoryStorage {
+    async fn get_idempotency_record(&self, key: &str, now: i64) -> EventBusResult<Option<IdempotencyRecord>> {
+        let records = self.idempotency.read().await;
+        Ok(records.get(key).filter(|record| !record.is_expired(now)).cloned())
+    }
+
+    async fn put_idempotency_record(&self, record: &IdempotencyRecord) -> EventBusResult<()> {
+        let mut records = self.idempotency.write().await;
+        records.insert(record.key.clone(), record.clone());
+        Ok(())
+    }
+
+    async fn cleanup_idempotency_records(&self, now: i64) -> EventBusResult<u64> {
+        let mut records = self.idempotency.write().await;
+        let before = records.len();
+        records.retain(|_, record| !record.is_expired(now));
+        Ok((before - records.len()) as u64)
+    }
 }
 
 #[async_trait]
@@ -359,6 +382,31 @@ mod tests {
         assert_eq!(results[0].topic, "test.topic");
     }
     
+    #[tokio::test]
+    async fn test_memory_storage_ignores_stored_event_ids() {
+        let storage = MemoryStorage::new();
+        let event = EventEnvelope::new("test.topic", json!({}));
+        
+        storage.store(&event).await.unwrap();
+        storage.store(&event).await.unwrap();
+        assert_eq!(storage.event_count().await, 1);
+    }
+    
+    #[tokio::test]
+    async fn test_memory_storage_evicts_oldest_per_topic() {
+        let storage = MemoryStorage::with_limits(2);
+        for index in 0..3 {
+            storage.store(&EventEnvelope::new("test.topic", json!({ "index": index }))).await.unwrap();
+        }
+        storage.store(&EventEnvelope::new("other.topic", json!({}))).await.unwrap();
+        
+        let results = storage.query(&EventQuery::new().with_topic("test.topic")).await.unwrap();
+        let kept: Vec<_> = results.iter().map(|event| event.payload["index"].clone()).collect();
+        assert_eq!(kept.len(), 2);
+        assert!(kept.contains(&json!(1)) && kept.contains(&json!(2)));
+        assert_eq!(storage.event_count().await, 3);
+    }
+    
     #[tokio::test]
     async fn test_memory_storage_filtering() {
         let storage = MemoryStorage::new();
@@ -6,7 +6,6 @@ pub mod postgres;
 
 use crate::core::traits::EventStorage;
 use crate::core::EventBusResult;
-use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 
 // Re-export storage implementations
@@ -14,30 +13,7 @@ pub use memory::MemoryStorage;
 pub use sqlite::SqliteStorage;
 pub use postgres::PostgresStorage;
 
-/// Storage configuration enum
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub enum StorageConfig {
-    /// In-memory storage (for testing/development)
-    Memory { 
-        max_events: usize 
-    },
-    /// SQLite storage (for single-node deployments)
-    Sqlite { 
-        database_url: String 
-    },
-    /// PostgreSQL storage (for production deployments)
-    Postgres {
-        database_url: String,
-        max_connections: u32,
-        enable_partitioning: bool,
-    },
-}
-
-impl Default for StorageConfig {
-    fn default() -> Self {
-        StorageConfig::Memory { max_events: 10000 }
-    }
-}
+pub use crate::config::StorageConfig;
 
 /// Create a storage instance based on configuration
 pub async fn create_storage(config: &StorageConfig) -> EventBusResult<Arc<dyn EventStorage>> {
@@ -46,14 +22,14 @@ pub async fn create_storage(config: &StorageConfig) -> EventBusResult<Arc<dyn Ev
             let storage = MemoryStorage::with_limits(*max_events);
             Arc::new(storage)
         }
-        StorageConfig::Sqlite { database_url } => {
-            let storage = SqliteStorage::new(database_url).await?;
+        StorageConfig::Sqlite { path } => {
+            let storage = SqliteStorage::new(path).await?;
             Arc::new(storage)
         }
-        StorageConfig::Postgres { database_url, max_connections, enable_partitioning } => {
+        StorageConfig::Postgres { url, pool_size, enable_partitioning } => {
             let postgres_config = postgres::PostgresConfig {
-                database_url: database_url.clone(),
-                max_connections: *max_connections,
+                database_url: url.clone(),
+                max_connections: *pool_size,
                 enable_partitioning: *enable_partitioning,
                 ..Default::default()
             };
@@ -6,12 +6,13 @@
 use async_trait::async_trait;
 use sqlx::{PgPool, Row, postgres::PgConnectOptions};
 use std::str::FromStr;
+use std::sync::Arc;
 use std::time::Duration;
 use serde_json;
 
 use crate::core::{
-    EventEnvelope, EventQuery, 
-    traits::{EventStorage, EventBusResult, StorageStats},
+    EventEnvelope, EventQuery, IdempotencyRecord,
+    traits::{EventStorage, EventBusResult, IdempotencyStorage, StorageStats},
     EventBusError
 };
 
@@ -166,14 +167,15 @@ impl PostgresStorage {
                 event.correlation_id.clone(),
                 event.sequence_number.map(|n| n as i64),
                 event.priority as i32,
+                event.expires_at,
             ));
         }
         
         // Execute individual inserts in a transaction
-        for (id, topic, payload, timestamp, metadata, source_trn, target_trn, correlation_id, sequence_number, priority) in event_data {
+        for (id, topic, payload, timestamp, metadata, source_trn, target_trn, correlation_id, sequence_number, priority, expires_at) in event_data {
             sqlx::query(
-                "INSERT INTO events (id, topic, payload, timestamp, metadata, source_trn, target_trn, correlation_id, sequence_number, priority) 
-                 VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10) 
+                "INSERT INTO events (id, topic, payload, timestamp, metadata, source_trn, target_trn, correlation_id, sequence_number, priority, expires_at) 
+                 VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11) 
                  ON CONFLICT (id) DO NOTHING"
             )
             .bind(&id)
@@ -186,6 +188,7 @@ impl PostgresStorage {
             .bind(&correlation_id)
             .bind(sequence_number)
             .bind(priority)
+            .bind(expires_at)
             .execute(&mut *tx)
             .await
             .map_err(|e| EventBusError::storage(format!("Failed to insert event: {}", e)))?;
@@ -217,6 +220,7 @@ impl PostgresStorage {
             "CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_events_source_trn ON events USING HASH (source_trn)",
             "CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_events_correlation_id ON events USING BTREE (correlation_id)",
             "CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_events_priority_timestamp ON events USING BTREE (priority DESC, timestamp DESC)",
+            "CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_events_expires_at ON events USING BTREE (expires_at) WHERE expires_at IS NOT NULL",
             "CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_events_topic_gin ON events USING GIN (topic gin_trgm_ops)",
         ];
         
@@ -269,6 +273,14 @@ impl PartitionManager {
 
 #[async_trait]
 impl EventStorage for PostgresStorage {
+    fn backend_name(&self) -> &str {
+        "postgres"
+    }
+    
+    fn idempotency_storage(self: Arc<Self>) -> Option<Arc<dyn IdempotencyStorage>> {
+        Some(self)
+    }
+    
     async fn initialize(&self) -> EventBusResult<()> {
         // Create main events table
         sqlx::query(
@@ -284,6 +296,7 @@ impl EventStorage for PostgresStorage {
                 correlation_id TEXT,
                 sequence_number BIGINT,
                 priority INTEGER NOT NULL DEFAULT 100,
+                expires_at BIGINT,
                 created_at TIMESTAMPTZ DEFAULT NOW()
             )
             "#
@@ -291,6 +304,12 @@ impl EventStorage for PostgresStorage {
         .execute(&self.pool)
         .await
         .map_err(|e| EventBusError::storage(format!("Failed to create events table: {}", e)))?;
+        
+        // Tables created before event expiry was supported lack the column
+        sqlx::query("ALTER TABLE events ADD COLUMN IF NOT EXISTS expires_at BIGINT")
+            .execute(&self.pool)
+            .await
+            .map_err(|e| EventBusError::storage(format!("Failed to add expires_at column: {}", e)))?;
 
         // Create rules table
         sqlx::query(
@@ -311,6 +330,27 @@ impl EventStorage for PostgresStorage {
         .await
         .map_err(|e| EventBusError::storage(format!("Failed to create rules table: {}", e)))?;
 
+        // Create idempotency keys table
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS idempotency_keys (
+                key TEXT PRIMARY KEY,
+                event_id TEXT NOT NULL,
+                response TEXT NOT NULL,
+                created_at BIGINT NOT NULL,
+                expires_at BIGINT NOT NULL
+            )
+            "#
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| EventBusError::storage(format!("Failed to create idempotency_keys table: {}", e)))?;
+
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_idempotency_expires_at ON idempotency_keys (expires_at)")
+            .execute(&self.pool)
+            .await
+            .map_err(|e| EventBusError::storage(format!("Failed to create idempotency expires_at index: {}", e)))?;
+
         // Create performance indexes
         self.create_performance_indexes().await?;
         
@@ -326,13 +366,20 @@ impl EventStorage for PostgresStorage {
         self.store_batch_optimized(&[event.clone()]).await
     }
     
+    async fn store_batch(&self, events: &[EventEnvelope]) -> EventBusResult<()> {
+        self.store_batch_optimized(events).await
+    }
+    
     async fn query(&self, query: &EventQuery) -> EventBusResult<Vec<EventEnvelope>> {
         // Advanced PostgreSQL query implementation with JSON operations
         let mut sql = String::from(
             "SELECT id, topic, payload, timestamp, metadata, source_trn, target_trn, 
-             correlation_id, sequence_number, priority FROM events WHERE 1=1"
+             correlation_id, sequence_number, priority, expires_at FROM events WHERE 1=1"
         );
         
+        // Expired events are hidden until the retention task purges them
+        sql.push_str(&format!(" AND (expires_at IS NULL OR expires_at > {})", chrono::Utc::now().timestamp()));
+        
         if let Some(ref topic) = query.topic {
             if topic.contains('*') || topic.contains('?') {
                 sql.push_str(" AND topic ~ ?");
@@ -389,6 +436,88 @@ impl EventStorage for PostgresStorage {
         
         Ok(result.rows_affected())
     }
+    
+    async fn cleanup_topic(&self, topic: &str, before_timestamp: i64) -> EventBusResult<u64> {
+        let result = sqlx::query("DELETE FROM events WHERE topic = $1 AND timestamp < $2")
+            .bind(topic)
+            .bind(before_timestamp)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| EventBusError::storage(format!("Failed to cleanup events: {}", e)))?;
+        
+        Ok(result.rows_affected())
+    }
+    
+    async fn purge_expired(&self, now: i64) -> EventBusResult<u64> {
+        let result = sqlx::query("DELETE FROM events WHERE expires_at IS NOT NULL AND expires_at <= $1")
+            .bind(now)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| EventBusError::storage(format!("Failed to purge expired events: {}", e)))?;
+        
+        Ok(result.rows_affected())
+    }
+}
+
+#[async_trait]
+impl IdempotencyStorage for PostgresStorage {
+    async fn get_idempotency_record(&self, key: &str, now: i64) -> EventBusResult<Option<IdempotencyRecord>> {
+        let row = sqlx::query(
+            "SELECT key, event_id, response, created_at, expires_at FROM idempotency_keys WHERE key = $1 AND expires_at > $2"
+        )
+        .bind(key)
+        .bind(now)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| EventBusError::storage(format!("Failed to get idempotency key: {}", e)))?;
+
+        let Some(row) = row else {
+            return Ok(None);
+        };
+
+        let response: String = row.try_get("response")
+            .map_err(|e| EventBusError::storage(format!("Failed to get response: {}", e)))?;
+
+        Ok(Some(IdempotencyRecord {
+            key: row.try_get("key")
+                .map_err(|e| EventBusError::storage(format!("Failed to get key: {}", e)))?,
+            event_id: row.try_get("event_id")
+                .map_err(|e| EventBusError::storage(format!("Failed to get event_id: {}", e)))?,
+            response: serde_json::from_str(&response)?,
+            created_at: row.try_get("created_at")
+                .map_err(|e| EventBusError::storage(format!("Failed to get created_at: {}", e)))?,
+            expires_at: row.try_get("expires_at")
+                .map_err(|e| EventBusError::storage(format!("Failed to get expires_at: {}", e)))?,
+        }))
+    }
+
+    async fn put_idempotency_record(&self, record: &IdempotencyRecord) -> EventBusResult<()> {
+        sqlx::query(
+            "INSERT INTO idempotency_keys (key, event_id, response, created_at, expires_at) VALUES ($1, $2, $3, $4, $5)
+             ON CONFLICT (key) DO UPDATE SET event_id = EXCLUDED.event_id, response = EXCLUDED.response,
+             created_at = EXCLUDED.created_at, expires_at = EXCLUDED.expires_at"
+        )
+        .bind(&record.key)
+        .bind(&record.event_id)
+        .bind(serde_json::to_string(&record.response)?)
+        .bind(record.created_at)
+        .bind(record.expires_at)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| EventBusError::storage(format!("Failed to store idempotency key: {}", e)))?;
+
+        Ok(())
+    }
+
+    async fn cleanup_idempotency_records(&self, now: i64) -> EventBusResult<u64> {
+        let result = sqlx::query("DELETE FROM idempotency_keys WHERE expires_at <= $1")
+            .bind(now)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| EventBusError::storage(format!("Failed to cleanup idempotency keys: {}", e)))?;
+
+        Ok(result.rows_affected())
+    }
 }
 
 // Additional helper methods would be implemented here... 
@@ -427,6 +556,7 @@ impl PostgresStorage {
             },
             priority: row.try_get::<i32, _>("priority")
                 .map_err(|e| EventBusError::storage(format!("Failed to get priority: {}", e)))? as u32,
+            expires_at: row.try_get::<Option<i64>, _>("expires_at").ok().flatten(),
         })
     }
 } 
\ No newline at end of file
@@ -6,13 +6,15 @@
 use async_trait::async_trait;
 use sqlx::{SqlitePool, Row, sqlite::SqliteConnectOptions};
 use std::str::FromStr;
+use std::sync::Arc;
 use std::time::Duration;
 use serde_json;
 
 use crate::core::{
     EventEnvelope, EventQuery, EventStorage, EventBusResult, EventBusError
 };
-use crate::core::traits::{StorageStats, RuleStorage};
+use crate::core::traits::{StorageStats, RuleStorage, IdempotencyStorage};
+use crate::core::types::IdempotencyRecord;
 
 /// SQLite storage implementation
 pub struct SqliteStorage {
@@ -139,8 +141,8 @@ impl SqliteStorage {
                 r#"
                 INSERT INTO events (
                     id, topic, payload, timestamp, metadata, 
-                    source_trn, target_trn, correlation_id, sequence, priority
-                ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+                    source_trn, target_trn, correlation_id, sequence, priority, expires_at
+                ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
                 "#
             )
             .bind(&event.event_id)
@@ -153,6 +155,7 @@ impl SqliteStorage {
             .bind(&event.correlation_id)
             .bind(event.sequence_number.unwrap_or(0) as i64)
             .bind(event.priority as i32)
+            .bind(event.expires_at)
             .execute(&mut *tx)
             .await
             .map_err(|e| EventBusError::storage(format!("Failed to insert event: {}", e)))?;
@@ -193,17 +196,18 @@ impl SqliteStorage {
                 event.correlation_id.clone(),
                 event.sequence_number.unwrap_or(0) as i64,
                 event.priority as i32,
+                event.expires_at,
             ));
         }
         
         // Execute batch insert using a single prepared statement
-        for (id, topic, payload, timestamp, metadata, source_trn, target_trn, correlation_id, sequence, priority) in event_data {
+        for (id, topic, payload, timestamp, metadata, source_trn, target_trn, correlation_id, sequence, priority, expires_at) in event_data {
             sqlx::query(
                 r#"
                 INSERT OR IGNORE INTO events (
                     id, topic, payload, timestamp, metadata, 
-                    source_trn, target_trn, correlation_id, sequence, priority
-                ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+                    source_trn, target_trn, correlation_id, sequence, priority, expires_at
+                ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
                 "#
             )
             .bind(&id)
@@ -216,6 +220,7 @@ impl SqliteStorage {
             .bind(&correlation_id)
             .bind(sequence)
             .bind(priority)
+            .bind(expires_at)
             .execute(&mut *tx)
             .await
             .map_err(|e| EventBusError::storage(format!("Failed to insert event: {}", e)))?;
@@ -233,6 +238,9 @@ impl SqliteStorage {
         let mut sql = String::from("SELECT * FROM events WHERE 1=1");
         let mut params: Vec<Box<dyn sqlx::Encode<'_, sqlx::Sqlite> + Send + Sync>> = Vec::new();
         
+        // Expired events are hidden until the retention task purges them
+        sql.push_str(&format!(" AND (expires_at IS NULL OR expires_at > {})", chrono::Utc::now().timestamp()));
+        
         if let Some(ref topic) = query.topic {
             if topic.contains('*') {
                 sql.push_str(" AND topic GLOB ?");
@@ -432,12 +440,21 @@ impl SqliteStorage {
             },
             priority: row.try_get::<i32, _>("priority")
                 .map_err(|e| EventBusError::storage(format!("Failed to get priority: {}", e)))? as u32,
+            expires_at: row.try_get::<Option<i64>, _>("expires_at").ok().flatten(),
         })
     }
 }
 
 #[async_trait]
 impl EventStorage for SqliteStorage {
+    fn backend_name(&self) -> &str {
+        "sqlite"
+    }
+    
+    fn idempotency_storage(self: Arc<Self>) -> Option<Arc<dyn IdempotencyStorage>> {
+        Some(self)
+    }
+    
     /// Initialize the storage (create tables)
     async fn initialize(&self) -> EventBusResult<()> {
         sqlx::query(
@@ -453,6 +470,7 @@ impl EventStorage for SqliteStorage {
                 correlation_id TEXT,
                 sequence INTEGER NOT NULL DEFAULT 0,
                 priority INTEGER NOT NULL DEFAULT 0,
+                expires_at INTEGER,
                 created_at DATETIME DEFAULT CURRENT_TIMESTAMP
             )
             "#
@@ -460,6 +478,20 @@ impl EventStorage for SqliteStorage {
         .execute(&self.pool)
         .await
         .map_err(|e| EventBusError::storage(format!("Failed to create events table: {}", e)))?;
+        
+        // Tables created before event expiry was supported lack the column
+        let has_expires_at: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM pragma_table_info('events') WHERE name = 'expires_at'"
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| EventBusError::storage(format!("Failed to inspect events table: {}", e)))?;
+        if has_expires_at == 0 {
+            sqlx::query("ALTER TABLE events ADD COLUMN expires_at INTEGER")
+                .execute(&self.pool)
+                .await
+                .map_err(|e| EventBusError::storage(format!("Failed to add expires_at column: {}", e)))?;
+        }
 
         // Create rules table
         sqlx::query(
@@ -504,6 +536,11 @@ impl EventStorage for SqliteStorage {
             .execute(&self.pool)
             .await
             .map_err(|e| EventBusError::storage(format!("Failed to create correlation_id index: {}", e)))?;
+        
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_events_expires_at ON events(expires_at)")
+            .execute(&self.pool)
+            .await
+            .map_err(|e| EventBusError::storage(format!("Failed to create expires_at index: {}", e)))?;
 
         // Create indexes for rules table
         sqlx::query("CREATE INDEX IF NOT EXISTS idx_rules_pattern ON rules(pattern)")
@@ -520,18 +557,40 @@ impl EventStorage for SqliteStorage {
             .execute(&self.pool)
             .await
             .map_err(|e| EventBusError::storage(format!("Failed to create rules priority index: {}", e)))?;
+
+        // Create idempotency keys table
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS idempotency_keys (
+                key TEXT PRIMARY KEY,
+                event_id TEXT NOT NULL,
+                response TEXT NOT NULL,
+                created_at INTEGER NOT NULL,
+                expires_at INTEGER NOT NULL
+            )
+            "#
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| EventBusError::storage(format!("Failed to create idempotency_keys table: {}", e)))?;
+
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_idempotency_expires_at ON idempotency_keys(expires_at)")
+            .execute(&self.pool)
+            .await
+            .map_err(|e| EventBusError::storage(format!("Failed to create idempotency expires_at index: {}", e)))?;
         
         Ok(())
     }
     
     /// Store a single event
     async fn store(&self, event: &EventEnvelope) -> EventBusResult<()> {
+        // Like `store_batch`, an event whose ID is already stored is ignored
         sqlx::query(
             r#"
-            INSERT INTO events (
+            INSERT OR IGNORE INTO events (
                 id, topic, payload, timestamp, metadata, 
-                source_trn, target_trn, correlation_id, sequence, priority
-            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+                source_trn, target_trn, correlation_id, sequence, priority, expires_at
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
             "#
         )
         .bind(&event.event_id)
@@ -544,6 +603,7 @@ impl EventStorage for SqliteStorage {
         .bind(&event.correlation_id)
         .bind(event.sequence_number.unwrap_or(0) as i64)
         .bind(event.priority as i32)
+        .bind(event.expires_at)
         .execute(&self.pool)
         .await
         .map_err(|e| EventBusError::storage(format!("Failed to store event: {}", e)))?;
@@ -551,6 +611,11 @@ impl EventStorage for SqliteStorage {
         Ok(())
     }
     
+    /// Store events in a single transaction
+    async fn store_batch(&self, events: &[EventEnvelope]) -> EventBusResult<()> {
+        self.store_batch_optimized(events).await
+    }
+    
     /// Query events
     async fn query(&self, query: &EventQuery) -> EventBusResult<Vec<EventEnvelope>> {
         self.query_advanced(query, query.limit.map(|l| l as u32), None).await
@@ -587,6 +652,29 @@ impl EventStorage for SqliteStorage {
         
         Ok(result.rows_affected())
     }
+    
+    /// Cleanup old events of a single topic
+    async fn cleanup_topic(&self, topic: &str, before_timestamp: i64) -> EventBusResult<u64> {
+        let result = sqlx::query("DELETE FROM events WHERE topic = ? AND timestamp < ?")
+            .bind(topic)
+            .bind(before_timestamp)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| EventBusError::storage(format!("Failed to cleanup events: {}", e)))?;
+        
+        Ok(result.rows_affected())
+    }
+    
+    /// Delete expired events
+    async fn purge_expired(&self, now: i64) -> EventBusResult<u64> {
+        let result = sqlx::query("DELETE FROM events WHERE expires_at IS NOT NULL AND expires_at <= ?")
+            .bind(now)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| EventBusError::storage(format!("Failed to purge expired events: {}", e)))?;
+        
+        Ok(result.rows_affected())
+    }
 } 
 
 #[async_trait]
@@ -771,4 +859,63 @@ impl RuleStorage for SqliteStorage {
         
         Ok(count as u64)
     }
-} 
\ No newline at end of file
+}
+
+#[async_trait]
+impl IdempotencyStorage for SqliteStorage {
+    async fn get_idempotency_record(&self, key: &str, now: i64) -> EventBusResult<Option<IdempotencyRecord>> {
+        let row = sqlx::query(
+            "SELECT key, event_id, response, created_at, expires_at FROM idempotency_keys WHERE key = ? AND expires_at > ?"
+        )
+        .bind(key)
+        .bind(now)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| EventBusError::storage(format!("Failed to get idempotency key: {}", e)))?;
+
+        let Some(row) = row else {
+            return Ok(None);
+        };
+
+        let response: String = row.try_get("response")
+            .map_err(|e| EventBusError::storage(format!("Failed to get response: {}", e)))?;
+
+        Ok(Some(IdempotencyRecord {
+            key: row.try_get("key")
+                .map_err(|e| EventBusError::storage(format!("Failed to get key: {}", e)))?,
+            event_id: row.try_get("event_id")
+                .map_err(|e| EventBusError::storage(format!("Failed to get event_id: {}", e)))?,
+            response: serde_json::from_str(&response)?,
+            created_at: row.try_get("created_at")
+                .map_err(|e| EventBusError::storage(format!("Failed to get created_at: {}", e)))?,
+            expires_at: row.try_get("expires_at")
+                .map_err(|e| EventBusError::storage(format!("Failed to get expires_at: {}", e)))?,
+        }))
+    }
+
+    async fn put_idempotency_record(&self, record: &IdempotencyRecord) -> EventBusResult<()> {
+        sqlx::query(
+            "INSERT OR REPLACE INTO idempotency_keys (key, event_id, response, created_at, expires_at) VALUES (?, ?, ?, ?, ?)"
+        )
+        .bind(&record.key)
+        .bind(&record.event_id)
+        .bind(serde_json::to_string(&record.response)?)
+        .bind(record.created_at)
+        .bind(record.expires_at)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| EventBusError::storage(format!("Failed to store idempotency key: {}", e)))?;
+
+        Ok(())
+    }
+
+    async fn cleanup_idempotency_records(&self, now: i64) -> EventBusResult<u64> {
+        let result = sqlx::query("DELETE FROM idempotency_keys WHERE expires_at <= ?")
+            .bind(now)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| EventBusError::storage(format!("Failed to cleanup idempotency keys: {}", e)))?;
+
+        Ok(result.rows_affected())
+    }
+}
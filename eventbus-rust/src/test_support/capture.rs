@@ -0,0 +1,162 @@
+//! Topic collectors with timeout-bounded waits
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures::StreamExt;
+use parking_lot::Mutex;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+
+use crate::core::traits::{EventBus, EventBusResult};
+use crate::core::EventEnvelope;
+
+/// Collects every event delivered to a subscription
+///
+/// Waiting methods wake up as soon as a new event arrives, so tests never
+/// need to sleep for a fixed amount of time. The subscription is dropped
+/// together with the capture.
+pub struct TopicCapture {
+    pattern: String,
+    events: Arc<Mutex<Vec<EventEnvelope>>>,
+    updates: watch::Receiver<usize>,
+    task: JoinHandle<()>,
+}
+
+impl TopicCapture {
+    /// Subscribe to `pattern` on the bus and start collecting events
+    ///
+    /// The subscription is active when this returns, so events emitted
+    /// afterwards are never missed.
+    pub async fn start<B: EventBus + ?Sized>(bus: &B, pattern: &str) -> EventBusResult<Self> {
+        let mut stream = bus.subscribe(pattern).await?;
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let (updates_tx, updates) = watch::channel(0);
+
+        let sink = Arc::clone(&events);
+        let task = tokio::spawn(async move {
+            while let Some(event) = stream.next().await {
+                let count = {
+                    let mut events = sink.lock();
+                    events.push(event);
+                    events.len()
+                };
+                let _ = updates_tx.send(count);
+            }
+        });
+
+        Ok(Self {
+            pattern: pattern.to_string(),
+            events,
+            updates,
+            task,
+        })
+    }
+
+    /// Subscription pattern
+    pub fn pattern(&self) -> &str {
+        &self.pattern
+    }
+
+    /// Snapshot of the captured events, in delivery order
+    pub fn events(&self) -> Vec<EventEnvelope> {
+        self.events.lock().clone()
+    }
+
+    /// Number of captured events
+    pub fn len(&self) -> usize {
+        self.events.lock().len()
+    }
+
+    /// Whether no events have been captured yet
+    pub fn is_empty(&self) -> bool {
+        self.events.lock().is_empty()
+    }
+
+    /// Discard captured events
+    pub fn clear(&self) {
+        self.events.lock().clear();
+    }
+
+    /// Wait until a captured event satisfies `predicate`
+    ///
+    /// Returns the first matching event, or `None` if the timeout elapses.
+    pub async fn wait_for<F>(&self, timeout: Duration, predicate: F) -> Option<EventEnvelope>
+    where
+        F: Fn(&EventEnvelope) -> bool,
+    {
+        let deadline = tokio::time::Instant::now() + timeout;
+        let mut updates = self.updates.clone();
+
+        loop {
+            // Mark the current state as seen before inspecting it so an event
+            // arriving in between still wakes us up
+            updates.borrow_and_update();
+            if let Some(event) = self.events.lock().iter().find(|event| predicate(event)) {
+                return Some(event.clone());
+            }
+
+            match tokio::time::timeout_at(deadline, updates.changed()).await {
+                Ok(Ok(())) => continue,
+                // Subscription ended or deadline reached
+                Ok(Err(_)) | Err(_) => return None,
+            }
+        }
+    }
+
+    /// Wait until at least `count` events have been captured
+    pub async fn wait_for_count(&self, count: usize, timeout: Duration) -> bool {
+        let deadline = tokio::time::Instant::now() + timeout;
+        let mut updates = self.updates.clone();
+
+        loop {
+            updates.borrow_and_update();
+            if self.len() >= count {
+                return true;
+            }
+
+            match tokio::time::timeout_at(deadline, updates.changed()).await {
+                Ok(Ok(())) => continue,
+                Ok(Err(_)) | Err(_) => return false,
+            }
+        }
+    }
+
+    /// Assert that an event whose topic matches `pattern` is captured within `timeout`
+    ///
+    /// Patterns follow [`EventEnvelope::matches_topic`]. Panics with the list
+    /// of captured topics when no event matches.
+    pub async fn assert_emitted_matching(&self, pattern: &str, timeout: Duration) -> EventEnvelope {
+        match self.wait_for(timeout, |event| event.matches_topic(pattern)).await {
+            Some(event) => event,
+            None => panic!(
+                "no event matching '{}' captured on '{}' within {:?}; captured topics: {:?}",
+                pattern,
+                self.pattern,
+                timeout,
+                self.topics(),
+            ),
+        }
+    }
+
+    /// Assert that no event captured so far matches `pattern`
+    pub fn assert_none_matching(&self, pattern: &str) {
+        let events = self.events.lock();
+        if let Some(event) = events.iter().find(|event| event.matches_topic(pattern)) {
+            panic!(
+                "unexpected event matching '{}' captured on '{}': {} ({})",
+                pattern, self.pattern, event.event_id, event.topic,
+            );
+        }
+    }
+
+    fn topics(&self) -> Vec<String> {
+        self.events.lock().iter().map(|event| event.topic.clone()).collect()
+    }
+}
+
+impl Drop for TopicCapture {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
@@ -0,0 +1,47 @@
+//! Deterministic clock for event timestamps
+
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Default start time for test clocks (2023-11-14T22:13:20Z)
+pub const DEFAULT_START_TIMESTAMP: i64 = 1_700_000_000;
+
+/// Manually driven clock producing Unix timestamps in seconds
+///
+/// Clones share the same time, so a clock handed to an [`EventFactory`](super::EventFactory)
+/// can be advanced from the test body.
+#[derive(Debug, Clone)]
+pub struct TestClock {
+    now: Arc<AtomicI64>,
+}
+
+impl TestClock {
+    /// Create a clock starting at the given Unix timestamp
+    pub fn new(start: i64) -> Self {
+        Self {
+            now: Arc::new(AtomicI64::new(start)),
+        }
+    }
+
+    /// Current Unix timestamp
+    pub fn now(&self) -> i64 {
+        self.now.load(Ordering::SeqCst)
+    }
+
+    /// Move the clock forward (sub-second precision is dropped)
+    pub fn advance(&self, by: Duration) -> i64 {
+        self.now.fetch_add(by.as_secs() as i64, Ordering::SeqCst) + by.as_secs() as i64
+    }
+
+    /// Set the clock to an absolute Unix timestamp
+    pub fn set(&self, timestamp: i64) {
+        self.now.store(timestamp, Ordering::SeqCst);
+    }
+}
+
+impl Default for TestClock {
+    fn default() -> Self {
+        Self::new(DEFAULT_START_TIMESTAMP)
+    }
+}
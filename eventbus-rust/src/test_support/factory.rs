@@ -0,0 +1,75 @@
+//! Event factories producing deterministic envelopes
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use serde_json::json;
+
+use crate::core::EventEnvelope;
+use super::TestClock;
+
+/// Builds events with predictable IDs, sequence numbers and timestamps
+///
+/// Event IDs take the form `<prefix>-<n>` and sequence numbers count up from 1,
+/// so two runs of the same test produce identical envelopes.
+#[derive(Debug, Clone)]
+pub struct EventFactory {
+    clock: TestClock,
+    prefix: String,
+    source_trn: Option<String>,
+    next: Arc<AtomicU64>,
+}
+
+impl EventFactory {
+    /// Create a factory stamping events with the given clock
+    pub fn new(clock: TestClock) -> Self {
+        Self {
+            clock,
+            prefix: "evt".to_string(),
+            source_trn: None,
+            next: Arc::new(AtomicU64::new(1)),
+        }
+    }
+
+    /// Set the event ID prefix
+    pub fn with_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = prefix.into();
+        self
+    }
+
+    /// Set the source TRN attached to every event
+    pub fn with_source_trn(mut self, source_trn: impl Into<String>) -> Self {
+        self.source_trn = Some(source_trn.into());
+        self
+    }
+
+    /// Clock used for event timestamps
+    pub fn clock(&self) -> &TestClock {
+        &self.clock
+    }
+
+    /// Create the next event for a topic
+    pub fn event(&self, topic: impl Into<String>, payload: serde_json::Value) -> EventEnvelope {
+        let n = self.next.fetch_add(1, Ordering::SeqCst);
+
+        let mut event = EventEnvelope::new(topic, payload);
+        event.event_id = format!("{}-{}", self.prefix, n);
+        event.timestamp = self.clock.now();
+        event.sequence_number = Some(n);
+        event.source_trn = self.source_trn.clone();
+        event
+    }
+
+    /// Create `count` events for a topic with `{"index": i}` payloads
+    pub fn events(&self, topic: &str, count: usize) -> Vec<EventEnvelope> {
+        (0..count)
+            .map(|index| self.event(topic, json!({ "index": index })))
+            .collect()
+    }
+}
+
+impl Default for EventFactory {
+    fn default() -> Self {
+        Self::new(TestClock::default())
+    }
+}
@@ -0,0 +1,108 @@
+//! In-process bus harness
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::core::traits::{EventBus, EventBusResult};
+use crate::core::EventEnvelope;
+use crate::service::{EventBusService, ServiceConfig};
+use super::{EventFactory, TestClock, TopicCapture};
+
+/// In-process event bus backed by memory storage
+///
+/// Every event emitted on the bus is recorded from the moment the harness is
+/// created, so a recorded run can be fed to [`replay`](Self::replay) on a
+/// fresh harness to reproduce it.
+pub struct BusHarness {
+    bus: Arc<EventBusService>,
+    clock: TestClock,
+    factory: EventFactory,
+    recorder: TopicCapture,
+}
+
+impl BusHarness {
+    /// Create a harness with the default service configuration
+    pub async fn new() -> EventBusResult<Self> {
+        Self::with_config(ServiceConfig {
+            instance_id: "test-harness".to_string(),
+            ..Default::default()
+        }).await
+    }
+
+    /// Create a harness around a service built from `config`
+    pub async fn with_config(config: ServiceConfig) -> EventBusResult<Self> {
+        Self::with_service(Arc::new(EventBusService::new(config))).await
+    }
+
+    /// Create a harness around an existing service
+    pub async fn with_service(bus: Arc<EventBusService>) -> EventBusResult<Self> {
+        let clock = TestClock::default();
+        let factory = EventFactory::new(clock.clone());
+        let recorder = TopicCapture::start(bus.as_ref(), "*").await?;
+
+        Ok(Self {
+            bus,
+            clock,
+            factory,
+            recorder,
+        })
+    }
+
+    /// Underlying bus service
+    pub fn bus(&self) -> &Arc<EventBusService> {
+        &self.bus
+    }
+
+    /// Clock used to timestamp events created by the harness
+    pub fn clock(&self) -> &TestClock {
+        &self.clock
+    }
+
+    /// Factory used to create events
+    pub fn factory(&self) -> &EventFactory {
+        &self.factory
+    }
+
+    /// Create an event with the harness factory and emit it
+    pub async fn emit(&self, topic: &str, payload: serde_json::Value) -> EventBusResult<EventEnvelope> {
+        let event = self.factory.event(topic, payload);
+        self.bus.emit(event.clone()).await?;
+        Ok(event)
+    }
+
+    /// Emit a prebuilt event
+    pub async fn emit_event(&self, event: EventEnvelope) -> EventBusResult<()> {
+        self.bus.emit(event).await
+    }
+
+    /// Start collecting events matching `pattern`
+    pub async fn capture_topic(&self, pattern: &str) -> EventBusResult<TopicCapture> {
+        TopicCapture::start(self.bus.as_ref(), pattern).await
+    }
+
+    /// Every event emitted since the harness was created
+    pub fn recorded(&self) -> Vec<EventEnvelope> {
+        self.recorder.events()
+    }
+
+    /// Emit recorded events in order, preserving their IDs and timestamps
+    ///
+    /// Events that have expired by now are skipped.
+    pub async fn replay(&self, events: impl IntoIterator<Item = EventEnvelope>) -> EventBusResult<()> {
+        let now = chrono::Utc::now().timestamp();
+        for event in events.into_iter().filter(|event| !event.is_expired(now)) {
+            self.bus.emit(event).await?;
+        }
+        Ok(())
+    }
+
+    /// Assert that an event whose topic matches `pattern` is emitted within `timeout`
+    pub async fn assert_emitted_matching(&self, pattern: &str, timeout: Duration) -> EventEnvelope {
+        self.recorder.assert_emitted_matching(pattern, timeout).await
+    }
+
+    /// Assert that no event emitted so far matches `pattern`
+    pub fn assert_none_matching(&self, pattern: &str) {
+        self.recorder.assert_none_matching(pattern)
+    }
+}
@@ -0,0 +1,100 @@
+//! Test support for event-driven code
+//!
+//! Available with the `test-utils` feature. Provides an in-process bus
+//! harness with a deterministic clock, topic collectors, event factories and
+//! assertion helpers, so downstream crates can test event-driven logic
+//! without sleeps or real storage.
+//!
+//! ```rust,no_run
+//! use std::time::Duration;
+//! use eventbus_rust::test_support::BusHarness;
+//!
+//! # async fn example() -> eventbus_rust::EventBusResult<()> {
+//! let harness = BusHarness::new().await?;
+//! let orders = harness.capture_topic("orders.*").await?;
+//!
+//! harness.emit("orders.created", serde_json::json!({"id": 1})).await?;
+//!
+//! orders.assert_emitted_matching("orders.created", Duration::from_secs(1)).await;
+//! # Ok(())
+//! # }
+//! ```
+
+mod clock;
+mod factory;
+mod capture;
+mod harness;
+
+pub use clock::{TestClock, DEFAULT_START_TIMESTAMP};
+pub use factory::EventFactory;
+pub use capture::TopicCapture;
+pub use harness::BusHarness;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use serde_json::json;
+
+    const WAIT: Duration = Duration::from_secs(1);
+
+    #[test]
+    fn test_factory_is_deterministic() {
+        let clock = TestClock::new(1_000);
+        let factory = EventFactory::new(clock.clone()).with_prefix("order");
+
+        let first = factory.event("orders.created", json!({}));
+        clock.advance(Duration::from_secs(5));
+        let second = factory.event("orders.created", json!({}));
+
+        assert_eq!(first.event_id, "order-1");
+        assert_eq!(first.timestamp, 1_000);
+        assert_eq!(second.event_id, "order-2");
+        assert_eq!(second.timestamp, 1_005);
+        assert_eq!(second.sequence_number, Some(2));
+    }
+
+    #[tokio::test]
+    async fn test_capture_and_assert() {
+        let harness = BusHarness::new().await.unwrap();
+        let orders = harness.capture_topic("orders.*").await.unwrap();
+
+        harness.emit("users.created", json!({})).await.unwrap();
+        harness.emit("orders.created", json!({"id": 1})).await.unwrap();
+
+        let event = orders.assert_emitted_matching("orders.created", WAIT).await;
+        assert_eq!(event.payload["id"], 1);
+        assert!(orders.wait_for_count(1, WAIT).await);
+        orders.assert_none_matching("users.*");
+
+        harness.assert_emitted_matching("users.created", WAIT).await;
+    }
+
+    #[tokio::test]
+    async fn test_wait_times_out_without_match() {
+        let harness = BusHarness::new().await.unwrap();
+        let capture = harness.capture_topic("*").await.unwrap();
+
+        let found = capture.wait_for(Duration::from_millis(20), |event| event.topic == "missing").await;
+        assert!(found.is_none());
+        assert!(capture.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_replay_reproduces_recording() {
+        let original = BusHarness::new().await.unwrap();
+        for topic in ["a", "b", "c"] {
+            original.emit(topic, json!({})).await.unwrap();
+        }
+        original.assert_emitted_matching("c", WAIT).await;
+
+        let replayed = BusHarness::new().await.unwrap();
+        replayed.replay(original.recorded()).await.unwrap();
+        replayed.assert_emitted_matching("c", WAIT).await;
+
+        let ids = |events: Vec<crate::core::EventEnvelope>| {
+            events.into_iter().map(|event| event.event_id).collect::<Vec<_>>()
+        };
+        assert_eq!(ids(original.recorded()), ids(replayed.recorded()));
+    }
+}
@@ -9,6 +9,4 @@ pub use event_utils::*;
 pub use trn_utils::*;
 pub use topic_utils::*;
 
-// Testing utilities will be implemented later
-// #[cfg(test)]
-// pub mod test_utils; 
\ No newline at end of file
+// Testing utilities live in `crate::test_support` (feature `test-utils`) 
\ No newline at end of file
@@ -182,6 +182,14 @@ pub fn trn_matches(event: &EventEnvelope, rule: &EventTriggerRule) -> EventBusRe
     Ok(true)
 }
 
+/// Check if an event's source or target TRN matches any of the patterns
+pub fn event_matches_trn_patterns(event: &EventEnvelope, patterns: &trn_rust::TrnPatternSet) -> bool {
+    [&event.source_trn, &event.target_trn]
+        .into_iter()
+        .flatten()
+        .any(|trn| patterns.matches_str(trn))
+}
+
 /// Validate a TRN string with caching
 pub fn validate_trn(trn: &str) -> EventBusResult<()> {
     // Check cache first
@@ -99,7 +99,7 @@ async fn test_eventbus_service_with_jsonrpc_wrapper() {
     // Test server handler methods
     use eventbus_rust::jsonrpc::methods::*;
     
-    let emit_params = EmitParams { event: event.clone() };
+    let emit_params = EmitParams { event: event.clone(), idempotency_key: None, queue_timeout_ms: None };
     let emit_result = rpc_server.handle_emit(emit_params).await;
     assert!(emit_result.is_ok(), "Emit handler should work");
     
@@ -110,4 +110,523 @@ async fn test_eventbus_service_with_jsonrpc_wrapper() {
     assert!(stats_result.is_ok(), "Get stats handler should work");
 
     println!("✅ JSON-RPC integration test completed successfully");
-} 
\ No newline at end of file
+} 
+/// Send a single length-prefixed JSON-RPC request and read the response
+async fn call_over_tcp(
+    framed: &mut tokio_util::codec::Framed<tokio::net::TcpStream, tokio_util::codec::LengthDelimitedCodec>,
+    request: serde_json::Value,
+) -> serde_json::Value {
+    use futures::{SinkExt, StreamExt};
+
+    framed.send(serde_json::to_vec(&request).unwrap().into()).await
+        .expect("Failed to send request");
+    let frame = framed.next().await
+        .expect("Connection closed")
+        .expect("Failed to read response");
+    serde_json::from_slice(&frame).expect("Invalid response JSON")
+}
+
+#[tokio::test]
+async fn test_jsonrpc_server_over_tcp() {
+    let event_bus_service = Arc::new(EventBusService::new(ServiceConfig::default()));
+    let rpc_server = EventBusRpcServer::new(Arc::clone(&event_bus_service));
+    let handle = rpc_server.spawn("127.0.0.1:0".parse().unwrap()).await
+        .expect("Failed to start server");
+
+    let stream = tokio::net::TcpStream::connect(handle.local_addr()).await
+        .expect("Failed to connect");
+    let mut framed = tokio_util::codec::Framed::new(stream, tokio_util::codec::LengthDelimitedCodec::new());
+
+    let event = EventEnvelope::new("tcp.test", serde_json::json!({"over": "tcp"}));
+    let response = call_over_tcp(&mut framed, serde_json::json!({
+        "jsonrpc": "2.0",
+        "method": "eventbus.emit",
+        "params": {"event": event},
+        "id": 1
+    })).await;
+    assert_eq!(response["id"], 1);
+    assert_eq!(response["result"]["success"], true);
+
+    let response = call_over_tcp(&mut framed, serde_json::json!({
+        "jsonrpc": "2.0",
+        "method": "eventbus.poll",
+        "params": {"query": EventQuery::new().with_topic("tcp.test")},
+        "id": 2
+    })).await;
+    assert_eq!(response["result"]["total_count"], 1);
+
+    let response = call_over_tcp(&mut framed, serde_json::json!({
+        "jsonrpc": "2.0",
+        "method": "eventbus.get_stats",
+        "id": 3
+    })).await;
+    assert_eq!(response["result"]["stats"]["events_processed"], 1);
+
+    let response = call_over_tcp(&mut framed, serde_json::json!({
+        "jsonrpc": "2.0",
+        "method": "eventbus.unknown",
+        "id": 4
+    })).await;
+    assert_eq!(response["error"]["code"], -32601);
+
+    let response = call_over_tcp(&mut framed, serde_json::json!({
+        "jsonrpc": "2.0",
+        "method": "eventbus.emit",
+        "params": {"wrong": true},
+        "id": 5
+    })).await;
+    assert_eq!(response["error"]["code"], -32602);
+
+    handle.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_jsonrpc_poll_wait_over_tcp() {
+    let event_bus_service = Arc::new(EventBusService::new(ServiceConfig::default()));
+    let rpc_server = EventBusRpcServer::new(Arc::clone(&event_bus_service));
+    let handle = rpc_server.spawn("127.0.0.1:0".parse().unwrap()).await
+        .expect("Failed to start server");
+
+    let stream = tokio::net::TcpStream::connect(handle.local_addr()).await
+        .expect("Failed to connect");
+    let mut framed = tokio_util::codec::Framed::new(stream, tokio_util::codec::LengthDelimitedCodec::new());
+
+    // The request is held open until an event is emitted
+    let publisher = {
+        let service = Arc::clone(&event_bus_service);
+        tokio::spawn(async move {
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+            service.emit(EventEnvelope::new("long.poll", serde_json::json!({"n": 1}))).await.unwrap();
+        })
+    };
+    let response = call_over_tcp(&mut framed, serde_json::json!({
+        "jsonrpc": "2.0",
+        "method": "eventbus.poll_wait",
+        "params": {"query": EventQuery::new().with_topic("long.*"), "timeout_ms": 5000},
+        "id": 1
+    })).await;
+    publisher.await.unwrap();
+    assert_eq!(response["result"]["total_count"], 1);
+    assert_eq!(response["result"]["events"][0]["topic"], "long.poll");
+
+    let response = call_over_tcp(&mut framed, serde_json::json!({
+        "jsonrpc": "2.0",
+        "method": "eventbus.poll_wait",
+        "params": {"query": EventQuery::new().with_topic("quiet.*"), "timeout_ms": 20},
+        "id": 2
+    })).await;
+    assert_eq!(response["result"]["total_count"], 0);
+
+    handle.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_jsonrpc_emit_stamps_ingest_metadata() {
+    use jsonrpc_rust::core::types::{AuthContext, ServiceContext};
+
+    let event_bus_service = Arc::new(EventBusService::new(ServiceConfig::default()));
+    let rpc_server = EventBusRpcServer::new(Arc::clone(&event_bus_service));
+    let handle = rpc_server.spawn("127.0.0.1:0".parse().unwrap()).await
+        .expect("Failed to start server");
+
+    let stream = tokio::net::TcpStream::connect(handle.local_addr()).await
+        .expect("Failed to connect");
+    let client_addr = stream.local_addr().unwrap().to_string();
+    let mut framed = tokio_util::codec::Framed::new(stream, tokio_util::codec::LengthDelimitedCodec::new());
+
+    // A client-supplied ingest section is replaced
+    let event = EventEnvelope::new("ingest.tcp", serde_json::json!({}))
+        .with_metadata(serde_json::json!({"ingest": {"auth_user": "mallory"}, "origin": "test"}));
+    let response = call_over_tcp(&mut framed, serde_json::json!({
+        "jsonrpc": "2.0",
+        "method": "eventbus.emit",
+        "params": {"event": event},
+        "id": "emit-1"
+    })).await;
+    assert_eq!(response["result"]["success"], true);
+
+    let stored = event_bus_service.poll(EventQuery::new().with_topic("ingest.tcp")).await.unwrap();
+    let ingest = stored[0].ingest().expect("ingest metadata");
+    assert_eq!(ingest.request_id, "emit-1");
+    assert_eq!(ingest.client_addr.as_deref(), Some(client_addr.as_str()));
+    assert_eq!(ingest.auth_user, None);
+    assert_eq!(stored[0].metadata.as_ref().unwrap()["origin"], "test");
+
+    // Hosted as a method handler, the caller's authenticated context is recorded
+    let context = ServiceContext::new("req-42").with_auth_context(AuthContext::new("alice", "bearer"));
+    let request = JsonRpcRequest::new(
+        "eventbus.emit_batch",
+        Some(serde_json::json!({"events": [EventEnvelope::new("ingest.handler", serde_json::json!({}))]})),
+    );
+    let response = rpc_server.handle_method(&request, &context).await.unwrap();
+    assert!(response.error.is_none());
+
+    let stored = event_bus_service.poll(EventQuery::new().with_topic("ingest.handler")).await.unwrap();
+    let ingest = stored[0].ingest().expect("ingest metadata");
+    assert_eq!(ingest.request_id, "req-42");
+    assert_eq!(ingest.auth_user.as_deref(), Some("alice"));
+    assert_eq!(ingest.auth_method.as_deref(), Some("bearer"));
+    assert_eq!(ingest.client_addr, None);
+
+    handle.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_jsonrpc_work_queue_over_tcp() {
+    let event_bus_service = Arc::new(EventBusService::new(ServiceConfig::default()));
+    let handle = EventBusRpcServer::new(Arc::clone(&event_bus_service))
+        .spawn("127.0.0.1:0".parse().unwrap()).await
+        .expect("Failed to start server");
+
+    let stream = tokio::net::TcpStream::connect(handle.local_addr()).await
+        .expect("Failed to connect");
+    let mut framed = tokio_util::codec::Framed::new(stream, tokio_util::codec::LengthDelimitedCodec::new());
+    let response = call_over_tcp(&mut framed, serde_json::json!({
+        "jsonrpc": "2.0",
+        "method": "eventbus.create_queue",
+        "params": {"group": "workers", "topic": "workflow.task.*", "max_in_flight": 2},
+        "id": 1
+    })).await;
+    assert_eq!(response["result"]["queue"]["config"]["strategy"], "round_robin");
+
+    for n in 0..3 {
+        event_bus_service.emit(EventEnvelope::new("workflow.task.run", serde_json::json!({"n": n}))).await.unwrap();
+    }
+
+    // Each worker leases disjoint events, up to its in-flight limit
+    let mut leased = Vec::new();
+    for consumer in ["worker-1", "worker-2"] {
+        let response = call_over_tcp(&mut framed, serde_json::json!({
+            "jsonrpc": "2.0",
+            "method": "eventbus.queue_fetch",
+            "params": {"group": "workers", "consumer_id": consumer, "max_events": 5},
+            "id": 2
+        })).await;
+        leased.extend(response["result"]["deliveries"].as_array().unwrap().clone());
+    }
+    assert_eq!(leased.len(), 3);
+    let mut ns: Vec<u64> = leased.iter().map(|d| d["event"]["payload"]["n"].as_u64().unwrap()).collect();
+    ns.sort();
+    assert_eq!(ns, vec![0, 1, 2]);
+
+    for delivery in &leased {
+        let response = call_over_tcp(&mut framed, serde_json::json!({
+            "jsonrpc": "2.0",
+            "method": "eventbus.queue_ack",
+            "params": {"group": "workers", "delivery_id": delivery["delivery_id"]},
+            "id": 3
+        })).await;
+        assert_eq!(response["result"]["success"], true);
+    }
+
+    let response = call_over_tcp(&mut framed, serde_json::json!({
+        "jsonrpc": "2.0",
+        "method": "eventbus.queue_ack",
+        "params": {"group": "workers", "delivery_id": leased[0]["delivery_id"]},
+        "id": 4
+    })).await;
+    assert_eq!(response["error"]["code"], -32009);
+
+    let response = call_over_tcp(&mut framed, serde_json::json!({
+        "jsonrpc": "2.0",
+        "method": "eventbus.queue_stats",
+        "params": {"group": "workers"},
+        "id": 5
+    })).await;
+    assert_eq!(response["result"]["queue"]["acked"], 3);
+    assert_eq!(response["result"]["queue"]["pending"], 0);
+
+    handle.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_jsonrpc_filtered_subscription_over_tcp() {
+    let event_bus_service = Arc::new(EventBusService::new(ServiceConfig::default()));
+    let handle = EventBusRpcServer::new(Arc::clone(&event_bus_service))
+        .spawn("127.0.0.1:0".parse().unwrap()).await
+        .expect("Failed to start server");
+
+    let stream = tokio::net::TcpStream::connect(handle.local_addr()).await
+        .expect("Failed to connect");
+    let mut framed = tokio_util::codec::Framed::new(stream, tokio_util::codec::LengthDelimitedCodec::new());
+
+    let response = call_over_tcp(&mut framed, serde_json::json!({
+        "jsonrpc": "2.0",
+        "method": "eventbus.subscribe",
+        "params": {"topic": "alerts.*", "filter": "payload.priority =="},
+        "id": 1
+    })).await;
+    assert_eq!(response["error"]["code"], -32602);
+
+    let response = call_over_tcp(&mut framed, serde_json::json!({
+        "jsonrpc": "2.0",
+        "method": "eventbus.subscribe",
+        "params": {"topic": "alerts.*", "filter": "payload.priority == \"high\""},
+        "id": 2
+    })).await;
+    let subscription_id = response["result"]["subscription_id"].clone();
+
+    // Only the matching event reaches the subscriber
+    let publisher = {
+        let service = Arc::clone(&event_bus_service);
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            for priority in ["low", "high", "low"] {
+                service.emit(EventEnvelope::new("alerts.disk", serde_json::json!({"priority": priority}))).await.unwrap();
+            }
+        })
+    };
+    let response = call_over_tcp(&mut framed, serde_json::json!({
+        "jsonrpc": "2.0",
+        "method": "eventbus.get_subscription_events",
+        "params": {"subscription_id": subscription_id, "max_events": 3, "timeout_ms": 300},
+        "id": 3
+    })).await;
+    publisher.await.unwrap();
+    let events = response["result"]["events"].as_array().unwrap();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0]["payload"]["priority"], "high");
+
+    handle.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_jsonrpc_server_rejects_oversized_messages() {
+    use futures::StreamExt;
+
+    let service_config = ServiceConfig {
+        transport: eventbus_rust::config::TransportConfig {
+            max_message_size: 64,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let event_bus_service = Arc::new(EventBusService::new(service_config));
+    let handle = EventBusRpcServer::new(event_bus_service)
+        .spawn("127.0.0.1:0".parse().unwrap()).await
+        .expect("Failed to start server");
+
+    let stream = tokio::net::TcpStream::connect(handle.local_addr()).await
+        .expect("Failed to connect");
+    let mut framed = tokio_util::codec::Framed::new(stream, tokio_util::codec::LengthDelimitedCodec::new());
+
+    let event = EventEnvelope::new("tcp.test", serde_json::json!({"padding": "x".repeat(128)}));
+    let request = serde_json::json!({
+        "jsonrpc": "2.0",
+        "method": "eventbus.emit",
+        "params": {"event": event},
+        "id": 1
+    });
+    futures::SinkExt::send(&mut framed, serde_json::to_vec(&request).unwrap().into()).await
+        .expect("Failed to send request");
+
+    // The server drops the connection instead of answering
+    let next = tokio::time::timeout(Duration::from_secs(5), framed.next()).await
+        .expect("Server did not close the connection");
+    assert!(matches!(next, None | Some(Err(_))));
+
+    handle.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_multi_bus_manager_serves_jsonrpc() {
+    use std::collections::HashMap;
+    use eventbus_rust::service::{BusMode, MultiBusConfig, MultiBusManager, GlobalConfig};
+
+    let mut buses = HashMap::new();
+    buses.insert("served".to_string(), ServiceConfig {
+        instance_id: "served".to_string(),
+        listen: Some("127.0.0.1:0".parse().unwrap()),
+        transport: eventbus_rust::config::TransportConfig {
+            admin_token: Some("secret".to_string()),
+            ..Default::default()
+        },
+        ..Default::default()
+    });
+    buses.insert("local".to_string(), ServiceConfig {
+        instance_id: "local".to_string(),
+        ..Default::default()
+    });
+
+    let mut manager = MultiBusManager::new(MultiBusConfig {
+        buses,
+        global: GlobalConfig::default(),
+        default_bus: Some("served".to_string()),
+    }).await.expect("Failed to create manager");
+    manager.start().await.expect("Failed to start manager");
+
+    assert!(manager.listen_addr("local").is_none());
+    let addr = manager.listen_addr("served").expect("Server should be running");
+
+    let stream = tokio::net::TcpStream::connect(addr).await
+        .expect("Failed to connect");
+    let mut framed = tokio_util::codec::Framed::new(stream, tokio_util::codec::LengthDelimitedCodec::new());
+    let response = call_over_tcp(&mut framed, serde_json::json!({
+        "jsonrpc": "2.0",
+        "method": "eventbus.list_topics",
+        "id": "topics"
+    })).await;
+    assert_eq!(response["id"], "topics");
+    assert!(response["result"]["topics"].is_array());
+
+    let topology = manager.describe_topology().await;
+    assert_eq!(topology["default_bus"], "served");
+    assert_eq!(topology["bus_count"], 2);
+    let served = &topology["buses"][1];
+    assert_eq!(served["name"], "served");
+    assert_eq!(served["listener"]["bound"], addr.to_string());
+    assert_eq!(served["connectors"][0]["kind"], "jsonrpc");
+    assert_eq!(served["storage"]["backend"], "memory");
+    assert_eq!(served["health"]["status"], "healthy");
+    assert_eq!(topology["buses"][0]["connectors"], serde_json::json!([]));
+
+    // Modes are switched on every bus together
+    let modes = manager.set_mode(BusMode::ReadOnly, Some("migrating storage".to_string()));
+    assert_eq!(modes.len(), 2);
+    assert_eq!(manager.get_bus("local").unwrap().mode().mode, BusMode::ReadOnly);
+    let event = EventEnvelope::new("mode.test", serde_json::json!({}));
+    let response = call_over_tcp(&mut framed, serde_json::json!({
+        "jsonrpc": "2.0",
+        "method": "eventbus.emit",
+        "params": {"event": event},
+        "id": "emit"
+    })).await;
+    assert_eq!(response["error"]["code"], -32004);
+    assert!(response["error"]["message"].as_str().unwrap().contains("read-only (migrating storage)"));
+    let response = call_over_tcp(&mut framed, serde_json::json!({
+        "jsonrpc": "2.0",
+        "method": "eventbus.list_topics",
+        "id": "topics"
+    })).await;
+    assert!(response["result"]["topics"].is_array());
+
+    // Admin methods need the admin token
+    let response = call_over_tcp(&mut framed, serde_json::json!({
+        "jsonrpc": "2.0",
+        "method": "eventbus.set_mode",
+        "params": {"mode": "maintenance", "admin_token": "guess"},
+        "id": "mode"
+    })).await;
+    assert_eq!(response["error"]["code"], -32011);
+    let response = call_over_tcp(&mut framed, serde_json::json!({
+        "jsonrpc": "2.0",
+        "method": "eventbus.set_mode",
+        "params": {"mode": "maintenance", "admin_token": "secret"},
+        "id": "mode"
+    })).await;
+    assert_eq!(response["result"]["mode"], "maintenance");
+    let response = call_over_tcp(&mut framed, serde_json::json!({
+        "jsonrpc": "2.0",
+        "method": "eventbus.list_topics",
+        "id": "topics"
+    })).await;
+    assert_eq!(response["error"]["code"], -32004);
+    let response = call_over_tcp(&mut framed, serde_json::json!({
+        "jsonrpc": "2.0",
+        "method": "eventbus.no_such_method",
+        "id": "unknown"
+    })).await;
+    assert_eq!(response["error"]["code"], -32004);
+    let response = call_over_tcp(&mut framed, serde_json::json!({
+        "jsonrpc": "2.0",
+        "method": "eventbus.health",
+        "id": "health"
+    })).await;
+    assert_eq!(response["result"]["status"], "healthy");
+    assert_eq!(response["result"]["mode"]["mode"], "maintenance");
+    assert_eq!(manager.modes()["local"].mode, BusMode::ReadOnly);
+
+    manager.set_mode(BusMode::Normal, None);
+    let response = call_over_tcp(&mut framed, serde_json::json!({
+        "jsonrpc": "2.0",
+        "method": "eventbus.emit",
+        "params": {"event": EventEnvelope::new("mode.test", serde_json::json!({}))},
+        "id": "emit"
+    })).await;
+    assert_eq!(response["result"]["success"], true);
+
+    manager.stop().await.expect("Failed to stop manager");
+    assert!(manager.listen_addr("served").is_none());
+
+    sleep(Duration::from_millis(50)).await;
+    assert!(tokio::net::TcpStream::connect(addr).await.is_err());
+}
+
+#[tokio::test]
+async fn test_replication_follower_over_tcp() {
+    use eventbus_rust::replication::{ReplicationConfig, ReplicationRole};
+
+    let leader = Arc::new(EventBusService::new(ServiceConfig {
+        instance_id: "leader".to_string(),
+        replication: ReplicationConfig {
+            topics: vec!["orders.*".to_string()],
+            ..Default::default()
+        },
+        ..Default::default()
+    }));
+    let leader_server = EventBusRpcServer::new(Arc::clone(&leader));
+    let leader_handle = leader_server.spawn("127.0.0.1:0".parse().unwrap()).await
+        .expect("Failed to start leader");
+
+    let follower = Arc::new(EventBusService::new(ServiceConfig {
+        instance_id: "follower".to_string(),
+        replication: ReplicationConfig {
+            leader: Some(leader_handle.local_addr().to_string()),
+            ..Default::default()
+        },
+        transport: eventbus_rust::config::TransportConfig {
+            admin_token: Some("secret".to_string()),
+            ..Default::default()
+        },
+        ..Default::default()
+    }));
+    let follower_server = EventBusRpcServer::new(Arc::clone(&follower));
+    let follower_handle = follower_server.spawn("127.0.0.1:0".parse().unwrap()).await
+        .expect("Failed to start follower");
+
+    let order = EventEnvelope::new("orders.created", serde_json::json!({"order_id": 42}));
+    leader.emit(order.clone()).await.unwrap();
+    leader.emit(EventEnvelope::new("users.created", serde_json::json!({}))).await.unwrap();
+    assert_eq!(follower.sync_replication().await.unwrap(), 1);
+    let replicated = follower.poll(EventQuery::new()).await.unwrap();
+    assert_eq!(replicated.len(), 1);
+    assert_eq!(replicated[0].event_id, order.event_id);
+
+    // The follower is read-only until promoted over JSON-RPC
+    let stream = tokio::net::TcpStream::connect(follower_handle.local_addr()).await
+        .expect("Failed to connect");
+    let mut framed = tokio_util::codec::Framed::new(stream, tokio_util::codec::LengthDelimitedCodec::new());
+    let response = call_over_tcp(&mut framed, serde_json::json!({
+        "jsonrpc": "2.0",
+        "method": "eventbus.emit",
+        "params": {"event": EventEnvelope::new("orders.created", serde_json::json!({}))},
+        "id": 1
+    })).await;
+    assert!(response["error"].is_object());
+
+    let response = call_over_tcp(&mut framed, serde_json::json!({
+        "jsonrpc": "2.0",
+        "method": "eventbus.replication_status",
+        "id": 2
+    })).await;
+    assert_eq!(response["result"]["replication"]["role"], "follower");
+    assert_eq!(response["result"]["replication"]["position"], 1);
+    assert_eq!(response["result"]["replication"]["lag_events"], 0);
+
+    leader_handle.shutdown().await;
+    let response = call_over_tcp(&mut framed, serde_json::json!({
+        "jsonrpc": "2.0",
+        "method": "eventbus.promote",
+        "id": 3
+    })).await;
+    assert_eq!(response["error"]["code"], -32011);
+    let response = call_over_tcp(&mut framed, serde_json::json!({
+        "jsonrpc": "2.0",
+        "method": "eventbus.promote",
+        "params": {"admin_token": "secret"},
+        "id": 4
+    })).await;
+    assert_eq!(response["result"]["replication"]["role"], "promoted");
+    assert_eq!(follower.replication_status().unwrap().role, ReplicationRole::Promoted);
+    follower.emit(EventEnvelope::new("orders.created", serde_json::json!({}))).await.unwrap();
+}
@@ -0,0 +1,228 @@
+//! Procedural macros for jsonrpc-rust
+//!
+//! Use them through `jsonrpc_rust` with the `macros` feature rather than
+//! depending on this crate directly; the generated code refers to
+//! `::jsonrpc_rust` paths.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::parse::{Parse, ParseStream};
+use syn::spanned::Spanned;
+use syn::{
+    Error, Expr, ExprLit, FnArg, GenericArgument, Ident, ItemFn, Lit, LitStr, Meta, PathArguments, ReturnType,
+    Token, Type,
+};
+
+/// Serve a JSON-RPC method with an async function
+///
+/// ```ignore
+/// use jsonrpc_rust::convenience::rpc_method;
+///
+/// /// Add two numbers
+/// #[rpc_method("math.add")]
+/// async fn add(params: AddParams) -> jsonrpc_rust::Result<i64> {
+///     Ok(params.a + params.b)
+/// }
+///
+/// // Generated: `AddHandler`, a `MethodHandler` serving `math.add`
+/// ```
+///
+/// The function is kept as written. Next to it the macro generates a unit
+/// struct named after it (`add` becomes `AddHandler`) implementing
+/// `MethodHandler` and `RpcMethod`:
+///
+/// - The function may take a params argument of any `Deserialize +
+///   JsonSchema` type, followed by an optional `&ServiceContext`; params
+///   that do not deserialize are answered with an `invalid_params` error,
+///   as are params sent to a function that takes none
+/// - It must return a `Result` whose value is `Serialize + JsonSchema` and
+///   whose error converts into `jsonrpc_rust::Error`
+/// - The method description comes from the doc comments, or from a
+///   `description = "..."` argument
+#[proc_macro_attribute]
+pub fn rpc_method(args: TokenStream, item: TokenStream) -> TokenStream {
+    let args = syn::parse_macro_input!(args as MethodArgs);
+    let function = syn::parse_macro_input!(item as ItemFn);
+    expand(args, function)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// Arguments of `#[rpc_method(...)]`
+struct MethodArgs {
+    method: LitStr,
+    description: Option<LitStr>,
+}
+
+impl Parse for MethodArgs {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let method: LitStr = input.parse()
+            .map_err(|e| Error::new(e.span(), "expected the method name, as in #[rpc_method(\"math.add\")]"))?;
+        if method.value().is_empty() {
+            return Err(Error::new(method.span(), "method name cannot be empty"));
+        }
+
+        let mut description = None;
+        while !input.is_empty() {
+            input.parse::<Token![,]>()?;
+            if input.is_empty() {
+                break;
+            }
+            let key: Ident = input.parse()?;
+            input.parse::<Token![=]>()?;
+            match key.to_string().as_str() {
+                "description" => description = Some(input.parse()?),
+                _ => return Err(Error::new(key.span(), format!("unknown rpc_method argument `{}`", key))),
+            }
+        }
+
+        Ok(Self { method, description })
+    }
+}
+
+fn expand(args: MethodArgs, function: ItemFn) -> syn::Result<TokenStream2> {
+    let signature = &function.sig;
+    if signature.asyncness.is_none() {
+        return Err(Error::new(signature.fn_token.span(), "rpc_method functions must be async"));
+    }
+    if !signature.generics.params.is_empty() {
+        return Err(Error::new(signature.generics.span(), "rpc_method functions cannot be generic"));
+    }
+
+    let mut params_type = None;
+    let mut takes_context = false;
+    for input in &signature.inputs {
+        let FnArg::Typed(argument) = input else {
+            return Err(Error::new(input.span(), "rpc_method functions cannot take self"));
+        };
+        if takes_context {
+            return Err(Error::new(input.span(), "the &ServiceContext argument must come last"));
+        }
+        match &*argument.ty {
+            Type::Reference(_) => takes_context = true,
+            _ if params_type.is_some() => {
+                return Err(Error::new(input.span(), "rpc_method functions take a single params argument"));
+            }
+            ty => params_type = Some(ty.clone()),
+        }
+    }
+    let result_type = result_type(&signature.output)?;
+
+    let name = &signature.ident;
+    let visibility = &function.vis;
+    let handler = format_ident!("{}Handler", camel_case(&name.to_string()), span = name.span());
+    let method = &args.method;
+    let description = args.description
+        .map(|description| description.value())
+        .unwrap_or_else(|| doc_comment(&function));
+    let handler_doc = format!("Serves `{}` with [`{}`]", method.value(), name);
+
+    let rpc = quote!(::jsonrpc_rust::__private);
+    let (parse_params, params_schema) = match &params_type {
+        Some(ty) => (
+            quote!(let __params: #ty = #rpc::parse_params(request.params.clone())?;),
+            quote!(::std::option::Option::Some(#rpc::schema_of::<#ty>())),
+        ),
+        None => (
+            quote!(#rpc::no_params(&request.params)?;),
+            quote!(::std::option::Option::None),
+        ),
+    };
+    let mut call_args = Vec::new();
+    if params_type.is_some() {
+        call_args.push(quote!(__params));
+    }
+    if takes_context {
+        call_args.push(quote!(context));
+    }
+
+    Ok(quote! {
+        #function
+
+        #[doc = #handler_doc]
+        #[derive(Debug, Clone, Copy, Default)]
+        #visibility struct #handler;
+
+        impl #handler {
+            /// Name of the method served
+            pub const METHOD: &'static str = #method;
+        }
+
+        #[#rpc::async_trait]
+        impl ::jsonrpc_rust::core::traits::MethodHandler for #handler {
+            async fn handle_method(
+                &self,
+                request: &::jsonrpc_rust::core::types::JsonRpcRequest,
+                context: &::jsonrpc_rust::core::types::ServiceContext,
+            ) -> ::jsonrpc_rust::core::error::Result<::jsonrpc_rust::core::types::JsonRpcResponse> {
+                let _ = context;
+                #parse_params
+                let result: #result_type = #name(#(#call_args),*).await?;
+                #rpc::respond(request, result)
+            }
+
+            fn supported_methods(&self) -> ::std::vec::Vec<::std::string::String> {
+                ::std::vec![::std::string::String::from(#method)]
+            }
+        }
+
+        impl ::jsonrpc_rust::convenience::RpcMethod for #handler {
+            fn method_info(&self) -> ::jsonrpc_rust::core::types::MethodInfo {
+                #rpc::method_info(#method, #description, #params_schema, #rpc::schema_of::<#result_type>())
+            }
+        }
+    })
+}
+
+/// The `T` of a `Result<T>` or `Result<T, E>` return type
+fn result_type(output: &ReturnType) -> syn::Result<Type> {
+    let error = || Error::new(output.span(), "rpc_method functions must return a Result");
+    let ReturnType::Type(_, ty) = output else {
+        return Err(error());
+    };
+    let Type::Path(path) = &**ty else {
+        return Err(error());
+    };
+    let segment = path.path.segments.last().ok_or_else(error)?;
+    if segment.ident != "Result" {
+        return Err(error());
+    }
+    let PathArguments::AngleBracketed(arguments) = &segment.arguments else {
+        return Err(error());
+    };
+    match arguments.args.first() {
+        Some(GenericArgument::Type(ty)) => Ok(ty.clone()),
+        _ => Err(error()),
+    }
+}
+
+/// The doc comments of a function, one line each
+fn doc_comment(function: &ItemFn) -> String {
+    let lines: Vec<String> = function.attrs.iter()
+        .filter(|attr| attr.path().is_ident("doc"))
+        .filter_map(|attr| match &attr.meta {
+            Meta::NameValue(doc) => match &doc.value {
+                Expr::Lit(ExprLit { lit: Lit::Str(text), .. }) => Some(text.value().trim().to_string()),
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect();
+    lines.join("\n").trim().to_string()
+}
+
+/// `snake_case` to `CamelCase`
+fn camel_case(name: &str) -> String {
+    name.trim_start_matches("r#")
+        .split('_')
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let mut chars = part.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
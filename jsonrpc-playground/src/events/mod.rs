@@ -1,14 +1,27 @@
 //! Event System Module
-//! 
-//! Provides event-driven architecture with pub/sub patterns for system-wide events
+//!
+//! Backs the playground's system-wide events with an embedded
+//! `eventbus_rust::EventBusService` (memory storage), exposing its real
+//! emit/poll/subscribe through the `/api/events/*` routes
 
-use std::collections::HashMap;
+use std::convert::Infallible;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use async_trait::async_trait;
+use axum::{
+    extract::{Json, Query},
+    http::StatusCode,
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse,
+    },
+};
+use eventbus_rust::{BusPlugin, EventBus, EventBusResult, EventBusService, EventEnvelope, EventQuery, ServiceConfig};
+use futures::stream::{Stream, StreamExt};
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
-use tokio::sync::{mpsc, RwLock};
+use serde_json::{json, Value};
 use tracing::{info, debug, error};
-use uuid::Uuid;
 
 /// Event types in the system
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
@@ -27,6 +40,26 @@ pub enum EventType {
     Custom(String),
 }
 
+impl EventType {
+    /// Event bus topic for this event type
+    pub fn topic(&self) -> String {
+        match self {
+            EventType::JsonRpcRequest => "jsonrpc.request".to_string(),
+            EventType::JsonRpcResponse => "jsonrpc.response".to_string(),
+            EventType::WebSocketConnect => "websocket.connect".to_string(),
+            EventType::WebSocketDisconnect => "websocket.disconnect".to_string(),
+            EventType::WebSocketMessage => "websocket.message".to_string(),
+            EventType::SseConnect => "sse.connect".to_string(),
+            EventType::SseDisconnect => "sse.disconnect".to_string(),
+            EventType::SystemStats => "system.stats".to_string(),
+            EventType::UserAction => "user.action".to_string(),
+            EventType::ServiceStart => "service.start".to_string(),
+            EventType::ServiceStop => "service.stop".to_string(),
+            EventType::Custom(name) => format!("custom.{}", name),
+        }
+    }
+}
+
 /// Event severity levels
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum EventLevel {
@@ -37,330 +70,292 @@ pub enum EventLevel {
     Critical,
 }
 
-/// System event structure
-#[derive(Debug, Clone, Serialize)]
-pub struct SystemEvent {
-    pub id: String,
-    pub event_type: EventType,
-    pub level: EventLevel,
-    pub timestamp: chrono::DateTime<chrono::Utc>,
-    pub source: String,
-    pub data: Value,
-    pub tags: Vec<String>,
-}
-
-impl SystemEvent {
-    #[allow(dead_code)]
-    pub fn new(event_type: EventType, level: EventLevel, source: String, data: Value) -> Self {
-        Self {
-            id: Uuid::new_v4().to_string(),
-            event_type,
-            level,
-            timestamp: chrono::Utc::now(),
-            source,
-            data,
-            tags: Vec::new(),
-        }
-    }
-
-    #[allow(dead_code)]
-    pub fn with_tags(mut self, tags: Vec<String>) -> Self {
-        self.tags = tags;
-        self
-    }
-}
-
-/// Event subscriber callback
-#[allow(dead_code)]
-pub type EventCallback = Box<dyn Fn(&SystemEvent) + Send + Sync>;
-
-/// Event subscriber
-#[allow(dead_code)]
-pub struct EventSubscriber {
-    pub id: String,
-    pub event_types: Vec<EventType>,
-    pub sender: mpsc::UnboundedSender<SystemEvent>,
+/// Build a system event envelope; level, source and tags travel as metadata
+pub fn system_event(event_type: EventType, level: EventLevel, source: &str, data: Value, tags: Vec<String>) -> EventEnvelope {
+    EventEnvelope::new(event_type.topic(), data).with_metadata(json!({
+        "level": level,
+        "source": source,
+        "tags": tags
+    }))
 }
 
-/// Event bus for pub/sub functionality
-pub struct EventBus {
-    subscribers: Arc<RwLock<HashMap<String, EventSubscriber>>>,
-    event_log: Arc<RwLock<Vec<SystemEvent>>>,
-    max_log_size: usize,
+/// Numbers events in emit order; SSE streams use the number as the event id
+/// so clients can resume with `Last-Event-ID`
+#[derive(Default)]
+struct SequencePlugin {
+    last: AtomicU64,
 }
 
-#[allow(dead_code)]
-impl EventBus {
-    pub fn new(max_log_size: usize) -> Self {
-        Self {
-            subscribers: Arc::new(RwLock::new(HashMap::new())),
-            event_log: Arc::new(RwLock::new(Vec::new())),
-            max_log_size,
-        }
+#[async_trait]
+impl BusPlugin for SequencePlugin {
+    fn name(&self) -> &str {
+        "playground-sequence"
     }
 
-    /// Subscribe to specific event types
-    pub async fn subscribe(&self, event_types: Vec<EventType>) -> (String, mpsc::UnboundedReceiver<SystemEvent>) {
-        let subscriber_id = Uuid::new_v4().to_string();
-        let (tx, rx) = mpsc::unbounded_channel();
-
-        let subscriber = EventSubscriber {
-            id: subscriber_id.clone(),
-            event_types,
-            sender: tx,
-        };
-
-        self.subscribers.write().await.insert(subscriber_id.clone(), subscriber);
-        info!("New event subscriber registered: {}", subscriber_id);
-
-        (subscriber_id, rx)
-    }
-
-    /// Unsubscribe from events
-    pub async fn unsubscribe(&self, subscriber_id: &str) {
-        if self.subscribers.write().await.remove(subscriber_id).is_some() {
-            info!("Event subscriber unregistered: {}", subscriber_id);
+    async fn before_emit(&self, event: &mut EventEnvelope) -> EventBusResult<()> {
+        if event.sequence_number.is_none() {
+            event.sequence_number = Some(self.last.fetch_add(1, Ordering::Relaxed) + 1);
         }
+        Ok(())
     }
+}
 
-    /// Publish event to all matching subscribers
-    pub async fn publish(&self, event: SystemEvent) {
-        debug!("Publishing event: {:?} from {}", event.event_type, event.source);
-
-        // Add to event log
-        {
-            let mut log = self.event_log.write().await;
-            log.push(event.clone());
-            
-            // Trim log if it exceeds max size
-            if log.len() > self.max_log_size {
-                let excess = log.len() - self.max_log_size;
-                log.drain(0..excess);
-            }
-        }
-
-        // Send to matching subscribers
-        let subscribers = self.subscribers.read().await;
-        for subscriber in subscribers.values() {
-            if subscriber.event_types.contains(&event.event_type) || 
-               subscriber.event_types.contains(&EventType::Custom("*".to_string())) {
-                if let Err(e) = subscriber.sender.send(event.clone()) {
-                    error!("Failed to send event to subscriber {}: {}", subscriber.id, e);
-                }
-            }
-        }
-    }
-
-    /// Get recent events from log
-    pub async fn get_recent_events(&self, limit: Option<usize>) -> Vec<SystemEvent> {
-        let log = self.event_log.read().await;
-        let count = limit.unwrap_or(100).min(log.len());
-        log.iter().rev().take(count).cloned().collect()
-    }
-
-    /// Get events by type
-    pub async fn get_events_by_type(&self, event_type: &EventType, limit: Option<usize>) -> Vec<SystemEvent> {
-        let log = self.event_log.read().await;
-        let count = limit.unwrap_or(100);
-        
-        log.iter()
-            .rev()
-            .filter(|event| &event.event_type == event_type)
-            .take(count)
-            .cloned()
-            .collect()
-    }
+lazy_static::lazy_static! {
+    pub static ref GLOBAL_EVENT_BUS: EventBusService = EventBusService::new(ServiceConfig {
+        instance_id: "jsonrpc-playground".to_string(),
+        ..ServiceConfig::default()
+    })
+    .with_plugin(Arc::new(SequencePlugin::default()));
+}
 
-    /// Get events by level
-    pub async fn get_events_by_level(&self, level: &EventLevel, limit: Option<usize>) -> Vec<SystemEvent> {
-        let log = self.event_log.read().await;
-        let count = limit.unwrap_or(100);
-        
-        log.iter()
-            .rev()
-            .filter(|event| std::mem::discriminant(&event.level) == std::mem::discriminant(level))
-            .take(count)
-            .cloned()
-            .collect()
-    }
+/// Publish an event to the global bus, logging failures
+pub async fn publish(event: EventEnvelope) {
+    debug!("Publishing event on topic {}", event.topic);
 
-    /// Get subscriber count
-    pub async fn get_subscriber_count(&self) -> usize {
-        self.subscribers.read().await.len()
+    if let Err(e) = GLOBAL_EVENT_BUS.emit(event).await {
+        error!("Failed to publish event: {}", e);
     }
-
-    /// Get event statistics
-    pub async fn get_event_stats(&self) -> Value {
-        let log = self.event_log.read().await;
-        let total_events = log.len();
-        
-        let mut type_counts: HashMap<String, usize> = HashMap::new();
-        let mut level_counts: HashMap<String, usize> = HashMap::new();
-        
-        for event in log.iter() {
-            let type_name = format!("{:?}", event.event_type);
-            let level_name = format!("{:?}", event.level);
-            
-            *type_counts.entry(type_name).or_insert(0) += 1;
-            *level_counts.entry(level_name).or_insert(0) += 1;
-        }
-        
-        serde_json::json!({
-            "total_events": total_events,
-            "max_log_size": self.max_log_size,
-            "active_subscribers": self.get_subscriber_count().await,
-            "event_types": type_counts,
-            "event_levels": level_counts,
-            "oldest_event": log.first().map(|e| e.timestamp),
-            "newest_event": log.last().map(|e| e.timestamp)
-        })
-    }
-}
-
-lazy_static::lazy_static! {
-    pub static ref GLOBAL_EVENT_BUS: EventBus = EventBus::new(1000);
 }
 
 /// Helper functions for common events
 #[allow(dead_code)]
 /// Publish JsonRPC request event
 pub async fn publish_jsonrpc_request(method: &str, params: &Value, request_id: &str) {
-    let event = SystemEvent::new(
+    publish(system_event(
         EventType::JsonRpcRequest,
         EventLevel::Info,
-        "jsonrpc-server".to_string(),
-        serde_json::json!({
+        "jsonrpc-server",
+        json!({
             "method": method,
             "params": params,
             "request_id": request_id
-        })
-    ).with_tags(vec!["jsonrpc".to_string(), "request".to_string()]);
-
-    GLOBAL_EVENT_BUS.publish(event).await;
+        }),
+        vec!["jsonrpc".to_string(), "request".to_string()],
+    )).await;
 }
 
 #[allow(dead_code)]
 /// Publish JsonRPC response event
 pub async fn publish_jsonrpc_response(method: &str, response: &Value, success: bool, request_id: &str) {
     let level = if success { EventLevel::Info } else { EventLevel::Error };
-    
-    let event = SystemEvent::new(
+
+    publish(system_event(
         EventType::JsonRpcResponse,
         level,
-        "jsonrpc-server".to_string(),
-        serde_json::json!({
+        "jsonrpc-server",
+        json!({
             "method": method,
             "response": response,
             "success": success,
             "request_id": request_id
-        })
-    ).with_tags(vec!["jsonrpc".to_string(), "response".to_string()]);
-
-    GLOBAL_EVENT_BUS.publish(event).await;
+        }),
+        vec!["jsonrpc".to_string(), "response".to_string()],
+    )).await;
 }
 
 #[allow(dead_code)]
 /// Publish WebSocket connection event
 pub async fn publish_websocket_connect(connection_id: &str, client_info: &Value) {
-    let event = SystemEvent::new(
+    publish(system_event(
         EventType::WebSocketConnect,
         EventLevel::Info,
-        "websocket-server".to_string(),
-        serde_json::json!({
+        "websocket-server",
+        json!({
             "connection_id": connection_id,
             "client_info": client_info
-        })
-    ).with_tags(vec!["websocket".to_string(), "connection".to_string()]);
-
-    GLOBAL_EVENT_BUS.publish(event).await;
+        }),
+        vec!["websocket".to_string(), "connection".to_string()],
+    )).await;
 }
 
 #[allow(dead_code)]
 /// Publish WebSocket disconnect event
 pub async fn publish_websocket_disconnect(connection_id: &str, reason: &str) {
-    let event = SystemEvent::new(
+    publish(system_event(
         EventType::WebSocketDisconnect,
         EventLevel::Info,
-        "websocket-server".to_string(),
-        serde_json::json!({
+        "websocket-server",
+        json!({
             "connection_id": connection_id,
             "reason": reason
-        })
-    ).with_tags(vec!["websocket".to_string(), "disconnection".to_string()]);
-
-    GLOBAL_EVENT_BUS.publish(event).await;
+        }),
+        vec!["websocket".to_string(), "disconnection".to_string()],
+    )).await;
 }
 
 #[allow(dead_code)]
 /// Publish SSE connection event
 pub async fn publish_sse_connect(connection_id: &str, stream_type: &str) {
-    let event = SystemEvent::new(
+    publish(system_event(
         EventType::SseConnect,
         EventLevel::Info,
-        "sse-server".to_string(),
-        serde_json::json!({
+        "sse-server",
+        json!({
             "connection_id": connection_id,
             "stream_type": stream_type
-        })
-    ).with_tags(vec!["sse".to_string(), "connection".to_string()]);
-
-    GLOBAL_EVENT_BUS.publish(event).await;
+        }),
+        vec!["sse".to_string(), "connection".to_string()],
+    )).await;
 }
 
 #[allow(dead_code)]
 /// Publish system stats event
 pub async fn publish_system_stats(stats: &Value) {
-    let event = SystemEvent::new(
+    publish(system_event(
         EventType::SystemStats,
         EventLevel::Debug,
-        "system-monitor".to_string(),
-        stats.clone()
-    ).with_tags(vec!["system".to_string(), "stats".to_string()]);
-
-    GLOBAL_EVENT_BUS.publish(event).await;
+        "system-monitor",
+        stats.clone(),
+        vec!["system".to_string(), "stats".to_string()],
+    )).await;
 }
 
 #[allow(dead_code)]
 /// Publish custom event
 pub async fn publish_custom_event(event_name: &str, level: EventLevel, source: &str, data: Value, tags: Vec<String>) {
-    let event = SystemEvent::new(
-        EventType::Custom(event_name.to_string()),
-        level,
-        source.to_string(),
-        data
-    ).with_tags(tags);
+    publish(system_event(EventType::Custom(event_name.to_string()), level, source, data, tags)).await;
+}
+
+/// Get recent events, newest first
+pub async fn get_recent_events(limit: Option<usize>) -> Vec<EventEnvelope> {
+    let limit = limit.unwrap_or(100).min(u32::MAX as usize) as u32;
 
-    GLOBAL_EVENT_BUS.publish(event).await;
+    match GLOBAL_EVENT_BUS.poll(EventQuery::new().with_pagination(limit, 0)).await {
+        Ok(events) => events,
+        Err(e) => {
+            error!("Failed to poll recent events: {}", e);
+            Vec::new()
+        }
+    }
 }
 
-#[allow(dead_code)]
-/// Event stream handler for SSE
-pub async fn create_event_stream_handler() -> mpsc::UnboundedReceiver<SystemEvent> {
-    let (_subscriber_id, receiver) = GLOBAL_EVENT_BUS.subscribe(vec![
-        EventType::JsonRpcRequest,
-        EventType::JsonRpcResponse,
-        EventType::WebSocketConnect,
-        EventType::WebSocketDisconnect,
-        EventType::SseConnect,
-        EventType::SseDisconnect,
-        EventType::SystemStats,
-        EventType::Custom("*".to_string()),
-    ]).await;
+/// Stored events matching a topic pattern with a sequence number after `sequence`, oldest first
+pub async fn events_after(topic: &str, sequence: u64) -> EventBusResult<Vec<EventEnvelope>> {
+    let mut events: Vec<EventEnvelope> = GLOBAL_EVENT_BUS.poll(EventQuery::new().with_topic(topic)).await?
+        .into_iter()
+        .filter(|event| event.sequence_number.is_some_and(|number| number > sequence))
+        .collect();
+    events.sort_by_key(|event| event.sequence_number);
+
+    Ok(events)
+}
 
-    receiver
+/// Get event statistics
+pub async fn get_event_stats() -> Value {
+    let stats = match GLOBAL_EVENT_BUS.get_stats().await {
+        Ok(stats) => json!({
+            "events_processed": stats.events_processed,
+            "active_subscriptions": stats.active_subscriptions,
+            "topic_count": stats.topic_count,
+            "events_per_second": stats.events_per_second
+        }),
+        Err(e) => json!({ "error": e.to_string() }),
+    };
+    let topics = GLOBAL_EVENT_BUS.list_topics().await.unwrap_or_default();
+
+    json!({
+        "bus": stats,
+        "instance_id": GLOBAL_EVENT_BUS.config().instance_id,
+        "max_memory_events": GLOBAL_EVENT_BUS.config().max_memory_events,
+        "topics": topics
+    })
+}
+
+/// Event emit request body
+#[derive(Debug, Deserialize)]
+pub struct EmitRequest {
+    pub topic: String,
+    #[serde(default)]
+    pub payload: Value,
+    pub source_trn: Option<String>,
+    pub target_trn: Option<String>,
+    pub correlation_id: Option<String>,
+}
+
+/// Event poll / subscribe parameters
+#[derive(Debug, Deserialize)]
+pub struct EventsParams {
+    pub topic: Option<String>,
+    pub since: Option<i64>,
+    pub until: Option<i64>,
+    pub limit: Option<u32>,
+    pub offset: Option<u32>,
+    /// TRN pattern matched against source/target TRNs (subscribe only)
+    pub trn: Option<String>,
+}
+
+/// Emit handler: publishes the event on the embedded bus
+pub async fn emit_handler(Json(request): Json<EmitRequest>) -> impl IntoResponse {
+    let mut event = EventEnvelope::with_trn(request.topic, request.payload, request.source_trn, request.target_trn);
+    if let Some(correlation_id) = request.correlation_id {
+        event = event.with_correlation_id(correlation_id);
+    }
+    let event_id = event.event_id.clone();
+
+    match GLOBAL_EVENT_BUS.emit(event).await {
+        Ok(()) => (StatusCode::OK, axum::Json(json!({ "event_id": event_id }))),
+        Err(e) => (StatusCode::BAD_REQUEST, axum::Json(json!({ "error": e.to_string() }))),
+    }
+}
+
+/// Poll handler: queries stored events
+pub async fn poll_handler(Query(params): Query<EventsParams>) -> impl IntoResponse {
+    let mut query = EventQuery::new().with_time_range(params.since, params.until);
+    if let Some(topic) = params.topic {
+        query = query.with_topic(topic);
+    }
+    query = query.with_pagination(params.limit.unwrap_or(100), params.offset.unwrap_or(0));
+
+    match GLOBAL_EVENT_BUS.poll(query).await {
+        Ok(events) => (StatusCode::OK, axum::Json(json!({ "count": events.len(), "events": events }))),
+        Err(e) => (StatusCode::BAD_REQUEST, axum::Json(json!({ "error": e.to_string() }))),
+    }
+}
+
+/// Subscribe handler: streams live events over SSE, by topic or TRN pattern
+pub async fn subscribe_handler(Query(params): Query<EventsParams>) -> axum::response::Response {
+    let subscription = match &params.trn {
+        Some(pattern) => GLOBAL_EVENT_BUS.subscribe_trn(pattern).await,
+        None => GLOBAL_EVENT_BUS.subscribe(params.topic.as_deref().unwrap_or("*")).await,
+    };
+
+    match subscription {
+        Ok(events) => {
+            info!("New event subscription: topic={:?} trn={:?}", params.topic, params.trn);
+            Sse::new(event_stream(events))
+                .keep_alive(KeepAlive::new().interval(Duration::from_secs(15)).text("keep-alive"))
+                .into_response()
+        }
+        Err(e) => (StatusCode::BAD_REQUEST, axum::Json(json!({ "error": e.to_string() }))).into_response(),
+    }
+}
+
+/// Convert bus events into SSE events
+fn event_stream(
+    events: impl Stream<Item = EventEnvelope> + Send + 'static,
+) -> impl Stream<Item = Result<Event, Infallible>> {
+    events.filter_map(|envelope| async move {
+        match Event::default().id(envelope.event_id.clone()).event(envelope.topic.clone()).json_data(&envelope) {
+            Ok(event) => Some(Ok(event)),
+            Err(e) => {
+                error!("Failed to serialize event {}: {}", envelope.event_id, e);
+                None
+            }
+        }
+    })
 }
 
 /// Get event API info
 pub async fn get_events_info() -> Value {
-    let stats = GLOBAL_EVENT_BUS.get_event_stats().await;
-    
-    serde_json::json!({
+    let stats = get_event_stats().await;
+
+    json!({
         "event_system": {
-            "description": "Global event bus with pub/sub functionality",
+            "description": "Embedded eventbus-rust EventBusService with memory storage",
             "features": [
                 "Real-time event publishing",
-                "Type-based subscriptions", 
-                "Event logging and history",
+                "Topic and TRN pattern subscriptions",
+                "Event querying and history",
                 "Statistics and analytics"
             ]
         },
@@ -375,10 +370,47 @@ pub async fn get_events_info() -> Value {
                 "description": "Get event statistics"
             },
             {
-                "endpoint": "/api/events/types/{type}",
-                "description": "Get events by type"
+                "endpoint": "/api/events/emit",
+                "description": "Emit an event (POST)",
+                "parameters": "{\"topic\", \"payload\", \"source_trn\", \"target_trn\", \"correlation_id\"}"
+            },
+            {
+                "endpoint": "/api/events/poll",
+                "description": "Query stored events",
+                "parameters": "?topic=user.*&since=&until=&limit=100&offset=0"
+            },
+            {
+                "endpoint": "/api/events/subscribe",
+                "description": "Stream live events over SSE",
+                "parameters": "?token=<session token>&topic=user.* or ?token=<session token>&trn=trn:org:acme:*:*:*"
             }
         ],
         "current_stats": stats
     })
-} 
\ No newline at end of file
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_events_go_through_the_embedded_bus() {
+        let mut stream = GLOBAL_EVENT_BUS.subscribe_trn("trn:user:demo:*:*:*").await.unwrap();
+
+        publish_custom_event("demo", EventLevel::Info, "test", json!({"n": 1}), vec![]).await;
+        publish(EventEnvelope::with_trn(
+            "demo.trn",
+            json!({"n": 2}),
+            Some("trn:user:demo:tool:notifier:v1.0".to_string()),
+            None,
+        )).await;
+
+        let delivered = stream.next().await.unwrap();
+        assert_eq!(delivered.topic, "demo.trn");
+
+        let recent = get_recent_events(Some(10)).await;
+        assert!(recent.iter().any(|event| event.topic == "custom.demo" && event.metadata.as_ref().unwrap()["level"] == "Info"));
+        assert!(get_event_stats().await["topics"].as_array().unwrap().contains(&json!("demo.trn")));
+    }
+}
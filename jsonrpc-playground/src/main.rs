@@ -5,6 +5,7 @@
 
 use axum::{
     extract::Query,
+    middleware,
     routing::{get, post},
     Router,
     response::Html,
@@ -24,6 +25,9 @@ mod services;
 mod websocket;
 mod sse;
 mod events;
+mod session;
+mod proxy;
+mod metrics;
 
 use server::AppState;
 use websocket::websocket_handler;
@@ -39,6 +43,18 @@ async fn main() -> anyhow::Result<()> {
 
     // 创建应用状态
     let app_state = AppState::new().await;
+    metrics::spawn_connection_sampler(app_state.metrics.clone());
+    session::spawn_session_sweeper(app_state.guard.clone());
+
+    // 需要会话令牌的 WebSocket、SSE 和代理路由
+    let authenticated = Router::new()
+        .route("/api/sse", get(sse::sse_handler))
+        .route("/api/events/subscribe", get(events::subscribe_handler))
+        .route("/ws", get(websocket_handler))
+        .route("/api/proxy", get(proxy::status_handler).delete(proxy::disconnect_handler))
+        .route("/api/proxy/connect", post(proxy::connect_handler))
+        .route("/api/proxy/jsonrpc", post(proxy::forward_handler))
+        .route_layer(middleware::from_fn_with_state(app_state.clone(), session::require_session));
 
     // 构建路由
     let app = Router::new()
@@ -47,19 +63,25 @@ async fn main() -> anyhow::Result<()> {
         
         // API路由
         .route("/api/jsonrpc", post(server::jsonrpc_handler))
+        .route("/api/jsonrpc/batch", post(server::batch_test_handler))
         .route("/api/health", get(server::health_handler))
+        .route("/api/methods", get(server::methods_handler))
+        .route("/api/sessions", post(session::create_session_handler))
+        .route("/api/metrics/history", get(metrics::history_handler))
+        .route("/api/ws/connections", get(websocket::connections_handler))
         
         // SSE路由
-        .route("/api/sse", get(sse::sse_handler))
         .route("/api/sse/info", get(sse_info_handler))
         
         // Events API路由
         .route("/api/events/recent", get(events_recent_handler))
         .route("/api/events/stats", get(events_stats_handler))
         .route("/api/events/info", get(events_info_handler))
+        .route("/api/events/emit", post(events::emit_handler))
+        .route("/api/events/poll", get(events::poll_handler))
         
-        // WebSocket路由
-        .route("/ws", get(websocket_handler))
+        // WebSocket、SSE 和代理路由
+        .merge(authenticated)
         
         // 静态文件服务
         .nest_service("/static", ServeDir::new("static"))
@@ -81,7 +103,8 @@ async fn main() -> anyhow::Result<()> {
     info!("📡 WebSocket 端点: ws://127.0.0.1:3000/ws");
     info!("🔧 JsonRPC API: http://127.0.0.1:3000/api/jsonrpc");
     
-    axum::serve(listener, app).await?;
+    // 会话创建按客户端地址限流
+    axum::serve(listener, app.into_make_service_with_connect_info::<std::net::SocketAddr>()).await?;
     
     Ok(())
 }
@@ -104,7 +127,7 @@ async fn events_recent_handler(
     let limit = params.get("limit")
         .and_then(|s| s.parse::<usize>().ok());
     
-    let events = events::GLOBAL_EVENT_BUS.get_recent_events(limit).await;
+    let events = events::get_recent_events(limit).await;
     axum::Json(serde_json::json!({
         "events": events,
         "count": events.len()
@@ -113,7 +136,7 @@ async fn events_recent_handler(
 
 /// Events stats handler
 async fn events_stats_handler() -> axum::Json<serde_json::Value> {
-    axum::Json(events::GLOBAL_EVENT_BUS.get_event_stats().await)
+    axum::Json(events::get_event_stats().await)
 }
 
 /// Events info handler
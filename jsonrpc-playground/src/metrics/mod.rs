@@ -0,0 +1,262 @@
+//! 指标时间序列
+//!
+//! `RequestStats` 只保存累计值。这里按分钟汇总请求数、错误数、延迟百分位和
+//! WebSocket / SSE 连接数，保留 24 小时，通过 `/api/metrics/history` 提供给界面绘制趋势图。
+
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::Duration;
+use axum::{
+    extract::{Query, State},
+    response::Json as ResponseJson,
+};
+use chrono::{DateTime, DurationRound, TimeDelta, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use tokio::sync::RwLock;
+
+use crate::server::AppState;
+
+/// 每个数据点覆盖的时长
+const BUCKET: TimeDelta = TimeDelta::minutes(1);
+
+/// 数据点保留时长
+const RETENTION: TimeDelta = TimeDelta::hours(24);
+
+/// 连接数采样间隔
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(10);
+
+/// 默认返回的分钟数
+const DEFAULT_HISTORY_MINUTES: i64 = 60;
+
+/// 一分钟的指标
+#[derive(Debug, Clone, Serialize)]
+pub struct MetricsPoint {
+    /// 该分钟的起始时间
+    pub timestamp: DateTime<Utc>,
+    pub requests: u64,
+    pub errors: u64,
+    pub latency_p50_ms: Option<u64>,
+    pub latency_p95_ms: Option<u64>,
+    pub latency_p99_ms: Option<u64>,
+    pub latency_max_ms: Option<u64>,
+    /// 该分钟内采样到的最大连接数
+    pub websocket_connections: usize,
+    pub sse_connections: usize,
+    /// 当前分钟尚未结束
+    pub partial: bool,
+}
+
+/// 正在汇总的一分钟
+#[derive(Debug)]
+struct Bucket {
+    minute: DateTime<Utc>,
+    requests: u64,
+    errors: u64,
+    latencies_ms: Vec<u64>,
+    websocket_connections: usize,
+    sse_connections: usize,
+}
+
+impl Bucket {
+    fn new(minute: DateTime<Utc>) -> Self {
+        Self {
+            minute,
+            requests: 0,
+            errors: 0,
+            latencies_ms: Vec::new(),
+            websocket_connections: 0,
+            sse_connections: 0,
+        }
+    }
+
+    fn point(&self, partial: bool) -> MetricsPoint {
+        let mut latencies = self.latencies_ms.clone();
+        latencies.sort_unstable();
+
+        MetricsPoint {
+            timestamp: self.minute,
+            requests: self.requests,
+            errors: self.errors,
+            latency_p50_ms: percentile(&latencies, 50),
+            latency_p95_ms: percentile(&latencies, 95),
+            latency_p99_ms: percentile(&latencies, 99),
+            latency_max_ms: latencies.last().copied(),
+            websocket_connections: self.websocket_connections,
+            sse_connections: self.sse_connections,
+            partial,
+        }
+    }
+}
+
+/// 最近秩法计算百分位，`sorted` 须已排序
+fn percentile(sorted: &[u64], p: usize) -> Option<u64> {
+    if sorted.is_empty() {
+        return None;
+    }
+    let rank = (sorted.len() * p).div_ceil(100).max(1);
+    Some(sorted[rank - 1])
+}
+
+fn minute_of(time: DateTime<Utc>) -> DateTime<Utc> {
+    time.duration_trunc(BUCKET).unwrap_or(time)
+}
+
+#[derive(Debug, Default)]
+struct Series {
+    /// 已结束的分钟，按时间先后排列
+    points: VecDeque<MetricsPoint>,
+    current: Option<Bucket>,
+}
+
+impl Series {
+    /// 返回 `now` 所在分钟的汇总，分钟变化时结束上一分钟并清理过期数据
+    fn bucket(&mut self, now: DateTime<Utc>) -> &mut Bucket {
+        let minute = minute_of(now);
+        if self.current.as_ref().is_some_and(|bucket| bucket.minute < minute) {
+            let finished = self.current.take().expect("checked above");
+            self.points.push_back(finished.point(false));
+        }
+        while self.points.front().is_some_and(|point| point.timestamp <= now - RETENTION) {
+            self.points.pop_front();
+        }
+
+        self.current.get_or_insert_with(|| Bucket::new(minute))
+    }
+}
+
+/// 按分钟汇总的指标收集器
+#[derive(Debug, Default)]
+pub struct MetricsCollector {
+    series: RwLock<Series>,
+}
+
+impl MetricsCollector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 记录一次请求
+    pub async fn record_request(&self, success: bool, response_time_ms: u64) {
+        self.record_request_at(Utc::now(), success, response_time_ms).await;
+    }
+
+    async fn record_request_at(&self, now: DateTime<Utc>, success: bool, response_time_ms: u64) {
+        let mut series = self.series.write().await;
+        let bucket = series.bucket(now);
+        bucket.requests += 1;
+        if !success {
+            bucket.errors += 1;
+        }
+        bucket.latencies_ms.push(response_time_ms);
+    }
+
+    /// 记录一次连接数采样
+    pub async fn record_connections(&self, websocket: usize, sse: usize) {
+        self.record_connections_at(Utc::now(), websocket, sse).await;
+    }
+
+    async fn record_connections_at(&self, now: DateTime<Utc>, websocket: usize, sse: usize) {
+        let mut series = self.series.write().await;
+        let bucket = series.bucket(now);
+        bucket.websocket_connections = bucket.websocket_connections.max(websocket);
+        bucket.sse_connections = bucket.sse_connections.max(sse);
+    }
+
+    /// 最近 `minutes` 分钟的数据点，按时间先后排列，最后一个可能是未结束的当前分钟
+    pub async fn history(&self, minutes: i64) -> Vec<MetricsPoint> {
+        self.history_at(Utc::now(), minutes).await
+    }
+
+    async fn history_at(&self, now: DateTime<Utc>, minutes: i64) -> Vec<MetricsPoint> {
+        let mut series = self.series.write().await;
+        series.bucket(now);
+
+        let since = minute_of(now) - TimeDelta::minutes(minutes.max(1) - 1);
+        series.points.iter()
+            .filter(|point| point.timestamp >= since)
+            .cloned()
+            .chain(series.current.as_ref().map(|bucket| bucket.point(true)))
+            .collect()
+    }
+}
+
+/// 定期采样 WebSocket 和 SSE 连接数
+pub fn spawn_connection_sampler(metrics: Arc<MetricsCollector>) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(SAMPLE_INTERVAL);
+        loop {
+            interval.tick().await;
+            let websocket = crate::websocket::connection_count().await;
+            let sse = crate::sse::connection_count().await;
+            metrics.record_connections(websocket, sse).await;
+        }
+    });
+}
+
+/// 历史查询参数
+#[derive(Debug, Deserialize)]
+pub struct HistoryParams {
+    /// 返回最近多少分钟，默认 60，最多 24 小时
+    pub minutes: Option<i64>,
+}
+
+/// 指标历史处理器
+pub async fn history_handler(
+    State(state): State<AppState>,
+    Query(params): Query<HistoryParams>,
+) -> ResponseJson<Value> {
+    let minutes = params.minutes
+        .unwrap_or(DEFAULT_HISTORY_MINUTES)
+        .clamp(1, RETENTION.num_minutes());
+    let points = state.metrics.history(minutes).await;
+
+    ResponseJson(json!({
+        "interval_seconds": BUCKET.num_seconds(),
+        "retention_minutes": RETENTION.num_minutes(),
+        "minutes": minutes,
+        "points": points
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_per_minute_history() {
+        let metrics = MetricsCollector::new();
+        let minute = minute_of(Utc::now());
+
+        // 超出保留时长的数据点会被清理
+        metrics.record_request_at(minute - TimeDelta::hours(25), true, 5).await;
+
+        for latency in 1..=100 {
+            metrics.record_request_at(minute + TimeDelta::seconds(1), latency % 10 != 0, latency).await;
+        }
+        metrics.record_connections_at(minute, 3, 1).await;
+        metrics.record_connections_at(minute + TimeDelta::seconds(30), 2, 4).await;
+
+        let now = minute + TimeDelta::minutes(2) + TimeDelta::seconds(5);
+        metrics.record_request_at(now, false, 7).await;
+
+        let history = metrics.history_at(now, 24 * 60).await;
+        assert_eq!(history.len(), 2);
+
+        let finished = &history[0];
+        assert_eq!(finished.timestamp, minute);
+        assert_eq!((finished.requests, finished.errors), (100, 10));
+        assert_eq!(finished.latency_p50_ms, Some(50));
+        assert_eq!(finished.latency_p95_ms, Some(95));
+        assert_eq!(finished.latency_p99_ms, Some(99));
+        assert_eq!(finished.latency_max_ms, Some(100));
+        assert_eq!((finished.websocket_connections, finished.sse_connections), (3, 4));
+        assert!(!finished.partial);
+
+        let current = &history[1];
+        assert_eq!((current.requests, current.errors), (1, 1));
+        assert!(current.partial);
+
+        assert_eq!(metrics.history_at(now, 1).await.len(), 1);
+    }
+}
@@ -0,0 +1,265 @@
+//! 外部服务代理
+//!
+//! 每个会话可以连接一个远程 JsonRPC 服务（`tcp://`、`ws://`、`wss://`、`http://`、
+//! `https://`，由 jsonrpc-rust 的 `TransportRegistry` 按协议选择传输）。之后发往
+//! `/api/proxy/jsonrpc` 的单个请求、批处理和通知经过同样的会话认证和限流后
+//! 转发给该服务，Playground 因此可以检查任意 jsonrpc-rust 服务，而不只是内置演示。
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use async_trait::async_trait;
+use axum::{
+    extract::{Extension, Json, State},
+    http::StatusCode,
+    response::{IntoResponse, Json as ResponseJson, Response},
+};
+use serde::Deserialize;
+use serde_json::{Value, json};
+use tokio::sync::RwLock;
+use tracing::{info, warn};
+
+use jsonrpc_rust::prelude::*;
+use jsonrpc_rust::convenience::DISCOVER_METHOD;
+use jsonrpc_rust::core::types::AuthContext;
+use jsonrpc_rust::transport::TransportRegistry;
+
+use crate::server::{process_jsonrpc_value, AppState};
+use crate::session::SessionToken;
+
+/// 默认的连接和调用超时
+const DEFAULT_TIMEOUT_MS: u64 = 10_000;
+
+/// 连接后获取远程方法目录的超时
+const DISCOVER_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// 会话连接的远程服务
+struct ProxyConnection {
+    endpoint: String,
+    client: JsonRpcClient,
+    connected_at: chrono::DateTime<chrono::Utc>,
+    /// 转发的调用数
+    forwarded: AtomicU64,
+}
+
+impl ProxyConnection {
+    fn info(&self) -> Value {
+        json!({
+            "endpoint": self.endpoint,
+            "connected_at": self.connected_at,
+            "forwarded_calls": self.forwarded.load(Ordering::Relaxed),
+            "pending_calls": self.client.pending_requests()
+        })
+    }
+}
+
+lazy_static::lazy_static! {
+    /// 各会话的代理连接，按会话 ID 索引
+    static ref PROXIES: RwLock<HashMap<String, Arc<ProxyConnection>>> = RwLock::new(HashMap::new());
+}
+
+/// 把调用转发给远程服务的方法处理器，供会话认证和限流中间件包装
+struct ProxyMethods {
+    connection: Arc<ProxyConnection>,
+}
+
+#[async_trait]
+impl MethodHandler for ProxyMethods {
+    async fn handle_method(&self, request: &JsonRpcRequest, _context: &ServiceContext) -> jsonrpc_rust::Result<JsonRpcResponse> {
+        self.connection.forwarded.fetch_add(1, Ordering::Relaxed);
+        let client = &self.connection.client;
+        let id = request.id().cloned().unwrap_or(Value::Null);
+
+        if request.is_notification() {
+            client.notify(&request.method, request.params.clone()).await?;
+            return Ok(JsonRpcResponse::success(id, Value::Null));
+        }
+
+        // 远程服务的错误响应原样返回，传输错误由中间件转换为错误响应
+        match client.call::<_, Value>(&request.method, request.params.clone()).await {
+            Ok(result) => Ok(JsonRpcResponse::success(id, result)),
+            Err(jsonrpc_rust::Error::JsonRpc(error)) => Ok(JsonRpcResponse::error(id, error)),
+            Err(err) => Err(err),
+        }
+    }
+
+    fn supported_methods(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    fn supports_method(&self, _method: &str) -> bool {
+        true
+    }
+}
+
+/// 代理连接参数
+#[derive(Debug, Deserialize)]
+pub struct ConnectRequest {
+    /// 远程服务地址，如 `ws://127.0.0.1:8080/rpc`
+    pub endpoint: String,
+    /// 连接和调用超时（毫秒）
+    pub timeout_ms: Option<u64>,
+}
+
+fn proxy_error(status: StatusCode, message: impl std::fmt::Display) -> Response {
+    (status, ResponseJson(json!({"error": message.to_string()}))).into_response()
+}
+
+/// 连接远程服务，替换会话已有的代理连接
+pub async fn connect_handler(
+    Extension(auth): Extension<AuthContext>,
+    Json(request): Json<ConnectRequest>,
+) -> Response {
+    let timeout = Duration::from_millis(request.timeout_ms.unwrap_or(DEFAULT_TIMEOUT_MS));
+    let registry = match TransportRegistry::default() {
+        Ok(registry) => registry,
+        Err(err) => return proxy_error(StatusCode::INTERNAL_SERVER_ERROR, err),
+    };
+
+    let transport = match tokio::time::timeout(timeout, registry.connect(&request.endpoint)).await {
+        Ok(Ok(transport)) => transport,
+        Ok(Err(err @ jsonrpc_rust::Error::Configuration { .. })) => return proxy_error(StatusCode::BAD_REQUEST, err),
+        Ok(Err(err)) => return proxy_error(StatusCode::BAD_GATEWAY, err),
+        Err(_) => return proxy_error(StatusCode::GATEWAY_TIMEOUT, format!("Connecting to {} timed out", request.endpoint)),
+    };
+    let client = JsonRpcClient::new(transport).with_timeout(timeout);
+
+    // 远程服务支持 rpc.discover 时附带其方法目录
+    let methods = client.call_with_timeout::<_, Value>(DISCOVER_METHOD, (), DISCOVER_TIMEOUT).await.ok();
+
+    info!("会话 {} 连接代理: {}", auth.user_id, request.endpoint);
+    let connection = Arc::new(ProxyConnection {
+        endpoint: request.endpoint,
+        client,
+        connected_at: chrono::Utc::now(),
+        forwarded: AtomicU64::new(0),
+    });
+
+    let previous = PROXIES.write().await.insert(auth.user_id, connection.clone());
+    if let Some(previous) = previous {
+        if let Err(err) = previous.client.close().await {
+            warn!("关闭代理连接 {} 失败: {}", previous.endpoint, err);
+        }
+    }
+
+    let mut response = connection.info();
+    response["status"] = json!("connected");
+    response["remote_service"] = methods.unwrap_or(Value::Null);
+    ResponseJson(response).into_response()
+}
+
+/// 会话的代理连接状态
+pub async fn status_handler(Extension(auth): Extension<AuthContext>) -> ResponseJson<Value> {
+    let proxies = PROXIES.read().await;
+    ResponseJson(match proxies.get(&auth.user_id) {
+        Some(connection) => {
+            let mut status = connection.info();
+            status["status"] = json!("connected");
+            status
+        }
+        None => json!({"status": "disconnected"}),
+    })
+}
+
+/// 移除并关闭会话的代理连接
+async fn disconnect(session_id: &str) -> Option<Arc<ProxyConnection>> {
+    let connection = PROXIES.write().await.remove(session_id)?;
+    if let Err(err) = connection.client.close().await {
+        warn!("关闭代理连接 {} 失败: {}", connection.endpoint, err);
+    }
+    Some(connection)
+}
+
+/// 会话过期时关闭它的代理连接
+pub async fn close_session(session_id: &str) {
+    disconnect(session_id).await;
+}
+
+/// 断开会话的代理连接
+pub async fn disconnect_handler(Extension(auth): Extension<AuthContext>) -> ResponseJson<Value> {
+    let Some(connection) = disconnect(&auth.user_id).await else {
+        return ResponseJson(json!({"status": "disconnected"}));
+    };
+
+    let mut status = connection.info();
+    status["status"] = json!("disconnected");
+    ResponseJson(status)
+}
+
+/// 把 JsonRPC 请求（单个或批处理）转发给会话连接的远程服务
+pub async fn forward_handler(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthContext>,
+    Extension(SessionToken(token)): Extension<SessionToken>,
+    Json(request_value): Json<Value>,
+) -> Response {
+    let connection = PROXIES.read().await.get(&auth.user_id).cloned();
+    let Some(connection) = connection else {
+        return proxy_error(StatusCode::CONFLICT, "No proxy connected, POST /api/proxy/connect first");
+    };
+
+    let handler: Arc<dyn MethodHandler> = Arc::new(ProxyMethods { connection });
+    process_jsonrpc_value(&state, &handler, Some(&token), request_value).await.into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{routing::get, Router};
+
+    #[tokio::test]
+    async fn test_forward_to_remote_websocket_service() {
+        // 远程服务：另一个 Playground 的 WebSocket 端点
+        let remote = AppState::new().await;
+        let app = Router::new()
+            .route("/ws", get(crate::websocket::websocket_handler))
+            .route_layer(axum::middleware::from_fn_with_state(remote.clone(), crate::session::require_session))
+            .with_state(remote.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        let (_, remote_token) = remote.create_session().await;
+
+        let state = AppState::new().await;
+        let (session_id, token) = state.create_session().await;
+        let auth = || Extension(AuthContext::new(session_id.clone(), "session_token"));
+        let forward = |body: Value| forward_handler(
+            State(state.clone()),
+            auth(),
+            Extension(SessionToken(token.clone())),
+            Json(body),
+        );
+
+        // 未连接时拒绝转发，无效地址连接失败
+        assert_eq!(forward(json!({"jsonrpc": "2.0", "method": "ws.ping", "id": 1})).await.status(), StatusCode::CONFLICT);
+        let bad = ConnectRequest { endpoint: "nope://x".to_string(), timeout_ms: None };
+        assert_eq!(connect_handler(auth(), Json(bad)).await.status(), StatusCode::BAD_REQUEST);
+
+        let endpoint = format!("ws://{}/ws?token={}", addr, remote_token);
+        let request = ConnectRequest { endpoint: endpoint.clone(), timeout_ms: Some(2_000) };
+        assert_eq!(connect_handler(auth(), Json(request)).await.status(), StatusCode::OK);
+
+        // 批处理逐条转发，远程错误原样返回，通知没有响应
+        let response = forward(json!([
+            {"jsonrpc": "2.0", "method": "ws.ping", "id": "a"},
+            {"jsonrpc": "2.0", "method": "ws.unknown", "id": "b"},
+            {"jsonrpc": "2.0", "method": "ws.ping"}
+        ])).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let responses: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(responses.as_array().unwrap().len(), 2);
+        assert_eq!(responses[0]["id"], "a");
+        assert!(responses[0]["result"].get("pong").is_some());
+        assert_eq!(responses[1]["id"], "b");
+        assert_eq!(responses[1]["error"]["code"], -32603);
+
+        let status = status_handler(auth()).await.0;
+        assert_eq!(status["endpoint"], endpoint);
+        assert_eq!(status["forwarded_calls"], 3);
+
+        assert_eq!(disconnect_handler(auth()).await.0["status"], "disconnected");
+        assert_eq!(status_handler(auth()).await.0["status"], "disconnected");
+    }
+}
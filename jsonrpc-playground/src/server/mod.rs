@@ -4,10 +4,11 @@
 
 use std::sync::Arc;
 use std::collections::HashMap;
+use async_trait::async_trait;
 use axum::{
     extract::{State, Json},
-    response::Json as ResponseJson,
-    http::StatusCode,
+    response::{IntoResponse, Json as ResponseJson, Response},
+    http::{header, HeaderMap, StatusCode},
 };
 use serde_json::{Value, json};
 use tokio::sync::RwLock;
@@ -16,8 +17,12 @@ use tracing::{info, error, debug};
 
 // 使用 jsonrpc-rust 库的类型定义
 use jsonrpc_rust::prelude::*;
+use jsonrpc_rust::convenience::AUTHORIZATION_METADATA;
 
+use crate::metrics::MetricsCollector;
 use crate::services::DemoServices;
+use crate::session::{SessionGuard, SessionVerifier, SESSION_RATE_LIMIT};
+use crate::websocket::WebSocketSettings;
 
 /// 应用全局状态
 #[derive(Clone)]
@@ -26,8 +31,16 @@ pub struct AppState {
     pub services: Arc<DemoServices>,
     /// 活跃会话记录
     pub sessions: Arc<RwLock<HashMap<String, SessionInfo>>>,
+    /// 会话令牌到会话 ID 的映射
+    pub tokens: Arc<RwLock<HashMap<String, String>>>,
+    /// 会话认证和限流
+    pub guard: SessionGuard,
     /// 请求统计
     pub stats: Arc<RwLock<RequestStats>>,
+    /// 按分钟汇总的指标时间序列
+    pub metrics: Arc<MetricsCollector>,
+    /// WebSocket 保活和空闲超时设置
+    pub websocket: WebSocketSettings,
 }
 
 /// 会话信息
@@ -55,21 +68,35 @@ impl AppState {
         
         let services = Arc::new(DemoServices::new().await);
         let sessions = Arc::new(RwLock::new(HashMap::new()));
+        let tokens = Arc::new(RwLock::new(HashMap::new()));
         let stats = Arc::new(RwLock::new(RequestStats::default()));
         
+        // 目录中不要求认证的方法允许匿名调用
+        let anonymous = crate::services::method_catalog()
+            .into_iter()
+            .filter(|method| !method.auth_required)
+            .map(|method| method.name);
+        let verifier = SessionVerifier::new(sessions.clone(), tokens.clone());
+        let guard = SessionGuard::new(verifier, anonymous, SESSION_RATE_LIMIT)
+            .expect("session rate limit is valid");
+        
         info!("应用状态初始化完成");
         
         Self {
             services,
             sessions,
+            tokens,
+            guard,
             stats,
+            metrics: Arc::new(MetricsCollector::new()),
+            websocket: WebSocketSettings::from_env(),
         }
     }
     
-    /// 创建新会话
-    #[allow(dead_code)]
-    pub async fn create_session(&self) -> String {
+    /// 创建新会话，返回会话 ID 和令牌
+    pub async fn create_session(&self) -> (String, String) {
         let session_id = Uuid::new_v4().to_string();
+        let token = format!("pg_{}", Uuid::new_v4().simple());
         let now = chrono::Utc::now();
         
         let session = SessionInfo {
@@ -80,9 +107,28 @@ impl AppState {
         };
         
         self.sessions.write().await.insert(session_id.clone(), session);
+        self.tokens.write().await.insert(token.clone(), session_id.clone());
         debug!("创建新会话: {}", session_id);
         
-        session_id
+        (session_id, token)
+    }
+    
+    /// 经过会话认证和限流调用方法处理器
+    ///
+    /// `token` 是连接或请求头提供的会话令牌，认证和限流失败时返回对应的错误响应
+    pub async fn call(&self, handler: Arc<dyn MethodHandler>, request: &JsonRpcRequest, token: Option<&str>) -> JsonRpcResponse {
+        let mut context = ServiceContext::new(Uuid::new_v4().to_string());
+        if let Some(token) = token {
+            context = context.with_metadata(AUTHORIZATION_METADATA, json!(token));
+        }
+        
+        match self.guard.layer(handler).handle_method(request, &context).await {
+            Ok(response) => response,
+            Err(err) => {
+                debug!("调用 {} 被拒绝: {}", request.method(), err);
+                JsonRpcResponse::error(request.id().cloned().unwrap_or(Value::Null), err.to_jsonrpc_error())
+            }
+        }
     }
 
     /// 更新会话活动
@@ -96,6 +142,8 @@ impl AppState {
     
     /// 记录请求统计
     pub async fn record_request(&self, success: bool, response_time_ms: u64) {
+        self.metrics.record_request(success, response_time_ms).await;
+        
         let mut stats = self.stats.write().await;
         stats.total_requests += 1;
         
@@ -112,45 +160,269 @@ impl AppState {
     }
 }
 
+/// HTTP 方法处理器，供会话认证和限流中间件包装
+struct HttpMethods {
+    state: AppState,
+}
+
+#[async_trait]
+impl MethodHandler for HttpMethods {
+    async fn handle_method(&self, request: &JsonRpcRequest, _context: &ServiceContext) -> jsonrpc_rust::Result<JsonRpcResponse> {
+        Ok(process_jsonrpc_request(&self.state, request).await)
+    }
+    
+    fn supported_methods(&self) -> Vec<String> {
+        crate::services::method_catalog()
+            .into_iter()
+            .map(|method| method.name)
+            .collect()
+    }
+}
+
+/// 请求头中的会话令牌
+pub fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    headers.get(header::AUTHORIZATION).and_then(|value| value.to_str().ok())
+}
+
 /// HTTP JsonRPC 请求处理器
+///
+/// 同时接受单个请求和批处理数组；通知不产生响应，
+/// 只含通知的请求返回 204 No Content。单个请求被限流时返回 429
 pub async fn jsonrpc_handler(
     State(state): State<AppState>,
+    headers: HeaderMap,
     Json(request_value): Json<Value>,
-) -> Result<ResponseJson<Value>, StatusCode> {
-    let start_time = std::time::Instant::now();
-    
+) -> Response {
     debug!("收到 JsonRPC 请求: {}", serde_json::to_string_pretty(&request_value).unwrap_or_default());
     
-    // 解析为 JsonRpcRequest
-    let request: JsonRpcRequest = match serde_json::from_value(request_value) {
-        Ok(req) => req,
+    let handler: Arc<dyn MethodHandler> = Arc::new(HttpMethods { state: state.clone() });
+    let outcome = process_jsonrpc_value(&state, &handler, bearer_token(&headers), request_value).await;
+    
+    debug!("返回 JsonRPC 响应: {:?}", outcome.responses);
+    
+    outcome.into_response()
+}
+
+/// 按规范返回响应体，被限流的单个请求以 429 返回并给出重试等待时间
+impl IntoResponse for BatchOutcome {
+    fn into_response(self) -> Response {
+        let Some(responses) = self.responses else {
+            return StatusCode::NO_CONTENT.into_response();
+        };
+        
+        let retry_after = serde_json::from_value::<JsonRpcResponse>(responses.clone())
+            .ok()
+            .and_then(|response| response.error)
+            .filter(|error| error.code == RATE_LIMITED)
+            .map(|error| error.retry_after().unwrap_or_default());
+        match retry_after {
+            Some(retry_after) => (
+                StatusCode::TOO_MANY_REQUESTS,
+                [(header::RETRY_AFTER, retry_after.as_secs_f64().ceil().max(1.0).to_string())],
+                ResponseJson(responses),
+            ).into_response(),
+            None => ResponseJson(responses).into_response(),
+        }
+    }
+}
+
+/// 限流错误码
+const RATE_LIMITED: i32 = -32005;
+
+/// JsonRPC 批处理测试处理器
+///
+/// 按规范处理单个请求或批处理数组，并返回逐条结果和规范符合性说明，
+/// 供 Playground 界面展示框架的批处理行为
+pub async fn batch_test_handler(State(state): State<AppState>, headers: HeaderMap, body: String) -> ResponseJson<Value> {
+    let handler: Arc<dyn MethodHandler> = Arc::new(HttpMethods { state: state.clone() });
+    let outcome = match serde_json::from_str::<Value>(&body) {
+        Ok(value) => process_jsonrpc_value(&state, &handler, bearer_token(&headers), value).await,
         Err(err) => {
-            error!("请求解析错误: {}", err);
-            let error_response = JsonRpcResponse::error(
-                serde_json::Value::Null,
-                JsonRpcError::parse_error("Invalid JSON-RPC request format")
-            );
-            return Ok(ResponseJson(serde_json::to_value(error_response).unwrap()));
+            let error = JsonRpcError::parse_error(format!("Invalid JSON: {}", err));
+            BatchOutcome::rejected(false, JsonRpcResponse::error(Value::Null, error))
         }
     };
     
-    // 处理请求
-    let response = process_jsonrpc_request(&state, request).await;
-    let duration = start_time.elapsed().as_millis() as u64;
+    ResponseJson(outcome.report())
+}
+
+/// 批处理中单个条目的处理结果
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct BatchItemReport {
+    /// 在批处理中的位置
+    pub index: usize,
+    /// 条目类型：request、notification 或 invalid
+    pub kind: &'static str,
+    pub method: Option<String>,
+    pub id: Value,
+    /// 返回给客户端的响应，通知没有响应
+    pub response: Option<Value>,
+    pub duration_ms: u64,
+}
+
+/// 一次 JsonRPC 调用（单个或批处理）的处理结果
+#[derive(Debug, Clone)]
+pub struct BatchOutcome {
+    /// 请求是否为批处理数组
+    batch: bool,
+    items: Vec<BatchItemReport>,
+    /// 按规范应返回的响应体，`None` 表示不返回任何内容
+    responses: Option<Value>,
+}
+
+impl BatchOutcome {
+    /// 整体被拒绝的调用（解析错误或空批处理）
+    fn rejected(batch: bool, response: JsonRpcResponse) -> Self {
+        Self {
+            batch,
+            items: Vec::new(),
+            responses: serde_json::to_value(response).ok(),
+        }
+    }
+    
+    /// 生成逐条结果和规范符合性说明
+    fn report(&self) -> Value {
+        let count = |kind: &str| self.items.iter().filter(|item| item.kind == kind).count();
+        let requests = count("request");
+        let notifications = count("notification");
+        let invalid = count("invalid");
+        let responses_returned = match &self.responses {
+            Some(Value::Array(responses)) => responses.len(),
+            Some(_) => 1,
+            None => 0,
+        };
+        let ids_match = self.items.iter()
+            .filter_map(|item| item.response.as_ref().map(|response| (item, response)))
+            .all(|(item, response)| response.get("id") == Some(&item.id));
+        
+        let mut notes = Vec::new();
+        if self.items.is_empty() {
+            notes.push("The call was rejected as a whole with a single error response and a null id");
+        }
+        if notifications > 0 {
+            notes.push("Notifications are executed but never answered");
+        }
+        if invalid > 0 {
+            notes.push("Invalid items are answered with -32600 Invalid Request without failing the rest of the batch");
+        }
+        if self.batch && !self.items.is_empty() {
+            notes.push("A batch is answered with one array holding a response per non-notification item");
+        }
+        if self.responses.is_none() {
+            notes.push("Nothing is returned when every item is a notification (HTTP 204)");
+        }
+        
+        json!({
+            "mode": if self.batch { "batch" } else { "single" },
+            "responses": self.responses,
+            "items": self.items,
+            "compliance": {
+                "spec": "JSON-RPC 2.0",
+                "jsonrpc_version": jsonrpc_rust::JSONRPC_VERSION,
+                "item_count": self.items.len(),
+                "requests": requests,
+                "notifications": notifications,
+                "invalid": invalid,
+                "responses_expected": requests + invalid,
+                "responses_returned": responses_returned,
+                "ids_match": ids_match,
+                "http_status": if self.responses.is_some() { 200 } else { 204 },
+                "notes": notes
+            }
+        })
+    }
+}
+
+/// 按 JsonRPC 2.0 规范处理单个请求或批处理数组
+///
+/// 每个条目都经过会话认证和限流后交给 `handler`
+pub async fn process_jsonrpc_value(
+    state: &AppState,
+    handler: &Arc<dyn MethodHandler>,
+    token: Option<&str>,
+    value: Value,
+) -> BatchOutcome {
+    match value {
+        Value::Array(batch) if batch.is_empty() => {
+            let error = JsonRpcError::invalid_request("Empty batch");
+            BatchOutcome::rejected(true, JsonRpcResponse::error(Value::Null, error))
+        }
+        Value::Array(batch) => {
+            let mut items = Vec::with_capacity(batch.len());
+            for (index, item) in batch.into_iter().enumerate() {
+                items.push(process_jsonrpc_item(state, handler, token, index, item).await);
+            }
+            
+            let responses: Vec<Value> = items.iter().filter_map(|item| item.response.clone()).collect();
+            BatchOutcome {
+                batch: true,
+                responses: (!responses.is_empty()).then_some(Value::Array(responses)),
+                items,
+            }
+        }
+        single => {
+            let item = process_jsonrpc_item(state, handler, token, 0, single).await;
+            BatchOutcome {
+                batch: false,
+                responses: item.response.clone(),
+                items: vec![item],
+            }
+        }
+    }
+}
+
+/// 处理单个条目并记录统计
+async fn process_jsonrpc_item(
+    state: &AppState,
+    handler: &Arc<dyn MethodHandler>,
+    token: Option<&str>,
+    index: usize,
+    value: Value,
+) -> BatchItemReport {
+    let start_time = std::time::Instant::now();
+    let id = value.get("id").cloned().unwrap_or(Value::Null);
+    let method = value.get("method").and_then(|m| m.as_str()).map(str::to_string);
     
-    // 记录统计
-    state.record_request(response.is_success(), duration).await;
+    // 解析为 JsonRpcRequest
+    let request = serde_json::from_value::<JsonRpcRequest>(value)
+        .map_err(|err| format!("Invalid request: {}", err))
+        .and_then(|request| match request.jsonrpc() {
+            jsonrpc_rust::JSONRPC_VERSION => Ok(request),
+            other => Err(format!("Unsupported jsonrpc version: {}", other)),
+        });
     
-    debug!("返回 JsonRPC 响应: {:?}", response);
+    let (kind, response) = match request {
+        Ok(request) => {
+            let notification = request.is_notification();
+            let response = state.call(handler.clone(), &request, token).await;
+            state.record_request(response.is_success(), start_time.elapsed().as_millis() as u64).await;
+            
+            if notification {
+                ("notification", None)
+            } else {
+                ("request", Some(response))
+            }
+        }
+        Err(message) => {
+            error!("请求解析错误: {}", message);
+            ("invalid", Some(JsonRpcResponse::error(id.clone(), JsonRpcError::invalid_request(message))))
+        }
+    };
     
-    let response_value = serde_json::to_value(response).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-    Ok(ResponseJson(response_value))
+    BatchItemReport {
+        index,
+        kind,
+        method,
+        id,
+        response: response.and_then(|response| serde_json::to_value(response).ok()),
+        duration_ms: start_time.elapsed().as_millis() as u64,
+    }
 }
 
 /// 处理JsonRPC请求
 async fn process_jsonrpc_request(
     state: &AppState,
-    request: JsonRpcRequest,
+    request: &JsonRpcRequest,
 ) -> JsonRpcResponse {
     let method = request.method();
     let params = request.params.clone().unwrap_or(Value::Null);
@@ -164,6 +436,7 @@ async fn process_jsonrpc_request(
         "system.info" => state.services.get_system_info().await,
         "system.stats" => get_system_stats(state).await,
         "system.sessions" => get_active_sessions(state).await,
+        jsonrpc_rust::convenience::DISCOVER_METHOD => serde_json::to_value(crate::services::service_info()).map_err(Into::into),
         
         // 数学计算服务
         "math.add" => state.services.math_add(params).await,
@@ -226,6 +499,13 @@ async fn get_active_sessions(state: &AppState) -> anyhow::Result<Value> {
     }))
 }
 
+/// 方法目录处理器
+///
+/// 返回全部方法的描述（参数 Schema、示例和认证要求），与 `rpc.discover` 的结果相同
+pub async fn methods_handler() -> ResponseJson<jsonrpc_rust::core::types::ServiceInfo> {
+    ResponseJson(crate::services::service_info())
+}
+
 /// 健康检查处理器
 pub async fn health_handler(State(_state): State<AppState>) -> ResponseJson<Value> {
     ResponseJson(json!({
@@ -235,4 +515,120 @@ pub async fn health_handler(State(_state): State<AppState>) -> ResponseJson<Valu
         "jsonrpc_version": jsonrpc_rust::JSONRPC_VERSION,
         "timestamp": chrono::Utc::now().to_rfc3339()
     }))
-} 
\ No newline at end of file
+} 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn http(state: &AppState) -> Arc<dyn MethodHandler> {
+        Arc::new(HttpMethods { state: state.clone() })
+    }
+    
+    #[tokio::test]
+    async fn test_batch_and_notifications() {
+        let state = AppState::new().await;
+        let (_, token) = state.create_session().await;
+        let token = Some(token.as_str());
+        
+        // 请求、通知和无效条目混合的批处理
+        let outcome = process_jsonrpc_value(&state, &http(&state), token, json!([
+            {"jsonrpc": "2.0", "method": "math.add", "params": [1, 2], "id": 1},
+            {"jsonrpc": "2.0", "method": "tools.echo", "params": {"a": 1}},
+            {"jsonrpc": "1.0", "method": "tools.uuid", "id": "old"},
+            {"foo": "bar"},
+            {"jsonrpc": "2.0", "method": "no.such", "id": 2}
+        ])).await;
+        let responses = outcome.responses.clone().unwrap();
+        let ids: Vec<_> = responses.as_array().unwrap().iter().map(|r| r["id"].clone()).collect();
+        assert_eq!(ids, vec![json!(1), json!("old"), Value::Null, json!(2)]);
+        assert_eq!(responses[0]["result"]["result"], 3.0);
+        assert_eq!(responses[1]["error"]["code"], -32600);
+        assert_eq!(responses[2]["error"]["code"], -32600);
+        
+        let report = outcome.report();
+        assert_eq!(report["mode"], "batch");
+        assert_eq!(report["items"][1]["kind"], "notification");
+        assert_eq!(report["compliance"]["responses_expected"], 4);
+        assert_eq!(report["compliance"]["responses_returned"], 4);
+        assert_eq!(report["compliance"]["ids_match"], true);
+        
+        // 只含通知时不返回任何内容
+        let outcome = process_jsonrpc_value(&state, &http(&state), token, json!([{"jsonrpc": "2.0", "method": "tools.uuid"}])).await;
+        assert!(outcome.responses.is_none());
+        assert_eq!(outcome.report()["compliance"]["http_status"], 204);
+        let outcome = process_jsonrpc_value(&state, &http(&state), token, json!({"jsonrpc": "2.0", "method": "tools.uuid"})).await;
+        assert!(outcome.responses.is_none());
+        
+        // 空批处理整体被拒绝
+        let outcome = process_jsonrpc_value(&state, &http(&state), token, json!([])).await;
+        assert_eq!(outcome.responses.unwrap()["error"]["code"], -32600);
+    }
+    
+    #[tokio::test]
+    async fn test_method_catalog_examples() {
+        let state = AppState::new().await;
+        let (_, token) = state.create_session().await;
+        let call = |request: JsonRpcRequest| {
+            let state = state.clone();
+            let token = token.clone();
+            async move {
+                let handler = Arc::new(HttpMethods { state: state.clone() });
+                state.call(handler, &request, Some(&token)).await
+            }
+        };
+        
+        // 目录中每个 HTTP 方法都能用示例参数调用
+        for method in crate::services::method_catalog() {
+            if !method.metadata["transports"].as_array().unwrap().contains(&json!("http")) {
+                continue;
+            }
+            let request = JsonRpcRequest::with_id(&method.name, method.example_params.clone(), json!(1));
+            let response = call(request).await;
+            assert!(response.is_success(), "{}", method.name);
+        }
+        
+        let request = JsonRpcRequest::with_id("rpc.discover", None, json!(1));
+        let response = serde_json::to_value(call(request).await).unwrap();
+        let methods = response["result"]["methods"].as_array().unwrap();
+        assert!(methods.iter().any(|m| m["name"] == "chat.send" && m["params_schema"]["required"] == json!(["room", "message"])));
+    }
+    
+    #[tokio::test]
+    async fn test_session_auth_and_rate_limit() {
+        let state = AppState::new().await;
+        let request = |method: &str| json!({"jsonrpc": "2.0", "method": method, "params": [1, 2], "id": 1});
+        let post = |headers: HeaderMap, body: Value| jsonrpc_handler(State(state.clone()), headers, Json(body));
+        
+        // 没有令牌时只能调用匿名方法
+        let outcome = process_jsonrpc_value(&state, &http(&state), None, request("math.add")).await;
+        assert_eq!(outcome.responses.unwrap()["error"]["code"], -32001);
+        let outcome = process_jsonrpc_value(&state, &http(&state), Some("pg_unknown"), request("math.add")).await;
+        assert_eq!(outcome.responses.unwrap()["error"]["code"], -32001);
+        let outcome = process_jsonrpc_value(&state, &http(&state), None, request("system.info")).await;
+        assert!(outcome.responses.unwrap().get("result").is_some());
+        
+        // 令牌也可以放在参数中
+        let (session_id, token) = state.create_session().await;
+        let body = json!({"jsonrpc": "2.0", "method": "tools.echo", "params": {"token": token, "x": 1}, "id": 1});
+        let outcome = process_jsonrpc_value(&state, &http(&state), None, body).await;
+        assert_eq!(outcome.responses.unwrap()["result"]["echo"], json!({"x": 1}));
+        
+        // 用完令牌桶后返回 -32005 和 HTTP 429
+        let mut headers = HeaderMap::new();
+        headers.insert(header::AUTHORIZATION, format!("Bearer {}", token).parse().unwrap());
+        for _ in 1..SESSION_RATE_LIMIT.burst {
+            assert_eq!(post(headers.clone(), request("math.add")).await.status(), StatusCode::OK);
+        }
+        let response = post(headers.clone(), request("math.add")).await;
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert!(response.headers().contains_key(header::RETRY_AFTER));
+        
+        // 其他会话不受影响
+        let (_, other) = state.create_session().await;
+        let outcome = process_jsonrpc_value(&state, &http(&state), Some(&other), request("math.add")).await;
+        assert!(outcome.responses.unwrap().get("result").is_some());
+        
+        let sessions = state.sessions.read().await;
+        assert_eq!(sessions[&session_id].request_count, u64::from(SESSION_RATE_LIMIT.burst) + 1);
+    }
+}
@@ -0,0 +1,213 @@
+//! 方法目录
+//!
+//! 用 jsonrpc-rust 的 `MethodInfo` / `ServiceInfo` 描述 Playground 提供的全部方法，
+//! 包括参数 JSON Schema、示例和认证要求，界面据此自动生成调用表单
+
+use std::collections::HashMap;
+use serde_json::{Value, json};
+use jsonrpc_rust::core::types::{MethodInfo, ServiceInfo};
+use jsonrpc_rust::convenience::DISCOVER_METHOD;
+
+/// 可通过 HTTP `/api/jsonrpc` 调用
+pub const TRANSPORT_HTTP: &str = "http";
+/// 可通过 WebSocket `/ws` 调用
+pub const TRANSPORT_WEBSOCKET: &str = "websocket";
+
+/// 描述一个没有参数、需要会话令牌的方法
+fn describe(name: &str, category: &str, description: &str, transports: &[&str]) -> MethodInfo {
+    let mut metadata = HashMap::new();
+    metadata.insert("category".to_string(), json!(category));
+    metadata.insert("transports".to_string(), json!(transports));
+
+    MethodInfo {
+        name: name.to_string(),
+        description: description.to_string(),
+        params_schema: None,
+        returns_schema: Some(json!({"type": "object"})),
+        example_params: None,
+        example_returns: None,
+        auth_required: true,
+        required_permissions: Vec::new(),
+        metadata,
+    }
+}
+
+/// 描述一个带参数的方法
+fn describe_with_params(
+    name: &str,
+    category: &str,
+    description: &str,
+    transports: &[&str],
+    params_schema: Value,
+    example_params: Value,
+) -> MethodInfo {
+    MethodInfo {
+        params_schema: Some(params_schema),
+        example_params: Some(example_params),
+        ..describe(name, category, description, transports)
+    }
+}
+
+/// 不需要会话令牌即可调用的方法
+fn anonymous(info: MethodInfo) -> MethodInfo {
+    MethodInfo { auth_required: false, ..info }
+}
+
+/// Playground 的全部方法描述
+pub fn method_catalog() -> Vec<MethodInfo> {
+    let http = &[TRANSPORT_HTTP][..];
+    let websocket = &[TRANSPORT_WEBSOCKET][..];
+    let both = &[TRANSPORT_HTTP, TRANSPORT_WEBSOCKET][..];
+
+    vec![
+        // 系统方法
+        anonymous(describe("system.info", "system", "Get system information", http)),
+        describe("system.stats", "system", "Get request statistics", http),
+        describe("system.sessions", "system", "List active sessions", http),
+        anonymous(describe(DISCOVER_METHOD, "system", "Describe all methods as a machine-readable catalog", http)),
+
+        // 数学计算服务
+        describe_with_params(
+            "math.add", "math", "Add numbers", http,
+            json!({"type": "array", "items": {"type": "number"}, "minItems": 1}),
+            json!([1, 2, 3, 4]),
+        ),
+        describe_with_params(
+            "math.multiply", "math", "Multiply two numbers", http,
+            json!({
+                "type": "object",
+                "properties": {"a": {"type": "number"}, "b": {"type": "number"}},
+                "required": ["a", "b"]
+            }),
+            json!({"a": 6, "b": 7}),
+        ),
+        describe_with_params(
+            "math.fibonacci", "math", "Calculate the Fibonacci sequence", http,
+            json!({
+                "type": "object",
+                "properties": {"n": {"type": "integer", "minimum": 0, "maximum": 100}},
+                "required": ["n"]
+            }),
+            json!({"n": 10}),
+        ),
+
+        // 工具服务
+        describe_with_params(
+            "tools.echo", "tools", "Echo the input", http,
+            json!({}),
+            json!({"message": "hello"}),
+        ),
+        describe("tools.timestamp", "tools", "Get the current timestamp", http),
+        describe("tools.uuid", "tools", "Generate a UUID", http),
+
+        // 流式服务（HTTP 返回说明，WebSocket 控制实际的流）
+        describe_with_params(
+            "stream.data", "stream", "Start or stop a data stream pushing stream.data.update notifications", both,
+            json!({
+                "type": "object",
+                "properties": {
+                    "action": {"type": "string", "enum": ["start", "stop"]},
+                    "interval_ms": {"type": "integer", "minimum": 1},
+                    "stream_id": {"type": "string"}
+                },
+                "required": ["action"]
+            }),
+            json!({"action": "start", "interval_ms": 1000}),
+        ),
+        describe_with_params(
+            "stream.chat", "stream", "Start or stop a chat stream", both,
+            json!({
+                "type": "object",
+                "properties": {
+                    "action": {"type": "string", "enum": ["start", "stop"]},
+                    "room": {"type": "string"}
+                },
+                "required": ["action"]
+            }),
+            json!({"action": "start", "room": "general"}),
+        ),
+
+        // WebSocket 特定方法
+        describe("ws.ping", "websocket", "Ping the server", websocket),
+        describe("ws.status", "websocket", "Get the connection status", websocket),
+        describe_with_params(
+            "ws.subscribe", "websocket", "Subscribe to a data stream, chat room or event topic", websocket,
+            json!({
+                "type": "object",
+                "properties": {
+                    "type": {"type": "string", "enum": ["data_stream", "chat_room", "events"]},
+                    "interval_ms": {"type": "integer", "minimum": 1},
+                    "room": {"type": "string"},
+                    "topic": {"type": "string"},
+                    "trn": {"type": "string"}
+                },
+                "required": ["type"]
+            }),
+            json!({"type": "events", "topic": "*"}),
+        ),
+        describe_with_params(
+            "ws.unsubscribe", "websocket", "Cancel a subscription", websocket,
+            json!({
+                "type": "object",
+                "properties": {
+                    "subscription_id": {"type": "string"},
+                    "type": {"type": "string", "enum": ["data_stream", "chat_room", "events"]},
+                    "room": {"type": "string"}
+                },
+                "required": ["subscription_id", "type"]
+            }),
+            json!({"subscription_id": "", "type": "events"}),
+        ),
+
+        // 实时聊天
+        describe_with_params(
+            "chat.join", "chat", "Join a chat room", websocket,
+            json!({
+                "type": "object",
+                "properties": {"room": {"type": "string"}, "username": {"type": "string"}},
+                "required": ["room"]
+            }),
+            json!({"room": "general", "username": "alice"}),
+        ),
+        describe_with_params(
+            "chat.send", "chat", "Send a message to the other members of a room", websocket,
+            json!({
+                "type": "object",
+                "properties": {
+                    "room": {"type": "string"},
+                    "message": {"type": "string"},
+                    "username": {"type": "string"}
+                },
+                "required": ["room", "message"]
+            }),
+            json!({"room": "general", "message": "hello", "username": "alice"}),
+        ),
+        describe_with_params(
+            "chat.leave", "chat", "Leave a chat room", websocket,
+            json!({
+                "type": "object",
+                "properties": {"room": {"type": "string"}, "username": {"type": "string"}},
+                "required": ["room"]
+            }),
+            json!({"room": "general", "username": "alice"}),
+        ),
+    ]
+}
+
+/// Playground 服务描述
+pub fn service_info() -> ServiceInfo {
+    let mut metadata = HashMap::new();
+    metadata.insert("endpoints".to_string(), json!({
+        TRANSPORT_HTTP: "/api/jsonrpc",
+        TRANSPORT_WEBSOCKET: "/ws"
+    }));
+
+    ServiceInfo {
+        name: "JsonRPC Playground".to_string(),
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        description: "Interactive testing platform for JsonRPC-Rust framework".to_string(),
+        methods: method_catalog(),
+        health_endpoint: Some("/api/health".to_string()),
+        metadata,
+    }
+}
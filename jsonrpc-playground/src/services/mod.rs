@@ -6,6 +6,10 @@ use serde_json::{Value, json};
 use uuid::Uuid;
 use tracing::{info, debug};
 
+mod catalog;
+
+pub use catalog::{method_catalog, service_info};
+
 /// 演示服务集合
 pub struct DemoServices {
     // 这里可以添加服务特定的状态
@@ -35,19 +39,12 @@ impl DemoServices {
                     "WebSocket transport"
                 ]
             },
-            "available_methods": [
-                "system.info",
-                "system.stats", 
-                "system.sessions",
-                "math.add",
-                "math.multiply",
-                "math.fibonacci",
-                "tools.echo",
-                "tools.timestamp",
-                "tools.uuid",
-                "stream.data",
-                "stream.chat"
-            ],
+            "available_methods": method_catalog()
+                .into_iter()
+                .map(|method| method.name)
+                .collect::<Vec<_>>(),
+            "method_catalog": "/api/methods",
+            "metrics_history": "/api/metrics/history",
             "timestamp": chrono::Utc::now()
         }))
     }
@@ -178,14 +175,15 @@ impl DemoServices {
                 "jsonrpc": "2.0",
                 "method": "stream.data",
                 "params": {
-                    "type": "start|stop",
-                    "interval_ms": "数字，数据间隔毫秒数"
+                    "action": "start|stop",
+                    "interval_ms": "数字，数据间隔毫秒数",
+                    "stream_id": "停止时指定的流 ID"
                 }
             },
             "example": {
                 "jsonrpc": "2.0",
                 "method": "stream.data",
-                "params": {"type": "start", "interval_ms": 1000},
+                "params": {"action": "start", "interval_ms": 1000},
                 "id": "stream-1"
             }
         }))
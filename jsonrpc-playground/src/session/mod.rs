@@ -0,0 +1,317 @@
+//! 会话令牌与限流
+//!
+//! `POST /api/sessions` 创建会话并返回令牌。JsonRPC 调用（HTTP 和 WebSocket）
+//! 经过 jsonrpc-rust 的 `AuthLayer` 和 `RateLimitLayer`：令牌由
+//! `Authorization: Bearer <token>` 请求头或参数中的 `token` 字段提供，
+//! 每个会话有自己的令牌桶，超出限制的调用得到 -32005 错误（HTTP 上为 429）。
+//! WebSocket、SSE 和代理接口在请求时校验令牌（请求头或 `token` 查询参数）。
+//!
+//! 创建会话按客户端 IP 限流（[`SESSION_CREATION_LIMIT`]，超出时返回 429）。
+//! 空闲超过 [`SESSION_IDLE_TIMEOUT`] 的会话过期：令牌立即失效，后台任务定期
+//! 删除过期会话、令牌映射和会话的代理连接。
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use async_trait::async_trait;
+use axum::{
+    extract::{ConnectInfo, Query, Request, State},
+    http::{header, HeaderMap, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Json as ResponseJson, Response},
+};
+use serde_json::json;
+use tokio::sync::RwLock;
+use tracing::{debug, info};
+
+use jsonrpc_rust::convenience::{AuthLayer, Layer, RateLimitLayer, RateLimiter, TokenSource, TokenVerifier};
+use jsonrpc_rust::core::error::{Error, Result};
+use jsonrpc_rust::core::traits::MethodHandler;
+use jsonrpc_rust::core::types::{AuthContext, ServiceContext};
+use jsonrpc_rust::transport::{RateLimit, RateLimitConfig, RateLimitKey};
+
+use crate::server::{AppState, SessionInfo};
+
+/// 携带令牌的参数名和查询参数名
+pub const TOKEN_PARAM: &str = "token";
+
+/// 每个会话的调用限制
+pub const SESSION_RATE_LIMIT: RateLimit = RateLimit { per_second: 10.0, burst: 20 };
+
+/// 每个客户端 IP 创建会话的限制
+pub const SESSION_CREATION_LIMIT: RateLimit = RateLimit { per_second: 0.1, burst: 5 };
+
+/// 会话空闲多久后过期
+pub const SESSION_IDLE_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+
+/// 清理过期会话的间隔
+const SESSION_SWEEP_INTERVAL: Duration = Duration::from_secs(60);
+
+/// 认证方式名称
+const AUTH_METHOD: &str = "session_token";
+
+/// 会话最后一次活动早于此时间即已过期
+fn idle_cutoff(now: chrono::DateTime<chrono::Utc>) -> chrono::DateTime<chrono::Utc> {
+    now - chrono::Duration::from_std(SESSION_IDLE_TIMEOUT).expect("idle timeout fits chrono")
+}
+
+/// 已校验的会话令牌，由 [`require_session`] 和会话的 `AuthContext` 一起放入请求扩展
+#[derive(Debug, Clone)]
+pub struct SessionToken(pub String);
+
+/// 按会话令牌认证的校验器
+#[derive(Clone)]
+pub struct SessionVerifier {
+    sessions: Arc<RwLock<HashMap<String, SessionInfo>>>,
+    /// 令牌到会话 ID 的映射
+    tokens: Arc<RwLock<HashMap<String, String>>>,
+}
+
+impl SessionVerifier {
+    pub fn new(
+        sessions: Arc<RwLock<HashMap<String, SessionInfo>>>,
+        tokens: Arc<RwLock<HashMap<String, String>>>,
+    ) -> Self {
+        Self { sessions, tokens }
+    }
+
+    /// 删除 `now` 时已过期的会话及其令牌，返回被删除的会话 ID
+    pub async fn evict_idle(&self, now: chrono::DateTime<chrono::Utc>) -> Vec<String> {
+        let cutoff = idle_cutoff(now);
+        let mut sessions = self.sessions.write().await;
+        let expired: Vec<String> = sessions.values()
+            .filter(|session| session.last_activity < cutoff)
+            .map(|session| session.id.clone())
+            .collect();
+        for session_id in &expired {
+            sessions.remove(session_id);
+        }
+        drop(sessions);
+
+        if !expired.is_empty() {
+            self.tokens.write().await.retain(|_, session_id| !expired.contains(session_id));
+        }
+        expired
+    }
+}
+
+#[async_trait]
+impl TokenVerifier for SessionVerifier {
+    async fn verify(&self, token: &str) -> Result<AuthContext> {
+        let session_id = self.tokens.read().await
+            .get(token)
+            .cloned()
+            .ok_or_else(|| Error::authentication("Unknown session token"))?;
+
+        // 记录会话活动；已过期但尚未清理的会话同样拒绝
+        let now = chrono::Utc::now();
+        let mut sessions = self.sessions.write().await;
+        let session = sessions.get_mut(&session_id)
+            .filter(|session| session.last_activity >= idle_cutoff(now))
+            .ok_or_else(|| Error::authentication("Session expired"))?;
+        session.last_activity = now;
+        session.request_count += 1;
+
+        Ok(AuthContext::new(session_id, AUTH_METHOD))
+    }
+}
+
+/// 会话认证和限流中间件
+#[derive(Clone)]
+pub struct SessionGuard {
+    verifier: SessionVerifier,
+    auth: AuthLayer,
+    rate_limit: RateLimitLayer,
+    /// 按客户端 IP 划分的会话创建令牌桶
+    creation: Arc<RateLimiter>,
+}
+
+impl SessionGuard {
+    /// 创建中间件，`anonymous` 中的方法无需令牌即可调用
+    pub fn new<I, M>(verifier: SessionVerifier, anonymous: I, limit: RateLimit) -> Result<Self>
+    where
+        I: IntoIterator<Item = M>,
+        M: Into<String>,
+    {
+        let auth = anonymous.into_iter().fold(
+            AuthLayer::new()
+                .with_verifier(verifier.clone())
+                .with_token_source(TokenSource::default())
+                .with_token_source(TokenSource::Param(TOKEN_PARAM.to_string())),
+            AuthLayer::with_anonymous_method,
+        );
+        // 认证之后限流，令牌桶按会话（认证用户）划分
+        let rate_limit = RateLimitLayer::new(RateLimitConfig::new(limit).with_keys([RateLimitKey::ClientId]))?;
+        let creation = RateLimiter::new(
+            RateLimitConfig::new(SESSION_CREATION_LIMIT).with_keys([RateLimitKey::RemoteAddr]),
+        )?;
+
+        Ok(Self { verifier, auth, rate_limit, creation: Arc::new(creation) })
+    }
+
+    /// 为 `peer` 创建会话取一个令牌，同一 IP 的不同端口共用令牌桶
+    pub fn check_creation(&self, peer: SocketAddr) -> Result<()> {
+        let context = ServiceContext::new(uuid::Uuid::new_v4().to_string())
+            .with_metadata("peer", json!(peer.ip().to_string()));
+        self.creation.check("create_session", &context)
+    }
+
+    /// 删除已过期的会话，见 [`SessionVerifier::evict_idle`]
+    pub async fn evict_idle(&self, now: chrono::DateTime<chrono::Utc>) -> Vec<String> {
+        self.verifier.evict_idle(now).await
+    }
+
+    /// 为方法处理器加上认证和限流
+    pub fn layer(&self, inner: Arc<dyn MethodHandler>) -> Arc<dyn MethodHandler> {
+        self.auth.layer(self.rate_limit.layer(inner))
+    }
+
+    /// 校验连接时提供的令牌
+    pub async fn verify(&self, token: &str) -> Result<AuthContext> {
+        self.verifier.verify(token).await
+    }
+
+    /// 每个会话的调用限制
+    pub fn rate_limit(&self) -> RateLimit {
+        self.rate_limit.limiter().config().limit
+    }
+}
+
+/// 请求头或查询参数中的令牌
+fn request_token(headers: &HeaderMap, query: &HashMap<String, String>) -> Option<String> {
+    let header_token = headers.get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.strip_prefix("Bearer ").unwrap_or(value).trim().to_string());
+
+    header_token.or_else(|| query.get(TOKEN_PARAM).cloned())
+}
+
+/// 要求有效会话令牌的路由中间件，用于 WebSocket、SSE 和代理
+pub async fn require_session(
+    State(state): State<AppState>,
+    Query(query): Query<HashMap<String, String>>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let Some(token) = request_token(request.headers(), &query) else {
+        return unauthorized("Missing session token");
+    };
+
+    match state.guard.verify(&token).await {
+        Ok(auth) => {
+            debug!("会话 {} 已认证: {}", auth.user_id, request.uri().path());
+            request.extensions_mut().insert(SessionToken(token));
+            request.extensions_mut().insert(auth);
+            next.run(request).await
+        }
+        Err(err) => unauthorized(&err.to_string()),
+    }
+}
+
+fn unauthorized(message: &str) -> Response {
+    (StatusCode::UNAUTHORIZED, ResponseJson(json!({
+        "error": message,
+        "hint": "Create a session with POST /api/sessions and pass its token"
+    }))).into_response()
+}
+
+/// 会话创建处理器，按客户端 IP 限流
+pub async fn create_session_handler(
+    State(state): State<AppState>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+) -> Response {
+    if let Err(err) = state.guard.check_creation(peer) {
+        debug!("{} 创建会话被限流: {}", peer, err);
+        let retry_after = err.to_jsonrpc_error().retry_after().unwrap_or_default();
+        return (
+            StatusCode::TOO_MANY_REQUESTS,
+            [(header::RETRY_AFTER, retry_after.as_secs_f64().ceil().max(1.0).to_string())],
+            ResponseJson(json!({"error": err.to_string()})),
+        ).into_response();
+    }
+
+    let (session_id, token) = state.create_session().await;
+    let limit = state.guard.rate_limit();
+
+    ResponseJson(json!({
+        "session_id": session_id,
+        "token": token,
+        "rate_limit": {
+            "per_second": limit.per_second,
+            "burst": limit.burst
+        },
+        "usage": {
+            "http": "Authorization: Bearer <token>, or a \"token\" field in by-name params",
+            "websocket": "/ws?token=<token>",
+            "sse": "/api/sse?token=<token>",
+            "proxy": "/api/proxy/connect, then /api/proxy/jsonrpc with the same token"
+        },
+        "idle_timeout_secs": SESSION_IDLE_TIMEOUT.as_secs()
+    })).into_response()
+}
+
+/// 定期删除过期会话，并断开它们的代理连接
+pub fn spawn_session_sweeper(guard: SessionGuard) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(SESSION_SWEEP_INTERVAL);
+        loop {
+            interval.tick().await;
+            let expired = guard.evict_idle(chrono::Utc::now()).await;
+            for session_id in &expired {
+                crate::proxy::close_session(session_id).await;
+            }
+            if !expired.is_empty() {
+                info!("清理过期会话 {} 个", expired.len());
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_session_creation_is_limited_per_ip() {
+        let state = AppState::new().await;
+        let create = |peer: &str| create_session_handler(State(state.clone()), ConnectInfo(peer.parse().unwrap()));
+
+        for port in 0..SESSION_CREATION_LIMIT.burst {
+            let response = create(&format!("10.0.0.1:{}", 40000 + port)).await;
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+        // 换端口不会得到新的令牌桶
+        let response = create("10.0.0.1:50000").await;
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert!(response.headers().contains_key(header::RETRY_AFTER));
+
+        assert_eq!(create("10.0.0.2:40000").await.status(), StatusCode::OK);
+        assert_eq!(state.sessions.read().await.len(), SESSION_CREATION_LIMIT.burst as usize + 1);
+    }
+
+    #[tokio::test]
+    async fn test_idle_sessions_expire_and_are_evicted() {
+        let state = AppState::new().await;
+        let (idle_id, idle_token) = state.create_session().await;
+        let (active_id, active_token) = state.create_session().await;
+
+        let long_ago = chrono::Utc::now() - chrono::Duration::from_std(SESSION_IDLE_TIMEOUT).unwrap();
+        state.sessions.write().await.get_mut(&idle_id).unwrap().last_activity = long_ago - chrono::Duration::seconds(1);
+
+        // 过期会话的令牌在清理前就已失效
+        assert!(state.guard.verify(&idle_token).await.is_err());
+        state.guard.verify(&active_token).await.unwrap();
+
+        assert_eq!(state.guard.evict_idle(chrono::Utc::now()).await, vec![idle_id.clone()]);
+        assert!(!state.sessions.read().await.contains_key(&idle_id));
+        assert!(!state.tokens.read().await.contains_key(&idle_token));
+        assert!(state.sessions.read().await.contains_key(&active_id));
+        assert!(state.tokens.read().await.contains_key(&active_token));
+
+        // 活跃会话空闲足够久后同样被清理
+        let later = chrono::Utc::now() + chrono::Duration::from_std(SESSION_IDLE_TIMEOUT).unwrap() + chrono::Duration::seconds(1);
+        assert_eq!(state.guard.evict_idle(later).await, vec![active_id]);
+        assert!(state.tokens.read().await.is_empty());
+    }
+}
@@ -7,11 +7,13 @@ use std::sync::Arc;
 use std::time::Duration;
 use axum::{
     extract::{Query, State},
+    http::HeaderMap,
     response::{
         sse::{Event, KeepAlive, Sse},
         IntoResponse,
     },
 };
+use eventbus_rust::{EventBus, EventEnvelope};
 use futures::stream::{self, Stream};
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
@@ -20,13 +22,19 @@ use tokio_stream::StreamExt as _;
 use tracing::{info, debug, error};
 use uuid::Uuid;
 
+use crate::events::{self, GLOBAL_EVENT_BUS};
 use crate::server::AppState;
 
+/// Header sent by reconnecting `EventSource` clients
+const LAST_EVENT_ID: &str = "last-event-id";
+
 /// SSE connection parameters
 #[derive(Debug, Deserialize)]
 pub struct SseParams {
     pub stream_type: Option<String>,
     pub interval_ms: Option<u64>,
+    /// Event bus topic pattern for the `topic` stream, e.g. `user.*`
+    pub topic: Option<String>,
     #[allow(dead_code)]
     pub filter: Option<String>,
 }
@@ -36,8 +44,7 @@ pub struct SseParams {
 pub enum SseStreamType {
     SystemStats,
     JsonRpcEvents,
-    DataStream,
-    LogStream,
+    BusTopic,
     MetricsStream,
 }
 
@@ -115,17 +122,42 @@ lazy_static::lazy_static! {
     };
 }
 
+/// Number of open SSE connections
+pub async fn connection_count() -> usize {
+    SSE_MANAGER.0.get_connection_count().await
+}
+
+/// Unregisters an SSE connection when its response stream is dropped
+///
+/// Clients that go away never let the stream reach its end, so the cleanup
+/// has to happen on drop rather than after the last event.
+struct ConnectionGuard(String);
+
+impl Drop for ConnectionGuard {
+    fn drop(&mut self) {
+        let connection_id = std::mem::take(&mut self.0);
+        tokio::spawn(async move {
+            SSE_MANAGER.0.remove_connection(&connection_id).await;
+            info!("SSE connection closed: {}", connection_id);
+        });
+    }
+}
+
 /// SSE endpoint handler
 pub async fn sse_handler(
     Query(params): Query<SseParams>,
     State(app_state): State<AppState>,
+    headers: HeaderMap,
 ) -> impl IntoResponse {
     let connection_id = Uuid::new_v4().to_string();
     let stream_type = parse_stream_type(params.stream_type.as_deref());
+    let last_event_id = headers.get(LAST_EVENT_ID)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse::<u64>().ok());
     
     info!("New SSE connection: {} with stream type: {:?}", connection_id, stream_type);
 
-    let stream = create_sse_stream(connection_id.clone(), stream_type.clone(), params, app_state).await;
+    let stream = create_sse_stream(connection_id.clone(), stream_type.clone(), params, last_event_id, app_state).await;
     
     Sse::new(stream)
         .keep_alive(
@@ -140,8 +172,7 @@ fn parse_stream_type(stream_type: Option<&str>) -> SseStreamType {
     match stream_type {
         Some("stats") => SseStreamType::SystemStats,
         Some("events") => SseStreamType::JsonRpcEvents,
-        Some("data") => SseStreamType::DataStream,
-        Some("logs") => SseStreamType::LogStream,
+        Some("topic") => SseStreamType::BusTopic,
         Some("metrics") => SseStreamType::MetricsStream,
         _ => SseStreamType::SystemStats,
     }
@@ -152,6 +183,7 @@ async fn create_sse_stream(
     connection_id: String,
     stream_type: SseStreamType,
     params: SseParams,
+    last_event_id: Option<u64>,
     app_state: AppState,
 ) -> impl Stream<Item = Result<Event, axum::Error>> {
     let (tx, rx) = mpsc::unbounded_channel::<SseMessage>();
@@ -161,7 +193,7 @@ async fn create_sse_stream(
         id: connection_id.clone(),
         stream_type: stream_type.clone(),
         connected_at: chrono::Utc::now(),
-        sender: tx,
+        sender: tx.clone(),
     };
     
     SSE_MANAGER.0.add_connection(connection).await;
@@ -174,11 +206,9 @@ async fn create_sse_stream(
         SseStreamType::JsonRpcEvents => {
             start_jsonrpc_events_stream(connection_id.clone()).await;
         }
-        SseStreamType::DataStream => {
-            start_data_stream(connection_id.clone(), params.interval_ms).await;
-        }
-        SseStreamType::LogStream => {
-            start_log_stream(connection_id.clone()).await;
+        SseStreamType::BusTopic => {
+            let topic = params.topic.unwrap_or_else(|| "*".to_string());
+            start_bus_topic_stream(connection_id.clone(), tx, topic, last_event_id).await;
         }
         SseStreamType::MetricsStream => {
             start_metrics_stream(connection_id.clone(), app_state).await;
@@ -186,7 +216,7 @@ async fn create_sse_stream(
     }
 
     // Convert receiver to SSE event stream
-    let connection_id_for_cleanup = connection_id.clone();
+    let guard = ConnectionGuard(connection_id);
     tokio_stream::wrappers::UnboundedReceiverStream::new(rx)
         .map(move |msg| {
             let event = Event::default()
@@ -206,9 +236,7 @@ async fn create_sse_stream(
             }
         })
         .chain(stream::once(async move {
-            // Cleanup on stream end
-            SSE_MANAGER.0.remove_connection(&connection_id_for_cleanup).await;
-            info!("SSE connection closed: {}", connection_id_for_cleanup);
+            drop(guard);
             Err(axum::Error::new(std::io::Error::new(std::io::ErrorKind::ConnectionAborted, "Stream ended")))
         }))
 }
@@ -261,74 +289,71 @@ async fn start_jsonrpc_events_stream(connection_id: String) {
     // This will be fed by the JsonRPC handler when requests are processed
 }
 
-/// Start data streaming
-async fn start_data_stream(connection_id: String, interval_ms: Option<u64>) {
-    let interval = Duration::from_millis(interval_ms.unwrap_or(1000));
-    let connection_id_clone = connection_id.clone();
-    
-    tokio::spawn(async move {
-        let mut interval_timer = tokio::time::interval(interval);
-        let mut counter = 0u64;
-        
-        loop {
-            interval_timer.tick().await;
-            counter += 1;
-            
-            let message = SseMessage {
-                id: format!("data-{}", counter),
-                event_type: "data-update".to_string(),
-                timestamp: chrono::Utc::now(),
-                data: json!({
-                    "counter": counter,
-                    "random_value": fastrand::f64(),
-                    "sine_wave": (counter as f64 * 0.1).sin(),
-                    "fibonacci": calculate_fibonacci_nth(counter % 20),
-                    "timestamp": chrono::Utc::now(),
-                }),
-            };
-            
-            SSE_MANAGER.0.send_event(message);
-            debug!("Sent data update #{} for connection {}", counter, connection_id_clone);
+/// Start event bus topic streaming
+///
+/// Subscribes before replaying stored events, so nothing published in between
+/// is lost; live events the replay already covered are skipped by sequence number.
+async fn start_bus_topic_stream(
+    connection_id: String,
+    sender: mpsc::UnboundedSender<SseMessage>,
+    topic: String,
+    last_event_id: Option<u64>,
+) {
+    let mut live = match GLOBAL_EVENT_BUS.subscribe(&topic).await {
+        Ok(live) => live,
+        Err(e) => {
+            error!("Failed to subscribe SSE connection {} to {}: {}", connection_id, topic, e);
+            return;
         }
-    });
-}
+    };
+
+    let replay = match last_event_id {
+        Some(last) => events::events_after(&topic, last).await.unwrap_or_else(|e| {
+            error!("Failed to replay events after {} on {}: {}", last, topic, e);
+            Vec::new()
+        }),
+        None => Vec::new(),
+    };
+    debug!("Replaying {} events on {} for connection {}", replay.len(), topic, connection_id);
 
-/// Start log streaming
-async fn start_log_stream(connection_id: String) {
-    let connection_id_clone = connection_id.clone();
-    
     tokio::spawn(async move {
-        let mut interval = tokio::time::interval(Duration::from_secs(3));
-        let mut counter = 0u64;
-        
-        let log_levels = ["INFO", "WARN", "ERROR", "DEBUG"];
-        let components = ["server", "websocket", "sse", "events", "services"];
-        
+        let mut delivered = last_event_id.unwrap_or(0);
+
+        for event in replay {
+            delivered = event.sequence_number.unwrap_or(delivered);
+            if sender.send(bus_event_message(event)).is_err() {
+                return;
+            }
+        }
+
         loop {
-            interval.tick().await;
-            counter += 1;
-            
-            let level = log_levels[counter as usize % log_levels.len()];
-            let component = components[counter as usize % components.len()];
-            
-            let message = SseMessage {
-                id: format!("log-{}", counter),
-                event_type: "log-entry".to_string(),
-                timestamp: chrono::Utc::now(),
-                data: json!({
-                    "level": level,
-                    "component": component,
-                    "message": format!("Sample log message #{} from {}", counter, component),
-                    "timestamp": chrono::Utc::now(),
-                }),
-            };
-            
-            SSE_MANAGER.0.send_event(message);
-            debug!("Sent log entry #{} for connection {}", counter, connection_id_clone);
+            tokio::select! {
+                _ = sender.closed() => break,
+                event = live.next() => match event {
+                    Some(event) if event.sequence_number.is_some_and(|number| number <= delivered) => {}
+                    Some(event) => {
+                        if sender.send(bus_event_message(event)).is_err() {
+                            break;
+                        }
+                    }
+                    None => break,
+                },
+            }
         }
+        debug!("Event bus stream on {} ended for connection {}", topic, connection_id);
     });
 }
 
+/// SSE message for a bus event; the sequence number is the resumable event id
+fn bus_event_message(event: EventEnvelope) -> SseMessage {
+    SseMessage {
+        id: event.sequence_number.map(|number| number.to_string()).unwrap_or_else(|| event.event_id.clone()),
+        event_type: "bus-event".to_string(),
+        timestamp: chrono::DateTime::from_timestamp(event.timestamp, 0).unwrap_or_else(chrono::Utc::now),
+        data: serde_json::to_value(&event).unwrap_or_default(),
+    }
+}
+
 /// Start metrics streaming
 async fn start_metrics_stream(connection_id: String, app_state: AppState) {
     let connection_id_clone = connection_id.clone();
@@ -386,28 +411,11 @@ pub fn send_jsonrpc_event(method: &str, params: &Value, response: &Value, succes
     SSE_MANAGER.0.send_event(message);
 }
 
-/// Calculate nth Fibonacci number
-fn calculate_fibonacci_nth(n: u64) -> u64 {
-    if n <= 1 {
-        return n;
-    }
-    
-    let mut a = 0;
-    let mut b = 1;
-    
-    for _ in 2..=n {
-        let temp = a + b;
-        a = b;
-        b = temp;
-    }
-    
-    b
-}
-
 /// Get SSE connection info
 pub async fn get_sse_info() -> Value {
     json!({
         "active_connections": SSE_MANAGER.0.get_connection_count().await,
+        "authentication": "Pass a session token from POST /api/sessions as ?token=<token> or an Authorization header",
         "available_streams": [
             {
                 "type": "stats",
@@ -420,14 +428,9 @@ pub async fn get_sse_info() -> Value {
                 "endpoint": "/api/sse?stream_type=events"
             },
             {
-                "type": "data",
-                "description": "Generated data stream",
-                "endpoint": "/api/sse?stream_type=data&interval_ms=1000"
-            },
-            {
-                "type": "logs",
-                "description": "System log entries",
-                "endpoint": "/api/sse?stream_type=logs"
+                "type": "topic",
+                "description": "Event bus envelopes on a topic pattern; reconnect with Last-Event-ID to resume from the bus storage",
+                "endpoint": "/api/sse?stream_type=topic&topic=user.*"
             },
             {
                 "type": "metrics",
@@ -436,4 +439,40 @@ pub async fn get_sse_info() -> Value {
             }
         ]
     })
-} 
\ No newline at end of file
+} 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn emit(topic: &str, n: u64) -> u64 {
+        let event = EventEnvelope::new(topic, json!({"n": n}));
+        let event_id = event.event_id.clone();
+        GLOBAL_EVENT_BUS.emit(event).await.unwrap();
+
+        let stored = events::events_after(topic, 0).await.unwrap();
+        stored.iter().find(|event| event.event_id == event_id).unwrap().sequence_number.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_topic_stream_resumes_from_last_event_id() {
+        let first = emit("resume.orders", 1).await;
+        let second = emit("resume.orders", 2).await;
+        emit("resume.other", 3).await;
+
+        // Resuming after the first event replays the stored second one, then pushes live events
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        start_bus_topic_stream("test".to_string(), tx, "resume.orders".to_string(), Some(first)).await;
+        let live = emit("resume.orders", 4).await;
+
+        let replayed = rx.recv().await.unwrap();
+        assert_eq!(replayed.id, second.to_string());
+        assert_eq!(replayed.event_type, "bus-event");
+        assert_eq!(replayed.data["topic"], "resume.orders");
+        assert_eq!(replayed.data["payload"]["n"], 2);
+
+        let pushed = rx.recv().await.unwrap();
+        assert_eq!(pushed.id, live.to_string());
+        assert_eq!(pushed.data["payload"]["n"], 4);
+        assert!(tokio::time::timeout(Duration::from_millis(100), rx.recv()).await.is_err());
+    }
+}
@@ -4,38 +4,90 @@
 
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Duration;
+use async_trait::async_trait;
 use axum::{
-    extract::{State, WebSocketUpgrade, ws::{WebSocket, Message}},
-    response::Response,
+    extract::{Extension, State, WebSocketUpgrade, ws::{close_code, CloseFrame, WebSocket, Message}},
+    response::{Json as ResponseJson, Response},
 };
 use tokio::sync::{RwLock, mpsc};
 use futures::{sink::SinkExt, stream::StreamExt};
 use serde_json::{Value, json};
 use uuid::Uuid;
-use tracing::{info, debug, error};
+use tracing::{info, debug, error, warn};
 
 // 使用 jsonrpc-rust 库的类型定义
 use jsonrpc_rust::prelude::*;
+use jsonrpc_rust::core::types::AuthContext;
+use eventbus_rust::EventBus;
 
 use crate::server::AppState;
+use crate::session::SessionToken;
 
 /// WebSocket连接管理器
 pub type ConnectionManager = Arc<RwLock<HashMap<String, ConnectionInfo>>>;
 
+/// 连接保活和空闲超时设置
+#[derive(Debug, Clone, Copy)]
+pub struct WebSocketSettings {
+    /// 服务器发送 Ping 的间隔
+    pub ping_interval: Duration,
+    /// Ping 间隔之后仍未收到任何帧（包括 Pong）的容忍时长，超过即判定连接失效
+    pub pong_timeout: Duration,
+    /// 没有 JsonRPC 消息多久后关闭连接
+    pub idle_timeout: Duration,
+}
+
+impl Default for WebSocketSettings {
+    fn default() -> Self {
+        Self {
+            ping_interval: Duration::from_secs(30),
+            pong_timeout: Duration::from_secs(10),
+            idle_timeout: Duration::from_secs(600),
+        }
+    }
+}
+
+impl WebSocketSettings {
+    /// 默认设置，可由 `PLAYGROUND_WS_PING_INTERVAL_SECS`、`PLAYGROUND_WS_PONG_TIMEOUT_SECS`
+    /// 和 `PLAYGROUND_WS_IDLE_TIMEOUT_SECS` 环境变量覆盖
+    pub fn from_env() -> Self {
+        let secs = |name: &str, default: Duration| {
+            std::env::var(name).ok()
+                .and_then(|value| value.parse::<u64>().ok())
+                .map(Duration::from_secs)
+                .unwrap_or(default)
+        };
+        let defaults = Self::default();
+
+        Self {
+            ping_interval: secs("PLAYGROUND_WS_PING_INTERVAL_SECS", defaults.ping_interval),
+            pong_timeout: secs("PLAYGROUND_WS_PONG_TIMEOUT_SECS", defaults.pong_timeout),
+            idle_timeout: secs("PLAYGROUND_WS_IDLE_TIMEOUT_SECS", defaults.idle_timeout),
+        }
+    }
+}
+
 /// 连接信息
 #[derive(Debug, Clone)]
 pub struct ConnectionInfo {
     pub id: String,
+    /// 建立连接的会话
+    pub session_id: String,
     pub connected_at: chrono::DateTime<chrono::Utc>,
+    /// 最近一次 JsonRPC 消息，用于空闲超时
     pub last_activity: chrono::DateTime<chrono::Utc>,
+    /// 最近一次收到任何帧（包括 Pong），用于存活检测
+    pub last_seen: chrono::DateTime<chrono::Utc>,
     pub message_count: u64,
     pub subscriptions: Vec<String>,
+    /// 出站消息通道，由该连接的写任务独占发送端
+    pub outbound: mpsc::UnboundedSender<Message>,
 }
 
 /// 活跃数据流
 #[derive(Debug)]
 pub struct DataStream {
-    #[allow(dead_code)]
     pub id: String,
     pub connection_id: String,
     #[allow(dead_code)]
@@ -70,30 +122,38 @@ lazy_static::lazy_static! {
 }
 
 /// WebSocket升级处理器
+///
+/// 连接需要会话令牌（由 `require_session` 校验），连接上的每个调用都以该令牌认证和限流
 pub async fn websocket_handler(
     ws: WebSocketUpgrade,
     State(state): State<AppState>,
+    Extension(SessionToken(token)): Extension<SessionToken>,
+    Extension(auth): Extension<AuthContext>,
 ) -> Response {
-    ws.on_upgrade(|socket| handle_websocket(socket, state))
+    ws.on_upgrade(|socket| handle_websocket(socket, state, token, auth.user_id))
 }
 
 /// 处理WebSocket连接
-async fn handle_websocket(socket: WebSocket, _state: AppState) {
+async fn handle_websocket(socket: WebSocket, state: AppState, token: String, session_id: String) {
     let connection_id = Uuid::new_v4().to_string();
+    let settings = state.websocket;
     info!("WebSocket 连接建立: {}", connection_id);
     
-    // 注册连接
-    let connection = ConnectionInfo {
-        id: connection_id.clone(),
-        connected_at: chrono::Utc::now(),
-        last_activity: chrono::Utc::now(),
-        message_count: 0,
-        subscriptions: Vec::new(),
-    };
+    let (mut sender, mut receiver) = socket.split();
     
-    WS_STATE.connections.write().await.insert(connection_id.clone(), connection);
+    // 写任务：响应、数据流更新、聊天广播和服务器通知都经由出站通道发送，发出关闭帧后结束
+    let (outbound, mut outbound_rx) = mpsc::unbounded_channel::<Message>();
+    let mut writer = tokio::spawn(async move {
+        while let Some(message) = outbound_rx.recv().await {
+            let closing = matches!(message, Message::Close(_));
+            if sender.send(message).await.is_err() || closing {
+                break;
+            }
+        }
+    });
     
-    let (mut sender, mut receiver) = socket.split();
+    // 注册连接
+    register_connection(&connection_id, &session_id, outbound.clone()).await;
     
     // 发送欢迎消息
     let welcome_response = JsonRpcResponse::success(
@@ -108,50 +168,218 @@ async fn handle_websocket(socket: WebSocket, _state: AppState) {
     );
     
     if let Ok(welcome_msg) = serde_json::to_string(&welcome_response) {
-        if sender.send(Message::Text(welcome_msg)).await.is_err() {
+        if outbound.send(Message::Text(welcome_msg)).is_err() {
             error!("发送欢迎消息失败");
+            cleanup_connection(&connection_id).await;
             return;
         }
     }
     
-    // 处理消息循环
-    while let Some(msg) = receiver.next().await {
-        match msg {
-            Ok(Message::Text(text)) => {
-                debug!("收到消息: {}", text);
-                
-                // 更新连接活动时间
-                if let Some(connection) = WS_STATE.connections.write().await.get_mut(&connection_id) {
-                    connection.last_activity = chrono::Utc::now();
-                    connection.message_count += 1;
-                }
+    // 处理消息循环，按 Ping 间隔检查存活和空闲
+    let mut heartbeat = tokio::time::interval_at(tokio::time::Instant::now() + settings.ping_interval, settings.ping_interval);
+    let mut last_seen = tokio::time::Instant::now();
+    let mut last_message = tokio::time::Instant::now();
+    let mut closing = false;
+    
+    loop {
+        tokio::select! {
+            msg = receiver.next() => {
+                let Some(msg) = msg else { break };
+                last_seen = tokio::time::Instant::now();
                 
-                // 处理JsonRPC请求
-                if let Some(response_text) = handle_jsonrpc_message(&connection_id, &text).await {
-                    if sender.send(Message::Text(response_text)).await.is_err() {
-                        error!("发送响应失败");
+                match msg {
+                    Ok(Message::Text(text)) => {
+                        debug!("收到消息: {}", text);
+                        last_message = last_seen;
+                        
+                        // 更新连接活动时间
+                        update_connection_activity(&connection_id, true).await;
+                        
+                        // 处理JsonRPC请求
+                        if let Some(response_text) = handle_jsonrpc_message(&state, &token, &connection_id, &text).await {
+                            if outbound.send(Message::Text(response_text)).is_err() {
+                                error!("发送响应失败");
+                                break;
+                            }
+                        }
+                    }
+                    Ok(Message::Close(_)) => {
+                        info!("WebSocket 连接关闭: {}", connection_id);
+                        break;
+                    }
+                    Err(e) => {
+                        error!("WebSocket 错误: {}", e);
                         break;
                     }
+                    Ok(_) => update_connection_activity(&connection_id, false).await,
                 }
             }
-            Ok(Message::Close(_)) => {
-                info!("WebSocket 连接关闭: {}", connection_id);
-                break;
-            }
-            Err(e) => {
-                error!("WebSocket 错误: {}", e);
-                break;
+            _ = heartbeat.tick() => {
+                if last_seen.elapsed() > settings.ping_interval + settings.pong_timeout {
+                    warn!("WebSocket 连接 {} 未响应 Ping，判定失效", connection_id);
+                    break;
+                }
+                if last_message.elapsed() >= settings.idle_timeout {
+                    info!("WebSocket 连接 {} 空闲超时", connection_id);
+                    closing = outbound.send(Message::Close(Some(CloseFrame {
+                        code: close_code::AWAY,
+                        reason: "idle timeout".into(),
+                    }))).is_ok();
+                    break;
+                }
+                if outbound.send(Message::Ping(Vec::new())).is_err() {
+                    break;
+                }
             }
-            _ => {}
         }
     }
     
-    // 清理连接
+    // 清理连接，等待关闭帧发出后停止写任务
     cleanup_connection(&connection_id).await;
+    if closing {
+        let _ = tokio::time::timeout(Duration::from_secs(1), &mut writer).await;
+    }
+    writer.abort();
+}
+
+/// 向出站通道发送 JsonRPC 通知，通道已关闭时返回 false
+fn send_notification(outbound: &mpsc::UnboundedSender<Message>, method: &str, params: Value) -> bool {
+    let notification = JsonRpcRequest::notification(method, Some(params));
+    match serde_json::to_string(&notification) {
+        Ok(text) => outbound.send(Message::Text(text)).is_ok(),
+        Err(e) => {
+            error!("序列化通知失败: {}", e);
+            false
+        }
+    }
+}
+
+/// 当前 WebSocket 连接数
+pub async fn connection_count() -> usize {
+    WS_STATE.connections.read().await.len()
+}
+
+/// 获取连接的出站通道
+async fn connection_outbound(connection_id: &str) -> anyhow::Result<mpsc::UnboundedSender<Message>> {
+    WS_STATE.connections.read().await
+        .get(connection_id)
+        .map(|connection| connection.outbound.clone())
+        .ok_or_else(|| anyhow::anyhow!("Connection not found"))
+}
+
+/// 向聊天室成员（除 `except` 外）广播通知，返回送达数量
+async fn broadcast_to_room(room_name: &str, except: Option<&str>, method: &str, params: Value) -> usize {
+    let rooms = WS_STATE.chat_rooms.read().await;
+    let connections = WS_STATE.connections.read().await;
+    
+    rooms.get(room_name)
+        .map(|room| {
+            room.members.iter()
+                .filter(|member| Some(member.as_str()) != except)
+                .filter_map(|member| connections.get(member))
+                .filter(|connection| send_notification(&connection.outbound, method, params.clone()))
+                .count()
+        })
+        .unwrap_or(0)
+}
+
+/// 启动数据生成任务，按间隔向连接推送 `stream.data.update` 通知
+async fn spawn_data_stream(connection_id: &str, stream_id: String, interval_ms: u64) -> anyhow::Result<()> {
+    let outbound = connection_outbound(connection_id).await?;
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    
+    // 存储流信息
+    let stream = DataStream {
+        id: stream_id.clone(),
+        connection_id: connection_id.to_string(),
+        interval_ms,
+        sender: tx,
+    };
+    
+    WS_STATE.data_streams.write().await.insert(stream_id.clone(), stream);
+    
+    tokio::spawn(async move {
+        let mut counter = 0u64;
+        let mut interval = tokio::time::interval(tokio::time::Duration::from_millis(interval_ms));
+        
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {
+                    counter += 1;
+                    
+                    let params = json!({
+                        "stream_id": stream_id,
+                        "counter": counter,
+                        "timestamp": chrono::Utc::now(),
+                        "random_value": fastrand::f64(),
+                        "data": format!("Generated data #{}", counter)
+                    });
+                    
+                    if !send_notification(&outbound, "stream.data.update", params) {
+                        debug!("数据流 [{}] 连接已关闭", stream_id);
+                        break;
+                    }
+                }
+                _ = rx.recv() => {
+                    info!("数据流 [{}] 停止", stream_id);
+                    break;
+                }
+            }
+        }
+        
+        // 清理流信息
+        WS_STATE.data_streams.write().await.remove(&stream_id);
+    });
+    
+    Ok(())
+}
+
+/// 启动事件转发任务，把事件总线上的事件作为 `events.published` 通知推送给连接
+async fn spawn_event_forwarder(connection_id: &str, stream_id: String, topic: &str, trn: Option<&str>) -> anyhow::Result<()> {
+    let outbound = connection_outbound(connection_id).await?;
+    let mut events = match trn {
+        Some(pattern) => crate::events::GLOBAL_EVENT_BUS.subscribe_trn(pattern).await?,
+        None => crate::events::GLOBAL_EVENT_BUS.subscribe(topic).await?,
+    };
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    
+    let stream = DataStream {
+        id: stream_id.clone(),
+        connection_id: connection_id.to_string(),
+        interval_ms: 0,
+        sender: tx,
+    };
+    
+    WS_STATE.data_streams.write().await.insert(stream_id.clone(), stream);
+    
+    tokio::spawn(async move {
+        loop {
+            tokio::select! {
+                Some(event) = events.next() => {
+                    let params = json!({
+                        "stream_id": stream_id,
+                        "event": event
+                    });
+                    
+                    if !send_notification(&outbound, "events.published", params) {
+                        break;
+                    }
+                }
+                _ = rx.recv() => {
+                    info!("事件订阅 [{}] 停止", stream_id);
+                    break;
+                }
+            }
+        }
+        
+        WS_STATE.data_streams.write().await.remove(&stream_id);
+    });
+    
+    Ok(())
 }
 
 /// 处理JsonRPC消息
-async fn handle_jsonrpc_message(connection_id: &str, text: &str) -> Option<String> {
+async fn handle_jsonrpc_message(state: &AppState, token: &str, connection_id: &str, text: &str) -> Option<String> {
     // 解析JsonRPC请求
     let request: JsonRpcRequest = match serde_json::from_str(text) {
         Ok(req) => req,
@@ -165,12 +393,33 @@ async fn handle_jsonrpc_message(connection_id: &str, text: &str) -> Option<Strin
         }
     };
     
-    let response = process_websocket_request(connection_id, request).await;
+    let handler = Arc::new(WebSocketMethods { connection_id: connection_id.to_string() });
+    let response = state.call(handler, &request, Some(token)).await;
     serde_json::to_string(&response).ok()
 }
 
+/// 单个连接的方法处理器，供会话认证和限流中间件包装
+struct WebSocketMethods {
+    connection_id: String,
+}
+
+#[async_trait]
+impl MethodHandler for WebSocketMethods {
+    async fn handle_method(&self, request: &JsonRpcRequest, _context: &ServiceContext) -> jsonrpc_rust::Result<JsonRpcResponse> {
+        Ok(process_websocket_request(&self.connection_id, request).await)
+    }
+    
+    fn supported_methods(&self) -> Vec<String> {
+        crate::services::method_catalog()
+            .into_iter()
+            .filter(|method| method.metadata["transports"].as_array().is_some_and(|t| t.contains(&json!("websocket"))))
+            .map(|method| method.name)
+            .collect()
+    }
+}
+
 /// 处理WebSocket JsonRPC请求
-async fn process_websocket_request(connection_id: &str, request: JsonRpcRequest) -> JsonRpcResponse {
+async fn process_websocket_request(connection_id: &str, request: &JsonRpcRequest) -> JsonRpcResponse {
     let method = request.method();
     let params = request.params.clone().unwrap_or(Value::Null);
     let request_id = request.id().cloned().unwrap_or(Value::Null);
@@ -220,8 +469,10 @@ async fn handle_connection_status(connection_id: &str) -> anyhow::Result<Value>
     
     Ok(json!({
         "id": connection_info.id,
+        "session_id": connection_info.session_id,
         "connected_at": connection_info.connected_at,
         "last_activity": connection_info.last_activity,
+        "last_seen": connection_info.last_seen,
         "message_count": connection_info.message_count,
         "subscriptions": connection_info.subscriptions
     }))
@@ -241,61 +492,34 @@ async fn handle_subscription(connection_id: &str, params: Value) -> anyhow::Resu
                 .and_then(|i| i.as_u64())
                 .unwrap_or(1000);
             
-                         let stream_id = format!("{}_{}", connection_id, subscription_id);
-             let stream_id_clone = stream_id.clone();
-             let (tx, mut rx) = mpsc::unbounded_channel();
-             
-             let stream = DataStream {
-                 id: stream_id.clone(),
-                 connection_id: connection_id.to_string(),
-                 interval_ms,
-                 sender: tx,
-             };
-             
-             WS_STATE.data_streams.write().await.insert(stream_id.clone(), stream);
-             
-             tokio::spawn(async move {
-                 let mut counter = 0u64;
-                 let mut interval = tokio::time::interval(tokio::time::Duration::from_millis(interval_ms));
-                 
-                 loop {
-                     tokio::select! {
-                         _ = interval.tick() => {
-                             counter += 1;
-                             
-                             let _data_msg = json!({
-                                 "jsonrpc": "2.0",
-                                 "method": "stream.data.update",
-                                 "params": {
-                                     "stream_id": stream_id_clone,
-                                     "counter": counter,
-                                     "timestamp": chrono::Utc::now(),
-                                     "random_value": fastrand::f64(),
-                                     "data": format!("Generated data #{}", counter)
-                                 }
-                             });
-                             
-                             // 这里应该向连接发送消息，暂时记录日志
-                             debug!("数据流 [{}] 生成数据: {}", stream_id_clone, counter);
-                         }
-                         _ = rx.recv() => {
-                             info!("数据流 [{}] 停止", stream_id_clone);
-                             break;
-                         }
-                     }
-                 }
-                 
-                 // 清理流信息
-                 WS_STATE.data_streams.write().await.remove(&stream_id_clone);
-             });
-             
-             Ok(json!({
-                 "subscription_id": subscription_id,
-                 "stream_id": stream_id,
-                 "status": "started",
-                 "interval_ms": interval_ms,
-                 "message": "Data stream subscription started"
-             }))
+            let stream_id = format!("{}_{}", connection_id, subscription_id);
+            spawn_data_stream(connection_id, stream_id.clone(), interval_ms).await?;
+            
+            Ok(json!({
+                "subscription_id": subscription_id,
+                "stream_id": stream_id,
+                "status": "started",
+                "interval_ms": interval_ms,
+                "message": "Data stream subscription started"
+            }))
+        }
+        "events" => {
+            let topic = params.get("topic")
+                .and_then(|t| t.as_str())
+                .unwrap_or("*");
+            let trn = params.get("trn").and_then(|t| t.as_str());
+            
+            let stream_id = format!("{}_{}", connection_id, subscription_id);
+            spawn_event_forwarder(connection_id, stream_id.clone(), topic, trn).await?;
+            
+            Ok(json!({
+                "subscription_id": subscription_id,
+                "stream_id": stream_id,
+                "status": "started",
+                "topic": topic,
+                "trn": trn,
+                "message": "Event subscription started"
+            }))
         }
         "chat_room" => {
             let room_name = params.get("room")
@@ -332,7 +556,7 @@ async fn handle_unsubscription(connection_id: &str, params: Value) -> anyhow::Re
         .ok_or_else(|| anyhow::anyhow!("Missing type parameter"))?;
     
     match subscription_type {
-        "data_stream" => {
+        "data_stream" | "events" => {
             let stream_id = format!("{}_{}", connection_id, subscription_id);
             let _ = stop_data_stream(&stream_id).await;
             Ok(json!({
@@ -466,22 +690,31 @@ async fn handle_chat_send(connection_id: &str, params: Value) -> anyhow::Result<
         .and_then(|u| u.as_str())
         .unwrap_or("Anonymous");
     
-    let rooms = WS_STATE.chat_rooms.read().await;
-    let room = rooms.get(room_name)
-        .ok_or_else(|| anyhow::anyhow!("Room not found"))?;
-    
-    if !room.members.contains(&connection_id.to_string()) {
-        return Err(anyhow::anyhow!("Not a member of this room"));
+    {
+        let rooms = WS_STATE.chat_rooms.read().await;
+        let room = rooms.get(room_name)
+            .ok_or_else(|| anyhow::anyhow!("Room not found"))?;
+        
+        if !room.members.contains(&connection_id.to_string()) {
+            return Err(anyhow::anyhow!("Not a member of this room"));
+        }
     }
     
-    Ok(json!({
-        "status": "sent",
+    let chat_message = json!({
         "room": room_name,
         "username": username,
         "message": message,
         "timestamp": chrono::Utc::now(),
         "message_id": Uuid::new_v4()
-    }))
+    });
+    
+    // 向房间其他成员广播
+    let delivered_to = broadcast_to_room(room_name, Some(connection_id), "chat.message", chat_message.clone()).await;
+    
+    let mut response = chat_message;
+    response["status"] = json!("sent");
+    response["delivered_to"] = json!(delivered_to);
+    Ok(response)
 }
 
 /// 处理离开聊天室
@@ -513,56 +746,7 @@ async fn handle_chat_leave(connection_id: &str, params: Value) -> anyhow::Result
 /// 启动数据流
 async fn start_data_stream(connection_id: &str, interval_ms: u64) -> anyhow::Result<Value> {
     let stream_id = format!("{}_{}", connection_id, Uuid::new_v4());
-    let (tx, mut rx) = mpsc::unbounded_channel();
-    
-    // 存储流信息
-    let stream = DataStream {
-        id: stream_id.clone(),
-        connection_id: connection_id.to_string(),
-        interval_ms,
-        sender: tx,
-    };
-    
-    WS_STATE.data_streams.write().await.insert(stream_id.clone(), stream);
-    
-    // 启动数据生成任务
-    let stream_id_clone = stream_id.clone();
-    let _connection_id_clone = connection_id.to_string();
-    
-    tokio::spawn(async move {
-        let mut counter = 0u64;
-        let mut interval = tokio::time::interval(tokio::time::Duration::from_millis(interval_ms));
-        
-        loop {
-            tokio::select! {
-                _ = interval.tick() => {
-                    counter += 1;
-                    
-                    let _data_msg = json!({
-                        "jsonrpc": "2.0",
-                        "method": "stream.data.update",
-                        "params": {
-                            "stream_id": stream_id_clone,
-                            "counter": counter,
-                            "timestamp": chrono::Utc::now(),
-                            "random_value": fastrand::f64(),
-                            "data": format!("Generated data #{}", counter)
-                        }
-                    });
-                    
-                    // 这里应该向连接发送消息，暂时记录日志
-                    debug!("数据流 [{}] 生成数据: {}", stream_id_clone, counter);
-                }
-                _ = rx.recv() => {
-                    info!("数据流 [{}] 停止", stream_id_clone);
-                    break;
-                }
-            }
-        }
-        
-        // 清理流信息
-        WS_STATE.data_streams.write().await.remove(&stream_id_clone);
-    });
+    spawn_data_stream(connection_id, stream_id.clone(), interval_ms).await?;
     
     Ok(json!({
         "stream_id": stream_id,
@@ -573,7 +757,23 @@ async fn start_data_stream(connection_id: &str, interval_ms: u64) -> anyhow::Res
 }
 
 /// 停止数据流
-async fn stop_data_stream(connection_id: &str) -> anyhow::Result<Value> {
+async fn stop_data_stream(stream_id: &str) -> anyhow::Result<Value> {
+    let stopped_count = match WS_STATE.data_streams.write().await.remove(stream_id) {
+        Some(stream) => {
+            let _ = stream.sender.send(());
+            1
+        }
+        None => 0,
+    };
+    
+    Ok(json!({
+        "stopped_streams": stopped_count,
+        "message": "Data streams stopped successfully"
+    }))
+}
+
+/// 停止连接的所有数据流
+async fn stop_connection_streams(connection_id: &str) -> anyhow::Result<Value> {
     let mut streams = WS_STATE.data_streams.write().await;
     let mut stopped_count = 0;
     
@@ -597,89 +797,32 @@ async fn stop_data_stream(connection_id: &str) -> anyhow::Result<Value> {
     }))
 }
 
-
-
-/// 加入聊天室
-async fn join_chat_room(connection_id: &str, room_name: &str) -> anyhow::Result<Value> {
-    let mut rooms = WS_STATE.chat_rooms.write().await;
-    
-    let room = rooms.entry(room_name.to_string()).or_insert_with(|| ChatRoom {
-        name: room_name.to_string(),
-        members: Vec::new(),
-        created_at: chrono::Utc::now(),
-    });
-    
-    if !room.members.contains(&connection_id.to_string()) {
-        room.members.push(connection_id.to_string());
-    }
-    
-    Ok(json!({
-        "room": room_name,
-        "action": "joined",
-        "member_count": room.members.len(),
-        "timestamp": chrono::Utc::now()
-    }))
-}
-
-/// 离开聊天室
-async fn leave_chat_room(connection_id: &str, room_name: &str) -> anyhow::Result<Value> {
-    let mut rooms = WS_STATE.chat_rooms.write().await;
-    
-    if let Some(room) = rooms.get_mut(room_name) {
-        room.members.retain(|id| id != connection_id);
-        
-        Ok(json!({
-            "room": room_name,
-            "action": "left",
-            "member_count": room.members.len(),
-            "timestamp": chrono::Utc::now()
-        }))
-    } else {
-        Err(anyhow::anyhow!("Room not found: {}", room_name))
-    }
-}
-
-/// 发送聊天消息
-async fn send_chat_message(connection_id: &str, room_name: &str, message: &str) -> anyhow::Result<Value> {
-    let rooms = WS_STATE.chat_rooms.read().await;
-    
-    if let Some(room) = rooms.get(room_name) {
-        // 这里应该向房间所有成员广播消息
-        info!("聊天消息 [{}] from {}: {}", room_name, connection_id, message);
-        
-        Ok(json!({
-            "room": room_name,
-            "action": "message_sent",
-            "message": message,
-            "sender": connection_id,
-            "timestamp": chrono::Utc::now(),
-            "delivered_to": room.members.len()
-        }))
-    } else {
-        Err(anyhow::anyhow!("Room not found: {}", room_name))
-    }
-}
-
 /// 注册新连接
-async fn register_connection(connection_id: &str) {
+async fn register_connection(connection_id: &str, session_id: &str, outbound: mpsc::UnboundedSender<Message>) {
     let now = chrono::Utc::now();
     let connection = ConnectionInfo {
         id: connection_id.to_string(),
+        session_id: session_id.to_string(),
         connected_at: now,
         last_activity: now,
+        last_seen: now,
         message_count: 0,
         subscriptions: Vec::new(),
+        outbound,
     };
     
     WS_STATE.connections.write().await.insert(connection_id.to_string(), connection);
     info!("注册WebSocket连接: {}", connection_id);
 }
 
-/// 更新连接活动
-async fn update_connection_activity(connection_id: &str) {
+/// 更新连接活动：任何帧都刷新最后收到时间，文本消息还计入活动时间和消息数
+async fn update_connection_activity(connection_id: &str, is_message: bool) {
     if let Some(conn) = WS_STATE.connections.write().await.get_mut(connection_id) {
-        conn.last_activity = chrono::Utc::now();
-        conn.message_count += 1;
+        conn.last_seen = chrono::Utc::now();
+        if is_message {
+            conn.last_activity = conn.last_seen;
+            conn.message_count += 1;
+        }
     }
 }
 
@@ -689,46 +832,245 @@ async fn cleanup_connection(connection_id: &str) {
     WS_STATE.connections.write().await.remove(connection_id);
     
     // 停止所有数据流
-    let _ = stop_data_stream(connection_id).await;
+    let _ = stop_connection_streams(connection_id).await;
     
-    // 从所有聊天室移除
+    // 从所有聊天室移除，并删除没有成员的聊天室
     let mut rooms = WS_STATE.chat_rooms.write().await;
     for room in rooms.values_mut() {
         room.members.retain(|id| id != connection_id);
     }
+    rooms.retain(|_, room| !room.members.is_empty());
 }
 
-/// 获取连接信息
-async fn get_connection_info(connection_id: &str) -> anyhow::Result<Value> {
+/// 列出所有连接，包括各自的数据流和聊天室
+async fn list_connections() -> Value {
     let connections = WS_STATE.connections.read().await;
+    let streams = WS_STATE.data_streams.read().await;
+    let rooms = WS_STATE.chat_rooms.read().await;
+    let now = chrono::Utc::now();
     
-    if let Some(conn) = connections.get(connection_id) {
-        Ok(json!({
-            "id": conn.id,
-            "connected_at": conn.connected_at,
-            "last_activity": conn.last_activity,
-            "message_count": conn.message_count,
-            "subscriptions": conn.subscriptions
-        }))
-    } else {
-        Err(anyhow::anyhow!("Connection not found"))
-    }
-}
-
-/// 列出所有连接
-async fn list_connections() -> anyhow::Result<Value> {
-    let connections = WS_STATE.connections.read().await;
     let connection_list: Vec<Value> = connections.values()
         .map(|conn| json!({
             "id": conn.id,
+            "session_id": conn.session_id,
             "connected_at": conn.connected_at,
             "last_activity": conn.last_activity,
-            "message_count": conn.message_count
+            "last_seen": conn.last_seen,
+            "idle_seconds": (now - conn.last_activity).num_seconds(),
+            "message_count": conn.message_count,
+            "subscriptions": conn.subscriptions,
+            "data_streams": streams.values()
+                .filter(|stream| stream.connection_id == conn.id)
+                .map(|stream| stream.id.as_str())
+                .collect::<Vec<_>>(),
+            "chat_rooms": rooms.iter()
+                .filter(|(_, room)| room.members.contains(&conn.id))
+                .map(|(name, _)| name.as_str())
+                .collect::<Vec<_>>()
         }))
         .collect();
     
-    Ok(json!({
+    json!({
         "count": connections.len(),
         "connections": connection_list
-    }))
-} 
\ No newline at end of file
+    })
+}
+
+/// WebSocket 连接管理视图
+pub async fn connections_handler(State(state): State<AppState>) -> ResponseJson<Value> {
+    let settings = state.websocket;
+    let mut view = list_connections().await;
+    view["settings"] = json!({
+        "ping_interval_secs": settings.ping_interval.as_secs_f64(),
+        "pong_timeout_secs": settings.pong_timeout.as_secs_f64(),
+        "idle_timeout_secs": settings.idle_timeout.as_secs_f64()
+    });
+    
+    ResponseJson(view)
+} 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{routing::get, Router};
+    use jsonrpc_rust::protocol::JsonRpcClient;
+    use jsonrpc_rust::transport::websocket::{WebSocketConfig, WebSocketTransport};
+    use std::time::Duration;
+
+    /// 启动只包含 WebSocket 路由的 Playground 服务，返回连接地址和状态
+    async fn start_server() -> (String, AppState) {
+        start_server_with(WebSocketSettings::default()).await
+    }
+
+    async fn start_server_with(settings: WebSocketSettings) -> (String, AppState) {
+        let mut state = AppState::new().await;
+        state.websocket = settings;
+        let app = Router::new()
+            .route("/ws", get(websocket_handler))
+            .route_layer(axum::middleware::from_fn_with_state(state.clone(), crate::session::require_session))
+            .with_state(state.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}/ws", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        (url, state)
+    }
+
+    /// 以新会话的令牌连接
+    async fn connect(url: &str, state: &AppState) -> JsonRpcClient {
+        let (_, token) = state.create_session().await;
+        let config = WebSocketConfig::client(format!("{}?token={}", url, token));
+        JsonRpcClient::new(WebSocketTransport::connect(config).await.unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_websocket_transport_interop() {
+        let (url, state) = start_server().await;
+        let (_, token) = state.create_session().await;
+
+        // 没有有效会话令牌时拒绝连接
+        assert!(WebSocketTransport::connect(WebSocketConfig::client(url.clone())).await.is_err());
+        assert!(WebSocketTransport::connect(WebSocketConfig::client(format!("{}?token=bogus", url))).await.is_err());
+
+        // 使用框架的 WebSocket 传输和客户端连接（欢迎消息没有对应的请求，会被忽略）
+        let config = WebSocketConfig::client(format!("{}?token={}", url, token)).with_ping_interval(Some(Duration::from_millis(50)));
+        let transport = WebSocketTransport::connect(config).await.unwrap();
+        let client = JsonRpcClient::new(transport);
+
+        let pong: Value = client.call("ws.ping", ()).await.unwrap();
+        assert!(pong.get("pong").is_some());
+
+        // 保活 ping 期间连接保持可用
+        tokio::time::sleep(Duration::from_millis(150)).await;
+        let status: Value = client.call("ws.status", ()).await.unwrap();
+        assert_eq!(status["message_count"], 2);
+
+        match client.call::<_, Value>("ws.unknown", ()).await {
+            Err(jsonrpc_rust::Error::JsonRpc(error)) => assert_eq!(error.code, -32603),
+            other => panic!("unexpected result: {:?}", other),
+        }
+
+        client.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_server_pushed_notifications() {
+        let (url, state) = start_server().await;
+        let alice = connect(&url, &state).await;
+        let bob = connect(&url, &state).await;
+        let timeout = Duration::from_secs(5);
+
+        // 数据流更新以通知的形式推送
+        let mut updates = alice.notifications("stream.data.update");
+        let started: Value = alice.call("stream.data", json!({"action": "start", "interval_ms": 10})).await.unwrap();
+        let stream_id = started["stream_id"].as_str().unwrap().to_string();
+        for counter in 1..=2 {
+            let update = tokio::time::timeout(timeout, updates.recv()).await.unwrap().unwrap();
+            let params = update.params.unwrap();
+            assert_eq!(params["stream_id"], stream_id);
+            assert_eq!(params["counter"], counter);
+        }
+        let stopped: Value = alice.call("stream.data", json!({"action": "stop", "stream_id": stream_id})).await.unwrap();
+        assert_eq!(stopped["stopped_streams"], 1);
+
+        // 聊天消息广播给房间其他成员
+        let mut messages = bob.notifications("chat.message");
+        let _: Value = alice.call("chat.join", json!({"room": "push-test", "username": "alice"})).await.unwrap();
+        let _: Value = bob.call("chat.join", json!({"room": "push-test", "username": "bob"})).await.unwrap();
+        let sent: Value = alice
+            .call("chat.send", json!({"room": "push-test", "username": "alice", "message": "hello"}))
+            .await
+            .unwrap();
+        assert_eq!(sent["delivered_to"], 1);
+        let message = tokio::time::timeout(timeout, messages.recv()).await.unwrap().unwrap();
+        assert_eq!(message.params.unwrap()["message"], "hello");
+
+        // 事件总线上的事件作为服务器通知推送
+        let mut events = bob.notifications("events.published");
+        let _: Value = bob
+            .call("ws.subscribe", json!({"type": "events", "topic": "custom.ws-push-test"}))
+            .await
+            .unwrap();
+        crate::events::publish_custom_event(
+            "ws-push-test",
+            crate::events::EventLevel::Info,
+            "test",
+            json!({"value": 42}),
+            Vec::new(),
+        )
+        .await;
+        let event = tokio::time::timeout(timeout, events.recv()).await.unwrap().unwrap();
+        assert_eq!(event.params.unwrap()["event"]["payload"]["value"], 42);
+
+        alice.close().await.unwrap();
+        bob.close().await.unwrap();
+    }
+
+    /// 等待满足条件的连接全部移除
+    async fn wait_until_removed(matches: impl Fn(&Value) -> bool) -> bool {
+        for _ in 0..100 {
+            let view = list_connections().await;
+            if !view["connections"].as_array().unwrap().iter().any(&matches) {
+                return true;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        false
+    }
+
+    #[tokio::test]
+    async fn test_idle_timeout_and_liveness() {
+        let (url, state) = start_server_with(WebSocketSettings {
+            ping_interval: Duration::from_millis(50),
+            pong_timeout: Duration::from_millis(50),
+            idle_timeout: Duration::from_millis(400),
+        }).await;
+
+        // 响应 Ping 的客户端在空闲超时前保持连接，超时后数据流和聊天室一并清理
+        let client = connect(&url, &state).await;
+        let status: Value = client.call("ws.status", ()).await.unwrap();
+        let connection_id = status["id"].as_str().unwrap().to_string();
+        let _: Value = client.call("stream.data", json!({"action": "start", "interval_ms": 1000})).await.unwrap();
+        let _: Value = client.call("chat.join", json!({"room": "idle-test"})).await.unwrap();
+
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        let view = list_connections().await;
+        let connection = view["connections"].as_array().unwrap().iter()
+            .find(|connection| connection["id"] == connection_id)
+            .expect("connection answering pings stays open")
+            .clone();
+        assert_eq!(connection["data_streams"].as_array().unwrap().len(), 1);
+        assert_eq!(connection["chat_rooms"], json!(["idle-test"]));
+
+        assert!(wait_until_removed(|connection| connection["id"] == connection_id).await);
+        assert!(!WS_STATE.data_streams.read().await.values().any(|stream| stream.connection_id == connection_id));
+        assert!(!WS_STATE.chat_rooms.read().await.contains_key("idle-test"));
+
+        // 完成握手后不再读取的客户端不会回复 Pong，在空闲超时之前就被判定失效
+        let (url, state) = start_server_with(WebSocketSettings {
+            ping_interval: Duration::from_millis(50),
+            pong_timeout: Duration::from_millis(50),
+            idle_timeout: Duration::from_secs(60),
+        }).await;
+        let (session_id, token) = state.create_session().await;
+        let address = url.trim_start_matches("ws://").trim_end_matches("/ws").to_string();
+        let mut socket = tokio::net::TcpStream::connect(&address).await.unwrap();
+        let handshake = format!(
+            "GET /ws?token={} HTTP/1.1\r\nHost: {}\r\nUpgrade: websocket\r\nConnection: Upgrade\r\n\
+             Sec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\nSec-WebSocket-Version: 13\r\n\r\n",
+            token, address
+        );
+        tokio::io::AsyncWriteExt::write_all(&mut socket, handshake.as_bytes()).await.unwrap();
+
+        let mut registered = false;
+        for _ in 0..50 {
+            let view = list_connections().await;
+            if view["connections"].as_array().unwrap().iter().any(|connection| connection["session_id"] == session_id) {
+                registered = true;
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        assert!(registered);
+        assert!(wait_until_removed(|connection| connection["session_id"] == session_id).await);
+    }
+}
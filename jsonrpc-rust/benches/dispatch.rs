@@ -0,0 +1,97 @@
+//! Routing and dispatch through a [`MethodRouter`]
+//!
+//! Run with `cargo bench --features benchmarks --bench dispatch`.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use jsonrpc_rust::core::error::Result;
+use jsonrpc_rust::core::traits::MethodHandler;
+use jsonrpc_rust::core::types::{JsonRpcRequest, JsonRpcResponse, ServiceContext};
+use jsonrpc_rust::protocol::MethodRouter;
+use serde_json::{json, Value};
+
+/// Answers every call with its params
+struct Echo;
+
+#[async_trait]
+impl MethodHandler for Echo {
+    async fn handle_method(&self, request: &JsonRpcRequest, _context: &ServiceContext) -> Result<JsonRpcResponse> {
+        Ok(JsonRpcResponse::success(Value::Null, request.params.clone().unwrap_or(Value::Null)))
+    }
+
+    fn supported_methods(&self) -> Vec<String> {
+        Vec::new()
+    }
+}
+
+/// A router with `methods` exact methods and as many namespaces
+fn router(methods: usize) -> MethodRouter {
+    let handler: Arc<dyn MethodHandler> = Arc::new(Echo);
+    let mut router = MethodRouter::new();
+    for i in 0..methods {
+        router.register(format!("service{}.method", i), Arc::clone(&handler)).unwrap();
+        router.register(format!("namespace{}.*", i), Arc::clone(&handler)).unwrap();
+    }
+    router
+}
+
+fn runtime() -> tokio::runtime::Runtime {
+    tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap()
+}
+
+fn routing(c: &mut Criterion) {
+    let mut group = c.benchmark_group("resolve");
+    for methods in [1, 100, 10_000] {
+        let router = router(methods);
+        let last = methods - 1;
+        group.bench_with_input(BenchmarkId::new("exact", methods), &format!("service{}.method", last), |b, method| {
+            b.iter(|| router.resolve(black_box(method)).unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new("namespace", methods), &format!("namespace{}.call", last), |b, method| {
+            b.iter(|| router.resolve(black_box(method)).unwrap())
+        });
+    }
+    group.finish();
+}
+
+fn dispatching(c: &mut Criterion) {
+    let runtime = runtime();
+    let context = ServiceContext::new("bench");
+    let mut group = c.benchmark_group("dispatch");
+    for methods in [1, 100, 10_000] {
+        let router = router(methods);
+        let method = format!("service{}.method", methods - 1);
+
+        let request = JsonRpcRequest::with_id(method.clone(), Some(json!([1, 2, 3])), json!(1));
+        group.bench_with_input(BenchmarkId::new("request", methods), &request, |b, request| {
+            b.iter(|| runtime.block_on(router.dispatch(black_box(request), &context)).unwrap())
+        });
+
+        // Parsing and serializing included, as transports use it; single
+        // messages borrow their params, unlike the owned path below
+        let message = serde_json::to_string(&request).unwrap();
+        group.bench_with_input(BenchmarkId::new("message", methods), &message, |b, message| {
+            b.iter(|| runtime.block_on(router.dispatch_message(black_box(message), &context)).unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new("message_owned", methods), &message, |b, message| {
+            b.iter(|| runtime.block_on(async {
+                let request: JsonRpcRequest = serde_json::from_str(black_box(message)).unwrap();
+                serde_json::to_string(&router.dispatch(&request, &context).await.unwrap()).unwrap()
+            }))
+        });
+    }
+
+    let router = router(1);
+    let batch = Value::Array((0..100).map(|id| json!({
+        "jsonrpc": "2.0", "method": "service0.method", "params": [id], "id": id
+    })).collect()).to_string();
+    group.bench_function("batch/100", |b| {
+        b.iter(|| runtime.block_on(router.dispatch_message(black_box(&batch), &context)).unwrap())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, routing, dispatching);
+criterion_main!(benches);
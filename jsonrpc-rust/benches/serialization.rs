@@ -0,0 +1,72 @@
+//! Request and response parsing and serialization
+//!
+//! Run with `cargo bench --features benchmarks --bench serialization`.
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use jsonrpc_rust::core::serialization::SerializationFormat;
+use jsonrpc_rust::core::types::{JsonRpcRequest, JsonRpcRequestRef, JsonRpcResponse};
+use serde_json::{json, Value};
+
+/// Params of about `items` small objects
+fn params(items: usize) -> Value {
+    Value::Array((0..items).map(|i| json!({"id": i, "name": format!("item-{}", i), "tags": ["a", "b"]})).collect())
+}
+
+fn requests(c: &mut Criterion) {
+    let mut group = c.benchmark_group("request");
+    for items in [0, 10, 1000] {
+        let request = JsonRpcRequest::with_id("bench.method", Some(params(items)), json!(1));
+        let text = serde_json::to_string(&request).unwrap();
+        group.throughput(Throughput::Bytes(text.len() as u64));
+
+        group.bench_with_input(BenchmarkId::new("parse", items), &text, |b, text| {
+            b.iter(|| serde_json::from_str::<JsonRpcRequest>(black_box(text)).unwrap())
+        });
+        // Params stay raw until asked for
+        group.bench_with_input(BenchmarkId::new("parse_ref", items), &text, |b, text| {
+            b.iter(|| JsonRpcRequestRef::parse(black_box(text)).unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new("serialize", items), &request, |b, request| {
+            b.iter(|| serde_json::to_string(black_box(request)).unwrap())
+        });
+    }
+    group.finish();
+}
+
+fn responses(c: &mut Criterion) {
+    let mut group = c.benchmark_group("response");
+    for items in [0, 10, 1000] {
+        let response = JsonRpcResponse::success(json!(1), params(items));
+        let text = serde_json::to_string(&response).unwrap();
+        group.throughput(Throughput::Bytes(text.len() as u64));
+
+        group.bench_with_input(BenchmarkId::new("parse", items), &text, |b, text| {
+            b.iter(|| serde_json::from_str::<JsonRpcResponse>(black_box(text)).unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new("serialize", items), &response, |b, response| {
+            b.iter(|| serde_json::to_string(black_box(response)).unwrap())
+        });
+    }
+    group.finish();
+}
+
+fn formats(c: &mut Criterion) {
+    let mut group = c.benchmark_group("format");
+    let request = JsonRpcRequest::with_id("bench.method", Some(params(10)), json!(1));
+    let formats = [SerializationFormat::Json, SerializationFormat::MessagePack, SerializationFormat::Cbor];
+    for format in formats.into_iter().filter(|format| format.is_supported()) {
+        let encoded = format.encode(&request).unwrap();
+        group.throughput(Throughput::Bytes(encoded.len() as u64));
+
+        group.bench_with_input(BenchmarkId::new("encode", format.name()), &request, |b, request| {
+            b.iter(|| format.encode(black_box(request)).unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new("decode", format.name()), &encoded, |b, encoded| {
+            b.iter(|| format.decode::<JsonRpcRequest>(black_box(encoded)).unwrap())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, requests, responses, formats);
+criterion_main!(benches);
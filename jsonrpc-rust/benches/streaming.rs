@@ -0,0 +1,61 @@
+//! Streaming throughput of [`JsonRpcStream`]
+//!
+//! Run with `cargo bench --features benchmarks --bench streaming`.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use futures::StreamExt;
+use jsonrpc_rust::core::future::{AdaptiveBatching, BackpressureSignal, JsonRpcStream, StreamControl};
+use jsonrpc_rust::core::types::JsonRpcResponse;
+use serde_json::json;
+
+const ITEMS: usize = 10_000;
+
+fn runtime() -> tokio::runtime::Runtime {
+    tokio::runtime::Builder::new_multi_thread().worker_threads(2).enable_all().build().unwrap()
+}
+
+/// A stream fed with `ITEMS` items by a producer task
+fn produced(buffer: usize) -> JsonRpcStream {
+    let (producer, stream) = JsonRpcStream::channel(
+        StreamControl::with_buffer_size(buffer).with_slow_down_delay(std::time::Duration::ZERO),
+    );
+    tokio::spawn(async move {
+        for i in 0..ITEMS {
+            if producer.send(Ok(JsonRpcResponse::success(json!(i), json!(i)))).await.is_err() {
+                break;
+            }
+        }
+    });
+    stream
+}
+
+fn throughput(c: &mut Criterion) {
+    let runtime = runtime();
+    let mut group = c.benchmark_group("stream");
+    group.throughput(Throughput::Elements(ITEMS as u64));
+
+    group.bench_function("iter", |b| {
+        b.iter(|| runtime.block_on(async {
+            let items = (0..ITEMS).map(|i| Ok(JsonRpcResponse::success(json!(i), json!(i))));
+            JsonRpcStream::from_iter(items.collect::<Vec<_>>()).count().await
+        }))
+    });
+
+    for buffer in [64, 1024] {
+        group.bench_with_input(BenchmarkId::new("channel", buffer), &buffer, |b, &buffer| {
+            b.iter(|| runtime.block_on(async { produced(buffer).count().await }))
+        });
+        group.bench_with_input(BenchmarkId::new("adaptive_batches", buffer), &buffer, |b, &buffer| {
+            b.iter(|| runtime.block_on(async {
+                let batching = AdaptiveBatching::new(64).under(BackpressureSignal::None);
+                produced(buffer).adaptive_batches(batching)
+                    .fold(0, |items, batch| async move { items + batch.unwrap().len() })
+                    .await
+            }))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, throughput);
+criterion_main!(benches);
@@ -0,0 +1,65 @@
+//! Round-trip latency of calls over TCP
+//!
+//! Run with `cargo bench --features benchmarks --bench tcp`.
+
+use std::sync::Arc;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use jsonrpc_rust::convenience::{JsonRpcServer, ServerHandle};
+use jsonrpc_rust::core::serialization::SerializationFormat;
+use jsonrpc_rust::protocol::JsonRpcClient;
+use jsonrpc_rust::transport::NegotiatedTransport;
+use serde_json::{json, Value};
+
+fn runtime() -> tokio::runtime::Runtime {
+    tokio::runtime::Builder::new_multi_thread().worker_threads(2).enable_all().build().unwrap()
+}
+
+async fn serve() -> ServerHandle {
+    JsonRpcServer::builder()
+        .bind("tcp://127.0.0.1:0")
+        .method("echo", |params, _context| async move { Ok(params) })
+        .serve()
+        .await
+        .unwrap()
+}
+
+async fn connect(handle: &ServerHandle) -> JsonRpcClient {
+    let stream = tokio::net::TcpStream::connect(handle.local_addrs()[0]).await.unwrap();
+    let transport = NegotiatedTransport::connect(stream, &[SerializationFormat::Json]).await.unwrap();
+    JsonRpcClient::new(transport)
+}
+
+fn round_trip(c: &mut Criterion) {
+    let runtime = runtime();
+    let handle = runtime.block_on(serve());
+    let client = Arc::new(runtime.block_on(connect(&handle)));
+
+    let mut group = c.benchmark_group("tcp");
+    for items in [0, 100] {
+        let params = json!({"items": vec![json!({"name": "item", "value": 1}); items]});
+        group.bench_with_input(BenchmarkId::new("round_trip", items), &params, |b, params| {
+            b.iter(|| runtime.block_on(client.call::<_, Value>("echo", params)).unwrap())
+        });
+    }
+
+    // Calls in flight at once share the connection
+    group.throughput(Throughput::Elements(64));
+    group.bench_function("concurrent/64", |b| {
+        b.iter(|| runtime.block_on(async {
+            let calls = (0..64).map(|i| {
+                let client = Arc::clone(&client);
+                tokio::spawn(async move { client.call::<_, Value>("echo", json!([i])).await })
+            });
+            for call in futures::future::join_all(calls).await {
+                call.unwrap().unwrap();
+            }
+        }))
+    });
+    group.finish();
+
+    runtime.block_on(handle.shutdown()).unwrap();
+}
+
+criterion_group!(benches, round_trip);
+criterion_main!(benches);
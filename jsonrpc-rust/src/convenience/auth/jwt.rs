@@ -0,0 +1,348 @@
+//! JSON Web Token verification
+//!
+//! [`JwtVerifier`] checks the signature and the registered claims of a
+//! token with a fixed key or with the keys an identity provider publishes
+//! as a JWKS document. The identity it yields takes its user id from
+//! `sub`, its expiry from `exp`, its roles from a `roles` array and its
+//! permissions from a `permissions` array and the space-separated `scope`;
+//! every claim is kept in its metadata.
+
+use std::sync::Arc;
+use std::time::{Duration, Instant, UNIX_EPOCH};
+
+use async_trait::async_trait;
+use http_body_util::{BodyExt, Empty};
+use hyper::body::Bytes;
+use hyper_util::client::legacy::Client;
+use hyper_util::rt::TokioExecutor;
+use jsonwebtoken::jwk::JwkSet;
+use jsonwebtoken::{Algorithm, DecodingKey, Validation};
+use parking_lot::RwLock;
+use serde_json::{Map, Value};
+
+use super::TokenVerifier;
+use crate::core::error::{Error, Result};
+use crate::core::types::AuthContext;
+
+/// Verifies signed JSON Web Tokens
+pub struct JwtVerifier {
+    keys: Keys,
+    algorithms: Vec<Algorithm>,
+    issuer: Option<String>,
+    audience: Vec<String>,
+    leeway: Duration,
+}
+
+enum Keys {
+    Fixed(DecodingKey),
+    Jwks(Arc<Jwks>),
+}
+
+impl JwtVerifier {
+    /// Verify HS256 tokens signed with a shared secret
+    pub fn hmac(secret: &[u8]) -> Self {
+        Self::from_key(DecodingKey::from_secret(secret), Algorithm::HS256)
+    }
+
+    /// Verify tokens signed with `algorithm` against `key`
+    pub fn from_key(key: DecodingKey, algorithm: Algorithm) -> Self {
+        Self::new(Keys::Fixed(key), vec![algorithm])
+    }
+
+    /// Verify tokens against a fixed set of JSON Web Keys
+    pub fn jwk_set(keys: JwkSet) -> Self {
+        Self::new(Keys::Jwks(Arc::new(Jwks::fixed(keys))), default_asymmetric())
+    }
+
+    /// Verify tokens against the keys published at `url`, fetched on first
+    /// use and refreshed every `refresh_interval` or when a token names an
+    /// unknown key
+    pub fn jwks_url(url: impl Into<String>, refresh_interval: Duration) -> Self {
+        Self::new(Keys::Jwks(Arc::new(Jwks::remote(url.into(), refresh_interval))), default_asymmetric())
+    }
+
+    fn new(keys: Keys, algorithms: Vec<Algorithm>) -> Self {
+        Self {
+            keys,
+            algorithms,
+            issuer: None,
+            audience: Vec::new(),
+            leeway: Duration::from_secs(60),
+        }
+    }
+
+    /// Accept only tokens signed with one of `algorithms`
+    pub fn with_algorithms(mut self, algorithms: Vec<Algorithm>) -> Self {
+        self.algorithms = algorithms;
+        self
+    }
+
+    /// Accept only tokens issued by `issuer`
+    pub fn with_issuer(mut self, issuer: impl Into<String>) -> Self {
+        self.issuer = Some(issuer.into());
+        self
+    }
+
+    /// Accept only tokens meant for `audience`, or one of the audiences
+    /// given in other calls
+    pub fn with_audience(mut self, audience: impl Into<String>) -> Self {
+        self.audience.push(audience.into());
+        self
+    }
+
+    /// Set the clock skew tolerated on `exp` and `nbf`
+    pub fn with_leeway(mut self, leeway: Duration) -> Self {
+        self.leeway = leeway;
+        self
+    }
+
+    fn validation(&self, algorithm: Algorithm) -> Validation {
+        let mut validation = Validation::new(algorithm);
+        validation.leeway = self.leeway.as_secs();
+        validation.validate_nbf = true;
+        if let Some(issuer) = &self.issuer {
+            validation.set_issuer(&[issuer]);
+        }
+        match self.audience.is_empty() {
+            true => validation.validate_aud = false,
+            false => validation.set_audience(&self.audience),
+        }
+        validation
+    }
+}
+
+#[async_trait]
+impl TokenVerifier for JwtVerifier {
+    async fn verify(&self, token: &str) -> Result<AuthContext> {
+        let header = jsonwebtoken::decode_header(token)
+            .map_err(|e| Error::authentication(format!("Malformed token: {}", e)))?;
+        if !self.algorithms.contains(&header.alg) {
+            return Err(Error::authentication(format!("Token algorithm {:?} is not accepted", header.alg)));
+        }
+
+        let key = match &self.keys {
+            Keys::Fixed(key) => key.clone(),
+            Keys::Jwks(jwks) => jwks.key(header.kid.as_deref()).await?,
+        };
+        let claims = jsonwebtoken::decode::<Map<String, Value>>(token, &key, &self.validation(header.alg))
+            .map_err(|e| Error::authentication(format!("Invalid token: {}", e)))?
+            .claims;
+        identity(claims)
+    }
+}
+
+impl std::fmt::Debug for JwtVerifier {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let keys = match &self.keys {
+            Keys::Fixed(_) => "fixed".to_string(),
+            Keys::Jwks(jwks) => format!("{:?}", jwks.url),
+        };
+        f.debug_struct("JwtVerifier")
+            .field("keys", &keys)
+            .field("algorithms", &self.algorithms)
+            .field("issuer", &self.issuer)
+            .field("audience", &self.audience)
+            .field("leeway", &self.leeway)
+            .finish()
+    }
+}
+
+fn default_asymmetric() -> Vec<Algorithm> {
+    vec![Algorithm::RS256, Algorithm::RS384, Algorithm::RS512, Algorithm::ES256, Algorithm::ES384, Algorithm::EdDSA]
+}
+
+/// The identity a token's claims describe
+fn identity(claims: Map<String, Value>) -> Result<AuthContext> {
+    let user_id = claims.get("sub")
+        .and_then(Value::as_str)
+        .ok_or_else(|| Error::authentication("Token has no subject"))?;
+    let strings = |claim: &str| -> Vec<String> {
+        claims.get(claim)
+            .and_then(Value::as_array)
+            .map(|values| values.iter().filter_map(Value::as_str).map(str::to_string).collect())
+            .unwrap_or_default()
+    };
+
+    let mut auth = AuthContext::new(user_id, "jwt")
+        .with_roles(strings("roles"))
+        .with_permissions(strings("permissions"));
+    if let Some(scope) = claims.get("scope").and_then(Value::as_str) {
+        auth = auth.with_permissions(scope.split_whitespace().map(str::to_string).collect());
+    }
+    if let Some(exp) = claims.get("exp").and_then(Value::as_u64) {
+        auth = auth.with_expiration(UNIX_EPOCH + Duration::from_secs(exp));
+    }
+    auth.metadata = claims.into_iter().collect();
+    Ok(auth)
+}
+
+/// Shortest wait between two fetches triggered by unknown keys
+const MIN_REFETCH: Duration = Duration::from_secs(30);
+
+/// How long a JWKS fetch may take
+const FETCH_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A key set, possibly fetched from a URL
+struct Jwks {
+    url: Option<String>,
+    refresh_interval: Duration,
+    keys: RwLock<JwkSet>,
+    fetched_at: RwLock<Option<Instant>>,
+    fetching: tokio::sync::Mutex<()>,
+}
+
+impl Jwks {
+    fn fixed(keys: JwkSet) -> Self {
+        Self {
+            url: None,
+            refresh_interval: Duration::MAX,
+            keys: RwLock::new(keys),
+            fetched_at: RwLock::new(Some(Instant::now())),
+            fetching: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    fn remote(url: String, refresh_interval: Duration) -> Self {
+        Self {
+            url: Some(url),
+            refresh_interval,
+            keys: RwLock::new(JwkSet { keys: Vec::new() }),
+            fetched_at: RwLock::new(None),
+            fetching: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    /// The key named `kid`, or the only key when the token names none
+    async fn key(&self, kid: Option<&str>) -> Result<DecodingKey> {
+        let age = self.fetched_at.read().map(|at| at.elapsed());
+        match age {
+            Some(age) if age < self.refresh_interval => {}
+            _ => self.refresh(None).await?,
+        }
+        if let Some(key) = self.find(kid)? {
+            return Ok(key);
+        }
+
+        // The provider may have rotated its keys since the last fetch
+        self.refresh(Some(MIN_REFETCH)).await?;
+        self.find(kid)?
+            .ok_or_else(|| Error::authentication("No key matches the token"))
+    }
+
+    fn find(&self, kid: Option<&str>) -> Result<Option<DecodingKey>> {
+        let keys = self.keys.read();
+        let jwk = match kid {
+            Some(kid) => keys.find(kid),
+            None if keys.keys.len() == 1 => keys.keys.first(),
+            None => return Err(Error::authentication("Token names no key")),
+        };
+        jwk.map(|jwk| DecodingKey::from_jwk(jwk)
+            .map_err(|e| Error::authentication(format!("Unusable key: {}", e))))
+            .transpose()
+    }
+
+    /// Fetch the keys again, unless they were fetched less than
+    /// `min_age` ago or while waiting for another fetch
+    async fn refresh(&self, min_age: Option<Duration>) -> Result<()> {
+        let Some(url) = &self.url else {
+            return Ok(());
+        };
+        let requested = Instant::now();
+        let _fetching = self.fetching.lock().await;
+        if let Some(fetched_at) = *self.fetched_at.read() {
+            let fresh = min_age.is_some_and(|min_age| fetched_at.elapsed() < min_age);
+            if fresh || fetched_at >= requested {
+                return Ok(());
+            }
+        }
+
+        let keys = tokio::time::timeout(FETCH_TIMEOUT, fetch(url))
+            .await
+            .map_err(|_| Error::timeout("JWKS fetch", FETCH_TIMEOUT))??;
+        *self.keys.write() = keys;
+        *self.fetched_at.write() = Some(Instant::now());
+        Ok(())
+    }
+}
+
+async fn fetch(url: &str) -> Result<JwkSet> {
+    let connector = hyper_rustls::HttpsConnectorBuilder::new()
+        .with_webpki_roots()
+        .https_or_http()
+        .enable_http1()
+        .build();
+    let client: Client<_, Empty<Bytes>> = Client::builder(TokioExecutor::new()).build(connector);
+    let uri: hyper::Uri = url.parse()
+        .map_err(|e| Error::configuration(format!("Invalid JWKS URL '{}': {}", url, e)))?;
+
+    let response = client.get(uri).await
+        .map_err(|e| Error::connection(format!("Failed to fetch JWKS from {}: {}", url, e)))?;
+    if !response.status().is_success() {
+        return Err(Error::connection(format!("JWKS fetch from {} answered {}", url, response.status())));
+    }
+    let body = response.into_body().collect().await
+        .map_err(|e| Error::connection(format!("Failed to read JWKS from {}: {}", url, e)))?
+        .to_bytes();
+    serde_json::from_slice(&body)
+        .map_err(|e| Error::serialization(format!("Invalid JWKS from {}: {}", url, e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jsonwebtoken::{encode, EncodingKey, Header};
+    use serde_json::json;
+    use std::time::SystemTime;
+
+    /// Seconds since the epoch, for `exp` claims
+    fn now() -> u64 {
+        SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs()
+    }
+
+    #[tokio::test]
+    async fn test_hmac_tokens() {
+        let secret = b"shared-secret";
+        let claims = json!({
+            "sub": "alice",
+            "exp": now() + 600,
+            "iss": "issuer",
+            "scope": "math:read math:write",
+            "roles": ["admin"],
+        });
+        let token = encode(&Header::default(), &claims, &EncodingKey::from_secret(secret)).unwrap();
+
+        let verifier = JwtVerifier::hmac(secret).with_issuer("issuer");
+        let auth = verifier.verify(&token).await.unwrap();
+        assert_eq!(auth.user_id, "alice");
+        assert!(auth.has_permission("math:write"));
+        assert!(auth.has_role("admin"));
+        assert_eq!(auth.metadata["iss"], json!("issuer"));
+
+        assert!(JwtVerifier::hmac(b"other").verify(&token).await.is_err());
+        assert!(JwtVerifier::hmac(secret).with_issuer("elsewhere").verify(&token).await.is_err());
+
+        let expired = json!({"sub": "alice", "exp": now() - 600});
+        let token = encode(&Header::default(), &expired, &EncodingKey::from_secret(secret)).unwrap();
+        assert!(verifier.verify(&token).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_jwk_set_picks_key_by_id() {
+        let keys: JwkSet = serde_json::from_value(json!({"keys": [
+            {"kty": "oct", "kid": "one", "alg": "HS256", "k": "b25lLXNlY3JldA"},
+            {"kty": "oct", "kid": "two", "alg": "HS256", "k": "dHdvLXNlY3JldA"},
+        ]}))
+        .unwrap();
+        let verifier = JwtVerifier::jwk_set(keys).with_algorithms(vec![Algorithm::HS256]);
+
+        let mut header = Header::new(Algorithm::HS256);
+        header.kid = Some("two".to_string());
+        let claims = json!({"sub": "bob", "exp": now() + 600});
+        let token = encode(&header, &claims, &EncodingKey::from_secret(b"two-secret")).unwrap();
+        assert_eq!(verifier.verify(&token).await.unwrap().user_id, "bob");
+
+        header.kid = Some("one".to_string());
+        let token = encode(&header, &claims, &EncodingKey::from_secret(b"two-secret")).unwrap();
+        assert!(verifier.verify(&token).await.is_err());
+    }
+}
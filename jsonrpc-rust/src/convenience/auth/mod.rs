@@ -0,0 +1,388 @@
+//! Authentication and per-method authorization
+//!
+//! An [`AuthLayer`] reads a token from each call, hands it to its
+//! [`TokenVerifier`]s and passes the resulting [`AuthContext`] to the
+//! handler in [`ServiceContext::auth_context`]. It then checks the
+//! permissions the method requires, declared on the layer or taken from
+//! [`MethodInfo::required_permissions`].
+//!
+//! ```rust
+//! use jsonrpc_rust::convenience::prelude::*;
+//! use jsonrpc_rust::core::types::AuthContext;
+//!
+//! let keys = ApiKeyVerifier::new()
+//!     .with_key("secret-key", AuthContext::new("alice", "api_key").with_permission("math:write"));
+//! let auth = AuthLayer::new()
+//!     .with_verifier(keys)
+//!     .with_anonymous_method("ping")
+//!     .with_required_permissions("math.add", ["math:write"]);
+//!
+//! let server = JsonRpcServer::builder()
+//!     .method("ping", |_params, _context| async { Ok("pong") })
+//!     .method("math.add", |_params, context| async move {
+//!         Ok(context.auth_context.map(|auth| auth.user_id))
+//!     })
+//!     .auth(auth)
+//!     .build();
+//! ```
+//!
+//! Tokens are looked up in the `authorization` metadata of the call, which
+//! the HTTP transport fills from the `Authorization` header, with any
+//! `Bearer ` prefix removed. Stream transports have no headers; callers
+//! there pass the token in a params field named with
+//! [`TokenSource::Param`], which the layer removes before the handler
+//! sees the params.
+//!
+//! Calls without a token are refused unless the method allows anonymous
+//! calls; calls with a token no verifier accepts are always refused.
+//! Failed authentication is answered with code `-32001` and missing
+//! permissions with `-32003`.
+//!
+//! With the `trn-integration` feature, a [`TrnAuthorizationLayer`] further
+//! authorizes calls by the caller's TRN.
+
+#[cfg(feature = "jwt")]
+pub mod jwt;
+#[cfg(feature = "trn-integration")]
+pub mod trn;
+
+#[cfg(feature = "jwt")]
+pub use jwt::*;
+#[cfg(feature = "trn-integration")]
+pub use trn::*;
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde_json::Value;
+
+use super::layer::Layer;
+use crate::core::error::{Error, Result};
+use crate::core::traits::MethodHandler;
+use crate::core::types::{AuthContext, JsonRpcRequest, JsonRpcResponse, MethodInfo, ServiceContext};
+
+/// Call metadata key holding the credentials of a call
+pub const AUTHORIZATION_METADATA: &str = "authorization";
+
+/// Checks a token and tells who presented it
+#[async_trait]
+pub trait TokenVerifier: Send + Sync {
+    /// The identity behind `token`, or an authentication error
+    async fn verify(&self, token: &str) -> Result<AuthContext>;
+}
+
+/// Accepts a fixed set of API keys
+#[derive(Clone, Default)]
+pub struct ApiKeyVerifier {
+    keys: HashMap<String, AuthContext>,
+}
+
+impl ApiKeyVerifier {
+    /// Create a verifier accepting no key
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Accept `key` as the identity `auth`
+    pub fn with_key(mut self, key: impl Into<String>, auth: AuthContext) -> Self {
+        self.keys.insert(key.into(), auth);
+        self
+    }
+}
+
+#[async_trait]
+impl TokenVerifier for ApiKeyVerifier {
+    async fn verify(&self, token: &str) -> Result<AuthContext> {
+        self.keys.get(token)
+            .cloned()
+            .ok_or_else(|| Error::authentication("Unknown API key"))
+    }
+}
+
+impl std::fmt::Debug for ApiKeyVerifier {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Never print the keys themselves
+        f.debug_struct("ApiKeyVerifier")
+            .field("keys", &self.keys.len())
+            .finish()
+    }
+}
+
+/// Where the token of a call is read from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TokenSource {
+    /// A call metadata entry, such as [`AUTHORIZATION_METADATA`]
+    Metadata(String),
+    /// A field of by-name params, removed before the handler runs
+    Param(String),
+}
+
+impl Default for TokenSource {
+    fn default() -> Self {
+        Self::Metadata(AUTHORIZATION_METADATA.to_string())
+    }
+}
+
+/// Layer authenticating calls and enforcing method permissions; see the
+/// [module docs](self)
+#[derive(Clone, Default)]
+pub struct AuthLayer {
+    verifiers: Vec<Arc<dyn TokenVerifier>>,
+    sources: Vec<TokenSource>,
+    anonymous: HashSet<String>,
+    permissions: HashMap<String, Vec<String>>,
+}
+
+impl AuthLayer {
+    /// Create a layer refusing every call until given a verifier
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Try `verifier` on tokens, after the verifiers added before it
+    pub fn with_verifier(mut self, verifier: impl TokenVerifier + 'static) -> Self {
+        self.verifiers.push(Arc::new(verifier));
+        self
+    }
+
+    /// Read tokens from `source`; without any source they are read from
+    /// the `authorization` metadata
+    pub fn with_token_source(mut self, source: TokenSource) -> Self {
+        self.sources.push(source);
+        self
+    }
+
+    /// Let calls to `method` through without a token
+    pub fn with_anonymous_method(mut self, method: impl Into<String>) -> Self {
+        self.anonymous.insert(method.into());
+        self
+    }
+
+    /// Require every one of `permissions` for calls to `method`
+    pub fn with_required_permissions<I, P>(mut self, method: impl Into<String>, permissions: I) -> Self
+    where
+        I: IntoIterator<Item = P>,
+        P: Into<String>,
+    {
+        self.permissions.entry(method.into())
+            .or_default()
+            .extend(permissions.into_iter().map(Into::into));
+        self
+    }
+
+    /// Require the permissions the methods describe, and a token for those
+    /// marked `auth_required` even if they were allowed anonymous calls
+    pub fn with_method_info(mut self, methods: &[MethodInfo]) -> Self {
+        for info in methods {
+            if info.auth_required {
+                self.anonymous.remove(&info.name);
+            }
+            if !info.required_permissions.is_empty() {
+                self = self.with_required_permissions(info.name.clone(), info.required_permissions.clone());
+            }
+        }
+        self
+    }
+
+    /// The token of a call, and the request to hand on
+    fn token(&self, request: &JsonRpcRequest, context: &ServiceContext) -> (Option<String>, Option<JsonRpcRequest>) {
+        let default = [TokenSource::default()];
+        let sources = match self.sources.is_empty() {
+            true => &default[..],
+            false => &self.sources[..],
+        };
+
+        for source in sources {
+            match source {
+                TokenSource::Metadata(key) => {
+                    if let Some(Value::String(value)) = context.metadata.get(key) {
+                        return (Some(strip_scheme(value).to_string()), None);
+                    }
+                }
+                TokenSource::Param(name) => {
+                    let Some(Value::Object(params)) = &request.params else {
+                        continue;
+                    };
+                    if let Some(Value::String(value)) = params.get(name) {
+                        let mut stripped = request.clone();
+                        if let Some(Value::Object(params)) = &mut stripped.params {
+                            params.remove(name);
+                        }
+                        return (Some(strip_scheme(value).to_string()), Some(stripped));
+                    }
+                }
+            }
+        }
+        (None, None)
+    }
+
+    /// The identity behind a token, from the first verifier accepting it
+    async fn authenticate(&self, token: &str) -> Result<AuthContext> {
+        let mut refused = None;
+        for verifier in &self.verifiers {
+            match verifier.verify(token).await {
+                Ok(auth) if auth.is_expired() => refused = Some(Error::authentication("Credentials expired")),
+                Ok(auth) => return Ok(auth),
+                Err(e) => refused = Some(e),
+            }
+        }
+        Err(refused.unwrap_or_else(|| Error::authentication("No verifier configured")))
+    }
+
+    /// Refuse `auth` if it lacks a permission `method` requires
+    fn authorize(&self, method: &str, auth: &AuthContext) -> Result<()> {
+        let required = self.permissions.get(method).map(Vec::as_slice).unwrap_or_default();
+        match required.iter().find(|permission| !auth.has_permission(permission)) {
+            Some(missing) => Err(Error::authorization(format!(
+                "Method '{}' requires permission '{}'", method, missing
+            ))),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Layer for AuthLayer {
+    fn layer(&self, inner: Arc<dyn MethodHandler>) -> Arc<dyn MethodHandler> {
+        Arc::new(Authenticated {
+            inner,
+            auth: Arc::new(self.clone()),
+        })
+    }
+}
+
+impl std::fmt::Debug for AuthLayer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AuthLayer")
+            .field("verifiers", &self.verifiers.len())
+            .field("sources", &self.sources)
+            .field("anonymous", &self.anonymous)
+            .field("permissions", &self.permissions)
+            .finish()
+    }
+}
+
+/// `Bearer abc` and `abc` both give `abc`
+fn strip_scheme(value: &str) -> &str {
+    match value.split_once(' ') {
+        Some((scheme, token)) if scheme.eq_ignore_ascii_case("bearer") => token.trim(),
+        _ => value.trim(),
+    }
+}
+
+struct Authenticated {
+    inner: Arc<dyn MethodHandler>,
+    auth: Arc<AuthLayer>,
+}
+
+#[async_trait]
+impl MethodHandler for Authenticated {
+    async fn handle_method(&self, request: &JsonRpcRequest, context: &ServiceContext) -> Result<JsonRpcResponse> {
+        let (token, stripped) = self.auth.token(request, context);
+        let request = stripped.as_ref().unwrap_or(request);
+
+        let Some(token) = token else {
+            if self.auth.anonymous.contains(&request.method) {
+                return self.inner.handle_method(request, context).await;
+            }
+            return Err(Error::authentication("Missing credentials"));
+        };
+
+        let identity = self.auth.authenticate(&token).await?;
+        self.auth.authorize(&request.method, &identity)?;
+        let context = context.clone().with_auth_context(identity);
+        self.inner.handle_method(request, &context).await
+    }
+
+    fn supported_methods(&self) -> Vec<String> {
+        self.inner.supported_methods()
+    }
+
+    fn supports_method(&self, method: &str) -> bool {
+        self.inner.supports_method(method)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::convenience::JsonRpcServer;
+    use serde_json::json;
+
+    fn server() -> JsonRpcServer {
+        let keys = ApiKeyVerifier::new()
+            .with_key("alice-key", AuthContext::new("alice", "api_key").with_permission("math:write"))
+            .with_key("bob-key", AuthContext::new("bob", "api_key"));
+        let auth = AuthLayer::new()
+            .with_verifier(keys)
+            .with_token_source(TokenSource::default())
+            .with_token_source(TokenSource::Param("token".to_string()))
+            .with_anonymous_method("ping")
+            .with_required_permissions("math.add", ["math:write"]);
+
+        JsonRpcServer::builder()
+            .method("ping", |_params, _context| async { Ok("pong") })
+            .method("whoami", |params, context| async move {
+                Ok(json!([context.auth_context.map(|auth| auth.user_id), params]))
+            })
+            .method("math.add", |_params, _context| async { Ok(3) })
+            .auth(auth)
+            .build()
+            .unwrap()
+    }
+
+    async fn call(server: &JsonRpcServer, method: &str, params: Value, token: Option<&str>) -> JsonRpcResponse {
+        let mut context = ServiceContext::new("req-1");
+        if let Some(token) = token {
+            context = context.with_metadata(AUTHORIZATION_METADATA, json!(token));
+        }
+        let request = JsonRpcRequest::with_id(method, Some(params), json!(1));
+        server.router().dispatch(&request, &context).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_tokens_are_verified_and_passed_on() {
+        let server = server();
+
+        let response = call(&server, "whoami", json!({}), Some("Bearer alice-key")).await;
+        assert_eq!(response.result, Some(json!(["alice", {}])));
+
+        let response = call(&server, "whoami", json!({"token": "bob-key", "x": 1}), None).await;
+        assert_eq!(response.result, Some(json!(["bob", {"x": 1}])));
+
+        let response = call(&server, "ping", json!({}), None).await;
+        assert_eq!(response.result, Some(json!("pong")));
+
+        let response = call(&server, "whoami", json!({}), None).await;
+        assert_eq!(response.error.unwrap().code, -32001);
+
+        let response = call(&server, "ping", json!({}), Some("stolen-key")).await;
+        assert_eq!(response.error.unwrap().code, -32001);
+    }
+
+    #[tokio::test]
+    async fn test_required_permissions() {
+        let server = server();
+
+        let response = call(&server, "math.add", json!({}), Some("alice-key")).await;
+        assert_eq!(response.result, Some(json!(3)));
+
+        let response = call(&server, "math.add", json!({}), Some("bob-key")).await;
+        assert_eq!(response.error.unwrap().code, -32003);
+
+        let info = MethodInfo {
+            name: "whoami".to_string(),
+            description: String::new(),
+            params_schema: None,
+            returns_schema: None,
+            example_params: None,
+            example_returns: None,
+            auth_required: true,
+            required_permissions: vec!["admin".to_string()],
+            metadata: HashMap::new(),
+        };
+        let auth = AuthLayer::new().with_method_info(&[info]);
+        assert!(auth.authorize("whoami", &AuthContext::new("bob", "api_key")).is_err());
+        assert!(auth.authorize("ping", &AuthContext::new("bob", "api_key")).is_ok());
+    }
+}
@@ -0,0 +1,251 @@
+//! Method authorization by TRN
+//!
+//! A [`TrnAuthorizationLayer`] lets a call through only if the caller's
+//! TRN matches one of the patterns its method requires, matched with
+//! [`trn_rust::TrnMatcher`]. Rules name a method (`tool.invoke`) or a
+//! namespace (`tool.*`); the most specific one applies.
+//!
+//! Patterns may name params of the call in braces, which are filled in
+//! before matching: with `trn:*:{scope}:tool:*:*` for `tool.invoke`, a call
+//! with `{"scope": "alice"}` needs a caller in the `alice` scope. Calls
+//! without such a param, or with one that is not a plain TRN component,
+//! are refused.
+//!
+//! The caller's TRN is the [`ServiceContext::trn_context`] of the call or,
+//! for calls authenticated by an [`AuthLayer`](super::AuthLayer) added
+//! before this layer, the `trn` entry of their identity's metadata, such as
+//! a JWT claim. Every decision is logged at info level with the
+//! `jsonrpc_rust::trn_authz` target.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde_json::Value;
+use trn_rust::TrnMatcher;
+
+use crate::convenience::layer::Layer;
+use crate::core::error::{Error, Result};
+use crate::core::traits::MethodHandler;
+use crate::core::types::{JsonRpcRequest, JsonRpcResponse, ServiceContext, TrnContext};
+
+/// Identity metadata key holding the caller's TRN
+pub const TRN_METADATA: &str = "trn";
+
+/// Layer authorizing calls by the caller's TRN; see the
+/// [module docs](self)
+#[derive(Debug, Clone, Default)]
+pub struct TrnAuthorizationLayer {
+    /// Patterns allowing calls, by method or namespace pattern
+    rules: HashMap<String, Vec<String>>,
+    deny_unlisted: bool,
+}
+
+impl TrnAuthorizationLayer {
+    /// Create a layer letting every call through until given rules
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Let calls to `method`, or to a namespace such as `tool.*`, through
+    /// for callers matching `pattern`, or any other pattern added for it
+    pub fn with_rule(mut self, method: impl Into<String>, pattern: impl Into<String>) -> Result<Self> {
+        let pattern = pattern.into();
+        // Placeholders stand for a single component
+        TrnMatcher::new(&fill(&pattern, |_| Some("x".to_string()))?)?;
+        self.rules.entry(method.into()).or_default().push(pattern);
+        Ok(self)
+    }
+
+    /// Refuse calls to methods without a rule instead of letting them through
+    pub fn deny_unlisted(mut self) -> Self {
+        self.deny_unlisted = true;
+        self
+    }
+
+    /// Patterns `method` requires, from its own rule or else from the rule
+    /// of its longest namespace
+    fn patterns(&self, method: &str) -> Option<&[String]> {
+        if let Some(patterns) = self.rules.get(method) {
+            return Some(patterns);
+        }
+        self.rules.iter()
+            .filter(|(rule, _)| {
+                rule.strip_suffix(".*")
+                    .is_some_and(|namespace| method.strip_prefix(namespace).is_some_and(|rest| rest.starts_with('.')))
+            })
+            .max_by_key(|(rule, _)| rule.len())
+            .map(|(_, patterns)| patterns.as_slice())
+    }
+
+    /// Refuse the call unless the caller's TRN matches a pattern its method requires
+    fn authorize(&self, request: &JsonRpcRequest, context: &ServiceContext) -> Result<()> {
+        let method = request.method.as_str();
+        let Some(patterns) = self.patterns(method) else {
+            let allowed = !self.deny_unlisted;
+            tracing::info!(target: "jsonrpc_rust::trn_authz", method, allowed, "No TRN rule for method");
+            return match allowed {
+                true => Ok(()),
+                false => Err(Error::authorization(format!("Method '{}' has no TRN rule", method))),
+            };
+        };
+
+        let Some(caller) = caller(context) else {
+            tracing::info!(target: "jsonrpc_rust::trn_authz", method, allowed = false, "Caller has no TRN");
+            return Err(Error::authorization(format!("Method '{}' requires a caller TRN", method)));
+        };
+
+        let params = match &request.params {
+            Some(Value::Object(params)) => Some(params),
+            _ => None,
+        };
+        for pattern in patterns {
+            let filled = fill(pattern, |name| match params?.get(name)? {
+                Value::String(value) => Some(value.clone()),
+                Value::Number(value) => Some(value.to_string()),
+                _ => None,
+            });
+            let Ok(filled) = filled else { continue };
+            if TrnMatcher::new(&filled).is_ok_and(|matcher| matcher.matches(&caller)) {
+                tracing::info!(target: "jsonrpc_rust::trn_authz", method, %caller, pattern = %filled, allowed = true, "Caller TRN matched");
+                return Ok(());
+            }
+        }
+
+        tracing::info!(target: "jsonrpc_rust::trn_authz", method, %caller, ?patterns, allowed = false, "Caller TRN matched no pattern");
+        Err(Error::authorization(format!(
+            "Caller '{}' matches no TRN pattern method '{}' requires", caller, method
+        )))
+    }
+}
+
+impl Layer for TrnAuthorizationLayer {
+    fn layer(&self, inner: Arc<dyn MethodHandler>) -> Arc<dyn MethodHandler> {
+        Arc::new(TrnAuthorized {
+            inner,
+            authorization: Arc::new(self.clone()),
+        })
+    }
+}
+
+/// The caller's TRN, from the context or else from its identity
+fn caller(context: &ServiceContext) -> Option<String> {
+    if let Some(trn) = &context.trn_context {
+        return Some(trn.to_trn_string());
+    }
+    let trn = context.auth_context.as_ref()?.metadata.get(TRN_METADATA)?.as_str()?;
+    TrnContext::from_trn_string(trn).ok().map(|trn| trn.to_trn_string())
+}
+
+/// Replace every `{name}` in `pattern` with `value(name)`; fails if a value
+/// is missing or could span or widen components
+fn fill(pattern: &str, value: impl Fn(&str) -> Option<String>) -> Result<String> {
+    let mut filled = String::with_capacity(pattern.len());
+    let mut rest = pattern;
+    while let Some(start) = rest.find('{') {
+        let end = rest[start..].find('}')
+            .ok_or_else(|| Error::configuration(format!("Unclosed placeholder in TRN pattern '{}'", pattern)))?;
+        let name = &rest[start + 1..start + end];
+        let component = value(name)
+            .filter(|component| !component.is_empty() && !component.contains([':', '*', '{', '}']))
+            .ok_or_else(|| Error::authorization(format!("No usable '{}' for TRN pattern '{}'", name, pattern)))?;
+        filled.push_str(&rest[..start]);
+        filled.push_str(&component);
+        rest = &rest[start + end + 1..];
+    }
+    filled.push_str(rest);
+    Ok(filled)
+}
+
+struct TrnAuthorized {
+    inner: Arc<dyn MethodHandler>,
+    authorization: Arc<TrnAuthorizationLayer>,
+}
+
+#[async_trait]
+impl MethodHandler for TrnAuthorized {
+    async fn handle_method(&self, request: &JsonRpcRequest, context: &ServiceContext) -> Result<JsonRpcResponse> {
+        self.authorization.authorize(request, context)?;
+        self.inner.handle_method(request, context).await
+    }
+
+    fn supported_methods(&self) -> Vec<String> {
+        self.inner.supported_methods()
+    }
+
+    fn supports_method(&self, method: &str) -> bool {
+        self.inner.supports_method(method)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::convenience::auth::{ApiKeyVerifier, AuthLayer, AUTHORIZATION_METADATA};
+    use crate::convenience::JsonRpcServer;
+    use crate::core::types::AuthContext;
+    use serde_json::json;
+
+    fn server(authorization: TrnAuthorizationLayer) -> JsonRpcServer {
+        let keys = ApiKeyVerifier::new().with_key("bob-key", {
+            let mut bob = AuthContext::new("bob", "api_key");
+            bob.metadata.insert(TRN_METADATA.into(), json!("trn:user:bob:tool:cli:v1"));
+            bob
+        });
+        JsonRpcServer::builder()
+            .method("tool.invoke", |_params, _context| async { Ok("invoked") })
+            .method("tool.list", |_params, _context| async { Ok("listed") })
+            .method("ping", |_params, _context| async { Ok("pong") })
+            .auth(["tool.invoke", "tool.list", "ping"].into_iter()
+                .fold(AuthLayer::new().with_verifier(keys), AuthLayer::with_anonymous_method))
+            .layer(authorization)
+            .build()
+            .unwrap()
+    }
+
+    async fn call(server: &JsonRpcServer, method: &str, params: Value, context: ServiceContext) -> Option<i32> {
+        let request = JsonRpcRequest::with_id(method, Some(params), json!(1));
+        let response = server.router().dispatch(&request, &context).await.unwrap();
+        response.error.map(|error| error.code)
+    }
+
+    fn as_caller(trn: &str) -> ServiceContext {
+        ServiceContext::new("req-1").with_trn_context(TrnContext::from_trn_string(trn).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_calls_need_a_matching_caller() {
+        let authorization = TrnAuthorizationLayer::new()
+            .with_rule("tool.invoke", "trn:*:{scope}:tool:*:*").unwrap()
+            .with_rule("tool.*", "trn:org:*:tool:*:*").unwrap();
+        let server = server(authorization);
+        let alice = || as_caller("trn:user:alice:tool:weather:v1");
+
+        assert_eq!(call(&server, "tool.invoke", json!({"scope": "alice"}), alice()).await, None);
+        assert_eq!(call(&server, "tool.invoke", json!({"scope": "bob"}), alice()).await, Some(-32003));
+        // Missing or wildcard params refuse the call rather than widen the pattern
+        assert_eq!(call(&server, "tool.invoke", json!({}), alice()).await, Some(-32003));
+        assert_eq!(call(&server, "tool.invoke", json!({"scope": "*"}), alice()).await, Some(-32003));
+
+        // The namespace rule applies to the other methods
+        assert_eq!(call(&server, "tool.list", json!({}), alice()).await, Some(-32003));
+        assert_eq!(call(&server, "tool.list", json!({}), as_caller("trn:org:acme:tool:sync:v2")).await, None);
+        assert_eq!(call(&server, "ping", json!({}), ServiceContext::new("req-2")).await, None);
+        assert_eq!(call(&server, "tool.invoke", json!({"scope": "alice"}), ServiceContext::new("req-3")).await, Some(-32003));
+    }
+
+    #[tokio::test]
+    async fn test_identity_trn_and_unlisted_methods() {
+        let authorization = TrnAuthorizationLayer::new()
+            .with_rule("tool.invoke", "trn:user:{scope}:tool:*:*").unwrap()
+            .deny_unlisted();
+        let server = server(authorization);
+        let bob = ServiceContext::new("req-1").with_metadata(AUTHORIZATION_METADATA, json!("bob-key"));
+
+        assert_eq!(call(&server, "tool.invoke", json!({"scope": "bob"}), bob.clone()).await, None);
+        assert_eq!(call(&server, "ping", json!({}), bob).await, Some(-32003));
+
+        assert!(TrnAuthorizationLayer::new().with_rule("tool.invoke", "not-a-pattern").is_err());
+        assert!(TrnAuthorizationLayer::new().with_rule("tool.invoke", "trn:*:{scope:tool:*:*").is_err());
+    }
+}
@@ -0,0 +1,381 @@
+//! Per-method concurrency limits
+//!
+//! A [`ConcurrencyLayer`] bounds the calls of each method answered at
+//! once, so a slow method cannot take every worker of a server. Calls
+//! finding their method at its limit are refused right away or wait in a
+//! bounded queue, as the [`Overflow`] of the method says; refused calls are
+//! answered with code `-32005`, like rate-limited ones.
+//!
+//! ```rust
+//! use jsonrpc_rust::convenience::prelude::*;
+//! use std::time::Duration;
+//!
+//! let server = JsonRpcServer::builder()
+//!     .method("report", |_params, _context| async { Ok("done") })
+//!     .concurrency(ConcurrencyConfig::default()
+//!         .with_method("report", ConcurrencyLimit::queued(2, 16).with_queue_timeout(Duration::from_secs(5))))
+//!     .build();
+//! ```
+//!
+//! With the `prometheus` feature, [`Metrics`](super::Metrics) given to
+//! the server count the calls waiting and refused by method.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+use super::layer::Layer;
+#[cfg(feature = "prometheus")]
+use super::metrics::Metrics;
+use crate::core::error::{Error, Result};
+use crate::core::traits::MethodHandler;
+use crate::core::types::{JsonRpcRequest, JsonRpcResponse, ServiceContext};
+
+/// What happens to calls arriving while their method is at its limit
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Overflow {
+    /// Refuse them
+    Reject,
+    /// Queue up to `max_queued` of them and refuse the others; queued
+    /// calls are refused after `timeout`, if any
+    Wait {
+        max_queued: usize,
+        timeout: Option<Duration>,
+    },
+}
+
+/// Concurrency limit of a method
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConcurrencyLimit {
+    /// Calls answered at once
+    pub max_in_flight: usize,
+    /// Handling of the calls over the limit
+    pub overflow: Overflow,
+}
+
+impl ConcurrencyLimit {
+    /// Answer `max_in_flight` calls at once and refuse the others
+    pub fn reject(max_in_flight: usize) -> Self {
+        Self { max_in_flight, overflow: Overflow::Reject }
+    }
+
+    /// Answer `max_in_flight` calls at once and queue up to `max_queued`
+    pub fn queued(max_in_flight: usize, max_queued: usize) -> Self {
+        Self {
+            max_in_flight,
+            overflow: Overflow::Wait { max_queued, timeout: None },
+        }
+    }
+
+    /// Refuse queued calls still waiting after `timeout`
+    pub fn with_queue_timeout(mut self, timeout: Duration) -> Self {
+        if let Overflow::Wait { timeout: ref mut current, .. } = self.overflow {
+            *current = Some(timeout);
+        }
+        self
+    }
+}
+
+/// Concurrency limits of the methods of a server
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ConcurrencyConfig {
+    /// Limit of the methods without one of their own; unlimited if `None`
+    pub default: Option<ConcurrencyLimit>,
+    /// Limits of single methods
+    pub methods: HashMap<String, ConcurrencyLimit>,
+}
+
+impl ConcurrencyConfig {
+    /// Limit every method without a limit of its own
+    pub fn with_default(mut self, limit: ConcurrencyLimit) -> Self {
+        self.default = Some(limit);
+        self
+    }
+
+    /// Limit one method
+    pub fn with_method(mut self, method: impl Into<String>, limit: ConcurrencyLimit) -> Self {
+        self.methods.insert(method.into(), limit);
+        self
+    }
+
+    /// The limit of a method
+    pub fn limit_for(&self, method: &str) -> Option<ConcurrencyLimit> {
+        self.methods.get(method).copied().or(self.default)
+    }
+
+    /// Check the configuration
+    pub fn validate(&self) -> Result<()> {
+        let limits = self.default.iter().map(|limit| ("default", limit))
+            .chain(self.methods.iter().map(|(method, limit)| (method.as_str(), limit)));
+        for (method, limit) in limits {
+            if limit.max_in_flight == 0 {
+                return Err(Error::configuration(format!(
+                    "Concurrency limit of '{}' must allow at least one call", method
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Slots and queue of one method
+struct Gate {
+    limit: ConcurrencyLimit,
+    slots: Arc<Semaphore>,
+    queued: AtomicUsize,
+}
+
+/// Slots shared by the calls of a server
+pub struct ConcurrencyLimiter {
+    config: ConcurrencyConfig,
+    gates: DashMap<String, Arc<Gate>>,
+    #[cfg(feature = "prometheus")]
+    metrics: Option<Metrics>,
+}
+
+impl ConcurrencyLimiter {
+    /// Create a limiter with every slot free
+    pub fn new(config: ConcurrencyConfig) -> Result<Self> {
+        config.validate()?;
+        Ok(Self {
+            config,
+            gates: DashMap::new(),
+            #[cfg(feature = "prometheus")]
+            metrics: None,
+        })
+    }
+
+    /// Count waiting and refused calls in `metrics`
+    #[cfg(feature = "prometheus")]
+    pub fn with_metrics(mut self, metrics: Metrics) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// The limiter configuration
+    pub fn config(&self) -> &ConcurrencyConfig {
+        &self.config
+    }
+
+    /// Calls of a method waiting for a slot
+    pub fn queued(&self, method: &str) -> usize {
+        self.gates.get(method).map_or(0, |gate| gate.queued.load(Ordering::Relaxed))
+    }
+
+    /// Take a slot for a call, waiting for one if its method allows;
+    /// `None` for methods without a limit
+    pub async fn acquire(&self, method: &str) -> Result<Option<OwnedSemaphorePermit>> {
+        let Some(gate) = self.gate(method) else {
+            return Ok(None);
+        };
+        if let Ok(permit) = Arc::clone(&gate.slots).try_acquire_owned() {
+            return Ok(Some(permit));
+        }
+
+        let Overflow::Wait { max_queued, timeout } = gate.limit.overflow else {
+            return Err(self.refuse(method, "calls in flight"));
+        };
+        if gate.queued.fetch_add(1, Ordering::AcqRel) >= max_queued {
+            gate.queued.fetch_sub(1, Ordering::AcqRel);
+            return Err(self.refuse(method, "calls queued"));
+        }
+        let _queued = Queued { limiter: self, gate: &gate, method };
+        #[cfg(feature = "prometheus")]
+        if let Some(ref metrics) = self.metrics {
+            metrics.record_queued(method, 1);
+        }
+
+        let slot = Arc::clone(&gate.slots).acquire_owned();
+        let permit = match timeout {
+            Some(timeout) => tokio::time::timeout(timeout, slot).await
+                .map_err(|_| self.refuse(method, "time in queue"))?,
+            None => slot.await,
+        };
+        // The semaphore is never closed
+        Ok(Some(permit.map_err(|_| Error::service("Concurrency limiter closed"))?))
+    }
+
+    fn gate(&self, method: &str) -> Option<Arc<Gate>> {
+        if let Some(gate) = self.gates.get(method) {
+            return Some(Arc::clone(&gate));
+        }
+        let limit = self.config.limit_for(method)?;
+        let gate = self.gates.entry(method.to_string()).or_insert_with(|| Arc::new(Gate {
+            limit,
+            slots: Arc::new(Semaphore::new(limit.max_in_flight)),
+            queued: AtomicUsize::new(0),
+        }));
+        Some(Arc::clone(&gate))
+    }
+
+    fn refuse(&self, method: &str, exhausted: &str) -> Error {
+        #[cfg(feature = "prometheus")]
+        if let Some(ref metrics) = self.metrics {
+            metrics.record_rejected(method);
+        }
+        Error::rate_limit(format!("Method '{}' is at its limit of {}", method, exhausted), None)
+    }
+}
+
+impl std::fmt::Debug for ConcurrencyLimiter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ConcurrencyLimiter")
+            .field("config", &self.config)
+            .field("methods", &self.gates.len())
+            .finish()
+    }
+}
+
+/// Counts a call in the queue until dropped, even if the call is abandoned
+struct Queued<'a> {
+    #[cfg_attr(not(feature = "prometheus"), allow(dead_code))]
+    limiter: &'a ConcurrencyLimiter,
+    gate: &'a Gate,
+    #[cfg_attr(not(feature = "prometheus"), allow(dead_code))]
+    method: &'a str,
+}
+
+impl Drop for Queued<'_> {
+    fn drop(&mut self) {
+        self.gate.queued.fetch_sub(1, Ordering::AcqRel);
+        #[cfg(feature = "prometheus")]
+        if let Some(ref metrics) = self.limiter.metrics {
+            metrics.record_queued(self.method, -1);
+        }
+    }
+}
+
+/// Layer limiting the calls of each method in flight; see the
+/// [module docs](self)
+#[derive(Debug, Clone)]
+pub struct ConcurrencyLayer {
+    limiter: Arc<ConcurrencyLimiter>,
+}
+
+impl ConcurrencyLayer {
+    /// Limit calls as `config` says
+    pub fn new(config: ConcurrencyConfig) -> Result<Self> {
+        Ok(Self::from_limiter(ConcurrencyLimiter::new(config)?))
+    }
+
+    /// Limit calls with an existing limiter
+    pub fn from_limiter(limiter: ConcurrencyLimiter) -> Self {
+        Self { limiter: Arc::new(limiter) }
+    }
+
+    /// The slots shared by every handler the layer wraps
+    pub fn limiter(&self) -> &Arc<ConcurrencyLimiter> {
+        &self.limiter
+    }
+}
+
+impl Layer for ConcurrencyLayer {
+    fn layer(&self, inner: Arc<dyn MethodHandler>) -> Arc<dyn MethodHandler> {
+        Arc::new(Limited {
+            inner,
+            limiter: Arc::clone(&self.limiter),
+        })
+    }
+}
+
+struct Limited {
+    inner: Arc<dyn MethodHandler>,
+    limiter: Arc<ConcurrencyLimiter>,
+}
+
+#[async_trait]
+impl MethodHandler for Limited {
+    async fn handle_method(&self, request: &JsonRpcRequest, context: &ServiceContext) -> Result<JsonRpcResponse> {
+        let _permit = self.limiter.acquire(&request.method).await?;
+        self.inner.handle_method(request, context).await
+    }
+
+    fn supported_methods(&self) -> Vec<String> {
+        self.inner.supported_methods()
+    }
+
+    fn supports_method(&self, method: &str) -> bool {
+        self.inner.supports_method(method)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::convenience::JsonRpcServer;
+    use serde_json::json;
+    use tokio::sync::Notify;
+
+    /// A server whose `slow` calls wait for `release`
+    fn server(config: ConcurrencyConfig, release: Arc<Notify>) -> JsonRpcServer {
+        JsonRpcServer::builder()
+            .method("slow", move |_params, _context| {
+                let release = Arc::clone(&release);
+                async move {
+                    release.notified().await;
+                    Ok("done")
+                }
+            })
+            .method("fast", |_params, _context| async { Ok("done") })
+            .concurrency(config)
+            .build()
+            .unwrap()
+    }
+
+    fn call(server: &JsonRpcServer, method: &str, id: i64) -> impl std::future::Future<Output = JsonRpcResponse> + 'static {
+        let router = server.router().clone();
+        let request = JsonRpcRequest::with_id(method, None, json!(id));
+        async move { router.dispatch(&request, &ServiceContext::new("req")).await.unwrap() }
+    }
+
+    #[tokio::test]
+    async fn test_reject_over_limit() {
+        let release = Arc::new(Notify::new());
+        let config = ConcurrencyConfig::default().with_method("slow", ConcurrencyLimit::reject(1));
+        let server = server(config, Arc::clone(&release));
+
+        let first = tokio::spawn(call(&server, "slow", 1));
+        tokio::task::yield_now().await;
+        let refused = call(&server, "slow", 2).await;
+        assert_eq!(refused.error.unwrap().code, -32005);
+        // Other methods are not held up
+        assert!(call(&server, "fast", 3).await.is_success());
+
+        release.notify_one();
+        assert!(first.await.unwrap().is_success());
+    }
+
+    #[tokio::test]
+    async fn test_queue_over_limit() {
+        let release = Arc::new(Notify::new());
+        let config = ConcurrencyConfig::default().with_default(ConcurrencyLimit::queued(1, 1));
+        let server = server(config, Arc::clone(&release));
+
+        let first = tokio::spawn(call(&server, "slow", 1));
+        tokio::task::yield_now().await;
+        let second = tokio::spawn(call(&server, "slow", 2));
+        tokio::task::yield_now().await;
+        // One in flight, one queued: the third is refused
+        let refused = call(&server, "slow", 3).await;
+        assert_eq!(refused.error.unwrap().code, -32005);
+
+        release.notify_one();
+        assert!(first.await.unwrap().is_success());
+        release.notify_one();
+        assert!(second.await.unwrap().is_success());
+
+        let timed_out = ConcurrencyLimiter::new(ConcurrencyConfig::default().with_default(
+            ConcurrencyLimit::queued(1, 1).with_queue_timeout(Duration::from_millis(10)),
+        )).unwrap();
+        let _held = timed_out.acquire("any").await.unwrap();
+        assert!(matches!(timed_out.acquire("any").await, Err(Error::RateLimit { .. })));
+        assert_eq!(timed_out.queued("any"), 0);
+    }
+}
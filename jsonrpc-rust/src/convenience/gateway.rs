@@ -0,0 +1,481 @@
+//! API gateway forwarding calls to upstream services
+//!
+//! A [`Gateway`] serves whole namespaces by forwarding their calls to
+//! other JSON-RPC services: with a [`Route`] for `bus`, a call to
+//! `bus.publish` goes to the event bus, answered or failed as the upstream
+//! answers it. Upstreams are any of the framework's clients, so a route can
+//! go through a connection pool, a circuit breaker or a response cache.
+//!
+//! Each route may authenticate its calls with its own [`AuthLayer`],
+//! rename the namespace upstream and rewrite the params on the way. The
+//! gateway is a [`MethodHandler`] like any other, so the server it is
+//! added to can still wrap it in layers of its own:
+//!
+//! ```rust
+//! use jsonrpc_rust::convenience::prelude::*;
+//! use jsonrpc_rust::protocol::JsonRpcClient;
+//! use std::sync::Arc;
+//!
+//! # fn example(eventbus: JsonRpcClient, trn: JsonRpcClient, auth: AuthLayer) -> jsonrpc_rust::Result<JsonRpcServer> {
+//! let gateway = Gateway::new("gateway", "1.0.0")
+//!     .route(Route::new("bus", eventbus).strip_namespace())
+//!     .route(Route::new("trn", trn).with_auth(auth));
+//!
+//! JsonRpcServer::builder()
+//!     .bind("tcp://0.0.0.0:9000")
+//!     .handler(Arc::new(gateway))
+//!     .build()
+//! # }
+//! ```
+//!
+//! The gateway also answers [`DISCOVER_METHOD`], describing every method
+//! its upstreams describe in their own answer to it, under the names
+//! callers of the gateway use. Upstreams that fail to answer are left out
+//! and listed in the `unavailable` metadata. Servers describe the methods
+//! added with [`rpc_method`](super::ServerBuilder::rpc_method) once made
+//! [`discoverable`](super::ServerBuilder::discoverable).
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde_json::Value;
+
+use super::auth::AuthLayer;
+use super::layer::Layer;
+use crate::core::error::{Error, Result};
+use crate::core::traits::MethodHandler;
+use crate::core::types::{JsonRpcRequest, JsonRpcResponse, MethodInfo, ServiceContext, ServiceInfo};
+use crate::protocol::{CachingClient, CircuitBreakerClient, ConnectionPool, JsonRpcClient};
+
+/// Method describing a service and its methods as a [`ServiceInfo`]
+pub const DISCOVER_METHOD: &str = "rpc.discover";
+
+/// A service calls are forwarded to
+#[async_trait]
+pub trait Upstream: Send + Sync {
+    /// Call `method`, failing with [`Error::JsonRpc`] when the service
+    /// answers with an error
+    async fn call(&self, method: &str, params: Option<Value>) -> Result<Value>;
+
+    /// Send a notification
+    async fn notify(&self, method: &str, params: Option<Value>) -> Result<()>;
+}
+
+#[async_trait]
+impl Upstream for JsonRpcClient {
+    async fn call(&self, method: &str, params: Option<Value>) -> Result<Value> {
+        JsonRpcClient::call(self, method, params).await
+    }
+
+    async fn notify(&self, method: &str, params: Option<Value>) -> Result<()> {
+        JsonRpcClient::notify(self, method, params).await
+    }
+}
+
+#[async_trait]
+impl Upstream for ConnectionPool {
+    async fn call(&self, method: &str, params: Option<Value>) -> Result<Value> {
+        ConnectionPool::call(self, method, params).await
+    }
+
+    async fn notify(&self, method: &str, params: Option<Value>) -> Result<()> {
+        ConnectionPool::notify(self, method, params).await
+    }
+}
+
+#[async_trait]
+impl Upstream for CircuitBreakerClient {
+    async fn call(&self, method: &str, params: Option<Value>) -> Result<Value> {
+        CircuitBreakerClient::call(self, method, params).await
+    }
+
+    async fn notify(&self, method: &str, params: Option<Value>) -> Result<()> {
+        CircuitBreakerClient::notify(self, method, params).await
+    }
+}
+
+#[async_trait]
+impl Upstream for CachingClient {
+    async fn call(&self, method: &str, params: Option<Value>) -> Result<Value> {
+        CachingClient::call(self, method, params).await
+    }
+
+    async fn notify(&self, method: &str, params: Option<Value>) -> Result<()> {
+        CachingClient::notify(self, method, params).await
+    }
+}
+
+/// Rewrites the params of the calls a route forwards
+type ParamsRewrite = dyn Fn(Option<Value>) -> Option<Value> + Send + Sync;
+
+/// Forwards the methods of a namespace to an upstream service
+pub struct Route {
+    namespace: String,
+    upstream: Arc<dyn Upstream>,
+    /// Namespace the methods have upstream; empty when they have none
+    upstream_namespace: String,
+    params: Option<Arc<ParamsRewrite>>,
+    auth: Option<AuthLayer>,
+}
+
+impl Route {
+    /// Forward the methods of `namespace`, such as `bus` for `bus.publish`,
+    /// to `upstream` under the same names
+    pub fn new(namespace: impl Into<String>, upstream: impl Upstream + 'static) -> Self {
+        let namespace = namespace.into();
+        Self {
+            upstream_namespace: namespace.clone(),
+            namespace,
+            upstream: Arc::new(upstream),
+            params: None,
+            auth: None,
+        }
+    }
+
+    /// Call the methods upstream in `namespace` instead, so `bus.publish`
+    /// becomes `eventbus.publish` with a namespace of `eventbus`
+    pub fn rename_namespace(mut self, namespace: impl Into<String>) -> Self {
+        self.upstream_namespace = namespace.into();
+        self
+    }
+
+    /// Call the methods upstream without their namespace, so `bus.publish`
+    /// becomes `publish`
+    pub fn strip_namespace(self) -> Self {
+        self.rename_namespace("")
+    }
+
+    /// Rewrite the params of every call before forwarding it
+    pub fn with_params_rewrite<F>(mut self, rewrite: F) -> Self
+    where
+        F: Fn(Option<Value>) -> Option<Value> + Send + Sync + 'static,
+    {
+        self.params = Some(Arc::new(rewrite));
+        self
+    }
+
+    /// Authenticate the calls of this route; permissions are required by
+    /// the names callers of the gateway use
+    pub fn with_auth(mut self, auth: AuthLayer) -> Self {
+        self.auth = Some(auth);
+        self
+    }
+
+    /// Namespace the route serves
+    pub fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
+    /// Name `method` has upstream, if it is in the route's namespace
+    pub fn upstream_method(&self, method: &str) -> Option<String> {
+        let name = method.strip_prefix(self.namespace.as_str())?.strip_prefix('.')?;
+        Some(match self.upstream_namespace.as_str() {
+            "" => name.to_string(),
+            namespace => format!("{}.{}", namespace, name),
+        })
+    }
+
+    /// Name callers of the gateway use for the upstream `method`, if the
+    /// route reaches it
+    fn public_method(&self, method: &str) -> Option<String> {
+        let name = match self.upstream_namespace.as_str() {
+            "" => method,
+            namespace => method.strip_prefix(namespace)?.strip_prefix('.')?,
+        };
+        Some(format!("{}.{}", self.namespace, name))
+    }
+
+    /// The methods the upstream describes, under their public names
+    async fn discover(&self) -> Result<Vec<MethodInfo>> {
+        let info: ServiceInfo = serde_json::from_value(self.upstream.call(DISCOVER_METHOD, None).await?)?;
+        Ok(info.methods.into_iter()
+            .filter_map(|mut method| {
+                method.name = self.public_method(&method.name)?;
+                method.auth_required |= self.auth.is_some();
+                method.metadata.insert("route".into(), Value::String(self.namespace.clone()));
+                Some(method)
+            })
+            .collect())
+    }
+}
+
+impl std::fmt::Debug for Route {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Route")
+            .field("namespace", &self.namespace)
+            .field("upstream_namespace", &self.upstream_namespace)
+            .field("params_rewrite", &self.params.is_some())
+            .field("auth", &self.auth)
+            .finish()
+    }
+}
+
+/// Forwards the calls of one route
+struct Forward {
+    route: Arc<Route>,
+}
+
+#[async_trait]
+impl MethodHandler for Forward {
+    async fn handle_method(&self, request: &JsonRpcRequest, _context: &ServiceContext) -> Result<JsonRpcResponse> {
+        let method = self.route.upstream_method(&request.method)
+            .ok_or_else(|| Error::method_not_found(&request.method))?;
+        let params = match &self.route.params {
+            Some(rewrite) => rewrite(request.params.clone()),
+            None => request.params.clone(),
+        };
+
+        let Some(id) = request.id.clone() else {
+            self.route.upstream.notify(&method, params).await?;
+            return Ok(JsonRpcResponse::success(Value::Null, Value::Null));
+        };
+        match self.route.upstream.call(&method, params).await {
+            Ok(result) => Ok(JsonRpcResponse::success(id, result)),
+            // The upstream's answer is passed on as it is
+            Err(Error::JsonRpc(error)) => Ok(JsonRpcResponse::error(id, error)),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn supported_methods(&self) -> Vec<String> {
+        vec![format!("{}.*", self.route.namespace)]
+    }
+}
+
+/// Serves namespaces by forwarding their calls to upstream services
+pub struct Gateway {
+    name: String,
+    version: String,
+    /// Routes with the handler forwarding their calls, behind their auth
+    routes: Vec<(Arc<Route>, Arc<dyn MethodHandler>)>,
+}
+
+impl Gateway {
+    /// Create a gateway without routes, described under `name` and
+    /// `version` by [`DISCOVER_METHOD`]
+    pub fn new(name: impl Into<String>, version: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            version: version.into(),
+            routes: Vec::new(),
+        }
+    }
+
+    /// Add a route; a later route for the same namespace replaces it
+    pub fn route(mut self, route: Route) -> Self {
+        let route = Arc::new(route);
+        let forward: Arc<dyn MethodHandler> = Arc::new(Forward { route: Arc::clone(&route) });
+        let handler = match &route.auth {
+            Some(auth) => auth.layer(forward),
+            None => forward,
+        };
+        self.routes.retain(|(existing, _)| existing.namespace != route.namespace);
+        self.routes.push((route, handler));
+        self
+    }
+
+    /// The routes, in the order they were added
+    pub fn routes(&self) -> impl Iterator<Item = &Route> {
+        self.routes.iter().map(|(route, _)| route.as_ref())
+    }
+
+    /// Handler of the route serving `method`, preferring the longest namespace
+    fn resolve(&self, method: &str) -> Option<&Arc<dyn MethodHandler>> {
+        self.routes.iter()
+            .filter(|(route, _)| route.upstream_method(method).is_some())
+            .max_by_key(|(route, _)| route.namespace.len())
+            .map(|(_, handler)| handler)
+    }
+
+    /// Describe the methods of every upstream answering
+    pub async fn discover(&self) -> ServiceInfo {
+        let answers = futures::future::join_all(self.routes.iter().map(|(route, _)| route.discover())).await;
+
+        let mut methods = Vec::new();
+        let mut unavailable = Vec::new();
+        for ((route, _), answer) in self.routes.iter().zip(answers) {
+            match answer {
+                Ok(described) => methods.extend(described),
+                Err(e) => {
+                    tracing::warn!("Upstream of '{}' failed to describe its methods: {}", route.namespace, e);
+                    unavailable.push(Value::String(route.namespace.clone()));
+                }
+            }
+        }
+
+        let mut metadata = HashMap::new();
+        if !unavailable.is_empty() {
+            metadata.insert("unavailable".to_string(), Value::Array(unavailable));
+        }
+        ServiceInfo {
+            name: self.name.clone(),
+            version: self.version.clone(),
+            description: String::new(),
+            methods,
+            health_endpoint: None,
+            metadata,
+        }
+    }
+}
+
+#[async_trait]
+impl MethodHandler for Gateway {
+    async fn handle_method(&self, request: &JsonRpcRequest, context: &ServiceContext) -> Result<JsonRpcResponse> {
+        if request.method == DISCOVER_METHOD {
+            let id = request.id.clone().unwrap_or(Value::Null);
+            return Ok(JsonRpcResponse::success(id, serde_json::to_value(self.discover().await)?));
+        }
+        let handler = self.resolve(&request.method)
+            .ok_or_else(|| Error::method_not_found(&request.method))?;
+        handler.handle_method(request, context).await
+    }
+
+    fn supported_methods(&self) -> Vec<String> {
+        std::iter::once(DISCOVER_METHOD.to_string())
+            .chain(self.routes.iter().map(|(route, _)| format!("{}.*", route.namespace)))
+            .collect()
+    }
+
+    fn supports_method(&self, method: &str) -> bool {
+        method == DISCOVER_METHOD || self.resolve(method).is_some()
+    }
+}
+
+impl std::fmt::Debug for Gateway {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Gateway")
+            .field("name", &self.name)
+            .field("version", &self.version)
+            .field("routes", &self.routes().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+/// Answers [`DISCOVER_METHOD`] with a fixed description
+#[derive(Debug, Clone)]
+pub struct Discovery {
+    info: ServiceInfo,
+}
+
+impl Discovery {
+    /// Describe a service as `info`
+    pub fn new(info: ServiceInfo) -> Self {
+        Self { info }
+    }
+}
+
+#[async_trait]
+impl MethodHandler for Discovery {
+    async fn handle_method(&self, request: &JsonRpcRequest, _context: &ServiceContext) -> Result<JsonRpcResponse> {
+        let id = request.id.clone().unwrap_or(Value::Null);
+        Ok(JsonRpcResponse::success(id, serde_json::to_value(&self.info)?))
+    }
+
+    fn supported_methods(&self) -> Vec<String> {
+        vec![DISCOVER_METHOD.to_string()]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::convenience::auth::ApiKeyVerifier;
+    use crate::convenience::server::JsonRpcServer;
+    use crate::core::types::AuthContext;
+    use crate::transport::InProcTransport;
+    use serde_json::json;
+
+    fn method_info(name: &str) -> MethodInfo {
+        MethodInfo {
+            name: name.to_string(),
+            description: String::new(),
+            params_schema: None,
+            returns_schema: None,
+            example_params: None,
+            example_returns: None,
+            auth_required: false,
+            required_permissions: Vec::new(),
+            metadata: HashMap::new(),
+        }
+    }
+
+    /// Client of a server answering `<namespace>.echo` with its params and
+    /// the name it was called by, and describing it
+    fn upstream(namespace: &str) -> JsonRpcClient {
+        let echo = format!("{}.echo", namespace);
+        let server = JsonRpcServer::builder()
+            .method(echo.clone(), {
+                let echo = echo.clone();
+                move |params, _context| {
+                    let echo = echo.clone();
+                    async move { Ok(json!({"method": echo, "params": params})) }
+                }
+            })
+            .method(format!("{}.fail", namespace), |_params, _context| async {
+                Err::<(), _>(Error::JsonRpc(crate::core::error::JsonRpcError::invalid_params("bad")))
+            })
+            .route(DISCOVER_METHOD, Arc::new(Discovery::new(ServiceInfo {
+                name: namespace.to_string(),
+                version: "1.0.0".into(),
+                description: String::new(),
+                methods: vec![method_info(&echo), method_info("elsewhere.echo")],
+                health_endpoint: None,
+                metadata: HashMap::new(),
+            })))
+            .build()
+            .unwrap();
+        JsonRpcClient::new(InProcTransport::connect(server.router().clone()))
+    }
+
+    async fn call(gateway: &Gateway, method: &str, params: Value, metadata: Option<(&str, &str)>) -> JsonRpcResponse {
+        let mut context = ServiceContext::new("test");
+        if let Some((key, value)) = metadata {
+            context.metadata.insert(key.into(), json!(value));
+        }
+        let request = JsonRpcRequest::with_id(method, Some(params), json!(1));
+        match gateway.handle_method(&request, &context).await {
+            Ok(response) => response,
+            Err(e) => JsonRpcResponse::error(json!(1), e.to_jsonrpc_error()),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_routes_rewrite_and_authenticate() {
+        let keys = ApiKeyVerifier::new().with_key("key", AuthContext::new("alice", "api_key"));
+        let gateway = Gateway::new("gateway", "1.0.0")
+            .route(Route::new("bus", upstream("eventbus")).rename_namespace("eventbus"))
+            .route(Route::new("trn", upstream("trn"))
+                .with_params_rewrite(|params| Some(json!({"wrapped": params})))
+                .with_auth(AuthLayer::new().with_verifier(keys)));
+
+        let echoed = call(&gateway, "bus.echo", json!([1]), None).await;
+        assert_eq!(echoed.result, Some(json!({"method": "eventbus.echo", "params": [1]})));
+        let failed = call(&gateway, "bus.fail", json!([]), None).await;
+        assert_eq!(failed.error.unwrap().code, -32602);
+        let missing = call(&gateway, "other.echo", json!([]), None).await;
+        assert_eq!(missing.error.unwrap().code, -32601);
+
+        let refused = call(&gateway, "trn.echo", json!([2]), None).await;
+        assert_eq!(refused.error.unwrap().code, -32001);
+        let rewritten = call(&gateway, "trn.echo", json!([2]), Some(("authorization", "key"))).await;
+        assert_eq!(rewritten.result, Some(json!({"method": "trn.echo", "params": {"wrapped": [2]}})));
+    }
+
+    #[tokio::test]
+    async fn test_discovery_is_aggregated() {
+        let gateway = Gateway::new("gateway", "1.0.0")
+            .route(Route::new("bus", upstream("eventbus")).rename_namespace("eventbus"))
+            .route(Route::new("trn", upstream("trn")).strip_namespace())
+            .route(Route::new("down", JsonRpcClient::new(InProcTransport::connect(Default::default()))));
+
+        let response = call(&gateway, DISCOVER_METHOD, json!([]), None).await;
+        let info: ServiceInfo = serde_json::from_value(response.result.unwrap()).unwrap();
+        let names: Vec<_> = info.methods.iter().map(|method| method.name.as_str()).collect();
+        // Methods outside a renamed namespace cannot be reached; stripped
+        // names all get the route's namespace in front
+        assert_eq!(names, vec!["bus.echo", "trn.trn.echo", "trn.elsewhere.echo"]);
+        assert_eq!(info.methods[0].metadata["route"], "bus");
+        assert_eq!(info.metadata["unavailable"], json!(["down"]));
+    }
+}
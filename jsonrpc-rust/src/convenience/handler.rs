@@ -0,0 +1,75 @@
+//! Method handlers from async closures, and self-describing handlers
+
+use std::future::Future;
+use std::marker::PhantomData;
+
+use async_trait::async_trait;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::core::error::Result;
+use crate::core::traits::MethodHandler;
+use crate::core::types::{JsonRpcRequest, JsonRpcResponse, MethodInfo, ServiceContext};
+
+/// A method handler that can describe the method it serves
+///
+/// Implemented by the handlers `#[rpc_method]` generates (`macros`
+/// feature); servers collect the descriptions for introspection.
+pub trait RpcMethod: MethodHandler {
+    /// Name, description and JSON schemas of the method
+    fn method_info(&self) -> MethodInfo;
+}
+
+/// A [`MethodHandler`] serving one method with an async closure
+///
+/// The closure receives the request params and a copy of the service
+/// context, and returns any serializable result.
+pub struct FnHandler<F, R> {
+    method: String,
+    handler: F,
+    _result: PhantomData<fn() -> R>,
+}
+
+impl<F, Fut, R> FnHandler<F, R>
+where
+    F: Fn(Option<Value>, ServiceContext) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<R>> + Send + 'static,
+    R: Serialize + 'static,
+{
+    /// Serve `method` with `handler`
+    pub fn new(method: impl Into<String>, handler: F) -> Self {
+        Self {
+            method: method.into(),
+            handler,
+            _result: PhantomData,
+        }
+    }
+}
+
+#[async_trait]
+impl<F, Fut, R> MethodHandler for FnHandler<F, R>
+where
+    F: Fn(Option<Value>, ServiceContext) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<R>> + Send + 'static,
+    R: Serialize + 'static,
+{
+    async fn handle_method(&self, request: &JsonRpcRequest, context: &ServiceContext) -> Result<JsonRpcResponse> {
+        let result = (self.handler)(request.params.clone(), context.clone()).await?;
+        Ok(JsonRpcResponse::success(
+            request.id.clone().unwrap_or(Value::Null),
+            serde_json::to_value(result)?,
+        ))
+    }
+
+    fn supported_methods(&self) -> Vec<String> {
+        vec![self.method.clone()]
+    }
+}
+
+impl<F, R> std::fmt::Debug for FnHandler<F, R> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FnHandler")
+            .field("method", &self.method)
+            .finish()
+    }
+}
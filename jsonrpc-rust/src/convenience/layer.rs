@@ -0,0 +1,80 @@
+//! Middleware layers
+//!
+//! A [`Layer`] wraps every method handler of a server in another handler,
+//! which can act before and after the call or answer it without calling
+//! the inner handler at all. Layers are applied in the order they are
+//! added, the first one ending up outermost.
+
+use std::sync::Arc;
+use std::time::Instant;
+
+use async_trait::async_trait;
+
+use crate::core::error::Result;
+use crate::core::traits::MethodHandler;
+use crate::core::types::{JsonRpcRequest, JsonRpcResponse, ServiceContext};
+
+/// Wraps method handlers in middleware
+pub trait Layer: Send + Sync {
+    /// Wrap `inner`
+    fn layer(&self, inner: Arc<dyn MethodHandler>) -> Arc<dyn MethodHandler>;
+}
+
+impl<F> Layer for F
+where
+    F: Fn(Arc<dyn MethodHandler>) -> Arc<dyn MethodHandler> + Send + Sync,
+{
+    fn layer(&self, inner: Arc<dyn MethodHandler>) -> Arc<dyn MethodHandler> {
+        self(inner)
+    }
+}
+
+/// Logs every call with its outcome and duration
+pub fn logging() -> LoggingLayer {
+    LoggingLayer
+}
+
+/// Layer logging calls through `tracing`; see [`logging`]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LoggingLayer;
+
+impl Layer for LoggingLayer {
+    fn layer(&self, inner: Arc<dyn MethodHandler>) -> Arc<dyn MethodHandler> {
+        Arc::new(Logged { inner })
+    }
+}
+
+struct Logged {
+    inner: Arc<dyn MethodHandler>,
+}
+
+#[async_trait]
+impl MethodHandler for Logged {
+    async fn handle_method(&self, request: &JsonRpcRequest, context: &ServiceContext) -> Result<JsonRpcResponse> {
+        let started = Instant::now();
+        let result = self.inner.handle_method(request, context).await;
+        let elapsed = started.elapsed();
+
+        match &result {
+            Ok(response) if response.is_success() => tracing::info!(
+                method = %request.method, request_id = %context.request_id, ?elapsed, "Call succeeded"
+            ),
+            Ok(response) => tracing::warn!(
+                method = %request.method, request_id = %context.request_id, ?elapsed,
+                code = response.error.as_ref().map(|error| error.code), "Call answered with an error"
+            ),
+            Err(e) => tracing::warn!(
+                method = %request.method, request_id = %context.request_id, ?elapsed, error = %e, "Call failed"
+            ),
+        }
+        result
+    }
+
+    fn supported_methods(&self) -> Vec<String> {
+        self.inner.supported_methods()
+    }
+
+    fn supports_method(&self, method: &str) -> bool {
+        self.inner.supports_method(method)
+    }
+}
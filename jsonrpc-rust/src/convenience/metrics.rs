@@ -0,0 +1,341 @@
+//! Prometheus metrics
+//!
+//! [`Metrics`] counts the calls a server answers and the traffic of its
+//! connections:
+//!
+//! | Metric | Labels | |
+//! |---|---|---|
+//! | `jsonrpc_requests_total` | `method` | Calls answered |
+//! | `jsonrpc_request_duration_seconds` | `method` | Time to answer calls |
+//! | `jsonrpc_requests_in_flight` | `method` | Calls being answered |
+//! | `jsonrpc_requests_queued` | `method` | Calls waiting at their method's concurrency limit |
+//! | `jsonrpc_requests_rejected_total` | `method` | Calls refused at their method's concurrency limit |
+//! | `jsonrpc_errors_total` | `method`, `code` | Calls answered with an error, by code |
+//! | `jsonrpc_handler_panics_total` | `method` | Calls whose handler panicked |
+//! | `jsonrpc_transport_bytes_total` | `direction` | Message bytes `received` and `sent` |
+//! | `jsonrpc_connections` | | Connections open |
+//! | `jsonrpc_connections_accepted_total` | | Connections accepted |
+//! | `jsonrpc_connections_refused_total` | | Connections refused at the connection limit |
+//!
+//! Give it to [`ServerBuilder::metrics`](super::ServerBuilder::metrics)
+//! and serve [`Metrics::encode`] to the scraper, or push it to a gateway:
+//!
+//! ```rust
+//! use jsonrpc_rust::convenience::prelude::*;
+//!
+//! let metrics = Metrics::new();
+//! let server = JsonRpcServer::builder()
+//!     .method("ping", |_params, _context| async { Ok("pong") })
+//!     .metrics(metrics.clone())
+//!     .build();
+//!
+//! let text = metrics.encode().unwrap();
+//! assert!(text.contains("jsonrpc_connections 0"));
+//! ```
+//!
+//! To expose them next to other metrics, register them in an existing
+//! registry with [`Metrics::register`] instead.
+
+use std::panic::AssertUnwindSafe;
+use std::sync::Arc;
+use std::time::Instant;
+
+use async_trait::async_trait;
+use futures::FutureExt;
+use prometheus_client::encoding::EncodeLabelSet;
+use prometheus_client::metrics::counter::Counter;
+use prometheus_client::metrics::family::Family;
+use prometheus_client::metrics::gauge::Gauge;
+use prometheus_client::metrics::histogram::{exponential_buckets, Histogram};
+use prometheus_client::registry::Registry;
+
+use super::layer::Layer;
+use crate::core::error::{Error, Result};
+use crate::core::traits::MethodHandler;
+use crate::core::types::{JsonRpcRequest, JsonRpcResponse, ServiceContext};
+
+#[derive(Debug, Clone, Hash, PartialEq, Eq, EncodeLabelSet)]
+struct MethodLabels {
+    method: String,
+}
+
+#[derive(Debug, Clone, Hash, PartialEq, Eq, EncodeLabelSet)]
+struct ErrorLabels {
+    method: String,
+    code: String,
+}
+
+#[derive(Debug, Clone, Hash, PartialEq, Eq, EncodeLabelSet)]
+struct DirectionLabels {
+    direction: &'static str,
+}
+
+struct Families {
+    requests: Family<MethodLabels, Counter>,
+    durations: Family<MethodLabels, Histogram>,
+    in_flight: Family<MethodLabels, Gauge>,
+    queued: Family<MethodLabels, Gauge>,
+    rejected: Family<MethodLabels, Counter>,
+    errors: Family<ErrorLabels, Counter>,
+    panics: Family<MethodLabels, Counter>,
+    bytes: Family<DirectionLabels, Counter>,
+    connections: Gauge,
+    accepted: Counter,
+    refused: Counter,
+}
+
+/// Request and transport metrics of a server; clones share the counts
+#[derive(Clone)]
+pub struct Metrics {
+    families: Arc<Families>,
+    registry: Option<Arc<Registry>>,
+}
+
+impl Metrics {
+    /// Create metrics in a registry of their own, for [`encode`](Self::encode)
+    pub fn new() -> Self {
+        let mut registry = Registry::default();
+        let mut metrics = Self::register(&mut registry);
+        metrics.registry = Some(Arc::new(registry));
+        metrics
+    }
+
+    /// Create metrics in `registry`, under the `jsonrpc` prefix
+    pub fn register(registry: &mut Registry) -> Self {
+        let families = Families {
+            requests: Family::default(),
+            durations: Family::new_with_constructor(duration_histogram),
+            in_flight: Family::default(),
+            queued: Family::default(),
+            rejected: Family::default(),
+            errors: Family::default(),
+            panics: Family::default(),
+            bytes: Family::default(),
+            connections: Gauge::default(),
+            accepted: Counter::default(),
+            refused: Counter::default(),
+        };
+
+        let registry = registry.sub_registry_with_prefix("jsonrpc");
+        registry.register("requests", "Calls answered", families.requests.clone());
+        registry.register("request_duration_seconds", "Time to answer calls", families.durations.clone());
+        registry.register("requests_in_flight", "Calls being answered", families.in_flight.clone());
+        registry.register("requests_queued", "Calls waiting at their method's concurrency limit", families.queued.clone());
+        registry.register("requests_rejected", "Calls refused at their method's concurrency limit", families.rejected.clone());
+        registry.register("errors", "Calls answered with an error, by code", families.errors.clone());
+        registry.register("handler_panics", "Calls whose handler panicked", families.panics.clone());
+        registry.register("transport_bytes", "Message bytes received and sent", families.bytes.clone());
+        registry.register("connections", "Connections open", families.connections.clone());
+        registry.register("connections_accepted", "Connections accepted", families.accepted.clone());
+        registry.register("connections_refused", "Connections refused at the connection limit", families.refused.clone());
+
+        Self {
+            families: Arc::new(families),
+            registry: None,
+        }
+    }
+
+    /// The metrics in the Prometheus text format; only for metrics made
+    /// with [`new`](Self::new), those registered elsewhere are encoded
+    /// with their registry
+    pub fn encode(&self) -> Result<String> {
+        let registry = self.registry.as_ref()
+            .ok_or_else(|| Error::configuration("Metrics live in an external registry"))?;
+        let mut text = String::new();
+        prometheus_client::encoding::text::encode(&mut text, registry)
+            .map_err(|e| Error::serialization(format!("Failed to encode metrics: {}", e)))?;
+        Ok(text)
+    }
+
+    /// Count bytes of a message received
+    pub fn record_received(&self, bytes: usize) {
+        self.families.bytes.get_or_create(&DirectionLabels { direction: "received" }).inc_by(bytes as u64);
+    }
+
+    /// Count bytes of a message sent
+    pub fn record_sent(&self, bytes: usize) {
+        self.families.bytes.get_or_create(&DirectionLabels { direction: "sent" }).inc_by(bytes as u64);
+    }
+
+    /// Count a connection opening
+    pub fn connection_opened(&self) {
+        self.families.accepted.inc();
+        self.families.connections.inc();
+    }
+
+    /// Count a connection closing
+    pub fn connection_closed(&self) {
+        self.families.connections.dec();
+    }
+
+    /// Count a connection refused at the connection limit
+    pub fn connection_refused(&self) {
+        self.families.refused.inc();
+    }
+
+    /// Count a call entering (`1`) or leaving (`-1`) the queue of its method
+    pub(crate) fn record_queued(&self, method: &str, delta: i64) {
+        self.families.queued.get_or_create(&MethodLabels { method: method.to_string() }).inc_by(delta);
+    }
+
+    /// Count a call refused at the concurrency limit of its method
+    pub(crate) fn record_rejected(&self, method: &str) {
+        self.families.rejected.get_or_create(&MethodLabels { method: method.to_string() }).inc();
+    }
+
+    /// Count a call answered
+    fn record_call(&self, method: &str, started: Instant, error_code: Option<i32>) {
+        let labels = MethodLabels { method: method.to_string() };
+        self.families.requests.get_or_create(&labels).inc();
+        self.families.durations.get_or_create(&labels).observe(started.elapsed().as_secs_f64());
+        if let Some(code) = error_code {
+            let labels = ErrorLabels { method: labels.method, code: code.to_string() };
+            self.families.errors.get_or_create(&labels).inc();
+        }
+    }
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl std::fmt::Debug for Metrics {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Metrics")
+            .field("own_registry", &self.registry.is_some())
+            .finish()
+    }
+}
+
+/// From 1ms to about 33s
+fn duration_histogram() -> Histogram {
+    Histogram::new(exponential_buckets(0.001, 2.0, 16))
+}
+
+impl Layer for Metrics {
+    fn layer(&self, inner: Arc<dyn MethodHandler>) -> Arc<dyn MethodHandler> {
+        Arc::new(Measured {
+            inner,
+            metrics: self.clone(),
+        })
+    }
+}
+
+struct Measured {
+    inner: Arc<dyn MethodHandler>,
+    metrics: Metrics,
+}
+
+#[async_trait]
+impl MethodHandler for Measured {
+    async fn handle_method(&self, request: &JsonRpcRequest, context: &ServiceContext) -> Result<JsonRpcResponse> {
+        let in_flight = InFlight(self.metrics.families.in_flight
+            .get_or_create(&MethodLabels { method: request.method.clone() })
+            .clone());
+        in_flight.0.inc();
+        let started = Instant::now();
+
+        let result = match AssertUnwindSafe(self.inner.handle_method(request, context)).catch_unwind().await {
+            Ok(result) => result,
+            Err(panic) => {
+                // Counted as the internal error the router answers it with
+                self.metrics.families.panics.get_or_create(&MethodLabels { method: request.method.clone() }).inc();
+                self.metrics.record_call(&request.method, started, Some(-32603));
+                std::panic::resume_unwind(panic);
+            }
+        };
+        let error_code = match &result {
+            Ok(response) => response.error.as_ref().map(|error| error.code),
+            Err(e) => Some(e.to_jsonrpc_error().code),
+        };
+        self.metrics.record_call(&request.method, started, error_code);
+        result
+    }
+
+    fn supported_methods(&self) -> Vec<String> {
+        self.inner.supported_methods()
+    }
+
+    fn supports_method(&self, method: &str) -> bool {
+        self.inner.supports_method(method)
+    }
+}
+
+/// Counts a call in flight until dropped, even if the call is abandoned
+struct InFlight(Gauge);
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        self.0.dec();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::convenience::JsonRpcServer;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_calls_are_counted_by_method_and_code() {
+        let metrics = Metrics::new();
+        let server = JsonRpcServer::builder()
+            .method("ping", |_params, _context| async { Ok("pong") })
+            .method("fail", |_params, _context| async { Err::<(), _>(Error::invalid_params("bad")) })
+            .method("crash", |_params, _context| async { panic!("handler bug") as Result<()> })
+            .metrics(metrics.clone())
+            .build()
+            .unwrap();
+        let context = ServiceContext::new("req-1");
+
+        for method in ["ping", "ping", "fail", "crash"] {
+            let request = JsonRpcRequest::with_id(method, None, json!(1));
+            server.router().dispatch(&request, &context).await.unwrap();
+        }
+        metrics.record_received(12);
+
+        let text = metrics.encode().unwrap();
+        assert!(text.contains("jsonrpc_requests_total{method=\"ping\"} 2"));
+        assert!(text.contains("jsonrpc_errors_total{method=\"fail\",code=\"-32602\"} 1"));
+        assert!(text.contains("jsonrpc_requests_in_flight{method=\"ping\"} 0"));
+        assert!(text.contains("jsonrpc_handler_panics_total{method=\"crash\"} 1"));
+        assert!(text.contains("jsonrpc_errors_total{method=\"crash\",code=\"-32603\"} 1"));
+        assert!(text.contains("jsonrpc_request_duration_seconds_count{method=\"fail\"} 1"));
+        assert!(text.contains("jsonrpc_transport_bytes_total{direction=\"received\"} 12"));
+
+        let mut registry = Registry::default();
+        let shared = Metrics::register(&mut registry);
+        assert!(shared.encode().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_concurrency_limits_are_counted() {
+        use crate::convenience::{ConcurrencyConfig, ConcurrencyLimit};
+
+        let metrics = Metrics::new();
+        let server = JsonRpcServer::builder()
+            .method("ping", |_params, _context| async { Ok("pong") })
+            .concurrency(ConcurrencyConfig::default().with_method("ping", ConcurrencyLimit::reject(1)))
+            .metrics(metrics.clone())
+            .build()
+            .unwrap();
+
+        let limiter = crate::convenience::ConcurrencyLimiter::new(
+            ConcurrencyConfig::default().with_default(ConcurrencyLimit::queued(1, 1)),
+        ).unwrap().with_metrics(metrics.clone());
+        let _held = limiter.acquire("report").await.unwrap();
+        let queued = limiter.acquire("report");
+        tokio::pin!(queued);
+        assert!(futures::poll!(queued.as_mut()).is_pending());
+        assert!(metrics.encode().unwrap().contains("jsonrpc_requests_queued{method=\"report\"} 1"));
+        assert!(limiter.acquire("report").await.is_err());
+
+        let request = JsonRpcRequest::with_id("ping", None, json!(1));
+        server.router().dispatch(&request, &ServiceContext::new("req-1")).await.unwrap();
+        let text = metrics.encode().unwrap();
+        assert!(text.contains("jsonrpc_requests_rejected_total{method=\"report\"} 1"));
+        assert!(text.contains("jsonrpc_requests_total{method=\"ping\"} 1"));
+    }
+}
@@ -0,0 +1,67 @@
+//! Convenience layer with builders and runtime wrappers (Phase 5)
+//!
+//! This module assembles the lower layers into ready-to-run pieces: a
+//! [`JsonRpcServer`] built from endpoints, method closures and middleware
+//! [`Layer`]s, with calls authenticated by an [`AuthLayer`] and rate
+//! limited by a [`RateLimitLayer`], with per-method concurrency limits
+//! from a [`ConcurrencyLayer`], served until a graceful shutdown. With
+//! the `macros` feature, [`rpc_method`] turns async functions with
+//! serde-typed params and results into self-describing handlers, and with
+//! the `validation` feature the server checks call params against their
+//! JSON schemas. The `prometheus` feature adds request and connection
+//! metrics. A [`Recorder`] captures calls for replay against a service or
+//! in place of one. A [`Gateway`] forwards whole namespaces to upstream
+//! services, as an API gateway built from these same pieces.
+
+pub mod auth;
+pub mod concurrency;
+pub mod gateway;
+pub mod handler;
+pub mod layer;
+#[cfg(feature = "prometheus")]
+pub mod metrics;
+pub mod rate_limit;
+pub mod recording;
+pub mod server;
+#[cfg(feature = "macros")]
+pub mod typed;
+#[cfg(feature = "validation")]
+pub mod validation;
+
+pub use auth::*;
+pub use concurrency::*;
+pub use gateway::*;
+pub use handler::*;
+pub use layer::*;
+#[cfg(feature = "prometheus")]
+pub use metrics::*;
+pub use rate_limit::*;
+pub use recording::{Recorder, RecordingLayer, ReplayHandler, ReplayReport};
+pub use server::*;
+#[cfg(feature = "validation")]
+pub use validation::*;
+#[cfg(feature = "macros")]
+pub use jsonrpc_macros::rpc_method;
+
+pub mod prelude {
+    //! Common imports for convenience layer usage
+
+    pub use super::auth::{ApiKeyVerifier, AuthLayer, TokenSource, TokenVerifier};
+    pub use super::concurrency::{ConcurrencyConfig, ConcurrencyLayer, ConcurrencyLimit, Overflow};
+    #[cfg(feature = "jwt")]
+    pub use super::auth::JwtVerifier;
+    #[cfg(feature = "trn-integration")]
+    pub use super::auth::TrnAuthorizationLayer;
+    pub use super::gateway::{Gateway, Route, Upstream};
+    pub use super::handler::{FnHandler, RpcMethod};
+    pub use super::layer::{logging, Layer, LoggingLayer};
+    #[cfg(feature = "prometheus")]
+    pub use super::metrics::Metrics;
+    pub use super::rate_limit::{RateLimitLayer, RateLimiter};
+    pub use super::recording::{Recorder, RecordingLayer, ReplayHandler};
+    pub use super::server::{JsonRpcServer, ServerBuilder, ServerConfig, ServerHandle};
+    #[cfg(feature = "macros")]
+    pub use jsonrpc_macros::rpc_method;
+    #[cfg(feature = "validation")]
+    pub use super::validation::{ParamsValidation, ParamsValidator, ParamsViolation};
+}
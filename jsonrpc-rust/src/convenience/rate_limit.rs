@@ -0,0 +1,261 @@
+//! Rate limiting of incoming calls
+//!
+//! A [`RateLimitLayer`] gives calls token buckets by client id, remote
+//! address and method as its [`RateLimitConfig`] says, and answers calls
+//! finding their bucket empty with code `-32005` and the wait before a
+//! token is back:
+//!
+//! ```json
+//! {"code": -32005, "message": "Rate limit exceeded: ...", "data": {"kind": "rate_limit", "retryable": true, "retry_after_ms": 250}}
+//! ```
+//!
+//! Servers apply the limits set on their connection configuration with
+//! [`NegotiatedConfig::with_rate_limit`](crate::transport::NegotiatedConfig::with_rate_limit),
+//! or those given to [`ServerBuilder::rate_limit`](super::ServerBuilder::rate_limit).
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use parking_lot::Mutex;
+use serde_json::Value;
+
+use super::layer::Layer;
+use crate::core::error::{Error, Result};
+use crate::core::traits::MethodHandler;
+use crate::core::types::{JsonRpcRequest, JsonRpcResponse, ServiceContext};
+use crate::transport::{RateLimit, RateLimitConfig, RateLimitKey};
+
+/// Token buckets shared by the calls of a server
+pub struct RateLimiter {
+    config: RateLimitConfig,
+    buckets: Mutex<HashMap<String, Bucket>>,
+}
+
+struct Bucket {
+    limit: RateLimit,
+    tokens: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    fn full(limit: RateLimit, now: Instant) -> Self {
+        Self {
+            limit,
+            tokens: f64::from(limit.burst),
+            updated: now,
+        }
+    }
+
+    /// Tokens in the bucket at `now`
+    fn tokens_at(&self, now: Instant) -> f64 {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        (self.tokens + elapsed * self.limit.per_second).min(f64::from(self.limit.burst))
+    }
+
+    fn refill(&mut self, now: Instant) {
+        self.tokens = self.tokens_at(now);
+        self.updated = now;
+    }
+}
+
+impl RateLimiter {
+    /// Create a limiter with full buckets
+    pub fn new(config: RateLimitConfig) -> Result<Self> {
+        config.validate()?;
+        Ok(Self {
+            config,
+            buckets: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// The limiter configuration
+    pub fn config(&self) -> &RateLimitConfig {
+        &self.config
+    }
+
+    /// Take a token for a call, or tell how long until one is back
+    pub fn check(&self, method: &str, context: &ServiceContext) -> Result<()> {
+        let limit = self.config.limit_for(method);
+        let key = self.bucket_key(method, context);
+        let now = Instant::now();
+
+        let mut buckets = self.buckets.lock();
+        if !buckets.contains_key(&key) && buckets.len() >= self.config.max_buckets {
+            self.evict(&mut buckets, now);
+        }
+        let bucket = buckets.entry(key).or_insert_with(|| Bucket::full(limit, now));
+        bucket.refill(now);
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            return Ok(());
+        }
+        let retry_after = Duration::from_secs_f64((1.0 - bucket.tokens) / limit.per_second);
+        Err(Error::rate_limit(
+            format!("Too many calls to '{}', retry in {:?}", method, retry_after),
+            Some(retry_after),
+        ))
+    }
+
+    /// The bucket of a call; methods with a limit of their own never share
+    /// buckets with other methods
+    fn bucket_key(&self, method: &str, context: &ServiceContext) -> String {
+        let mut parts: Vec<&str> = self.config.keys.iter()
+            .map(|key| match key {
+                RateLimitKey::ClientId => client_id(context),
+                RateLimitKey::RemoteAddr => remote_addr(context),
+                RateLimitKey::Method => method,
+            })
+            .collect();
+        if !self.config.keys.contains(&RateLimitKey::Method) && self.config.methods.contains_key(method) {
+            parts.push(method);
+        }
+        parts.join("\u{1f}")
+    }
+
+    /// Make room for a bucket, dropping buckets that have refilled and
+    /// then the least recently used one
+    fn evict(&self, buckets: &mut HashMap<String, Bucket>, now: Instant) {
+        buckets.retain(|_, bucket| bucket.tokens_at(now) < f64::from(bucket.limit.burst));
+        if buckets.len() >= self.config.max_buckets {
+            let oldest = buckets.iter()
+                .min_by_key(|(_, bucket)| bucket.updated)
+                .map(|(key, _)| key.clone());
+            if let Some(oldest) = oldest {
+                buckets.remove(&oldest);
+            }
+        }
+    }
+}
+
+impl std::fmt::Debug for RateLimiter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RateLimiter")
+            .field("config", &self.config)
+            .field("buckets", &self.buckets.lock().len())
+            .finish()
+    }
+}
+
+/// The client id of a call, falling back to the authenticated user
+fn client_id(context: &ServiceContext) -> &str {
+    context.client_info.as_ref()
+        .and_then(|client| client.client_id.as_deref())
+        .or_else(|| context.auth_context.as_ref().map(|auth| auth.user_id.as_str()))
+        .unwrap_or_default()
+}
+
+/// The address a call came from, as the client info or the server's
+/// `peer` metadata gives it
+fn remote_addr(context: &ServiceContext) -> &str {
+    context.client_info.as_ref()
+        .and_then(|client| client.remote_addr.as_deref())
+        .or_else(|| context.metadata.get("peer").and_then(Value::as_str))
+        .unwrap_or_default()
+}
+
+/// Layer rate limiting calls; see the [module docs](self)
+#[derive(Debug, Clone)]
+pub struct RateLimitLayer {
+    limiter: Arc<RateLimiter>,
+}
+
+impl RateLimitLayer {
+    /// Limit calls as `config` says
+    pub fn new(config: RateLimitConfig) -> Result<Self> {
+        Ok(Self {
+            limiter: Arc::new(RateLimiter::new(config)?),
+        })
+    }
+
+    /// The buckets shared by every handler the layer wraps
+    pub fn limiter(&self) -> &Arc<RateLimiter> {
+        &self.limiter
+    }
+}
+
+impl Layer for RateLimitLayer {
+    fn layer(&self, inner: Arc<dyn MethodHandler>) -> Arc<dyn MethodHandler> {
+        Arc::new(RateLimited {
+            inner,
+            limiter: Arc::clone(&self.limiter),
+        })
+    }
+}
+
+struct RateLimited {
+    inner: Arc<dyn MethodHandler>,
+    limiter: Arc<RateLimiter>,
+}
+
+#[async_trait]
+impl MethodHandler for RateLimited {
+    async fn handle_method(&self, request: &JsonRpcRequest, context: &ServiceContext) -> Result<JsonRpcResponse> {
+        self.limiter.check(&request.method, context)?;
+        self.inner.handle_method(request, context).await
+    }
+
+    fn supported_methods(&self) -> Vec<String> {
+        self.inner.supported_methods()
+    }
+
+    fn supports_method(&self, method: &str) -> bool {
+        self.inner.supports_method(method)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::convenience::JsonRpcServer;
+    use crate::transport::NegotiatedConfig;
+    use crate::convenience::ServerConfig;
+    use serde_json::json;
+
+    fn peer(addr: &str) -> ServiceContext {
+        ServiceContext::new("req-1").with_metadata("peer", json!(addr))
+    }
+
+    #[test]
+    fn test_buckets_by_key_and_method() {
+        let config = RateLimitConfig::new(RateLimit::new(1.0, 2))
+            .with_keys([RateLimitKey::RemoteAddr])
+            .with_method("expensive", RateLimit::new(0.5, 1));
+        let limiter = RateLimiter::new(config).unwrap();
+
+        assert!(limiter.check("ping", &peer("a")).is_ok());
+        assert!(limiter.check("echo", &peer("a")).is_ok());
+        let Err(Error::RateLimit { retry_after: Some(retry_after), .. }) = limiter.check("ping", &peer("a")) else {
+            panic!("third call should be limited");
+        };
+        assert!(retry_after > Duration::from_millis(500) && retry_after <= Duration::from_secs(1));
+
+        assert!(limiter.check("ping", &peer("b")).is_ok());
+        assert!(limiter.check("expensive", &peer("a")).is_ok());
+        assert!(limiter.check("expensive", &peer("a")).is_err());
+
+        assert!(RateLimiter::new(RateLimitConfig::new(RateLimit::new(0.0, 1))).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_server_applies_connection_rate_limit() {
+        let connection = NegotiatedConfig::default()
+            .with_rate_limit(RateLimitConfig::new(RateLimit::new(1.0, 1)).with_keys([]));
+        let server = JsonRpcServer::builder()
+            .method("ping", |_params, _context| async { Ok("pong") })
+            .config(ServerConfig::default().with_connection(connection))
+            .build()
+            .unwrap();
+        let request = JsonRpcRequest::with_id("ping", None, json!(1));
+
+        let response = server.router().dispatch(&request, &peer("a")).await.unwrap();
+        assert_eq!(response.result, Some(json!("pong")));
+
+        let response = server.router().dispatch(&request, &peer("b")).await.unwrap();
+        let error = response.error.unwrap();
+        assert_eq!(error.code, -32005);
+        assert!(error.data.unwrap()["retry_after_ms"].as_u64().unwrap() > 0);
+    }
+}
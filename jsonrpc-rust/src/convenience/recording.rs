@@ -0,0 +1,453 @@
+//! Recording and replay of calls
+//!
+//! A [`Recorder`] captures every call a server answers, through the
+//! [`RecordingLayer`] it hands out, as one JSON object per line: the
+//! request, the response, the time it was received and took to answer,
+//! and the context it ran in.
+//!
+//! ```rust,no_run
+//! use jsonrpc_rust::convenience::prelude::*;
+//! use jsonrpc_rust::convenience::recording::Recorder;
+//!
+//! # async fn example() -> jsonrpc_rust::Result<()> {
+//! let recorder = Recorder::to_file("calls.jsonl")?;
+//! let handle = JsonRpcServer::builder()
+//!     .bind("tcp://0.0.0.0:9000")
+//!     .method("ping", |_params, _context| async { Ok("pong") })
+//!     .layer(recorder.layer())
+//!     .serve()
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Recorded sessions are loaded with [`load`] and played back either way:
+//! [`replay_router`] and [`replay_client`] send the recorded requests to a
+//! service again and report the responses that changed, while a
+//! [`ReplayHandler`] stands in for the recorded service, answering as it
+//! did.
+//!
+//! Lines are written as calls complete, with blocking writes; record to a
+//! local file or to memory.
+
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::layer::Layer;
+use crate::core::error::{Error, Result};
+use crate::core::traits::MethodHandler;
+use crate::core::types::{ClientInfo, JsonRpcRequest, JsonRpcResponse, ServiceContext};
+use crate::protocol::{JsonRpcClient, MethodRouter};
+
+/// A call captured by a [`RecordingLayer`]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RecordedCall {
+    /// When the call was received
+    pub timestamp: DateTime<Utc>,
+    /// Time taken to answer, in microseconds
+    pub elapsed_us: u64,
+    /// The request as the handler saw it
+    pub request: JsonRpcRequest,
+    /// The answer, including failures as the error response sent for them
+    pub response: JsonRpcResponse,
+    /// The context the call ran in
+    pub context: RecordedContext,
+}
+
+/// The parts of a [`ServiceContext`] kept in a recording
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct RecordedContext {
+    /// Request identifier
+    pub request_id: String,
+    /// Client information
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_info: Option<ClientInfo>,
+    /// Authenticated user, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_id: Option<String>,
+    /// Request metadata
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub metadata: HashMap<String, Value>,
+}
+
+impl RecordedContext {
+    /// Keep the recordable parts of a context
+    pub fn from_context(context: &ServiceContext) -> Self {
+        Self {
+            request_id: context.request_id.clone(),
+            client_info: context.client_info.clone(),
+            user_id: context.auth_context.as_ref().map(|auth| auth.user_id.clone()),
+            metadata: context.metadata.clone(),
+        }
+    }
+
+    /// A context to run the call in again
+    ///
+    /// The authenticated user is not restored.
+    pub fn to_context(&self) -> ServiceContext {
+        let mut context = ServiceContext::new(self.request_id.clone());
+        context.client_info = self.client_info.clone();
+        context.metadata = self.metadata.clone();
+        context
+    }
+}
+
+/// Where recorded calls go
+enum Sink {
+    Memory(Vec<RecordedCall>),
+    Writer(Box<dyn Write + Send>),
+}
+
+/// Collects recorded calls, in memory or as JSON lines
+///
+/// Clones share the same destination.
+#[derive(Clone)]
+pub struct Recorder {
+    sink: Arc<Mutex<Sink>>,
+}
+
+impl Recorder {
+    /// Keep calls in memory, to read back with [`calls`](Self::calls)
+    pub fn in_memory() -> Self {
+        Self { sink: Arc::new(Mutex::new(Sink::Memory(Vec::new()))) }
+    }
+
+    /// Append calls to a JSON lines file, creating it if needed
+    pub fn to_file(path: impl AsRef<Path>) -> Result<Self> {
+        let file = std::fs::OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self::to_writer(std::io::BufWriter::new(file)))
+    }
+
+    /// Write calls as JSON lines
+    pub fn to_writer(writer: impl Write + Send + 'static) -> Self {
+        Self { sink: Arc::new(Mutex::new(Sink::Writer(Box::new(writer)))) }
+    }
+
+    /// The layer recording the calls of the handlers it wraps
+    pub fn layer(&self) -> RecordingLayer {
+        RecordingLayer { recorder: self.clone() }
+    }
+
+    /// Record a call
+    pub fn record(&self, call: &RecordedCall) -> Result<()> {
+        match *self.sink.lock() {
+            Sink::Memory(ref mut calls) => calls.push(call.clone()),
+            Sink::Writer(ref mut writer) => {
+                let mut line = serde_json::to_vec(call)?;
+                line.push(b'\n');
+                writer.write_all(&line)?;
+                writer.flush()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Calls recorded in memory so far; empty when writing elsewhere
+    pub fn calls(&self) -> Vec<RecordedCall> {
+        match *self.sink.lock() {
+            Sink::Memory(ref calls) => calls.clone(),
+            Sink::Writer(_) => Vec::new(),
+        }
+    }
+}
+
+impl std::fmt::Debug for Recorder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let sink = match *self.sink.lock() {
+            Sink::Memory(ref calls) => format!("memory ({} calls)", calls.len()),
+            Sink::Writer(_) => "writer".to_string(),
+        };
+        f.debug_struct("Recorder").field("sink", &sink).finish()
+    }
+}
+
+/// Read calls recorded as JSON lines
+pub fn read_calls(reader: impl BufRead) -> Result<Vec<RecordedCall>> {
+    let mut calls = Vec::new();
+    for line in reader.lines() {
+        let line = line?;
+        if !line.trim().is_empty() {
+            calls.push(serde_json::from_str(&line)?);
+        }
+    }
+    Ok(calls)
+}
+
+/// Load a recording made by [`Recorder::to_file`]
+pub fn load(path: impl AsRef<Path>) -> Result<Vec<RecordedCall>> {
+    read_calls(BufReader::new(std::fs::File::open(path)?))
+}
+
+/// Layer recording calls; see [`Recorder::layer`]
+#[derive(Debug, Clone)]
+pub struct RecordingLayer {
+    recorder: Recorder,
+}
+
+impl Layer for RecordingLayer {
+    fn layer(&self, inner: Arc<dyn MethodHandler>) -> Arc<dyn MethodHandler> {
+        Arc::new(Recorded { inner, recorder: self.recorder.clone() })
+    }
+}
+
+struct Recorded {
+    inner: Arc<dyn MethodHandler>,
+    recorder: Recorder,
+}
+
+#[async_trait]
+impl MethodHandler for Recorded {
+    async fn handle_method(&self, request: &JsonRpcRequest, context: &ServiceContext) -> Result<JsonRpcResponse> {
+        let timestamp = Utc::now();
+        let started = Instant::now();
+        let result = self.inner.handle_method(request, context).await;
+
+        let id = request.id.clone().unwrap_or(Value::Null);
+        let response = match result {
+            Ok(ref response) => response.clone(),
+            Err(ref e) => JsonRpcResponse::error(id, e.to_jsonrpc_error()),
+        };
+        let call = RecordedCall {
+            timestamp,
+            elapsed_us: started.elapsed().as_micros() as u64,
+            request: request.clone(),
+            response,
+            context: RecordedContext::from_context(context),
+        };
+        if let Err(e) = self.recorder.record(&call) {
+            tracing::warn!("Failed to record call to '{}': {}", request.method, e);
+        }
+        result
+    }
+
+    fn supported_methods(&self) -> Vec<String> {
+        self.inner.supported_methods()
+    }
+
+    fn supports_method(&self, method: &str) -> bool {
+        self.inner.supports_method(method)
+    }
+}
+
+/// Answers calls as a recording says the recorded service did
+///
+/// A call is answered with the first recorded call to the same method with
+/// the same params not used yet, so repeated calls get the answers in the
+/// order they were recorded; once all are used, the last one is repeated.
+pub struct ReplayHandler {
+    calls: Vec<RecordedCall>,
+    used: Mutex<Vec<bool>>,
+}
+
+impl ReplayHandler {
+    /// Answer from these recorded calls
+    pub fn new(calls: Vec<RecordedCall>) -> Self {
+        let used = Mutex::new(vec![false; calls.len()]);
+        Self { calls, used }
+    }
+}
+
+#[async_trait]
+impl MethodHandler for ReplayHandler {
+    async fn handle_method(&self, request: &JsonRpcRequest, _context: &ServiceContext) -> Result<JsonRpcResponse> {
+        let matching = |call: &&RecordedCall| {
+            call.request.method == request.method && call.request.params == request.params
+        };
+        let index = {
+            let mut used = self.used.lock();
+            let index = self.calls.iter().enumerate()
+                .filter(|(_, call)| matching(call))
+                .find(|(index, _)| !used[*index])
+                .or_else(|| self.calls.iter().enumerate().rfind(|(_, call)| matching(call)))
+                .map(|(index, _)| index);
+            if let Some(index) = index {
+                used[index] = true;
+            }
+            index
+        };
+
+        let Some(index) = index else {
+            return Err(Error::resource_not_found(format!("recorded call to '{}' with these params", request.method)));
+        };
+        let mut response = self.calls[index].response.clone();
+        response.id = request.id.clone().unwrap_or(Value::Null);
+        Ok(response)
+    }
+
+    fn supported_methods(&self) -> Vec<String> {
+        let mut methods: Vec<_> = self.calls.iter().map(|call| call.request.method.clone()).collect();
+        methods.sort();
+        methods.dedup();
+        methods
+    }
+}
+
+impl std::fmt::Debug for ReplayHandler {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ReplayHandler")
+            .field("calls", &self.calls.len())
+            .finish()
+    }
+}
+
+/// A replayed call whose answer differs from the recorded one
+#[derive(Debug, Clone)]
+pub struct ReplayMismatch {
+    /// The recorded call
+    pub call: RecordedCall,
+    /// The answer it got this time, or why it got none
+    pub response: std::result::Result<JsonRpcResponse, String>,
+}
+
+/// Outcome of replaying a recording against a service
+#[derive(Debug, Clone, Default)]
+pub struct ReplayReport {
+    /// Calls sent again
+    pub replayed: usize,
+    /// Calls answered differently
+    pub mismatches: Vec<ReplayMismatch>,
+}
+
+impl ReplayReport {
+    /// Whether every call was answered as recorded
+    pub fn is_clean(&self) -> bool {
+        self.mismatches.is_empty()
+    }
+
+    fn check(&mut self, call: &RecordedCall, response: Result<JsonRpcResponse>) {
+        self.replayed += 1;
+        let same = response.as_ref().is_ok_and(|response| same_answer(&call.response, response));
+        if !same {
+            self.mismatches.push(ReplayMismatch {
+                call: call.clone(),
+                response: response.map_err(|e| e.to_string()),
+            });
+        }
+    }
+}
+
+/// Results are compared whole; errors by code, as their messages and data
+/// carry ids and timings
+fn same_answer(recorded: &JsonRpcResponse, replayed: &JsonRpcResponse) -> bool {
+    match (&recorded.error, &replayed.error) {
+        (Some(recorded), Some(replayed)) => recorded.code == replayed.code,
+        (None, None) => recorded.result == replayed.result,
+        _ => false,
+    }
+}
+
+/// Dispatch the recorded requests to a router again, in order and in
+/// their recorded context
+///
+/// Notifications are dispatched but not compared.
+pub async fn replay_router(calls: &[RecordedCall], router: &MethodRouter) -> ReplayReport {
+    let mut report = ReplayReport::default();
+    for call in calls {
+        let response = router.dispatch(&call.request, &call.context.to_context()).await;
+        if call.request.id.is_some() {
+            let response = response.ok_or_else(|| Error::service("No response to a request"));
+            report.check(call, response);
+        }
+    }
+    report
+}
+
+/// Send the recorded requests to a service again through a client, in
+/// order, waiting up to `timeout` for each answer
+///
+/// Notifications are sent but not compared.
+pub async fn replay_client(calls: &[RecordedCall], client: &JsonRpcClient, timeout: Duration) -> ReplayReport {
+    let mut report = ReplayReport::default();
+    for call in calls {
+        if call.request.id.is_none() {
+            if let Err(e) = client.notify(&call.request.method, call.request.params.clone()).await {
+                tracing::warn!("Failed to replay notification '{}': {}", call.request.method, e);
+            }
+            continue;
+        }
+        let response = client.request(call.request.clone(), timeout).await;
+        report.check(call, response);
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::convenience::server::JsonRpcServer;
+    use serde_json::json;
+
+    /// Collects what a writer recorder writes
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Buffer {
+        fn write(&mut self, data: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().extend_from_slice(data);
+            Ok(data.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn server(recorder: &Recorder, offset: i64) -> JsonRpcServer {
+        JsonRpcServer::builder()
+            .method("add", move |params, _context| async move {
+                let numbers: Vec<i64> = serde_json::from_value(params.unwrap_or_default())?;
+                Ok(numbers.iter().sum::<i64>() + offset)
+            })
+            .layer(recorder.layer())
+            .build()
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_record_and_replay() {
+        let buffer = Buffer::default();
+        let recorder = Recorder::to_writer(buffer.clone());
+        let recorded = server(&recorder, 0);
+
+        let context = ServiceContext::new("req-1").with_metadata("peer", json!("10.0.0.1"));
+        for request in [
+            JsonRpcRequest::with_id("add", Some(json!([1, 2])), json!(1)),
+            JsonRpcRequest::with_id("add", Some(json!("nope")), json!(2)),
+        ] {
+            recorded.router().dispatch(&request, &context).await.unwrap();
+        }
+
+        let calls = read_calls(buffer.0.lock().as_slice()).unwrap();
+        assert_eq!(calls.len(), 2);
+        assert_eq!(calls[0].response.result, Some(json!(3)));
+        assert_eq!(calls[0].context.metadata["peer"], json!("10.0.0.1"));
+        assert_eq!(calls[1].response.error.as_ref().unwrap().code, -32700);
+
+        // The same service answers the same; a changed one does not
+        let report = replay_router(&calls, recorded.router()).await;
+        assert_eq!(report.replayed, 2);
+        assert!(report.is_clean());
+
+        let changed = server(&Recorder::in_memory(), 1);
+        let report = replay_router(&calls, changed.router()).await;
+        assert_eq!(report.mismatches.len(), 1);
+        assert_eq!(report.mismatches[0].response.as_ref().unwrap().result, Some(json!(4)));
+
+        // A replay handler stands in for the recorded service
+        let mut router = MethodRouter::new();
+        router.register_handler(Arc::new(ReplayHandler::new(calls.clone()))).unwrap();
+        assert!(replay_router(&calls, &router).await.is_clean());
+        let unknown = JsonRpcRequest::with_id("add", Some(json!([5])), json!(3));
+        let response = router.dispatch(&unknown, &context).await.unwrap();
+        assert!(response.is_error());
+    }
+}
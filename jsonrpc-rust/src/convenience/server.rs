@@ -0,0 +1,893 @@
+//! JSON-RPC server runtime
+//!
+//! [`JsonRpcServer`] puts the transport, the [`MethodRouter`] and the
+//! connection lifecycle together:
+//!
+//! ```rust,no_run
+//! use jsonrpc_rust::convenience::prelude::*;
+//!
+//! # async fn example() -> jsonrpc_rust::Result<()> {
+//! let handle = JsonRpcServer::builder()
+//!     .bind("tcp://0.0.0.0:9000")
+//!     .method("ping", |_params, _context| async { Ok("pong") })
+//!     .layer(logging())
+//!     .serve()
+//!     .await?;
+//!
+//! // Stop accepting, let calls in flight finish, then close connections
+//! handle.shutdown().await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! `tcp://` endpoints speak [`NegotiatedTransport`] framing, so clients
+//! connect with [`NegotiatedTransport::connect`]. With the `stdio`
+//! feature, `stdio://` serves a single connection over standard input and
+//! output. Requests on a connection are dispatched concurrently and
+//! answered as they complete.
+//!
+//! Each connection has a [`Session`](crate::core::session::Session) for
+//! per-connection state; see [`session_hooks`](ServerBuilder::session_hooks)
+//! and [`session_resumption`](ServerBuilder::session_resumption).
+
+use std::future::Future;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde::Serialize;
+use serde_json::Value;
+use tokio::sync::watch;
+use tokio::task::{JoinHandle, JoinSet};
+use tracing::Instrument;
+use url::Url;
+use uuid::Uuid;
+
+use super::auth::AuthLayer;
+use super::concurrency::{ConcurrencyConfig, ConcurrencyLayer, ConcurrencyLimiter};
+use super::gateway::Discovery;
+use super::handler::{FnHandler, RpcMethod};
+use super::layer::Layer;
+#[cfg(feature = "prometheus")]
+use super::metrics::Metrics;
+use super::rate_limit::RateLimitLayer;
+#[cfg(feature = "validation")]
+use super::validation::{ParamsValidation, ParamsValidator, ValidatedHandler};
+use crate::core::error::{Error, Result};
+use crate::core::session::{SessionHooks, SessionManager, SessionMethods};
+use crate::core::traits::{MethodHandler, Transport};
+use crate::core::types::{MethodInfo, ServiceContext, ServiceInfo};
+use crate::protocol::{Compatibility, JsonRpcPeer, MethodRouter};
+use crate::transport::{NegotiatedConfig, RateLimitConfig, TransportConfig};
+#[cfg(feature = "tcp")]
+use crate::transport::NegotiatedTransport;
+
+/// Server configuration
+#[derive(Debug, Clone)]
+pub struct ServerConfig {
+    /// Configuration of accepted connections; `max_connections` caps the
+    /// connections open at once on each endpoint
+    pub connection: NegotiatedConfig,
+    /// How long shutdown waits for calls in flight before dropping them
+    pub shutdown_timeout: Duration,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            connection: NegotiatedConfig::default(),
+            shutdown_timeout: Duration::from_secs(30),
+        }
+    }
+}
+
+impl ServerConfig {
+    /// Set the configuration of accepted connections
+    pub fn with_connection(mut self, connection: NegotiatedConfig) -> Self {
+        self.connection = connection;
+        self
+    }
+
+    /// Set how long shutdown waits for calls in flight
+    pub fn with_shutdown_timeout(mut self, shutdown_timeout: Duration) -> Self {
+        self.shutdown_timeout = shutdown_timeout;
+        self
+    }
+
+    /// Validate the configuration
+    pub fn validate(&self) -> Result<()> {
+        self.connection.validate()?;
+        if self.connection.connection_limits.max_connections == 0 {
+            return Err(Error::configuration("Max connections cannot be zero"));
+        }
+        Ok(())
+    }
+}
+
+/// Assembles a [`JsonRpcServer`]
+///
+/// Configuration errors, such as a bad endpoint or a method registered
+/// twice, are reported by [`build`](Self::build) and
+/// [`serve`](Self::serve).
+#[derive(Default)]
+pub struct ServerBuilder {
+    endpoints: Vec<String>,
+    handlers: Vec<(Option<String>, Arc<dyn MethodHandler>)>,
+    method_info: Vec<MethodInfo>,
+    layers: Vec<Arc<dyn Layer>>,
+    auth: Option<AuthLayer>,
+    rate_limit: Option<RateLimitConfig>,
+    concurrency: Option<ConcurrencyConfig>,
+    sessions: SessionManager,
+    compatibility: Compatibility,
+    /// Name and version to describe the server under
+    discovery: Option<(String, String)>,
+    #[cfg(feature = "prometheus")]
+    metrics: Option<Metrics>,
+    #[cfg(feature = "validation")]
+    validation: ParamsValidation,
+    config: ServerConfig,
+}
+
+impl ServerBuilder {
+    /// Create an empty builder
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Listen on an endpoint such as `tcp://0.0.0.0:9000`
+    pub fn bind(mut self, endpoint: impl Into<String>) -> Self {
+        self.endpoints.push(endpoint.into());
+        self
+    }
+
+    /// Serve a method with an async closure
+    pub fn method<F, Fut, R>(self, method: impl Into<String>, handler: F) -> Self
+    where
+        F: Fn(Option<Value>, ServiceContext) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<R>> + Send + 'static,
+        R: Serialize + 'static,
+    {
+        let method = method.into();
+        let handler = FnHandler::new(method.clone(), handler);
+        self.route(method, Arc::new(handler))
+    }
+
+    /// Serve a method or namespace pattern (`math.*`) with a handler
+    pub fn route(mut self, method: impl Into<String>, handler: Arc<dyn MethodHandler>) -> Self {
+        self.handlers.push((Some(method.into()), handler));
+        self
+    }
+
+    /// Serve every method in a handler's `supported_methods`
+    pub fn handler(mut self, handler: Arc<dyn MethodHandler>) -> Self {
+        self.handlers.push((None, handler));
+        self
+    }
+
+    /// Serve a self-describing handler, such as one generated by
+    /// `#[rpc_method]`, and keep its description
+    pub fn rpc_method(mut self, handler: impl RpcMethod + 'static) -> Self {
+        self.method_info.push(handler.method_info());
+        self.handler(Arc::new(handler))
+    }
+
+    /// Validate the params of every method with a schema before calling
+    /// its handler; see [`validation`](super::validation)
+    #[cfg(feature = "validation")]
+    pub fn validate_params(mut self, enabled: bool) -> Self {
+        self.validation.enabled = enabled;
+        self
+    }
+
+    /// Turn params validation on or off for one method, whatever
+    /// [`validate_params`](Self::validate_params) says
+    #[cfg(feature = "validation")]
+    pub fn validate_method_params(mut self, method: impl Into<String>, enabled: bool) -> Self {
+        self.validation.methods.insert(method.into(), enabled);
+        self
+    }
+
+    /// Declare the params schema of a method, replacing the one its
+    /// handler describes
+    #[cfg(feature = "validation")]
+    pub fn params_schema(mut self, method: impl Into<String>, schema: Value) -> Self {
+        self.validation.schemas.insert(method.into(), schema);
+        self
+    }
+
+    /// Wrap every handler in a layer; the first layer added is outermost
+    pub fn layer(mut self, layer: impl Layer + 'static) -> Self {
+        self.layers.push(Arc::new(layer));
+        self
+    }
+
+    /// Authenticate calls before any other layer sees them, also
+    /// enforcing the permissions `rpc_method` handlers describe
+    pub fn auth(mut self, auth: AuthLayer) -> Self {
+        self.auth = Some(auth);
+        self
+    }
+
+    /// Rate limit calls, after authentication and before other layers,
+    /// instead of by the limits of the connection configuration
+    pub fn rate_limit(mut self, rate_limit: RateLimitConfig) -> Self {
+        self.rate_limit = Some(rate_limit);
+        self
+    }
+
+    /// Limit the calls of each method answered at once, after rate
+    /// limiting and before other layers; see [`concurrency`](super::concurrency)
+    pub fn concurrency(mut self, concurrency: ConcurrencyConfig) -> Self {
+        self.concurrency = Some(concurrency);
+        self
+    }
+
+    /// Run `hooks` when connections open and close; a hook failing on
+    /// connect closes the connection
+    pub fn session_hooks(mut self, hooks: impl SessionHooks + 'static) -> Self {
+        self.sessions = self.sessions.with_hooks(Arc::new(hooks));
+        self
+    }
+
+    /// Keep the sessions of closed connections for `ttl`, and serve
+    /// [`SESSION_METHOD`](crate::core::session::SESSION_METHOD) and
+    /// [`RESUME_SESSION_METHOD`](crate::core::session::RESUME_SESSION_METHOD)
+    /// so clients can take theirs back after reconnecting
+    pub fn session_resumption(mut self, ttl: Duration) -> Self {
+        self.sessions = self.sessions.with_resumption(ttl);
+        self
+    }
+
+    /// Also answer JSON-RPC 1.0 requests with
+    /// [`Compatibility::Legacy`]; strict by default
+    pub fn compatibility(mut self, compatibility: Compatibility) -> Self {
+        self.compatibility = compatibility;
+        self
+    }
+
+    /// Answer [`DISCOVER_METHOD`](super::gateway::DISCOVER_METHOD) with
+    /// the methods added with [`rpc_method`](Self::rpc_method), so a
+    /// [`Gateway`](super::gateway::Gateway) can describe them
+    pub fn discoverable(mut self, name: impl Into<String>, version: impl Into<String>) -> Self {
+        self.discovery = Some((name.into(), version.into()));
+        self
+    }
+
+    /// Record request and connection metrics, counting calls refused by
+    /// authentication and rate limiting too
+    #[cfg(feature = "prometheus")]
+    pub fn metrics(mut self, metrics: Metrics) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Set the server configuration
+    pub fn config(mut self, config: ServerConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Set how long shutdown waits for calls in flight
+    pub fn shutdown_timeout(mut self, shutdown_timeout: Duration) -> Self {
+        self.config.shutdown_timeout = shutdown_timeout;
+        self
+    }
+
+    /// Check the configuration and register the handlers
+    pub fn build(self) -> Result<JsonRpcServer> {
+        self.config.validate()?;
+        let endpoints = self.endpoints.iter()
+            .map(|endpoint| Endpoint::parse(endpoint))
+            .collect::<Result<Vec<_>>>()?;
+
+        #[cfg(feature = "validation")]
+        let validator = {
+            let described = self.method_info.iter()
+                .filter_map(|info| Some((info.name.as_str(), info.params_schema.as_ref()?)));
+            Arc::new(ParamsValidator::from_validation(&self.validation, described)?)
+        };
+
+        let auth = self.auth.map(|auth| auth.with_method_info(&self.method_info));
+        let rate_limit = self.rate_limit
+            .or_else(|| self.config.connection.rate_limit.clone())
+            .map(RateLimitLayer::new)
+            .transpose()?;
+        let concurrency = match self.concurrency {
+            Some(concurrency) => {
+                let limiter = ConcurrencyLimiter::new(concurrency)?;
+                #[cfg(feature = "prometheus")]
+                let limiter = match self.metrics {
+                    Some(ref metrics) => limiter.with_metrics(metrics.clone()),
+                    None => limiter,
+                };
+                Some(ConcurrencyLayer::from_limiter(limiter))
+            }
+            None => None,
+        };
+
+        let sessions = Arc::new(self.sessions);
+        let mut router = MethodRouter::new().with_compatibility(self.compatibility);
+        if sessions.is_resumable() {
+            // Resuming comes before anything a layer would check, like
+            // the authentication the session carries
+            router.register_handler(Arc::new(SessionMethods::new(Arc::clone(&sessions))))?;
+        }
+        if let Some((name, version)) = self.discovery {
+            router.register_handler(Arc::new(Discovery::new(ServiceInfo {
+                name,
+                version,
+                description: String::new(),
+                methods: self.method_info.clone(),
+                health_endpoint: None,
+                metadata: Default::default(),
+            })))?;
+        }
+        for (method, handler) in self.handlers {
+            // Params are checked after every layer has seen the call
+            #[cfg(feature = "validation")]
+            let handler: Arc<dyn MethodHandler> = match validator.is_empty() {
+                true => handler,
+                false => Arc::new(ValidatedHandler::new(handler, validator.clone())),
+            };
+            let handler = self.layers.iter()
+                .rev()
+                .fold(handler, |handler, layer| layer.layer(handler));
+            let handler = match &concurrency {
+                Some(concurrency) => concurrency.layer(handler),
+                None => handler,
+            };
+            let handler = match &rate_limit {
+                Some(rate_limit) => rate_limit.layer(handler),
+                None => handler,
+            };
+            let handler = match &auth {
+                Some(auth) => auth.layer(handler),
+                None => handler,
+            };
+            #[cfg(feature = "prometheus")]
+            let handler = match &self.metrics {
+                Some(metrics) => metrics.layer(handler),
+                None => handler,
+            };
+            match method {
+                Some(method) => router.register(method, handler)?,
+                None => router.register_handler(handler)?,
+            }
+        }
+
+        Ok(JsonRpcServer {
+            shared: Arc::new(Shared {
+                router,
+                config: self.config,
+                connections: AtomicUsize::new(0),
+                sessions,
+                #[cfg(feature = "prometheus")]
+                metrics: self.metrics,
+            }),
+            endpoints,
+            method_info: self.method_info,
+        })
+    }
+
+    /// Build the server and start serving its endpoints
+    pub async fn serve(self) -> Result<ServerHandle> {
+        self.build()?.serve().await
+    }
+}
+
+impl std::fmt::Debug for ServerBuilder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ServerBuilder")
+            .field("endpoints", &self.endpoints)
+            .field("handlers", &self.handlers.len())
+            .field("layers", &self.layers.len())
+            .field("auth", &self.auth)
+            .field("rate_limit", &self.rate_limit)
+            .field("concurrency", &self.concurrency)
+            .field("sessions", &self.sessions)
+            .field("compatibility", &self.compatibility)
+            .field("discovery", &self.discovery)
+            .field("config", &self.config)
+            .finish()
+    }
+}
+
+/// An endpoint the server listens on
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Endpoint {
+    #[cfg(feature = "tcp")]
+    Tcp(String),
+    #[cfg(feature = "stdio")]
+    Stdio,
+}
+
+impl Endpoint {
+    fn parse(endpoint: &str) -> Result<Self> {
+        let url = Url::parse(endpoint)
+            .map_err(|e| Error::configuration(format!("Invalid endpoint {}: {}", endpoint, e)))?;
+
+        match url.scheme() {
+            #[cfg(feature = "tcp")]
+            "tcp" => {
+                let host = url.host_str()
+                    .ok_or_else(|| Error::configuration(format!("Endpoint {} has no host", endpoint)))?;
+                let port = url.port()
+                    .ok_or_else(|| Error::configuration(format!("Endpoint {} has no port", endpoint)))?;
+                Ok(Self::Tcp(format!("{}:{}", host, port)))
+            }
+            #[cfg(feature = "stdio")]
+            "stdio" => Ok(Self::Stdio),
+            scheme => Err(Error::configuration(format!(
+                "Unsupported endpoint scheme '{}' in {}", scheme, endpoint
+            ))),
+        }
+    }
+}
+
+/// State shared by every connection of a server
+struct Shared {
+    router: MethodRouter,
+    config: ServerConfig,
+    connections: AtomicUsize,
+    sessions: Arc<SessionManager>,
+    #[cfg(feature = "prometheus")]
+    metrics: Option<Metrics>,
+}
+
+impl Shared {
+    /// Record an event in the metrics, if the server keeps any
+    #[cfg_attr(not(feature = "prometheus"), allow(unused_variables))]
+    fn record(&self, event: ServerEvent) {
+        #[cfg(feature = "prometheus")]
+        if let Some(metrics) = &self.metrics {
+            match event {
+                ServerEvent::Received(bytes) => metrics.record_received(bytes),
+                ServerEvent::Sent(bytes) => metrics.record_sent(bytes),
+                ServerEvent::Opened => metrics.connection_opened(),
+                ServerEvent::Closed => metrics.connection_closed(),
+                ServerEvent::Refused => metrics.connection_refused(),
+            }
+        }
+    }
+}
+
+/// Something worth counting on a connection
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(not(feature = "prometheus"), allow(dead_code))]
+enum ServerEvent {
+    Received(usize),
+    Sent(usize),
+    Opened,
+    Closed,
+    Refused,
+}
+
+/// A JSON-RPC server ready to serve its endpoints
+pub struct JsonRpcServer {
+    shared: Arc<Shared>,
+    endpoints: Vec<Endpoint>,
+    method_info: Vec<MethodInfo>,
+}
+
+impl JsonRpcServer {
+    /// Start assembling a server
+    pub fn builder() -> ServerBuilder {
+        ServerBuilder::new()
+    }
+
+    /// The router answering requests
+    pub fn router(&self) -> &MethodRouter {
+        &self.shared.router
+    }
+
+    /// Server configuration
+    pub fn config(&self) -> &ServerConfig {
+        &self.shared.config
+    }
+
+    /// Descriptions of the methods added with
+    /// [`rpc_method`](ServerBuilder::rpc_method), in the order they were added
+    pub fn method_info(&self) -> &[MethodInfo] {
+        &self.method_info
+    }
+
+    /// Start serving every endpoint
+    ///
+    /// Fails without serving anything if an endpoint cannot be bound.
+    #[cfg_attr(not(feature = "tcp"), allow(unused_mut, unused_variables))]
+    pub async fn serve(self) -> Result<ServerHandle> {
+        if self.endpoints.is_empty() {
+            return Err(Error::configuration("No endpoint to serve; add one with bind()"));
+        }
+
+        let (shutdown, signal) = watch::channel(false);
+        let mut local_addrs = Vec::new();
+        let mut tasks = Vec::new();
+
+        // Bind every endpoint before serving any, so a failure leaves
+        // nothing running
+        #[cfg(feature = "tcp")]
+        let mut listeners = Vec::new();
+        for endpoint in &self.endpoints {
+            match *endpoint {
+                #[cfg(feature = "tcp")]
+                Endpoint::Tcp(ref address) => {
+                    let listener = tokio::net::TcpListener::bind(address.as_str()).await
+                        .map_err(|e| Error::transport(format!("Failed to bind to {}: {}", address, e)))?;
+                    local_addrs.push(listener.local_addr()?);
+                    listeners.push(listener);
+                }
+                #[cfg(feature = "stdio")]
+                Endpoint::Stdio => {}
+            }
+        }
+
+        #[cfg(feature = "tcp")]
+        for listener in listeners {
+            if let Ok(local_addr) = listener.local_addr() {
+                tracing::info!("JSON-RPC server listening on tcp://{}", local_addr);
+            }
+            tasks.push(tokio::spawn(run_listener(listener, Arc::clone(&self.shared), signal.clone())));
+        }
+        #[cfg(feature = "stdio")]
+        if self.endpoints.contains(&Endpoint::Stdio) {
+            let transport = crate::transport::StdioTransport::new(Default::default())?;
+            let shared = Arc::clone(&self.shared);
+            let signal = signal.clone();
+            tasks.push(tokio::spawn(async move {
+                serve_connection(transport, "stdio".into(), shared, signal).await;
+            }));
+        }
+
+        Ok(ServerHandle {
+            local_addrs,
+            shared: self.shared,
+            shutdown,
+            tasks,
+        })
+    }
+
+    /// Answer the requests arriving on a single transport until it closes
+    pub async fn serve_transport(&self, transport: impl Transport + 'static) {
+        // Never signalled: the connection ends with the transport
+        let (_shutdown, signal) = watch::channel(false);
+        serve_connection(transport, Value::Null, Arc::clone(&self.shared), signal).await;
+    }
+
+    /// Answer the requests arriving on a single transport with this
+    /// server's methods while calling the other side over it
+    pub fn peer(&self, transport: impl Transport + 'static) -> JsonRpcPeer {
+        JsonRpcPeer::new(transport, self.shared.router.clone())
+    }
+}
+
+impl std::fmt::Debug for JsonRpcServer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("JsonRpcServer")
+            .field("endpoints", &self.endpoints)
+            .field("router", &self.shared.router)
+            .finish()
+    }
+}
+
+/// Controls a running server
+///
+/// Dropping the handle leaves the server running in the background.
+pub struct ServerHandle {
+    local_addrs: Vec<std::net::SocketAddr>,
+    shared: Arc<Shared>,
+    shutdown: watch::Sender<bool>,
+    tasks: Vec<JoinHandle<()>>,
+}
+
+impl ServerHandle {
+    /// Addresses the TCP endpoints are bound to, in `bind` order
+    pub fn local_addrs(&self) -> &[std::net::SocketAddr] {
+        &self.local_addrs
+    }
+
+    /// Number of connections currently open
+    pub fn active_connections(&self) -> usize {
+        self.shared.connections.load(Ordering::SeqCst)
+    }
+
+    /// Stop gracefully
+    ///
+    /// Stops accepting connections and reading requests, waits up to the
+    /// shutdown timeout for calls in flight to be answered, then closes
+    /// every connection.
+    pub async fn shutdown(self) -> Result<()> {
+        let _ = self.shutdown.send(true);
+        for task in self.tasks {
+            if let Err(e) = task.await {
+                if e.is_panic() {
+                    return Err(Error::service(format!("Server task panicked: {}", e)));
+                }
+            }
+        }
+        tracing::info!("JSON-RPC server stopped");
+        Ok(())
+    }
+
+    /// Shut down gracefully once `signal` completes, for example on Ctrl-C
+    pub async fn shutdown_on(self, signal: impl Future) -> Result<()> {
+        signal.await;
+        self.shutdown().await
+    }
+}
+
+impl std::fmt::Debug for ServerHandle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ServerHandle")
+            .field("local_addrs", &self.local_addrs)
+            .field("active_connections", &self.active_connections())
+            .finish()
+    }
+}
+
+/// Resolve once shutdown is requested; never if the handle is gone
+async fn signalled(signal: &mut watch::Receiver<bool>) {
+    if signal.wait_for(|stop| *stop).await.is_err() {
+        std::future::pending::<()>().await;
+    }
+}
+
+/// Accept connections until shutdown, then wait for them to finish
+#[cfg(feature = "tcp")]
+async fn run_listener(listener: tokio::net::TcpListener, shared: Arc<Shared>, mut signal: watch::Receiver<bool>) {
+    let max_connections = shared.config.connection.connection_limits.max_connections;
+    let mut connections = JoinSet::new();
+
+    loop {
+        tokio::select! {
+            _ = signalled(&mut signal) => break,
+            Some(_) = connections.join_next(), if !connections.is_empty() => {}
+            accepted = listener.accept() => {
+                let (stream, peer) = match accepted {
+                    Ok(accepted) => accepted,
+                    Err(e) => {
+                        tracing::warn!("Failed to accept connection: {}", e);
+                        tokio::time::sleep(Duration::from_millis(50)).await;
+                        continue;
+                    }
+                };
+                if connections.len() >= max_connections {
+                    tracing::warn!("Refusing connection from {}: {} connections open", peer, max_connections);
+                    shared.record(ServerEvent::Refused);
+                    continue;
+                }
+                let _ = stream.set_nodelay(true);
+
+                let shared = Arc::clone(&shared);
+                let signal = signal.clone();
+                connections.spawn(async move {
+                    let config = shared.config.connection.clone();
+                    match NegotiatedTransport::accept_with(stream, config).await {
+                        Ok(transport) => serve_connection(transport, peer.to_string().into(), shared, signal).await,
+                        Err(e) => tracing::debug!("Handshake with {} failed: {}", peer, e),
+                    }
+                });
+            }
+        }
+    }
+    drop(listener);
+
+    let timeout = shared.config.shutdown_timeout;
+    let drained = tokio::time::timeout(timeout, async {
+        while connections.join_next().await.is_some() {}
+    });
+    if drained.await.is_err() {
+        tracing::warn!("Dropping {} connections still busy after {:?}", connections.len(), timeout);
+        connections.shutdown().await;
+    }
+}
+
+/// Answer the requests of one connection
+///
+/// Requests are dispatched concurrently. Once shutdown is requested no more
+/// requests are read; the calls in flight are still answered.
+async fn serve_connection(
+    mut transport: impl Transport,
+    peer: Value,
+    shared: Arc<Shared>,
+    mut signal: watch::Receiver<bool>,
+) {
+    let session = match shared.sessions.open(peer.clone()).await {
+        Ok(session) => session,
+        Err(e) => {
+            tracing::debug!("Refusing connection from {}: {}", peer, e);
+            shared.record(ServerEvent::Refused);
+            let _ = transport.close().await;
+            return;
+        }
+    };
+    shared.connections.fetch_add(1, Ordering::SeqCst);
+    shared.record(ServerEvent::Opened);
+    let mut calls = JoinSet::new();
+
+    loop {
+        tokio::select! {
+            biased;
+            _ = signalled(&mut signal) => break,
+            Some(answered) = calls.join_next(), if !calls.is_empty() => {
+                if !send_answer(&mut transport, &shared, answered).await {
+                    break;
+                }
+            }
+            received = transport.receive() => {
+                let message = match received {
+                    Ok(message) => message,
+                    Err(e) => {
+                        tracing::debug!("Connection from {} ended: {}", peer, e);
+                        break;
+                    }
+                };
+                shared.record(ServerEvent::Received(message.len()));
+                let shared = Arc::clone(&shared);
+                let context = ServiceContext::new(Uuid::new_v4().to_string())
+                    .with_metadata("peer", peer.clone())
+                    .with_session(session.clone());
+                let span = tracing::debug_span!("jsonrpc.receive", peer = %peer, bytes = message.len());
+                calls.spawn(async move { shared.router.dispatch_message(&message, &context).await }.instrument(span));
+            }
+        }
+    }
+
+    while let Some(answered) = calls.join_next().await {
+        if !send_answer(&mut transport, &shared, answered).await {
+            break;
+        }
+    }
+    let _ = transport.close().await;
+    shared.sessions.close(&session).await;
+    shared.connections.fetch_sub(1, Ordering::SeqCst);
+    shared.record(ServerEvent::Closed);
+}
+
+/// Send the answer of a finished call, if any; false once the connection
+/// is unusable
+async fn send_answer(
+    transport: &mut impl Transport,
+    shared: &Shared,
+    answered: std::result::Result<Option<String>, tokio::task::JoinError>,
+) -> bool {
+    match answered {
+        Ok(Some(response)) => match transport.send(&response)
+            .instrument(tracing::debug_span!("jsonrpc.send", bytes = response.len()))
+            .await
+        {
+            Ok(()) => {
+                shared.record(ServerEvent::Sent(response.len()));
+                true
+            }
+            Err(e) => {
+                tracing::debug!("Failed to send response: {}", e);
+                false
+            }
+        },
+        Ok(None) => true,
+        Err(e) => {
+            tracing::error!("Request task failed: {}", e);
+            true
+        }
+    }
+}
+
+#[cfg(all(test, feature = "tcp"))]
+mod tests {
+    use super::*;
+    use crate::convenience::layer::logging;
+    use crate::core::serialization::SerializationFormat;
+    use crate::protocol::JsonRpcClient;
+    use serde_json::json;
+
+    async fn connect(handle: &ServerHandle) -> JsonRpcClient {
+        let stream = tokio::net::TcpStream::connect(handle.local_addrs()[0]).await.unwrap();
+        let transport = NegotiatedTransport::connect(stream, &[SerializationFormat::Json]).await.unwrap();
+        JsonRpcClient::new(transport)
+    }
+
+    #[tokio::test]
+    async fn test_serve_and_shutdown() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counted = Arc::clone(&calls);
+        let handle = JsonRpcServer::builder()
+            .bind("tcp://127.0.0.1:0")
+            .method("ping", |_params, _context| async { Ok("pong") })
+            .method("slow", |params, _context| async move {
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                Ok(params)
+            })
+            .layer(logging())
+            .layer(move |inner: Arc<dyn MethodHandler>| {
+                counted.fetch_add(1, Ordering::SeqCst);
+                inner
+            })
+            .serve()
+            .await
+            .unwrap();
+        // The layer wrapped both methods
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+        let client = Arc::new(connect(&handle).await);
+        assert_eq!(client.call::<_, String>("ping", ()).await.unwrap(), "pong");
+        assert!(matches!(client.call::<_, Value>("missing", ()).await, Err(Error::JsonRpc(_))));
+        assert_eq!(handle.active_connections(), 1);
+
+        // A call in flight when shutdown starts is still answered
+        let slow = tokio::spawn({
+            let client = Arc::clone(&client);
+            async move { client.call::<_, Value>("slow", json!([1])).await }
+        });
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        let addr = handle.local_addrs()[0];
+        handle.shutdown().await.unwrap();
+        assert_eq!(slow.await.unwrap().unwrap(), json!([1]));
+
+        assert!(tokio::net::TcpStream::connect(addr).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_sessions_keep_state_across_reconnects() {
+        use crate::transport::InProcTransport;
+
+        struct Calls(AtomicUsize);
+
+        let server = Arc::new(JsonRpcServer::builder()
+            .method("count", |_params, context| async move {
+                let session = context.session.expect("calls on a connection have a session");
+                let calls = session.get_or_insert_with(|| Calls(AtomicUsize::new(0)));
+                Ok(calls.0.fetch_add(1, Ordering::SeqCst) + 1)
+            })
+            .session_resumption(Duration::from_secs(60))
+            .build()
+            .unwrap());
+        let connect = |server: &Arc<JsonRpcServer>| {
+            let (client, connection) = InProcTransport::pair();
+            let server = Arc::clone(server);
+            let serving = tokio::spawn(async move { server.serve_transport(connection).await });
+            (JsonRpcClient::new(client), serving)
+        };
+
+        let (client, serving) = connect(&server);
+        assert_eq!(client.call::<_, usize>("count", ()).await.unwrap(), 1);
+        assert_eq!(client.call::<_, usize>("count", ()).await.unwrap(), 2);
+        let session: Value = client.call(crate::core::session::SESSION_METHOD, ()).await.unwrap();
+        drop(client);
+        tokio::time::timeout(Duration::from_secs(5), serving).await.unwrap().unwrap();
+
+        // A new connection starts afresh, unless it resumes the old session
+        let (client, _serving) = connect(&server);
+        assert_eq!(client.call::<_, usize>("count", ()).await.unwrap(), 1);
+        let resumed: Value = client.call(
+            crate::core::session::RESUME_SESSION_METHOD,
+            json!({"token": session["resume_token"]}),
+        ).await.unwrap();
+        assert_eq!(resumed["id"], session["id"]);
+        assert_eq!(client.call::<_, usize>("count", ()).await.unwrap(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_builder_errors() {
+        assert!(JsonRpcServer::builder().serve().await.is_err());
+        assert!(JsonRpcServer::builder().bind("udp://127.0.0.1:9000").build().is_err());
+        assert!(JsonRpcServer::builder().bind("tcp://127.0.0.1").build().is_err());
+
+        let duplicate = JsonRpcServer::builder()
+            .bind("tcp://127.0.0.1:0")
+            .method("ping", |_params, _context| async { Ok(1) })
+            .method("ping", |_params, _context| async { Ok(2) })
+            .build();
+        assert!(matches!(duplicate, Err(Error::Configuration { .. })));
+
+        let server = JsonRpcServer::builder()
+            .method("math.add", |params, _context| async move {
+                let args: Vec<i64> = serde_json::from_value(params.unwrap_or(Value::Null))?;
+                Ok(args.iter().sum::<i64>())
+            })
+            .build()
+            .unwrap();
+        assert_eq!(server.router().methods(), vec!["math.add".to_string()]);
+    }
+}
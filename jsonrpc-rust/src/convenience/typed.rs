@@ -0,0 +1,154 @@
+//! Support code for `#[rpc_method]`
+//!
+//! The generated handlers call these through `jsonrpc_rust::__private`;
+//! they are not meant to be used directly.
+
+use std::collections::HashMap;
+
+use schemars::JsonSchema;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::core::error::{Error, Result};
+use crate::core::types::{JsonRpcRequest, JsonRpcResponse, MethodInfo};
+
+/// Deserialize the params of a call, answering failures with
+/// `invalid_params`
+///
+/// Missing params deserialize from `null`, so `Option` and `()` params
+/// accept calls without any.
+pub fn parse_params<P: DeserializeOwned>(params: Option<Value>) -> Result<P> {
+    serde_json::from_value(params.unwrap_or(Value::Null))
+        .map_err(|e| Error::invalid_params(format!("Invalid params: {}", e)))
+}
+
+/// Refuse params sent to a method that takes none
+pub fn no_params(params: &Option<Value>) -> Result<()> {
+    match params {
+        None | Some(Value::Null) => Ok(()),
+        Some(Value::Array(args)) if args.is_empty() => Ok(()),
+        Some(Value::Object(args)) if args.is_empty() => Ok(()),
+        Some(_) => Err(Error::invalid_params("Method takes no params")),
+    }
+}
+
+/// Answer a call with its result
+pub fn respond<R: Serialize>(request: &JsonRpcRequest, result: R) -> Result<JsonRpcResponse> {
+    Ok(JsonRpcResponse::success(
+        request.id.clone().unwrap_or(Value::Null),
+        serde_json::to_value(result)?,
+    ))
+}
+
+/// JSON schema of a type
+pub fn schema_of<T: JsonSchema>() -> Value {
+    serde_json::to_value(schemars::schema_for!(T)).unwrap_or(Value::Null)
+}
+
+/// Describe a method
+pub fn method_info(name: &str, description: &str, params_schema: Option<Value>, returns_schema: Value) -> MethodInfo {
+    MethodInfo {
+        name: name.to_string(),
+        description: description.to_string(),
+        params_schema,
+        returns_schema: Some(returns_schema),
+        example_params: None,
+        example_returns: None,
+        auth_required: false,
+        required_permissions: Vec::new(),
+        metadata: HashMap::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::convenience::{rpc_method, JsonRpcServer, RpcMethod};
+    use crate::core::error::{Error, Result};
+    use crate::core::traits::MethodHandler;
+    use crate::core::types::{JsonRpcRequest, ServiceContext};
+    use schemars::JsonSchema;
+    use serde::{Deserialize, Serialize};
+    use serde_json::{json, Value};
+
+    #[derive(Debug, Deserialize, JsonSchema)]
+    struct AddParams {
+        a: i64,
+        b: i64,
+    }
+
+    #[derive(Debug, Serialize, JsonSchema)]
+    struct Greeting {
+        text: String,
+        request: String,
+    }
+
+    /// Add two numbers
+    #[rpc_method("math.add")]
+    async fn add(params: AddParams) -> Result<i64> {
+        params.a.checked_add(params.b).ok_or_else(|| Error::invalid_params("Overflow"))
+    }
+
+    #[rpc_method("greet", description = "Greet someone")]
+    async fn greet(name: String, context: &ServiceContext) -> Result<Greeting> {
+        Ok(Greeting { text: format!("Hello, {}", name), request: context.request_id.clone() })
+    }
+
+    #[rpc_method("ping")]
+    async fn ping() -> Result<&'static str> {
+        Ok("pong")
+    }
+
+    async fn call(handler: &dyn MethodHandler, method: &str, params: Option<Value>) -> Result<Value> {
+        let request = JsonRpcRequest::with_id(method, params, json!(1));
+        let response = handler.handle_method(&request, &ServiceContext::new("req-1")).await?;
+        assert_eq!(response.id, json!(1));
+        Ok(response.result.unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_generated_handlers() {
+        assert_eq!(call(&AddHandler, "math.add", Some(json!({ "a": 2, "b": 3 }))).await.unwrap(), json!(5));
+        assert_eq!(call(&AddHandler, "math.add", Some(json!([2, 3]))).await.unwrap(), json!(5));
+        assert_eq!(AddHandler.supported_methods(), vec!["math.add".to_string()]);
+        assert_eq!(AddHandler::METHOD, "math.add");
+
+        let greeting = call(&GreetHandler, "greet", Some(json!("Ada"))).await.unwrap();
+        assert_eq!(greeting, json!({ "text": "Hello, Ada", "request": "req-1" }));
+        assert_eq!(call(&PingHandler, "ping", None).await.unwrap(), json!("pong"));
+
+        for (handler, params) in [
+            (&AddHandler as &dyn MethodHandler, json!({ "a": "two", "b": 3 })),
+            (&AddHandler, json!({ "a": i64::MAX, "b": 1 })),
+            (&PingHandler, json!(["extra"])),
+        ] {
+            match call(handler, "x", Some(params)).await {
+                Err(e) => assert_eq!(e.to_jsonrpc_error().code, -32602, "{}", e),
+                Ok(result) => panic!("unexpected result: {}", result),
+            }
+        }
+    }
+
+    #[test]
+    fn test_method_info() {
+        let info = AddHandler.method_info();
+        assert_eq!(info.name, "math.add");
+        assert_eq!(info.description, "Add two numbers");
+        let params = info.params_schema.unwrap();
+        assert_eq!(params["properties"]["a"]["type"], json!("integer"));
+        assert_eq!(params["required"], json!(["a", "b"]));
+        assert_eq!(info.returns_schema.unwrap()["type"], json!("integer"));
+
+        assert_eq!(GreetHandler.method_info().description, "Greet someone");
+        assert!(PingHandler.method_info().params_schema.is_none());
+
+        let server = JsonRpcServer::builder()
+            .rpc_method(AddHandler)
+            .rpc_method(PingHandler)
+            .build()
+            .unwrap();
+        let names: Vec<_> = server.method_info().iter().map(|info| info.name.as_str()).collect();
+        assert_eq!(names, vec!["math.add", "ping"]);
+        assert!(server.router().has_method("ping"));
+    }
+}
@@ -0,0 +1,277 @@
+//! Validation of call params against JSON schemas
+//!
+//! A [`ParamsValidator`] holds a compiled schema per method and checks the
+//! params of incoming calls before they reach the handler. Params that do
+//! not match are answered with an `invalid_params` error whose data lists
+//! every violation with the JSON pointer of the offending field:
+//!
+//! ```json
+//! {"errors": [{"path": "/a", "message": "\"two\" is not of type \"integer\""}]}
+//! ```
+//!
+//! Missing params are validated as `null`. Servers enable validation with
+//! [`ServerBuilder::validate_params`](super::ServerBuilder::validate_params),
+//! using the schemas of `#[rpc_method]` handlers and those declared with
+//! [`ServerBuilder::params_schema`](super::ServerBuilder::params_schema).
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::core::error::{Error, JsonRpcError, Result};
+use crate::core::traits::MethodHandler;
+use crate::core::types::{JsonRpcRequest, JsonRpcResponse, ServiceContext};
+
+/// One way in which params fail their schema
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ParamsViolation {
+    /// JSON pointer to the offending value; empty for the params themselves
+    pub path: String,
+    /// What is wrong with it
+    pub message: String,
+}
+
+/// Which methods have their params validated
+///
+/// Validation is off unless enabled, either for every method with a
+/// schema or method by method; a per-method setting wins.
+#[derive(Debug, Clone, Default)]
+pub struct ParamsValidation {
+    /// Validate the params of every method with a schema
+    pub enabled: bool,
+    /// Per-method settings overriding `enabled`
+    pub methods: HashMap<String, bool>,
+    /// Schemas declared for methods, taking precedence over those of
+    /// self-describing handlers
+    pub schemas: HashMap<String, Value>,
+}
+
+impl ParamsValidation {
+    /// Validate the params of every method with a schema
+    pub fn with_enabled(mut self, enabled: bool) -> Self {
+        self.enabled = enabled;
+        self
+    }
+
+    /// Turn validation on or off for one method
+    pub fn with_method(mut self, method: impl Into<String>, enabled: bool) -> Self {
+        self.methods.insert(method.into(), enabled);
+        self
+    }
+
+    /// Declare the params schema of a method
+    pub fn with_schema(mut self, method: impl Into<String>, schema: Value) -> Self {
+        self.schemas.insert(method.into(), schema);
+        self
+    }
+
+    /// Whether the params of `method` are validated
+    pub fn is_enabled_for(&self, method: &str) -> bool {
+        self.methods.get(method).copied().unwrap_or(self.enabled)
+    }
+}
+
+/// Compiled params schemas, by method
+#[derive(Default)]
+pub struct ParamsValidator {
+    schemas: HashMap<String, jsonschema::Validator>,
+}
+
+impl ParamsValidator {
+    /// Create a validator without schemas
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Compile the schemas of the methods `validation` enables, from its
+    /// own schemas and from `described` (method name and params schema)
+    pub fn from_validation<'a>(
+        validation: &ParamsValidation,
+        described: impl IntoIterator<Item = (&'a str, &'a Value)>,
+    ) -> Result<Self> {
+        let mut validator = Self::new();
+        for (method, schema) in described {
+            if validation.is_enabled_for(method) && !validation.schemas.contains_key(method) {
+                validator.add(method, schema)?;
+            }
+        }
+        for (method, schema) in &validation.schemas {
+            if validation.is_enabled_for(method) {
+                validator.add(method.clone(), schema)?;
+            }
+        }
+        Ok(validator)
+    }
+
+    /// Validate the params of `method` against `schema`, replacing any
+    /// schema it had
+    pub fn add(&mut self, method: impl Into<String>, schema: &Value) -> Result<()> {
+        let method = method.into();
+        let compiled = jsonschema::validator_for(schema)
+            .map_err(|e| Error::configuration(format!("Invalid params schema for '{}': {}", method, e)))?;
+        self.schemas.insert(method, compiled);
+        Ok(())
+    }
+
+    /// Stop validating the params of `method`
+    pub fn remove(&mut self, method: &str) -> bool {
+        self.schemas.remove(method).is_some()
+    }
+
+    /// Whether the params of `method` are validated
+    pub fn validates(&self, method: &str) -> bool {
+        self.schemas.contains_key(method)
+    }
+
+    /// Whether no method is validated
+    pub fn is_empty(&self) -> bool {
+        self.schemas.is_empty()
+    }
+
+    /// Every way in which `params` fail the schema of `method`; none when
+    /// the method has no schema
+    pub fn violations(&self, method: &str, params: Option<&Value>) -> Vec<ParamsViolation> {
+        let Some(schema) = self.schemas.get(method) else {
+            return Vec::new();
+        };
+        let params = params.unwrap_or(&Value::Null);
+        schema.iter_errors(params)
+            .map(|error| ParamsViolation {
+                path: error.instance_path.as_str().to_string(),
+                message: error.to_string(),
+            })
+            .collect()
+    }
+
+    /// Check the params of a call, answering violations with
+    /// `invalid_params`
+    pub fn validate(&self, method: &str, params: Option<&Value>) -> Result<()> {
+        let violations = self.violations(method, params);
+        let Some(first) = violations.first() else {
+            return Ok(());
+        };
+
+        let message = match first.path.as_str() {
+            "" => format!("Invalid params: {}", first.message),
+            path => format!("Invalid params at {}: {}", path, first.message),
+        };
+        Err(Error::JsonRpc(
+            JsonRpcError::invalid_params(message).with_data(json!({ "errors": violations })),
+        ))
+    }
+}
+
+impl std::fmt::Debug for ParamsValidator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut methods: Vec<_> = self.schemas.keys().collect();
+        methods.sort();
+        f.debug_struct("ParamsValidator")
+            .field("methods", &methods)
+            .finish()
+    }
+}
+
+/// A [`MethodHandler`] validating params before calling the handler it wraps
+pub struct ValidatedHandler {
+    inner: Arc<dyn MethodHandler>,
+    validator: Arc<ParamsValidator>,
+}
+
+impl ValidatedHandler {
+    /// Validate the calls `inner` serves with `validator`
+    pub fn new(inner: Arc<dyn MethodHandler>, validator: Arc<ParamsValidator>) -> Self {
+        Self { inner, validator }
+    }
+}
+
+#[async_trait]
+impl MethodHandler for ValidatedHandler {
+    async fn handle_method(&self, request: &JsonRpcRequest, context: &ServiceContext) -> Result<JsonRpcResponse> {
+        self.validator.validate(&request.method, request.params.as_ref())?;
+        self.inner.handle_method(request, context).await
+    }
+
+    fn supported_methods(&self) -> Vec<String> {
+        self.inner.supported_methods()
+    }
+
+    fn supports_method(&self, method: &str) -> bool {
+        self.inner.supports_method(method)
+    }
+}
+
+impl std::fmt::Debug for ValidatedHandler {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ValidatedHandler")
+            .field("validator", &self.validator)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::convenience::JsonRpcServer;
+
+    fn add_schema() -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "a": {"type": "integer"},
+                "b": {"type": "integer"}
+            },
+            "required": ["a", "b"]
+        })
+    }
+
+    #[test]
+    fn test_violations_carry_json_pointers() {
+        let mut validator = ParamsValidator::new();
+        validator.add("math.add", &add_schema()).unwrap();
+
+        assert!(validator.validate("math.add", Some(&json!({"a": 1, "b": 2}))).is_ok());
+        assert!(validator.validate("other", Some(&json!("anything"))).is_ok());
+
+        let violations = validator.violations("math.add", Some(&json!({"a": "two"})));
+        assert_eq!(violations.len(), 2);
+        assert!(violations.iter().any(|violation| violation.path == "/a"));
+        assert!(violations.iter().any(|violation| violation.path.is_empty()));
+
+        let error = validator.validate("math.add", None).unwrap_err().to_jsonrpc_error();
+        assert_eq!(error.code, -32602);
+        assert_eq!(error.data.unwrap()["errors"][0]["path"], json!(""));
+
+        assert!(validator.add("bad", &json!({"type": 12})).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_server_validates_enabled_methods() {
+        let server = JsonRpcServer::builder()
+            .method("math.add", |_params, _context| async { Ok(3) })
+            .method("math.sub", |_params, _context| async { Ok(-1) })
+            .params_schema("math.add", add_schema())
+            .params_schema("math.sub", add_schema())
+            .validate_params(true)
+            .validate_method_params("math.sub", false)
+            .build()
+            .unwrap();
+        let context = ServiceContext::new("req-1");
+
+        let request = JsonRpcRequest::with_id("math.add", Some(json!({"a": 1, "b": [2]})), json!(1));
+        let response = server.router().dispatch(&request, &context).await.unwrap();
+        let error = response.error.unwrap();
+        assert_eq!(error.code, -32602);
+        assert_eq!(error.data.unwrap()["errors"][0]["path"], json!("/b"));
+
+        let request = JsonRpcRequest::with_id("math.add", Some(json!({"a": 1, "b": 2})), json!(2));
+        let response = server.router().dispatch(&request, &context).await.unwrap();
+        assert_eq!(response.result, Some(json!(3)));
+
+        let request = JsonRpcRequest::with_id("math.sub", Some(json!("not an object")), json!(3));
+        let response = server.router().dispatch(&request, &context).await.unwrap();
+        assert_eq!(response.result, Some(json!(-1)));
+    }
+}
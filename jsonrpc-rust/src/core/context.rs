@@ -0,0 +1,131 @@
+//! Context of the call being handled
+//!
+//! The router makes the [`ServiceContext`] of a call current while its
+//! handler runs, so that code deep in the call tree reaches the request id,
+//! identity, TRN and deadline of the call through [`Context::current`]
+//! without them being passed down:
+//!
+//! ```rust
+//! use jsonrpc_rust::core::context::Context;
+//!
+//! fn audit(action: &str) {
+//!     let caller = Context::current()
+//!         .and_then(|context| context.auth_context.clone())
+//!         .map(|auth| auth.user_id);
+//!     tracing::info!(?caller, request_id = ?Context::request_id(), "{}", action);
+//! }
+//! ```
+//!
+//! Task-locals stay with their task: work spawned with [`tokio::spawn`]
+//! starts without a current call. Spawn it with [`Context::spawn`] instead,
+//! or wrap it in [`Context::propagate`] for another executor, and it keeps
+//! the call's context, [trace context](super::trace), deadline and span.
+
+use std::future::Future;
+use std::sync::Arc;
+
+use tokio::task::JoinHandle;
+use tracing::Instrument;
+
+use super::deadline;
+use super::trace::TraceContext;
+use super::types::ServiceContext;
+
+tokio::task_local! {
+    static CURRENT: Arc<ServiceContext>;
+}
+
+/// Access to the context of the call being handled
+#[derive(Debug, Clone, Copy)]
+pub struct Context;
+
+impl Context {
+    /// The context of the call being handled, if any
+    pub fn current() -> Option<Arc<ServiceContext>> {
+        CURRENT.try_with(Arc::clone).ok()
+    }
+
+    /// Request id of the call being handled, if any
+    pub fn request_id() -> Option<String> {
+        CURRENT.try_with(|context| context.request_id.clone()).ok()
+    }
+
+    /// Run `future` with `context` current
+    pub async fn scope<F: Future>(context: impl Into<Arc<ServiceContext>>, future: F) -> F::Output {
+        CURRENT.scope(context.into(), future).await
+    }
+
+    /// Wrap `future` so that it runs with the call context, trace context,
+    /// deadline and span current where it was created
+    pub fn propagate<F: Future>(future: F) -> impl Future<Output = F::Output> {
+        let context = Self::current();
+        let trace = TraceContext::current();
+        let deadline = deadline::current();
+        let future = deadline::within(deadline, future).in_current_span();
+        async move {
+            match (context, trace) {
+                (Some(context), Some(trace)) => CURRENT.scope(context, trace.scope(future)).await,
+                (Some(context), None) => CURRENT.scope(context, future).await,
+                (None, Some(trace)) => trace.scope(future).await,
+                (None, None) => future.await,
+            }
+        }
+    }
+
+    /// Spawn a task that keeps the context of the call spawning it
+    pub fn spawn<F>(future: F) -> JoinHandle<F::Output>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        tokio::spawn(Self::propagate(future))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::error::Result;
+    use crate::core::traits::MethodHandler;
+    use crate::core::types::{JsonRpcRequest, JsonRpcResponse};
+    use crate::protocol::MethodRouter;
+    use async_trait::async_trait;
+    use serde_json::{json, Value};
+
+    /// Answers with what a spawned task and a plain tokio task see
+    struct Spawner;
+
+    #[async_trait]
+    impl MethodHandler for Spawner {
+        async fn handle_method(&self, request: &JsonRpcRequest, _context: &ServiceContext) -> Result<JsonRpcResponse> {
+            let seen = || (Context::request_id(), deadline::current().is_some(), TraceContext::current().is_some());
+            let spawned = Context::spawn(async move { seen() }).await.unwrap();
+            let plain = tokio::spawn(async move { seen() }).await.unwrap();
+            Ok(JsonRpcResponse::success(request.id.clone().unwrap_or(Value::Null), json!([spawned, plain])))
+        }
+
+        fn supported_methods(&self) -> Vec<String> {
+            vec!["spawn".to_string()]
+        }
+    }
+
+    #[tokio::test]
+    async fn test_spawned_tasks_keep_the_call_context() {
+        let router = MethodRouter::new().with_handler(Arc::new(Spawner)).unwrap();
+        let mut request = JsonRpcRequest::with_id("spawn", None, json!(1));
+        deadline::inject(std::time::Instant::now() + std::time::Duration::from_secs(5), &mut request);
+
+        let response = router.dispatch(&request, &ServiceContext::new("req-7")).await.unwrap();
+        assert_eq!(response.result, Some(json!([["req-7", true, true], [null, false, false]])));
+        assert!(Context::current().is_none());
+
+        // Spawning takes the context current when called
+        let context = ServiceContext::new("outside");
+        let id = Context::scope(context, Context::spawn(async { Context::request_id() })).await.unwrap();
+        assert_eq!(id, None);
+        let id = Context::scope(ServiceContext::new("outside"), async {
+            Context::spawn(async { Context::request_id() }).await.unwrap()
+        }).await;
+        assert_eq!(id.as_deref(), Some("outside"));
+    }
+}
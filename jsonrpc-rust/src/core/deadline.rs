@@ -0,0 +1,106 @@
+//! Deadline propagation
+//!
+//! A caller gives its callee the time left to answer in the `timeout_ms`
+//! member of the request `meta`. The router sets the
+//! [`ServiceContext::deadline`] of the call from it, answers with a timeout
+//! error once it passes, and makes it current while the handler runs, so
+//! that calls the handler makes with a
+//! [`JsonRpcClient`](crate::protocol::JsonRpcClient) wait no longer than
+//! what is left and pass that on.
+//!
+//! Handlers check the time they have left with [`ServiceContext::remaining`].
+
+use std::borrow::Cow;
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+use super::error::{Error, Result};
+use super::types::{JsonRpcRequest, ServiceContext};
+
+/// Request meta key of the time left to answer, in milliseconds
+pub const TIMEOUT_META: &str = "timeout_ms";
+
+tokio::task_local! {
+    static CURRENT: Instant;
+}
+
+/// The deadline of the call being handled, if it has one
+pub fn current() -> Option<Instant> {
+    CURRENT.try_with(|deadline| *deadline).ok()
+}
+
+/// Time left before the deadline of the call being handled
+pub fn remaining() -> Option<Duration> {
+    current().map(|deadline| deadline.saturating_duration_since(Instant::now()))
+}
+
+/// Give a request the time left before `deadline`
+pub fn inject(deadline: Instant, request: &mut JsonRpcRequest) {
+    let left = deadline.saturating_duration_since(Instant::now());
+    request.meta.get_or_insert_with(Default::default)
+        .insert(TIMEOUT_META.to_string(), (left.as_millis() as u64).into());
+}
+
+/// The deadline a request asks for, measured from now
+pub fn extract(request: &JsonRpcRequest) -> Option<Instant> {
+    let millis = request.meta_value(TIMEOUT_META)?.as_u64()?;
+    Some(Instant::now() + Duration::from_millis(millis))
+}
+
+/// The context of a call, with the earlier of its own deadline and the one
+/// the request asks for
+pub(crate) fn adopt<'a>(request: &JsonRpcRequest, context: &'a ServiceContext) -> Cow<'a, ServiceContext> {
+    match extract(request) {
+        Some(deadline) if context.deadline.is_none_or(|own| deadline < own) => {
+            Cow::Owned(context.clone().with_deadline(deadline))
+        }
+        _ => Cow::Borrowed(context),
+    }
+}
+
+/// Run `future` with `deadline` current, if there is one
+pub(crate) async fn within<F: Future>(deadline: Option<Instant>, future: F) -> F::Output {
+    match deadline {
+        Some(deadline) => CURRENT.scope(deadline, future).await,
+        None => future.await,
+    }
+}
+
+/// Run a call, failing it once `deadline` passes
+pub(crate) async fn run<T, F>(method: &str, deadline: Option<Instant>, call: F) -> Result<T>
+where
+    F: Future<Output = Result<T>>,
+{
+    let Some(deadline) = deadline else {
+        return call.await;
+    };
+    let budget = deadline.saturating_duration_since(Instant::now());
+    CURRENT.scope(deadline, tokio::time::timeout_at(deadline.into(), call)).await
+        .unwrap_or_else(|_| Err(Error::timeout(format!("call to '{}'", method), budget)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_request_deadline_shortens_context_deadline() {
+        let later = Instant::now() + Duration::from_secs(60);
+        let context = ServiceContext::new("req-1").with_deadline(later);
+        let mut request = JsonRpcRequest::with_id("ping", None, json!(1));
+        assert_eq!(adopt(&request, &context).deadline, Some(later));
+
+        inject(Instant::now() + Duration::from_millis(50), &mut request);
+        let adopted = adopt(&request, &context);
+        assert!(adopted.remaining().unwrap() <= Duration::from_millis(50));
+
+        let result = run("slow", adopted.deadline, async {
+            assert!(remaining().unwrap() <= Duration::from_millis(50));
+            tokio::time::sleep(Duration::from_secs(5)).await;
+            Ok(())
+        });
+        assert!(matches!(result.await, Err(Error::Timeout { .. })));
+        assert!(current().is_none());
+    }
+}
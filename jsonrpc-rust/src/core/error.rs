@@ -3,6 +3,11 @@
 //! This module provides comprehensive error handling with automatic conversions
 //! from common error types, optional source location tracking for debugging,
 //! and retry logic for transient failures.
+//!
+//! Errors sent to clients carry an [`ErrorData`] envelope in their `data`:
+//! the kind of the error, whether it is worth retrying and after how long,
+//! and the id of the failed call. [`Error::typed`] turns such an error back
+//! into the [`Error`] variant it came from.
 
 use std::fmt;
 use std::time::Duration;
@@ -113,6 +118,10 @@ pub struct JsonRpcError {
     pub data: Option<serde_json::Value>,
 }
 
+/// Code of calls cancelled before they were answered, as in the Language
+/// Server Protocol
+pub const REQUEST_CANCELLED: i32 = -32800;
+
 impl JsonRpcError {
     /// Create a new JSON-RPC error
     pub fn new(code: JsonRpcErrorCode, message: impl Into<String>) -> Self {
@@ -157,6 +166,15 @@ impl JsonRpcError {
         Self::new(JsonRpcErrorCode::InternalError, message)
     }
     
+    /// Create a request cancelled error ([`REQUEST_CANCELLED`])
+    pub fn request_cancelled(message: impl Into<String>) -> Self {
+        Self {
+            code: REQUEST_CANCELLED,
+            message: message.into(),
+            data: None,
+        }
+    }
+    
     /// Create a server error
     pub fn server_error(code: i32, message: impl Into<String>) -> std::result::Result<Self, crate::core::error::Error> {
         let error_code = JsonRpcErrorCode::server_error(code)?;
@@ -164,6 +182,103 @@ impl JsonRpcError {
     }
 }
 
+impl JsonRpcError {
+    /// Merge `details` into the error data
+    ///
+    /// Data that is not an object is left alone.
+    pub fn with_details(mut self, details: ErrorData) -> Self {
+        let Ok(serde_json::Value::Object(members)) = serde_json::to_value(details) else {
+            return self;
+        };
+        match self.data {
+            Some(serde_json::Value::Object(ref mut data)) => data.extend(members),
+            None => self.data = Some(serde_json::Value::Object(members)),
+            Some(_) => {}
+        }
+        self
+    }
+
+    /// Record the id of the failed call in the error data
+    pub fn with_correlation_id(self, correlation_id: impl Into<String>) -> Self {
+        let details = ErrorData {
+            correlation_id: Some(correlation_id.into()),
+            ..self.details().unwrap_or_default()
+        };
+        self.with_details(details)
+    }
+
+    /// The typed envelope of the error data, if the data is an object
+    pub fn details(&self) -> Option<ErrorData> {
+        match self.data {
+            Some(ref data @ serde_json::Value::Object(_)) => serde_json::from_value(data.clone()).ok(),
+            _ => None,
+        }
+    }
+
+    /// How long the server asks to wait before trying again
+    pub fn retry_after(&self) -> Option<Duration> {
+        self.details()?.retry_after_ms.map(Duration::from_millis)
+    }
+
+    /// The [`Error`] variant named by the error data, or [`Error::JsonRpc`]
+    /// when it names none
+    pub fn into_error(self) -> Error {
+        let Some(details) = self.details() else {
+            return Error::JsonRpc(self);
+        };
+        let retry_after = details.retry_after_ms.map(Duration::from_millis);
+        // Messages were made by the `Display` of the variant
+        let message = |prefix: &str| self.message.strip_prefix(prefix).unwrap_or(&self.message).to_string();
+
+        match details.kind.as_deref().and_then(ErrorKind::from_name) {
+            Some(ErrorKind::Transport) => Error::transport(message("Transport error: ")),
+            Some(ErrorKind::Connection) => Error::connection(message("Connection error: ")),
+            Some(ErrorKind::Authentication) => Error::authentication(message("Authentication error: ")),
+            Some(ErrorKind::Authorization) => Error::authorization(message("Authorization error: ")),
+            Some(ErrorKind::Validation) => Error::validation(message("Validation error: ")),
+            Some(ErrorKind::Service) => Error::service(message("Service error: ")),
+            Some(ErrorKind::InvalidParams) => Error::invalid_params(message("Invalid parameters: ")),
+            Some(ErrorKind::RateLimit) => Error::rate_limit(message("Rate limit exceeded: "), retry_after),
+            Some(ErrorKind::ResourceNotFound) => Error::resource_not_found(message("Resource not found: ")),
+            // The time the server waited is not sent
+            Some(ErrorKind::Timeout) => Error::timeout(message("Operation timed out: "), Duration::ZERO),
+            Some(ErrorKind::Cancelled) => Error::cancelled(message("Operation was cancelled: ")),
+            Some(ErrorKind::CircuitOpen) => Error::circuit_open(message("Circuit open: "), retry_after),
+            Some(ErrorKind::ResourceExhausted) => {
+                let amount = |name: &str| details.extra.get(name).and_then(serde_json::Value::as_u64).unwrap_or(0);
+                match details.extra.get("resource").and_then(serde_json::Value::as_str) {
+                    Some(resource) => Error::resource_exhausted(resource, amount("limit"), amount("used")),
+                    None => Error::JsonRpc(self),
+                }
+            }
+            _ => Error::JsonRpc(self),
+        }
+    }
+}
+
+/// Typed envelope of the `data` of JSON-RPC errors
+///
+/// Lets clients act on an error without parsing its message. Members put
+/// in `data` by other servers are kept in `extra`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct ErrorData {
+    /// Kind of the error on the server, as named by [`ErrorKind::name`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kind: Option<String>,
+    /// Whether the same call may succeed later
+    #[serde(default)]
+    pub retryable: bool,
+    /// Milliseconds to wait before trying again
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry_after_ms: Option<u64>,
+    /// Id of the failed call, to find it in the server logs
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub correlation_id: Option<String>,
+    /// Other members
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
 impl fmt::Display for JsonRpcError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "JSON-RPC Error {}: {}", self.code, self.message)?;
@@ -189,6 +304,9 @@ pub struct RetryPolicy {
     pub backoff_multiplier: f64,
     /// Jitter factor (0.0 to 1.0) for randomizing delays
     pub jitter_factor: f64,
+    /// Total time budget across all attempts; no retry starts past it
+    #[serde(default)]
+    pub max_elapsed_time: Option<Duration>,
 }
 
 impl RetryPolicy {
@@ -200,6 +318,7 @@ impl RetryPolicy {
             max_delay: Duration::from_secs(60), // 1 minute max
             backoff_multiplier: 2.0,
             jitter_factor: 0.1,
+            max_elapsed_time: None,
         }
     }
     
@@ -236,6 +355,12 @@ impl RetryPolicy {
         self
     }
     
+    /// Set the total time budget across all attempts
+    pub fn with_max_elapsed_time(mut self, max_elapsed_time: Duration) -> Self {
+        self.max_elapsed_time = Some(max_elapsed_time);
+        self
+    }
+    
     /// Calculate delay for a specific attempt (0-based)
     pub fn delay_for_attempt(&self, attempt: u32) -> Duration {
         if attempt >= self.max_attempts {
@@ -373,6 +498,22 @@ pub enum Error {
     #[error("Operation was cancelled: {operation}")]
     Cancelled { operation: String },
     
+    /// Calls rejected without being attempted because a circuit breaker is open
+    #[error("Circuit open: {circuit}")]
+    CircuitOpen {
+        circuit: String,
+        retry_after: Option<Duration>,
+    },
+    
+    /// Work stopped for using more of a resource than its
+    /// [`ResourceLimits`](crate::core::future::ResourceLimits) allow
+    #[error("Resource exhausted: {resource} ({used} of {limit})")]
+    ResourceExhausted {
+        resource: String,
+        limit: u64,
+        used: u64,
+    },
+    
     /// Custom errors for extensibility
     #[error("Custom error: {message}")]
     Custom {
@@ -423,10 +564,71 @@ pub enum ErrorKind {
     Timeout,
     /// Cancellation
     Cancelled,
+    /// Circuit breaker open
+    CircuitOpen,
+    /// Resource limit exceeded
+    ResourceExhausted,
     /// Custom errors
     Custom,
 }
 
+impl ErrorKind {
+    /// Name of the kind in [`ErrorData::kind`]
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Protocol => "protocol",
+            Self::Serialization => "serialization",
+            Self::Transport => "transport",
+            Self::Connection => "connection",
+            Self::Authentication => "authentication",
+            Self::Authorization => "authorization",
+            Self::Validation => "validation",
+            Self::Service => "service",
+            Self::MethodNotFound => "method_not_found",
+            Self::InvalidParams => "invalid_params",
+            Self::RateLimit => "rate_limit",
+            Self::ResourceNotFound => "resource_not_found",
+            Self::Configuration => "configuration",
+            Self::Io => "io",
+            #[cfg(feature = "trn-integration")]
+            Self::Trn => "trn",
+            Self::Timeout => "timeout",
+            Self::Cancelled => "cancelled",
+            Self::CircuitOpen => "circuit_open",
+            Self::ResourceExhausted => "resource_exhausted",
+            Self::Custom => "custom",
+        }
+    }
+
+    /// The kind with this name
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "protocol" => Self::Protocol,
+            "serialization" => Self::Serialization,
+            "transport" => Self::Transport,
+            "connection" => Self::Connection,
+            "authentication" => Self::Authentication,
+            "authorization" => Self::Authorization,
+            "validation" => Self::Validation,
+            "service" => Self::Service,
+            "method_not_found" => Self::MethodNotFound,
+            "invalid_params" => Self::InvalidParams,
+            "rate_limit" => Self::RateLimit,
+            "resource_not_found" => Self::ResourceNotFound,
+            "configuration" => Self::Configuration,
+            "io" => Self::Io,
+            #[cfg(feature = "trn-integration")]
+            "trn" => Self::Trn,
+            "timeout" => Self::Timeout,
+            "cancelled" => Self::Cancelled,
+            "circuit_open" => Self::CircuitOpen,
+            "resource_exhausted" => Self::ResourceExhausted,
+            "custom" => Self::Custom,
+            _ => return None,
+        })
+    }
+}
+
 /// Type alias for Result with our Error type
 pub type Result<T> = std::result::Result<T, Error>;
 
@@ -452,6 +654,8 @@ impl Error {
             Error::Trn(_) => ErrorKind::Trn,
             Error::Timeout { .. } => ErrorKind::Timeout,
             Error::Cancelled { .. } => ErrorKind::Cancelled,
+            Error::CircuitOpen { .. } => ErrorKind::CircuitOpen,
+            Error::ResourceExhausted { .. } => ErrorKind::ResourceExhausted,
             Error::Custom { .. } => ErrorKind::Custom,
         }
     }
@@ -475,23 +679,71 @@ impl Error {
             },
             #[cfg(feature = "trn-integration")]
             Error::Trn(_) => false,
-            Error::JsonRpc(_) | Error::Serialization { .. } | Error::Authentication { .. }
+            Error::JsonRpc(error) => error.details().is_some_and(|details| details.retryable),
+            Error::Serialization { .. } | Error::Authentication { .. }
             | Error::Authorization { .. } | Error::Validation { .. } | Error::MethodNotFound { .. }
             | Error::InvalidParams { .. } | Error::ResourceNotFound { .. } 
-            | Error::Configuration { .. } | Error::Cancelled { .. } => false,
+            | Error::Configuration { .. } | Error::Cancelled { .. }
+            | Error::CircuitOpen { .. } | Error::ResourceExhausted { .. } => false,
             Error::Custom { .. } => false, // Custom errors should specify their own retry logic
         }
     }
     
-    /// Convert to a JSON-RPC error
-    pub fn to_jsonrpc_error(&self) -> JsonRpcError {
+    /// How long to wait before trying again, when the error says
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            Error::RateLimit { retry_after, .. } | Error::CircuitOpen { retry_after, .. } => *retry_after,
+            Error::JsonRpc(error) => error.retry_after(),
+            _ => None,
+        }
+    }
+
+    /// For a JSON-RPC error received from a server, the variant its
+    /// [`ErrorData`] names; other errors are returned as they are
+    ///
+    /// ```rust
+    /// # use jsonrpc_rust::core::error::Error;
+    /// # use std::time::Duration;
+    /// let sent = Error::rate_limit("slow down", Some(Duration::from_secs(1))).to_jsonrpc_error();
+    /// let received = Error::JsonRpc(sent).typed();
+    /// assert!(matches!(received, Error::RateLimit { .. }));
+    /// assert_eq!(received.retry_after(), Some(Duration::from_secs(1)));
+    /// ```
+    pub fn typed(self) -> Self {
         match self {
-            Error::JsonRpc(err) => err.clone(),
+            Error::JsonRpc(error) => error.into_error(),
+            other => other,
+        }
+    }
+
+    /// Convert to a JSON-RPC error, with its [`ErrorData`]
+    pub fn to_jsonrpc_error(&self) -> JsonRpcError {
+        let error = match self {
+            Error::JsonRpc(err) => return err.clone(),
             Error::MethodNotFound { method } => JsonRpcError::method_not_found(method),
             Error::InvalidParams { message, .. } => JsonRpcError::invalid_params(message),
             Error::Serialization { message, .. } => JsonRpcError::parse_error(message),
+            Error::Authentication { .. } => JsonRpcError::new(JsonRpcErrorCode::ServerError(-32001), self.to_string()),
+            Error::Authorization { .. } => JsonRpcError::new(JsonRpcErrorCode::ServerError(-32003), self.to_string()),
+            Error::RateLimit { .. } => JsonRpcError::new(JsonRpcErrorCode::ServerError(-32005), self.to_string()),
+            Error::Timeout { .. } => JsonRpcError::new(JsonRpcErrorCode::ServerError(-32008), self.to_string()),
+            Error::Cancelled { .. } => JsonRpcError::request_cancelled(self.to_string()),
+            Error::ResourceExhausted { .. } => JsonRpcError::new(JsonRpcErrorCode::ServerError(-32006), self.to_string()),
             _ => JsonRpcError::internal_error(self.to_string()),
+        };
+        let mut extra = serde_json::Map::new();
+        if let Error::ResourceExhausted { resource, limit, used } = self {
+            extra.insert("resource".to_string(), serde_json::json!(resource));
+            extra.insert("limit".to_string(), serde_json::json!(limit));
+            extra.insert("used".to_string(), serde_json::json!(used));
         }
+        error.with_details(ErrorData {
+            kind: Some(self.kind().name().to_string()),
+            retryable: self.is_retryable(),
+            retry_after_ms: self.retry_after().map(|retry_after| retry_after.as_millis() as u64),
+            extra,
+            ..ErrorData::default()
+        })
     }
     
     /// Create a transport error
@@ -603,6 +855,23 @@ impl Error {
         }
     }
     
+    /// Create a circuit open error
+    pub fn circuit_open(circuit: impl Into<String>, retry_after: Option<Duration>) -> Self {
+        Self::CircuitOpen {
+            circuit: circuit.into(),
+            retry_after,
+        }
+    }
+    
+    /// Create a resource exhausted error
+    pub fn resource_exhausted(resource: impl Into<String>, limit: u64, used: u64) -> Self {
+        Self::ResourceExhausted {
+            resource: resource.into(),
+            limit,
+            used,
+        }
+    }
+    
     /// Create a custom error
     pub fn custom(message: impl Into<String>) -> Self {
         Self::Custom {
@@ -795,6 +1064,41 @@ mod tests {
         assert!(!Error::validation("invalid input").is_retryable());
     }
 
+    #[test]
+    fn test_error_data_round_trip() {
+        let sent = Error::rate_limit("slow down", Some(Duration::from_millis(250)))
+            .to_jsonrpc_error()
+            .with_correlation_id("req-7");
+        assert_eq!(sent.code, -32005);
+        let details = sent.details().unwrap();
+        assert_eq!(details.kind.as_deref(), Some("rate_limit"));
+        assert!(details.retryable);
+        assert_eq!(details.correlation_id.as_deref(), Some("req-7"));
+
+        let received = Error::JsonRpc(sent);
+        assert!(received.is_retryable());
+        assert_eq!(received.retry_after(), Some(Duration::from_millis(250)));
+        match received.typed() {
+            Error::RateLimit { message, retry_after } => {
+                assert_eq!(message, "slow down");
+                assert_eq!(retry_after, Some(Duration::from_millis(250)));
+            }
+            other => panic!("Expected a rate limit error, got {:?}", other),
+        }
+
+        let validation = Error::JsonRpc(Error::validation("bad").to_jsonrpc_error());
+        assert!(!validation.is_retryable());
+        assert!(matches!(validation.typed(), Error::Validation { message, .. } if message == "bad"));
+
+        // Data of other servers is kept, and errors without a kind stay as they are
+        let foreign = JsonRpcError::internal_error("boom")
+            .with_data(serde_json::json!({"trace": "x"}))
+            .with_correlation_id("req-8");
+        assert_eq!(foreign.data.as_ref().unwrap()["trace"], "x");
+        assert!(matches!(foreign.into_error(), Error::JsonRpc(_)));
+        assert_eq!(ErrorKind::from_name(ErrorKind::CircuitOpen.name()), Some(ErrorKind::CircuitOpen));
+    }
+
     #[test]
     fn test_automatic_conversions() {
         let json_error = serde_json::Error::io(std::io::Error::new(std::io::ErrorKind::UnexpectedEof, "EOF"));
@@ -0,0 +1,433 @@
+//! Priority-aware task execution
+//!
+//! [`PriorityExecutor`] runs [`JsonRpcFuture`]s according to their
+//! [`SpawnPolicy`](crate::core::future::SpawnPolicy). Submitted futures wait
+//! in one queue per [`Priority`] and are started highest priority first,
+//! oldest first within a priority, as long as the executor-wide and
+//! per-priority concurrency limits allow. The policy timeout is enforced on
+//! the running future, and queueing and execution times are recorded in the
+//! future's [`ExecutionStats`].
+
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Instant;
+
+use parking_lot::Mutex;
+use tokio::sync::oneshot;
+
+use crate::core::error::{Error, Result};
+use crate::core::future::{ExecutionStats, JsonRpcFuture, Priority};
+use crate::core::types::JsonRpcResponse;
+
+/// Number of priority levels, indexed by [`Priority::value`]
+const PRIORITY_LEVELS: usize = 4;
+
+/// Configuration for a [`PriorityExecutor`]
+#[derive(Debug, Clone)]
+pub struct ExecutorConfig {
+    /// Maximum number of futures running at once
+    pub max_concurrent_tasks: usize,
+    /// Maximum number of futures of one priority running at once
+    pub priority_limits: HashMap<Priority, usize>,
+}
+
+impl Default for ExecutorConfig {
+    fn default() -> Self {
+        Self {
+            max_concurrent_tasks: 64,
+            priority_limits: HashMap::new(),
+        }
+    }
+}
+
+impl ExecutorConfig {
+    /// Create a configuration with the default limits
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the maximum number of futures running at once
+    pub fn with_max_concurrent_tasks(mut self, max: usize) -> Self {
+        self.max_concurrent_tasks = max;
+        self
+    }
+
+    /// Cap the number of running futures of one priority
+    pub fn with_priority_limit(mut self, priority: Priority, max: usize) -> Self {
+        self.priority_limits.insert(priority, max);
+        self
+    }
+
+    fn limit_for(&self, priority: Priority) -> usize {
+        self.priority_limits.get(&priority).copied().unwrap_or(usize::MAX)
+    }
+}
+
+/// Snapshot of executor activity
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExecutorStats {
+    /// Futures waiting to start
+    pub queued: usize,
+    /// Futures currently running
+    pub running: usize,
+    /// Futures that finished successfully
+    pub completed: u64,
+    /// Futures that finished with an error, timeouts included
+    pub failed: u64,
+    /// Futures stopped by their policy timeout
+    pub timed_out: u64,
+}
+
+struct Task {
+    future: JsonRpcFuture,
+    result: oneshot::Sender<Result<JsonRpcResponse>>,
+}
+
+#[derive(Default)]
+struct State {
+    queues: [VecDeque<Task>; PRIORITY_LEVELS],
+    running: [usize; PRIORITY_LEVELS],
+    completed: u64,
+    failed: u64,
+    timed_out: u64,
+}
+
+impl State {
+    fn total_running(&self) -> usize {
+        self.running.iter().sum()
+    }
+}
+
+struct Shared {
+    config: ExecutorConfig,
+    state: Mutex<State>,
+}
+
+/// Executor scheduling futures by priority under concurrency limits
+///
+/// Scheduling is strict: a lower priority future only starts when no higher
+/// priority future is waiting for a free slot. Cloning the executor shares
+/// its queues and limits. Futures are spawned on the current Tokio runtime.
+#[derive(Clone)]
+pub struct PriorityExecutor {
+    shared: Arc<Shared>,
+}
+
+impl PriorityExecutor {
+    /// Create an executor with the given limits
+    pub fn new(config: ExecutorConfig) -> Self {
+        Self {
+            shared: Arc::new(Shared {
+                config,
+                state: Mutex::new(State::default()),
+            }),
+        }
+    }
+
+    /// Executor configuration
+    pub fn config(&self) -> &ExecutorConfig {
+        &self.shared.config
+    }
+
+    /// Queue a future for execution according to its spawn policy
+    pub fn submit(&self, future: JsonRpcFuture) -> TaskHandle {
+        let (sender, receiver) = oneshot::channel();
+        let priority = future.priority();
+        let stats = future.stats_handle();
+        let cancellation_token = future.cancellation_token();
+        stats.lock().unwrap().queued_at = Some(Instant::now());
+
+        self.shared.state.lock().queues[priority.value() as usize].push_back(Task {
+            future,
+            result: sender,
+        });
+        Shared::dispatch(&self.shared);
+
+        TaskHandle {
+            receiver,
+            priority,
+            stats,
+            cancellation_token,
+        }
+    }
+
+    /// Current executor activity
+    pub fn stats(&self) -> ExecutorStats {
+        let state = self.shared.state.lock();
+        ExecutorStats {
+            queued: state.queues.iter().map(VecDeque::len).sum(),
+            running: state.total_running(),
+            completed: state.completed,
+            failed: state.failed,
+            timed_out: state.timed_out,
+        }
+    }
+
+    /// Number of futures of one priority waiting to start
+    pub fn queued(&self, priority: Priority) -> usize {
+        self.shared.state.lock().queues[priority.value() as usize].len()
+    }
+}
+
+impl Default for PriorityExecutor {
+    fn default() -> Self {
+        Self::new(ExecutorConfig::default())
+    }
+}
+
+impl std::fmt::Debug for PriorityExecutor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PriorityExecutor")
+            .field("config", &self.shared.config)
+            .field("stats", &self.stats())
+            .finish()
+    }
+}
+
+impl Shared {
+    /// Start queued futures while slots are free
+    fn dispatch(shared: &Arc<Shared>) {
+        let mut state = shared.state.lock();
+        while state.total_running() < shared.config.max_concurrent_tasks {
+            let next = Priority::all().iter().rev().copied().find(|&priority| {
+                let index = priority.value() as usize;
+                !state.queues[index].is_empty() && state.running[index] < shared.config.limit_for(priority)
+            });
+            let Some(priority) = next else {
+                break;
+            };
+
+            let index = priority.value() as usize;
+            let Some(task) = state.queues[index].pop_front() else {
+                break;
+            };
+            if task.future.is_cancelled() {
+                let _ = task.result.send(Err(Error::cancelled("PriorityExecutor task")));
+                continue;
+            }
+
+            state.running[index] += 1;
+            let slot = RunningSlot {
+                shared: Arc::clone(shared),
+                index,
+                released: false,
+            };
+            tokio::spawn(slot.run(task));
+        }
+    }
+}
+
+/// Slot held by a running future, released on drop even if the future panics
+struct RunningSlot {
+    shared: Arc<Shared>,
+    index: usize,
+    released: bool,
+}
+
+impl RunningSlot {
+    async fn run(mut self, task: Task) {
+        let Task { future, result: sender } = task;
+        let stats = future.stats_handle();
+        let timeout = future.policy().timeout;
+
+        let result = match timeout {
+            Some(duration) => match tokio::time::timeout(duration, future).await {
+                Ok(result) => result,
+                Err(_) => Err(Error::timeout("PriorityExecutor task", duration)),
+            },
+            None => future.await,
+        };
+
+        {
+            let mut stats = stats.lock().unwrap();
+            if stats.completed_at.is_none() {
+                stats.complete();
+            }
+        }
+        self.release(Some(&result));
+        let _ = sender.send(result);
+    }
+
+    /// Record the outcome and hand the slot to the next queued future
+    ///
+    /// `None` means the future panicked, which counts as a failure.
+    fn release(&mut self, result: Option<&Result<JsonRpcResponse>>) {
+        if std::mem::replace(&mut self.released, true) {
+            return;
+        }
+
+        {
+            let mut state = self.shared.state.lock();
+            state.running[self.index] -= 1;
+            match result {
+                Some(Ok(_)) => state.completed += 1,
+                Some(Err(Error::Timeout { .. })) => {
+                    state.failed += 1;
+                    state.timed_out += 1;
+                }
+                Some(Err(_)) | None => state.failed += 1,
+            }
+        }
+        if tokio::runtime::Handle::try_current().is_ok() {
+            Shared::dispatch(&self.shared);
+        }
+    }
+}
+
+impl Drop for RunningSlot {
+    fn drop(&mut self) {
+        self.release(None);
+    }
+}
+
+/// Handle to a future submitted to a [`PriorityExecutor`]
+///
+/// Awaiting the handle yields the future's result. Dropping it does not
+/// stop the future; use [`TaskHandle::cancel`] for that.
+pub struct TaskHandle {
+    receiver: oneshot::Receiver<Result<JsonRpcResponse>>,
+    priority: Priority,
+    stats: Arc<std::sync::Mutex<ExecutionStats>>,
+    cancellation_token: Arc<AtomicBool>,
+}
+
+impl TaskHandle {
+    /// Priority the future was scheduled with
+    pub fn priority(&self) -> Priority {
+        self.priority
+    }
+
+    /// Execution statistics of the future
+    pub fn stats(&self) -> ExecutionStats {
+        self.stats.lock().unwrap().clone()
+    }
+
+    /// Cancel the future, whether queued or running
+    pub fn cancel(&self) {
+        self.cancellation_token.store(true, Ordering::SeqCst);
+    }
+
+    /// Check if the future was cancelled
+    pub fn is_cancelled(&self) -> bool {
+        self.cancellation_token.load(Ordering::SeqCst)
+    }
+}
+
+impl Future for TaskHandle {
+    type Output = Result<JsonRpcResponse>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if self.is_cancelled() {
+            return Poll::Ready(Err(Error::cancelled("PriorityExecutor task")));
+        }
+
+        match Pin::new(&mut self.receiver).poll(cx) {
+            Poll::Ready(Ok(result)) => Poll::Ready(result),
+            Poll::Ready(Err(_)) => Poll::Ready(Err(Error::service("Executor task stopped without a result"))),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+impl std::fmt::Debug for TaskHandle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TaskHandle")
+            .field("priority", &self.priority)
+            .field("cancelled", &self.is_cancelled())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::future::SpawnPolicy;
+    use serde_json::json;
+    use std::time::Duration;
+    use tokio::sync::Notify;
+
+    fn recorded(order: &Arc<Mutex<Vec<&'static str>>>, name: &'static str, priority: Priority) -> JsonRpcFuture {
+        let order = Arc::clone(order);
+        JsonRpcFuture::with_policy(
+            async move {
+                order.lock().push(name);
+                Ok(JsonRpcResponse::success(json!(name), json!(null)))
+            },
+            SpawnPolicy::new().with_priority(priority),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_runs_highest_priority_first() {
+        let executor = PriorityExecutor::new(ExecutorConfig::new().with_max_concurrent_tasks(1));
+        let gate = Arc::new(Notify::new());
+        let order = Arc::new(Mutex::new(Vec::new()));
+
+        let blocker_gate = Arc::clone(&gate);
+        let blocker = executor.submit(JsonRpcFuture::new(async move {
+            blocker_gate.notified().await;
+            Ok(JsonRpcResponse::success(json!(0), json!(null)))
+        }));
+
+        let handles = vec![
+            executor.submit(recorded(&order, "low", Priority::Low)),
+            executor.submit(recorded(&order, "normal", Priority::Normal)),
+            recorded(&order, "critical", Priority::Critical).spawn_on(&executor),
+            executor.submit(recorded(&order, "high", Priority::High)),
+        ];
+        assert_eq!(executor.stats().queued, 4);
+        assert_eq!(executor.queued(Priority::Critical), 1);
+
+        gate.notify_one();
+        blocker.await.unwrap();
+        for handle in handles {
+            handle.await.unwrap();
+        }
+
+        assert_eq!(*order.lock(), vec!["critical", "high", "normal", "low"]);
+        let stats = executor.stats();
+        assert_eq!((stats.queued, stats.running, stats.completed), (0, 0, 5));
+    }
+
+    #[tokio::test]
+    async fn test_priority_limits_timeouts_and_stats() {
+        let executor = PriorityExecutor::new(ExecutorConfig::new().with_priority_limit(Priority::Low, 1));
+        let gate = Arc::new(Notify::new());
+
+        let slow_gate = Arc::clone(&gate);
+        let slow = executor.submit(JsonRpcFuture::with_policy(
+            async move {
+                slow_gate.notified().await;
+                Ok(JsonRpcResponse::success(json!(1), json!(null)))
+            },
+            SpawnPolicy::background(),
+        ));
+        let mut waiting = executor.submit(JsonRpcFuture::with_policy(
+            async { Ok(JsonRpcResponse::success(json!(2), json!(null))) },
+            SpawnPolicy::background(),
+        ));
+        assert_eq!(executor.queued(Priority::Low), 1);
+
+        let timed_out = executor.submit(JsonRpcFuture::with_policy(
+            std::future::pending(),
+            SpawnPolicy::high_priority().with_timeout(Duration::from_millis(10)),
+        ));
+        assert!(matches!(timed_out.await, Err(Error::Timeout { .. })));
+
+        gate.notify_one();
+        slow.await.unwrap();
+        (&mut waiting).await.unwrap();
+        assert!(waiting.stats().queue_delay().is_some());
+        assert!(waiting.stats().duration().is_some());
+        let stats = executor.stats();
+        assert_eq!((stats.completed, stats.failed, stats.timed_out), (2, 1, 1));
+
+        let cancelled = executor.submit(JsonRpcFuture::new(std::future::pending()));
+        cancelled.cancel();
+        assert!(matches!(cancelled.await, Err(Error::Cancelled { .. })));
+    }
+}
@@ -2,23 +2,53 @@
 //!
 //! This module provides async primitives with advanced features like priority scheduling,
 //! backpressure control, cancellation support, and convenient chain operations.
+//!
+//! # Resource limits
+//!
+//! A [`JsonRpcFuture`] enforces the [`ResourceLimits`] of its policy on a
+//! best-effort basis. The time spent polling it is counted as its CPU time,
+//! and it fails with [`Error::ResourceExhausted`] once a poll ends over
+//! `max_cpu_time_ms`. Work that runs long between awaits calls
+//! [`check_budget`] to stop early, and work that holds large buffers reports
+//! them with [`record_allocation`] and [`record_release`] so `max_memory_bytes`
+//! is enforced too:
+//!
+//! ```rust
+//! use jsonrpc_rust::core::future::{check_budget, record_allocation, JsonRpcFuture};
+//! use jsonrpc_rust::core::types::JsonRpcResponse;
+//! use serde_json::json;
+//!
+//! let future = JsonRpcFuture::new(async {
+//!     let rows = vec![0u8; 4096];
+//!     record_allocation(rows.len() as u64)?;
+//!     for _chunk in rows.chunks(512) {
+//!         check_budget()?;
+//!     }
+//!     Ok(JsonRpcResponse::success(json!(1), json!(rows.len())))
+//! });
+//! ```
+//!
+//! Outside of a [`JsonRpcFuture`] the hooks do nothing. File descriptors and
+//! bandwidth are not tracked.
 
 use std::future::Future;
 use std::pin::Pin;
 use std::task::{Context, Poll};
 use std::time::{Duration, Instant};
-use std::sync::{Arc, atomic::{AtomicBool, Ordering}};
+use std::sync::{Arc, atomic::{AtomicBool, AtomicU64, Ordering}};
 use std::collections::HashMap;
 
 use futures::{Stream, StreamExt};
+use tokio_util::sync::{CancellationToken, WaitForCancellationFutureOwned};
 // use tokio::sync::{mpsc, oneshot, Semaphore};
 use serde::{Deserialize, Serialize};
 
-use crate::core::error::{Error, Result};
+use crate::core::error::{Error, Result, RetryPolicy};
+use crate::core::executor::{PriorityExecutor, TaskHandle};
 use crate::core::types::JsonRpcResponse;
 
 /// Priority levels for futures and streams
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
 pub enum Priority {
     /// Lowest priority (best effort)
     Low = 0,
@@ -180,6 +210,8 @@ impl Default for ResourceLimits {
 /// Execution statistics for monitoring
 #[derive(Debug, Clone, Default)]
 pub struct ExecutionStats {
+    /// Time the future was queued on an executor
+    pub queued_at: Option<Instant>,
     /// Start time
     pub started_at: Option<Instant>,
     /// Completion time
@@ -203,6 +235,14 @@ impl ExecutionStats {
         }
     }
     
+    /// Time spent waiting in an executor queue before starting
+    pub fn queue_delay(&self) -> Option<Duration> {
+        match (self.queued_at, self.started_at) {
+            (Some(queued), Some(start)) => Some(start.duration_since(queued)),
+            _ => None,
+        }
+    }
+    
     /// Check if execution is complete
     pub fn is_complete(&self) -> bool {
         self.completed_at.is_some()
@@ -219,12 +259,115 @@ impl ExecutionStats {
     }
 }
 
+/// Resources used by one [`JsonRpcFuture`], shared with the code it runs
+struct ResourceBudget {
+    limits: ResourceLimits,
+    origin: Instant,
+    /// Polling time of the finished polls
+    cpu_time_us: AtomicU64,
+    /// Start of the running poll, in microseconds since `origin`
+    poll_started_us: AtomicU64,
+    memory_bytes: AtomicU64,
+    peak_memory_bytes: AtomicU64,
+}
+
+tokio::task_local! {
+    static BUDGET: Arc<ResourceBudget>;
+}
+
+impl ResourceBudget {
+    fn new(limits: ResourceLimits) -> Self {
+        Self {
+            limits,
+            origin: Instant::now(),
+            cpu_time_us: AtomicU64::new(0),
+            poll_started_us: AtomicU64::new(0),
+            memory_bytes: AtomicU64::new(0),
+            peak_memory_bytes: AtomicU64::new(0),
+        }
+    }
+
+    fn now_us(&self) -> u64 {
+        self.origin.elapsed().as_micros() as u64
+    }
+
+    /// CPU time used so far, the running poll included
+    fn cpu_time_us(&self) -> u64 {
+        let running = self.now_us().saturating_sub(self.poll_started_us.load(Ordering::Relaxed));
+        self.cpu_time_us.load(Ordering::Relaxed) + running
+    }
+
+    fn check(&self, cpu_time_us: u64) -> Result<()> {
+        if let Some(limit) = self.limits.max_cpu_time_ms {
+            let used = cpu_time_us / 1000;
+            if used > limit {
+                return Err(Error::resource_exhausted("cpu_time_ms", limit, used));
+            }
+        }
+        if let Some(limit) = self.limits.max_memory_bytes {
+            let used = self.memory_bytes.load(Ordering::Relaxed);
+            if used > limit {
+                return Err(Error::resource_exhausted("memory_bytes", limit, used));
+            }
+        }
+        Ok(())
+    }
+
+    /// Poll `inner` with the budget in scope, counting the time it takes
+    fn poll<T>(self: &Arc<Self>, inner: impl FnOnce() -> T) -> T {
+        let started = self.now_us();
+        self.poll_started_us.store(started, Ordering::Relaxed);
+        let output = BUDGET.sync_scope(Arc::clone(self), inner);
+        self.cpu_time_us.fetch_add(self.now_us().saturating_sub(started), Ordering::Relaxed);
+        self.poll_started_us.store(self.now_us(), Ordering::Relaxed);
+        output
+    }
+}
+
+/// Fail with [`Error::ResourceExhausted`] if the [`JsonRpcFuture`] running
+/// this code is over its CPU time or memory limit
+///
+/// Lets work that runs long between awaits stop at safe points. Always
+/// succeeds outside of a [`JsonRpcFuture`].
+pub fn check_budget() -> Result<()> {
+    BUDGET.try_with(|budget| budget.check(budget.cpu_time_us())).unwrap_or(Ok(()))
+}
+
+/// Count `bytes` held by the [`JsonRpcFuture`] running this code, failing
+/// with [`Error::ResourceExhausted`] once it holds more than its memory limit
+///
+/// The bytes stay counted even when the limit is exceeded; give them back
+/// with [`record_release`].
+pub fn record_allocation(bytes: u64) -> Result<()> {
+    BUDGET.try_with(|budget| {
+        let used = budget.memory_bytes.fetch_add(bytes, Ordering::Relaxed) + bytes;
+        budget.peak_memory_bytes.fetch_max(used, Ordering::Relaxed);
+        match budget.limits.max_memory_bytes {
+            Some(limit) if used > limit => Err(Error::resource_exhausted("memory_bytes", limit, used)),
+            _ => Ok(()),
+        }
+    }).unwrap_or(Ok(()))
+}
+
+/// Stop counting `bytes` given to [`record_allocation`]
+pub fn record_release(bytes: u64) {
+    let _ = BUDGET.try_with(|budget| {
+        let _ = budget.memory_bytes.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |used| {
+            Some(used.saturating_sub(bytes))
+        });
+    });
+}
+
 /// Enhanced JSON-RPC Future with priority and spawn policy support
 pub struct JsonRpcFuture {
     inner: Pin<Box<dyn Future<Output = Result<JsonRpcResponse>> + Send>>,
     policy: SpawnPolicy,
     cancellation_token: Arc<AtomicBool>,
+    /// Cancelled along with the token it was linked to
+    linked: Option<Pin<Box<WaitForCancellationFutureOwned>>>,
     stats: Arc<std::sync::Mutex<ExecutionStats>>,
+    /// Made from the policy limits on first poll
+    budget: Option<Arc<ResourceBudget>>,
 }
 
 impl JsonRpcFuture {
@@ -237,7 +380,9 @@ impl JsonRpcFuture {
             inner: Box::pin(future),
             policy: SpawnPolicy::default(),
             cancellation_token: Arc::new(AtomicBool::new(false)),
+            linked: None,
             stats: Arc::new(std::sync::Mutex::new(ExecutionStats::default())),
+            budget: None,
         }
     }
     
@@ -277,11 +422,29 @@ impl JsonRpcFuture {
         self.cancellation_token.store(true, Ordering::SeqCst);
     }
     
+    /// Cancel the future as soon as `token` is, e.g. the
+    /// [`ServiceContext::cancellation`](crate::core::types::ServiceContext::cancellation)
+    /// of the call it answers
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.linked = Some(Box::pin(token.cancelled_owned()));
+        self
+    }
+    
     /// Get execution statistics
     pub fn stats(&self) -> ExecutionStats {
         self.stats.lock().unwrap().clone()
     }
     
+    /// Shared handle to the execution statistics
+    pub(crate) fn stats_handle(&self) -> Arc<std::sync::Mutex<ExecutionStats>> {
+        Arc::clone(&self.stats)
+    }
+    
+    /// Shared handle to the cancellation flag
+    pub(crate) fn cancellation_token(&self) -> Arc<AtomicBool> {
+        Arc::clone(&self.cancellation_token)
+    }
+    
     /// Box the future for type erasure
     pub fn boxed(self) -> Pin<Box<dyn Future<Output = Result<JsonRpcResponse>> + Send>> {
         Box::pin(self)
@@ -335,26 +498,56 @@ impl JsonRpcFuture {
         })
     }
     
-    /// Retry the future on failure
-    /// Note: This is a simplified implementation - in practice we'd need to recreate the future
-    pub fn retry(self, max_attempts: u32, delay: Duration) -> Pin<Box<dyn Future<Output = Result<JsonRpcResponse>> + Send>> {
-        Box::pin(async move {
-            // This is a placeholder implementation
-            // In a real retry system, we'd need a way to recreate the future
-            // For now, just execute once and handle the error
-            match self.await {
-                Ok(response) => Ok(response),
-                Err(e) => {
-                    if max_attempts > 1 && e.is_retryable() {
-                        tokio::time::sleep(delay).await;
-                        // In a real implementation, we'd recreate and retry the future
-                        Err(Error::service("Retry not fully implemented yet"))
-                    } else {
-                        Err(e)
+    /// Run an operation, re-creating it with backoff until it succeeds
+    ///
+    /// `factory` is called once per attempt. Failed attempts are retried
+    /// only while the error is retryable, `policy` allows another attempt
+    /// and the next delay still fits in its `max_elapsed_time` budget; the
+    /// last error is returned otherwise. A `retry_after` hint on the error,
+    /// local or sent by the server, lengthens the delay.
+    pub fn retry<F, Fut>(mut factory: F, policy: RetryPolicy) -> Self
+    where
+        F: FnMut() -> Fut + Send + 'static,
+        Fut: Future<Output = Result<JsonRpcResponse>> + Send + 'static,
+    {
+        let stats = Arc::new(std::sync::Mutex::new(ExecutionStats::default()));
+        let retry_stats = Arc::clone(&stats);
+
+        let mut fut = Self::new(async move {
+            let started = Instant::now();
+            let mut attempt = 0;
+            loop {
+                let error = match factory().await {
+                    Ok(response) => return Ok(response),
+                    Err(e) => e,
+                };
+                if !error.is_retryable() || !policy.should_retry(attempt) {
+                    return Err(error);
+                }
+
+                let mut delay = policy.delay_for_attempt(attempt);
+                if let Some(retry_after) = error.retry_after() {
+                    delay = delay.max(retry_after);
+                }
+                if let Some(budget) = policy.max_elapsed_time {
+                    if started.elapsed() + delay > budget {
+                        return Err(error);
                     }
                 }
+
+                tracing::debug!("Attempt {} failed, retrying in {:?}: {}", attempt + 1, delay, error);
+                tokio::time::sleep(delay).await;
+                attempt += 1;
+                retry_stats.lock().unwrap().retry_count = attempt;
             }
-        })
+        });
+        fut.stats = stats;
+        fut
+    }
+    
+    /// Submit the future to a priority executor, which schedules it by its policy
+    pub fn spawn_on(self, executor: &PriorityExecutor) -> TaskHandle {
+        executor.submit(self)
     }
     
     /// Spawn the future with high priority
@@ -378,6 +571,10 @@ impl Future for JsonRpcFuture {
     
     fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
         // Check for cancellation
+        let linked_cancelled = self.linked.as_mut().is_some_and(|linked| linked.as_mut().poll(cx).is_ready());
+        if linked_cancelled {
+            self.cancel();
+        }
         if self.cancellation_token.load(Ordering::SeqCst) {
             return Poll::Ready(Err(Error::cancelled("JsonRpcFuture")));
         }
@@ -390,23 +587,42 @@ impl Future for JsonRpcFuture {
             }
         }
         
-        // Poll the inner future
-        match self.inner.as_mut().poll(cx) {
-            Poll::Ready(result) => {
-                // Update completion stats
-                {
-                    let mut stats = self.stats.lock().unwrap();
-                    stats.complete();
+        // Poll the inner future, accounting for the resources it uses
+        let this = &mut *self;
+        let budget = Arc::clone(this.budget.get_or_insert_with(|| {
+            Arc::new(ResourceBudget::new(this.policy.resource_limits.clone()))
+        }));
+        let inner = &mut this.inner;
+        let polled = budget.poll(|| inner.as_mut().poll(cx));
+        let cpu_time_us = budget.cpu_time_us.load(Ordering::Relaxed);
+        {
+            let mut stats = self.stats.lock().unwrap();
+            stats.cpu_time_ms = cpu_time_us / 1000;
+            stats.peak_memory_bytes = budget.peak_memory_bytes.load(Ordering::Relaxed);
+        }
+        let result = match polled {
+            Poll::Ready(result) => result,
+            Poll::Pending => match budget.check(cpu_time_us) {
+                Ok(()) => return Poll::Pending,
+                Err(e) => {
+                    // Terminate the work now rather than when this future is dropped
+                    self.inner = Box::pin(std::future::pending());
+                    Err(e)
                 }
-                Poll::Ready(result)
-            }
-            Poll::Pending => Poll::Pending,
+            },
+        };
+        
+        // Update completion stats
+        {
+            let mut stats = self.stats.lock().unwrap();
+            stats.complete();
         }
+        Poll::Ready(result)
     }
 }
 
-/// Backpressure signals for flow control
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Backpressure signals for flow control, from least to most severe
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub enum BackpressureSignal {
     /// Normal flow - no backpressure
     None,
@@ -418,7 +634,28 @@ pub enum BackpressureSignal {
     Drop,
 }
 
+/// Snapshot of the buffer of a stream
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BufferStats {
+    /// Items produced but not consumed yet
+    pub buffered: usize,
+    /// Most items ever buffered at once
+    pub peak_buffered: usize,
+    /// Buffer size the backpressure signal is computed against
+    pub capacity: usize,
+    /// Items producers dropped on [`BackpressureSignal::Drop`]
+    pub dropped: usize,
+    /// `buffered` over `capacity`
+    pub utilization: f64,
+}
+
 /// Stream control for managing flow and lifecycle
+///
+/// Shared by a stream and its producers: producers count the items they
+/// buffer with [`item_buffered`](Self::item_buffered) and wait for
+/// [`ready`](Self::ready) before producing more, so they slow down, pause
+/// and finally drop items as the consumer falls behind instead of growing
+/// the buffer without bound. [`JsonRpcStream::channel`] does both.
 #[derive(Debug, Clone)]
 pub struct StreamControl {
     /// Cancellation token
@@ -431,6 +668,17 @@ pub struct StreamControl {
     pub max_buffer_size: usize,
     /// Current buffer size
     pub current_buffer_size: Arc<std::sync::atomic::AtomicUsize>,
+    /// Most items buffered at once
+    pub peak_buffer_size: Arc<std::sync::atomic::AtomicUsize>,
+    /// Items dropped by producers
+    pub dropped_items: Arc<std::sync::atomic::AtomicUsize>,
+    /// How long [`ready`](Self::ready) holds producers back on
+    /// [`BackpressureSignal::SlowDown`]
+    pub slow_down_delay: Duration,
+    /// Wakes producers waiting in `ready`
+    changed: Arc<tokio::sync::Notify>,
+    /// Wakes the consumer of a paused stream
+    consumer: Arc<futures::task::AtomicWaker>,
 }
 
 impl Default for StreamControl {
@@ -441,6 +689,11 @@ impl Default for StreamControl {
             backpressure: Arc::new(std::sync::Mutex::new(BackpressureSignal::None)),
             max_buffer_size: 1000,
             current_buffer_size: Arc::new(std::sync::atomic::AtomicUsize::new(0)),
+            peak_buffer_size: Arc::new(std::sync::atomic::AtomicUsize::new(0)),
+            dropped_items: Arc::new(std::sync::atomic::AtomicUsize::new(0)),
+            slow_down_delay: Duration::from_millis(10),
+            changed: Arc::new(tokio::sync::Notify::new()),
+            consumer: Arc::new(futures::task::AtomicWaker::new()),
         }
     }
 }
@@ -459,9 +712,16 @@ impl StreamControl {
         }
     }
     
+    /// Set how long producers are held back on [`BackpressureSignal::SlowDown`]
+    pub fn with_slow_down_delay(mut self, delay: Duration) -> Self {
+        self.slow_down_delay = delay;
+        self
+    }
+    
     /// Cancel the stream
     pub fn cancel(&self) {
         self.cancellation_token.store(true, Ordering::SeqCst);
+        self.wake_all();
     }
     
     /// Check if cancelled
@@ -477,6 +737,7 @@ impl StreamControl {
     /// Resume the stream
     pub fn resume(&self) {
         self.pause_token.store(false, Ordering::SeqCst);
+        self.wake_all();
     }
     
     /// Check if paused
@@ -486,7 +747,10 @@ impl StreamControl {
     
     /// Set backpressure signal
     pub fn set_backpressure(&self, signal: BackpressureSignal) {
-        *self.backpressure.lock().unwrap() = signal;
+        let previous = std::mem::replace(&mut *self.backpressure.lock().unwrap(), signal);
+        if previous != signal {
+            self.changed.notify_waiters();
+        }
     }
     
     /// Get current backpressure signal
@@ -497,6 +761,11 @@ impl StreamControl {
     /// Update buffer size
     pub fn update_buffer_size(&self, size: usize) {
         self.current_buffer_size.store(size, Ordering::SeqCst);
+        self.buffer_size_changed(size);
+    }
+    
+    fn buffer_size_changed(&self, size: usize) {
+        self.peak_buffer_size.fetch_max(size, Ordering::SeqCst);
         
         // Automatic backpressure based on buffer utilization
         let utilization = size as f64 / self.max_buffer_size as f64;
@@ -510,11 +779,152 @@ impl StreamControl {
         self.set_backpressure(signal);
     }
     
+    /// Count an item put in the buffer by a producer
+    pub fn item_buffered(&self) {
+        self.adjust_buffer_size(|size| size + 1);
+    }
+    
+    /// Count an item taken from the buffer by the consumer
+    pub fn item_consumed(&self) {
+        self.adjust_buffer_size(|size| size.saturating_sub(1));
+    }
+    
+    fn adjust_buffer_size(&self, adjust: impl Fn(usize) -> usize) {
+        let previous = self.current_buffer_size
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |size| Some(adjust(size)))
+            .unwrap_or_default();
+        self.buffer_size_changed(adjust(previous));
+    }
+    
     /// Get buffer utilization (0.0 to 1.0)
     pub fn buffer_utilization(&self) -> f64 {
         let current = self.current_buffer_size.load(Ordering::SeqCst);
         current as f64 / self.max_buffer_size as f64
     }
+    
+    /// Snapshot of the buffer occupancy
+    pub fn buffer_stats(&self) -> BufferStats {
+        BufferStats {
+            buffered: self.current_buffer_size.load(Ordering::SeqCst),
+            peak_buffered: self.peak_buffer_size.load(Ordering::SeqCst),
+            capacity: self.max_buffer_size,
+            dropped: self.dropped_items.load(Ordering::SeqCst),
+            utilization: self.buffer_utilization(),
+        }
+    }
+    
+    /// Wait until a producer may produce the next item
+    ///
+    /// Returns at once without backpressure, after
+    /// [`slow_down_delay`](Self::slow_down_delay) on
+    /// [`BackpressureSignal::SlowDown`], and once the stream is resumed or
+    /// the signal eases when paused. Returns `false` when the item should
+    /// not be produced: the stream is cancelled, or the signal is
+    /// [`BackpressureSignal::Drop`], in which case the item is counted as
+    /// dropped.
+    pub async fn ready(&self) -> bool {
+        loop {
+            // Registered before checking, so no change is missed
+            let changed = self.changed.notified();
+            if self.is_cancelled() {
+                return false;
+            }
+            if self.is_paused() {
+                changed.await;
+                continue;
+            }
+            match self.backpressure() {
+                BackpressureSignal::None => return true,
+                BackpressureSignal::SlowDown => {
+                    tokio::time::sleep(self.slow_down_delay).await;
+                    return !self.is_cancelled();
+                }
+                BackpressureSignal::Pause => changed.await,
+                BackpressureSignal::Drop => {
+                    self.dropped_items.fetch_add(1, Ordering::SeqCst);
+                    return false;
+                }
+            }
+        }
+    }
+    
+    fn wake_all(&self) {
+        self.changed.notify_waiters();
+        self.consumer.wake();
+    }
+}
+
+/// Adaptive batching of a [`JsonRpcStream`]
+///
+/// Items are passed on one by one while the stream keeps up. Once its
+/// backpressure reaches [`under`](Self::under), the items already waiting
+/// are coalesced into batches of up to [`max_items`](Self::max_items), so a
+/// consumer paying per message (a frame, a write, a flush) catches up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AdaptiveBatching {
+    /// Most items in a batch
+    pub max_items: usize,
+    /// Backpressure from which items are coalesced
+    pub under: BackpressureSignal,
+}
+
+impl Default for AdaptiveBatching {
+    fn default() -> Self {
+        Self {
+            max_items: 64,
+            under: BackpressureSignal::SlowDown,
+        }
+    }
+}
+
+impl AdaptiveBatching {
+    /// Coalesce up to `max_items` items
+    pub fn new(max_items: usize) -> Self {
+        Self {
+            max_items: max_items.max(1),
+            ..Self::default()
+        }
+    }
+    
+    /// Coalesce items from `signal` on; [`BackpressureSignal::None`] always
+    /// coalesces the items that are ready
+    pub fn under(mut self, signal: BackpressureSignal) -> Self {
+        self.under = signal;
+        self
+    }
+}
+
+/// Producer end of a [`JsonRpcStream::channel`]
+#[derive(Debug, Clone)]
+pub struct StreamProducer {
+    sender: tokio::sync::mpsc::UnboundedSender<Result<JsonRpcResponse>>,
+    control: StreamControl,
+}
+
+impl StreamProducer {
+    /// Send an item once the stream is [`ready`](StreamControl::ready) for it
+    ///
+    /// Returns `Ok(false)` when the item was dropped for backpressure, and
+    /// an error once the stream is cancelled or gone.
+    pub async fn send(&self, item: Result<JsonRpcResponse>) -> Result<bool> {
+        if !self.control.ready().await {
+            if self.control.is_cancelled() {
+                return Err(Error::cancelled("JsonRpcStream"));
+            }
+            return Ok(false);
+        }
+        self.control.item_buffered();
+        if self.sender.send(item).is_err() {
+            self.control.item_consumed();
+            return Err(Error::cancelled("JsonRpcStream"));
+        }
+        Ok(true)
+    }
+    
+    /// The control shared with the stream
+    pub fn control(&self) -> &StreamControl {
+        &self.control
+    }
 }
 
 /// Enhanced JSON-RPC Stream with priority and flow control
@@ -642,6 +1052,38 @@ impl JsonRpcStream {
         }))
     }
     
+    /// Coalesce the items of the stream under load; see [`AdaptiveBatching`]
+    ///
+    /// An error is passed on alone, after the batch built before it.
+    pub fn adaptive_batches(self, batching: AdaptiveBatching) -> Pin<Box<dyn Stream<Item = Result<Vec<JsonRpcResponse>>> + Send>> {
+        let max_items = batching.max_items.max(1);
+        let mut stream = self;
+        let mut failed: Option<Error> = None;
+        Box::pin(futures::stream::poll_fn(move |cx| {
+            if let Some(error) = failed.take() {
+                return Poll::Ready(Some(Err(error)));
+            }
+            let mut batch = match Pin::new(&mut stream).poll_next(cx) {
+                Poll::Ready(Some(Ok(response))) => vec![response],
+                Poll::Ready(Some(Err(error))) => return Poll::Ready(Some(Err(error))),
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => return Poll::Pending,
+            };
+            // Only what is ready is coalesced, so batching never adds latency
+            while batch.len() < max_items && stream.control.backpressure() >= batching.under {
+                match Pin::new(&mut stream).poll_next(cx) {
+                    Poll::Ready(Some(Ok(response))) => batch.push(response),
+                    Poll::Ready(Some(Err(error))) => {
+                        failed = Some(error);
+                        break;
+                    }
+                    Poll::Ready(None) | Poll::Pending => break,
+                }
+            }
+            Poll::Ready(Some(Ok(batch)))
+        }))
+    }
+    
     /// Create a buffered stream
     /// Note: This would require futures that produce JsonRpcResponse
     /// For now, we'll just return the original stream
@@ -661,34 +1103,17 @@ impl Stream for JsonRpcStream {
             return Poll::Ready(None);
         }
         
-        // Check for pause
+        // Check for pause, registering before checking again so a resume
+        // in between is not missed
         if self.control.is_paused() {
-            cx.waker().wake_by_ref();
-            return Poll::Pending;
-        }
-        
-        // Check backpressure
-        match self.control.backpressure() {
-            BackpressureSignal::Drop => {
-                // Skip this item due to backpressure
-                cx.waker().wake_by_ref();
+            self.control.consumer.register(cx.waker());
+            if self.control.is_paused() {
                 return Poll::Pending;
             }
-            BackpressureSignal::Pause => {
-                // Temporarily pause
-                cx.waker().wake_by_ref();
-                return Poll::Pending;
-            }
-            BackpressureSignal::SlowDown => {
-                // Add a small delay - schedule yield for next poll
-                // This is a polling context, so we just return Pending to slow down
-                return Poll::Pending;
-            }
-            BackpressureSignal::None => {
-                // Normal processing
-            }
         }
         
+        // Backpressure is for producers: consuming is what relieves it
+        
         // Update stats
         {
             let mut stats = self.stats.lock().unwrap();
@@ -700,9 +1125,7 @@ impl Stream for JsonRpcStream {
         // Poll the inner stream
         match self.inner.as_mut().poll_next(cx) {
             Poll::Ready(Some(item)) => {
-                // Update buffer size (simulated)
-                let current_size = self.control.current_buffer_size.load(Ordering::SeqCst);
-                self.control.update_buffer_size(current_size.saturating_sub(1));
+                self.control.item_consumed();
                 Poll::Ready(Some(item))
             }
             Poll::Ready(None) => {
@@ -753,6 +1176,15 @@ impl JsonRpcStream {
         Self::new(futures::stream::iter(iter))
     }
     
+    /// Create a stream fed by a [`StreamProducer`], whose sends react to
+    /// the backpressure of the stream
+    pub fn channel(control: StreamControl) -> (StreamProducer, Self) {
+        let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+        let mut stream = Self::new(futures::stream::poll_fn(move |cx| receiver.poll_recv(cx)));
+        stream.control = control.clone();
+        (StreamProducer { sender, control }, stream)
+    }
+    
     /// Create empty stream
     pub fn empty() -> Self {
         Self::new(futures::stream::empty())
@@ -809,6 +1241,70 @@ mod tests {
         assert_eq!(result.unwrap_err().kind(), crate::core::error::ErrorKind::Cancelled);
     }
 
+    #[tokio::test]
+    async fn test_service_future_linked_cancellation() {
+        let token = CancellationToken::new();
+        let future = JsonRpcFuture::new(async {
+            tokio::time::sleep(Duration::from_secs(10)).await;
+            Ok(JsonRpcResponse::success(json!(1), json!({"result": "test"})))
+        })
+        .with_cancellation(token.clone());
+
+        let canceller = tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            token.cancel();
+        });
+        let result = tokio::time::timeout(Duration::from_secs(5), future).await.unwrap();
+        assert_eq!(result.unwrap_err().kind(), crate::core::error::ErrorKind::Cancelled);
+        canceller.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_resource_limits_are_enforced() {
+        let limits = |cpu_time_ms, memory_bytes| ResourceLimits {
+            max_cpu_time_ms: Some(cpu_time_ms),
+            max_memory_bytes: Some(memory_bytes),
+            ..ResourceLimits::default()
+        };
+
+        // Blocking polls add up over the CPU time limit
+        let busy = JsonRpcFuture::with_policy(async {
+            loop {
+                std::thread::sleep(Duration::from_millis(5));
+                tokio::task::yield_now().await;
+            }
+        }, SpawnPolicy::new().with_resource_limits(limits(20, 1024)));
+        let stats = busy.stats_handle();
+        match busy.await {
+            Err(Error::ResourceExhausted { resource, limit, used }) => {
+                assert_eq!(resource, "cpu_time_ms");
+                assert_eq!(limit, 20);
+                assert!(used > 20);
+            }
+            other => panic!("Expected the CPU time to run out, got {:?}", other),
+        }
+        assert!(stats.lock().unwrap().cpu_time_ms > 20);
+
+        // Allocations are counted against the memory limit, and the budget
+        // is checked between awaits
+        let hungry = JsonRpcFuture::with_policy(async {
+            record_allocation(600)?;
+            record_release(200);
+            check_budget()?;
+            record_allocation(800)?;
+            Ok(JsonRpcResponse::success(json!(1), json!("unreachable")))
+        }, SpawnPolicy::new().with_resource_limits(limits(1000, 1024)));
+        let stats = hungry.stats_handle();
+        let error = hungry.await.unwrap_err();
+        assert_eq!(error.kind(), crate::core::error::ErrorKind::ResourceExhausted);
+        assert!(!error.is_retryable());
+        assert_eq!(stats.lock().unwrap().peak_memory_bytes, 1200);
+
+        // Outside of a future the hooks do nothing
+        assert!(record_allocation(u64::MAX).is_ok());
+        assert!(check_budget().is_ok());
+    }
+
     #[tokio::test]
     async fn test_service_future_with_timeout() {
         let future = JsonRpcFuture::new(async {
@@ -899,6 +1395,120 @@ mod tests {
         assert_eq!(policy.custom_config.get("custom"), Some(&json!("value")));
     }
 
+    #[tokio::test]
+    async fn test_retry_recreates_operation() {
+        let attempts = Arc::new(std::sync::atomic::AtomicU32::new(0));
+        let counter = Arc::clone(&attempts);
+        let policy = RetryPolicy::new(3, Duration::from_millis(1)).with_jitter_factor(0.0);
+
+        let future = JsonRpcFuture::retry(move || {
+            let attempt = counter.fetch_add(1, Ordering::SeqCst);
+            async move {
+                if attempt < 2 {
+                    Err(Error::transport("connection dropped"))
+                } else {
+                    Ok(JsonRpcResponse::success(json!(1), json!("ok")))
+                }
+            }
+        }, policy);
+        let stats = Arc::clone(&future.stats);
+
+        let response = future.await.unwrap();
+        assert_eq!(response.result, Some(json!("ok")));
+        assert_eq!(attempts.load(Ordering::SeqCst), 3);
+        assert_eq!(stats.lock().unwrap().retry_count, 2);
+    }
+
+    #[tokio::test]
+    async fn test_retry_stops_on_permanent_errors_and_budget() {
+        let attempts = Arc::new(std::sync::atomic::AtomicU32::new(0));
+        let counter = Arc::clone(&attempts);
+        let result = JsonRpcFuture::retry(move || {
+            counter.fetch_add(1, Ordering::SeqCst);
+            async { Err(Error::invalid_params("bad input")) }
+        }, RetryPolicy::new(5, Duration::from_millis(1))).await;
+        assert!(matches!(result, Err(Error::InvalidParams { .. })));
+        assert_eq!(attempts.load(Ordering::SeqCst), 1);
+
+        let attempts = Arc::new(std::sync::atomic::AtomicU32::new(0));
+        let counter = Arc::clone(&attempts);
+        let policy = RetryPolicy::new(10, Duration::from_millis(30))
+            .with_backoff_multiplier(1.0)
+            .with_jitter_factor(0.0)
+            .with_max_elapsed_time(Duration::from_millis(75));
+        let result = JsonRpcFuture::retry(move || {
+            counter.fetch_add(1, Ordering::SeqCst);
+            async { Err(Error::timeout("call", Duration::from_millis(1))) }
+        }, policy).await;
+        assert!(matches!(result, Err(Error::Timeout { .. })));
+        assert_eq!(attempts.load(Ordering::SeqCst), 3);
+
+        let attempts = Arc::new(std::sync::atomic::AtomicU32::new(0));
+        let counter = Arc::clone(&attempts);
+        let result = JsonRpcFuture::retry(move || {
+            counter.fetch_add(1, Ordering::SeqCst);
+            async { Err(Error::transport("unreachable")) }
+        }, RetryPolicy::new(2, Duration::from_millis(1))).await;
+        assert!(result.is_err());
+        assert_eq!(attempts.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_producer_backpressure_and_adaptive_batches() {
+        let item = |n: i64| Ok(JsonRpcResponse::success(json!(n), json!(n)));
+        let control = StreamControl::with_buffer_size(10).with_slow_down_delay(Duration::from_millis(1));
+        let (producer, stream) = JsonRpcStream::channel(control.clone());
+
+        // Nobody consumes: the producer pauses with 8 items buffered
+        let feeding = tokio::spawn(async move {
+            for n in 0..12 {
+                assert!(producer.send(item(n)).await.unwrap());
+            }
+            producer
+        });
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(!feeding.is_finished());
+        assert_eq!(control.buffer_stats().buffered, 8);
+        assert_eq!(control.backpressure(), BackpressureSignal::Pause);
+
+        // Waiting items are coalesced under load, and consuming them lets
+        // the producer go on
+        let mut batches = stream.adaptive_batches(AdaptiveBatching::new(4));
+        assert_eq!(batches.next().await.unwrap().unwrap().len(), 4);
+        drop(feeding.await.unwrap());
+        let mut received = 4;
+        while let Some(batch) = batches.next().await {
+            let batch = batch.unwrap();
+            assert!(batch.len() <= 4);
+            received += batch.len();
+        }
+        assert_eq!(received, 12);
+        let stats = control.buffer_stats();
+        assert_eq!((stats.buffered, stats.peak_buffered, stats.dropped), (0, 8, 0));
+
+        // Overloaded streams shed items, cancelled ones refuse them
+        let (producer, _stream) = JsonRpcStream::channel(StreamControl::new());
+        producer.control().set_backpressure(BackpressureSignal::Drop);
+        assert!(!producer.send(item(1)).await.unwrap());
+        assert_eq!(producer.control().buffer_stats().dropped, 1);
+        producer.control().cancel();
+        assert!(producer.send(item(2)).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_paused_stream_wakes_on_resume() {
+        let mut stream = JsonRpcStream::from_iter(vec![Ok(JsonRpcResponse::success(json!(1), json!(1)))]);
+        let control = stream.control().clone();
+        control.pause();
+        let resumer = tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            control.resume();
+        });
+        let item = tokio::time::timeout(Duration::from_secs(5), stream.next()).await.unwrap();
+        assert!(item.unwrap().is_ok());
+        resumer.await.unwrap();
+    }
+
     #[test]
     fn test_backpressure_signals() {
         let control = StreamControl::with_buffer_size(100);
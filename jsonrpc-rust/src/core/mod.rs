@@ -10,6 +10,12 @@ pub mod error;
 pub mod types;
 pub mod traits;
 pub mod future;
+pub mod executor;
+pub mod serialization;
+pub mod trace;
+pub mod context;
+pub mod deadline;
+pub mod session;
 
 // Organized public exports
 pub mod core_types {
@@ -36,9 +42,20 @@ pub mod core_traits {
     pub use super::traits::ServiceInfo as ServiceInfoTrait;
 }
 
+pub mod serializers {
+    //! Message serializers and wire formats
+    pub use super::serialization::{SerializationFormat, JsonSerializer};
+    
+    #[cfg(feature = "msgpack")]
+    pub use super::serialization::MessagePackSerializer;
+    
+    #[cfg(feature = "cbor")]
+    pub use super::serialization::CborSerializer;
+}
+
 pub mod errors {
     //! Error handling types
-    pub use super::error::{Error, ErrorKind, JsonRpcError, JsonRpcErrorCode, RetryPolicy};
+    pub use super::error::{Error, ErrorData, ErrorKind, JsonRpcError, JsonRpcErrorCode, RetryPolicy};
     
     #[cfg(feature = "debug-location")]
     pub use super::error::SourceLocation;
@@ -47,8 +64,11 @@ pub mod errors {
 pub mod streaming {
     //! Streaming and future types
     pub use super::future::{
-        JsonRpcFuture, JsonRpcStream, ServiceStream, StreamControl, BackpressureSignal
+        JsonRpcFuture, JsonRpcStream, ServiceStream, StreamControl, BackpressureSignal,
+        AdaptiveBatching, BufferStats, StreamProducer,
+        check_budget, record_allocation, record_release
     };
+    pub use super::executor::{PriorityExecutor, ExecutorConfig, ExecutorStats, TaskHandle};
 }
 
 // TRN integration (conditional)
@@ -75,6 +95,8 @@ pub mod prelude {
         ServiceContext, AuthContext, MessageId, 
         ChannelBidirectionalStream, ChannelBidirectionalStreamPeer
     };
+    pub use super::session::{Session, SessionHooks};
+    pub use super::context::Context;
     
     // Core traits (using new trait design)
     pub use super::traits::{
@@ -0,0 +1,268 @@
+//! Message serializers
+//!
+//! JSON is always available. MessagePack (`msgpack` feature) and CBOR
+//! (`cbor` feature) trade readability for smaller messages that are
+//! cheaper to parse, which pays off on high-throughput internal links.
+//!
+//! [`SerializationFormat`] names a format, serializes any value in it and
+//! picks the format two peers have in common during connection setup.
+
+use std::fmt;
+use std::str::FromStr;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::core::error::{Error, Result};
+use crate::core::traits::MessageSerializer;
+use crate::core::types::{JsonRpcRequest, JsonRpcResponse};
+
+/// A wire format for JSON-RPC messages
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum SerializationFormat {
+    /// Textual JSON
+    #[serde(rename = "json")]
+    Json,
+    /// MessagePack, with structs encoded as maps
+    #[serde(rename = "msgpack")]
+    MessagePack,
+    /// CBOR (RFC 8949)
+    #[serde(rename = "cbor")]
+    Cbor,
+}
+
+impl SerializationFormat {
+    /// Formats compiled into this build, most compact first
+    pub fn supported() -> Vec<Self> {
+        [Self::MessagePack, Self::Cbor, Self::Json]
+            .into_iter()
+            .filter(|format| format.is_supported())
+            .collect()
+    }
+
+    /// Whether this build can encode and decode the format
+    pub fn is_supported(self) -> bool {
+        match self {
+            Self::Json => true,
+            Self::MessagePack => cfg!(feature = "msgpack"),
+            Self::Cbor => cfg!(feature = "cbor"),
+        }
+    }
+
+    /// Name used in negotiation
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Json => "json",
+            Self::MessagePack => "msgpack",
+            Self::Cbor => "cbor",
+        }
+    }
+
+    /// MIME type of messages in this format
+    pub fn content_type(self) -> &'static str {
+        match self {
+            Self::Json => "application/json",
+            Self::MessagePack => "application/msgpack",
+            Self::Cbor => "application/cbor",
+        }
+    }
+
+    /// Whether messages in this format are binary rather than UTF-8 text
+    pub fn is_binary(self) -> bool {
+        self != Self::Json
+    }
+
+    /// The first of `offered` that is also in `accepted` and supported here
+    pub fn negotiate(offered: &[Self], accepted: &[Self]) -> Option<Self> {
+        offered.iter()
+            .copied()
+            .find(|format| format.is_supported() && accepted.contains(format))
+    }
+
+    /// Serializer for this format
+    pub fn serializer(self) -> Result<Box<dyn MessageSerializer>> {
+        match self {
+            Self::Json => Ok(Box::new(JsonSerializer)),
+            #[cfg(feature = "msgpack")]
+            Self::MessagePack => Ok(Box::new(MessagePackSerializer)),
+            #[cfg(feature = "cbor")]
+            Self::Cbor => Ok(Box::new(CborSerializer)),
+            #[allow(unreachable_patterns)]
+            _ => Err(self.unsupported()),
+        }
+    }
+
+    /// Serialize any value in this format
+    pub fn encode<T: Serialize + ?Sized>(self, value: &T) -> Result<Vec<u8>> {
+        match self {
+            Self::Json => Ok(serde_json::to_vec(value)?),
+            #[cfg(feature = "msgpack")]
+            Self::MessagePack => rmp_serde::to_vec_named(value)
+                .map_err(|e| Error::serialization(format!("MessagePack encoding failed: {}", e))),
+            #[cfg(feature = "cbor")]
+            Self::Cbor => {
+                let mut buffer = Vec::new();
+                ciborium::ser::into_writer(value, &mut buffer)
+                    .map_err(|e| Error::serialization(format!("CBOR encoding failed: {}", e)))?;
+                Ok(buffer)
+            }
+            #[allow(unreachable_patterns)]
+            _ => Err(self.unsupported()),
+        }
+    }
+
+    /// Deserialize a value in this format
+    pub fn decode<T: DeserializeOwned>(self, data: &[u8]) -> Result<T> {
+        match self {
+            Self::Json => Ok(serde_json::from_slice(data)?),
+            #[cfg(feature = "msgpack")]
+            Self::MessagePack => rmp_serde::from_slice(data)
+                .map_err(|e| Error::serialization(format!("MessagePack decoding failed: {}", e))),
+            #[cfg(feature = "cbor")]
+            Self::Cbor => ciborium::de::from_reader(data)
+                .map_err(|e| Error::serialization(format!("CBOR decoding failed: {}", e))),
+            #[allow(unreachable_patterns)]
+            _ => Err(self.unsupported()),
+        }
+    }
+
+    #[allow(dead_code)]
+    fn unsupported(self) -> Error {
+        Error::configuration(format!("Serialization format '{}' is not enabled in this build", self))
+    }
+}
+
+impl fmt::Display for SerializationFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for SerializationFormat {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "json" => Ok(Self::Json),
+            "msgpack" | "messagepack" => Ok(Self::MessagePack),
+            "cbor" => Ok(Self::Cbor),
+            _ => Err(Error::validation(format!("Unknown serialization format: {}", s))),
+        }
+    }
+}
+
+/// Implement [`MessageSerializer`] for a serializer of one format
+macro_rules! format_serializer {
+    ($(#[$meta:meta])* $name:ident, $format:expr) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, Copy, Default)]
+        pub struct $name;
+
+        impl MessageSerializer for $name {
+            fn serialize_request(&self, request: &JsonRpcRequest) -> Result<Vec<u8>> {
+                $format.encode(request)
+            }
+
+            fn deserialize_request(&self, data: &[u8]) -> Result<JsonRpcRequest> {
+                $format.decode(data)
+            }
+
+            fn serialize_response(&self, response: &JsonRpcResponse) -> Result<Vec<u8>> {
+                $format.encode(response)
+            }
+
+            fn deserialize_response(&self, data: &[u8]) -> Result<JsonRpcResponse> {
+                $format.decode(data)
+            }
+
+            fn format_name(&self) -> &str {
+                $format.name()
+            }
+
+            fn content_type(&self) -> &str {
+                $format.content_type()
+            }
+        }
+    };
+}
+
+format_serializer!(
+    /// JSON serializer
+    JsonSerializer, SerializationFormat::Json
+);
+
+#[cfg(feature = "msgpack")]
+format_serializer!(
+    /// MessagePack serializer
+    MessagePackSerializer, SerializationFormat::MessagePack
+);
+
+#[cfg(feature = "cbor")]
+format_serializer!(
+    /// CBOR serializer
+    CborSerializer, SerializationFormat::Cbor
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::error::JsonRpcError;
+    use serde_json::{json, Value};
+
+    fn request() -> JsonRpcRequest {
+        JsonRpcRequest::with_id(
+            "tools.run",
+            Some(json!({ "name": "grep", "args": ["-n", "x"], "limit": 10, "ratio": 0.5, "offset": -3, "dry": true, "tag": null })),
+            json!(7),
+        )
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let response = JsonRpcResponse::error(json!("a-1"), JsonRpcError::invalid_params("missing name"));
+        let notification = JsonRpcRequest::notification("progress", None);
+
+        for format in SerializationFormat::supported() {
+            let serializer = format.serializer().unwrap();
+            assert_eq!(serializer.format_name(), format.name());
+
+            let data = serializer.serialize_request(&request()).unwrap();
+            assert_eq!(serializer.deserialize_request(&data).unwrap(), request(), "{}", format);
+            let data = serializer.serialize_request(&notification).unwrap();
+            assert_eq!(serializer.deserialize_request(&data).unwrap(), notification, "{}", format);
+            let data = serializer.serialize_response(&response).unwrap();
+            assert_eq!(serializer.deserialize_response(&data).unwrap(), response, "{}", format);
+        }
+    }
+
+    #[test]
+    fn test_cross_format() {
+        // A message relayed through every format comes out unchanged
+        let original = serde_json::to_value(request()).unwrap();
+        let mut value = original.clone();
+        for format in SerializationFormat::supported() {
+            value = format.decode::<Value>(&format.encode(&value).unwrap()).unwrap();
+        }
+        assert_eq!(value, original);
+
+        let binary: Vec<_> = SerializationFormat::supported().into_iter().filter(|f| f.is_binary()).collect();
+        for format in binary {
+            let encoded = format.encode(&original).unwrap();
+            assert!(encoded.len() < serde_json::to_vec(&original).unwrap().len(), "{} is not smaller", format);
+            assert!(SerializationFormat::Json.decode::<Value>(&encoded).is_err());
+        }
+    }
+
+    #[test]
+    fn test_negotiate() {
+        use SerializationFormat::*;
+        assert_eq!(SerializationFormat::negotiate(&[Json], &[Json, Cbor]), Some(Json));
+        assert_eq!(SerializationFormat::negotiate(&[Cbor, MessagePack], &[Json]), None);
+        assert_eq!(
+            SerializationFormat::negotiate(&[MessagePack, Cbor, Json], &[Cbor, MessagePack, Json]),
+            SerializationFormat::supported().first().copied(),
+        );
+        assert_eq!("MessagePack".parse::<SerializationFormat>().unwrap(), MessagePack);
+        assert!("yaml".parse::<SerializationFormat>().is_err());
+        assert_eq!(serde_json::to_value(MessagePack).unwrap(), json!("msgpack"));
+    }
+}
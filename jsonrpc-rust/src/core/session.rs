@@ -0,0 +1,359 @@
+//! Sessions spanning the lifetime of a connection
+//!
+//! Every connection a server answers gets a [`Session`], found in the
+//! [`ServiceContext::session`] of each of its calls. A session carries an
+//! id and a typed state container, so handlers can keep per-connection
+//! state such as subscriptions or a cached authentication:
+//!
+//! ```rust
+//! use jsonrpc_rust::core::types::ServiceContext;
+//!
+//! struct Greeted(String);
+//!
+//! fn greet(context: &ServiceContext, name: &str) -> String {
+//!     let Some(session) = &context.session else {
+//!         return format!("Hello {}", name);
+//!     };
+//!     let first = session.get_or_insert_with(|| Greeted(name.to_string()));
+//!     format!("Hello {}, first was {}", name, first.0)
+//! }
+//! ```
+//!
+//! [`SessionHooks`] run when a connection opens and closes. With
+//! resumption enabled, the session of a closed connection is kept for a
+//! while: a client that reconnects calls [`RESUME_SESSION_METHOD`] with
+//! the token [`SESSION_METHOD`] gave it and gets its id and state back.
+
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+
+use async_trait::async_trait;
+use dashmap::DashMap;
+use parking_lot::RwLock;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use super::error::{Error, Result};
+use super::traits::MethodHandler;
+use super::types::{JsonRpcRequest, JsonRpcResponse, ServiceContext};
+
+/// Method answering the id and resumption token of the caller's session
+pub const SESSION_METHOD: &str = "$/session";
+
+/// Method giving the caller's connection a suspended session back; params
+/// are `{"token": ...}`
+pub const RESUME_SESSION_METHOD: &str = "$/resumeSession";
+
+/// What a session resumption carries over
+struct Current {
+    id: String,
+    resume_token: String,
+    state: HashMap<TypeId, Arc<dyn Any + Send + Sync>>,
+}
+
+struct Inner {
+    peer: Value,
+    connected_at: SystemTime,
+    current: RwLock<Current>,
+}
+
+/// State of one connection; clones share it
+#[derive(Clone)]
+pub struct Session {
+    inner: Arc<Inner>,
+}
+
+impl Session {
+    /// Create the session of a connection with `peer`
+    pub fn new(peer: Value) -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                peer,
+                connected_at: SystemTime::now(),
+                current: RwLock::new(Current {
+                    id: Uuid::new_v4().to_string(),
+                    resume_token: Uuid::new_v4().to_string(),
+                    state: HashMap::new(),
+                }),
+            }),
+        }
+    }
+
+    /// Session id; a resumed session takes the id it had before
+    pub fn id(&self) -> String {
+        self.inner.current.read().id.clone()
+    }
+
+    /// The other end of the connection, as the transport names it
+    pub fn peer(&self) -> &Value {
+        &self.inner.peer
+    }
+
+    /// When the connection opened
+    pub fn connected_at(&self) -> SystemTime {
+        self.inner.connected_at
+    }
+
+    /// Token resuming this session on another connection; it changes on
+    /// every resumption
+    pub fn resume_token(&self) -> String {
+        self.inner.current.read().resume_token.clone()
+    }
+
+    /// Store a value, replacing the one of the same type
+    pub fn insert<T: Send + Sync + 'static>(&self, value: T) -> Option<Arc<T>> {
+        let previous = self.inner.current.write().state.insert(TypeId::of::<T>(), Arc::new(value));
+        previous.and_then(|previous| previous.downcast().ok())
+    }
+
+    /// The stored value of type `T`
+    pub fn get<T: Send + Sync + 'static>(&self) -> Option<Arc<T>> {
+        let state = self.inner.current.read().state.get(&TypeId::of::<T>()).cloned();
+        state.and_then(|value| value.downcast().ok())
+    }
+
+    /// The stored value of type `T`, storing the one `init` makes first if
+    /// there is none
+    pub fn get_or_insert_with<T: Send + Sync + 'static>(&self, init: impl FnOnce() -> T) -> Arc<T> {
+        if let Some(value) = self.get() {
+            return value;
+        }
+        // `init` runs unlocked, so it may use the session too
+        let made: Arc<dyn Any + Send + Sync> = Arc::new(init());
+        let value = self.inner.current.write().state.entry(TypeId::of::<T>())
+            .or_insert(made)
+            .clone();
+        value.downcast().unwrap_or_else(|_| unreachable!("state is keyed by type"))
+    }
+
+    /// Remove the stored value of type `T`
+    pub fn remove<T: Send + Sync + 'static>(&self) -> Option<Arc<T>> {
+        let previous = self.inner.current.write().state.remove(&TypeId::of::<T>());
+        previous.and_then(|previous| previous.downcast().ok())
+    }
+
+    /// Take over the id and state of `suspended`, with a new token
+    fn adopt(&self, suspended: &Session) {
+        let taken = std::mem::replace(&mut *suspended.inner.current.write(), Current {
+            id: String::new(),
+            resume_token: String::new(),
+            state: HashMap::new(),
+        });
+        *self.inner.current.write() = Current {
+            resume_token: Uuid::new_v4().to_string(),
+            ..taken
+        };
+    }
+
+    fn describe(&self) -> Value {
+        let current = self.inner.current.read();
+        json!({"id": current.id, "resume_token": current.resume_token})
+    }
+}
+
+impl std::fmt::Debug for Session {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let current = self.inner.current.read();
+        f.debug_struct("Session")
+            .field("id", &current.id)
+            .field("peer", &self.inner.peer)
+            .field("values", &current.state.len())
+            .finish()
+    }
+}
+
+/// Callbacks on the lifecycle of sessions
+#[async_trait]
+pub trait SessionHooks: Send + Sync {
+    /// A connection opened; an error closes it before any call is read
+    async fn on_connect(&self, _session: &Session) -> Result<()> {
+        Ok(())
+    }
+
+    /// A connection closed; its session may still be resumed
+    async fn on_disconnect(&self, _session: &Session) {}
+}
+
+/// Opens and closes the sessions of a server, keeping closed ones for
+/// resumption if enabled
+#[derive(Default)]
+pub struct SessionManager {
+    hooks: Vec<Arc<dyn SessionHooks>>,
+    resume_ttl: Option<Duration>,
+    /// Closed sessions by resumption token
+    suspended: DashMap<String, (Session, Instant)>,
+}
+
+impl SessionManager {
+    /// Create a manager without hooks or resumption
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Run `hooks` on every session, after those added before
+    pub fn with_hooks(mut self, hooks: Arc<dyn SessionHooks>) -> Self {
+        self.hooks.push(hooks);
+        self
+    }
+
+    /// Keep the sessions of closed connections for `ttl`, for resumption
+    pub fn with_resumption(mut self, ttl: Duration) -> Self {
+        self.resume_ttl = Some(ttl);
+        self
+    }
+
+    /// Whether closed sessions may be resumed
+    pub fn is_resumable(&self) -> bool {
+        self.resume_ttl.is_some()
+    }
+
+    /// Open the session of a new connection, failing if a hook refuses it
+    pub async fn open(&self, peer: Value) -> Result<Session> {
+        let session = Session::new(peer);
+        for hooks in &self.hooks {
+            hooks.on_connect(&session).await?;
+        }
+        Ok(session)
+    }
+
+    /// Close the session of a connection that ended
+    pub async fn close(&self, session: &Session) {
+        for hooks in &self.hooks {
+            hooks.on_disconnect(session).await;
+        }
+        if let Some(ttl) = self.resume_ttl {
+            let now = Instant::now();
+            self.suspended.retain(|_, (_, expires)| *expires > now);
+            self.suspended.insert(session.resume_token(), (session.clone(), now + ttl));
+        }
+    }
+
+    /// Give `session` the id and state of the closed session `token` resumes
+    pub fn resume(&self, session: &Session, token: &str) -> Result<()> {
+        let Some((_, (suspended, expires))) = self.suspended.remove(token) else {
+            return Err(Error::resource_not_found(format!("session for token {}", token)));
+        };
+        if expires <= Instant::now() {
+            return Err(Error::resource_not_found(format!("session for token {}", token)));
+        }
+        session.adopt(&suspended);
+        Ok(())
+    }
+
+    /// Sessions waiting to be resumed, expired ones included until the
+    /// next close
+    pub fn suspended(&self) -> usize {
+        self.suspended.len()
+    }
+}
+
+impl std::fmt::Debug for SessionManager {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SessionManager")
+            .field("hooks", &self.hooks.len())
+            .field("resume_ttl", &self.resume_ttl)
+            .field("suspended", &self.suspended.len())
+            .finish()
+    }
+}
+
+/// Handler of [`SESSION_METHOD`] and [`RESUME_SESSION_METHOD`]
+pub struct SessionMethods {
+    manager: Arc<SessionManager>,
+}
+
+impl SessionMethods {
+    /// Answer the session methods with `manager`
+    pub fn new(manager: Arc<SessionManager>) -> Self {
+        Self { manager }
+    }
+}
+
+#[async_trait]
+impl MethodHandler for SessionMethods {
+    async fn handle_method(&self, request: &JsonRpcRequest, context: &ServiceContext) -> Result<JsonRpcResponse> {
+        let session = context.session.as_ref()
+            .ok_or_else(|| Error::service("Call is not part of a session"))?;
+        if request.method == RESUME_SESSION_METHOD {
+            let token = request.params.as_ref()
+                .and_then(|params| params.get("token"))
+                .and_then(Value::as_str)
+                .ok_or_else(|| Error::invalid_params("Expected {\"token\": string}"))?;
+            self.manager.resume(session, token)?;
+        }
+        let id = request.id.clone().unwrap_or(Value::Null);
+        Ok(JsonRpcResponse::success(id, session.describe()))
+    }
+
+    fn supported_methods(&self) -> Vec<String> {
+        vec![SESSION_METHOD.to_string(), RESUME_SESSION_METHOD.to_string()]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    struct Subscriptions(Vec<String>);
+
+    #[derive(Default)]
+    struct Counting {
+        connected: AtomicUsize,
+        disconnected: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl SessionHooks for Counting {
+        async fn on_connect(&self, session: &Session) -> Result<()> {
+            if session.peer() == "banned" {
+                return Err(Error::authorization("banned"));
+            }
+            self.connected.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        }
+
+        async fn on_disconnect(&self, _session: &Session) {
+            self.disconnected.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    fn call(method: &str, params: Value, session: &Session) -> (JsonRpcRequest, ServiceContext) {
+        let request = JsonRpcRequest::with_id(method, Some(params), json!(1));
+        (request, ServiceContext::new("req-1").with_session(session.clone()))
+    }
+
+    #[tokio::test]
+    async fn test_state_hooks_and_resumption() {
+        let hooks = Arc::new(Counting::default());
+        let manager = Arc::new(SessionManager::new()
+            .with_hooks(hooks.clone())
+            .with_resumption(Duration::from_secs(60)));
+        let methods = SessionMethods::new(Arc::clone(&manager));
+        assert!(manager.open(json!("banned")).await.is_err());
+
+        let first = manager.open(json!("10.0.0.1:4000")).await.unwrap();
+        assert!(first.insert(Subscriptions(vec!["prices".into()])).is_none());
+        assert_eq!(first.get_or_insert_with(|| Subscriptions(Vec::new())).0, ["prices"]);
+        let (request, context) = call(SESSION_METHOD, json!({}), &first);
+        let described = methods.handle_method(&request, &context).await.unwrap().result.unwrap();
+        let token = described["resume_token"].as_str().unwrap().to_string();
+        manager.close(&first).await;
+        assert_eq!(manager.suspended(), 1);
+
+        // The reconnected client gets its id and state back, once
+        let second = manager.open(json!("10.0.0.1:4001")).await.unwrap();
+        assert!(second.get::<Subscriptions>().is_none());
+        let (request, context) = call(RESUME_SESSION_METHOD, json!({"token": token}), &second);
+        let resumed = methods.handle_method(&request, &context).await.unwrap().result.unwrap();
+        assert_eq!(resumed["id"], described["id"]);
+        assert_ne!(resumed["resume_token"], described["resume_token"]);
+        assert_eq!(second.get::<Subscriptions>().unwrap().0, ["prices"]);
+        assert!(methods.handle_method(&request, &context).await.is_err());
+
+        assert_eq!(hooks.connected.load(Ordering::SeqCst), 2);
+        assert_eq!(hooks.disconnected.load(Ordering::SeqCst), 1);
+    }
+}
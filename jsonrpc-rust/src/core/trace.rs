@@ -0,0 +1,315 @@
+//! Distributed trace context
+//!
+//! Calls carry the [W3C trace context](https://www.w3.org/TR/trace-context/)
+//! of their caller in the `traceparent` and `tracestate` members of the
+//! request `meta`; over HTTP the headers of the same names work too.
+//!
+//! The router opens a `jsonrpc.dispatch` span for every call, continuing
+//! the caller's trace or starting a new one, and makes its
+//! [`TraceContext`] current while the handler runs. Calls the handler makes
+//! with a [`JsonRpcClient`](crate::protocol::JsonRpcClient) carry it on, and
+//! [`ResponseMetaInfo`](super::types::ResponseMetaInfo) picks up its trace id.
+//!
+//! Code outside a handler starts a trace of its own with [`TraceContext::scope`]:
+//!
+//! ```rust
+//! use jsonrpc_rust::core::trace::TraceContext;
+//!
+//! # async fn example() {
+//! let trace = TraceContext::root();
+//! let trace_id = trace.trace_id.clone();
+//! trace.scope(async move {
+//!     assert_eq!(TraceContext::current().unwrap().trace_id, trace_id);
+//! }).await;
+//! # }
+//! ```
+//!
+//! With the `otlp` feature, [`init_otlp`] exports the spans to an
+//! OpenTelemetry collector, and trace ids are those of the exported spans.
+
+use std::future::Future;
+
+use serde_json::Value;
+
+use super::types::{JsonRpcRequest, ServiceContext};
+
+/// Request meta and metadata key of the trace parent
+pub const TRACEPARENT: &str = "traceparent";
+
+/// Request meta and metadata key of the vendor trace state
+pub const TRACESTATE: &str = "tracestate";
+
+/// Only version of `traceparent` produced
+const VERSION: &str = "00";
+
+tokio::task_local! {
+    static CURRENT: TraceContext;
+}
+
+/// Position of a call in a distributed trace
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceContext {
+    /// Trace id, 32 lowercase hex digits
+    pub trace_id: String,
+    /// Id of the span, 16 lowercase hex digits
+    pub span_id: String,
+    /// Whether the caller records the trace
+    pub sampled: bool,
+    /// Vendor entries, passed on untouched
+    pub trace_state: Option<String>,
+}
+
+impl TraceContext {
+    /// Start a new sampled trace
+    pub fn root() -> Self {
+        Self {
+            trace_id: format!("{:032x}", rand::random::<u128>().max(1)),
+            span_id: new_span_id(),
+            sampled: true,
+            trace_state: None,
+        }
+    }
+
+    /// A span of the same trace, child of this one
+    pub fn child(&self) -> Self {
+        Self {
+            span_id: new_span_id(),
+            ..self.clone()
+        }
+    }
+
+    /// Parse a `traceparent` value; `None` if it is malformed
+    pub fn parse(traceparent: &str) -> Option<Self> {
+        let mut parts = traceparent.trim().split('-');
+        let version = parts.next()?;
+        let trace_id = parts.next()?;
+        let span_id = parts.next()?;
+        let flags = parts.next()?;
+
+        // Later versions may append fields, version 00 may not
+        let rest_allowed = version != VERSION;
+        if !is_hex(version, 2) || version == "ff" || (!rest_allowed && parts.next().is_some()) {
+            return None;
+        }
+        if !is_id(trace_id, 32) || !is_id(span_id, 16) || !is_hex(flags, 2) {
+            return None;
+        }
+        let flags = u8::from_str_radix(flags, 16).ok()?;
+
+        Some(Self {
+            trace_id: trace_id.to_string(),
+            span_id: span_id.to_string(),
+            sampled: flags & 0x01 != 0,
+            trace_state: None,
+        })
+    }
+
+    /// Set the vendor trace state
+    pub fn with_trace_state(mut self, trace_state: impl Into<String>) -> Self {
+        self.trace_state = Some(trace_state.into());
+        self
+    }
+
+    /// The `traceparent` value of this context
+    pub fn traceparent(&self) -> String {
+        format!("{}-{}-{}-{:02x}", VERSION, self.trace_id, self.span_id, u8::from(self.sampled))
+    }
+
+    /// The caller's context, from the request `meta` or else from the
+    /// metadata the transport put in the service context
+    pub fn extract(request: &JsonRpcRequest, context: &ServiceContext) -> Option<Self> {
+        let (parent, state) = match request.meta_value(TRACEPARENT) {
+            Some(parent) => (parent, request.meta_value(TRACESTATE)),
+            None => (context.metadata.get(TRACEPARENT)?, context.metadata.get(TRACESTATE)),
+        };
+        let trace = Self::parse(parent.as_str()?)?;
+        Some(match state.and_then(Value::as_str) {
+            Some(state) => trace.with_trace_state(state),
+            None => trace,
+        })
+    }
+
+    /// Carry this context in the `meta` of a request
+    pub fn inject(&self, request: &mut JsonRpcRequest) {
+        let meta = request.meta.get_or_insert_with(Default::default);
+        meta.insert(TRACEPARENT.to_string(), Value::String(self.traceparent()));
+        if let Some(ref state) = self.trace_state {
+            meta.insert(TRACESTATE.to_string(), Value::String(state.clone()));
+        }
+    }
+
+    /// The context of the call being handled, if any
+    pub fn current() -> Option<Self> {
+        CURRENT.try_with(Clone::clone).ok()
+    }
+
+    /// Run `future` with this context current
+    pub async fn scope<F: Future>(self, future: F) -> F::Output {
+        CURRENT.scope(self, future).await
+    }
+}
+
+impl std::fmt::Display for TraceContext {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.traceparent())
+    }
+}
+
+fn new_span_id() -> String {
+    format!("{:016x}", rand::random::<u64>().max(1))
+}
+
+fn is_hex(value: &str, len: usize) -> bool {
+    value.len() == len && value.bytes().all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b))
+}
+
+/// Ids are lowercase hex and never all zeros
+fn is_id(value: &str, len: usize) -> bool {
+    is_hex(value, len) && value.bytes().any(|b| b != b'0')
+}
+
+/// Open the span of a call and the context its handler runs in
+pub(crate) fn dispatch_span(request: &JsonRpcRequest, context: &ServiceContext) -> (tracing::Span, TraceContext) {
+    let parent = TraceContext::extract(request, context);
+    let trace = parent.as_ref().map_or_else(TraceContext::root, TraceContext::child);
+    let span = tracing::info_span!(
+        "jsonrpc.dispatch",
+        rpc.method = %request.method,
+        request_id = %context.request_id,
+        trace_id = %trace.trace_id,
+        span_id = %trace.span_id,
+    );
+
+    #[cfg(feature = "otlp")]
+    let trace = otlp::adopt(&span, parent.as_ref()).unwrap_or(trace);
+    (span, trace)
+}
+
+/// Open the span of an outgoing call and the context it carries, when
+/// made while handling a call
+pub(crate) fn call_span(method: &str) -> (tracing::Span, Option<TraceContext>) {
+    let span = tracing::debug_span!("jsonrpc.call", rpc.method = %method);
+    let trace = TraceContext::current().map(|current| {
+        #[cfg(feature = "otlp")]
+        if let Some(own) = otlp::adopt(&span, None) {
+            return own.with_state_of(&current);
+        }
+        current.child()
+    });
+    (span, trace)
+}
+
+#[cfg(feature = "otlp")]
+pub use otlp::init_otlp;
+
+#[cfg(feature = "otlp")]
+mod otlp {
+    use opentelemetry::trace::{SpanContext, SpanId, TraceContextExt, TraceFlags, TraceId, TraceState, TracerProvider};
+    use opentelemetry_otlp::WithExportConfig;
+    use opentelemetry_sdk::trace::SdkTracerProvider;
+    use tracing_opentelemetry::OpenTelemetrySpanExt;
+    use tracing_subscriber::layer::SubscriberExt;
+    use tracing_subscriber::util::SubscriberInitExt;
+
+    use super::TraceContext;
+    use crate::core::error::{Error, Result};
+
+    /// Export spans to the OTLP/gRPC collector at `endpoint`
+    ///
+    /// Installs the global `tracing` subscriber. Keep the provider and call
+    /// its `shutdown` before exiting to flush the spans not yet exported.
+    pub fn init_otlp(endpoint: &str, service_name: &str) -> Result<SdkTracerProvider> {
+        let exporter = opentelemetry_otlp::SpanExporter::builder()
+            .with_tonic()
+            .with_endpoint(endpoint)
+            .build()
+            .map_err(|e| Error::configuration(format!("Invalid OTLP exporter: {}", e)))?;
+        let provider = SdkTracerProvider::builder()
+            .with_batch_exporter(exporter)
+            .with_resource(opentelemetry_sdk::Resource::builder().with_service_name(service_name.to_string()).build())
+            .build();
+
+        tracing_subscriber::registry()
+            .with(tracing_opentelemetry::layer().with_tracer(provider.tracer("jsonrpc-rust")))
+            .try_init()
+            .map_err(|e| Error::configuration(format!("Failed to install the tracing subscriber: {}", e)))?;
+        Ok(provider)
+    }
+
+    /// Make the OpenTelemetry span of `span` a child of the caller, and
+    /// take its ids; `None` when no exporter records the span
+    pub(super) fn adopt(span: &tracing::Span, parent: Option<&TraceContext>) -> Option<TraceContext> {
+        if let Some(parent) = parent {
+            let remote = SpanContext::new(
+                TraceId::from_hex(&parent.trace_id).ok()?,
+                SpanId::from_hex(&parent.span_id).ok()?,
+                if parent.sampled { TraceFlags::SAMPLED } else { TraceFlags::default() },
+                true,
+                TraceState::default(),
+            );
+            let _ = span.set_parent(opentelemetry::Context::new().with_remote_span_context(remote));
+        }
+
+        let context = span.context();
+        let own = context.span().span_context().clone();
+        if !own.is_valid() {
+            return None;
+        }
+        let trace = TraceContext {
+            trace_id: own.trace_id().to_string(),
+            span_id: own.span_id().to_string(),
+            sampled: own.is_sampled(),
+            trace_state: None,
+        };
+        Some(match parent {
+            Some(parent) => trace.with_state_of(parent),
+            None => trace,
+        })
+    }
+
+    impl TraceContext {
+        /// Keep the vendor trace state of `other`
+        pub(super) fn with_state_of(mut self, other: &TraceContext) -> Self {
+            self.trace_state = other.trace_state.clone();
+            self
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_traceparent_round_trip() {
+        let parent = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01";
+        let trace = TraceContext::parse(parent).unwrap();
+        assert_eq!(trace.trace_id, "4bf92f3577b34da6a3ce929d0e0e4736");
+        assert!(trace.sampled);
+        assert_eq!(trace.traceparent(), parent);
+
+        let child = trace.child();
+        assert_eq!(child.trace_id, trace.trace_id);
+        assert_ne!(child.span_id, trace.span_id);
+        assert!(TraceContext::parse(&TraceContext::root().traceparent()).is_some());
+
+        assert!(TraceContext::parse("00-00000000000000000000000000000000-00f067aa0ba902b7-01").is_none());
+        assert!(TraceContext::parse("00-4BF92F3577B34DA6A3CE929D0E0E4736-00f067aa0ba902b7-01").is_none());
+        assert!(TraceContext::parse("ff-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01").is_none());
+        assert!(TraceContext::parse("00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01-x").is_none());
+        assert!(TraceContext::parse("01-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-00-x").is_some());
+    }
+
+    #[test]
+    fn test_extract_prefers_request_meta() {
+        let mut request = JsonRpcRequest::with_id("ping", None, json!(1));
+        let context = ServiceContext::new("req-1")
+            .with_metadata(TRACEPARENT, json!("00-11111111111111111111111111111111-2222222222222222-00"));
+        assert_eq!(TraceContext::extract(&request, &context).unwrap().trace_id, "11111111111111111111111111111111");
+
+        let trace = TraceContext::root().with_trace_state("vendor=1");
+        trace.inject(&mut request);
+        assert_eq!(TraceContext::extract(&request, &context), Some(trace));
+    }
+}
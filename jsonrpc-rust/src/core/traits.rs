@@ -10,7 +10,7 @@ use std::pin::Pin;
 use futures::Stream;
 use serde_json::Value;
 use crate::core::error::{Error, Result};
-use crate::core::types::{JsonRpcRequest, JsonRpcResponse, ServiceContext};
+use crate::core::types::{JsonRpcRequest, JsonRpcRequestRef, JsonRpcResponse, ServiceContext};
 use crate::core::future::{JsonRpcFuture, ServiceStream};
 
 /// Core message trait for JSON-RPC operations
@@ -110,6 +110,30 @@ pub trait Transport: Send + Sync {
     }
 }
 
+/// Boxed transports, such as those made by a connector, are transports too
+#[async_trait]
+impl<T: Transport + ?Sized> Transport for Box<T> {
+    async fn send(&mut self, message: &str) -> Result<()> {
+        (**self).send(message).await
+    }
+
+    async fn receive(&mut self) -> Result<String> {
+        (**self).receive().await
+    }
+
+    async fn close(&mut self) -> Result<()> {
+        (**self).close().await
+    }
+
+    fn is_bidirectional(&self) -> bool {
+        (**self).is_bidirectional()
+    }
+
+    fn metadata(&self) -> HashMap<String, Value> {
+        (**self).metadata()
+    }
+}
+
 /// Connection abstraction for managing transport connections
 /// 
 /// This trait provides higher-level connection management on top of
@@ -298,6 +322,20 @@ pub trait MethodHandler: Send + Sync {
         context: &ServiceContext,
     ) -> Result<JsonRpcResponse>;
     
+    /// Handle a call whose params are still raw JSON
+    ///
+    /// The router calls this for requests it parsed without their params.
+    /// Handlers override it to deserialize the params straight into their
+    /// own type; by default the params are parsed and the call passed to
+    /// [`handle_method`](Self::handle_method), which is also all layers do.
+    async fn handle_raw(
+        &self,
+        request: &JsonRpcRequestRef<'_>,
+        context: &ServiceContext,
+    ) -> Result<JsonRpcResponse> {
+        self.handle_method(&request.to_request()?, context).await
+    }
+    
     /// Get list of supported methods
     fn supported_methods(&self) -> Vec<String>;
     
@@ -526,6 +564,10 @@ pub trait JsonRpcService: Send + Sync {
 /// 
 /// This trait handles the conversion between Rust types and JSON-RPC
 /// message formats, supporting different serialization strategies.
+/// Messages are serialized to bytes so that binary formats such as
+/// MessagePack and CBOR fit alongside JSON; see
+/// [`SerializationFormat`](crate::core::serialization::SerializationFormat)
+/// for the built-in implementations.
 /// 
 /// # Example
 /// 
@@ -537,44 +579,49 @@ pub trait JsonRpcService: Send + Sync {
 /// struct JsonSerializer;
 /// 
 /// impl MessageSerializer for JsonSerializer {
-///     fn serialize_request(&self, request: &JsonRpcRequest) -> Result<String> {
-///         serde_json::to_string(request)
+///     fn serialize_request(&self, request: &JsonRpcRequest) -> Result<Vec<u8>> {
+///         serde_json::to_vec(request)
 ///             .map_err(|e| Error::Serialization { message: format!("JSON serialization failed: {}", e), source: Some(Box::new(e)) })
 ///     }
 ///     
-///     fn deserialize_request(&self, data: &str) -> Result<JsonRpcRequest> {
-///         serde_json::from_str(data)
+///     fn deserialize_request(&self, data: &[u8]) -> Result<JsonRpcRequest> {
+///         serde_json::from_slice(data)
 ///             .map_err(|e| Error::Serialization { message: format!("JSON deserialization failed: {}", e), source: Some(Box::new(e)) })
 ///     }
 ///     
-///     fn serialize_response(&self, response: &JsonRpcResponse) -> Result<String> {
-///         serde_json::to_string(response)
+///     fn serialize_response(&self, response: &JsonRpcResponse) -> Result<Vec<u8>> {
+///         serde_json::to_vec(response)
 ///             .map_err(|e| Error::Serialization { message: format!("JSON serialization failed: {}", e), source: Some(Box::new(e)) })
 ///     }
 ///     
-///     fn deserialize_response(&self, data: &str) -> Result<JsonRpcResponse> {
-///         serde_json::from_str(data)
+///     fn deserialize_response(&self, data: &[u8]) -> Result<JsonRpcResponse> {
+///         serde_json::from_slice(data)
 ///             .map_err(|e| Error::Serialization { message: format!("JSON deserialization failed: {}", e), source: Some(Box::new(e)) })
 ///     }
 /// }
 /// ```
 pub trait MessageSerializer: Send + Sync {
     /// Serialize a JSON-RPC request
-    fn serialize_request(&self, request: &JsonRpcRequest) -> Result<String>;
+    fn serialize_request(&self, request: &JsonRpcRequest) -> Result<Vec<u8>>;
     
     /// Deserialize a JSON-RPC request
-    fn deserialize_request(&self, data: &str) -> Result<JsonRpcRequest>;
+    fn deserialize_request(&self, data: &[u8]) -> Result<JsonRpcRequest>;
     
     /// Serialize a JSON-RPC response
-    fn serialize_response(&self, response: &JsonRpcResponse) -> Result<String>;
+    fn serialize_response(&self, response: &JsonRpcResponse) -> Result<Vec<u8>>;
     
     /// Deserialize a JSON-RPC response
-    fn deserialize_response(&self, data: &str) -> Result<JsonRpcResponse>;
+    fn deserialize_response(&self, data: &[u8]) -> Result<JsonRpcResponse>;
     
     /// Get serialization format name
     fn format_name(&self) -> &str {
         "json"
     }
+    
+    /// Get the MIME type of serialized messages
+    fn content_type(&self) -> &str {
+        "application/json"
+    }
 }
 
 #[cfg(test)]
@@ -621,23 +668,23 @@ mod tests {
     struct JsonSerializer;
 
     impl MessageSerializer for JsonSerializer {
-        fn serialize_request(&self, request: &JsonRpcRequest) -> Result<String> {
-            serde_json::to_string(request)
+        fn serialize_request(&self, request: &JsonRpcRequest) -> Result<Vec<u8>> {
+            serde_json::to_vec(request)
                 .map_err(|e| Error::serialization(format!("Failed to serialize: {}", e)))
         }
         
-        fn deserialize_request(&self, data: &str) -> Result<JsonRpcRequest> {
-            serde_json::from_str(data)
+        fn deserialize_request(&self, data: &[u8]) -> Result<JsonRpcRequest> {
+            serde_json::from_slice(data)
                 .map_err(|e| Error::serialization(format!("Failed to deserialize: {}", e)))
         }
         
-        fn serialize_response(&self, response: &JsonRpcResponse) -> Result<String> {
-            serde_json::to_string(response)
+        fn serialize_response(&self, response: &JsonRpcResponse) -> Result<Vec<u8>> {
+            serde_json::to_vec(response)
                 .map_err(|e| Error::serialization(format!("Failed to serialize: {}", e)))
         }
         
-        fn deserialize_response(&self, data: &str) -> Result<JsonRpcResponse> {
-            serde_json::from_str(data)
+        fn deserialize_response(&self, data: &[u8]) -> Result<JsonRpcResponse> {
+            serde_json::from_slice(data)
                 .map_err(|e| Error::serialization(format!("Failed to deserialize: {}", e)))
         }
     }
@@ -5,14 +5,16 @@
 //! and streaming message handling.
 
 use std::collections::HashMap;
-use std::time::{Duration, SystemTime};
+use std::time::{Duration, Instant, SystemTime};
 use std::fmt;
 use std::sync::atomic::{AtomicU64, Ordering};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
+use tokio_util::sync::CancellationToken;
 use async_trait::async_trait;
 
 use crate::core::error::JsonRpcError;
+use crate::core::session::Session;
 use crate::{Result, Error};
 
 /// Type alias for message IDs
@@ -31,6 +33,10 @@ pub struct JsonRpcRequest {
     /// Request ID (for tracking responses)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub id: Option<MessageId>,
+    /// Call metadata travelling with the request, such as trace context;
+    /// an extension member peers without support ignore
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub meta: Option<serde_json::Map<String, serde_json::Value>>,
 }
 
 impl JsonRpcRequest {
@@ -41,6 +47,7 @@ impl JsonRpcRequest {
             method: method.into(),
             params,
             id: Some(serde_json::Value::String(Uuid::new_v4().to_string())),
+            meta: None,
         }
     }
     
@@ -51,6 +58,7 @@ impl JsonRpcRequest {
             method: method.into(),
             params,
             id: Some(id),
+            meta: None,
         }
     }
     
@@ -61,6 +69,7 @@ impl JsonRpcRequest {
             method: method.into(),
             params,
             id: None,
+            meta: None,
         }
     }
     
@@ -83,6 +92,91 @@ impl JsonRpcRequest {
     pub fn id(&self) -> Option<&MessageId> {
         self.id.as_ref()
     }
+    
+    /// Add call metadata
+    pub fn with_meta(mut self, key: impl Into<String>, value: serde_json::Value) -> Self {
+        self.meta.get_or_insert_with(Default::default).insert(key.into(), value);
+        self
+    }
+    
+    /// Get a call metadata entry
+    pub fn meta_value(&self, key: &str) -> Option<&serde_json::Value> {
+        self.meta.as_ref()?.get(key)
+    }
+}
+
+/// Borrowed view of a JSON-RPC request
+///
+/// Parsing one leaves the params as the raw JSON text of the message, so a
+/// server can route on the method name and deserialize the params only in
+/// the handler, straight into their own type, without building a
+/// [`serde_json::Value`] first:
+///
+/// ```rust
+/// use jsonrpc_rust::core::types::JsonRpcRequestRef;
+///
+/// let message = r#"{"jsonrpc": "2.0", "method": "add", "params": [1, 2], "id": 7}"#;
+/// let request = JsonRpcRequestRef::parse(message)?;
+/// assert_eq!(request.method, "add");
+/// let (a, b): (i64, i64) = request.params()?;
+/// assert_eq!(a + b, 3);
+/// # Ok::<(), jsonrpc_rust::Error>(())
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+pub struct JsonRpcRequestRef<'a> {
+    /// JSON-RPC version
+    #[serde(borrow)]
+    pub jsonrpc: std::borrow::Cow<'a, str>,
+    /// Method name to call
+    #[serde(borrow)]
+    pub method: std::borrow::Cow<'a, str>,
+    /// Method parameters, unparsed
+    #[serde(borrow, default)]
+    pub params: Option<&'a serde_json::value::RawValue>,
+    /// Request ID
+    #[serde(default)]
+    pub id: Option<MessageId>,
+    /// Call metadata
+    #[serde(default)]
+    pub meta: Option<serde_json::Map<String, serde_json::Value>>,
+}
+
+impl<'a> JsonRpcRequestRef<'a> {
+    /// Parse a request, borrowing from `message`
+    pub fn parse(message: &'a str) -> Result<Self> {
+        Ok(serde_json::from_str(message)?)
+    }
+    
+    /// Check if this is a notification
+    pub fn is_notification(&self) -> bool {
+        self.id.is_none()
+    }
+    
+    /// Deserialize the params, as `null` when there are none
+    pub fn params<T: Deserialize<'a>>(&self) -> Result<T> {
+        let raw = self.params.map_or("null", serde_json::value::RawValue::get);
+        serde_json::from_str(raw).map_err(|e| Error::invalid_params(e.to_string()))
+    }
+    
+    /// The owned request, params parsed
+    pub fn to_request(&self) -> Result<JsonRpcRequest> {
+        let params = match self.params {
+            Some(raw) => Some(serde_json::from_str(raw.get())?),
+            None => None,
+        };
+        Ok(JsonRpcRequest { params, ..self.header() })
+    }
+    
+    /// The owned request without its params
+    pub(crate) fn header(&self) -> JsonRpcRequest {
+        JsonRpcRequest {
+            jsonrpc: self.jsonrpc.to_string(),
+            method: self.method.to_string(),
+            params: None,
+            id: self.id.clone(),
+            meta: self.meta.clone(),
+        }
+    }
 }
 
 /// JSON-RPC response message
@@ -333,14 +427,14 @@ pub struct ResponseMetaInfo {
 }
 
 impl ResponseMetaInfo {
-    /// Create new metadata
+    /// Create new metadata, with the trace id of the call being handled
     pub fn new() -> Self {
         Self {
             processing_duration_ms: None,
             server_timestamp: SystemTime::now(),
             cache_info: None,
             resource_usage: None,
-            trace_id: None,
+            trace_id: super::trace::TraceContext::current().map(|trace| trace.trace_id),
             correlation_id: None,
             custom: HashMap::new(),
         }
@@ -491,6 +585,12 @@ pub struct ServiceContext {
     pub trn_context: Option<TrnContext>,
     /// Authentication context
     pub auth_context: Option<AuthContext>,
+    /// Time by which the caller needs the answer
+    pub deadline: Option<Instant>,
+    /// Cancelled when the caller gives up on the call
+    pub cancellation: CancellationToken,
+    /// Session of the connection the call came on
+    pub session: Option<Session>,
 }
 
 impl ServiceContext {
@@ -504,6 +604,9 @@ impl ServiceContext {
             #[cfg(feature = "trn-integration")]
             trn_context: None,
             auth_context: None,
+            deadline: None,
+            cancellation: CancellationToken::new(),
+            session: None,
         }
     }
     
@@ -531,6 +634,44 @@ impl ServiceContext {
         self.auth_context = Some(auth_context);
         self
     }
+    
+    /// Set the deadline
+    pub fn with_deadline(mut self, deadline: Instant) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+    
+    /// Set the deadline `timeout` from now
+    pub fn with_timeout(self, timeout: Duration) -> Self {
+        self.with_deadline(Instant::now() + timeout)
+    }
+    
+    /// Time left before the deadline, if there is one
+    pub fn remaining(&self) -> Option<Duration> {
+        self.deadline.map(|deadline| deadline.saturating_duration_since(Instant::now()))
+    }
+    
+    /// Check if the deadline has passed
+    pub fn is_past_deadline(&self) -> bool {
+        self.deadline.is_some_and(|deadline| Instant::now() >= deadline)
+    }
+    
+    /// Set the cancellation token
+    pub fn with_cancellation(mut self, cancellation: CancellationToken) -> Self {
+        self.cancellation = cancellation;
+        self
+    }
+    
+    /// Check if the caller has cancelled the call
+    pub fn is_cancelled(&self) -> bool {
+        self.cancellation.is_cancelled()
+    }
+    
+    /// Set the session of the connection
+    pub fn with_session(mut self, session: Session) -> Self {
+        self.session = Some(session);
+        self
+    }
 }
 
 /// Authentication context for request processing
@@ -674,7 +815,7 @@ impl TrnContext {
     }
     
     /// Parse from TRN string
-    pub fn from_trn_string(trn: &str) -> Result<Self, crate::core::error::Error> {
+    pub fn from_trn_string(trn: &str) -> crate::core::error::Result<Self> {
         let parts: Vec<&str> = trn.split(':').collect();
         if parts.len() != 6 || parts[0] != "trn" {
             return Err(crate::core::error::Error::validation(
@@ -0,0 +1,542 @@
+//! Chunked transfer of large results
+//!
+//! Transports cap the size of a single message, so a call whose result is
+//! larger than `max_message_size` fails outright. With chunked transfer the
+//! server splits such a result into ordered `rpc.chunk` notifications and
+//! answers the call with a small marker; the client reassembles the chunks
+//! into the original result.
+//!
+//! - On the server, a [`ChunkedHandler`] wraps the handler of the methods
+//!   whose results may be large and pushes the chunks to a notification sink
+//!   that the connection forwards to the client
+//! - On the client, a [`ChunkedClient`] makes the calls and puts the result
+//!   back together, checking that no chunk is lost or reordered; plain
+//!   clients of a chunked method see the marker instead of a large result
+//!
+//! Every chunk carries the id of the call it belongs to and its position,
+//! numbered from 1. Results no larger than one chunk are answered as usual,
+//! so a chunked method costs nothing for small results.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use parking_lot::Mutex;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+use crate::core::error::{Error, Result};
+use crate::core::traits::MethodHandler;
+use crate::core::types::{JsonRpcRequest, JsonRpcResponse, MessageId, SequenceValidator, ServiceContext, StreamMessage};
+use crate::protocol::JsonRpcClient;
+
+/// Method of the notifications carrying chunks
+pub const CHUNK_METHOD: &str = "rpc.chunk";
+
+/// Key of the marker object a chunked call is answered with
+const MARKER_KEY: &str = "$chunked";
+
+/// One piece of a chunked result, sent as the params of an `rpc.chunk`
+/// notification
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ResultChunk {
+    /// Id of the call the chunk belongs to
+    pub transfer: MessageId,
+    /// Position in the transfer, starting at 1
+    pub sequence: u64,
+    /// Slice of the serialized result
+    pub data: String,
+}
+
+impl ResultChunk {
+    /// Wrap the chunk in its notification
+    pub fn into_notification(self) -> Result<JsonRpcRequest> {
+        Ok(JsonRpcRequest::notification(CHUNK_METHOD, Some(serde_json::to_value(self)?)))
+    }
+}
+
+/// Describes a chunked result; answers the call in place of the result
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChunkedTransfer {
+    /// Number of chunks sent
+    pub chunks: u64,
+    /// Length of the serialized result in bytes
+    pub size: usize,
+}
+
+impl ChunkedTransfer {
+    /// The marker answering a chunked call
+    pub fn into_result(self) -> Result<Value> {
+        let mut marker = serde_json::Map::new();
+        marker.insert(MARKER_KEY.to_string(), serde_json::to_value(self)?);
+        Ok(Value::Object(marker))
+    }
+
+    /// Read the marker out of a call result, if the result is one
+    pub fn from_result(result: &Value) -> Option<Self> {
+        match result {
+            Value::Object(map) if map.len() == 1 => {
+                serde_json::from_value(map.get(MARKER_KEY)?.clone()).ok()
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Chunked transfer configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ChunkingConfig {
+    /// Largest chunk, measured as it appears in its notification; keep it
+    /// well under the transport's `max_message_size` to leave room for the
+    /// envelope
+    pub chunk_size: usize,
+    /// Largest result the client reassembles
+    pub max_result_size: usize,
+}
+
+impl Default for ChunkingConfig {
+    fn default() -> Self {
+        Self {
+            chunk_size: 256 * 1024,
+            max_result_size: 64 * 1024 * 1024,
+        }
+    }
+}
+
+impl ChunkingConfig {
+    /// Set the largest chunk
+    pub fn with_chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = chunk_size;
+        self
+    }
+
+    /// Set the largest result the client reassembles
+    pub fn with_max_result_size(mut self, max_result_size: usize) -> Self {
+        self.max_result_size = max_result_size;
+        self
+    }
+
+    /// Validate the configuration
+    pub fn validate(&self) -> Result<()> {
+        // A chunk must have room for any character, escaped
+        if self.chunk_size < 16 {
+            return Err(Error::configuration("Chunk size must be at least 16 bytes"));
+        }
+        if self.max_result_size == 0 {
+            return Err(Error::configuration("Maximum result size must be greater than 0"));
+        }
+        Ok(())
+    }
+}
+
+/// Length of a character once escaped in a JSON string
+fn escaped_len(c: char) -> usize {
+    match c {
+        '"' | '\\' | '\u{08}' | '\u{0c}' | '\n' | '\r' | '\t' => 2,
+        c if c < ' ' => 6,
+        c => c.len_utf8(),
+    }
+}
+
+/// Split `text` into pieces no longer than `chunk_size` once escaped,
+/// never inside a character
+fn split_escaped(text: &str, chunk_size: usize) -> Vec<&str> {
+    let mut pieces = Vec::new();
+    let mut start = 0;
+    let mut length = 0;
+    for (index, c) in text.char_indices() {
+        let width = escaped_len(c);
+        if length + width > chunk_size {
+            pieces.push(&text[start..index]);
+            start = index;
+            length = 0;
+        }
+        length += width;
+    }
+    if start < text.len() {
+        pieces.push(&text[start..]);
+    }
+    pieces
+}
+
+/// Wraps a method handler, sending results larger than a chunk in pieces
+pub struct ChunkedHandler {
+    inner: Arc<dyn MethodHandler>,
+    sink: mpsc::UnboundedSender<JsonRpcRequest>,
+    config: ChunkingConfig,
+}
+
+impl ChunkedHandler {
+    /// Wrap `inner`, sending chunk notifications to `sink`
+    pub fn new(
+        inner: Arc<dyn MethodHandler>,
+        sink: mpsc::UnboundedSender<JsonRpcRequest>,
+        config: ChunkingConfig,
+    ) -> Result<Self> {
+        config.validate()?;
+        Ok(Self { inner, sink, config })
+    }
+
+    /// Chunking configuration
+    pub fn config(&self) -> &ChunkingConfig {
+        &self.config
+    }
+
+    /// Send `result` as chunks of the call `transfer`, returning the marker
+    /// that answers the call
+    fn send_chunks(&self, transfer: &MessageId, result: &Value) -> Result<Option<Value>> {
+        let text = serde_json::to_string(result)?;
+        let pieces = split_escaped(&text, self.config.chunk_size);
+        if pieces.len() <= 1 {
+            return Ok(None);
+        }
+
+        for (index, data) in pieces.iter().enumerate() {
+            let chunk = ResultChunk {
+                transfer: transfer.clone(),
+                sequence: index as u64 + 1,
+                data: data.to_string(),
+            };
+            self.sink.send(chunk.into_notification()?)
+                .map_err(|_| Error::connection("Connection closed while sending chunks"))?;
+        }
+
+        tracing::debug!("Sent result of {} bytes in {} chunks", text.len(), pieces.len());
+        let marker = ChunkedTransfer {
+            chunks: pieces.len() as u64,
+            size: text.len(),
+        };
+        marker.into_result().map(Some)
+    }
+}
+
+#[async_trait]
+impl MethodHandler for ChunkedHandler {
+    async fn handle_method(&self, request: &JsonRpcRequest, context: &ServiceContext) -> Result<JsonRpcResponse> {
+        let mut response = self.inner.handle_method(request, context).await?;
+        // Notifications are never answered, so there is nothing to chunk
+        let Some(id) = request.id.as_ref() else {
+            return Ok(response);
+        };
+
+        if let Some(result) = response.result.as_ref() {
+            if let Some(marker) = self.send_chunks(id, result)? {
+                response.result = Some(marker);
+            }
+        }
+        Ok(response)
+    }
+
+    fn supported_methods(&self) -> Vec<String> {
+        self.inner.supported_methods()
+    }
+
+    fn supports_method(&self, method: &str) -> bool {
+        self.inner.supports_method(method)
+    }
+}
+
+impl std::fmt::Debug for ChunkedHandler {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ChunkedHandler")
+            .field("methods", &self.inner.supported_methods())
+            .field("config", &self.config)
+            .finish()
+    }
+}
+
+/// Chunk receivers of the calls in flight, keyed by serialized call id
+type TransferMap = Mutex<HashMap<String, mpsc::UnboundedSender<ResultChunk>>>;
+
+/// Makes calls through a [`JsonRpcClient`], reassembling chunked results
+pub struct ChunkedClient {
+    client: Arc<JsonRpcClient>,
+    transfers: Arc<TransferMap>,
+    config: ChunkingConfig,
+}
+
+impl ChunkedClient {
+    /// Create a chunked client sharing a JSON-RPC client
+    ///
+    /// The chunked client takes over the `rpc.chunk` notifications of the
+    /// JSON-RPC client.
+    pub fn new(client: Arc<JsonRpcClient>, config: ChunkingConfig) -> Result<Self> {
+        config.validate()?;
+
+        let transfers = Arc::new(TransferMap::default());
+        let mut notifications = client.notifications(CHUNK_METHOD);
+        let routed = Arc::downgrade(&transfers);
+        tokio::spawn(async move {
+            while let Some(notification) = notifications.recv().await {
+                let Some(transfers) = routed.upgrade() else {
+                    break;
+                };
+                match serde_json::from_value::<ResultChunk>(notification.params.unwrap_or(Value::Null)) {
+                    Ok(chunk) => {
+                        let key = chunk.transfer.to_string();
+                        match transfers.lock().get(&key) {
+                            Some(tx) => {
+                                let _ = tx.send(chunk);
+                            }
+                            None => tracing::debug!("Discarding chunk for unknown call {}", key),
+                        }
+                    }
+                    Err(e) => tracing::warn!("Discarding malformed chunk: {}", e),
+                }
+            }
+        });
+
+        Ok(Self { client, transfers, config })
+    }
+
+    /// Underlying JSON-RPC client
+    pub fn client(&self) -> &Arc<JsonRpcClient> {
+        &self.client
+    }
+
+    /// Call a method, reassembling its result if it was chunked
+    pub async fn call<P, R>(&self, method: &str, params: P) -> Result<R>
+    where
+        P: Serialize,
+        R: DeserializeOwned,
+    {
+        let timeout = self.client.config().request_timeout;
+        self.call_with_timeout(method, params, timeout).await
+    }
+
+    /// Call a method with a timeout covering the transfer of every chunk
+    pub async fn call_with_timeout<P, R>(&self, method: &str, params: P, timeout: Duration) -> Result<R>
+    where
+        P: Serialize,
+        R: DeserializeOwned,
+    {
+        let params = match serde_json::to_value(params)? {
+            Value::Null => None,
+            params => Some(params),
+        };
+        let id = Value::String(Uuid::new_v4().to_string());
+        let request = JsonRpcRequest::with_id(method, params, id.clone());
+
+        // Register before sending so no chunk can arrive unclaimed
+        let key = id.to_string();
+        let (tx, mut chunks) = mpsc::unbounded_channel();
+        self.transfers.lock().insert(key.clone(), tx);
+        let _guard = TransferGuard { transfers: &self.transfers, key };
+
+        let result = tokio::time::timeout(timeout, async {
+            let response = self.client.request(request, timeout).await?;
+            if let Some(error) = response.error {
+                return Err(Error::JsonRpc(error));
+            }
+            let result = response.result.unwrap_or(Value::Null);
+            match ChunkedTransfer::from_result(&result) {
+                Some(transfer) => self.reassemble(&transfer, &mut chunks).await,
+                None => Ok(result),
+            }
+        })
+        .await
+        .map_err(|_| Error::timeout(format!("chunked call to '{}'", method), timeout))??;
+
+        Ok(serde_json::from_value(result)?)
+    }
+
+    async fn reassemble(
+        &self,
+        transfer: &ChunkedTransfer,
+        chunks: &mut mpsc::UnboundedReceiver<ResultChunk>,
+    ) -> Result<Value> {
+        if transfer.size > self.config.max_result_size {
+            return Err(Error::validation(format!(
+                "Chunked result of {} bytes exceeds the {} byte limit",
+                transfer.size, self.config.max_result_size
+            )));
+        }
+
+        let validator = SequenceValidator::new(false);
+        let mut text = String::with_capacity(transfer.size);
+        while validator.last_sequence() < transfer.chunks {
+            let chunk = chunks.recv().await
+                .ok_or_else(|| Error::connection("Client closed before every chunk arrived"))?;
+            let response = JsonRpcResponse::success(chunk.transfer, Value::Null);
+            validator.validate(&StreamMessage::new(response, chunk.sequence))?;
+
+            text.push_str(&chunk.data);
+            if text.len() > transfer.size {
+                return Err(Error::validation("Chunked result is larger than announced"));
+            }
+        }
+
+        if text.len() != transfer.size {
+            return Err(Error::validation(format!(
+                "Chunked result is {} bytes, {} were announced",
+                text.len(), transfer.size
+            )));
+        }
+        Ok(serde_json::from_str(&text)?)
+    }
+}
+
+impl std::fmt::Debug for ChunkedClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ChunkedClient")
+            .field("client", &self.client)
+            .field("config", &self.config)
+            .finish()
+    }
+}
+
+/// Removes a call from the transfer map however it ends
+struct TransferGuard<'a> {
+    transfers: &'a TransferMap,
+    key: String,
+}
+
+impl Drop for TransferGuard<'_> {
+    fn drop(&mut self) {
+        self.transfers.lock().remove(&self.key);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::traits::Transport;
+    use crate::protocol::MethodRouter;
+    use serde_json::json;
+
+    struct ChannelTransport {
+        outgoing: mpsc::UnboundedSender<String>,
+        incoming: mpsc::UnboundedReceiver<String>,
+    }
+
+    #[async_trait]
+    impl Transport for ChannelTransport {
+        async fn send(&mut self, message: &str) -> Result<()> {
+            self.outgoing.send(message.to_string()).map_err(|_| Error::transport("closed"))
+        }
+
+        async fn receive(&mut self) -> Result<String> {
+            self.incoming.recv().await.ok_or_else(|| Error::transport("closed"))
+        }
+
+        async fn close(&mut self) -> Result<()> {
+            self.incoming.close();
+            Ok(())
+        }
+    }
+
+    /// Answers `dump` with `size` lines of tool output
+    struct Dump;
+
+    #[async_trait]
+    impl MethodHandler for Dump {
+        async fn handle_method(&self, request: &JsonRpcRequest, _context: &ServiceContext) -> Result<JsonRpcResponse> {
+            let lines = request.params.as_ref().and_then(|p| p["lines"].as_u64()).unwrap_or(0);
+            let output: Vec<String> = (0..lines).map(|n| format!("line {}\t\"ok\" — ✓\n", n)).collect();
+            Ok(JsonRpcResponse::success(Value::Null, json!({ "output": output.concat() })))
+        }
+
+        fn supported_methods(&self) -> Vec<String> {
+            vec!["dump".to_string()]
+        }
+    }
+
+    /// Client connected to a router serving `dump` in chunks, and the size
+    /// of the largest message the server sent
+    fn serve(config: ChunkingConfig) -> (ChunkedClient, Arc<Mutex<usize>>) {
+        let (client_tx, mut server_rx) = mpsc::unbounded_channel::<String>();
+        let (server_tx, client_rx) = mpsc::unbounded_channel();
+        let (sink, mut notifications) = mpsc::unbounded_channel();
+
+        let handler = ChunkedHandler::new(Arc::new(Dump), sink, config.clone()).unwrap();
+        let router = MethodRouter::new().with_handler(Arc::new(handler)).unwrap();
+        let largest = Arc::new(Mutex::new(0));
+        let seen = Arc::clone(&largest);
+        tokio::spawn(async move {
+            let context = ServiceContext::new("chunked");
+            let send = |message: String| {
+                let mut largest = seen.lock();
+                *largest = (*largest).max(message.len());
+                let _ = server_tx.send(message);
+            };
+            loop {
+                tokio::select! {
+                    Some(message) = server_rx.recv() => {
+                        if let Some(response) = router.dispatch_message(&message, &context).await {
+                            send(response);
+                        }
+                    }
+                    Some(notification) = notifications.recv() => {
+                        send(serde_json::to_string(&notification).unwrap());
+                    }
+                    else => break,
+                }
+            }
+        });
+
+        let client = JsonRpcClient::new(ChannelTransport { outgoing: client_tx, incoming: client_rx });
+        (ChunkedClient::new(Arc::new(client), config).unwrap(), largest)
+    }
+
+    #[test]
+    fn test_split_escaped() {
+        let text = "ab\"c\u{1}✓d";
+        let pieces = split_escaped(text, 6);
+        assert_eq!(pieces.concat(), text);
+        assert!(pieces.iter().all(|piece| serde_json::to_string(piece).unwrap().len() <= 6 + 2));
+        assert_eq!(split_escaped("", 16), Vec::<&str>::new());
+        assert!(ChunkingConfig::default().with_chunk_size(8).validate().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_chunked_round_trip() {
+        let (client, largest) = serve(ChunkingConfig::default().with_chunk_size(512));
+
+        let small: Value = client.call("dump", json!({ "lines": 2 })).await.unwrap();
+        assert_eq!(small["output"].as_str().unwrap().lines().count(), 2);
+
+        // Far larger than any single message the server sends
+        let large: Value = client.call("dump", json!({ "lines": 2000 })).await.unwrap();
+        let output = large["output"].as_str().unwrap();
+        assert_eq!(output.lines().count(), 2000);
+        assert!(output.starts_with("line 0\t\"ok\" — ✓\n"));
+        assert!(output.len() > 40_000);
+        assert!(*largest.lock() < 1024, "sent a message of {} bytes", *largest.lock());
+        assert_eq!(client.transfers.lock().len(), 0);
+
+        match client.call::<_, Value>("missing", ()).await {
+            Err(Error::JsonRpc(error)) => assert_eq!(error.code, -32601),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_chunked_transfer_checks() {
+        let (client_tx, mut server_rx) = mpsc::unbounded_channel::<String>();
+        let (server_tx, client_rx) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            while let Some(message) = server_rx.recv().await {
+                let request: JsonRpcRequest = serde_json::from_str(&message).unwrap();
+                let id = request.id.unwrap();
+                let size = if request.method == "huge" { 1 << 30 } else { 9 };
+                // Second chunk missing
+                for (sequence, data) in [(1, "\"abc"), (3, "ghi\"")] {
+                    let chunk = ResultChunk { transfer: id.clone(), sequence, data: data.to_string() };
+                    server_tx.send(serde_json::to_string(&chunk.into_notification().unwrap()).unwrap()).unwrap();
+                }
+                let marker = ChunkedTransfer { chunks: 3, size }.into_result().unwrap();
+                server_tx.send(serde_json::to_string(&JsonRpcResponse::success(id, marker)).unwrap()).unwrap();
+            }
+        });
+
+        let client = JsonRpcClient::new(ChannelTransport { outgoing: client_tx, incoming: client_rx });
+        let client = ChunkedClient::new(Arc::new(client), ChunkingConfig::default()).unwrap();
+        assert!(matches!(client.call::<_, Value>("gap", ()).await, Err(Error::Validation { .. })));
+        assert!(matches!(client.call::<_, Value>("huge", ()).await, Err(Error::Validation { .. })));
+    }
+}
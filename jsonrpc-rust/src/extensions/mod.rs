@@ -0,0 +1,23 @@
+//! Extension layer for advanced features (Phase 4)
+//!
+//! This module builds on the protocol layer with features outside the
+//! JSON-RPC 2.0 specification: streaming subscriptions, streams multiplexed
+//! with per-stream flow control, and chunked transfer of large results.
+
+pub mod chunked;
+pub mod multiplex;
+pub mod subscription;
+
+pub use chunked::*;
+pub use multiplex::*;
+pub use subscription::*;
+
+pub mod prelude {
+    //! Common imports for extension layer usage
+
+    pub use super::chunked::{ChunkedClient, ChunkedHandler, ChunkingConfig};
+    pub use super::multiplex::{MultiplexClient, MultiplexConfig, MultiplexHandler, MultiplexedStream};
+    pub use super::subscription::{
+        Subscription, SubscriptionClient, SubscriptionHandler, SubscriptionItem, SubscriptionSource,
+    };
+}
@@ -0,0 +1,652 @@
+//! Multiplexed streams over one connection
+//!
+//! Subscriptions send their items as fast as their source produces them, so
+//! one large stream fills the connection and holds up the responses to
+//! every call made behind it. Multiplexed streams share the connection
+//! fairly instead:
+//!
+//! - `rpc.stream.open` with `{"source", "params", "window"}` starts a
+//!   stream from one of the server's [`SubscriptionSource`]s and returns
+//!   `{"stream", "window"}` with the window the server granted
+//! - `rpc.stream.item` notifications carry each [`SubscriptionItem`],
+//!   numbered from 1 within their stream
+//! - `rpc.stream.ack` notifications with `{"stream", "count"}` hand credit
+//!   back as the client consumes items
+//! - `rpc.stream.close` with `[stream]` stops a stream early
+//!
+//! A stream never has more items sent and unacknowledged than its window,
+//! so the items queued on the connection stay bounded however large the
+//! streams. On the server, a [`MultiplexHandler`] sends the items of its
+//! streams in turn, one each, yielding to the connection in between. On
+//! the client, a [`MultiplexClient`] opens the streams and acknowledges
+//! items as they are read from each [`MultiplexedStream`], checking their
+//! sequence as subscriptions do.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Weak};
+
+use async_trait::async_trait;
+use futures::stream::BoxStream;
+use futures::StreamExt;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tokio::sync::{mpsc, Notify, Semaphore};
+use tokio::task::AbortHandle;
+use uuid::Uuid;
+
+use super::subscription::{Demux, ItemReceiver, SubscriptionItem, SubscriptionSource};
+use crate::core::error::{Error, Result};
+use crate::core::future::JsonRpcStream;
+use crate::core::traits::MethodHandler;
+use crate::core::types::{JsonRpcRequest, JsonRpcResponse, ServiceContext};
+use crate::protocol::JsonRpcClient;
+
+/// Method opening a multiplexed stream
+pub const STREAM_OPEN_METHOD: &str = "rpc.stream.open";
+/// Notification carrying an item of a multiplexed stream
+pub const STREAM_ITEM_METHOD: &str = "rpc.stream.item";
+/// Notification handing credit back to a multiplexed stream
+pub const STREAM_ACK_METHOD: &str = "rpc.stream.ack";
+/// Method closing a multiplexed stream
+pub const STREAM_CLOSE_METHOD: &str = "rpc.stream.close";
+
+/// Multiplexed stream configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MultiplexConfig {
+    /// Window of streams opened without asking for one
+    pub default_window: u32,
+    /// Largest window granted
+    pub max_window: u32,
+    /// Streams open at once on a connection
+    pub max_streams: usize,
+}
+
+impl Default for MultiplexConfig {
+    fn default() -> Self {
+        Self {
+            default_window: 16,
+            max_window: 256,
+            max_streams: 64,
+        }
+    }
+}
+
+impl MultiplexConfig {
+    /// Set the window of streams opened without asking for one
+    pub fn with_default_window(mut self, window: u32) -> Self {
+        self.default_window = window;
+        self
+    }
+
+    /// Set the largest window granted
+    pub fn with_max_window(mut self, window: u32) -> Self {
+        self.max_window = window;
+        self
+    }
+
+    /// Set the number of streams open at once
+    pub fn with_max_streams(mut self, max_streams: usize) -> Self {
+        self.max_streams = max_streams;
+        self
+    }
+
+    /// Check the configuration
+    pub fn validate(&self) -> Result<()> {
+        if self.default_window == 0 || self.max_window == 0 {
+            return Err(Error::configuration("Stream windows cannot be zero"));
+        }
+        if self.max_streams == 0 {
+            return Err(Error::configuration("Max streams cannot be zero"));
+        }
+        Ok(())
+    }
+}
+
+/// Params of `rpc.stream.open`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct OpenParams {
+    source: String,
+    #[serde(default)]
+    params: Option<Value>,
+    #[serde(default)]
+    window: Option<u32>,
+}
+
+/// Result of `rpc.stream.open`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Opened {
+    stream: String,
+    window: u32,
+}
+
+/// Params of `rpc.stream.ack`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Ack {
+    stream: String,
+    count: u32,
+}
+
+/// A stream's items waiting for their turn
+struct Lane {
+    queue: VecDeque<SubscriptionItem>,
+    /// Items the stream may still produce before being acknowledged
+    credit: Arc<Semaphore>,
+    window: usize,
+    task: AbortHandle,
+}
+
+#[derive(Default)]
+struct Lanes {
+    lanes: HashMap<String, Lane>,
+    /// Streams with queued items, the next to send in front
+    turns: VecDeque<String>,
+}
+
+/// Streams of one connection and the sink their items go to
+struct Mux {
+    lanes: Mutex<Lanes>,
+    sink: mpsc::UnboundedSender<JsonRpcRequest>,
+}
+
+impl Mux {
+    fn push(&self, item: SubscriptionItem) {
+        let mut lanes = self.lanes.lock();
+        let Lanes { lanes, turns } = &mut *lanes;
+        if let Some(lane) = lanes.get_mut(&item.subscription) {
+            if lane.queue.is_empty() {
+                turns.push_back(item.subscription.clone());
+            }
+            lane.queue.push_back(item);
+        }
+    }
+
+    /// The next item to send, taking the streams in turn
+    fn next_item(&self) -> Option<SubscriptionItem> {
+        let mut lanes = self.lanes.lock();
+        let Lanes { lanes, turns } = &mut *lanes;
+        while let Some(id) = turns.pop_front() {
+            let Some(lane) = lanes.get_mut(&id) else { continue };
+            let Some(item) = lane.queue.pop_front() else { continue };
+            if item.done || item.error.is_some() {
+                lanes.remove(&id);
+            } else if !lane.queue.is_empty() {
+                turns.push_back(id);
+            }
+            return Some(item);
+        }
+        None
+    }
+
+    fn close(&self, id: &str) -> bool {
+        match self.lanes.lock().lanes.remove(id) {
+            Some(lane) => {
+                lane.task.abort();
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn close_all(&self) {
+        for (_, lane) in self.lanes.lock().lanes.drain() {
+            lane.task.abort();
+        }
+    }
+}
+
+/// Serves multiplexed streams from named sources on one connection
+///
+/// Must be created inside a Tokio runtime; streams stop when the handler is
+/// dropped or the sink closes.
+pub struct MultiplexHandler {
+    sources: HashMap<String, Arc<dyn SubscriptionSource>>,
+    config: MultiplexConfig,
+    mux: Arc<Mux>,
+    ready: Arc<Notify>,
+}
+
+impl MultiplexHandler {
+    /// Create a handler sending item notifications to `sink`
+    pub fn new(sink: mpsc::UnboundedSender<JsonRpcRequest>, config: MultiplexConfig) -> Result<Self> {
+        config.validate()?;
+        let mux = Arc::new(Mux {
+            lanes: Mutex::new(Lanes::default()),
+            sink,
+        });
+        let ready = Arc::new(Notify::new());
+        tokio::spawn(schedule(Arc::downgrade(&mux), Arc::clone(&ready)));
+        Ok(Self {
+            sources: HashMap::new(),
+            config,
+            mux,
+            ready,
+        })
+    }
+
+    /// Serve the streams of `source` under `name`
+    pub fn with_source(mut self, name: impl Into<String>, source: Arc<dyn SubscriptionSource>) -> Self {
+        self.sources.insert(name.into(), source);
+        self
+    }
+
+    /// Multiplexing configuration
+    pub fn config(&self) -> &MultiplexConfig {
+        &self.config
+    }
+
+    /// Number of streams still open
+    pub fn active_streams(&self) -> usize {
+        self.mux.lanes.lock().lanes.len()
+    }
+
+    async fn open(&self, params: Option<Value>, context: &ServiceContext) -> Result<Value> {
+        let params: OpenParams = serde_json::from_value(params.unwrap_or(Value::Null))
+            .map_err(|e| Error::invalid_params(format!("Invalid stream.open params: {}", e)))?;
+        let source = self.sources.get(&params.source)
+            .ok_or_else(|| Error::invalid_params(format!("No stream source '{}'", params.source)))?;
+        let window = params.window.unwrap_or(self.config.default_window).clamp(1, self.config.max_window);
+
+        let open = self.active_streams();
+        if open >= self.config.max_streams {
+            return Err(Error::resource_exhausted("streams", self.config.max_streams as u64, open as u64));
+        }
+        let stream = source.subscribe(params.params, context).await?;
+        let id = Uuid::new_v4().to_string();
+        let credit = Arc::new(Semaphore::new(window as usize));
+
+        // Hold the lock while spawning so the lane exists before its first item
+        let mut lanes = self.mux.lanes.lock();
+        let task = tokio::spawn(produce(
+            id.clone(),
+            stream,
+            Arc::clone(&credit),
+            Arc::clone(&self.mux),
+            Arc::clone(&self.ready),
+        ));
+        lanes.lanes.insert(id.clone(), Lane {
+            queue: VecDeque::new(),
+            credit,
+            window: window as usize,
+            task: task.abort_handle(),
+        });
+
+        Ok(serde_json::to_value(Opened { stream: id, window })?)
+    }
+
+    fn ack(&self, params: Option<Value>) -> Result<()> {
+        let ack: Ack = serde_json::from_value(params.unwrap_or(Value::Null))
+            .map_err(|e| Error::invalid_params(format!("Invalid stream.ack params: {}", e)))?;
+        if let Some(lane) = self.mux.lanes.lock().lanes.get(&ack.stream) {
+            // Never more credit than the window allows
+            let missing = lane.window.saturating_sub(lane.credit.available_permits());
+            lane.credit.add_permits((ack.count as usize).min(missing));
+        }
+        Ok(())
+    }
+
+    fn close(&self, params: Option<Value>) -> Result<Value> {
+        let id = match params {
+            Some(Value::Array(mut args)) if args.len() == 1 => args.pop(),
+            Some(Value::Object(mut args)) => args.remove("stream"),
+            _ => None,
+        };
+        let Some(Value::String(id)) = id else {
+            return Err(Error::invalid_params("Expected a stream id"));
+        };
+        Ok(Value::Bool(self.mux.close(&id)))
+    }
+}
+
+#[async_trait]
+impl MethodHandler for MultiplexHandler {
+    async fn handle_method(&self, request: &JsonRpcRequest, context: &ServiceContext) -> Result<JsonRpcResponse> {
+        let result = match request.method.as_str() {
+            STREAM_OPEN_METHOD => self.open(request.params.clone(), context).await?,
+            STREAM_ACK_METHOD => {
+                self.ack(request.params.clone())?;
+                Value::Null
+            }
+            STREAM_CLOSE_METHOD => self.close(request.params.clone())?,
+            method => return Err(Error::method_not_found(method)),
+        };
+        Ok(JsonRpcResponse::success(request.id.clone().unwrap_or(Value::Null), result))
+    }
+
+    fn supported_methods(&self) -> Vec<String> {
+        [STREAM_OPEN_METHOD, STREAM_ACK_METHOD, STREAM_CLOSE_METHOD].map(String::from).to_vec()
+    }
+}
+
+impl Drop for MultiplexHandler {
+    fn drop(&mut self) {
+        self.mux.close_all();
+        // Lets the scheduler see the streams are gone
+        self.ready.notify_one();
+    }
+}
+
+impl std::fmt::Debug for MultiplexHandler {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MultiplexHandler")
+            .field("sources", &self.sources.keys().collect::<Vec<_>>())
+            .field("config", &self.config)
+            .field("active", &self.active_streams())
+            .finish()
+    }
+}
+
+/// Pull the items of one stream as its credit allows
+async fn produce(
+    id: String,
+    mut stream: BoxStream<'static, Result<Value>>,
+    credit: Arc<Semaphore>,
+    mux: Arc<Mux>,
+    ready: Arc<Notify>,
+) {
+    let mut sequence = 0;
+    loop {
+        match credit.acquire().await {
+            Ok(permit) => permit.forget(),
+            Err(_) => return,
+        }
+        sequence += 1;
+        let (item, last) = match stream.next().await {
+            Some(Ok(result)) => (SubscriptionItem::result(&id, sequence, result), false),
+            Some(Err(e)) => (SubscriptionItem::error(&id, sequence, e.to_jsonrpc_error()), true),
+            None => (SubscriptionItem::done(&id, sequence), true),
+        };
+        mux.push(item);
+        ready.notify_one();
+        if last {
+            return;
+        }
+    }
+}
+
+/// Send queued items to the sink, one stream at a time
+async fn schedule(mux: Weak<Mux>, ready: Arc<Notify>) {
+    loop {
+        ready.notified().await;
+        loop {
+            let Some(mux) = mux.upgrade() else { return };
+            let Some(item) = mux.next_item() else { break };
+            let sent = serde_json::to_value(item)
+                .map(|params| JsonRpcRequest::notification(STREAM_ITEM_METHOD, Some(params)))
+                .map_err(|e| tracing::error!("Failed to serialize stream item: {}", e))
+                .is_ok_and(|notification| mux.sink.send(notification).is_ok());
+            if !sent {
+                mux.close_all();
+                return;
+            }
+            drop(mux);
+            // Let responses to calls in between
+            tokio::task::yield_now().await;
+        }
+    }
+}
+
+/// Opens multiplexed streams through a [`JsonRpcClient`]
+///
+/// Must be created inside a Tokio runtime. The client takes over the
+/// `rpc.stream.item` notifications of its JSON-RPC client.
+pub struct MultiplexClient {
+    client: Arc<JsonRpcClient>,
+    demux: Arc<Mutex<Demux>>,
+    window: Option<u32>,
+}
+
+impl MultiplexClient {
+    /// Create a client sharing a JSON-RPC client
+    pub fn new(client: Arc<JsonRpcClient>) -> Self {
+        let demux = Demux::spawn(&client, STREAM_ITEM_METHOD.to_string());
+        Self {
+            client,
+            demux,
+            window: None,
+        }
+    }
+
+    /// Ask for `window` items in flight per stream instead of the server's default
+    pub fn with_window(mut self, window: u32) -> Self {
+        self.window = Some(window);
+        self
+    }
+
+    /// Underlying JSON-RPC client
+    pub fn client(&self) -> &Arc<JsonRpcClient> {
+        &self.client
+    }
+
+    /// Open a stream from the server's `source`
+    pub async fn open(&self, source: &str, params: impl Serialize) -> Result<MultiplexedStream> {
+        let params = OpenParams {
+            source: source.to_string(),
+            params: Some(serde_json::to_value(params)?).filter(|params| !params.is_null()),
+            window: self.window,
+        };
+        let window = Mutex::new(0);
+        let receiver = Demux::open(&self.demux, async {
+            let opened: Opened = self.client.call(STREAM_OPEN_METHOD, &params).await?;
+            *window.lock() = opened.window;
+            Ok(opened.stream)
+        })
+        .await?;
+
+        let window = window.into_inner();
+        Ok(MultiplexedStream {
+            client: Arc::clone(&self.client),
+            receiver,
+            // Acknowledging every half window keeps the stream flowing
+            ack_every: (window / 2).max(1),
+            unacked: 0,
+            window,
+        })
+    }
+}
+
+impl std::fmt::Debug for MultiplexClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MultiplexClient")
+            .field("client", &self.client)
+            .field("window", &self.window)
+            .finish()
+    }
+}
+
+/// Client side of an open multiplexed stream
+///
+/// Items are returned as responses whose id is the stream id, and
+/// acknowledged as they are read. An item arriving out of sequence ends the
+/// stream with a validation error.
+pub struct MultiplexedStream {
+    client: Arc<JsonRpcClient>,
+    receiver: ItemReceiver,
+    window: u32,
+    ack_every: u32,
+    unacked: u32,
+}
+
+impl MultiplexedStream {
+    /// Stream id assigned by the server
+    pub fn id(&self) -> &str {
+        self.receiver.id()
+    }
+
+    /// Items the server may send ahead of those read
+    pub fn window(&self) -> u32 {
+        self.window
+    }
+
+    /// Wait for the next item; `None` once the stream has ended
+    pub async fn next_response(&mut self) -> Option<Result<JsonRpcResponse>> {
+        let response = self.receiver.next_response().await;
+        if matches!(response, Some(Ok(_))) {
+            self.unacked += 1;
+            if self.unacked >= self.ack_every {
+                let ack = json!({"stream": self.id(), "count": self.unacked});
+                if let Err(e) = self.client.notify(STREAM_ACK_METHOD, ack).await {
+                    return Some(Err(e));
+                }
+                self.unacked = 0;
+            }
+        }
+        response
+    }
+
+    /// Stop the stream; returns whether the server still had it open
+    pub async fn close(self) -> Result<bool> {
+        self.client.call(STREAM_CLOSE_METHOD, [self.id()]).await
+    }
+
+    /// Turn the stream into a stream of its items
+    pub fn into_stream(self) -> JsonRpcStream {
+        JsonRpcStream::new(futures::stream::unfold(self, |mut stream| async move {
+            let item = stream.next_response().await?;
+            Some((item, stream))
+        }))
+    }
+}
+
+impl std::fmt::Debug for MultiplexedStream {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MultiplexedStream")
+            .field("id", &self.id())
+            .field("window", &self.window)
+            .field("unacked", &self.unacked)
+            .field("finished", &self.receiver.is_finished())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::convenience::FnHandler;
+    use crate::core::traits::Transport;
+    use crate::protocol::MethodRouter;
+    use std::time::Duration;
+
+    struct ChannelTransport {
+        outgoing: mpsc::UnboundedSender<String>,
+        incoming: mpsc::UnboundedReceiver<String>,
+    }
+
+    #[async_trait]
+    impl Transport for ChannelTransport {
+        async fn send(&mut self, message: &str) -> Result<()> {
+            self.outgoing.send(message.to_string()).map_err(|_| Error::transport("closed"))
+        }
+
+        async fn receive(&mut self) -> Result<String> {
+            self.incoming.recv().await.ok_or_else(|| Error::transport("closed"))
+        }
+
+        async fn close(&mut self) -> Result<()> {
+            self.incoming.close();
+            Ok(())
+        }
+    }
+
+    /// Counts from 1 to `count`
+    struct Counter;
+
+    #[async_trait]
+    impl SubscriptionSource for Counter {
+        async fn subscribe(
+            &self,
+            params: Option<Value>,
+            _context: &ServiceContext,
+        ) -> Result<BoxStream<'static, Result<Value>>> {
+            let count = params.unwrap_or(Value::Null)["count"].as_u64()
+                .ok_or_else(|| Error::invalid_params("missing count"))?;
+            Ok(futures::stream::iter((1..=count).map(|n| Ok(json!(n)))).boxed())
+        }
+    }
+
+    /// Client connected to a router serving `counter` streams next to a
+    /// `ping` method, with every message the server sends recorded
+    fn serve(config: MultiplexConfig) -> (MultiplexClient, Arc<MultiplexHandler>, Arc<Mutex<Vec<String>>>) {
+        let (client_tx, mut server_rx) = mpsc::unbounded_channel::<String>();
+        let (server_tx, client_rx) = mpsc::unbounded_channel();
+        let (sink, mut items) = mpsc::unbounded_channel();
+
+        let handler = Arc::new(MultiplexHandler::new(sink, config).unwrap().with_source("counter", Arc::new(Counter)));
+        let mut router = MethodRouter::new().with_handler(handler.clone()).unwrap();
+        router.register("ping", Arc::new(FnHandler::new("ping", |_params, _context| async { Ok("pong") }))).unwrap();
+
+        let sent = Arc::new(Mutex::new(Vec::new()));
+        let log = Arc::clone(&sent);
+        tokio::spawn(async move {
+            let context = ServiceContext::new("streams");
+            loop {
+                let message = tokio::select! {
+                    Some(message) = server_rx.recv() => {
+                        match router.dispatch_message(&message, &context).await {
+                            Some(response) => response,
+                            None => continue,
+                        }
+                    }
+                    Some(item) = items.recv() => serde_json::to_string(&item).unwrap(),
+                    else => break,
+                };
+                log.lock().push(message.clone());
+                let _ = server_tx.send(message);
+            }
+        });
+
+        let client = JsonRpcClient::new(ChannelTransport { outgoing: client_tx, incoming: client_rx });
+        (MultiplexClient::new(Arc::new(client)), handler, sent)
+    }
+
+    #[tokio::test]
+    async fn test_streams_are_windowed_and_interleaved() {
+        let (client, handler, sent) = serve(MultiplexConfig::default());
+        let client = client.with_window(4);
+
+        let mut big = client.open("counter", json!({"count": 1000})).await.unwrap();
+        let small = client.open("counter", json!({"count": 3})).await.unwrap();
+        assert_eq!(big.window(), 4);
+
+        // Without reads, the big stream stops at its window
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        let items = |stream: &str| sent.lock().iter()
+            .filter(|message| message.contains(STREAM_ITEM_METHOD) && message.contains(stream))
+            .count();
+        assert_eq!(items(big.id()), 4);
+
+        // The small stream and calls get through meanwhile
+        let results: Vec<_> = small.into_stream().map(|item| item.unwrap().result.unwrap()).collect().await;
+        assert_eq!(results, vec![json!(1), json!(2), json!(3)]);
+        let pong: String = client.client().call("ping", ()).await.unwrap();
+        assert_eq!(pong, "pong");
+
+        for expected in 1..=1000 {
+            assert_eq!(big.next_response().await.unwrap().unwrap().result, Some(json!(expected)));
+        }
+        assert!(big.next_response().await.is_none());
+        assert_eq!(handler.active_streams(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_close_and_stream_limit() {
+        let (client, handler, _sent) = serve(MultiplexConfig::default().with_max_streams(1));
+
+        let mut open = client.open("counter", json!({"count": 100})).await.unwrap();
+        assert_eq!(open.window(), 16);
+        assert_eq!(open.next_response().await.unwrap().unwrap().result, Some(json!(1)));
+        match client.open("counter", json!({"count": 1})).await {
+            Err(Error::JsonRpc(error)) => assert_eq!(error.code, -32006),
+            other => panic!("unexpected result: {:?}", other),
+        }
+
+        assert!(open.close().await.unwrap());
+        assert_eq!(handler.active_streams(), 0);
+        match client.open("missing", json!({})).await {
+            Err(Error::JsonRpc(error)) => assert_eq!(error.code, -32602),
+            other => panic!("unexpected result: {:?}", other),
+        }
+        assert!(MultiplexConfig::default().with_max_window(0).validate().is_err());
+    }
+}
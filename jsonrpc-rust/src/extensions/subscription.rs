@@ -0,0 +1,627 @@
+//! Streaming subscriptions
+//!
+//! A subscription named `ticker` is served by three methods:
+//!
+//! - `ticker.subscribe` starts the stream and returns its subscription id
+//! - `ticker.item` notifications carry each [`SubscriptionItem`], numbered
+//!   from 1; the last one is marked `done` or carries an error
+//! - `ticker.unsubscribe` with `[id]` stops the stream early
+//!
+//! On the server, a [`SubscriptionHandler`] registered on a
+//! [`MethodRouter`](crate::protocol::MethodRouter) runs the stream of a
+//! [`SubscriptionSource`] and pushes its items to a notification sink that
+//! the connection forwards to the client. On the client, a
+//! [`SubscriptionClient`] turns the notifications back into a
+//! [`Subscription`], checking that no item is lost or reordered.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use futures::stream::BoxStream;
+use futures::StreamExt;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::sync::mpsc;
+use tokio::task::AbortHandle;
+use uuid::Uuid;
+
+use crate::core::error::{Error, JsonRpcError, Result};
+use crate::core::future::JsonRpcStream;
+use crate::core::traits::MethodHandler;
+use crate::core::types::{JsonRpcRequest, JsonRpcResponse, SequenceValidator, ServiceContext, StreamMessage};
+use crate::protocol::JsonRpcClient;
+
+fn subscribe_method(name: &str) -> String {
+    format!("{}.subscribe", name)
+}
+
+fn unsubscribe_method(name: &str) -> String {
+    format!("{}.unsubscribe", name)
+}
+
+fn item_method(name: &str) -> String {
+    format!("{}.item", name)
+}
+
+/// One item of a subscription, sent as the params of an `.item` notification
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SubscriptionItem {
+    /// Subscription the item belongs to
+    pub subscription: String,
+    /// Position in the subscription, starting at 1
+    pub sequence: u64,
+    /// Produced value
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result: Option<Value>,
+    /// Error that ended the subscription
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<JsonRpcError>,
+    /// Marks the end of the subscription
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub done: bool,
+}
+
+impl SubscriptionItem {
+    /// Item carrying a value
+    pub fn result(subscription: impl Into<String>, sequence: u64, result: Value) -> Self {
+        Self {
+            subscription: subscription.into(),
+            sequence,
+            result: Some(result),
+            error: None,
+            done: false,
+        }
+    }
+
+    /// Item ending the subscription with an error
+    pub fn error(subscription: impl Into<String>, sequence: u64, error: JsonRpcError) -> Self {
+        Self {
+            subscription: subscription.into(),
+            sequence,
+            result: None,
+            error: Some(error),
+            done: false,
+        }
+    }
+
+    /// Item ending the subscription normally
+    pub fn done(subscription: impl Into<String>, sequence: u64) -> Self {
+        Self {
+            subscription: subscription.into(),
+            sequence,
+            result: None,
+            error: None,
+            done: true,
+        }
+    }
+
+    /// Wrap the item in its notification
+    pub fn into_notification(self, name: &str) -> Result<JsonRpcRequest> {
+        Ok(JsonRpcRequest::notification(item_method(name), Some(serde_json::to_value(self)?)))
+    }
+}
+
+/// Produces the stream behind a subscription
+#[async_trait]
+pub trait SubscriptionSource: Send + Sync {
+    /// Start a subscription with the params of its `.subscribe` call
+    async fn subscribe(
+        &self,
+        params: Option<Value>,
+        context: &ServiceContext,
+    ) -> Result<BoxStream<'static, Result<Value>>>;
+}
+
+/// Serves the `.subscribe` and `.unsubscribe` methods of a subscription
+pub struct SubscriptionHandler {
+    name: String,
+    source: Arc<dyn SubscriptionSource>,
+    sink: mpsc::UnboundedSender<JsonRpcRequest>,
+    active: Arc<Mutex<HashMap<String, AbortHandle>>>,
+}
+
+impl SubscriptionHandler {
+    /// Create a handler sending item notifications to `sink`
+    pub fn new(
+        name: impl Into<String>,
+        source: Arc<dyn SubscriptionSource>,
+        sink: mpsc::UnboundedSender<JsonRpcRequest>,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            source,
+            sink,
+            active: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Subscription name
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Number of subscriptions still producing items
+    pub fn active_subscriptions(&self) -> usize {
+        self.active.lock().len()
+    }
+
+    async fn subscribe(&self, params: Option<Value>, context: &ServiceContext) -> Result<Value> {
+        let stream = self.source.subscribe(params, context).await?;
+        let id = Uuid::new_v4().to_string();
+
+        // Hold the lock while spawning so the task cannot deregister
+        // itself before it is registered
+        let mut active = self.active.lock();
+        let task = tokio::spawn(pump(
+            self.name.clone(),
+            id.clone(),
+            stream,
+            self.sink.clone(),
+            Arc::clone(&self.active),
+        ));
+        active.insert(id.clone(), task.abort_handle());
+
+        Ok(Value::String(id))
+    }
+
+    fn unsubscribe(&self, params: Option<Value>) -> Result<Value> {
+        let id = match params {
+            Some(Value::Array(mut args)) if args.len() == 1 => args.pop(),
+            Some(Value::Object(mut args)) => args.remove("subscription"),
+            _ => None,
+        };
+        let Some(Value::String(id)) = id else {
+            return Err(Error::invalid_params("Expected a subscription id"));
+        };
+
+        let task = self.active.lock().remove(&id);
+        Ok(Value::Bool(match task {
+            Some(task) => {
+                task.abort();
+                true
+            }
+            None => false,
+        }))
+    }
+}
+
+#[async_trait]
+impl MethodHandler for SubscriptionHandler {
+    async fn handle_method(&self, request: &JsonRpcRequest, context: &ServiceContext) -> Result<JsonRpcResponse> {
+        let result = if request.method == subscribe_method(&self.name) {
+            self.subscribe(request.params.clone(), context).await?
+        } else if request.method == unsubscribe_method(&self.name) {
+            self.unsubscribe(request.params.clone())?
+        } else {
+            return Err(Error::method_not_found(&request.method));
+        };
+        Ok(JsonRpcResponse::success(request.id.clone().unwrap_or(Value::Null), result))
+    }
+
+    fn supported_methods(&self) -> Vec<String> {
+        vec![subscribe_method(&self.name), unsubscribe_method(&self.name)]
+    }
+}
+
+impl std::fmt::Debug for SubscriptionHandler {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SubscriptionHandler")
+            .field("name", &self.name)
+            .field("active", &self.active_subscriptions())
+            .finish()
+    }
+}
+
+/// Forward the items of one subscription to the notification sink
+async fn pump(
+    name: String,
+    id: String,
+    mut stream: BoxStream<'static, Result<Value>>,
+    sink: mpsc::UnboundedSender<JsonRpcRequest>,
+    active: Arc<Mutex<HashMap<String, AbortHandle>>>,
+) {
+    let mut sequence = 0;
+    let last = loop {
+        sequence += 1;
+        let item = match stream.next().await {
+            Some(Ok(result)) => SubscriptionItem::result(&id, sequence, result),
+            Some(Err(e)) => break SubscriptionItem::error(&id, sequence, e.to_jsonrpc_error()),
+            None => break SubscriptionItem::done(&id, sequence),
+        };
+        if !send_item(&name, item, &sink) {
+            active.lock().remove(&id);
+            return;
+        }
+    };
+
+    active.lock().remove(&id);
+    send_item(&name, last, &sink);
+}
+
+fn send_item(name: &str, item: SubscriptionItem, sink: &mpsc::UnboundedSender<JsonRpcRequest>) -> bool {
+    match item.into_notification(name) {
+        Ok(notification) => sink.send(notification).is_ok(),
+        Err(e) => {
+            tracing::error!("Failed to serialize subscription item: {}", e);
+            false
+        }
+    }
+}
+
+/// Items received for one subscription name, routed by subscription id
+#[derive(Default)]
+pub(crate) struct Demux {
+    subscribers: HashMap<String, mpsc::UnboundedSender<SubscriptionItem>>,
+    /// Subscribe calls in flight; items are only buffered while there are some
+    subscribing: usize,
+    /// Items that arrived before the response to their subscribe call
+    early: HashMap<String, Vec<SubscriptionItem>>,
+}
+
+impl Demux {
+    /// Route the items `client` receives as `method` notifications
+    pub(crate) fn spawn(client: &JsonRpcClient, method: String) -> Arc<Mutex<Self>> {
+        let demux = Arc::new(Mutex::new(Self::default()));
+        let mut notifications = client.notifications(method);
+        let routed = Arc::clone(&demux);
+        tokio::spawn(async move {
+            while let Some(notification) = notifications.recv().await {
+                match serde_json::from_value::<SubscriptionItem>(notification.params.unwrap_or(Value::Null)) {
+                    Ok(item) => routed.lock().deliver(item),
+                    Err(e) => tracing::warn!("Discarding malformed subscription item: {}", e),
+                }
+            }
+        });
+        demux
+    }
+
+    /// Receive the items of the stream `open` opens, keeping those that
+    /// arrive before it returns
+    pub(crate) async fn open<F>(demux: &Arc<Mutex<Self>>, open: F) -> Result<ItemReceiver>
+    where
+        F: std::future::Future<Output = Result<String>>,
+    {
+        demux.lock().subscribing += 1;
+        let opened = open.await;
+
+        let mut state = demux.lock();
+        state.subscribing -= 1;
+        let early = match &opened {
+            Ok(id) => state.early.remove(id).unwrap_or_default(),
+            Err(_) => Vec::new(),
+        };
+        if state.subscribing == 0 {
+            state.early.clear();
+        }
+        let id = opened?;
+
+        let (tx, items) = mpsc::unbounded_channel();
+        for item in early {
+            let _ = tx.send(item);
+        }
+        state.subscribers.insert(id.clone(), tx);
+        drop(state);
+
+        Ok(ItemReceiver {
+            id,
+            demux: Arc::clone(demux),
+            items,
+            validator: SequenceValidator::new(false),
+            finished: false,
+        })
+    }
+
+    fn deliver(&mut self, item: SubscriptionItem) {
+        if let Some(tx) = self.subscribers.get(&item.subscription) {
+            if let Err(mpsc::error::SendError(item)) = tx.send(item) {
+                self.subscribers.remove(&item.subscription);
+            }
+        } else if self.subscribing > 0 {
+            self.early.entry(item.subscription.clone()).or_default().push(item);
+        } else {
+            tracing::debug!("Discarding item for unknown subscription {}", item.subscription);
+        }
+    }
+}
+
+/// Opens subscriptions through a [`JsonRpcClient`]
+pub struct SubscriptionClient {
+    client: Arc<JsonRpcClient>,
+    demuxes: Mutex<HashMap<String, Arc<Mutex<Demux>>>>,
+}
+
+impl SubscriptionClient {
+    /// Create a subscription client sharing a JSON-RPC client
+    ///
+    /// The subscription client takes over the `.item` notifications of the
+    /// subscriptions it opens.
+    pub fn new(client: Arc<JsonRpcClient>) -> Self {
+        Self {
+            client,
+            demuxes: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Underlying JSON-RPC client
+    pub fn client(&self) -> &Arc<JsonRpcClient> {
+        &self.client
+    }
+
+    /// Call `<name>.subscribe` and start receiving its items
+    pub async fn subscribe(&self, name: &str, params: impl Serialize) -> Result<Subscription> {
+        let demux = self.demux(name);
+        let receiver = Demux::open(&demux, self.client.call(&subscribe_method(name), params)).await?;
+        Ok(Subscription {
+            name: name.to_string(),
+            client: Arc::clone(&self.client),
+            receiver,
+        })
+    }
+
+    fn demux(&self, name: &str) -> Arc<Mutex<Demux>> {
+        let mut demuxes = self.demuxes.lock();
+        if let Some(demux) = demuxes.get(name) {
+            return Arc::clone(demux);
+        }
+
+        let demux = Demux::spawn(&self.client, item_method(name));
+        demuxes.insert(name.to_string(), Arc::clone(&demux));
+        demux
+    }
+}
+
+impl std::fmt::Debug for SubscriptionClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SubscriptionClient")
+            .field("client", &self.client)
+            .finish()
+    }
+}
+
+/// Receives the items of one stream routed by a [`Demux`], checking their
+/// sequence
+pub(crate) struct ItemReceiver {
+    id: String,
+    demux: Arc<Mutex<Demux>>,
+    items: mpsc::UnboundedReceiver<SubscriptionItem>,
+    validator: SequenceValidator,
+    finished: bool,
+}
+
+impl ItemReceiver {
+    /// Id of the stream
+    pub(crate) fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Whether the stream has ended
+    pub(crate) fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// Wait for the next item; `None` once the stream has ended
+    pub(crate) async fn next_response(&mut self) -> Option<Result<JsonRpcResponse>> {
+        if self.finished {
+            return None;
+        }
+
+        let Some(item) = self.items.recv().await else {
+            self.finished = true;
+            return Some(Err(Error::connection(format!(
+                "Stream {} closed before it finished",
+                self.id
+            ))));
+        };
+
+        let id = Value::String(self.id.clone());
+        let response = match item.error {
+            Some(error) => JsonRpcResponse::error(id, error),
+            None => JsonRpcResponse::success(id, item.result.unwrap_or(Value::Null)),
+        };
+        if let Err(e) = self.validator.validate(&StreamMessage::new(response.clone(), item.sequence)) {
+            self.finished = true;
+            return Some(Err(e));
+        }
+
+        if item.done {
+            self.finished = true;
+            return None;
+        }
+        if let Some(error) = response.error {
+            self.finished = true;
+            return Some(Err(Error::JsonRpc(error)));
+        }
+        Some(Ok(response))
+    }
+}
+
+impl Drop for ItemReceiver {
+    fn drop(&mut self) {
+        self.demux.lock().subscribers.remove(&self.id);
+    }
+}
+
+/// Client side of an open subscription
+///
+/// Items are returned as responses whose id is the subscription id. An item
+/// arriving out of sequence ends the subscription with a validation error.
+pub struct Subscription {
+    name: String,
+    client: Arc<JsonRpcClient>,
+    receiver: ItemReceiver,
+}
+
+impl Subscription {
+    /// Subscription id assigned by the server
+    pub fn id(&self) -> &str {
+        self.receiver.id()
+    }
+
+    /// Subscription name
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Wait for the next item; `None` once the subscription has ended
+    pub async fn next_response(&mut self) -> Option<Result<JsonRpcResponse>> {
+        self.receiver.next_response().await
+    }
+
+    /// Stop the subscription; returns whether the server still had it running
+    pub async fn unsubscribe(self) -> Result<bool> {
+        self.client.call(&unsubscribe_method(&self.name), [self.id()]).await
+    }
+
+    /// Turn the subscription into a stream of its items
+    pub fn into_stream(self) -> JsonRpcStream {
+        JsonRpcStream::new(futures::stream::unfold(self, |mut subscription| async move {
+            let item = subscription.next_response().await?;
+            Some((item, subscription))
+        }))
+    }
+}
+
+impl std::fmt::Debug for Subscription {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Subscription")
+            .field("id", &self.id())
+            .field("name", &self.name)
+            .field("finished", &self.receiver.is_finished())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::traits::Transport;
+    use crate::protocol::MethodRouter;
+    use serde_json::json;
+
+    struct ChannelTransport {
+        outgoing: mpsc::UnboundedSender<String>,
+        incoming: mpsc::UnboundedReceiver<String>,
+    }
+
+    #[async_trait]
+    impl Transport for ChannelTransport {
+        async fn send(&mut self, message: &str) -> Result<()> {
+            self.outgoing.send(message.to_string()).map_err(|_| Error::transport("closed"))
+        }
+
+        async fn receive(&mut self) -> Result<String> {
+            self.incoming.recv().await.ok_or_else(|| Error::transport("closed"))
+        }
+
+        async fn close(&mut self) -> Result<()> {
+            self.incoming.close();
+            Ok(())
+        }
+    }
+
+    /// Counts from 1 to `count`, then either ends or stays open
+    struct Counter;
+
+    #[async_trait]
+    impl SubscriptionSource for Counter {
+        async fn subscribe(
+            &self,
+            params: Option<Value>,
+            _context: &ServiceContext,
+        ) -> Result<BoxStream<'static, Result<Value>>> {
+            let params = params.unwrap_or(Value::Null);
+            let count = params["count"].as_u64().ok_or_else(|| Error::invalid_params("missing count"))?;
+            let items = futures::stream::iter((1..=count).map(|n| Ok(json!(n))));
+            if params["open"] == json!(true) {
+                Ok(items.chain(futures::stream::pending()).boxed())
+            } else {
+                Ok(items.boxed())
+            }
+        }
+    }
+
+    /// Client connected to a router serving the `counter` subscription
+    fn serve() -> (SubscriptionClient, Arc<SubscriptionHandler>) {
+        let (client_tx, mut server_rx) = mpsc::unbounded_channel::<String>();
+        let (server_tx, client_rx) = mpsc::unbounded_channel();
+        let (sink, mut notifications) = mpsc::unbounded_channel();
+
+        let handler = Arc::new(SubscriptionHandler::new("counter", Arc::new(Counter), sink));
+        let router = MethodRouter::new().with_handler(handler.clone()).unwrap();
+        tokio::spawn(async move {
+            let context = ServiceContext::new("subscriptions");
+            loop {
+                tokio::select! {
+                    Some(message) = server_rx.recv() => {
+                        if let Some(response) = router.dispatch_message(&message, &context).await {
+                            let _ = server_tx.send(response);
+                        }
+                    }
+                    Some(notification) = notifications.recv() => {
+                        let _ = server_tx.send(serde_json::to_string(&notification).unwrap());
+                    }
+                    else => break,
+                }
+            }
+        });
+
+        let client = JsonRpcClient::new(ChannelTransport { outgoing: client_tx, incoming: client_rx });
+        (SubscriptionClient::new(Arc::new(client)), handler)
+    }
+
+    #[tokio::test]
+    async fn test_subscription_round_trip() {
+        let (subscriptions, handler) = serve();
+
+        let subscription = subscriptions.subscribe("counter", json!({ "count": 3 })).await.unwrap();
+        let id = json!(subscription.id());
+        let responses: Vec<_> = subscription.into_stream().collect().await;
+        let results: Vec<_> = responses.into_iter().map(|r| r.unwrap()).collect();
+        assert!(results.iter().all(|r| r.id == id));
+        assert_eq!(
+            results.into_iter().map(|r| r.result.unwrap()).collect::<Vec<_>>(),
+            vec![json!(1), json!(2), json!(3)]
+        );
+        assert_eq!(handler.active_subscriptions(), 0);
+
+        let mut open = subscriptions.subscribe("counter", json!({ "count": 1, "open": true })).await.unwrap();
+        assert_eq!(open.next_response().await.unwrap().unwrap().result, Some(json!(1)));
+        assert_eq!(handler.active_subscriptions(), 1);
+        assert!(open.unsubscribe().await.unwrap());
+        assert_eq!(handler.active_subscriptions(), 0);
+
+        match subscriptions.subscribe("counter", json!({})).await {
+            Err(Error::JsonRpc(error)) => assert_eq!(error.code, -32602),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sequence_gap_ends_subscription() {
+        let (client_tx, mut server_rx) = mpsc::unbounded_channel::<String>();
+        let (server_tx, client_rx) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            let request: JsonRpcRequest = serde_json::from_str(&server_rx.recv().await.unwrap()).unwrap();
+            assert_eq!(request.method, "feed.subscribe");
+
+            // Items may arrive before the subscribe response
+            for item in [SubscriptionItem::result("s1", 1, json!("a")), SubscriptionItem::result("s1", 3, json!("c"))] {
+                let notification = item.into_notification("feed").unwrap();
+                server_tx.send(serde_json::to_string(&notification).unwrap()).unwrap();
+            }
+            let response = JsonRpcResponse::success(request.id.unwrap(), json!("s1"));
+            server_tx.send(serde_json::to_string(&response).unwrap()).unwrap();
+            server_rx
+        });
+
+        let client = JsonRpcClient::new(ChannelTransport { outgoing: client_tx, incoming: client_rx });
+        let subscriptions = SubscriptionClient::new(Arc::new(client));
+        let mut subscription = subscriptions.subscribe("feed", ()).await.unwrap();
+
+        assert_eq!(subscription.next_response().await.unwrap().unwrap().result, Some(json!("a")));
+        assert!(matches!(subscription.next_response().await, Some(Err(Error::Validation { .. }))));
+        assert!(subscription.next_response().await.is_none());
+    }
+}
@@ -134,6 +134,10 @@
 //! - `benchmarks` - Benchmark support
 //! - `fuzz` - Fuzzing support
 //! - `prometheus` - Prometheus metrics integration
+//! - `macros` - `#[rpc_method]` typed handlers with JSON schemas
+//! - `validation` - Validation of call params against JSON schemas
+//! - `jwt` - JSON Web Token verification, with JWKS fetching
+//! - `otlp` - Export of call spans to an OpenTelemetry collector
 
 /// JSON-RPC version constant
 pub const JSONRPC_VERSION: &str = "2.0";
@@ -144,15 +148,6 @@ pub mod core;
 // Transport layer abstractions (Phase 2) - will be implemented in future phases
 // pub mod transport;
 
-// Protocol layer implementation (Phase 3) - will be implemented in future phases  
-// pub mod protocol;
-
-// Extension layer for advanced features (Phase 4) - will be implemented in future phases
-// pub mod extensions;
-
-// Convenience layer with macros and builders (Phase 5) - will be implemented in future phases
-// pub mod convenience;
-
 /// Prelude module for convenient imports
 /// 
 /// This module re-exports the most commonly used types and traits.
@@ -170,13 +165,17 @@ pub mod prelude {
     // Transport layer (Phase 2)
     pub use crate::transport::prelude::*;
     
+    // Protocol layer (Phase 3)
+    pub use crate::protocol::prelude::*;
+    
+    // Extension layer (Phase 4)
+    pub use crate::extensions::prelude::*;
+    
+    // Convenience layer (Phase 5)
+    pub use crate::convenience::prelude::*;
+    
     // Version constant
     pub use crate::JSONRPC_VERSION;
-    
-         // Future extensions (will be available in later phases)
-     // pub use crate::protocol::*;
-     // pub use crate::extensions::*;
-     // pub use crate::convenience::*;
 }
 
 // Modern modular exports (recommended)
@@ -258,6 +257,18 @@ pub mod info {
         #[cfg(feature = "prometheus")]
         features.push("prometheus");
         
+        #[cfg(feature = "macros")]
+        features.push("macros");
+
+        #[cfg(feature = "validation")]
+        features.push("validation");
+
+        #[cfg(feature = "jwt")]
+        features.push("jwt");
+
+        #[cfg(feature = "otlp")]
+        features.push("otlp");
+        
         features
     }
     
@@ -280,25 +291,26 @@ pub mod info {
 // Transport layer implementation (Phase 2)
 pub mod transport;
 
-pub mod protocol {
-    //! Protocol layer implementation (Phase 3)
-    //! 
-    //! This module will provide the core JSON-RPC 2.0 protocol implementation,
-    //! message routing, and request/response handling.
-}
+// Protocol layer implementation (Phase 3)
+pub mod protocol;
 
-pub mod extensions {
-    //! Extension layer for advanced features (Phase 4)
-    //! 
-    //! This module will provide streaming support, event systems,
-    //! and other advanced functionality.
-}
+// Extension layer implementation (Phase 4)
+pub mod extensions;
+
+// Convenience layer implementation (Phase 5)
+pub mod convenience;
+
+// Lets code generated by `#[rpc_method]` name this crate from inside it
+#[cfg(feature = "macros")]
+extern crate self as jsonrpc_rust;
+
+#[cfg(feature = "macros")]
+#[doc(hidden)]
+pub mod __private {
+    //! Items used by macro-generated code; not part of the public API
 
-pub mod convenience {
-    //! Convenience layer with macros and builders (Phase 5)
-    //! 
-    //! This module will provide procedural macros, builder patterns,
-    //! and runtime wrappers for easier usage.
+    pub use async_trait::async_trait;
+    pub use crate::convenience::typed::{method_info, no_params, parse_params, respond, schema_of};
 }
 
 #[cfg(test)]
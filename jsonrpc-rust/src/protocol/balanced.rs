@@ -0,0 +1,483 @@
+//! Load balancing and request hedging across endpoints
+//!
+//! A [`BalancedClient`] holds one [`JsonRpcClient`] per endpoint and sends
+//! each call to the endpoint a [`BalanceStrategy`] picks. Strategies for
+//! round-robin, fewest calls in flight and lowest latency are provided;
+//! any closure over [`EndpointStats`] works too.
+//!
+//! For idempotent methods, [`BalancedClient::call_hedged`] improves tail
+//! latency: if the first endpoint has not answered within its recent p99
+//! latency, the same call is sent to a second endpoint and whichever
+//! succeeds first wins. The slower attempt is abandoned, so only use it
+//! where running a call twice is harmless.
+
+use std::collections::VecDeque;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::core::error::{Error, Result};
+use super::client::JsonRpcClient;
+
+/// Latency samples kept per endpoint
+const LATENCY_SAMPLES: usize = 256;
+
+/// Load and latency of one endpoint, as seen by strategies
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EndpointStats {
+    /// Endpoint name
+    pub name: String,
+    /// Calls in flight
+    pub in_flight: usize,
+    /// Calls completed
+    pub calls: u64,
+    /// Calls that failed
+    pub failures: u64,
+    /// Mean latency of recent successful calls
+    pub mean_latency: Option<Duration>,
+}
+
+/// Picks the endpoint for a call
+pub trait BalanceStrategy: Send + Sync {
+    /// Index into `endpoints` of the one to use; `endpoints` is never empty
+    fn select(&self, endpoints: &[EndpointStats]) -> usize;
+}
+
+impl<F> BalanceStrategy for F
+where
+    F: Fn(&[EndpointStats]) -> usize + Send + Sync,
+{
+    fn select(&self, endpoints: &[EndpointStats]) -> usize {
+        self(endpoints)
+    }
+}
+
+/// Take endpoints in turn
+#[derive(Debug, Default)]
+pub struct RoundRobinStrategy {
+    next: AtomicUsize,
+}
+
+impl RoundRobinStrategy {
+    /// Start with the first endpoint
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl BalanceStrategy for RoundRobinStrategy {
+    fn select(&self, endpoints: &[EndpointStats]) -> usize {
+        self.next.fetch_add(1, Ordering::Relaxed) % endpoints.len()
+    }
+}
+
+/// Take the endpoint with the fewest calls in flight
+#[derive(Debug, Default, Clone, Copy)]
+pub struct LeastInFlightStrategy;
+
+impl BalanceStrategy for LeastInFlightStrategy {
+    fn select(&self, endpoints: &[EndpointStats]) -> usize {
+        endpoints.iter()
+            .enumerate()
+            .min_by_key(|(_, endpoint)| endpoint.in_flight)
+            .map_or(0, |(index, _)| index)
+    }
+}
+
+/// Take the endpoint with the lowest mean latency, trying endpoints with
+/// no samples yet first
+#[derive(Debug, Default, Clone, Copy)]
+pub struct LowestLatencyStrategy;
+
+impl BalanceStrategy for LowestLatencyStrategy {
+    fn select(&self, endpoints: &[EndpointStats]) -> usize {
+        endpoints.iter()
+            .enumerate()
+            .min_by_key(|(_, endpoint)| endpoint.mean_latency.unwrap_or(Duration::ZERO))
+            .map_or(0, |(index, _)| index)
+    }
+}
+
+/// When to send the second attempt of a hedged call
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HedgingConfig {
+    /// Latency percentile of the first endpoint to wait for
+    pub percentile: f64,
+    /// Lower bound on the wait
+    pub min_delay: Duration,
+    /// Wait used until the endpoint has `min_samples` latency samples
+    pub default_delay: Duration,
+    /// Samples needed before the percentile is trusted
+    pub min_samples: usize,
+}
+
+impl Default for HedgingConfig {
+    fn default() -> Self {
+        Self {
+            percentile: 0.99,
+            min_delay: Duration::from_millis(5),
+            default_delay: Duration::from_millis(100),
+            min_samples: 20,
+        }
+    }
+}
+
+impl HedgingConfig {
+    /// Set the latency percentile to wait for
+    pub fn with_percentile(mut self, percentile: f64) -> Self {
+        self.percentile = percentile;
+        self
+    }
+
+    /// Set the delay bounds
+    pub fn with_delays(mut self, min_delay: Duration, default_delay: Duration) -> Self {
+        self.min_delay = min_delay;
+        self.default_delay = default_delay;
+        self
+    }
+
+    /// Check the configuration is usable
+    pub fn validate(&self) -> Result<()> {
+        if !(self.percentile > 0.0 && self.percentile <= 1.0) {
+            return Err(Error::configuration("Hedging percentile must be in (0, 1]"));
+        }
+
+        if self.min_samples == 0 {
+            return Err(Error::configuration("Hedging min samples cannot be zero"));
+        }
+
+        Ok(())
+    }
+}
+
+struct Endpoint {
+    name: String,
+    client: JsonRpcClient,
+    in_flight: AtomicUsize,
+    calls: AtomicU64,
+    failures: AtomicU64,
+    latencies: parking_lot::Mutex<VecDeque<Duration>>,
+}
+
+impl Endpoint {
+    fn stats(&self) -> EndpointStats {
+        let latencies = self.latencies.lock();
+        let mean_latency = (!latencies.is_empty())
+            .then(|| latencies.iter().sum::<Duration>() / latencies.len() as u32);
+        EndpointStats {
+            name: self.name.clone(),
+            in_flight: self.in_flight.load(Ordering::SeqCst),
+            calls: self.calls.load(Ordering::SeqCst),
+            failures: self.failures.load(Ordering::SeqCst),
+            mean_latency,
+        }
+    }
+
+    fn record_latency(&self, latency: Duration) {
+        let mut latencies = self.latencies.lock();
+        latencies.push_back(latency);
+        if latencies.len() > LATENCY_SAMPLES {
+            latencies.pop_front();
+        }
+    }
+
+    fn percentile(&self, percentile: f64, min_samples: usize) -> Option<Duration> {
+        let mut samples: Vec<Duration> = self.latencies.lock().iter().copied().collect();
+        if samples.len() < min_samples {
+            return None;
+        }
+        samples.sort();
+        let rank = ((samples.len() as f64 * percentile).ceil() as usize).clamp(1, samples.len());
+        Some(samples[rank - 1])
+    }
+}
+
+/// Decrements an endpoint's in-flight count however the call ends
+struct InFlight<'a>(&'a Endpoint);
+
+impl Drop for InFlight<'_> {
+    fn drop(&mut self) {
+        self.0.in_flight.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// A client spreading calls over several endpoints
+pub struct BalancedClient {
+    endpoints: Vec<Endpoint>,
+    strategy: Box<dyn BalanceStrategy>,
+    hedging: Option<HedgingConfig>,
+    hedged_calls: AtomicU64,
+}
+
+impl BalancedClient {
+    /// Create a client with no endpoints, choosing between them with `strategy`
+    pub fn new(strategy: impl BalanceStrategy + 'static) -> Self {
+        Self {
+            endpoints: Vec::new(),
+            strategy: Box::new(strategy),
+            hedging: None,
+            hedged_calls: AtomicU64::new(0),
+        }
+    }
+
+    /// Add an endpoint
+    pub fn with_endpoint(mut self, name: impl Into<String>, client: JsonRpcClient) -> Self {
+        self.endpoints.push(Endpoint {
+            name: name.into(),
+            client,
+            in_flight: AtomicUsize::new(0),
+            calls: AtomicU64::new(0),
+            failures: AtomicU64::new(0),
+            latencies: parking_lot::Mutex::new(VecDeque::new()),
+        });
+        self
+    }
+
+    /// Enable hedging for [`call_hedged`](Self::call_hedged)
+    pub fn with_hedging(mut self, hedging: HedgingConfig) -> Result<Self> {
+        hedging.validate()?;
+        self.hedging = Some(hedging);
+        Ok(self)
+    }
+
+    /// Call a method on one endpoint
+    pub async fn call<P, R>(&self, method: &str, params: P) -> Result<R>
+    where
+        P: Serialize,
+        R: DeserializeOwned,
+    {
+        let params = serde_json::to_value(params)?;
+        let index = self.pick(None)?;
+        let result = self.attempt(index, method, params).await?;
+        Ok(serde_json::from_value(result)?)
+    }
+
+    /// Call an idempotent method, racing a second endpoint if the first is slow
+    ///
+    /// Behaves like [`call`](Self::call) when hedging is not enabled or
+    /// there is only one endpoint.
+    pub async fn call_hedged<P, R>(&self, method: &str, params: P) -> Result<R>
+    where
+        P: Serialize,
+        R: DeserializeOwned,
+    {
+        let params = serde_json::to_value(params)?;
+        let primary = self.pick(None)?;
+        let first = self.attempt(primary, method, params.clone());
+
+        let Some(ref hedging) = self.hedging else {
+            return Ok(serde_json::from_value(first.await?)?);
+        };
+        let delay = self.endpoints[primary]
+            .percentile(hedging.percentile, hedging.min_samples)
+            .unwrap_or(hedging.default_delay)
+            .max(hedging.min_delay);
+
+        tokio::pin!(first);
+        let secondary = tokio::select! {
+            result = &mut first => return Ok(serde_json::from_value(result?)?),
+            _ = tokio::time::sleep(delay) => self.pick(Some(primary)),
+        };
+        let Ok(secondary) = secondary else {
+            return Ok(serde_json::from_value(first.await?)?);
+        };
+
+        tracing::debug!(
+            "Hedging call to '{}': {} slower than {:?}, trying {}",
+            method, self.endpoints[primary].name, delay, self.endpoints[secondary].name
+        );
+        self.hedged_calls.fetch_add(1, Ordering::Relaxed);
+        let second = self.attempt(secondary, method, params);
+        tokio::pin!(second);
+
+        // The first success wins; a failure waits for the other attempt
+        let result = tokio::select! {
+            result = &mut first => match result {
+                Ok(value) => Ok(value),
+                Err(e) => second.await.map_err(|_| e),
+            },
+            result = &mut second => match result {
+                Ok(value) => Ok(value),
+                Err(_) => first.await,
+            },
+        };
+        Ok(serde_json::from_value(result?)?)
+    }
+
+    /// Load and latency of every endpoint
+    pub fn endpoint_stats(&self) -> Vec<EndpointStats> {
+        self.endpoints.iter().map(Endpoint::stats).collect()
+    }
+
+    /// Number of calls that sent a second attempt
+    pub fn hedged_calls(&self) -> u64 {
+        self.hedged_calls.load(Ordering::Relaxed)
+    }
+
+    /// Close every endpoint's client
+    pub async fn close(&self) -> Result<()> {
+        for endpoint in &self.endpoints {
+            endpoint.client.close().await?;
+        }
+        Ok(())
+    }
+
+    /// Choose an open endpoint other than `exclude`
+    fn pick(&self, exclude: Option<usize>) -> Result<usize> {
+        let candidates: Vec<usize> = (0..self.endpoints.len())
+            .filter(|&index| Some(index) != exclude && !self.endpoints[index].client.is_closed())
+            .collect();
+        if candidates.is_empty() {
+            return Err(Error::connection("No endpoint available"));
+        }
+
+        let stats: Vec<EndpointStats> = candidates.iter().map(|&index| self.endpoints[index].stats()).collect();
+        let choice = self.strategy.select(&stats);
+        candidates.get(choice).copied()
+            .ok_or_else(|| Error::service(format!("Balance strategy chose endpoint {} of {}", choice, candidates.len())))
+    }
+
+    fn attempt<'a>(&'a self, index: usize, method: &'a str, params: Value) -> impl Future<Output = Result<Value>> + 'a {
+        let endpoint = &self.endpoints[index];
+        async move {
+            endpoint.in_flight.fetch_add(1, Ordering::SeqCst);
+            let _in_flight = InFlight(endpoint);
+            let started = Instant::now();
+
+            let result = endpoint.client.call::<_, Value>(method, params).await;
+            endpoint.calls.fetch_add(1, Ordering::Relaxed);
+            match result {
+                Ok(_) => endpoint.record_latency(started.elapsed()),
+                Err(_) => {
+                    endpoint.failures.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+            result
+        }
+    }
+}
+
+impl std::fmt::Debug for BalancedClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BalancedClient")
+            .field("endpoints", &self.endpoint_stats())
+            .field("hedging", &self.hedging)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::traits::Transport;
+    use crate::core::types::{JsonRpcRequest, JsonRpcResponse};
+    use async_trait::async_trait;
+    use serde_json::json;
+    use std::sync::Arc;
+    use tokio::sync::mpsc;
+
+    struct Link {
+        tx: mpsc::UnboundedSender<String>,
+        rx: mpsc::UnboundedReceiver<String>,
+    }
+
+    #[async_trait]
+    impl Transport for Link {
+        async fn send(&mut self, message: &str) -> Result<()> {
+            self.tx.send(message.to_string()).map_err(|_| Error::connection("link broken"))
+        }
+
+        async fn receive(&mut self) -> Result<String> {
+            self.rx.recv().await.ok_or_else(|| Error::connection("link broken"))
+        }
+
+        async fn close(&mut self) -> Result<()> {
+            self.rx.close();
+            Ok(())
+        }
+    }
+
+    /// Client for a server answering every call with its name after `delay`
+    fn endpoint(name: &'static str, delay: Duration) -> JsonRpcClient {
+        let (a_tx, a_rx) = mpsc::unbounded_channel::<String>();
+        let (b_tx, b_rx) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            let mut requests = a_rx;
+            while let Some(message) = requests.recv().await {
+                let request: JsonRpcRequest = serde_json::from_str(&message).unwrap();
+                let response = JsonRpcResponse::success(request.id.unwrap(), json!(name));
+                let b_tx = b_tx.clone();
+                tokio::spawn(async move {
+                    tokio::time::sleep(delay).await;
+                    let _ = b_tx.send(serde_json::to_string(&response).unwrap());
+                });
+            }
+        });
+        JsonRpcClient::new(Link { tx: a_tx, rx: b_rx })
+    }
+
+    #[tokio::test]
+    async fn test_strategies() {
+        let client = BalancedClient::new(RoundRobinStrategy::new())
+            .with_endpoint("a", endpoint("a", Duration::ZERO))
+            .with_endpoint("b", endpoint("b", Duration::ZERO));
+        let mut answers = Vec::new();
+        for _ in 0..4 {
+            answers.push(client.call::<_, String>("who", ()).await.unwrap());
+        }
+        assert_eq!(answers, vec!["a", "b", "a", "b"]);
+
+        // Any closure is a strategy
+        let client = BalancedClient::new(|endpoints: &[EndpointStats]| endpoints.len() - 1)
+            .with_endpoint("a", endpoint("a", Duration::ZERO))
+            .with_endpoint("b", endpoint("b", Duration::ZERO));
+        assert_eq!(client.call::<_, String>("who", ()).await.unwrap(), "b");
+        let stats = client.endpoint_stats();
+        assert_eq!((stats[0].calls, stats[1].calls), (0, 1));
+        assert!(stats[1].mean_latency.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_least_in_flight() {
+        let client = Arc::new(BalancedClient::new(LeastInFlightStrategy)
+            .with_endpoint("slow", endpoint("slow", Duration::from_millis(200)))
+            .with_endpoint("fast", endpoint("fast", Duration::ZERO)));
+
+        let busy = tokio::spawn({
+            let client = Arc::clone(&client);
+            async move { client.call::<_, String>("who", ()).await }
+        });
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert_eq!(client.endpoint_stats()[0].in_flight, 1);
+        assert_eq!(client.call::<_, String>("who", ()).await.unwrap(), "fast");
+        assert_eq!(busy.await.unwrap().unwrap(), "slow");
+    }
+
+    #[tokio::test]
+    async fn test_hedged_call_takes_first_response() {
+        let hedging = HedgingConfig::default().with_delays(Duration::from_millis(5), Duration::from_millis(20));
+        let client = BalancedClient::new(RoundRobinStrategy::new())
+            .with_endpoint("slow", endpoint("slow", Duration::from_secs(5)))
+            .with_endpoint("fast", endpoint("fast", Duration::ZERO))
+            .with_hedging(hedging)
+            .unwrap();
+
+        let started = Instant::now();
+        assert_eq!(client.call_hedged::<_, String>("who", ()).await.unwrap(), "fast");
+        assert!(started.elapsed() < Duration::from_secs(1));
+        assert_eq!(client.hedged_calls(), 1);
+        // The abandoned attempt is no longer counted as in flight
+        assert_eq!(client.endpoint_stats()[0].in_flight, 0);
+
+        // A primary answering in time is not hedged
+        let client = BalancedClient::new(LeastInFlightStrategy)
+            .with_endpoint("fast", endpoint("fast", Duration::ZERO))
+            .with_hedging(HedgingConfig::default())
+            .unwrap();
+        assert_eq!(client.call_hedged::<_, String>("who", ()).await.unwrap(), "fast");
+        assert_eq!(client.hedged_calls(), 0);
+    }
+}
@@ -0,0 +1,472 @@
+//! Client-side caching of results of idempotent methods
+//!
+//! A [`CachingClient`] answers repeated calls to the methods named in its
+//! [`CacheConfig`] from a [`ResponseCache`], keyed on the method and a hash
+//! of the params. Results stay fresh for the configured TTL, and the least
+//! recently used go first once the cache is full. Error responses are never
+//! cached.
+//!
+//! With stale-while-revalidate, a result past its TTL is still returned for
+//! a while longer; the first call finding it stale refreshes it in the
+//! background.
+//!
+//! [`CachingClient::call_with_info`] reports how a call was answered in the
+//! [`CacheInfo`] of its [`ResponseMetaInfo`].
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::core::error::Result;
+use crate::core::types::{CacheInfo, ResponseMetaInfo};
+use super::client::JsonRpcClient;
+
+/// Response cache configuration
+#[derive(Debug, Clone)]
+pub struct CacheConfig {
+    /// How long a result stays fresh
+    pub ttl: Duration,
+    /// Results kept at most
+    pub max_entries: usize,
+    /// How long past its TTL a result is still returned while it is
+    /// refreshed; `None` fetches expired results again before answering
+    pub stale_while_revalidate: Option<Duration>,
+    /// Methods whose results are cached; calls to any other method always
+    /// go to the server
+    pub methods: HashSet<String>,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self {
+            ttl: Duration::from_secs(60),
+            max_entries: 1000,
+            stale_while_revalidate: None,
+            methods: HashSet::new(),
+        }
+    }
+}
+
+impl CacheConfig {
+    /// Set how long results stay fresh
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Set the number of results kept
+    pub fn with_max_entries(mut self, max_entries: usize) -> Self {
+        self.max_entries = max_entries;
+        self
+    }
+
+    /// Return expired results for up to `window` while refreshing them
+    pub fn with_stale_while_revalidate(mut self, window: Duration) -> Self {
+        self.stale_while_revalidate = Some(window);
+        self
+    }
+
+    /// Cache the results of an idempotent method
+    pub fn with_method(mut self, method: impl Into<String>) -> Self {
+        self.methods.insert(method.into());
+        self
+    }
+}
+
+/// A cached result
+struct Entry {
+    method: String,
+    /// Serialized params, telling apart params whose hashes collide
+    params: String,
+    value: Value,
+    stored: Instant,
+    cached_at: SystemTime,
+    /// Position in `Entries::recency`
+    used: u64,
+    revalidating: bool,
+}
+
+#[derive(Default)]
+struct Entries {
+    map: HashMap<String, Entry>,
+    /// Keys by last use, least recent first
+    recency: BTreeMap<u64, String>,
+    tick: u64,
+}
+
+impl Entries {
+    fn touch(&mut self, key: &str) {
+        if let Some(entry) = self.map.get_mut(key) {
+            self.recency.remove(&entry.used);
+            self.tick += 1;
+            entry.used = self.tick;
+            self.recency.insert(self.tick, key.to_string());
+        }
+    }
+
+    fn remove(&mut self, key: &str) -> Option<Entry> {
+        let entry = self.map.remove(key)?;
+        self.recency.remove(&entry.used);
+        Some(entry)
+    }
+}
+
+/// How a lookup found the cache
+enum Lookup {
+    Miss,
+    Fresh { value: Value, cached_at: SystemTime },
+    /// Past its TTL; `refresh` is set for the one call that should refresh it
+    Stale { value: Value, cached_at: SystemTime, refresh: bool },
+}
+
+/// Results of idempotent calls, shared by the clients using it
+pub struct ResponseCache {
+    config: CacheConfig,
+    entries: parking_lot::Mutex<Entries>,
+}
+
+impl ResponseCache {
+    /// Create an empty cache
+    pub fn new(config: CacheConfig) -> Self {
+        Self {
+            config,
+            entries: parking_lot::Mutex::new(Entries::default()),
+        }
+    }
+
+    /// Cache configuration
+    pub fn config(&self) -> &CacheConfig {
+        &self.config
+    }
+
+    /// Whether results of `method` are cached
+    pub fn is_cached(&self, method: &str) -> bool {
+        self.config.methods.contains(method)
+    }
+
+    /// Key the result of calling `method` with `params` is cached under
+    pub fn key(method: &str, params: &Value) -> String {
+        Self::key_of(method, &params.to_string())
+    }
+
+    fn key_of(method: &str, params: &str) -> String {
+        let mut hasher = DefaultHasher::new();
+        params.hash(&mut hasher);
+        format!("{}:{:016x}", method, hasher.finish())
+    }
+
+    /// Number of cached results, expired ones included
+    pub fn len(&self) -> usize {
+        self.entries.lock().map.len()
+    }
+
+    /// Whether nothing is cached
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Drop the cached results of `method`, returning how many there were
+    pub fn invalidate(&self, method: &str) -> usize {
+        let mut entries = self.entries.lock();
+        let keys: Vec<String> = entries.map.iter()
+            .filter(|(_, entry)| entry.method == method)
+            .map(|(key, _)| key.clone())
+            .collect();
+        for key in &keys {
+            entries.remove(key);
+        }
+        keys.len()
+    }
+
+    /// Drop every cached result
+    pub fn clear(&self) {
+        *self.entries.lock() = Entries::default();
+    }
+
+    fn lookup(&self, key: &str, params: &str) -> Lookup {
+        let mut entries = self.entries.lock();
+        let Some(entry) = entries.map.get_mut(key).filter(|entry| entry.params == params) else {
+            return Lookup::Miss;
+        };
+
+        let age = entry.stored.elapsed();
+        let stale = age >= self.config.ttl;
+        let servable = match self.config.stale_while_revalidate {
+            Some(window) => age < self.config.ttl + window,
+            None => !stale,
+        };
+        if !servable {
+            return Lookup::Miss;
+        }
+
+        let (value, cached_at) = (entry.value.clone(), entry.cached_at);
+        let lookup = if stale {
+            let refresh = !entry.revalidating;
+            entry.revalidating = true;
+            Lookup::Stale { value, cached_at, refresh }
+        } else {
+            Lookup::Fresh { value, cached_at }
+        };
+        entries.touch(key);
+        lookup
+    }
+
+    /// Cache `value`, evicting the least recently used results over the limit
+    fn store(&self, key: &str, method: &str, params: String, value: Value) -> SystemTime {
+        let cached_at = SystemTime::now();
+        if self.config.max_entries == 0 {
+            return cached_at;
+        }
+
+        let mut entries = self.entries.lock();
+        entries.remove(key);
+        entries.map.insert(key.to_string(), Entry {
+            method: method.to_string(),
+            params,
+            value,
+            stored: Instant::now(),
+            cached_at,
+            used: 0,
+            revalidating: false,
+        });
+        entries.touch(key);
+
+        while entries.map.len() > self.config.max_entries {
+            let Some((_, oldest)) = entries.recency.pop_first() else { break };
+            entries.map.remove(&oldest);
+        }
+        cached_at
+    }
+
+    /// Let the next call finding `key` stale try refreshing it again
+    fn revalidation_failed(&self, key: &str) {
+        if let Some(entry) = self.entries.lock().map.get_mut(key) {
+            entry.revalidating = false;
+        }
+    }
+}
+
+impl std::fmt::Debug for ResponseCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ResponseCache")
+            .field("config", &self.config)
+            .field("entries", &self.len())
+            .finish()
+    }
+}
+
+/// A client answering calls to idempotent methods from a [`ResponseCache`]
+#[derive(Debug)]
+pub struct CachingClient {
+    client: Arc<JsonRpcClient>,
+    cache: Arc<ResponseCache>,
+}
+
+impl CachingClient {
+    /// Cache the results `client` gets as `config` says
+    pub fn new(client: impl Into<Arc<JsonRpcClient>>, config: CacheConfig) -> Self {
+        Self::with_cache(client, Arc::new(ResponseCache::new(config)))
+    }
+
+    /// Use a cache that may be shared with other clients of the same server
+    pub fn with_cache(client: impl Into<Arc<JsonRpcClient>>, cache: Arc<ResponseCache>) -> Self {
+        Self {
+            client: client.into(),
+            cache,
+        }
+    }
+
+    /// Call a method, answering from the cache when it can
+    pub async fn call<P, R>(&self, method: &str, params: P) -> Result<R>
+    where
+        P: Serialize,
+        R: DeserializeOwned,
+    {
+        Ok(self.call_with_info(method, params).await?.0)
+    }
+
+    /// Call a method, also returning metadata whose `cache_info` tells
+    /// whether the result came from the cache
+    ///
+    /// Calls to methods that are not cached have no `cache_info`.
+    pub async fn call_with_info<P, R>(&self, method: &str, params: P) -> Result<(R, ResponseMetaInfo)>
+    where
+        P: Serialize,
+        R: DeserializeOwned,
+    {
+        let params = serde_json::to_value(params)?;
+        let mut meta = ResponseMetaInfo::new();
+        if !self.cache.is_cached(method) {
+            return Ok((self.client.call(method, params).await?, meta));
+        }
+
+        let text = params.to_string();
+        let key = ResponseCache::key_of(method, &text);
+        let (value, cache_hit, cached_at) = match self.cache.lookup(&key, &text) {
+            Lookup::Fresh { value, cached_at } => (value, true, cached_at),
+            Lookup::Stale { value, cached_at, refresh } => {
+                if refresh {
+                    self.revalidate(method, params, key.clone(), text);
+                }
+                (value, true, cached_at)
+            }
+            Lookup::Miss => {
+                let value: Value = self.client.call(method, params).await?;
+                let cached_at = self.cache.store(&key, method, text, value.clone());
+                (value, false, cached_at)
+            }
+        };
+
+        meta.cache_info = Some(CacheInfo {
+            cache_hit,
+            cache_key: Some(key),
+            ttl_seconds: Some(self.cache.config.ttl.as_secs()),
+            cached_at: Some(cached_at),
+        });
+        Ok((serde_json::from_value(value)?, meta))
+    }
+
+    /// Send a notification; notifications are never cached
+    pub async fn notify<P: Serialize>(&self, method: &str, params: P) -> Result<()> {
+        self.client.notify(method, params).await
+    }
+
+    /// The wrapped client
+    pub fn client(&self) -> &Arc<JsonRpcClient> {
+        &self.client
+    }
+
+    /// The cache in use
+    pub fn cache(&self) -> &Arc<ResponseCache> {
+        &self.cache
+    }
+
+    /// Refresh a stale result in the background
+    fn revalidate(&self, method: &str, params: Value, key: String, text: String) {
+        let client = Arc::clone(&self.client);
+        let cache = Arc::clone(&self.cache);
+        let method = method.to_string();
+        tokio::spawn(async move {
+            match client.call::<_, Value>(&method, params).await {
+                Ok(value) => {
+                    cache.store(&key, &method, text, value);
+                }
+                Err(e) => {
+                    tracing::debug!("Failed to revalidate cached '{}': {}", method, e);
+                    cache.revalidation_failed(&key);
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::error::Error;
+    use crate::core::traits::Transport;
+    use crate::core::types::{JsonRpcRequest, JsonRpcResponse};
+    use async_trait::async_trait;
+    use serde_json::json;
+    use tokio::sync::mpsc;
+
+    struct Link {
+        tx: mpsc::UnboundedSender<String>,
+        rx: mpsc::UnboundedReceiver<String>,
+    }
+
+    #[async_trait]
+    impl Transport for Link {
+        async fn send(&mut self, message: &str) -> Result<()> {
+            self.tx.send(message.to_string()).map_err(|_| Error::connection("link broken"))
+        }
+
+        async fn receive(&mut self) -> Result<String> {
+            self.rx.recv().await.ok_or_else(|| Error::connection("link broken"))
+        }
+
+        async fn close(&mut self) -> Result<()> {
+            self.rx.close();
+            Ok(())
+        }
+    }
+
+    /// Client for a server answering every call with how many it has had
+    fn counting() -> JsonRpcClient {
+        let (a_tx, mut a_rx) = mpsc::unbounded_channel::<String>();
+        let (b_tx, b_rx) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            let mut calls = 0;
+            while let Some(message) = a_rx.recv().await {
+                let request: JsonRpcRequest = serde_json::from_str(&message).unwrap();
+                calls += 1;
+                let response = JsonRpcResponse::success(request.id.unwrap(), json!(calls));
+                let _ = b_tx.send(serde_json::to_string(&response).unwrap());
+            }
+        });
+        JsonRpcClient::new(Link { tx: a_tx, rx: b_rx })
+    }
+
+    #[tokio::test]
+    async fn test_hits_expiry_and_eviction() {
+        let config = CacheConfig::default()
+            .with_ttl(Duration::from_millis(100))
+            .with_max_entries(2)
+            .with_method("get");
+        let client = CachingClient::new(counting(), config);
+
+        let (first, meta) = client.call_with_info::<_, u64>("get", ["a"]).await.unwrap();
+        let info = meta.cache_info.unwrap();
+        assert!(!info.cache_hit);
+        assert_eq!(info.cache_key, Some(ResponseCache::key("get", &json!(["a"]))));
+        let (again, meta) = client.call_with_info::<_, u64>("get", ["a"]).await.unwrap();
+        assert_eq!((first, again), (1, 1));
+        assert!(meta.cache_info.unwrap().cache_hit);
+
+        // Other params and uncached methods go to the server
+        assert_eq!(client.call::<_, u64>("get", ["b"]).await.unwrap(), 2);
+        let (put, meta) = client.call_with_info::<_, u64>("put", ["a"]).await.unwrap();
+        assert_eq!(put, 3);
+        assert!(meta.cache_info.is_none());
+
+        // "a" was used last, so "b" is evicted for "c"
+        client.call::<_, u64>("get", ["a"]).await.unwrap();
+        assert_eq!(client.call::<_, u64>("get", ["c"]).await.unwrap(), 4);
+        assert_eq!(client.cache().len(), 2);
+        assert_eq!(client.call::<_, u64>("get", ["a"]).await.unwrap(), 1);
+        assert_eq!(client.call::<_, u64>("get", ["b"]).await.unwrap(), 5);
+
+        tokio::time::sleep(Duration::from_millis(150)).await;
+        assert_eq!(client.call::<_, u64>("get", ["b"]).await.unwrap(), 6);
+
+        assert_eq!(client.cache().invalidate("get"), 2);
+        assert!(client.cache().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_stale_while_revalidate() {
+        let config = CacheConfig::default()
+            .with_ttl(Duration::from_millis(50))
+            .with_stale_while_revalidate(Duration::from_secs(10))
+            .with_method("get");
+        let client = CachingClient::new(counting(), config);
+        assert_eq!(client.call::<_, u64>("get", ()).await.unwrap(), 1);
+
+        // Stale results are still served, and refreshed only once
+        tokio::time::sleep(Duration::from_millis(80)).await;
+        let (stale, meta) = client.call_with_info::<_, u64>("get", ()).await.unwrap();
+        assert_eq!(stale, 1);
+        assert!(meta.cache_info.unwrap().cache_hit);
+        assert_eq!(client.call::<_, u64>("get", ()).await.unwrap(), 1);
+
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(client.call::<_, u64>("get", ()).await.unwrap(), 2);
+        assert_eq!(client.call::<_, u64>("get", ()).await.unwrap(), 2);
+    }
+}
@@ -0,0 +1,543 @@
+//! Circuit breaking for clients
+//!
+//! A [`CircuitBreaker`] watches the outcome of recent calls, per endpoint
+//! or per method, and stops sending calls to a target that keeps failing.
+//! While a circuit is open, calls fail immediately with
+//! [`Error::CircuitOpen`] so callers can fall back instead of waiting on
+//! timeouts. After a cool-down the circuit lets a few probe calls through
+//! (half-open) and closes again once they succeed.
+//!
+//! Only errors that say the target is unavailable (the retryable ones:
+//! transport, connection, service and timeout errors) count as failures.
+//! An error response is a sign the target is up, so it counts as a success.
+
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::core::error::{Error, Result};
+use super::client::JsonRpcClient;
+
+/// What a circuit covers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CircuitScope {
+    /// One circuit per endpoint
+    Endpoint,
+    /// One circuit per method of each endpoint
+    Method,
+}
+
+/// Circuit breaker configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CircuitBreakerConfig {
+    /// What each circuit covers
+    pub scope: CircuitScope,
+    /// Share of failed calls in the window that opens the circuit
+    pub failure_rate_threshold: f64,
+    /// Calls needed in the window before the rate is acted on
+    pub minimum_calls: usize,
+    /// Number of recent calls the rate is computed over
+    pub window_size: usize,
+    /// How long an open circuit rejects calls before probing
+    pub open_duration: Duration,
+    /// Probe calls allowed at once while half-open; this many successes
+    /// close the circuit
+    pub half_open_probes: usize,
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self {
+            scope: CircuitScope::Endpoint,
+            failure_rate_threshold: 0.5,
+            minimum_calls: 10,
+            window_size: 20,
+            open_duration: Duration::from_secs(30),
+            half_open_probes: 1,
+        }
+    }
+}
+
+impl CircuitBreakerConfig {
+    /// Set what each circuit covers
+    pub fn with_scope(mut self, scope: CircuitScope) -> Self {
+        self.scope = scope;
+        self
+    }
+
+    /// Open the circuit once `rate` of at least `minimum_calls` recent calls failed
+    pub fn with_failure_rate(mut self, rate: f64, minimum_calls: usize) -> Self {
+        self.failure_rate_threshold = rate;
+        self.minimum_calls = minimum_calls;
+        self.window_size = self.window_size.max(minimum_calls);
+        self
+    }
+
+    /// Set the number of recent calls considered
+    pub fn with_window_size(mut self, window_size: usize) -> Self {
+        self.window_size = window_size;
+        self
+    }
+
+    /// Set how long an open circuit waits before probing
+    pub fn with_open_duration(mut self, open_duration: Duration) -> Self {
+        self.open_duration = open_duration;
+        self
+    }
+
+    /// Set the number of probe calls while half-open
+    pub fn with_half_open_probes(mut self, probes: usize) -> Self {
+        self.half_open_probes = probes;
+        self
+    }
+
+    /// Check the configuration is usable
+    pub fn validate(&self) -> Result<()> {
+        if !(self.failure_rate_threshold > 0.0 && self.failure_rate_threshold <= 1.0) {
+            return Err(Error::configuration("Failure rate threshold must be in (0, 1]"));
+        }
+
+        if self.minimum_calls == 0 || self.window_size < self.minimum_calls {
+            return Err(Error::configuration("Window size must be at least the minimum calls, which cannot be zero"));
+        }
+
+        if self.half_open_probes == 0 {
+            return Err(Error::configuration("Half-open probes cannot be zero"));
+        }
+
+        Ok(())
+    }
+}
+
+/// State of one circuit
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CircuitState {
+    /// Calls go through and their outcomes are recorded
+    Closed,
+    /// Calls are rejected
+    Open,
+    /// A limited number of probe calls go through
+    HalfOpen,
+}
+
+/// Snapshot of one circuit
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CircuitStats {
+    /// Current state
+    pub state: CircuitState,
+    /// Calls in the window
+    pub calls: usize,
+    /// Failed calls in the window, timeouts included
+    pub failures: usize,
+    /// Timed out calls in the window
+    pub timeouts: usize,
+    /// Calls rejected since the circuit last opened
+    pub rejected: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Outcome {
+    Success,
+    Failure,
+    Timeout,
+}
+
+#[derive(Debug)]
+struct Circuit {
+    state: CircuitState,
+    window: VecDeque<Outcome>,
+    opened_at: Option<Instant>,
+    probes_in_flight: usize,
+    probe_successes: usize,
+    rejected: u64,
+}
+
+impl Circuit {
+    fn new() -> Self {
+        Self {
+            state: CircuitState::Closed,
+            window: VecDeque::new(),
+            opened_at: None,
+            probes_in_flight: 0,
+            probe_successes: 0,
+            rejected: 0,
+        }
+    }
+
+    fn open(&mut self) {
+        self.state = CircuitState::Open;
+        self.opened_at = Some(Instant::now());
+        self.window.clear();
+        self.probes_in_flight = 0;
+        self.probe_successes = 0;
+        self.rejected = 0;
+    }
+
+    fn close(&mut self) {
+        self.state = CircuitState::Closed;
+        self.opened_at = None;
+        self.window.clear();
+        self.probes_in_flight = 0;
+        self.probe_successes = 0;
+    }
+
+    fn count(&self, outcome: Outcome) -> usize {
+        self.window.iter().filter(|&&recorded| recorded == outcome).count()
+    }
+}
+
+/// How a call was admitted
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Admission {
+    Normal,
+    Probe,
+}
+
+/// Tracks call outcomes and rejects calls to failing targets
+///
+/// Shared between clients, typically behind an `Arc`; each circuit is
+/// keyed by endpoint, or by endpoint and method (see [`CircuitScope`]).
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    config: CircuitBreakerConfig,
+    circuits: parking_lot::Mutex<HashMap<String, Circuit>>,
+}
+
+impl CircuitBreaker {
+    /// Create a breaker with no circuits yet
+    pub fn new(config: CircuitBreakerConfig) -> Result<Self> {
+        config.validate()?;
+        Ok(Self {
+            config,
+            circuits: parking_lot::Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Breaker configuration
+    pub fn config(&self) -> &CircuitBreakerConfig {
+        &self.config
+    }
+
+    /// Circuit key for a call to `method` on `endpoint`
+    pub fn circuit_key(&self, endpoint: &str, method: &str) -> String {
+        match self.config.scope {
+            CircuitScope::Endpoint => endpoint.to_string(),
+            CircuitScope::Method => format!("{}#{}", endpoint, method),
+        }
+    }
+
+    /// Run `call` through the circuit `key`
+    ///
+    /// Returns [`Error::CircuitOpen`] without polling `call` when the
+    /// circuit rejects it.
+    pub async fn call<F, T>(&self, key: &str, call: F) -> Result<T>
+    where
+        F: Future<Output = Result<T>>,
+    {
+        let admission = self.admit(key)?;
+        let mut guard = AdmissionGuard { breaker: self, key, admission: Some(admission) };
+
+        let result = call.await;
+        let outcome = match result {
+            Err(Error::Timeout { .. }) => Outcome::Timeout,
+            Err(ref e) if e.is_retryable() => Outcome::Failure,
+            _ => Outcome::Success,
+        };
+        if let Some(admission) = guard.admission.take() {
+            self.record(key, admission, outcome);
+        }
+        result
+    }
+
+    /// Current state of a circuit; unknown circuits are closed
+    pub fn state(&self, key: &str) -> CircuitState {
+        self.stats(key).state
+    }
+
+    /// Snapshot of a circuit
+    pub fn stats(&self, key: &str) -> CircuitStats {
+        let circuits = self.circuits.lock();
+        match circuits.get(key) {
+            Some(circuit) => CircuitStats {
+                state: circuit.state,
+                calls: circuit.window.len(),
+                failures: circuit.count(Outcome::Failure) + circuit.count(Outcome::Timeout),
+                timeouts: circuit.count(Outcome::Timeout),
+                rejected: circuit.rejected,
+            },
+            None => CircuitStats {
+                state: CircuitState::Closed,
+                calls: 0,
+                failures: 0,
+                timeouts: 0,
+                rejected: 0,
+            },
+        }
+    }
+
+    /// Close a circuit and forget its history
+    pub fn reset(&self, key: &str) {
+        self.circuits.lock().remove(key);
+    }
+
+    fn admit(&self, key: &str) -> Result<Admission> {
+        let mut circuits = self.circuits.lock();
+        let circuit = circuits.entry(key.to_string()).or_insert_with(Circuit::new);
+
+        if circuit.state == CircuitState::Open {
+            let elapsed = circuit.opened_at.map_or(Duration::ZERO, |opened_at| opened_at.elapsed());
+            if elapsed < self.config.open_duration {
+                circuit.rejected += 1;
+                return Err(Error::circuit_open(key, Some(self.config.open_duration - elapsed)));
+            }
+            tracing::debug!("Circuit {} half-open", key);
+            circuit.state = CircuitState::HalfOpen;
+        }
+
+        match circuit.state {
+            CircuitState::HalfOpen if circuit.probes_in_flight + circuit.probe_successes < self.config.half_open_probes => {
+                circuit.probes_in_flight += 1;
+                Ok(Admission::Probe)
+            }
+            CircuitState::HalfOpen => {
+                circuit.rejected += 1;
+                Err(Error::circuit_open(key, None))
+            }
+            _ => Ok(Admission::Normal),
+        }
+    }
+
+    fn record(&self, key: &str, admission: Admission, outcome: Outcome) {
+        let mut circuits = self.circuits.lock();
+        let Some(circuit) = circuits.get_mut(key) else {
+            return;
+        };
+
+        match (circuit.state, admission) {
+            (CircuitState::Closed, Admission::Normal) => {
+                circuit.window.push_back(outcome);
+                if circuit.window.len() > self.config.window_size {
+                    circuit.window.pop_front();
+                }
+
+                let calls = circuit.window.len();
+                let failures = calls - circuit.count(Outcome::Success);
+                if calls >= self.config.minimum_calls
+                    && failures as f64 >= self.config.failure_rate_threshold * calls as f64
+                {
+                    tracing::warn!("Circuit {} open after {} of {} calls failed", key, failures, calls);
+                    circuit.open();
+                }
+            }
+            (CircuitState::HalfOpen, Admission::Probe) => {
+                circuit.probes_in_flight -= 1;
+                if outcome != Outcome::Success {
+                    tracing::debug!("Circuit {} probe failed, open again", key);
+                    circuit.open();
+                    return;
+                }
+                circuit.probe_successes += 1;
+                if circuit.probe_successes >= self.config.half_open_probes {
+                    tracing::info!("Circuit {} closed", key);
+                    circuit.close();
+                }
+            }
+            // Calls admitted before the circuit changed state say nothing
+            // about the current one
+            _ => {}
+        }
+    }
+
+    /// A probe that never finished frees its slot
+    fn abandon(&self, key: &str, admission: Admission) {
+        if admission != Admission::Probe {
+            return;
+        }
+        let mut circuits = self.circuits.lock();
+        if let Some(circuit) = circuits.get_mut(key) {
+            if circuit.state == CircuitState::HalfOpen {
+                circuit.probes_in_flight = circuit.probes_in_flight.saturating_sub(1);
+            }
+        }
+    }
+}
+
+/// Releases the admission of a call dropped before it completed
+struct AdmissionGuard<'a> {
+    breaker: &'a CircuitBreaker,
+    key: &'a str,
+    admission: Option<Admission>,
+}
+
+impl Drop for AdmissionGuard<'_> {
+    fn drop(&mut self) {
+        if let Some(admission) = self.admission.take() {
+            self.breaker.abandon(self.key, admission);
+        }
+    }
+}
+
+/// A client whose calls go through a [`CircuitBreaker`]
+#[derive(Debug)]
+pub struct CircuitBreakerClient {
+    client: JsonRpcClient,
+    endpoint: String,
+    breaker: Arc<CircuitBreaker>,
+}
+
+impl CircuitBreakerClient {
+    /// Guard the calls `client` makes to `endpoint` with `breaker`
+    pub fn new(client: JsonRpcClient, endpoint: impl Into<String>, breaker: Arc<CircuitBreaker>) -> Self {
+        Self {
+            client,
+            endpoint: endpoint.into(),
+            breaker,
+        }
+    }
+
+    /// Call a method unless its circuit is open
+    pub async fn call<P, R>(&self, method: &str, params: P) -> Result<R>
+    where
+        P: Serialize,
+        R: DeserializeOwned,
+    {
+        let key = self.breaker.circuit_key(&self.endpoint, method);
+        self.breaker.call(&key, self.client.call(method, params)).await
+    }
+
+    /// Send a notification unless its circuit is open
+    pub async fn notify<P: Serialize>(&self, method: &str, params: P) -> Result<()> {
+        let key = self.breaker.circuit_key(&self.endpoint, method);
+        self.breaker.call(&key, self.client.notify(method, params)).await
+    }
+
+    /// State of the circuit a call to `method` goes through
+    pub fn circuit_state(&self, method: &str) -> CircuitState {
+        self.breaker.state(&self.breaker.circuit_key(&self.endpoint, method))
+    }
+
+    /// The wrapped client
+    pub fn client(&self) -> &JsonRpcClient {
+        &self.client
+    }
+
+    /// The breaker in use
+    pub fn breaker(&self) -> &Arc<CircuitBreaker> {
+        &self.breaker
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::error::JsonRpcError;
+    use crate::core::traits::Transport;
+    use async_trait::async_trait;
+
+    fn breaker(config: CircuitBreakerConfig) -> CircuitBreaker {
+        CircuitBreaker::new(config).unwrap()
+    }
+
+    async fn fail(breaker: &CircuitBreaker, key: &str) -> Result<()> {
+        breaker.call(key, async { Err(Error::connection("refused")) }).await
+    }
+
+    async fn succeed(breaker: &CircuitBreaker, key: &str) -> Result<()> {
+        breaker.call(key, async { Ok(()) }).await
+    }
+
+    #[tokio::test]
+    async fn test_circuit_opens_on_failure_rate() {
+        let breaker = breaker(CircuitBreakerConfig::default().with_failure_rate(0.5, 4));
+
+        // Error responses and fewer than `minimum_calls` failures keep it closed
+        let rejected = breaker.call("a", async { Err::<(), _>(Error::JsonRpc(JsonRpcError::invalid_params("no"))) });
+        assert!(rejected.await.is_err());
+        succeed(&breaker, "a").await.unwrap();
+        assert!(fail(&breaker, "a").await.is_err());
+        assert_eq!(breaker.state("a"), CircuitState::Closed);
+
+        let timed_out = breaker.call("a", async { Err::<(), _>(Error::timeout("call", Duration::from_secs(1))) });
+        assert!(timed_out.await.is_err());
+        assert_eq!(breaker.state("a"), CircuitState::Open);
+
+        // Rejected without running the call, and other circuits are unaffected
+        let mut ran = false;
+        let result = breaker.call("a", async { ran = true; Ok::<_, Error>(()) }).await;
+        assert!(matches!(result, Err(Error::CircuitOpen { retry_after: Some(_), .. })));
+        assert!(!ran);
+        assert_eq!(breaker.stats("a").rejected, 1);
+        succeed(&breaker, "b").await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_half_open_probes() {
+        let config = CircuitBreakerConfig::default()
+            .with_failure_rate(1.0, 1)
+            .with_open_duration(Duration::from_millis(20))
+            .with_half_open_probes(2);
+        let breaker = breaker(config);
+
+        assert!(fail(&breaker, "a").await.is_err());
+        tokio::time::sleep(Duration::from_millis(30)).await;
+
+        // A failed probe opens the circuit again
+        assert!(matches!(fail(&breaker, "a").await, Err(Error::Connection { .. })));
+        assert_eq!(breaker.state("a"), CircuitState::Open);
+        tokio::time::sleep(Duration::from_millis(30)).await;
+
+        // Only two probes at once; both succeeding closes the circuit
+        let (release, hold) = tokio::sync::oneshot::channel::<()>();
+        let probe = breaker.call("a", async { hold.await.map_err(|_| Error::cancelled("probe")) });
+        let (slow, fast) = tokio::join!(probe, async {
+            let fast = succeed(&breaker, "a").await;
+            assert_eq!(breaker.state("a"), CircuitState::HalfOpen);
+            assert!(matches!(succeed(&breaker, "a").await, Err(Error::CircuitOpen { retry_after: None, .. })));
+            release.send(()).unwrap();
+            fast
+        });
+        slow.unwrap();
+        fast.unwrap();
+        assert_eq!(breaker.state("a"), CircuitState::Closed);
+    }
+
+    /// Transport whose peer is gone
+    struct Unreachable;
+
+    #[async_trait]
+    impl Transport for Unreachable {
+        async fn send(&mut self, _message: &str) -> Result<()> {
+            Err(Error::connection("unreachable"))
+        }
+
+        async fn receive(&mut self) -> Result<String> {
+            std::future::pending().await
+        }
+
+        async fn close(&mut self) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_per_method_circuits() {
+        let config = CircuitBreakerConfig::default()
+            .with_scope(CircuitScope::Method)
+            .with_failure_rate(1.0, 2);
+        let breaker = Arc::new(breaker(config));
+        let client = CircuitBreakerClient::new(JsonRpcClient::new(Unreachable), "backend", Arc::clone(&breaker));
+
+        for _ in 0..2 {
+            assert!(matches!(client.call::<_, ()>("slow", ()).await, Err(Error::Connection { .. })));
+        }
+        assert_eq!(client.circuit_state("slow"), CircuitState::Open);
+        assert!(matches!(client.call::<_, ()>("slow", ()).await, Err(Error::CircuitOpen { .. })));
+
+        // The other method still reaches the transport
+        assert!(matches!(client.call::<_, ()>("fast", ()).await, Err(Error::Connection { .. })));
+        assert_eq!(breaker.stats("backend#fast").failures, 1);
+    }
+}
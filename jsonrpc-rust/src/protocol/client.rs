@@ -0,0 +1,691 @@
+//! Typed JSON-RPC client with request correlation
+//!
+//! [`JsonRpcClient`] works over any [`Transport`]. It assigns request ids,
+//! keeps a map of pending calls and hands every incoming response to the
+//! call waiting for its id, so concurrent calls may be answered in any order.
+//!
+//! The transport is owned by a driver task that sends outgoing messages as
+//! they come and reads incoming ones in between. A pending `receive` is
+//! dropped whenever a message has to go out, so transports used with the
+//! client should have a cancel-safe `receive`. Transports whose `receive`
+//! fails when nothing is queued are polled every `poll_interval`.
+//!
+//! Notifications sent by the server are delivered to the receiver
+//! registered for their method with [`JsonRpcClient::notifications`].
+//! Requests sent by the server are only answered by a
+//! [`JsonRpcPeer`](super::peer::JsonRpcPeer).
+//!
+//! Error responses fail calls with [`Error::JsonRpc`];
+//! [`Error::typed`] turns those carrying an
+//! [`ErrorData`](crate::core::error::ErrorData) envelope back into the
+//! variant the server failed with, such as [`Error::RateLimit`] with its
+//! `retry_after`.
+
+use std::collections::HashMap;
+use std::future::{Future, IntoFuture};
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+use tokio::sync::{mpsc, oneshot};
+use tokio::task::JoinSet;
+use tracing::Instrument;
+use uuid::Uuid;
+
+use crate::core::error::{Error, Result};
+use crate::core::{deadline, trace};
+use crate::core::session::Session;
+use crate::core::traits::Transport;
+use crate::core::types::{JsonRpcRequest, JsonRpcResponse, MessageId, ServiceContext};
+use super::router::{MethodRouter, CANCEL_REQUEST_METHOD};
+
+/// Client configuration
+#[derive(Debug, Clone)]
+pub struct ClientConfig {
+    /// Timeout for calls that do not set their own
+    pub request_timeout: Duration,
+    /// Delay before reading again after the transport reported an error
+    pub poll_interval: Duration,
+}
+
+impl Default for ClientConfig {
+    fn default() -> Self {
+        Self {
+            request_timeout: Duration::from_secs(30),
+            poll_interval: Duration::from_millis(10),
+        }
+    }
+}
+
+/// Calls waiting for a response, keyed by serialized request id
+type PendingMap = parking_lot::Mutex<HashMap<String, oneshot::Sender<JsonRpcResponse>>>;
+
+/// Receivers of server notifications, keyed by method
+type NotificationMap = parking_lot::Mutex<HashMap<String, mpsc::UnboundedSender<JsonRpcRequest>>>;
+
+/// Requests from the client to its driver task
+enum Command {
+    Send(String, oneshot::Sender<Result<()>>),
+    Close(oneshot::Sender<Result<()>>),
+}
+
+/// JSON-RPC client over any transport
+///
+/// Must be created inside a Tokio runtime; the driver task stops when the
+/// client is closed or dropped.
+pub struct JsonRpcClient {
+    commands: mpsc::UnboundedSender<Command>,
+    pending: Arc<PendingMap>,
+    notifications: Arc<NotificationMap>,
+    next_id: AtomicU64,
+    config: ClientConfig,
+}
+
+impl JsonRpcClient {
+    /// Create a client with the default configuration
+    pub fn new(transport: impl Transport + 'static) -> Self {
+        Self::with_config(transport, ClientConfig::default())
+    }
+
+    /// Create a client with a custom configuration
+    pub fn with_config(transport: impl Transport + 'static, config: ClientConfig) -> Self {
+        Self::spawn(Box::new(transport), config, None)
+    }
+
+    /// Start the driver task; with a router, requests and notifications
+    /// for its methods arriving on the transport are answered with it
+    pub(crate) fn spawn(transport: Box<dyn Transport>, config: ClientConfig, router: Option<Arc<MethodRouter>>) -> Self {
+        let (commands, command_rx) = mpsc::unbounded_channel();
+        let pending = Arc::new(parking_lot::Mutex::new(HashMap::new()));
+        let notifications = Arc::new(parking_lot::Mutex::new(HashMap::new()));
+
+        tokio::spawn(drive(
+            transport,
+            command_rx,
+            Arc::clone(&pending),
+            Arc::clone(&notifications),
+            router,
+            config.poll_interval,
+        ));
+
+        Self {
+            commands,
+            pending,
+            notifications,
+            next_id: AtomicU64::new(1),
+            config,
+        }
+    }
+
+    /// Set the default call timeout
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.config.request_timeout = timeout;
+        self
+    }
+
+    /// Client configuration
+    pub fn config(&self) -> &ClientConfig {
+        &self.config
+    }
+
+    /// Call a method and deserialize its result
+    ///
+    /// Params serializing to `null` (e.g. `()`) are omitted from the request.
+    /// An error response is returned as [`Error::JsonRpc`].
+    pub async fn call<P, R>(&self, method: &str, params: P) -> Result<R>
+    where
+        P: Serialize,
+        R: DeserializeOwned,
+    {
+        self.call_with_timeout(method, params, self.config.request_timeout).await
+    }
+
+    /// Call a method with an explicit timeout
+    ///
+    /// Made while handling a call, the request carries on its
+    /// [trace context](crate::core::trace), and waits no longer than the
+    /// time left before its [deadline](crate::core::deadline), which it
+    /// passes on.
+    pub async fn call_with_timeout<P, R>(&self, method: &str, params: P, timeout: Duration) -> Result<R>
+    where
+        P: Serialize,
+        R: DeserializeOwned,
+    {
+        let mut request = JsonRpcRequest::with_id(method, to_params(params)?, self.next_id());
+        let (span, timeout) = prepare(&mut request, timeout);
+        let response = self.request(request, timeout).instrument(span).await?;
+        into_result(response)
+    }
+
+    /// Start a call that can be cancelled before it is answered
+    ///
+    /// Resolves once the request is sent; await the handle for the result.
+    /// Dropping the handle abandons the call, [`CallHandle::cancel`] also
+    /// asks the server to stop working on it.
+    pub async fn start<P, R>(&self, method: &str, params: P) -> Result<CallHandle<R>>
+    where
+        P: Serialize,
+        R: DeserializeOwned,
+    {
+        let mut request = JsonRpcRequest::with_id(method, to_params(params)?, self.next_id());
+        let (span, timeout) = prepare(&mut request, self.config.request_timeout);
+        let id = request.id.clone().unwrap_or_default();
+        let (guard, response) = self.register(&id)?;
+        self.send(&request).instrument(span).await?;
+
+        Ok(CallHandle {
+            id,
+            method: method.to_string(),
+            timeout,
+            response,
+            commands: self.commands.clone(),
+            _guard: guard,
+            _result: PhantomData,
+        })
+    }
+
+    /// Send a notification; no response is expected
+    pub async fn notify<P: Serialize>(&self, method: &str, params: P) -> Result<()> {
+        let mut notification = JsonRpcRequest::notification(method, to_params(params)?);
+        let (span, trace) = trace::call_span(method);
+        if let Some(trace) = trace {
+            trace.inject(&mut notification);
+        }
+        self.send(&notification).instrument(span).await
+    }
+
+    /// Send a prepared request and wait for its response
+    ///
+    /// The request must carry an id that is not already in flight.
+    pub async fn request(&self, request: JsonRpcRequest, timeout: Duration) -> Result<JsonRpcResponse> {
+        let id = request.id.as_ref()
+            .ok_or_else(|| Error::validation("Requests awaiting a response need an id"))?;
+        let (_guard, rx) = self.register(id)?;
+
+        tokio::time::timeout(timeout, async {
+            self.send(&request).await?;
+            rx.await.map_err(|_| Error::connection("Client closed before the response arrived"))
+        })
+        .await
+        .map_err(|_| Error::timeout(format!("call to '{}'", request.method), timeout))?
+    }
+
+    /// Receive the notifications the server sends for a method
+    ///
+    /// Replaces the receiver registered earlier for the same method.
+    /// Notifications for methods nobody listens to are discarded.
+    pub fn notifications(&self, method: impl Into<String>) -> mpsc::UnboundedReceiver<JsonRpcRequest> {
+        let (tx, rx) = mpsc::unbounded_channel();
+        self.notifications.lock().insert(method.into(), tx);
+        rx
+    }
+
+    /// Number of calls waiting for a response
+    pub fn pending_requests(&self) -> usize {
+        self.pending.lock().len()
+    }
+
+    /// Whether the client has been closed or its driver has stopped
+    pub fn is_closed(&self) -> bool {
+        self.commands.is_closed()
+    }
+
+    /// Close the transport and fail all pending calls
+    pub async fn close(&self) -> Result<()> {
+        let (ack, done) = oneshot::channel();
+        if self.commands.send(Command::Close(ack)).is_err() {
+            return Ok(());
+        }
+        done.await.unwrap_or(Ok(()))
+    }
+
+    fn next_id(&self) -> MessageId {
+        Value::from(self.next_id.fetch_add(1, Ordering::Relaxed))
+    }
+
+    /// Wait for the response to `id`, until the guard is dropped
+    fn register(&self, id: &MessageId) -> Result<(PendingGuard, oneshot::Receiver<JsonRpcResponse>)> {
+        let key = id_key(id);
+        let (tx, rx) = oneshot::channel();
+        let mut pending = self.pending.lock();
+        if pending.contains_key(&key) {
+            return Err(Error::validation(format!("Request id {} is already in flight", key)));
+        }
+        pending.insert(key.clone(), tx);
+        Ok((PendingGuard { pending: Arc::clone(&self.pending), key }, rx))
+    }
+
+    async fn send(&self, request: &JsonRpcRequest) -> Result<()> {
+        send(&self.commands, request).await
+    }
+}
+
+/// Have the driver send a message and wait until it is on the transport
+async fn send(commands: &mpsc::UnboundedSender<Command>, request: &JsonRpcRequest) -> Result<()> {
+    let message = serde_json::to_string(request)?;
+    let (ack, sent) = oneshot::channel();
+    commands.send(Command::Send(message, ack))
+        .map_err(|_| Error::connection("Client is closed"))?;
+    sent.await.map_err(|_| Error::connection("Client is closed"))?
+}
+
+/// Carry on the trace and deadline of the call being handled, if any;
+/// returns the span of the call and how long it may wait
+fn prepare(request: &mut JsonRpcRequest, timeout: Duration) -> (tracing::Span, Duration) {
+    let (span, trace) = trace::call_span(&request.method);
+    if let Some(trace) = trace {
+        trace.inject(request);
+    }
+    let timeout = match deadline::current() {
+        Some(deadline) => {
+            deadline::inject(deadline, request);
+            timeout.min(deadline.saturating_duration_since(Instant::now()))
+        }
+        None => timeout,
+    };
+    (span, timeout)
+}
+
+/// The result of a response, or its error as [`Error::JsonRpc`]
+fn into_result<R: DeserializeOwned>(response: JsonRpcResponse) -> Result<R> {
+    if let Some(error) = response.error {
+        return Err(Error::JsonRpc(error));
+    }
+    Ok(serde_json::from_value(response.result.unwrap_or(Value::Null))?)
+}
+
+/// A call in flight, started with [`JsonRpcClient::start`]
+///
+/// Await it for the result of the call.
+pub struct CallHandle<R> {
+    id: MessageId,
+    method: String,
+    timeout: Duration,
+    response: oneshot::Receiver<JsonRpcResponse>,
+    commands: mpsc::UnboundedSender<Command>,
+    _guard: PendingGuard,
+    _result: PhantomData<fn() -> R>,
+}
+
+impl<R> CallHandle<R> {
+    /// Id of the request
+    pub fn id(&self) -> &MessageId {
+        &self.id
+    }
+
+    /// Abandon the call and ask the server to stop working on it with a
+    /// `$/cancelRequest` notification
+    pub async fn cancel(self) -> Result<()> {
+        let params = serde_json::json!({ "id": self.id });
+        let notification = JsonRpcRequest::notification(CANCEL_REQUEST_METHOD, Some(params));
+        send(&self.commands, &notification).await
+    }
+}
+
+impl<R: DeserializeOwned + Send + 'static> IntoFuture for CallHandle<R> {
+    type Output = Result<R>;
+    type IntoFuture = Pin<Box<dyn Future<Output = Result<R>> + Send>>;
+
+    fn into_future(self) -> Self::IntoFuture {
+        // The guard keeps the call pending until the future is done
+        let Self { method, timeout, response, _guard: guard, .. } = self;
+        Box::pin(async move {
+            let _guard = guard;
+            let response = tokio::time::timeout(timeout, response)
+                .await
+                .map_err(|_| Error::timeout(format!("call to '{}'", method), timeout))?
+                .map_err(|_| Error::connection("Client closed before the response arrived"))?;
+            into_result(response)
+        })
+    }
+}
+
+impl<R> std::fmt::Debug for CallHandle<R> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CallHandle")
+            .field("id", &self.id)
+            .field("method", &self.method)
+            .finish()
+    }
+}
+
+impl std::fmt::Debug for JsonRpcClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("JsonRpcClient")
+            .field("pending", &self.pending_requests())
+            .field("config", &self.config)
+            .finish()
+    }
+}
+
+/// Removes a call from the pending map however it ends
+struct PendingGuard {
+    pending: Arc<PendingMap>,
+    key: String,
+}
+
+impl Drop for PendingGuard {
+    fn drop(&mut self) {
+        self.pending.lock().remove(&self.key);
+    }
+}
+
+/// What woke the driver task up
+enum DriverEvent {
+    Command(Option<Command>),
+    Answered(std::result::Result<Option<String>, tokio::task::JoinError>),
+    Received(Result<String>),
+}
+
+/// Own the transport: send what the client asks for, route what arrives
+/// and send the answers of the router, if any
+async fn drive(
+    mut transport: Box<dyn Transport>,
+    mut commands: mpsc::UnboundedReceiver<Command>,
+    pending: Arc<PendingMap>,
+    notifications: Arc<NotificationMap>,
+    router: Option<Arc<MethodRouter>>,
+    poll_interval: Duration,
+) {
+    let mut backoff = None;
+    let mut calls = JoinSet::new();
+    // Names the connection for the calls the router answers, so that
+    // `$/cancelRequest` only reaches calls made on it
+    let peer = Value::String(Uuid::new_v4().to_string());
+    let session = Session::new(peer.clone());
+
+    loop {
+        let delay = backoff;
+        let event = tokio::select! {
+            biased;
+            command = commands.recv() => DriverEvent::Command(command),
+            Some(answered) = calls.join_next(), if !calls.is_empty() => DriverEvent::Answered(answered),
+            received = async {
+                if let Some(delay) = delay {
+                    tokio::time::sleep(delay).await;
+                }
+                transport.receive().await
+            } => DriverEvent::Received(received),
+        };
+
+        match event {
+            DriverEvent::Command(Some(Command::Send(message, ack))) => {
+                let span = tracing::debug_span!("jsonrpc.send", bytes = message.len());
+                let _ = ack.send(transport.send(&message).instrument(span).await);
+            }
+            DriverEvent::Command(Some(Command::Close(ack))) => {
+                let _ = ack.send(transport.close().await);
+                break;
+            }
+            DriverEvent::Command(None) => {
+                let _ = transport.close().await;
+                break;
+            }
+            DriverEvent::Answered(Ok(Some(answer))) => {
+                let span = tracing::debug_span!("jsonrpc.send", bytes = answer.len());
+                if let Err(e) = transport.send(&answer).instrument(span).await {
+                    tracing::debug!("Failed to send response: {}", e);
+                }
+            }
+            DriverEvent::Answered(Ok(None)) => {}
+            DriverEvent::Answered(Err(e)) => tracing::error!("Request task failed: {}", e),
+            DriverEvent::Received(Ok(message)) => {
+                backoff = None;
+                let span = tracing::debug_span!("jsonrpc.receive", bytes = message.len());
+                let requests = span.in_scope(|| route(&pending, &notifications, router.as_deref(), &message));
+                if let (Some(requests), Some(router)) = (requests, &router) {
+                    let router = Arc::clone(router);
+                    let context = ServiceContext::new(Uuid::new_v4().to_string())
+                        .with_metadata("peer", peer.clone())
+                        .with_session(session.clone());
+                    calls.spawn(async move { router.dispatch_message(&requests, &context).await }.instrument(span));
+                }
+            }
+            DriverEvent::Received(Err(e)) => {
+                tracing::trace!("Transport receive failed, retrying: {}", e);
+                backoff = Some(poll_interval);
+            }
+        }
+    }
+
+    // Dropping the senders wakes the pending calls with an error and ends
+    // the notification receivers
+    pending.lock().clear();
+    notifications.lock().clear();
+}
+
+/// Hand incoming responses to the calls waiting for them and notifications
+/// to their receivers
+///
+/// With a router, returns the message it has to answer, made of the
+/// requests and of the notifications for its methods.
+fn route(pending: &PendingMap, notifications: &NotificationMap, router: Option<&MethodRouter>, message: &str) -> Option<String> {
+    let value: Value = match serde_json::from_str(message) {
+        Ok(value) => value,
+        Err(e) => {
+            tracing::warn!("Discarding malformed JSON-RPC message: {}", e);
+            return None;
+        }
+    };
+
+    let (items, batch) = match value {
+        Value::Array(items) => (items, true),
+        single => (vec![single], false),
+    };
+
+    let mut requests = Vec::new();
+    for item in items {
+        if let Some(method) = item.get("method") {
+            let answered = router.is_some_and(|router| {
+                item.get("id").is_some() || method.as_str().is_some_and(|method| {
+                    method == CANCEL_REQUEST_METHOD || router.has_method(method)
+                })
+            });
+            if answered {
+                requests.push(item);
+            } else {
+                route_notification(notifications, item);
+            }
+            continue;
+        }
+        match serde_json::from_value::<JsonRpcResponse>(item) {
+            Ok(response) => {
+                let waiter = pending.lock().remove(&id_key(&response.id));
+                match waiter {
+                    Some(tx) => {
+                        let _ = tx.send(response);
+                    }
+                    None => tracing::debug!("Discarding response for unknown id {}", response.id),
+                }
+            }
+            Err(e) => tracing::warn!("Discarding malformed JSON-RPC response: {}", e),
+        }
+    }
+
+    match requests.len() {
+        0 => None,
+        1 if !batch => requests.pop().map(|request| request.to_string()),
+        _ => Some(Value::Array(requests).to_string()),
+    }
+}
+
+fn route_notification(notifications: &NotificationMap, item: Value) {
+    let request = match serde_json::from_value::<JsonRpcRequest>(item) {
+        Ok(request) if request.is_notification() => request,
+        Ok(request) => {
+            tracing::debug!("Ignoring server-initiated request '{}'; answering needs a JsonRpcPeer", request.method);
+            return;
+        }
+        Err(e) => {
+            tracing::warn!("Discarding malformed JSON-RPC notification: {}", e);
+            return;
+        }
+    };
+
+    let mut notifications = notifications.lock();
+    if let Some(tx) = notifications.get(&request.method) {
+        if let Err(mpsc::error::SendError(request)) = tx.send(request) {
+            notifications.remove(&request.method);
+        }
+    }
+}
+
+fn id_key(id: &MessageId) -> String {
+    id.to_string()
+}
+
+fn to_params<P: Serialize>(params: P) -> Result<Option<Value>> {
+    match serde_json::to_value(params)? {
+        Value::Null => Ok(None),
+        params => Ok(Some(params)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use serde_json::json;
+    use tokio::sync::mpsc;
+
+    /// Client end of an in-memory connection
+    struct ChannelTransport {
+        outgoing: mpsc::UnboundedSender<String>,
+        incoming: mpsc::UnboundedReceiver<String>,
+    }
+
+    #[async_trait]
+    impl Transport for ChannelTransport {
+        async fn send(&mut self, message: &str) -> Result<()> {
+            self.outgoing.send(message.to_string()).map_err(|_| Error::transport("closed"))
+        }
+
+        async fn receive(&mut self) -> Result<String> {
+            self.incoming.recv().await.ok_or_else(|| Error::transport("closed"))
+        }
+
+        async fn close(&mut self) -> Result<()> {
+            self.incoming.close();
+            Ok(())
+        }
+    }
+
+    /// Client plus the server side of its connection
+    fn connect() -> (JsonRpcClient, mpsc::UnboundedReceiver<String>, mpsc::UnboundedSender<String>) {
+        let (client_tx, server_rx) = mpsc::unbounded_channel();
+        let (server_tx, client_rx) = mpsc::unbounded_channel();
+        let client = JsonRpcClient::new(ChannelTransport { outgoing: client_tx, incoming: client_rx });
+        (client, server_rx, server_tx)
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_calls_answered_out_of_order() {
+        let (client, mut requests, responses) = connect();
+
+        // Answer both requests, the second one first
+        tokio::spawn(async move {
+            let mut received = Vec::new();
+            while received.len() < 2 {
+                let request: JsonRpcRequest = serde_json::from_str(&requests.recv().await.unwrap()).unwrap();
+                received.push(request);
+            }
+            for request in received.into_iter().rev() {
+                let sum: i64 = serde_json::from_value::<Vec<i64>>(request.params.unwrap()).unwrap().iter().sum();
+                let response = JsonRpcResponse::success(request.id.unwrap(), json!(sum));
+                responses.send(serde_json::to_string(&response).unwrap()).unwrap();
+            }
+        });
+
+        let (a, b) = tokio::join!(
+            client.call::<_, i64>("add", vec![1, 2]),
+            client.call::<_, i64>("add", vec![10, 20]),
+        );
+        assert_eq!(a.unwrap(), 3);
+        assert_eq!(b.unwrap(), 30);
+        assert_eq!(client.pending_requests(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_error_responses_notifications_and_timeouts() {
+        let (client, mut requests, responses) = connect();
+
+        let server = tokio::spawn(async move {
+            let request: JsonRpcRequest = serde_json::from_str(&requests.recv().await.unwrap()).unwrap();
+            let response = JsonRpcResponse::error(
+                request.id.unwrap(),
+                crate::core::error::JsonRpcError::method_not_found(&request.method),
+            );
+            responses.send(serde_json::to_string(&response).unwrap()).unwrap();
+
+            let notification: JsonRpcRequest = serde_json::from_str(&requests.recv().await.unwrap()).unwrap();
+            assert!(notification.is_notification());
+            assert_eq!(notification.params, None);
+
+            // Swallow the last request without answering
+            requests.recv().await.unwrap();
+            responses
+        });
+
+        match client.call::<_, Value>("missing", json!({"x": 1})).await {
+            Err(Error::JsonRpc(error)) => assert_eq!(error.code, -32601),
+            other => panic!("unexpected result: {:?}", other),
+        }
+
+        client.notify("log", ()).await.unwrap();
+
+        let timed_out = client
+            .call_with_timeout::<_, Value>("slow", (), Duration::from_millis(50))
+            .await
+            .unwrap_err();
+        assert!(matches!(timed_out, Error::Timeout { .. }));
+        assert_eq!(client.pending_requests(), 0);
+
+        let _responses = server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_calls_made_by_handlers_carry_trace_and_deadline() {
+        let (client, mut requests, _responses) = connect();
+        let trace = trace::TraceContext::root();
+        let deadline = Instant::now() + Duration::from_millis(80);
+
+        let call = client.call_with_timeout::<_, Value>("slow", (), Duration::from_secs(30));
+        let handler = trace.clone().scope(deadline::run("handler", Some(deadline), call));
+        let started = Instant::now();
+        assert!(matches!(handler.await, Err(Error::Timeout { .. })));
+        assert!(started.elapsed() < Duration::from_secs(5));
+
+        let request: JsonRpcRequest = serde_json::from_str(&requests.recv().await.unwrap()).unwrap();
+        assert!(request.meta_value(deadline::TIMEOUT_META).unwrap().as_u64().unwrap() <= 80);
+        let parent = trace::TraceContext::parse(request.meta_value(trace::TRACEPARENT).unwrap().as_str().unwrap()).unwrap();
+        assert_eq!(parent.trace_id, trace.trace_id);
+    }
+
+    #[tokio::test]
+    async fn test_call_handle_cancel() {
+        let (client, mut requests, responses) = connect();
+
+        let handle = client.start::<_, i64>("slow", ()).await.unwrap();
+        let id = handle.id().clone();
+        assert_eq!(client.pending_requests(), 1);
+        handle.cancel().await.unwrap();
+        assert_eq!(client.pending_requests(), 0);
+
+        let request: JsonRpcRequest = serde_json::from_str(&requests.recv().await.unwrap()).unwrap();
+        let cancel: JsonRpcRequest = serde_json::from_str(&requests.recv().await.unwrap()).unwrap();
+        assert_eq!(cancel.method, CANCEL_REQUEST_METHOD);
+        assert_eq!(request.id, Some(id.clone()));
+        assert_eq!(cancel.params, Some(json!({"id": id})));
+
+        let handle = client.start::<_, i64>("add", ()).await.unwrap();
+        let request: JsonRpcRequest = serde_json::from_str(&requests.recv().await.unwrap()).unwrap();
+        let response = JsonRpcResponse::success(request.id.unwrap(), json!(3));
+        responses.send(serde_json::to_string(&response).unwrap()).unwrap();
+        assert_eq!(handle.await.unwrap(), 3);
+    }
+}
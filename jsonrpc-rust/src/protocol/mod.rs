@@ -0,0 +1,74 @@
+//! Protocol layer implementation (Phase 3)
+//!
+//! This module provides the JSON-RPC 2.0 protocol implementation on top of
+//! the core traits: message routing on the server side and a typed client
+//! correlating requests with their responses, optionally pooled over several
+//! connections, balanced across endpoints, guarded by a circuit breaker and
+//! answering idempotent calls from a cache, and peers that do both over the
+//! same connection.
+//!
+//! # Example
+//!
+//! ```rust
+//! use jsonrpc_rust::prelude::*;
+//! use jsonrpc_rust::protocol::MethodRouter;
+//! use async_trait::async_trait;
+//! use serde_json::json;
+//! use std::sync::Arc;
+//!
+//! struct MathHandler;
+//!
+//! #[async_trait]
+//! impl MethodHandler for MathHandler {
+//!     async fn handle_method(
+//!         &self,
+//!         request: &JsonRpcRequest,
+//!         _context: &ServiceContext,
+//!     ) -> jsonrpc_rust::Result<JsonRpcResponse> {
+//!         let id = request.id.clone().unwrap_or(json!(null));
+//!         Ok(JsonRpcResponse::success(id, json!(request.method)))
+//!     }
+//!
+//!     fn supported_methods(&self) -> Vec<String> {
+//!         vec!["math.*".to_string()]
+//!     }
+//! }
+//!
+//! # async fn example() -> jsonrpc_rust::Result<()> {
+//! let mut router = MethodRouter::new();
+//! router.register_handler(Arc::new(MathHandler))?;
+//!
+//! let request = JsonRpcRequest::with_id("math.add", None, json!(1));
+//! let response = router.dispatch(&request, &ServiceContext::new("req-1")).await;
+//! assert_eq!(response.unwrap().result, Some(json!("math.add")));
+//! # Ok(())
+//! # }
+//! ```
+
+pub mod router;
+pub mod client;
+pub mod peer;
+pub mod pool;
+pub mod circuit_breaker;
+pub mod balanced;
+pub mod cache;
+
+pub use router::*;
+pub use client::*;
+pub use peer::*;
+pub use pool::*;
+pub use circuit_breaker::*;
+pub use balanced::*;
+pub use cache::*;
+
+pub mod prelude {
+    //! Common imports for protocol layer usage
+
+    pub use super::router::{Compatibility, MethodRouter};
+    pub use super::client::{JsonRpcClient, ClientConfig, CallHandle};
+    pub use super::peer::JsonRpcPeer;
+    pub use super::pool::{ConnectionPool, PoolConfig, PooledClient, LoadBalancing};
+    pub use super::circuit_breaker::{CircuitBreaker, CircuitBreakerConfig, CircuitBreakerClient, CircuitScope, CircuitState};
+    pub use super::balanced::{BalancedClient, BalanceStrategy, HedgingConfig};
+    pub use super::cache::{CacheConfig, CachingClient, ResponseCache};
+}
@@ -0,0 +1,130 @@
+//! Bidirectional JSON-RPC peers
+//!
+//! A [`JsonRpcPeer`] is both ends of a connection at once: it calls the
+//! other side like a [`JsonRpcClient`] and answers the requests the other
+//! side sends with its own [`MethodRouter`]. Each side numbers its own
+//! requests, so ids only have to be unique per direction: messages with a
+//! `method` go to the router, the others are responses to the peer's calls.
+//!
+//! Notifications for methods the router does not know are delivered to the
+//! receivers registered with [`JsonRpcClient::notifications`]. Incoming
+//! calls are answered concurrently and may be cancelled with
+//! `$/cancelRequest`.
+//!
+//! ```rust
+//! use jsonrpc_rust::prelude::*;
+//! use jsonrpc_rust::protocol::{JsonRpcPeer, MethodRouter};
+//!
+//! # async fn example(transport: impl Transport + 'static) -> jsonrpc_rust::Result<()> {
+//! let peer = JsonRpcPeer::new(transport, MethodRouter::new());
+//! let _output: String = peer.call("tool.run", serde_json::json!({"name": "ls"})).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::ops::Deref;
+use std::sync::Arc;
+
+use crate::core::traits::Transport;
+use super::client::{ClientConfig, JsonRpcClient};
+use super::router::MethodRouter;
+
+/// Client and server sharing one connection
+pub struct JsonRpcPeer {
+    client: JsonRpcClient,
+    router: Arc<MethodRouter>,
+}
+
+impl JsonRpcPeer {
+    /// Create a peer answering incoming requests with `router`
+    pub fn new(transport: impl Transport + 'static, router: MethodRouter) -> Self {
+        Self::with_config(transport, router, ClientConfig::default())
+    }
+
+    /// Create a peer with a custom client configuration
+    pub fn with_config(transport: impl Transport + 'static, router: MethodRouter, config: ClientConfig) -> Self {
+        let router = Arc::new(router);
+        let client = JsonRpcClient::spawn(Box::new(transport), config, Some(Arc::clone(&router)));
+        Self { client, router }
+    }
+
+    /// The router answering incoming requests
+    pub fn router(&self) -> &MethodRouter {
+        &self.router
+    }
+
+    /// The client end, for APIs taking a [`JsonRpcClient`]
+    pub fn client(&self) -> &JsonRpcClient {
+        &self.client
+    }
+}
+
+impl Deref for JsonRpcPeer {
+    type Target = JsonRpcClient;
+
+    fn deref(&self) -> &JsonRpcClient {
+        &self.client
+    }
+}
+
+impl std::fmt::Debug for JsonRpcPeer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("JsonRpcPeer")
+            .field("client", &self.client)
+            .field("router", &self.router)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::error::Result;
+    use crate::core::traits::MethodHandler;
+    use crate::core::types::{JsonRpcRequest, JsonRpcResponse, ServiceContext};
+    use crate::transport::InProcTransport;
+    use async_trait::async_trait;
+    use serde_json::{json, Value};
+
+    /// Answers with its name and the method called
+    struct NameHandler(&'static str);
+
+    #[async_trait]
+    impl MethodHandler for NameHandler {
+        async fn handle_method(&self, request: &JsonRpcRequest, _context: &ServiceContext) -> Result<JsonRpcResponse> {
+            let id = request.id.clone().unwrap_or(Value::Null);
+            Ok(JsonRpcResponse::success(id, json!(format!("{}:{}", self.0, request.method))))
+        }
+
+        fn supported_methods(&self) -> Vec<String> {
+            vec!["whoami".to_string()]
+        }
+    }
+
+    fn router(name: &'static str) -> MethodRouter {
+        let mut router = MethodRouter::new();
+        router.register_handler(Arc::new(NameHandler(name))).unwrap();
+        router
+    }
+
+    #[tokio::test]
+    async fn test_both_sides_call_each_other() {
+        let (a, b) = InProcTransport::pair();
+        let agent = JsonRpcPeer::new(a, router("agent"));
+        let tool = JsonRpcPeer::new(b, router("tool"));
+        let mut events = agent.notifications("tool.event");
+
+        // Both sides start at the same ids; the directions must not mix up
+        let (to_tool, to_agent): (Result<String>, Result<String>) =
+            tokio::join!(agent.call("whoami", json!([])), tool.call("whoami", json!([])));
+        assert_eq!(to_tool.unwrap(), "tool:whoami");
+        assert_eq!(to_agent.unwrap(), "agent:whoami");
+
+        tool.notify("tool.event", json!({"done": true})).await.unwrap();
+        let event = events.recv().await.unwrap();
+        assert_eq!(event.params, Some(json!({"done": true})));
+
+        let missing: Result<Value> = tool.call("missing", json!([])).await;
+        assert!(missing.is_err());
+    }
+}
@@ -0,0 +1,645 @@
+//! Connection pooling for clients
+//!
+//! A [`ConnectionPool`] keeps several [`JsonRpcClient`]s open to one
+//! endpoint, so concurrent calls are spread over several connections
+//! instead of queueing behind each other on a single stream.
+//!
+//! Calls check a client out of the pool, picking a connection round-robin
+//! or by fewest calls in flight. Each connection accepts a bounded number
+//! of concurrent calls; when all of them are busy, checkout waits for one
+//! to free up, up to the checkout timeout. Broken connections are replaced
+//! in the background, either after a call fails on them or when a periodic
+//! health check finds them dead.
+
+use std::ops::Deref;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Weak};
+use std::time::Duration;
+use futures::future::BoxFuture;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::sync::Notify;
+use tokio::task::JoinHandle;
+
+use crate::core::error::{Error, Result};
+use crate::core::traits::Transport;
+use crate::transport::TransportConnector;
+use super::client::{ClientConfig, JsonRpcClient};
+
+/// How checkouts are spread over the pooled connections
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LoadBalancing {
+    /// Take connections in turn
+    RoundRobin,
+    /// Take the connection with the fewest calls in flight
+    LeastInFlight,
+}
+
+/// Connection pool configuration
+#[derive(Debug, Clone)]
+pub struct PoolConfig {
+    /// Number of connections kept open
+    pub size: usize,
+    /// Concurrent calls allowed on one connection
+    pub max_in_flight: usize,
+    /// How long a checkout waits for a free connection
+    pub checkout_timeout: Duration,
+    /// Connection selection strategy
+    pub load_balancing: LoadBalancing,
+    /// Interval between health checks; `None` disables them, in which case
+    /// connections that failed to open are not retried
+    pub health_check_interval: Option<Duration>,
+    /// Method called to probe a connection; any response, even an error
+    /// response, counts as healthy. Without it only closed clients are
+    /// detected.
+    pub health_check_method: Option<String>,
+    /// Timeout for a health check call
+    pub health_check_timeout: Duration,
+    /// Configuration of each pooled client
+    pub client_config: ClientConfig,
+}
+
+impl Default for PoolConfig {
+    fn default() -> Self {
+        Self {
+            size: 4,
+            max_in_flight: 100,
+            checkout_timeout: Duration::from_secs(5),
+            load_balancing: LoadBalancing::LeastInFlight,
+            health_check_interval: Some(Duration::from_secs(30)),
+            health_check_method: None,
+            health_check_timeout: Duration::from_secs(5),
+            client_config: ClientConfig::default(),
+        }
+    }
+}
+
+impl PoolConfig {
+    /// Set the number of connections
+    pub fn with_size(mut self, size: usize) -> Self {
+        self.size = size;
+        self
+    }
+
+    /// Set the concurrent calls allowed per connection
+    pub fn with_max_in_flight(mut self, max_in_flight: usize) -> Self {
+        self.max_in_flight = max_in_flight;
+        self
+    }
+
+    /// Set the checkout timeout
+    pub fn with_checkout_timeout(mut self, timeout: Duration) -> Self {
+        self.checkout_timeout = timeout;
+        self
+    }
+
+    /// Set the load balancing strategy
+    pub fn with_load_balancing(mut self, load_balancing: LoadBalancing) -> Self {
+        self.load_balancing = load_balancing;
+        self
+    }
+
+    /// Probe connections by calling `method` every `interval`
+    pub fn with_health_check(mut self, method: impl Into<String>, interval: Duration) -> Self {
+        self.health_check_method = Some(method.into());
+        self.health_check_interval = Some(interval);
+        self
+    }
+
+    /// Disable periodic health checks
+    pub fn without_health_checks(mut self) -> Self {
+        self.health_check_interval = None;
+        self
+    }
+
+    /// Set the configuration of each pooled client
+    pub fn with_client_config(mut self, client_config: ClientConfig) -> Self {
+        self.client_config = client_config;
+        self
+    }
+
+    /// Check the configuration is usable
+    pub fn validate(&self) -> Result<()> {
+        if self.size == 0 {
+            return Err(Error::configuration("Pool size cannot be zero"));
+        }
+
+        if self.max_in_flight == 0 {
+            return Err(Error::configuration("Max in-flight calls per connection cannot be zero"));
+        }
+
+        if self.health_check_interval.is_some_and(|interval| interval.is_zero()) {
+            return Err(Error::configuration("Health check interval cannot be zero"));
+        }
+
+        Ok(())
+    }
+}
+
+/// Snapshot of a pool's connections
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PoolStats {
+    /// Configured number of connections
+    pub size: usize,
+    /// Connections currently usable
+    pub healthy: usize,
+    /// Calls in flight across all connections
+    pub in_flight: usize,
+}
+
+/// One pooled connection
+struct Slot {
+    client: parking_lot::RwLock<Option<Arc<JsonRpcClient>>>,
+    in_flight: AtomicUsize,
+    healthy: AtomicBool,
+    replacing: AtomicBool,
+}
+
+struct PoolInner {
+    endpoint: String,
+    config: PoolConfig,
+    connector: TransportConnector,
+    slots: Vec<Slot>,
+    /// Rotates the starting slot between checkouts
+    next: AtomicUsize,
+    /// Signalled whenever a connection may have become available
+    available: Notify,
+}
+
+/// A fixed-size pool of clients connected to one endpoint
+///
+/// Must be created inside a Tokio runtime. Dropping the pool stops its
+/// health checks; connections close once no checked-out client uses them.
+pub struct ConnectionPool {
+    inner: Arc<PoolInner>,
+    health_task: Option<JoinHandle<()>>,
+}
+
+impl ConnectionPool {
+    /// Open `config.size` connections to `endpoint` using `connector`
+    ///
+    /// Fails only if no connection could be opened; the others are retried
+    /// by the health checks.
+    pub async fn connect<F>(endpoint: impl Into<String>, connector: F, config: PoolConfig) -> Result<Self>
+    where
+        F: Fn() -> BoxFuture<'static, Result<Box<dyn Transport>>> + Send + Sync + 'static,
+    {
+        config.validate()?;
+
+        let inner = Arc::new(PoolInner {
+            endpoint: endpoint.into(),
+            slots: (0..config.size).map(|_| Slot {
+                client: parking_lot::RwLock::new(None),
+                in_flight: AtomicUsize::new(0),
+                healthy: AtomicBool::new(false),
+                replacing: AtomicBool::new(false),
+            }).collect(),
+            config,
+            connector: Arc::new(connector),
+            next: AtomicUsize::new(0),
+            available: Notify::new(),
+        });
+
+        let opened = futures::future::join_all(inner.slots.iter().map(|_| inner.open())).await;
+        let mut first_error = None;
+        for (slot, result) in inner.slots.iter().zip(opened) {
+            match result {
+                Ok(client) => {
+                    *slot.client.write() = Some(Arc::new(client));
+                    slot.healthy.store(true, Ordering::SeqCst);
+                }
+                Err(e) => {
+                    tracing::warn!("Failed to open pooled connection to {}: {}", inner.endpoint, e);
+                    first_error.get_or_insert(e);
+                }
+            }
+        }
+        if let Some(e) = first_error.filter(|_| inner.healthy_count() == 0) {
+            return Err(e);
+        }
+
+        let health_task = inner.config.health_check_interval
+            .map(|interval| tokio::spawn(health_loop(Arc::downgrade(&inner), interval)));
+
+        Ok(Self { inner, health_task })
+    }
+
+    /// Endpoint the pool connects to
+    pub fn endpoint(&self) -> &str {
+        &self.inner.endpoint
+    }
+
+    /// Pool configuration
+    pub fn config(&self) -> &PoolConfig {
+        &self.inner.config
+    }
+
+    /// Take a client for one or more calls
+    ///
+    /// Waits for a connection with spare capacity for at most the checkout
+    /// timeout. The connection is returned when the [`PooledClient`] drops.
+    pub async fn checkout(&self) -> Result<PooledClient> {
+        let timeout = self.inner.config.checkout_timeout;
+        tokio::time::timeout(timeout, async {
+            loop {
+                // Register before looking, so a release in between is not missed
+                let available = self.inner.available.notified();
+                if let Some(client) = self.inner.try_checkout() {
+                    return client;
+                }
+                available.await;
+            }
+        })
+        .await
+        .map_err(|_| Error::timeout(format!("connection checkout from {}", self.inner.endpoint), timeout))
+    }
+
+    /// Call a method on a pooled connection
+    ///
+    /// A connection on which the call fails with a transport or connection
+    /// error is replaced.
+    pub async fn call<P, R>(&self, method: &str, params: P) -> Result<R>
+    where
+        P: Serialize,
+        R: DeserializeOwned,
+    {
+        let client = self.checkout().await?;
+        let result = client.call(method, params).await;
+        if let Err(ref e) = result {
+            if is_connection_failure(e) {
+                client.discard();
+            }
+        }
+        result
+    }
+
+    /// Send a notification on a pooled connection
+    pub async fn notify<P: Serialize>(&self, method: &str, params: P) -> Result<()> {
+        let client = self.checkout().await?;
+        let result = client.notify(method, params).await;
+        if let Err(ref e) = result {
+            if is_connection_failure(e) {
+                client.discard();
+            }
+        }
+        result
+    }
+
+    /// Current connection health and load
+    pub fn stats(&self) -> PoolStats {
+        PoolStats {
+            size: self.inner.slots.len(),
+            healthy: self.inner.healthy_count(),
+            in_flight: self.inner.slots.iter()
+                .map(|slot| slot.in_flight.load(Ordering::SeqCst))
+                .sum(),
+        }
+    }
+
+    /// Stop health checks and close every connection
+    ///
+    /// Calls still in flight fail.
+    pub async fn close(&mut self) -> Result<()> {
+        if let Some(task) = self.health_task.take() {
+            task.abort();
+        }
+        for slot in &self.inner.slots {
+            slot.healthy.store(false, Ordering::SeqCst);
+            let client = slot.client.write().take();
+            if let Some(client) = client {
+                client.close().await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Drop for ConnectionPool {
+    fn drop(&mut self) {
+        if let Some(task) = self.health_task.take() {
+            task.abort();
+        }
+    }
+}
+
+impl std::fmt::Debug for ConnectionPool {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ConnectionPool")
+            .field("endpoint", &self.inner.endpoint)
+            .field("stats", &self.stats())
+            .finish()
+    }
+}
+
+impl PoolInner {
+    async fn open(&self) -> Result<JsonRpcClient> {
+        let transport = (self.connector)().await?;
+        Ok(JsonRpcClient::with_config(transport, self.config.client_config.clone()))
+    }
+
+    fn healthy_count(&self) -> usize {
+        self.slots.iter().filter(|slot| slot.healthy.load(Ordering::SeqCst)).count()
+    }
+
+    /// Reserve a call on a healthy connection with spare capacity
+    fn try_checkout(self: &Arc<Self>) -> Option<PooledClient> {
+        let count = self.slots.len();
+        let start = self.next.fetch_add(1, Ordering::Relaxed) % count;
+        let mut order: Vec<usize> = (0..count).map(|offset| (start + offset) % count).collect();
+        if self.config.load_balancing == LoadBalancing::LeastInFlight {
+            // Stable, so ties still rotate
+            order.sort_by_key(|&index| self.slots[index].in_flight.load(Ordering::SeqCst));
+        }
+
+        for index in order {
+            let slot = &self.slots[index];
+            if !slot.healthy.load(Ordering::SeqCst) {
+                continue;
+            }
+            let Some(client) = slot.client.read().clone() else {
+                continue;
+            };
+            if client.is_closed() {
+                self.replace(index);
+                continue;
+            }
+
+            let max = self.config.max_in_flight;
+            let reserved = slot.in_flight
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| (n < max).then_some(n + 1))
+                .is_ok();
+            if reserved {
+                return Some(PooledClient { pool: Arc::clone(self), index, client });
+            }
+        }
+        None
+    }
+
+    /// Take a connection out of rotation and open a new one in its place
+    fn replace(self: &Arc<Self>, index: usize) {
+        let slot = &self.slots[index];
+        slot.healthy.store(false, Ordering::SeqCst);
+        if slot.replacing.swap(true, Ordering::SeqCst) {
+            return;
+        }
+
+        let pool = Arc::clone(self);
+        tokio::spawn(async move {
+            let slot = &pool.slots[index];
+            match pool.open().await {
+                Ok(client) => {
+                    // The old client closes once its last checkout is returned
+                    *slot.client.write() = Some(Arc::new(client));
+                    slot.healthy.store(true, Ordering::SeqCst);
+                    tracing::debug!("Replaced pooled connection {} to {}", index, pool.endpoint);
+                }
+                Err(e) => {
+                    tracing::warn!("Failed to reopen pooled connection to {}: {}", pool.endpoint, e);
+                }
+            }
+            slot.replacing.store(false, Ordering::SeqCst);
+            pool.available.notify_waiters();
+        });
+    }
+
+    /// Probe every connection and replace those that are down
+    async fn check_health(self: &Arc<Self>) {
+        for (index, slot) in self.slots.iter().enumerate() {
+            if slot.replacing.load(Ordering::SeqCst) {
+                continue;
+            }
+
+            let client = slot.client.read().clone();
+            let alive = match client {
+                Some(client) if !client.is_closed() => match self.config.health_check_method {
+                    Some(ref method) => {
+                        let probe = client
+                            .call_with_timeout::<_, Value>(method, (), self.config.health_check_timeout)
+                            .await;
+                        match probe {
+                            Ok(_) | Err(Error::JsonRpc(_)) => true,
+                            Err(e) => {
+                                tracing::debug!("Health check of connection {} to {} failed: {}", index, self.endpoint, e);
+                                false
+                            }
+                        }
+                    }
+                    None => true,
+                },
+                _ => false,
+            };
+
+            if !alive {
+                self.replace(index);
+            }
+        }
+    }
+}
+
+async fn health_loop(pool: Weak<PoolInner>, interval: Duration) {
+    let mut ticker = tokio::time::interval(interval);
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    // The first tick completes immediately; the pool was just opened
+    ticker.tick().await;
+
+    loop {
+        ticker.tick().await;
+        let Some(pool) = pool.upgrade() else {
+            break;
+        };
+        pool.check_health().await;
+    }
+}
+
+fn is_connection_failure(error: &Error) -> bool {
+    matches!(error, Error::Connection { .. } | Error::Transport { .. })
+}
+
+/// A client checked out of a [`ConnectionPool`]
+///
+/// Dereferences to the [`JsonRpcClient`] of the chosen connection.
+/// Dropping it returns the connection to the pool.
+pub struct PooledClient {
+    pool: Arc<PoolInner>,
+    index: usize,
+    client: Arc<JsonRpcClient>,
+}
+
+impl PooledClient {
+    /// Index of the pooled connection in use
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Report the connection as broken so the pool replaces it
+    pub fn discard(&self) {
+        let current = self.pool.slots[self.index].client.read().clone();
+        // Only if it has not been replaced already
+        if current.is_some_and(|current| Arc::ptr_eq(&current, &self.client)) {
+            self.pool.replace(self.index);
+        }
+    }
+}
+
+impl Deref for PooledClient {
+    type Target = JsonRpcClient;
+
+    fn deref(&self) -> &JsonRpcClient {
+        &self.client
+    }
+}
+
+impl Drop for PooledClient {
+    fn drop(&mut self) {
+        self.pool.slots[self.index].in_flight.fetch_sub(1, Ordering::SeqCst);
+        self.pool.available.notify_waiters();
+    }
+}
+
+impl std::fmt::Debug for PooledClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PooledClient")
+            .field("endpoint", &self.pool.endpoint)
+            .field("index", &self.index)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::types::{JsonRpcRequest, JsonRpcResponse};
+    use async_trait::async_trait;
+    use futures::FutureExt;
+    use serde_json::json;
+    use tokio::sync::mpsc;
+
+    /// One end of an in-memory link; dropping either end breaks it
+    struct Link {
+        tx: mpsc::UnboundedSender<String>,
+        rx: mpsc::UnboundedReceiver<String>,
+    }
+
+    #[async_trait]
+    impl Transport for Link {
+        async fn send(&mut self, message: &str) -> Result<()> {
+            self.tx.send(message.to_string()).map_err(|_| Error::connection("link broken"))
+        }
+
+        async fn receive(&mut self) -> Result<String> {
+            self.rx.recv().await.ok_or_else(|| Error::connection("link broken"))
+        }
+
+        async fn close(&mut self) -> Result<()> {
+            self.rx.close();
+            Ok(())
+        }
+    }
+
+    /// Echo server on the far end of a link
+    async fn echo(mut link: Link) {
+        while let Ok(message) = link.receive().await {
+            let request: JsonRpcRequest = serde_json::from_str(&message).unwrap();
+            let Some(id) = request.id else { continue };
+            let response = JsonRpcResponse::success(id, request.params.unwrap_or(Value::Null));
+            if link.send(&serde_json::to_string(&response).unwrap()).await.is_err() {
+                break;
+            }
+        }
+    }
+
+    /// Connector whose servers echo; their tasks are handed to the test
+    fn connector() -> (TransportConnector, mpsc::UnboundedReceiver<JoinHandle<()>>) {
+        let (servers_tx, servers) = mpsc::unbounded_channel();
+        let connect = move || {
+            let (a_tx, a_rx) = mpsc::unbounded_channel();
+            let (b_tx, b_rx) = mpsc::unbounded_channel();
+            let _ = servers_tx.send(tokio::spawn(echo(Link { tx: b_tx, rx: a_rx })));
+            async move { Ok(Box::new(Link { tx: a_tx, rx: b_rx }) as Box<dyn Transport>) }.boxed()
+        };
+        (Arc::new(connect), servers)
+    }
+
+    #[tokio::test]
+    async fn test_load_balancing() {
+        let (connect, _servers) = connector();
+        let config = PoolConfig::default().with_size(3).with_load_balancing(LoadBalancing::RoundRobin);
+        let pool = ConnectionPool::connect("memory", move || connect(), config).await.unwrap();
+        assert_eq!(pool.stats(), PoolStats { size: 3, healthy: 3, in_flight: 0 });
+
+        let order: Vec<usize> = futures::future::join_all((0..4).map(|_| pool.checkout())).await
+            .into_iter().map(|client| client.unwrap().index()).collect();
+        assert_eq!(order, vec![0, 1, 2, 0]);
+
+        // Least-in-flight avoids the busy connections
+        let (connect, _servers) = connector();
+        let pool = ConnectionPool::connect("memory", move || connect(), PoolConfig::default().with_size(3)).await.unwrap();
+        let first = pool.checkout().await.unwrap();
+        let second = pool.checkout().await.unwrap();
+        let third = pool.checkout().await.unwrap();
+        let mut indices = vec![first.index(), second.index(), third.index()];
+        indices.sort();
+        assert_eq!(indices, vec![0, 1, 2]);
+        assert_eq!(pool.stats().in_flight, 3);
+
+        drop(second);
+        let next = pool.checkout().await.unwrap();
+        assert_ne!(next.index(), first.index());
+        assert_ne!(next.index(), third.index());
+
+        let echoed: Value = pool.call("echo", json!([1, 2])).await.unwrap();
+        assert_eq!(echoed, json!([1, 2]));
+    }
+
+    #[tokio::test]
+    async fn test_checkout_timeout() {
+        let (connect, _servers) = connector();
+        let config = PoolConfig::default()
+            .with_size(2)
+            .with_max_in_flight(1)
+            .with_checkout_timeout(Duration::from_millis(50));
+        let pool = Arc::new(ConnectionPool::connect("memory", move || connect(), config).await.unwrap());
+
+        let first = pool.checkout().await.unwrap();
+        let _second = pool.checkout().await.unwrap();
+        assert!(matches!(pool.checkout().await, Err(Error::Timeout { .. })));
+
+        // A waiting checkout gets the connection as soon as it is returned
+        let waiting = tokio::spawn({
+            let pool = Arc::clone(&pool);
+            async move { pool.checkout().await.map(|client| client.index()) }
+        });
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        let index = first.index();
+        drop(first);
+        assert_eq!(waiting.await.unwrap().unwrap(), index);
+    }
+
+    #[tokio::test]
+    async fn test_health_check_replaces_broken_connection() {
+        let (connect, mut servers) = connector();
+        let config = PoolConfig::default()
+            .with_size(1)
+            .with_health_check("ping", Duration::from_millis(20))
+            .with_client_config(ClientConfig {
+                request_timeout: Duration::from_millis(200),
+                ..ClientConfig::default()
+            });
+        let pool = ConnectionPool::connect("memory", move || connect(), config).await.unwrap();
+
+        // Kill the server; the next health check notices and reconnects
+        servers.recv().await.unwrap().abort();
+        tokio::time::timeout(Duration::from_secs(2), servers.recv()).await.unwrap().unwrap();
+        tokio::time::timeout(Duration::from_secs(2), async {
+            while pool.stats().healthy == 0 {
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+        })
+        .await
+        .unwrap();
+
+        let echoed: Value = pool.call("echo", json!("back")).await.unwrap();
+        assert_eq!(echoed, json!("back"));
+    }
+}
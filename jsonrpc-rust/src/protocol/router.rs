@@ -0,0 +1,770 @@
+//! Method routing and request dispatch
+//!
+//! [`MethodRouter`] maps method names to [`MethodHandler`]s. Handlers are
+//! registered under exact names (`math.add`) or whole namespaces
+//! (`math.*`); exact names win over namespaces, and the longest matching
+//! namespace wins over shorter ones.
+//!
+//! The router keeps track of the calls it is answering, so that a caller
+//! can give up on one with a `$/cancelRequest` notification naming its id:
+//!
+//! ```json
+//! {"jsonrpc": "2.0", "method": "$/cancelRequest", "params": {"id": 7}}
+//! ```
+//!
+//! The call's [`ServiceContext::cancellation`] token is cancelled, its
+//! handler dropped and the call answered with code `-32800`. Ids are
+//! matched within the connection (`peer` metadata) or HTTP session
+//! (`session_id` metadata) the notification comes from.
+//!
+//! Routers are strict by default and refuse anything but JSON-RPC 2.0.
+//! With [`Compatibility::Legacy`] they also answer JSON-RPC 1.0 requests,
+//! which have no `jsonrpc` member and mark notifications with a `null` id:
+//!
+//! ```json
+//! {"method": "echo", "params": ["hello"], "id": 1}
+//! ```
+//!
+//! Such requests are handled as their 2.0 equivalent and answered in the
+//! 1.0 shape, with both `result` and `error` and no `jsonrpc` member:
+//!
+//! ```json
+//! {"result": ["hello"], "error": null, "id": 1}
+//! ```
+//!
+//! A handler that panics does not take the connection down: the panic is
+//! caught, logged at error level with a new incident id, counted in
+//! [`MethodRouter::panics`] and answered as an internal error (`-32603`)
+//! whose data carries the same `incident_id`, so that a report from the
+//! caller leads to the log line.
+//!
+//! Single requests given to [`MethodRouter::dispatch_message`] are parsed
+//! as a [`JsonRpcRequestRef`], leaving their params unparsed until the
+//! handler's [`MethodHandler::handle_raw`] needs them.
+
+use std::collections::HashMap;
+use std::panic::AssertUnwindSafe;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use dashmap::DashMap;
+use futures::FutureExt;
+use serde_json::Value;
+use tokio_util::sync::CancellationToken;
+use tracing::Instrument;
+use uuid::Uuid;
+
+use crate::core::error::{Error, ErrorData, JsonRpcError, Result};
+use crate::core::context::Context;
+use crate::core::{deadline, trace};
+use crate::core::traits::MethodHandler;
+use crate::core::types::{JsonRpcRequest, JsonRpcRequestRef, JsonRpcResponse, ServiceContext};
+
+/// Suffix marking a namespace registration
+const NAMESPACE_WILDCARD: &str = ".*";
+
+/// Method of the notification cancelling a call in flight
+pub const CANCEL_REQUEST_METHOD: &str = "$/cancelRequest";
+
+/// Protocol versions a [`MethodRouter`] answers
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Compatibility {
+    /// JSON-RPC 2.0 only
+    #[default]
+    Strict,
+    /// JSON-RPC 2.0, and 1.0 requests answered in kind
+    Legacy,
+}
+
+/// Routes JSON-RPC requests to registered method handlers
+#[derive(Default, Clone)]
+pub struct MethodRouter {
+    /// Handlers registered under exact method names
+    methods: HashMap<String, Arc<dyn MethodHandler>>,
+    /// Handlers registered under a namespace, keyed by prefix including the dot
+    namespaces: HashMap<String, Arc<dyn MethodHandler>>,
+    /// Calls being answered, shared by clones
+    in_flight: Arc<InFlight>,
+    /// Handler panics caught, shared by clones
+    panics: Arc<AtomicU64>,
+    compatibility: Compatibility,
+}
+
+impl MethodRouter {
+    /// Create an empty router
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a handler for a method name or namespace pattern (`math.*`)
+    pub fn register(&mut self, method: impl Into<String>, handler: Arc<dyn MethodHandler>) -> Result<()> {
+        let method = method.into();
+
+        let (table, key) = match method.strip_suffix(NAMESPACE_WILDCARD) {
+            Some(namespace) if !namespace.is_empty() => (&mut self.namespaces, format!("{}.", namespace)),
+            _ if method.is_empty() || method.contains('*') => {
+                return Err(Error::configuration(format!("Invalid method pattern: '{}'", method)));
+            }
+            _ => (&mut self.methods, method.clone()),
+        };
+
+        if table.contains_key(&key) {
+            return Err(Error::configuration(format!("Method '{}' is already registered", method)));
+        }
+        table.insert(key, handler);
+        Ok(())
+    }
+
+    /// Register a handler under every name in its `supported_methods`
+    pub fn register_handler(&mut self, handler: Arc<dyn MethodHandler>) -> Result<()> {
+        for method in handler.supported_methods() {
+            self.register(method, Arc::clone(&handler))?;
+        }
+        Ok(())
+    }
+
+    /// Register a handler, builder style
+    pub fn with_handler(mut self, handler: Arc<dyn MethodHandler>) -> Result<Self> {
+        self.register_handler(handler)?;
+        Ok(self)
+    }
+
+    /// Answer the protocol versions `compatibility` allows
+    pub fn with_compatibility(mut self, compatibility: Compatibility) -> Self {
+        self.compatibility = compatibility;
+        self
+    }
+
+    /// Protocol versions the router answers
+    pub fn compatibility(&self) -> Compatibility {
+        self.compatibility
+    }
+
+    /// Number of handler panics caught and answered as internal errors
+    pub fn panics(&self) -> u64 {
+        self.panics.load(Ordering::Relaxed)
+    }
+
+    /// Find the handler for a method
+    pub fn resolve(&self, method: &str) -> Option<&Arc<dyn MethodHandler>> {
+        if let Some(handler) = self.methods.get(method) {
+            return Some(handler);
+        }
+
+        self.namespaces
+            .iter()
+            .filter(|(prefix, _)| method.len() > prefix.len() && method.starts_with(prefix.as_str()))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map(|(_, handler)| handler)
+    }
+
+    /// Check whether a method has a handler
+    pub fn has_method(&self, method: &str) -> bool {
+        self.resolve(method).is_some()
+    }
+
+    /// Registered method names and namespace patterns, sorted
+    pub fn methods(&self) -> Vec<String> {
+        let mut methods: Vec<String> = self.methods.keys().cloned()
+            .chain(self.namespaces.keys().map(|prefix| format!("{}*", prefix)))
+            .collect();
+        methods.sort();
+        methods
+    }
+
+    /// Dispatch a request to its handler
+    ///
+    /// Returns `None` for notifications, which are routed to their handler
+    /// but never answered, not even with an error.
+    ///
+    /// The handler runs in a `jsonrpc.dispatch` span, with its context
+    /// [current](crate::core::context) and the caller's
+    /// [trace context](crate::core::trace) continued, and is answered with
+    /// a timeout error (`-32008`) once the [deadline](crate::core::deadline)
+    /// of the call passes, or a cancelled error (`-32800`) once the caller
+    /// [cancels](self) it.
+    pub async fn dispatch(&self, request: &JsonRpcRequest, context: &ServiceContext) -> Option<JsonRpcResponse> {
+        self.dispatch_call(request, None, context).await
+    }
+
+    /// Dispatch a request whose params are still raw JSON to its handler's
+    /// [`handle_raw`](MethodHandler::handle_raw), as [`dispatch`](Self::dispatch)
+    /// does otherwise
+    pub async fn dispatch_ref(&self, request: &JsonRpcRequestRef<'_>, context: &ServiceContext) -> Option<JsonRpcResponse> {
+        self.dispatch_call(&request.header(), Some(request), context).await
+    }
+
+    /// Dispatch `request`, calling the handler with `raw` instead when
+    /// given; `request` then has no params
+    async fn dispatch_call(
+        &self,
+        request: &JsonRpcRequest,
+        raw: Option<&JsonRpcRequestRef<'_>>,
+        context: &ServiceContext,
+    ) -> Option<JsonRpcResponse> {
+        if let (Some(raw), true) = (raw, request.method == CANCEL_REQUEST_METHOD) {
+            // The id to cancel is in the params
+            let request = match raw.to_request() {
+                Ok(request) => request,
+                Err(e) => return request.id.clone().map(|id| JsonRpcResponse::error(id, e.to_jsonrpc_error())),
+            };
+            return Box::pin(self.dispatch_call(&request, None, context)).await;
+        }
+        if request.method == CANCEL_REQUEST_METHOD && request.is_notification() {
+            self.cancel_request(request, context);
+            return None;
+        }
+
+        let (span, trace) = trace::dispatch_span(request, context);
+        let mut context = deadline::adopt(request, context);
+        let _in_flight = request.id.as_ref().map(|id| {
+            let (guard, cancellation) = self.in_flight.track(call_key(&context, id), &context.cancellation);
+            context.to_mut().cancellation = cancellation;
+            guard
+        });
+
+        let cancellation = context.cancellation.clone();
+        let call = deadline::run(&request.method, context.deadline, self.call(request, raw, &context));
+        let call = async {
+            tokio::select! {
+                biased;
+                _ = cancellation.cancelled() => Err(Error::cancelled(format!("call to '{}'", request.method))),
+                result = call => result,
+            }
+        };
+        let current = Arc::new(context.clone().into_owned());
+        let result = Context::scope(current, trace.scope(call)).instrument(span).await;
+
+        let id = match request.id {
+            Some(ref id) => id.clone(),
+            None => {
+                if let Err(e) = result {
+                    tracing::debug!("Notification '{}' failed: {}", request.method, e);
+                }
+                return None;
+            }
+        };
+
+        Some(match result {
+            Ok(mut response) => {
+                response.id = id;
+                response
+            }
+            Err(e) => JsonRpcResponse::error(id, e.to_jsonrpc_error().with_correlation_id(&context.request_id)),
+        })
+    }
+
+    /// Dispatch a raw JSON-RPC message, single or batch
+    ///
+    /// Returns the serialized response, or `None` when nothing should be
+    /// sent back (a notification or a batch made only of notifications).
+    pub async fn dispatch_message(&self, message: &str, context: &ServiceContext) -> Option<String> {
+        // Anything unusual takes the path below, which reports it
+        if message.trim_start().starts_with('{') {
+            if let Ok(request) = JsonRpcRequestRef::parse(message) {
+                if request.jsonrpc == crate::JSONRPC_VERSION {
+                    let response = self.dispatch_ref(&request, context).await?;
+                    return serialize(&response);
+                }
+            }
+        }
+
+        let value: Value = match serde_json::from_str(message) {
+            Ok(value) => value,
+            Err(e) => {
+                let error = JsonRpcError::parse_error(format!("Invalid JSON: {}", e));
+                return serialize(&JsonRpcResponse::error(Value::Null, error));
+            }
+        };
+
+        match value {
+            Value::Array(batch) if batch.is_empty() => {
+                let error = JsonRpcError::invalid_request("Empty batch");
+                serialize(&JsonRpcResponse::error(Value::Null, error))
+            }
+            Value::Array(batch) => {
+                let mut responses = Vec::with_capacity(batch.len());
+                for item in batch {
+                    if let Some(response) = self.dispatch_value(item, context).await {
+                        responses.push(response);
+                    }
+                }
+                if responses.is_empty() {
+                    None
+                } else {
+                    serialize(&responses)
+                }
+            }
+            single => {
+                let response = self.dispatch_value(single, context).await?;
+                serialize(&response)
+            }
+        }
+    }
+
+    async fn dispatch_value(&self, mut value: Value, context: &ServiceContext) -> Option<Value> {
+        let legacy = self.compatibility == Compatibility::Legacy && upgrade_legacy(&mut value);
+        let id = value.get("id").cloned().unwrap_or(Value::Null);
+
+        let response = match serde_json::from_value::<JsonRpcRequest>(value) {
+            Ok(request) => self.dispatch(&request, context).await?,
+            Err(e) => JsonRpcResponse::error(
+                id,
+                JsonRpcError::invalid_request(format!("Invalid request: {}", e)),
+            ),
+        };
+        let mut response = match serde_json::to_value(response) {
+            Ok(response) => response,
+            Err(e) => {
+                tracing::error!("Failed to serialize JSON-RPC response: {}", e);
+                return None;
+            }
+        };
+        if legacy {
+            downgrade_response(&mut response);
+        }
+        Some(response)
+    }
+
+    /// Cancel the call a `$/cancelRequest` notification names
+    fn cancel_request(&self, request: &JsonRpcRequest, context: &ServiceContext) {
+        let Some(id) = request.params.as_ref().and_then(|params| params.get("id")) else {
+            tracing::debug!("Ignoring {} without an id", CANCEL_REQUEST_METHOD);
+            return;
+        };
+        if !self.in_flight.cancel(&call_key(context, id)) {
+            tracing::debug!("No call {} in flight to cancel", id);
+        }
+    }
+
+    async fn call(
+        &self,
+        request: &JsonRpcRequest,
+        raw: Option<&JsonRpcRequestRef<'_>>,
+        context: &ServiceContext,
+    ) -> Result<JsonRpcResponse> {
+        if request.jsonrpc != crate::JSONRPC_VERSION {
+            return Err(JsonRpcError::invalid_request(
+                format!("Unsupported JSON-RPC version: {}", request.jsonrpc)
+            ).into());
+        }
+
+        let handler = self.resolve(&request.method)
+            .ok_or_else(|| Error::method_not_found(&request.method))?;
+        let handled = match raw {
+            Some(raw) => AssertUnwindSafe(handler.handle_raw(raw, context)).catch_unwind().await,
+            None => AssertUnwindSafe(handler.handle_method(request, context)).catch_unwind().await,
+        };
+        handled.unwrap_or_else(|panic| Err(self.panicked(&request.method, context, panic)))
+    }
+
+    /// Record a handler panic and turn it into the error answering the call
+    fn panicked(&self, method: &str, context: &ServiceContext, panic: Box<dyn std::any::Any + Send>) -> Error {
+        let incident = Uuid::new_v4().to_string();
+        let message = panic.downcast_ref::<&str>().copied()
+            .or_else(|| panic.downcast_ref::<String>().map(String::as_str))
+            .unwrap_or("non-string panic payload");
+        self.panics.fetch_add(1, Ordering::Relaxed);
+        tracing::error!(
+            method,
+            request_id = %context.request_id,
+            incident_id = %incident,
+            panic = message,
+            "Handler panicked"
+        );
+
+        let mut details = ErrorData::default();
+        details.extra.insert("incident_id".to_string(), Value::String(incident));
+        JsonRpcError::internal_error(format!("Internal error in '{}'", method))
+            .with_details(details)
+            .into()
+    }
+}
+
+impl std::fmt::Debug for MethodRouter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MethodRouter")
+            .field("methods", &self.methods())
+            .finish()
+    }
+}
+
+/// Calls being answered, by connection and request id
+#[derive(Default)]
+struct InFlight {
+    calls: DashMap<String, (u64, CancellationToken)>,
+    next: AtomicU64,
+}
+
+impl InFlight {
+    /// Track a call until the guard is dropped; its token is cancelled
+    /// along with `parent`
+    fn track(self: &Arc<Self>, key: String, parent: &CancellationToken) -> (InFlightGuard, CancellationToken) {
+        let cancellation = parent.child_token();
+        let seq = self.next.fetch_add(1, Ordering::Relaxed);
+        self.calls.insert(key.clone(), (seq, cancellation.clone()));
+        let guard = InFlightGuard {
+            in_flight: Arc::clone(self),
+            key,
+            seq,
+        };
+        (guard, cancellation)
+    }
+
+    fn cancel(&self, key: &str) -> bool {
+        match self.calls.get(key) {
+            Some(call) => {
+                call.1.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Stops tracking a call however it ends, unless a later call reused its id
+struct InFlightGuard {
+    in_flight: Arc<InFlight>,
+    key: String,
+    seq: u64,
+}
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        self.in_flight.calls.remove_if(&self.key, |_, (seq, _)| *seq == self.seq);
+    }
+}
+
+/// Request ids are unique within a connection, or an HTTP session
+fn call_key(context: &ServiceContext, id: &Value) -> String {
+    let scope = context.metadata.get("session_id").or_else(|| context.metadata.get("peer"));
+    format!("{}\u{1f}{}", scope.map(Value::to_string).unwrap_or_default(), id)
+}
+
+/// Turn a JSON-RPC 1.0 request into its 2.0 equivalent; false for 2.0
+/// requests and anything that is not a request object
+fn upgrade_legacy(value: &mut Value) -> bool {
+    let Value::Object(request) = value else {
+        return false;
+    };
+    if request.get("jsonrpc").and_then(Value::as_str) == Some(crate::JSONRPC_VERSION) {
+        return false;
+    }
+    request.insert("jsonrpc".to_string(), Value::from(crate::JSONRPC_VERSION));
+    // 1.0 notifications have a null id, 2.0 ones none
+    if request.get("id") == Some(&Value::Null) {
+        request.remove("id");
+    }
+    true
+}
+
+/// Give a response the JSON-RPC 1.0 shape
+fn downgrade_response(value: &mut Value) {
+    if let Value::Object(response) = value {
+        response.remove("jsonrpc");
+        response.entry("result").or_insert(Value::Null);
+        response.entry("error").or_insert(Value::Null);
+    }
+}
+
+fn serialize<T: serde::Serialize>(response: &T) -> Option<String> {
+    match serde_json::to_string(response) {
+        Ok(json) => Some(json),
+        Err(e) => {
+            tracing::error!("Failed to serialize JSON-RPC response: {}", e);
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::trace::TraceContext;
+    use crate::core::types::ResponseMetaInfo;
+    use async_trait::async_trait;
+    use parking_lot::Mutex;
+    use serde_json::json;
+
+    /// Answers with its own name and the called method, recording every call
+    struct EchoHandler {
+        name: &'static str,
+        methods: Vec<String>,
+        calls: Mutex<Vec<String>>,
+    }
+
+    impl EchoHandler {
+        fn new(name: &'static str, methods: &[&str]) -> Arc<Self> {
+            Arc::new(Self {
+                name,
+                methods: methods.iter().map(|m| m.to_string()).collect(),
+                calls: Mutex::new(Vec::new()),
+            })
+        }
+    }
+
+    #[async_trait]
+    impl MethodHandler for EchoHandler {
+        async fn handle_method(&self, request: &JsonRpcRequest, _context: &ServiceContext) -> Result<JsonRpcResponse> {
+            self.calls.lock().push(request.method.clone());
+            if request.method.ends_with(".fail") {
+                return Err(Error::invalid_params("bad input"));
+            }
+            if request.method.ends_with(".panic") {
+                panic!("handler bug");
+            }
+            Ok(JsonRpcResponse::success(Value::Null, json!([self.name, request.method])))
+        }
+
+        fn supported_methods(&self) -> Vec<String> {
+            self.methods.clone()
+        }
+    }
+
+    fn context() -> ServiceContext {
+        ServiceContext::new("test")
+    }
+
+    #[tokio::test]
+    async fn test_exact_and_namespace_routing() {
+        let math = EchoHandler::new("math", &["math.*"]);
+        let stats = EchoHandler::new("stats", &["math.stats.*"]);
+        let special = EchoHandler::new("special", &["math.special"]);
+
+        let mut router = MethodRouter::new();
+        router.register_handler(math.clone()).unwrap();
+        router.register_handler(stats).unwrap();
+        router.register_handler(special).unwrap();
+
+        for (method, expected) in [
+            ("math.add", "math"),
+            ("math.stats.mean", "stats"),
+            ("math.special", "special"),
+        ] {
+            let request = JsonRpcRequest::with_id(method, None, json!(7));
+            let response = router.dispatch(&request, &context()).await.unwrap();
+            assert_eq!(response.id, json!(7));
+            assert_eq!(response.result, Some(json!([expected, method])));
+        }
+
+        assert!(!router.has_method("math"));
+        assert!(!router.has_method("mathematics.add"));
+        assert_eq!(router.methods(), vec!["math.*", "math.special", "math.stats.*"]);
+
+        assert!(router.register("math.*", math.clone()).is_err());
+        assert!(router.register("*", math).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_errors_and_notifications() {
+        let handler = EchoHandler::new("echo", &["echo.*"]);
+        let router = MethodRouter::new().with_handler(handler.clone()).unwrap();
+
+        let unknown = JsonRpcRequest::with_id("missing", None, json!(1));
+        let response = router.dispatch(&unknown, &context()).await.unwrap();
+        assert_eq!(response.error.unwrap().code, -32601);
+
+        let failing = JsonRpcRequest::with_id("echo.fail", None, json!(2));
+        let response = router.dispatch(&failing, &context()).await.unwrap();
+        assert_eq!(response.error.unwrap().code, -32602);
+
+        let notification = JsonRpcRequest::notification("echo.log", None);
+        assert!(router.dispatch(&notification, &context()).await.is_none());
+        assert!(router.dispatch(&JsonRpcRequest::notification("missing", None), &context()).await.is_none());
+        assert_eq!(*handler.calls.lock(), vec!["echo.fail", "echo.log"]);
+    }
+
+    #[tokio::test]
+    async fn test_handler_panics_are_answered() {
+        let router = MethodRouter::new().with_handler(EchoHandler::new("echo", &["echo.*"])).unwrap();
+
+        let message = r#"{"jsonrpc": "2.0", "method": "echo.panic", "id": 1}"#;
+        let response: JsonRpcResponse = serde_json::from_str(&router.dispatch_message(message, &context()).await.unwrap()).unwrap();
+        let error = response.error.unwrap();
+        assert_eq!(error.code, -32603);
+        let details = error.details().unwrap();
+        assert!(details.extra["incident_id"].is_string());
+        assert_eq!(details.correlation_id.as_deref(), Some("test"));
+
+        // Notifications and later calls go on as before
+        router.dispatch(&JsonRpcRequest::notification("echo.panic", None), &context()).await;
+        assert_eq!(router.panics(), 2);
+        let response = router.dispatch(&JsonRpcRequest::with_id("echo.ok", None, json!(2)), &context()).await.unwrap();
+        assert_eq!(response.result, Some(json!(["echo", "echo.ok"])));
+    }
+
+    #[tokio::test]
+    async fn test_dispatch_message_batches() {
+        let router = MethodRouter::new()
+            .with_handler(EchoHandler::new("echo", &["echo.*"]))
+            .unwrap();
+
+        let parse_error: Value = serde_json::from_str(
+            &router.dispatch_message("{not json", &context()).await.unwrap()
+        ).unwrap();
+        assert_eq!(parse_error["error"]["code"], -32700);
+
+        let batch = json!([
+            {"jsonrpc": "2.0", "method": "echo.a", "id": 1},
+            {"jsonrpc": "2.0", "method": "echo.b"},
+            {"jsonrpc": "1.0", "method": "echo.c", "id": 3},
+            {"method": 5, "id": 4}
+        ]);
+        let responses: Value = serde_json::from_str(
+            &router.dispatch_message(&batch.to_string(), &context()).await.unwrap()
+        ).unwrap();
+        let responses = responses.as_array().unwrap();
+        assert_eq!(responses.len(), 3);
+        assert_eq!(responses[0]["result"], json!(["echo", "echo.a"]));
+        assert_eq!(responses[1]["error"]["code"], -32600);
+        assert_eq!(responses[2]["id"], 4);
+
+        let notifications = json!([{"jsonrpc": "2.0", "method": "echo.x"}]);
+        assert!(router.dispatch_message(&notifications.to_string(), &context()).await.is_none());
+        assert!(router.dispatch_message("[]", &context()).await.unwrap().contains("-32600"));
+    }
+
+    #[tokio::test]
+    async fn test_legacy_compatibility() {
+        let handler = EchoHandler::new("echo", &["echo.*"]);
+        let legacy = json!({"method": "echo.a", "params": ["hi"], "id": 1}).to_string();
+
+        // Strict routers refuse 1.0 requests
+        let strict = MethodRouter::new().with_handler(handler.clone()).unwrap();
+        let refused: Value = serde_json::from_str(&strict.dispatch_message(&legacy, &context()).await.unwrap()).unwrap();
+        assert_eq!(refused["error"]["code"], -32600);
+
+        let router = strict.with_compatibility(Compatibility::Legacy);
+        let answered: Value = serde_json::from_str(&router.dispatch_message(&legacy, &context()).await.unwrap()).unwrap();
+        assert_eq!(answered, json!({"result": ["echo", "echo.a"], "error": null, "id": 1}));
+
+        let failed = json!({"jsonrpc": "1.0", "method": "echo.fail", "params": [], "id": 2}).to_string();
+        let failed: Value = serde_json::from_str(&router.dispatch_message(&failed, &context()).await.unwrap()).unwrap();
+        assert_eq!(failed["result"], Value::Null);
+        assert_eq!(failed["error"]["code"], -32602);
+
+        // 1.0 notifications have a null id; 2.0 requests keep their shape
+        let notification = json!({"method": "echo.log", "params": [], "id": null}).to_string();
+        assert!(router.dispatch_message(&notification, &context()).await.is_none());
+        let modern = json!({"jsonrpc": "2.0", "method": "echo.b", "id": 3}).to_string();
+        let modern: Value = serde_json::from_str(&router.dispatch_message(&modern, &context()).await.unwrap()).unwrap();
+        assert_eq!(modern["jsonrpc"], "2.0");
+        assert!(modern.get("error").is_none());
+        assert_eq!(*handler.calls.lock(), vec!["echo.a", "echo.fail", "echo.log", "echo.b"]);
+    }
+
+    /// Adds its two params, reading them straight from the raw JSON
+    struct AddHandler;
+
+    #[async_trait]
+    impl MethodHandler for AddHandler {
+        async fn handle_method(&self, _request: &JsonRpcRequest, _context: &ServiceContext) -> Result<JsonRpcResponse> {
+            Err(JsonRpcError::internal_error("only raw requests expected").into())
+        }
+
+        async fn handle_raw(&self, request: &JsonRpcRequestRef<'_>, _context: &ServiceContext) -> Result<JsonRpcResponse> {
+            let (a, b) = request.params::<(i64, i64)>()?;
+            Ok(JsonRpcResponse::success(request.id.clone().unwrap_or(Value::Null), json!(a + b)))
+        }
+
+        fn supported_methods(&self) -> Vec<String> {
+            vec!["add".to_string()]
+        }
+    }
+
+    #[tokio::test]
+    async fn test_single_messages_keep_params_raw() {
+        let router = MethodRouter::new().with_handler(Arc::new(AddHandler)).unwrap();
+        let call = |message: Value| {
+            let router = router.clone();
+            async move {
+                let response = router.dispatch_message(&message.to_string(), &context()).await.unwrap();
+                serde_json::from_str::<Value>(&response).unwrap()
+            }
+        };
+
+        let sum = call(json!({"jsonrpc": "2.0", "method": "add", "params": [2, 3], "id": 1})).await;
+        assert_eq!(sum["result"], 5);
+        let invalid = call(json!({"jsonrpc": "2.0", "method": "add", "params": ["two"], "id": 2})).await;
+        assert_eq!(invalid["error"]["code"], -32602);
+        let missing = call(json!({"jsonrpc": "2.0", "method": "sub", "params": [2, 3], "id": 3})).await;
+        assert_eq!(missing["error"]["code"], -32601);
+
+        // Owned requests, as in batches, still reach handle_method
+        let batch = call(json!([{"jsonrpc": "2.0", "method": "add", "params": [2, 3], "id": 4}])).await;
+        assert_eq!(batch[0]["error"]["code"], -32603);
+    }
+
+    /// Answers with the trace context its call runs in
+    struct TraceHandler;
+
+    #[async_trait]
+    impl MethodHandler for TraceHandler {
+        async fn handle_method(&self, _request: &JsonRpcRequest, _context: &ServiceContext) -> Result<JsonRpcResponse> {
+            let trace = TraceContext::current().unwrap();
+            let meta_info = ResponseMetaInfo::new();
+            Ok(JsonRpcResponse::success(Value::Null, json!([trace.traceparent(), meta_info.trace_id])))
+        }
+
+        fn supported_methods(&self) -> Vec<String> {
+            vec!["trace".to_string()]
+        }
+    }
+
+    #[tokio::test]
+    async fn test_dispatch_continues_caller_trace() {
+        let router = MethodRouter::new().with_handler(Arc::new(TraceHandler)).unwrap();
+        let caller = TraceContext::parse("00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01").unwrap();
+        let mut request = JsonRpcRequest::with_id("trace", None, json!(1));
+        caller.inject(&mut request);
+
+        let result = router.dispatch(&request, &context()).await.unwrap().result.unwrap();
+        let trace = TraceContext::parse(result[0].as_str().unwrap()).unwrap();
+        assert_eq!(trace.trace_id, caller.trace_id);
+        assert_ne!(trace.span_id, caller.span_id);
+        assert_eq!(result[1], json!(caller.trace_id));
+
+        let request = JsonRpcRequest::with_id("trace", None, json!(2));
+        let result = router.dispatch(&request, &context()).await.unwrap().result.unwrap();
+        assert_ne!(TraceContext::parse(result[0].as_str().unwrap()).unwrap().trace_id, caller.trace_id);
+    }
+
+    /// Never answers until cancelled
+    struct StuckHandler;
+
+    #[async_trait]
+    impl MethodHandler for StuckHandler {
+        async fn handle_method(&self, _request: &JsonRpcRequest, context: &ServiceContext) -> Result<JsonRpcResponse> {
+            context.cancellation.cancelled().await;
+            Ok(JsonRpcResponse::success(Value::Null, json!("answered after cancellation")))
+        }
+
+        fn supported_methods(&self) -> Vec<String> {
+            vec!["stuck".to_string()]
+        }
+    }
+
+    #[tokio::test]
+    async fn test_cancel_request_answers_call_cancelled() {
+        let router = MethodRouter::new().with_handler(Arc::new(StuckHandler)).unwrap();
+        let peer = |addr: &str| context().with_metadata("peer", json!(addr));
+
+        let call = tokio::spawn({
+            let router = router.clone();
+            let request = JsonRpcRequest::with_id("stuck", None, json!(7));
+            async move { router.dispatch(&request, &peer("a")).await }
+        });
+        tokio::task::yield_now().await;
+
+        let cancel = JsonRpcRequest::notification(CANCEL_REQUEST_METHOD, Some(json!({"id": 7})));
+        assert!(router.dispatch(&cancel, &peer("b")).await.is_none());
+        tokio::task::yield_now().await;
+        assert!(!call.is_finished());
+
+        assert!(router.dispatch(&cancel, &peer("a")).await.is_none());
+        let response = call.await.unwrap().unwrap();
+        assert_eq!(response.id, json!(7));
+        assert_eq!(response.error.unwrap().code, crate::core::error::REQUEST_CANCELLED);
+        assert!(router.in_flight.calls.is_empty());
+    }
+}
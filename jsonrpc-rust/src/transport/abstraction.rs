@@ -16,6 +16,7 @@ use tokio::sync::RwLock;
 use crate::core::error::{Error, Result};
 use crate::core::traits::{Transport, Connection, Message};
 use crate::core::types::{JsonRpcRequest, JsonRpcResponse, MessageId};
+use super::compression::CompressionConfig;
 
 /// Enhanced transport layer with connection management and message codecs
 /// 
@@ -108,6 +109,17 @@ pub trait TransportConfig: Send + Sync + Clone + fmt::Debug {
     
     /// Get connection limits
     fn connection_limits(&self) -> ConnectionLimits;
+    
+    /// Get the compression settings; transports that cannot negotiate
+    /// compression ignore them
+    fn compression(&self) -> CompressionConfig {
+        CompressionConfig::default()
+    }
+
+    /// Get the rate limits servers apply to calls on the transport, if any
+    fn rate_limit(&self) -> Option<RateLimitConfig> {
+        None
+    }
 }
 
 /// Unified JSON-RPC message type
@@ -246,6 +258,8 @@ pub enum FramingType {
     LengthPrefixed,
     /// Newline-delimited framing
     LineDelimited,
+    /// `Content-Length` header framing, as used by LSP
+    ContentLength,
     /// WebSocket frames
     WebSocketFrames,
     /// HTTP request/response
@@ -378,6 +392,115 @@ impl Default for ConnectionLimits {
     }
 }
 
+/// A token bucket: calls spend a token each, tokens come back at a steady
+/// rate and at most `burst` accumulate
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct RateLimit {
+    /// Tokens restored per second
+    pub per_second: f64,
+    /// Bucket capacity, the calls allowed at once after a quiet period
+    pub burst: u32,
+}
+
+impl RateLimit {
+    /// Allow `per_second` calls a second, in bursts of up to `burst`
+    pub fn new(per_second: f64, burst: u32) -> Self {
+        Self { per_second, burst }
+    }
+
+    /// Validate the limit
+    pub fn validate(&self) -> Result<()> {
+        if !(self.per_second.is_finite() && self.per_second > 0.0) {
+            return Err(Error::configuration("Rate limit must be a positive number of calls per second"));
+        }
+        if self.burst == 0 {
+            return Err(Error::configuration("Rate limit burst cannot be zero"));
+        }
+        Ok(())
+    }
+}
+
+/// What calls share a rate limit bucket by
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RateLimitKey {
+    /// The client id of the call, or the authenticated user
+    ClientId,
+    /// The address the call came from
+    RemoteAddr,
+    /// The method called
+    Method,
+}
+
+/// Rate limits applied to incoming calls
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RateLimitConfig {
+    /// What separates buckets; calls agreeing on every key share one, and
+    /// without keys all calls share a single bucket
+    pub keys: Vec<RateLimitKey>,
+    /// Limit of each bucket
+    pub limit: RateLimit,
+    /// Limits replacing `limit` for some methods
+    pub methods: HashMap<String, RateLimit>,
+    /// Most buckets kept at once; full buckets are dropped first when more
+    /// are needed
+    pub max_buckets: usize,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            keys: vec![RateLimitKey::ClientId],
+            limit: RateLimit::new(100.0, 200),
+            methods: HashMap::new(),
+            max_buckets: 10_000,
+        }
+    }
+}
+
+impl RateLimitConfig {
+    /// Limit each bucket to `limit`
+    pub fn new(limit: RateLimit) -> Self {
+        Self { limit, ..Self::default() }
+    }
+
+    /// Set what separates buckets
+    pub fn with_keys(mut self, keys: impl IntoIterator<Item = RateLimitKey>) -> Self {
+        self.keys = keys.into_iter().collect();
+        self
+    }
+
+    /// Limit calls to `method` to `limit` instead
+    pub fn with_method(mut self, method: impl Into<String>, limit: RateLimit) -> Self {
+        self.methods.insert(method.into(), limit);
+        self
+    }
+
+    /// Set the most buckets kept at once
+    pub fn with_max_buckets(mut self, max_buckets: usize) -> Self {
+        self.max_buckets = max_buckets;
+        self
+    }
+
+    /// The limit of calls to `method`
+    pub fn limit_for(&self, method: &str) -> RateLimit {
+        self.methods.get(method).copied().unwrap_or(self.limit)
+    }
+
+    /// Validate the configuration
+    pub fn validate(&self) -> Result<()> {
+        self.limit.validate()?;
+        for limit in self.methods.values() {
+            limit.validate()?;
+        }
+        if self.max_buckets == 0 {
+            return Err(Error::configuration("Max rate limit buckets cannot be zero"));
+        }
+        Ok(())
+    }
+}
+
 /// Transport-specific error types
 #[derive(Debug, thiserror::Error)]
 pub enum TransportError {
@@ -430,6 +553,11 @@ impl MessageCodec for DefaultMessageCodec {
                 result.push(b'\n');
                 Ok(result)
             }
+            FramingType::ContentLength => {
+                let mut result = format!("Content-Length: {}\r\n\r\n", bytes.len()).into_bytes();
+                result.extend_from_slice(bytes);
+                Ok(result)
+            }
             _ => Ok(bytes.to_vec()),
         }
     }
@@ -453,6 +581,14 @@ impl MessageCodec for DefaultMessageCodec {
                     data
                 }
             }
+            FramingType::ContentLength => {
+                let body_start = data.windows(4).position(|window| window == b"\r\n\r\n")
+                    .ok_or_else(|| Error::Transport {
+                        message: "Missing Content-Length header terminator".to_string(),
+                        source: None,
+                    })?;
+                &data[body_start + 4..]
+            }
             _ => data,
         };
         
@@ -517,4 +653,16 @@ mod tests {
         let decoded = codec.decode(&encoded).unwrap();
         assert_eq!(message, decoded);
     }
+    
+    #[test]
+    fn test_content_length_codec() {
+        let codec = DefaultMessageCodec::new(FramingType::ContentLength);
+        let message = JsonRpcMessage::notification("test", None);
+        
+        let encoded = codec.encode(&message).unwrap();
+        assert!(encoded.starts_with(b"Content-Length: "));
+        
+        let decoded = codec.decode(&encoded).unwrap();
+        assert_eq!(message, decoded);
+    }
 } 
\ No newline at end of file
@@ -0,0 +1,256 @@
+//! Per-connection message compression
+//!
+//! Compression is negotiated when a connection is set up (see
+//! [`NegotiatedTransport`](super::NegotiatedTransport)) and applied to
+//! serialized frames, below the serializer. Small frames are sent as they
+//! are, since compressing them costs more than it saves. zstd (`zstd`
+//! feature) is the better choice between peers that both have it; deflate
+//! (`deflate` feature) is pure Rust and widely available.
+
+use std::fmt;
+use std::str::FromStr;
+use serde::{Deserialize, Serialize};
+
+use crate::core::error::{Error, Result};
+
+/// A compression algorithm
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CompressionAlgorithm {
+    /// No compression
+    None,
+    /// Raw deflate (RFC 1951)
+    Deflate,
+    /// Zstandard
+    Zstd,
+}
+
+impl CompressionAlgorithm {
+    /// Whether this build can compress and decompress with the algorithm
+    pub fn is_supported(self) -> bool {
+        match self {
+            Self::None => true,
+            Self::Deflate => cfg!(feature = "deflate"),
+            Self::Zstd => cfg!(feature = "zstd"),
+        }
+    }
+
+    /// Name used in negotiation
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::None => "none",
+            Self::Deflate => "deflate",
+            Self::Zstd => "zstd",
+        }
+    }
+
+    /// The first of `offered` that is also in `accepted` and supported here,
+    /// or `None` when there is no such algorithm
+    pub fn negotiate(offered: &[Self], accepted: &[Self]) -> Self {
+        offered.iter()
+            .copied()
+            .find(|algorithm| algorithm.is_supported() && accepted.contains(algorithm))
+            .unwrap_or(Self::None)
+    }
+
+    /// Compress `data`; `level` is algorithm specific, `None` for the default
+    #[cfg_attr(not(any(feature = "deflate", feature = "zstd")), allow(unused_variables))]
+    pub fn compress(self, data: &[u8], level: Option<i32>) -> Result<Vec<u8>> {
+        match self {
+            Self::None => Ok(data.to_vec()),
+            #[cfg(feature = "deflate")]
+            Self::Deflate => {
+                use std::io::Write;
+                let level = level.map_or(flate2::Compression::default(), |level| {
+                    flate2::Compression::new(level.clamp(0, 9) as u32)
+                });
+                let mut encoder = flate2::write::DeflateEncoder::new(Vec::new(), level);
+                encoder.write_all(data)?;
+                Ok(encoder.finish()?)
+            }
+            #[cfg(feature = "zstd")]
+            Self::Zstd => Ok(zstd::bulk::compress(data, level.unwrap_or(zstd::DEFAULT_COMPRESSION_LEVEL))?),
+            #[allow(unreachable_patterns)]
+            _ => Err(self.unsupported()),
+        }
+    }
+
+    /// Decompress `data`, refusing output larger than `max_size`
+    pub fn decompress(self, data: &[u8], max_size: usize) -> Result<Vec<u8>> {
+        let output = match self {
+            Self::None => data.to_vec(),
+            #[cfg(feature = "deflate")]
+            Self::Deflate => {
+                use std::io::Read;
+                let mut output = Vec::new();
+                flate2::read::DeflateDecoder::new(data)
+                    .take(max_size as u64 + 1)
+                    .read_to_end(&mut output)?;
+                output
+            }
+            #[cfg(feature = "zstd")]
+            Self::Zstd => zstd::bulk::decompress(data, max_size)
+                .map_err(|e| Error::transport(format!("zstd decompression failed: {}", e)))?,
+            #[allow(unreachable_patterns)]
+            _ => return Err(self.unsupported()),
+        };
+
+        if output.len() > max_size {
+            return Err(Error::validation(format!("Decompressed message exceeds the {} byte limit", max_size)));
+        }
+        Ok(output)
+    }
+
+    #[allow(dead_code)]
+    fn unsupported(self) -> Error {
+        Error::configuration(format!("Compression algorithm '{}' is not enabled in this build", self))
+    }
+}
+
+impl fmt::Display for CompressionAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for CompressionAlgorithm {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "none" => Ok(Self::None),
+            "deflate" => Ok(Self::Deflate),
+            "zstd" => Ok(Self::Zstd),
+            _ => Err(Error::validation(format!("Unknown compression algorithm: {}", s))),
+        }
+    }
+}
+
+/// Compression configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CompressionConfig {
+    /// Algorithms to offer or accept, most preferred first; empty disables
+    /// compression
+    pub algorithms: Vec<CompressionAlgorithm>,
+    /// Frames smaller than this many bytes are sent uncompressed
+    pub threshold: usize,
+    /// Algorithm specific level, `None` for the default
+    pub level: Option<i32>,
+}
+
+impl Default for CompressionConfig {
+    fn default() -> Self {
+        Self {
+            algorithms: Vec::new(),
+            threshold: 1024,
+            level: None,
+        }
+    }
+}
+
+impl CompressionConfig {
+    /// Offer or accept the given algorithms, most preferred first
+    pub fn with_algorithms(mut self, algorithms: impl IntoIterator<Item = CompressionAlgorithm>) -> Self {
+        self.algorithms = algorithms.into_iter().collect();
+        self
+    }
+
+    /// Set the size below which frames are not compressed
+    pub fn with_threshold(mut self, threshold: usize) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    /// Set the compression level
+    pub fn with_level(mut self, level: i32) -> Self {
+        self.level = Some(level);
+        self
+    }
+
+    /// Whether any algorithm is enabled
+    pub fn is_enabled(&self) -> bool {
+        self.algorithms.iter().any(|algorithm| *algorithm != CompressionAlgorithm::None)
+    }
+
+    /// Check every configured algorithm is compiled in
+    pub fn validate(&self) -> Result<()> {
+        match self.algorithms.iter().find(|algorithm| !algorithm.is_supported()) {
+            Some(algorithm) => Err(algorithm.unsupported()),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Compression statistics of the frames sent on a connection
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CompressionStats {
+    /// Frames sent compressed
+    pub compressed_frames: u64,
+    /// Frames sent as they were, being under the threshold or not
+    /// shrinking when compressed
+    pub uncompressed_frames: u64,
+    /// Size of all frames before compression
+    pub bytes_in: u64,
+    /// Size of all frames as sent
+    pub bytes_out: u64,
+}
+
+impl CompressionStats {
+    /// Bytes sent per byte before compression; below 1.0 means savings
+    pub fn ratio(&self) -> f64 {
+        if self.bytes_in == 0 {
+            1.0
+        } else {
+            self.bytes_out as f64 / self.bytes_in as f64
+        }
+    }
+
+    pub(crate) fn record(&mut self, before: usize, after: usize, compressed: bool) {
+        if compressed {
+            self.compressed_frames += 1;
+        } else {
+            self.uncompressed_frames += 1;
+        }
+        self.bytes_in += before as u64;
+        self.bytes_out += after as u64;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compression_round_trip() {
+        let data = br#"{"jsonrpc":"2.0","result":"aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa","id":1}"#.repeat(20);
+
+        for algorithm in [CompressionAlgorithm::None, CompressionAlgorithm::Deflate, CompressionAlgorithm::Zstd] {
+            if !algorithm.is_supported() {
+                assert!(algorithm.compress(&data, None).is_err());
+                continue;
+            }
+            let compressed = algorithm.compress(&data, Some(3)).unwrap();
+            if algorithm != CompressionAlgorithm::None {
+                assert!(compressed.len() < data.len() / 4, "{} barely compressed", algorithm);
+            }
+            assert_eq!(algorithm.decompress(&compressed, data.len()).unwrap(), data);
+            // Output past the limit is refused
+            assert!(algorithm.decompress(&compressed, data.len() - 1).is_err());
+        }
+    }
+
+    #[test]
+    fn test_compression_negotiation() {
+        use CompressionAlgorithm::*;
+        assert_eq!(CompressionAlgorithm::negotiate(&[Deflate], &[]), None);
+        assert_eq!(CompressionAlgorithm::negotiate(&[None], &[None]), None);
+        let expected = if cfg!(feature = "zstd") { Zstd } else if cfg!(feature = "deflate") { Deflate } else { None };
+        assert_eq!(CompressionAlgorithm::negotiate(&[Zstd, Deflate], &[Deflate, Zstd]), expected);
+
+        let config = CompressionConfig::default().with_algorithms([Zstd, Deflate]);
+        assert!(config.is_enabled());
+        assert_eq!(config.validate().is_ok(), Zstd.is_supported() && Deflate.is_supported());
+        assert!(!CompressionConfig::default().is_enabled());
+    }
+}
@@ -0,0 +1,780 @@
+//! HTTP transport implementation for JSON-RPC
+//!
+//! Requests travel as `POST` bodies and the reply comes back in the HTTP
+//! response, so a single message or a whole batch costs one round trip.
+//! Notifications get no reply and are answered with `204 No Content`.
+//!
+//! HTTP has no way for the server to speak first. When long-polling is
+//! enabled the client keeps a `GET` request open on the same path, tagged
+//! with its session id; the server holds it until a notification is queued
+//! for that session or the poll times out.
+//!
+//! [`HttpTransport`] is the client side; [`HttpServer`] answers requests
+//! with a [`MethodRouter`] and pushes notifications through an
+//! [`HttpNotifier`].
+
+use std::collections::{HashMap, VecDeque};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use async_trait::async_trait;
+use axum::body::Bytes;
+use axum::extract::{DefaultBodyLimit, State};
+use axum::http::{HeaderMap, HeaderName, HeaderValue, Method, StatusCode, header};
+use axum::response::{IntoResponse, Response};
+use axum::routing::post;
+use http_body_util::{BodyExt, Full};
+use hyper_util::client::legacy::Client;
+use hyper_util::client::legacy::connect::HttpConnector;
+use hyper_util::rt::TokioExecutor;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use tokio::net::TcpListener;
+use tokio::sync::{mpsc, Notify};
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+use uuid::Uuid;
+
+use crate::core::error::{Error, Result};
+use crate::core::trace::{TRACEPARENT, TRACESTATE};
+use crate::core::traits::{Transport, Connection};
+use crate::core::types::{JsonRpcRequest, ServiceContext};
+use crate::protocol::MethodRouter;
+use super::abstraction::{
+    TransportConfig, ConnectionState, TimeoutConfig, RetryConfig, ConnectionLimits,
+};
+
+/// Header carrying the long-polling session id
+pub const SESSION_HEADER: &str = "x-jsonrpc-session";
+
+/// HTTP transport configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HttpConfig {
+    /// Endpoint URL for client connections (`http://host:port/path`)
+    pub url: Option<String>,
+    /// Bind address for server mode
+    pub bind_address: Option<SocketAddr>,
+    /// Path the server answers on
+    pub path: String,
+    /// Extra headers sent with every request (client) or response (server)
+    pub headers: HashMap<String, String>,
+    /// Timeout configuration; `idle_timeout` also expires unpolled sessions
+    pub timeouts: TimeoutConfig,
+    /// Retry configuration
+    pub retry_config: RetryConfig,
+    /// Connection limits; `max_message_size` caps request bodies
+    pub connection_limits: ConnectionLimits,
+    /// How long a long-poll is held, `None` to disable long-polling
+    pub long_poll_timeout: Option<Duration>,
+}
+
+impl Default for HttpConfig {
+    fn default() -> Self {
+        Self {
+            url: None,
+            bind_address: None,
+            path: "/".to_string(),
+            headers: HashMap::new(),
+            timeouts: TimeoutConfig::default(),
+            retry_config: RetryConfig::default(),
+            connection_limits: ConnectionLimits::default(),
+            long_poll_timeout: None,
+        }
+    }
+}
+
+impl HttpConfig {
+    /// Configuration for a client posting to `url`
+    pub fn client(url: impl Into<String>) -> Self {
+        Self {
+            url: Some(url.into()),
+            ..Self::default()
+        }
+    }
+
+    /// Configuration for a server listening on `bind_address`
+    pub fn server(bind_address: SocketAddr) -> Self {
+        Self {
+            bind_address: Some(bind_address),
+            ..Self::default()
+        }
+    }
+
+    /// Set the path the server answers on
+    pub fn with_path(mut self, path: impl Into<String>) -> Self {
+        self.path = path.into();
+        self
+    }
+
+    /// Add a header to every request or response
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.insert(name.into(), value.into());
+        self
+    }
+
+    /// Enable long-polling for server-to-client notifications
+    pub fn with_long_polling(mut self, timeout: Duration) -> Self {
+        self.long_poll_timeout = Some(timeout);
+        self
+    }
+
+    /// Set the maximum message size
+    pub fn with_max_message_size(mut self, max_message_size: usize) -> Self {
+        self.connection_limits.max_message_size = max_message_size;
+        self
+    }
+
+    fn header_map(&self) -> Result<HeaderMap> {
+        let mut headers = HeaderMap::with_capacity(self.headers.len());
+        for (name, value) in &self.headers {
+            let name = HeaderName::try_from(name.as_str())
+                .map_err(|e| Error::configuration(format!("Invalid header name {}: {}", name, e)))?;
+            let value = HeaderValue::try_from(value.as_str())
+                .map_err(|e| Error::configuration(format!("Invalid value for header {}: {}", name, e)))?;
+            headers.insert(name, value);
+        }
+        Ok(headers)
+    }
+}
+
+impl TransportConfig for HttpConfig {
+    fn validate(&self) -> Result<()> {
+        if let Some(ref url) = self.url {
+            let parsed = url::Url::parse(url)
+                .map_err(|e| Error::configuration(format!("Invalid HTTP URL {}: {}", url, e)))?;
+            if parsed.scheme() != "http" {
+                return Err(Error::configuration(format!("Unsupported HTTP URL scheme: {}", parsed.scheme())));
+            }
+        }
+
+        if !self.path.starts_with('/') {
+            return Err(Error::configuration(format!("HTTP path must start with '/': {}", self.path)));
+        }
+
+        if self.timeouts.connect_timeout.is_zero() {
+            return Err(Error::configuration("Connect timeout cannot be zero"));
+        }
+
+        if self.connection_limits.max_message_size == 0 {
+            return Err(Error::configuration("Max message size cannot be zero"));
+        }
+
+        if self.long_poll_timeout.is_some_and(|timeout| timeout.is_zero()) {
+            return Err(Error::configuration("Long-poll timeout cannot be zero"));
+        }
+
+        self.header_map()?;
+        Ok(())
+    }
+
+    fn timeouts(&self) -> TimeoutConfig {
+        self.timeouts.clone()
+    }
+
+    fn retry_config(&self) -> RetryConfig {
+        self.retry_config.clone()
+    }
+
+    fn connection_limits(&self) -> ConnectionLimits {
+        self.connection_limits.clone()
+    }
+}
+
+type HttpClient = Client<HttpConnector, Full<Bytes>>;
+
+/// HTTP connection implementation
+///
+/// A connection is a session rather than a socket: `send` posts a message
+/// and queues the reply, and `receive` hands out queued replies and
+/// long-polled notifications in arrival order.
+pub struct HttpConnection {
+    /// Connection ID, also the long-polling session id
+    id: String,
+    /// Connection configuration
+    config: HttpConfig,
+    /// Connection state
+    state: ConnectionState,
+    /// Pooled HTTP client
+    client: Option<HttpClient>,
+    /// Headers added to every request, including the session id
+    headers: HeaderMap,
+    /// Sender side of the incoming queue, shared with the poller
+    incoming_tx: mpsc::UnboundedSender<Result<String>>,
+    /// Replies and notifications not yet received
+    incoming: mpsc::UnboundedReceiver<Result<String>>,
+    /// Long-polling task
+    poller: Option<JoinHandle<()>>,
+    /// Messages sent on this connection
+    messages_sent: u64,
+    /// Messages received on this connection
+    messages_received: u64,
+    /// Last error
+    last_error: Option<Error>,
+}
+
+impl HttpConnection {
+    /// Create a disconnected client connection
+    pub fn new(config: HttpConfig) -> Self {
+        let (incoming_tx, incoming) = mpsc::unbounded_channel();
+        Self {
+            id: Uuid::new_v4().to_string(),
+            config,
+            state: ConnectionState::Disconnected,
+            client: None,
+            headers: HeaderMap::new(),
+            incoming_tx,
+            incoming,
+            poller: None,
+            messages_sent: 0,
+            messages_received: 0,
+            last_error: None,
+        }
+    }
+
+    /// Connection ID, sent as the long-polling session id
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Post a message, queueing the reply if there is one
+    pub async fn post(&mut self, message: &str) -> Result<()> {
+        let limit = self.config.connection_limits.max_message_size;
+        if message.len() > limit {
+            return Err(Error::validation(format!(
+                "Message of {} bytes exceeds the {} byte limit", message.len(), limit
+            )));
+        }
+
+        let client = self.client.as_ref()
+            .ok_or_else(|| Error::connection("HTTP connection not established"))?;
+        let url = self.config.url.as_deref().unwrap_or_default();
+        let mut request = hyper::Request::post(url)
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Full::new(Bytes::from(message.to_string())))
+            .map_err(|e| Error::transport(format!("Invalid HTTP request: {}", e)))?;
+        request.headers_mut().extend(self.headers.clone());
+
+        let exchange = exchange(client, request);
+        let reply = match tokio::time::timeout(self.config.timeouts.write_timeout, exchange).await {
+            Ok(Ok(reply)) => reply,
+            Ok(Err(e)) => {
+                self.last_error = Some(Error::transport(e.to_string()));
+                return Err(e);
+            }
+            Err(_) => return Err(Error::timeout(format!("POST to {}", url), self.config.timeouts.write_timeout)),
+        };
+
+        self.messages_sent += 1;
+        if let Some(body) = reply {
+            let _ = self.incoming_tx.send(Ok(body));
+        }
+        Ok(())
+    }
+
+    /// Receive the next reply or notification
+    pub async fn next_message(&mut self) -> Result<String> {
+        if self.client.is_none() {
+            return Err(Error::connection("HTTP connection not established"));
+        }
+
+        match self.incoming.recv().await {
+            Some(Ok(message)) => {
+                self.messages_received += 1;
+                Ok(message)
+            }
+            Some(Err(e)) => Err(e),
+            // The connection keeps a sender, so the queue never closes
+            None => Err(Error::connection("HTTP connection closed")),
+        }
+    }
+
+    fn start_polling(&mut self, client: HttpClient, hold: Duration) {
+        let url = self.config.url.clone().unwrap_or_default();
+        let headers = self.headers.clone();
+        let wait = hold + self.config.timeouts.read_timeout;
+        let retry_delay = self.config.retry_config.initial_delay;
+        let incoming = self.incoming_tx.clone();
+
+        self.poller = Some(tokio::spawn(async move {
+            loop {
+                let mut request = hyper::Request::get(url.as_str())
+                    .body(Full::new(Bytes::new()))
+                    .expect("validated URL");
+                request.headers_mut().extend(headers.clone());
+
+                match tokio::time::timeout(wait, exchange(&client, request)).await {
+                    Ok(Ok(Some(body))) => {
+                        if incoming.send(Ok(body)).is_err() {
+                            break;
+                        }
+                    }
+                    Ok(Ok(None)) => {}
+                    Ok(Err(e)) => {
+                        tracing::debug!("Long-poll to {} failed: {}", url, e);
+                        tokio::time::sleep(retry_delay).await;
+                    }
+                    Err(_) => tracing::debug!("Long-poll to {} timed out", url),
+                }
+            }
+        }));
+    }
+}
+
+impl Drop for HttpConnection {
+    fn drop(&mut self) {
+        if let Some(poller) = self.poller.take() {
+            poller.abort();
+        }
+    }
+}
+
+#[async_trait]
+impl Connection for HttpConnection {
+    async fn connect(&mut self) -> Result<()> {
+        if self.is_connected() {
+            return Ok(());
+        }
+
+        self.config.validate()?;
+        if self.config.url.is_none() {
+            return Err(Error::configuration("No HTTP URL configured"));
+        }
+        self.headers = self.config.header_map()?;
+        self.headers.insert(
+            HeaderName::from_static(SESSION_HEADER),
+            HeaderValue::try_from(self.id.as_str()).expect("uuid is a valid header value"),
+        );
+
+        let mut connector = HttpConnector::new();
+        connector.set_connect_timeout(Some(self.config.timeouts.connect_timeout));
+        let client: HttpClient = Client::builder(TokioExecutor::new())
+            .pool_idle_timeout(self.config.timeouts.idle_timeout)
+            .build(connector);
+
+        if let Some(hold) = self.config.long_poll_timeout {
+            self.start_polling(client.clone(), hold);
+        }
+        self.client = Some(client);
+        self.state = ConnectionState::Connected;
+        Ok(())
+    }
+
+    async fn disconnect(&mut self) -> Result<()> {
+        if let Some(poller) = self.poller.take() {
+            poller.abort();
+        }
+        self.client = None;
+        self.state = ConnectionState::Disconnected;
+        Ok(())
+    }
+
+    fn is_connected(&self) -> bool {
+        matches!(self.state, ConnectionState::Connected)
+    }
+
+    fn is_closed(&self) -> bool {
+        matches!(self.state, ConnectionState::Disconnected)
+    }
+
+    fn connection_info(&self) -> HashMap<String, serde_json::Value> {
+        let mut info = HashMap::new();
+        info.insert("id".to_string(), self.id.clone().into());
+        info.insert("protocol".to_string(), "http".into());
+        info.insert("state".to_string(), format!("{:?}", self.state).into());
+
+        if let Some(ref url) = self.config.url {
+            info.insert("url".to_string(), url.clone().into());
+        }
+
+        info.insert("long_polling".to_string(), self.poller.is_some().into());
+        info.insert("messages_sent".to_string(), self.messages_sent.into());
+        info.insert("messages_received".to_string(), self.messages_received.into());
+
+        info
+    }
+
+    fn last_error(&self) -> Option<&Error> {
+        self.last_error.as_ref()
+    }
+}
+
+/// Perform one HTTP exchange, returning the body of a `200` reply and
+/// `None` for `204 No Content`
+async fn exchange(client: &HttpClient, request: hyper::Request<Full<Bytes>>) -> Result<Option<String>> {
+    let response = client.request(request).await
+        .map_err(|e| Error::connection(format!("HTTP request failed: {}", e)))?;
+
+    let status = response.status();
+    let body = response.into_body().collect().await
+        .map_err(|e| Error::transport(format!("Failed to read HTTP response: {}", e)))?
+        .to_bytes();
+
+    match status {
+        StatusCode::NO_CONTENT => Ok(None),
+        StatusCode::OK => String::from_utf8(body.to_vec())
+            .map(Some)
+            .map_err(|e| Error::transport(format!("HTTP response is not UTF-8: {}", e))),
+        status => Err(Error::transport(format!(
+            "HTTP status {}: {}", status, String::from_utf8_lossy(&body)
+        ))),
+    }
+}
+
+/// HTTP transport implementation (client side of one session)
+pub struct HttpTransport {
+    /// The underlying connection
+    connection: HttpConnection,
+}
+
+impl HttpTransport {
+    /// Connect to an HTTP endpoint
+    pub async fn connect(config: HttpConfig) -> Result<Self> {
+        let mut connection = HttpConnection::new(config);
+        connection.connect().await?;
+        Ok(Self { connection })
+    }
+
+    /// Connect to an HTTP URL with the default configuration
+    pub async fn client(url: impl Into<String>) -> Result<Self> {
+        Self::connect(HttpConfig::client(url)).await
+    }
+
+    /// The underlying connection
+    pub fn connection(&self) -> &HttpConnection {
+        &self.connection
+    }
+}
+
+#[async_trait]
+impl Transport for HttpTransport {
+    async fn send(&mut self, message: &str) -> Result<()> {
+        self.connection.post(message).await
+    }
+
+    async fn receive(&mut self) -> Result<String> {
+        self.connection.next_message().await
+    }
+
+    async fn close(&mut self) -> Result<()> {
+        self.connection.disconnect().await
+    }
+
+    fn is_bidirectional(&self) -> bool {
+        self.connection.config.long_poll_timeout.is_some()
+    }
+
+    fn metadata(&self) -> HashMap<String, serde_json::Value> {
+        let mut metadata = self.connection.connection_info();
+        metadata.insert(
+            "max_message_size".to_string(),
+            self.connection.config.connection_limits.max_message_size.into(),
+        );
+        metadata
+    }
+}
+
+/// Notifications queued for one long-polling client
+struct Session {
+    queue: Mutex<VecDeque<String>>,
+    ready: Notify,
+    last_poll: Mutex<Instant>,
+}
+
+impl Session {
+    fn new() -> Self {
+        Self {
+            queue: Mutex::new(VecDeque::new()),
+            ready: Notify::new(),
+            last_poll: Mutex::new(Instant::now()),
+        }
+    }
+
+    fn push(&self, message: String) {
+        self.queue.lock().push_back(message);
+        self.ready.notify_one();
+    }
+
+    /// Wait up to `hold` for notifications and take them all
+    async fn poll(&self, hold: Duration) -> Vec<String> {
+        let deadline = Instant::now() + hold;
+        loop {
+            *self.last_poll.lock() = Instant::now();
+            let drained: Vec<String> = self.queue.lock().drain(..).collect();
+            if !drained.is_empty() {
+                return drained;
+            }
+            if tokio::time::timeout_at(deadline, self.ready.notified()).await.is_err() {
+                return Vec::new();
+            }
+        }
+    }
+}
+
+/// Pushes notifications to long-polling clients of an [`HttpServer`]
+#[derive(Clone)]
+pub struct HttpNotifier {
+    sessions: Arc<Mutex<HashMap<String, Arc<Session>>>>,
+    /// Sessions not polled for this long are dropped
+    session_timeout: Duration,
+}
+
+impl HttpNotifier {
+    fn new(session_timeout: Duration) -> Self {
+        Self {
+            sessions: Arc::new(Mutex::new(HashMap::new())),
+            session_timeout,
+        }
+    }
+
+    /// Queue a notification for every session, returning how many it reached
+    pub fn notify(&self, notification: &JsonRpcRequest) -> Result<usize> {
+        let message = serde_json::to_string(notification)?;
+        let mut sessions = self.sessions.lock();
+        sessions.retain(|_, session| session.last_poll.lock().elapsed() < self.session_timeout);
+        for session in sessions.values() {
+            session.push(message.clone());
+        }
+        Ok(sessions.len())
+    }
+
+    /// Queue a notification for one session, returning whether it exists
+    pub fn notify_session(&self, session_id: &str, notification: &JsonRpcRequest) -> Result<bool> {
+        let message = serde_json::to_string(notification)?;
+        match self.sessions.lock().get(session_id) {
+            Some(session) => {
+                session.push(message);
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Number of sessions currently known
+    pub fn session_count(&self) -> usize {
+        self.sessions.lock().len()
+    }
+
+    fn session(&self, session_id: &str) -> Arc<Session> {
+        Arc::clone(self.sessions.lock()
+            .entry(session_id.to_string())
+            .or_insert_with(|| Arc::new(Session::new())))
+    }
+}
+
+/// State shared by the request handlers of an [`HttpServer`]
+struct ServerState {
+    router: Arc<MethodRouter>,
+    notifier: HttpNotifier,
+    headers: HeaderMap,
+    long_poll_timeout: Option<Duration>,
+}
+
+/// HTTP server answering JSON-RPC requests
+pub struct HttpServer {
+    /// Server configuration
+    config: HttpConfig,
+    /// Bound listener
+    listener: TcpListener,
+    /// Long-polling sessions
+    notifier: HttpNotifier,
+}
+
+impl HttpServer {
+    /// Bind to the configured address
+    pub async fn bind(config: HttpConfig) -> Result<Self> {
+        config.validate()?;
+        let bind_addr = config.bind_address
+            .ok_or_else(|| Error::configuration("No bind address configured for server mode"))?;
+
+        let listener = TcpListener::bind(bind_addr).await
+            .map_err(|e| Error::transport(format!("Failed to bind to {}: {}", bind_addr, e)))?;
+        tracing::info!("HTTP transport listening on {}", bind_addr);
+
+        let notifier = HttpNotifier::new(config.timeouts.idle_timeout);
+        Ok(Self { config, listener, notifier })
+    }
+
+    /// Address the server is listening on
+    pub fn local_addr(&self) -> Result<SocketAddr> {
+        Ok(self.listener.local_addr()?)
+    }
+
+    /// Handle for pushing notifications to long-polling clients
+    pub fn notifier(&self) -> HttpNotifier {
+        self.notifier.clone()
+    }
+
+    /// Serve requests with `router` until the listener fails
+    pub async fn serve(self, router: Arc<MethodRouter>) -> Result<()> {
+        let state = Arc::new(ServerState {
+            router,
+            notifier: self.notifier,
+            headers: self.config.header_map()?,
+            long_poll_timeout: self.config.long_poll_timeout,
+        });
+
+        let app = axum::Router::new()
+            .route(&self.config.path, post(handle_post).get(handle_poll))
+            .layer(DefaultBodyLimit::max(self.config.connection_limits.max_message_size))
+            .with_state(state);
+
+        axum::serve(self.listener, app).await
+            .map_err(|e| Error::transport(format!("HTTP server failed: {}", e)))
+    }
+}
+
+/// Dispatch a posted message, single or batch
+async fn handle_post(State(state): State<Arc<ServerState>>, headers: HeaderMap, body: String) -> Response {
+    let mut context = ServiceContext::new(Uuid::new_v4().to_string())
+        .with_metadata("transport", "http".into());
+    if let Some(session) = headers.get(SESSION_HEADER).and_then(|value| value.to_str().ok()) {
+        context = context.with_metadata("session_id", session.into());
+    }
+    if let Some(authorization) = headers.get(header::AUTHORIZATION).and_then(|value| value.to_str().ok()) {
+        context = context.with_metadata("authorization", authorization.into());
+    }
+    for key in [TRACEPARENT, TRACESTATE] {
+        if let Some(value) = headers.get(key).and_then(|value| value.to_str().ok()) {
+            context = context.with_metadata(key, value.into());
+        }
+    }
+
+    match state.router.dispatch_message(&body, &context).await {
+        Some(reply) => json_response(&state, reply),
+        None => (StatusCode::NO_CONTENT, state.headers.clone()).into_response(),
+    }
+}
+
+/// Hold a long-poll until notifications arrive for its session
+async fn handle_poll(State(state): State<Arc<ServerState>>, method: Method, headers: HeaderMap) -> Response {
+    let Some(hold) = state.long_poll_timeout else {
+        return (StatusCode::METHOD_NOT_ALLOWED, format!("{} requires long-polling", method)).into_response();
+    };
+    let Some(session_id) = headers.get(SESSION_HEADER).and_then(|value| value.to_str().ok()) else {
+        return (StatusCode::BAD_REQUEST, format!("Missing {} header", SESSION_HEADER)).into_response();
+    };
+
+    let notifications = state.notifier.session(session_id).poll(hold).await;
+    if notifications.is_empty() {
+        return (StatusCode::NO_CONTENT, state.headers.clone()).into_response();
+    }
+    json_response(&state, format!("[{}]", notifications.join(",")))
+}
+
+fn json_response(state: &ServerState, body: String) -> Response {
+    let mut response = (
+        StatusCode::OK,
+        [(header::CONTENT_TYPE, "application/json")],
+        body,
+    ).into_response();
+    response.headers_mut().extend(state.headers.clone());
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::traits::MethodHandler;
+    use crate::core::types::JsonRpcResponse;
+    use crate::protocol::JsonRpcClient;
+    use serde_json::{json, Value};
+
+    struct EchoHandler;
+
+    #[async_trait]
+    impl MethodHandler for EchoHandler {
+        async fn handle_method(&self, request: &JsonRpcRequest, context: &ServiceContext) -> Result<JsonRpcResponse> {
+            let session = context.metadata.get("session_id").cloned().unwrap_or(Value::Null);
+            let result = match request.method.as_str() {
+                "session" => session,
+                _ => request.params.clone().unwrap_or(Value::Null),
+            };
+            Ok(JsonRpcResponse::success(Value::Null, result))
+        }
+
+        fn supported_methods(&self) -> Vec<String> {
+            vec!["echo".to_string(), "session".to_string()]
+        }
+    }
+
+    async fn echo_server(config: HttpConfig) -> (String, HttpNotifier) {
+        let server = HttpServer::bind(config).await.unwrap();
+        let url = format!("http://{}/rpc", server.local_addr().unwrap());
+        let notifier = server.notifier();
+        let router = Arc::new(MethodRouter::new().with_handler(Arc::new(EchoHandler)).unwrap());
+        tokio::spawn(server.serve(router));
+        (url, notifier)
+    }
+
+    fn server_config() -> HttpConfig {
+        HttpConfig::server("127.0.0.1:0".parse().unwrap())
+            .with_path("/rpc")
+            .with_header("x-server", "jsonrpc-rust")
+    }
+
+    #[test]
+    fn test_http_config() {
+        assert!(HttpConfig::client("http://localhost:8080/rpc").validate().is_ok());
+        assert!(HttpConfig::client("ws://localhost:8080").validate().is_err());
+        assert!(HttpConfig::default().with_path("rpc").validate().is_err());
+        assert!(HttpConfig::default().with_header("bad header", "x").validate().is_err());
+        assert!(HttpConfig::default().with_long_polling(Duration::ZERO).validate().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_post_requests_and_batches() {
+        let (url, _) = echo_server(server_config()).await;
+        let config = HttpConfig::client(url.clone())
+            .with_header("x-client", "test")
+            .with_max_message_size(1024);
+        let client = JsonRpcClient::new(HttpTransport::connect(config).await.unwrap());
+
+        let echoed: Value = client.call("echo", json!({ "hello": "world" })).await.unwrap();
+        assert_eq!(echoed, json!({ "hello": "world" }));
+        client.notify("echo", json!(1)).await.unwrap();
+
+        let oversized = client.call::<_, Value>("echo", "x".repeat(2048)).await.unwrap_err();
+        assert!(matches!(oversized, Error::Validation { .. }));
+
+        // A batch is answered in one response carrying the server's headers
+        let mut transport = HttpTransport::client(url).await.unwrap();
+        let batch = json!([
+            { "jsonrpc": "2.0", "method": "echo", "params": 1, "id": 1 },
+            { "jsonrpc": "2.0", "method": "echo", "params": 2 },
+            { "jsonrpc": "2.0", "method": "echo", "params": 3, "id": 2 },
+        ]);
+        transport.send(&batch.to_string()).await.unwrap();
+        let replies: Vec<JsonRpcResponse> = serde_json::from_str(&transport.receive().await.unwrap()).unwrap();
+        assert_eq!(replies.iter().map(|r| r.result.clone().unwrap()).collect::<Vec<_>>(), vec![json!(1), json!(3)]);
+
+        client.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_long_polling_notifications() {
+        let (url, notifier) = echo_server(server_config().with_long_polling(Duration::from_millis(200))).await;
+        let config = HttpConfig::client(url).with_long_polling(Duration::from_millis(200));
+        let transport = HttpTransport::connect(config).await.unwrap();
+        let session_id = transport.connection().id().to_string();
+        assert!(transport.is_bidirectional());
+
+        let client = JsonRpcClient::new(transport);
+        let mut ticks = client.notifications("tick");
+        let seen: String = client.call("session", json!(null)).await.unwrap();
+        assert_eq!(seen, session_id);
+
+        // Wait for the poller to register its session
+        while notifier.session_count() == 0 {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        notifier.notify(&JsonRpcRequest::notification("tick", Some(json!(1)))).unwrap();
+        assert!(notifier.notify_session(&session_id, &JsonRpcRequest::notification("tick", Some(json!(2)))).unwrap());
+        assert!(!notifier.notify_session("unknown", &JsonRpcRequest::notification("tick", None)).unwrap());
+
+        let first = tokio::time::timeout(Duration::from_secs(1), ticks.recv()).await.unwrap().unwrap();
+        let second = tokio::time::timeout(Duration::from_secs(1), ticks.recv()).await.unwrap().unwrap();
+        assert_eq!((first.params, second.params), (Some(json!(1)), Some(json!(2))));
+
+        client.close().await.unwrap();
+    }
+}
@@ -0,0 +1,217 @@
+//! In-process transport
+//!
+//! [`InProcTransport`] connects two ends living in the same process through
+//! channels. Messages are handed over as they are: no framing, no codec and
+//! no socket, so tests and embedded setups run a client against a server
+//! without any I/O.
+//!
+//! [`InProcTransport::pair`] makes both ends of a connection, for instance
+//! to put a [`JsonRpcPeer`](crate::protocol::JsonRpcPeer) on each side.
+//! [`InProcTransport::connect`] serves the other end with a
+//! [`MethodRouter`] right away:
+//!
+//! ```rust
+//! use jsonrpc_rust::protocol::{JsonRpcClient, MethodRouter};
+//! use jsonrpc_rust::transport::InProcTransport;
+//!
+//! # async fn example(router: MethodRouter) -> jsonrpc_rust::Result<()> {
+//! let client = JsonRpcClient::new(InProcTransport::connect(router));
+//! let pong: String = client.call("ping", serde_json::json!([])).await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! With [`InProcConfig::roundtrip`] set, every message is encoded in the
+//! given format and decoded back on its way, so messages a wire transport
+//! in that format could not carry fail here too.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde_json::Value;
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+use crate::core::error::{Error, Result};
+use crate::core::serialization::SerializationFormat;
+use crate::core::session::Session;
+use crate::core::traits::Transport;
+use crate::core::types::ServiceContext;
+use crate::protocol::MethodRouter;
+
+/// In-process transport configuration
+#[derive(Debug, Clone, Default)]
+pub struct InProcConfig {
+    /// Encode and decode every message in this format on the way, for the
+    /// fidelity of a wire transport; `None` hands messages over untouched
+    pub roundtrip: Option<SerializationFormat>,
+}
+
+impl InProcConfig {
+    /// Round-trip every message through `format`
+    pub fn with_roundtrip(mut self, format: SerializationFormat) -> Self {
+        self.roundtrip = Some(format);
+        self
+    }
+}
+
+/// One end of an in-process connection
+pub struct InProcTransport {
+    outgoing: mpsc::UnboundedSender<String>,
+    incoming: mpsc::UnboundedReceiver<String>,
+    config: InProcConfig,
+}
+
+impl InProcTransport {
+    /// Both ends of a new connection
+    pub fn pair() -> (Self, Self) {
+        Self::pair_with_config(InProcConfig::default())
+    }
+
+    /// Both ends of a new connection, with a custom configuration
+    pub fn pair_with_config(config: InProcConfig) -> (Self, Self) {
+        let (a_tx, b_rx) = mpsc::unbounded_channel();
+        let (b_tx, a_rx) = mpsc::unbounded_channel();
+        (
+            Self { outgoing: a_tx, incoming: a_rx, config: config.clone() },
+            Self { outgoing: b_tx, incoming: b_rx, config },
+        )
+    }
+
+    /// A connection whose other end is answered by `router`
+    pub fn connect(router: MethodRouter) -> Self {
+        Self::connect_with_config(router, InProcConfig::default())
+    }
+
+    /// A connection whose other end is answered by `router`, with a custom
+    /// configuration
+    pub fn connect_with_config(router: MethodRouter, config: InProcConfig) -> Self {
+        let (client, server) = Self::pair_with_config(config);
+        tokio::spawn(serve(server, Arc::new(router)));
+        client
+    }
+}
+
+/// Put a message through the round trip of `format`, if any
+fn roundtrip(format: Option<SerializationFormat>, message: &str) -> Result<String> {
+    let Some(format) = format else {
+        return Ok(message.to_string());
+    };
+    let value: Value = serde_json::from_str(message)?;
+    let decoded: Value = format.decode(&format.encode(&value)?)?;
+    Ok(decoded.to_string())
+}
+
+#[async_trait]
+impl Transport for InProcTransport {
+    async fn send(&mut self, message: &str) -> Result<()> {
+        let message = roundtrip(self.config.roundtrip, message)?;
+        self.outgoing.send(message).map_err(|_| Error::transport("In-process connection closed"))
+    }
+
+    async fn receive(&mut self) -> Result<String> {
+        self.incoming.recv().await.ok_or_else(|| Error::transport("In-process connection closed"))
+    }
+
+    async fn close(&mut self) -> Result<()> {
+        self.incoming.close();
+        Ok(())
+    }
+
+    fn metadata(&self) -> HashMap<String, Value> {
+        let mut metadata = HashMap::new();
+        metadata.insert("transport".to_string(), Value::String("inproc".to_string()));
+        if let Some(format) = self.config.roundtrip {
+            metadata.insert("roundtrip".to_string(), Value::String(format.name().to_string()));
+        }
+        metadata
+    }
+}
+
+impl std::fmt::Debug for InProcTransport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("InProcTransport")
+            .field("closed", &self.outgoing.is_closed())
+            .field("config", &self.config)
+            .finish()
+    }
+}
+
+/// Answer the requests arriving on `transport`, concurrently, until the
+/// other end goes away
+async fn serve(mut transport: InProcTransport, router: Arc<MethodRouter>) {
+    let peer = Value::String(Uuid::new_v4().to_string());
+    let session = Session::new(peer.clone());
+    while let Ok(message) = transport.receive().await {
+        let router = Arc::clone(&router);
+        let outgoing = transport.outgoing.clone();
+        let format = transport.config.roundtrip;
+        let context = ServiceContext::new(Uuid::new_v4().to_string())
+            .with_metadata("peer", peer.clone())
+            .with_session(session.clone());
+        tokio::spawn(async move {
+            let Some(answer) = router.dispatch_message(&message, &context).await else {
+                return;
+            };
+            match roundtrip(format, &answer) {
+                Ok(answer) => {
+                    let _ = outgoing.send(answer);
+                }
+                Err(e) => tracing::warn!("Dropping response that does not survive the round trip: {}", e),
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::traits::MethodHandler;
+    use crate::core::types::{JsonRpcRequest, JsonRpcResponse};
+    use crate::protocol::JsonRpcClient;
+    use serde_json::json;
+
+    struct EchoHandler;
+
+    #[async_trait]
+    impl MethodHandler for EchoHandler {
+        async fn handle_method(&self, request: &JsonRpcRequest, _context: &ServiceContext) -> Result<JsonRpcResponse> {
+            let id = request.id.clone().unwrap_or(Value::Null);
+            Ok(JsonRpcResponse::success(id, request.params.clone().unwrap_or(Value::Null)))
+        }
+
+        fn supported_methods(&self) -> Vec<String> {
+            vec!["echo".to_string()]
+        }
+    }
+
+    #[tokio::test]
+    async fn test_client_calls_router_in_process() {
+        let mut router = MethodRouter::new();
+        router.register_handler(Arc::new(EchoHandler)).unwrap();
+
+        let config = InProcConfig::default().with_roundtrip(SerializationFormat::Json);
+        let client = JsonRpcClient::new(InProcTransport::connect_with_config(router, config));
+        let echoed: Value = client.call("echo", json!({"n": 1})).await.unwrap();
+        assert_eq!(echoed, json!({"n": 1}));
+        assert!(client.call::<_, Value>("missing", json!([])).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_pair_and_roundtrip() {
+        let (mut a, mut b) = InProcTransport::pair();
+        a.send("not even json").await.unwrap();
+        assert_eq!(b.receive().await.unwrap(), "not even json");
+
+        let (mut a, mut b) = InProcTransport::pair_with_config(
+            InProcConfig::default().with_roundtrip(SerializationFormat::Json),
+        );
+        assert!(a.send("not even json").await.is_err());
+        a.send(r#"{ "jsonrpc": "2.0", "method": "ping" }"#).await.unwrap();
+        assert_eq!(b.receive().await.unwrap(), r#"{"jsonrpc":"2.0","method":"ping"}"#);
+
+        drop(b);
+        assert!(a.send("{}").await.is_err());
+    }
+}
@@ -2,6 +2,10 @@
 //! 
 //! This module provides mock implementations of transport and connection
 //! traits for unit testing, integration testing, and fuzzing.
+//!
+//! A [`MockScenario`] scripts a whole conversation: the requests expected,
+//! their answers, latencies and dropped connections, so client retry and
+//! reconnection logic can be tested deterministically.
 
 use std::collections::{HashMap, VecDeque};
 use std::sync::Arc;
@@ -12,8 +16,9 @@ use tokio::sync::{mpsc, RwLock, Mutex};
 use tokio::time::sleep;
 use uuid::Uuid;
 
-use crate::core::error::{Error, Result};
+use crate::core::error::{Error, JsonRpcError, Result};
 use crate::core::traits::{Transport, Connection};
+use crate::core::types::{JsonRpcRequest, JsonRpcResponse};
 use super::abstraction::{
     TransportLayer, ConnectionManager, MessageCodec, TransportConfig,
     JsonRpcMessage, TransportStats, ConnectionInfo, ConnectionState,
@@ -37,6 +42,8 @@ pub struct MockTransport {
     stats: Arc<RwLock<TransportStats>>,
     /// Mock behaviors
     behaviors: Arc<RwLock<MockBehaviors>>,
+    /// Scenario played instead of the queues, if any
+    scripted: Option<ScriptedConnection>,
 }
 
 /// Mock transport configuration
@@ -512,8 +519,23 @@ impl MockTransport {
             error_injection,
             stats,
             behaviors,
+            scripted: None,
         })
     }
+
+    /// Create a mock transport playing a scenario
+    ///
+    /// Requests sent are checked against the scenario and answered as it
+    /// says; `receive` waits for the next answer.
+    pub async fn scripted(scenario: &MockScenario) -> Result<Self> {
+        Self::playing(Arc::clone(&scenario.script)).await
+    }
+
+    async fn playing(script: Arc<parking_lot::Mutex<Script>>) -> Result<Self> {
+        let mut transport = Self::new(MockConfig { network_latency: Duration::ZERO, ..MockConfig::default() }).await?;
+        transport.scripted = Some(ScriptedConnection::new(script));
+        Ok(transport)
+    }
     
     /// Create a simple mock transport with default configuration
     pub async fn simple() -> Result<Self> {
@@ -672,16 +694,25 @@ impl TransportLayer for MockTransport {
 #[async_trait]
 impl Transport for MockTransport {
     async fn send(&mut self, message: &str) -> Result<()> {
+        if let Some(ref mut scripted) = self.scripted {
+            return scripted.send(message);
+        }
         let json_message = JsonRpcMessage::from_json(message)?;
         self.send_message(json_message, "mock://default").await
     }
     
     async fn receive(&mut self) -> Result<String> {
+        if let Some(ref mut scripted) = self.scripted {
+            return scripted.receive().await;
+        }
         let message = self.receive_message().await?;
         message.to_json()
     }
     
     async fn close(&mut self) -> Result<()> {
+        if let Some(ref mut scripted) = self.scripted {
+            scripted.dropped = true;
+        }
         self.shutdown().await
     }
     
@@ -700,6 +731,340 @@ impl Transport for MockTransport {
     }
 }
 
+/// Computes the result of a scripted reply from the request
+type ReplyFn = Arc<dyn Fn(&JsonRpcRequest) -> serde_json::Value + Send + Sync>;
+
+/// Decides whether a scripted step applies to a request
+type MatchFn = Arc<dyn Fn(&JsonRpcRequest) -> bool + Send + Sync>;
+
+/// What a scripted step does with the request it matches
+#[derive(Clone)]
+enum Reply {
+    Result(ReplyFn),
+    Error(JsonRpcError),
+    Disconnect,
+    Ignore,
+}
+
+/// A request a scenario expects, or answers whenever it comes
+#[derive(Clone)]
+struct Step {
+    method: String,
+    matcher: Option<MatchFn>,
+    reply: Reply,
+    delay: Duration,
+}
+
+impl Step {
+    fn matches(&self, request: &JsonRpcRequest) -> bool {
+        self.method == request.method && self.matcher.as_ref().is_none_or(|matcher| matcher(request))
+    }
+}
+
+/// State shared by a scenario and the transports playing it
+#[derive(Default)]
+struct Script {
+    /// Requests still expected, in order
+    expected: VecDeque<Step>,
+    /// Requests answered whenever they come
+    rules: Vec<Step>,
+    /// Messages that matched no step
+    unexpected: Vec<String>,
+    /// Transports created so far
+    connections: usize,
+}
+
+/// Scripted conversation played by [`MockTransport::scripted`]
+///
+/// Expectations are met in order: each request sent must match the next
+/// one, or else a rule added with [`on`](Self::on), which answers as often
+/// as needed. Anything else is recorded as unexpected and answered with a
+/// method-not-found error. Transports created from the same scenario play
+/// it in turn, so a reconnecting client picks up where the dropped
+/// connection left off.
+///
+/// Dropping the scenario panics if expectations are left or unexpected
+/// messages were sent; [`verify`](Self::verify) checks the same without
+/// panicking.
+///
+/// ```rust
+/// use jsonrpc_rust::transport::{MockScenario, MockTransport};
+/// use std::time::Duration;
+/// use serde_json::json;
+///
+/// # async fn example() -> jsonrpc_rust::Result<()> {
+/// let scenario = MockScenario::new()
+///     .expect("login").with_params(json!({"user": "ada"})).respond(json!(true))
+///     .expect("fetch").after(Duration::from_millis(5)).disconnect()
+///     .expect("fetch").respond(json!([1, 2, 3]))
+///     .on("ping").respond(json!("pong"));
+///
+/// let transport = MockTransport::scripted(&scenario).await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct MockScenario {
+    script: Arc<parking_lot::Mutex<Script>>,
+}
+
+impl MockScenario {
+    /// Create an empty scenario
+    pub fn new() -> Self {
+        Self { script: Arc::new(parking_lot::Mutex::new(Script::default())) }
+    }
+
+    /// Expect a request for `method` next
+    pub fn expect(self, method: impl Into<String>) -> StepBuilder {
+        StepBuilder::new(self, method.into(), false)
+    }
+
+    /// Answer requests for `method` whenever they come
+    pub fn on(self, method: impl Into<String>) -> StepBuilder {
+        StepBuilder::new(self, method.into(), true)
+    }
+
+    /// A connector creating transports that play this scenario, for
+    /// [`ReconnectingConnection`](super::ReconnectingConnection)
+    pub fn connector(&self) -> super::reconnect::TransportConnector {
+        let script = Arc::clone(&self.script);
+        Arc::new(move || {
+            let script = Arc::clone(&script);
+            Box::pin(async move {
+                let transport = MockTransport::playing(script).await?;
+                Ok(Box::new(transport) as Box<dyn Transport>)
+            })
+        })
+    }
+
+    /// Number of expectations not met yet
+    pub fn remaining(&self) -> usize {
+        self.script.lock().expected.len()
+    }
+
+    /// Number of transports created from this scenario
+    pub fn connections(&self) -> usize {
+        self.script.lock().connections
+    }
+
+    /// Check that every expectation was met and nothing unexpected was sent
+    pub fn verify(&self) -> Result<()> {
+        let script = self.script.lock();
+        if let Some(message) = script.unexpected.first() {
+            return Err(Error::transport(format!(
+                "{} unexpected message(s), first: {}", script.unexpected.len(), message,
+            )));
+        }
+        if !script.expected.is_empty() {
+            let methods: Vec<_> = script.expected.iter().map(|step| step.method.as_str()).collect();
+            return Err(Error::transport(format!("Expected requests not sent: {}", methods.join(", "))));
+        }
+        Ok(())
+    }
+
+    fn push(self, step: Step, repeat: bool) -> Self {
+        {
+            let mut script = self.script.lock();
+            if repeat {
+                script.rules.push(step);
+            } else {
+                script.expected.push_back(step);
+            }
+        }
+        self
+    }
+}
+
+impl Default for MockScenario {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for MockScenario {
+    fn drop(&mut self) {
+        if std::thread::panicking() {
+            return;
+        }
+        if let Err(e) = self.verify() {
+            panic!("Mock scenario not played out: {}", e);
+        }
+    }
+}
+
+impl std::fmt::Debug for MockScenario {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let script = self.script.lock();
+        f.debug_struct("MockScenario")
+            .field("expected", &script.expected.len())
+            .field("rules", &script.rules.len())
+            .field("unexpected", &script.unexpected.len())
+            .field("connections", &script.connections)
+            .finish()
+    }
+}
+
+/// A step of a [`MockScenario`] being written; ends with its reply
+pub struct StepBuilder {
+    scenario: MockScenario,
+    method: String,
+    matcher: Option<MatchFn>,
+    delay: Duration,
+    repeat: bool,
+}
+
+impl StepBuilder {
+    fn new(scenario: MockScenario, method: String, repeat: bool) -> Self {
+        Self { scenario, method, matcher: None, delay: Duration::ZERO, repeat }
+    }
+
+    /// Only match requests with exactly these params
+    pub fn with_params(self, params: serde_json::Value) -> Self {
+        self.when(move |request| request.params.as_ref() == Some(&params))
+    }
+
+    /// Only match requests for which `matcher` holds
+    pub fn when<F>(mut self, matcher: F) -> Self
+    where
+        F: Fn(&JsonRpcRequest) -> bool + Send + Sync + 'static,
+    {
+        self.matcher = Some(Arc::new(matcher));
+        self
+    }
+
+    /// Wait this long before replying
+    pub fn after(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    /// Answer with this result
+    pub fn respond(self, result: serde_json::Value) -> MockScenario {
+        self.respond_with(move |_| result.clone())
+    }
+
+    /// Answer with the result computed from the request
+    pub fn respond_with<F>(self, reply: F) -> MockScenario
+    where
+        F: Fn(&JsonRpcRequest) -> serde_json::Value + Send + Sync + 'static,
+    {
+        self.reply(Reply::Result(Arc::new(reply)))
+    }
+
+    /// Answer with this error
+    pub fn respond_error(self, error: JsonRpcError) -> MockScenario {
+        self.reply(Reply::Error(error))
+    }
+
+    /// Drop the connection instead of answering
+    pub fn disconnect(self) -> MockScenario {
+        self.reply(Reply::Disconnect)
+    }
+
+    /// Never answer, as for a notification or a lost response
+    pub fn ignore(self) -> MockScenario {
+        self.reply(Reply::Ignore)
+    }
+
+    fn reply(self, reply: Reply) -> MockScenario {
+        let step = Step {
+            method: self.method,
+            matcher: self.matcher,
+            reply,
+            delay: self.delay,
+        };
+        self.scenario.push(step, self.repeat)
+    }
+}
+
+/// A transport's side of a scenario
+struct ScriptedConnection {
+    script: Arc<parking_lot::Mutex<Script>>,
+    replies_tx: mpsc::UnboundedSender<Option<String>>,
+    /// Replies in the order they are due; `None` drops the connection
+    replies_rx: mpsc::UnboundedReceiver<Option<String>>,
+    dropped: bool,
+}
+
+impl ScriptedConnection {
+    fn new(script: Arc<parking_lot::Mutex<Script>>) -> Self {
+        script.lock().connections += 1;
+        let (replies_tx, replies_rx) = mpsc::unbounded_channel();
+        Self { script, replies_tx, replies_rx, dropped: false }
+    }
+
+    fn send(&mut self, message: &str) -> Result<()> {
+        if self.dropped {
+            return Err(Error::transport("Mock connection dropped"));
+        }
+        let value: serde_json::Value = serde_json::from_str(message)?;
+        let items = match value {
+            serde_json::Value::Array(items) => items,
+            single => vec![single],
+        };
+        for item in items {
+            match serde_json::from_value::<JsonRpcRequest>(item.clone()) {
+                Ok(request) => self.play(request),
+                Err(_) => self.script.lock().unexpected.push(item.to_string()),
+            }
+        }
+        Ok(())
+    }
+
+    /// Find the step for a request and schedule its reply
+    fn play(&mut self, request: JsonRpcRequest) {
+        let step = {
+            let mut script = self.script.lock();
+            if script.expected.front().is_some_and(|step| step.matches(&request)) {
+                script.expected.pop_front()
+            } else if let Some(rule) = script.rules.iter().find(|rule| rule.matches(&request)) {
+                Some(rule.clone())
+            } else {
+                script.unexpected.push(serde_json::to_string(&request).unwrap_or_default());
+                None
+            }
+        };
+
+        let reply = match step {
+            Some(ref step) => step.reply.clone(),
+            None => Reply::Error(JsonRpcError::method_not_found(&request.method)),
+        };
+        let id = request.id.clone();
+        let answer = match reply {
+            Reply::Ignore => return,
+            Reply::Disconnect => None,
+            // Notifications are never answered
+            _ if id.is_none() => return,
+            Reply::Result(result) => Some(JsonRpcResponse::success(id.unwrap_or_default(), result(&request))),
+            Reply::Error(error) => Some(JsonRpcResponse::error(id.unwrap_or_default(), error)),
+        };
+        let answer = answer.map(|response| serde_json::to_string(&response).unwrap_or_default());
+
+        let delay = step.map_or(Duration::ZERO, |step| step.delay);
+        if delay.is_zero() {
+            let _ = self.replies_tx.send(answer);
+        } else {
+            let replies = self.replies_tx.clone();
+            tokio::spawn(async move {
+                sleep(delay).await;
+                let _ = replies.send(answer);
+            });
+        }
+    }
+
+    async fn receive(&mut self) -> Result<String> {
+        if self.dropped {
+            return Err(Error::transport("Mock connection dropped"));
+        }
+        match self.replies_rx.recv().await {
+            Some(Some(answer)) => Ok(answer),
+            _ => {
+                self.dropped = true;
+                Err(Error::transport("Mock connection dropped"))
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -800,4 +1165,66 @@ mod tests {
         let connections = manager.list_connections().await;
         assert_eq!(connections.len(), 0);
     }
-} 
\ No newline at end of file
+    
+    #[tokio::test]
+    async fn test_scenario_answers_in_order() {
+        use crate::protocol::JsonRpcClient;
+
+        let scenario = MockScenario::new()
+            .expect("login").with_params(json!({"user": "ada"})).respond(json!(true))
+            .expect("fetch").after(Duration::from_millis(5)).respond_with(|request| request.params.clone().unwrap())
+            .expect("fail").respond_error(JsonRpcError::invalid_params("no"))
+            .on("ping").respond(json!("pong"));
+        let client = JsonRpcClient::new(MockTransport::scripted(&scenario).await.unwrap());
+
+        let pong: String = client.call("ping", json!([])).await.unwrap();
+        assert_eq!(pong, "pong");
+        assert!(client.call::<_, bool>("login", json!({"user": "ada"})).await.unwrap());
+        assert_eq!(client.call::<_, serde_json::Value>("fetch", json!([7])).await.unwrap(), json!([7]));
+        assert!(client.call::<_, serde_json::Value>("fail", json!([])).await.is_err());
+        let pong: String = client.call("ping", json!([])).await.unwrap();
+        assert_eq!(pong, "pong");
+
+        assert_eq!(scenario.remaining(), 0);
+        scenario.verify().unwrap();
+    }
+    
+    #[tokio::test]
+    async fn test_scenario_drives_reconnection() {
+        use crate::protocol::JsonRpcClient;
+        use super::super::reconnect::{ReconnectConfig, ReconnectingConnection, ReplayPolicy};
+        use crate::core::error::RetryPolicy;
+
+        // The first connection drops in the middle of the call, which is
+        // replayed on the second
+        let scenario = MockScenario::new()
+            .expect("fetch").after(Duration::from_millis(5)).disconnect()
+            .expect("fetch").respond(json!([1, 2, 3]));
+        let connect = scenario.connector();
+        let config = ReconnectConfig::default()
+            .with_retry_policy(RetryPolicy::new(3, Duration::from_millis(5)).with_jitter_factor(0.0))
+            .with_replay_policy(ReplayPolicy::Replay);
+        let connection = ReconnectingConnection::connect_with(move || connect(), config).await.unwrap();
+        let client = JsonRpcClient::new(connection);
+
+        let items: Vec<u32> = client.call("fetch", json!([])).await.unwrap();
+        assert_eq!(items, vec![1, 2, 3]);
+        assert_eq!(scenario.connections(), 2);
+    }
+    
+    #[tokio::test]
+    async fn test_scenario_reports_unmet_expectations() {
+        let scenario = MockScenario::new()
+            .expect("first").respond(json!(1))
+            .expect("second").ignore();
+        let mut transport = MockTransport::scripted(&scenario).await.unwrap();
+
+        transport.send(r#"{"jsonrpc":"2.0","method":"other","id":1}"#).await.unwrap();
+        let answer: serde_json::Value = serde_json::from_str(&transport.receive().await.unwrap()).unwrap();
+        assert_eq!(answer["error"]["code"], json!(-32601));
+        assert!(scenario.verify().unwrap_err().to_string().contains("unexpected"));
+
+        let dropped = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| drop(scenario)));
+        assert!(dropped.is_err());
+    }
+}
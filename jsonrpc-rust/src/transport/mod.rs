@@ -1,8 +1,8 @@
 //! Transport layer implementations for JSON-RPC framework
 //! 
 //! This module provides concrete implementations of transport protocols
-//! for JSON-RPC communication, including TCP, WebSocket, HTTP, and mock
-//! transports for testing.
+//! for JSON-RPC communication, including TCP, WebSocket, HTTP, stdio,
+//! in-process and mock transports for testing.
 //! 
 //! # Architecture
 //! 
@@ -12,7 +12,7 @@
 //! - **Abstraction Layer**: Common interfaces and utilities
 //! - **Protocol Implementations**: TCP, WebSocket, HTTP transports
 //! - **Registry**: Dynamic transport selection and management
-//! - **Testing**: Mock implementations for unit tests
+//! - **Testing**: Mock and in-process implementations for unit tests
 //! 
 //! # Example
 //! 
@@ -40,10 +40,16 @@ pub mod abstraction;
 // Protocol implementations
 pub mod tcp;
 pub mod mock;
+pub mod inproc;
 
 // Transport registry
 pub mod registry;
 
+// Client connection management
+pub mod reconnect;
+pub mod negotiated;
+pub mod compression;
+
 // Optional protocol implementations (feature-gated)
 #[cfg(feature = "websocket")]
 pub mod websocket;
@@ -51,11 +57,21 @@ pub mod websocket;
 #[cfg(feature = "http")]
 pub mod http;
 
+#[cfg(feature = "stdio")]
+pub mod stdio;
+
+#[cfg(feature = "tls")]
+pub mod tls;
+
 // Re-export commonly used types
 pub use abstraction::*;
 pub use tcp::*;
 pub use mock::*;
+pub use inproc::*;
 pub use registry::*;
+pub use reconnect::*;
+pub use negotiated::*;
+pub use compression::*;
 
 #[cfg(feature = "websocket")]
 pub use websocket::*;
@@ -63,6 +79,12 @@ pub use websocket::*;
 #[cfg(feature = "http")]
 pub use http::*;
 
+#[cfg(feature = "stdio")]
+pub use stdio::*;
+
+#[cfg(feature = "tls")]
+pub use tls::*;
+
 /// Prelude module for convenient imports
 pub mod prelude {
     //! Common imports for transport layer usage
@@ -70,13 +92,19 @@ pub mod prelude {
     // Core transport traits
     pub use super::abstraction::{
         TransportLayer, ConnectionManager, MessageCodec,
-        TransportConfig, ConnectionInfo, TransportError
+        TransportConfig, ConnectionInfo, TransportError,
+        RateLimit, RateLimitConfig, RateLimitKey
     };
     
     // Concrete implementations
     pub use super::tcp::{TcpTransport, TcpConnection, TcpConfig};
     pub use super::mock::{MockTransport, MockConnection, MockConfig};
-    pub use super::registry::{TransportRegistry, TransportType, RegistryConfig};
+    pub use super::inproc::{InProcTransport, InProcConfig};
+    pub use super::registry::{TransportRegistry, TransportType, RegistryConfig, SchemeHandler, TransportListener};
+    pub use super::reconnect::{ReconnectingConnection, ReconnectConfig, ReplayPolicy, ConnectionEvent};
+    pub use super::negotiated::{NegotiatedTransport, NegotiatedConfig};
+    pub use super::compression::{CompressionAlgorithm, CompressionConfig};
+    pub use crate::core::serialization::SerializationFormat;
     
     // Core traits from parent modules
     pub use crate::core::traits::{Transport, Connection, Message};
@@ -89,6 +117,12 @@ pub mod prelude {
     
     #[cfg(feature = "http")]
     pub use super::http::{HttpTransport, HttpConnection, HttpConfig};
+    
+    #[cfg(feature = "stdio")]
+    pub use super::stdio::{StdioTransport, StdioConfig};
+    
+    #[cfg(feature = "tls")]
+    pub use super::tls::TlsConfig;
 }
 
 /// Transport layer version information
@@ -103,6 +137,8 @@ pub enum Protocol {
     WebSocket,
     /// HTTP POST transport
     Http,
+    /// Standard input/output transport
+    Stdio,
     /// Mock transport for testing
     Mock,
 }
@@ -113,6 +149,7 @@ impl std::fmt::Display for Protocol {
             Protocol::Tcp => write!(f, "tcp"),
             Protocol::WebSocket => write!(f, "websocket"),
             Protocol::Http => write!(f, "http"),
+            Protocol::Stdio => write!(f, "stdio"),
             Protocol::Mock => write!(f, "mock"),
         }
     }
@@ -126,6 +163,7 @@ impl std::str::FromStr for Protocol {
             "tcp" => Ok(Protocol::Tcp),
             "websocket" | "ws" => Ok(Protocol::WebSocket),
             "http" | "https" => Ok(Protocol::Http),
+            "stdio" => Ok(Protocol::Stdio),
             "mock" => Ok(Protocol::Mock),
             _ => Err(format!("Unknown protocol: {}", s)),
         }
@@ -141,6 +179,7 @@ mod tests {
         assert_eq!(Protocol::Tcp.to_string(), "tcp");
         assert_eq!(Protocol::WebSocket.to_string(), "websocket");
         assert_eq!(Protocol::Http.to_string(), "http");
+        assert_eq!(Protocol::Stdio.to_string(), "stdio");
         assert_eq!(Protocol::Mock.to_string(), "mock");
     }
     
@@ -151,6 +190,7 @@ mod tests {
         assert_eq!("ws".parse::<Protocol>().unwrap(), Protocol::WebSocket);
         assert_eq!("http".parse::<Protocol>().unwrap(), Protocol::Http);
         assert_eq!("https".parse::<Protocol>().unwrap(), Protocol::Http);
+        assert_eq!("stdio".parse::<Protocol>().unwrap(), Protocol::Stdio);
         assert_eq!("mock".parse::<Protocol>().unwrap(), Protocol::Mock);
         
         assert!("unknown".parse::<Protocol>().is_err());
@@ -0,0 +1,527 @@
+//! Byte stream transport with a negotiated wire format
+//!
+//! [`NegotiatedTransport`] carries JSON-RPC over any byte stream, such as
+//! a TCP socket or a pipe, as length-prefixed frames. When the connection
+//! is set up the two ends agree on a [`SerializationFormat`] and,
+//! optionally, a [`CompressionAlgorithm`]:
+//!
+//! 1. The client sends a JSON `rpc.negotiate` request listing the formats
+//!    and compression algorithms it can use, most preferred first.
+//! 2. The server answers with the first of each it also accepts, or with
+//!    an error response when no format is shared.
+//!
+//! Every later frame uses the agreed format. With compression on, each
+//! frame starts with a flag byte saying whether the rest is compressed.
+//! The [`Transport`] interface stays JSON text, so clients and routers
+//! work unchanged; messages are converted at the frame boundary.
+
+use std::collections::HashMap;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use crate::core::error::{Error, JsonRpcError, Result};
+use crate::core::serialization::SerializationFormat;
+use crate::core::traits::Transport;
+use crate::core::types::{JsonRpcRequest, JsonRpcResponse};
+use super::abstraction::{TransportConfig, TimeoutConfig, RetryConfig, ConnectionLimits, RateLimitConfig};
+use super::compression::{CompressionAlgorithm, CompressionConfig, CompressionStats};
+
+/// Method of the handshake request
+pub const NEGOTIATE_METHOD: &str = "rpc.negotiate";
+
+/// Flag byte of an uncompressed frame when compression is on
+const FRAME_RAW: u8 = 0;
+/// Flag byte of a compressed frame
+const FRAME_COMPRESSED: u8 = 1;
+
+/// Negotiated transport configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NegotiatedConfig {
+    /// Formats to offer (client) or accept (server), most preferred first
+    pub formats: Vec<SerializationFormat>,
+    /// Compression to offer or accept
+    pub compression: CompressionConfig,
+    /// Timeout configuration; `connect_timeout` bounds the handshake
+    pub timeouts: TimeoutConfig,
+    /// Retry configuration
+    pub retry_config: RetryConfig,
+    /// Connection limits; `max_message_size` caps frames in both
+    /// directions, before and after compression
+    pub connection_limits: ConnectionLimits,
+    /// Rate limits a server applies to calls on accepted connections
+    pub rate_limit: Option<RateLimitConfig>,
+}
+
+impl Default for NegotiatedConfig {
+    fn default() -> Self {
+        Self {
+            formats: SerializationFormat::supported(),
+            compression: CompressionConfig::default(),
+            timeouts: TimeoutConfig::default(),
+            retry_config: RetryConfig::default(),
+            connection_limits: ConnectionLimits::default(),
+            rate_limit: None,
+        }
+    }
+}
+
+impl NegotiatedConfig {
+    /// Set the formats, most preferred first
+    pub fn with_formats(mut self, formats: impl IntoIterator<Item = SerializationFormat>) -> Self {
+        self.formats = formats.into_iter().collect();
+        self
+    }
+
+    /// Set the compression configuration
+    pub fn with_compression(mut self, compression: CompressionConfig) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// Set the maximum message size
+    pub fn with_max_message_size(mut self, max_message_size: usize) -> Self {
+        self.connection_limits.max_message_size = max_message_size;
+        self
+    }
+
+    /// Rate limit calls on accepted connections
+    pub fn with_rate_limit(mut self, rate_limit: RateLimitConfig) -> Self {
+        self.rate_limit = Some(rate_limit);
+        self
+    }
+}
+
+impl TransportConfig for NegotiatedConfig {
+    fn validate(&self) -> Result<()> {
+        if !self.formats.iter().any(|format| format.is_supported()) {
+            return Err(Error::configuration("No supported serialization format configured"));
+        }
+
+        if self.connection_limits.max_message_size == 0 {
+            return Err(Error::configuration("Max message size cannot be zero"));
+        }
+
+        if let Some(rate_limit) = &self.rate_limit {
+            rate_limit.validate()?;
+        }
+        self.compression.validate()
+    }
+
+    fn timeouts(&self) -> TimeoutConfig {
+        self.timeouts.clone()
+    }
+
+    fn retry_config(&self) -> RetryConfig {
+        self.retry_config.clone()
+    }
+
+    fn connection_limits(&self) -> ConnectionLimits {
+        self.connection_limits.clone()
+    }
+
+    fn compression(&self) -> CompressionConfig {
+        self.compression.clone()
+    }
+
+    fn rate_limit(&self) -> Option<RateLimitConfig> {
+        self.rate_limit.clone()
+    }
+}
+
+/// Transport over a byte stream using the format agreed at connection setup
+pub struct NegotiatedTransport<S> {
+    stream: S,
+    format: SerializationFormat,
+    compression: CompressionAlgorithm,
+    /// Smallest frame worth compressing
+    threshold: usize,
+    level: Option<i32>,
+    stats: CompressionStats,
+    /// Bytes read but not yet framed into a message
+    buffer: Vec<u8>,
+    max_message_size: usize,
+    closed: bool,
+    messages_sent: u64,
+    messages_received: u64,
+}
+
+impl<S> NegotiatedTransport<S>
+where
+    S: AsyncRead + AsyncWrite + Send + Sync + Unpin + 'static,
+{
+    /// Client side: offer `preferred` formats and use the one the server picks
+    pub async fn connect(stream: S, preferred: &[SerializationFormat]) -> Result<Self> {
+        Self::connect_with(stream, NegotiatedConfig::default().with_formats(preferred.iter().copied())).await
+    }
+
+    /// Client side with full configuration
+    pub async fn connect_with(stream: S, config: NegotiatedConfig) -> Result<Self> {
+        config.validate()?;
+        let timeout = config.timeouts.connect_timeout;
+        tokio::time::timeout(timeout, Self::offer(stream, config))
+            .await
+            .map_err(|_| Error::timeout("serialization format negotiation", timeout))?
+    }
+
+    /// Server side: pick the client's most preferred format among `accepted`
+    pub async fn accept(stream: S, accepted: &[SerializationFormat]) -> Result<Self> {
+        Self::accept_with(stream, NegotiatedConfig::default().with_formats(accepted.iter().copied())).await
+    }
+
+    /// Server side with full configuration
+    pub async fn accept_with(stream: S, config: NegotiatedConfig) -> Result<Self> {
+        config.validate()?;
+        let timeout = config.timeouts.connect_timeout;
+        tokio::time::timeout(timeout, Self::answer(stream, config))
+            .await
+            .map_err(|_| Error::timeout("serialization format negotiation", timeout))?
+    }
+
+    async fn offer(stream: S, config: NegotiatedConfig) -> Result<Self> {
+        let offered: Vec<SerializationFormat> = config.formats.iter()
+            .copied()
+            .filter(|format| format.is_supported())
+            .collect();
+
+        let mut transport = Self::new(stream, &config);
+        let params = json!({ "formats": offered, "compression": config.compression.algorithms });
+        let request = JsonRpcRequest::with_id(NEGOTIATE_METHOD, Some(params), json!(0));
+        transport.write_frame(&serde_json::to_vec(&request)?).await?;
+
+        let response: JsonRpcResponse = serde_json::from_slice(&transport.read_frame().await?)?;
+        if let Some(error) = response.error {
+            return Err(Error::JsonRpc(error));
+        }
+        let result = response.result.unwrap_or(Value::Null);
+        let format: SerializationFormat = serde_json::from_value(result["format"].clone())?;
+        if !offered.contains(&format) {
+            return Err(Error::transport(format!("Server chose serialization format '{}', which was not offered", format)));
+        }
+        // Servers that do not compress leave the field out
+        let compression = match result.get("compression") {
+            Some(compression) => serde_json::from_value(compression.clone())?,
+            None => CompressionAlgorithm::None,
+        };
+        if compression != CompressionAlgorithm::None && !config.compression.algorithms.contains(&compression) {
+            return Err(Error::transport(format!("Server chose compression '{}', which was not offered", compression)));
+        }
+
+        transport.format = format;
+        transport.compression = compression;
+        Ok(transport)
+    }
+
+    async fn answer(stream: S, config: NegotiatedConfig) -> Result<Self> {
+        let mut transport = Self::new(stream, &config);
+        let request: JsonRpcRequest = serde_json::from_slice(&transport.read_frame().await?)?;
+        if request.method != NEGOTIATE_METHOD {
+            return Err(Error::transport(format!("Expected {} handshake, got '{}'", NEGOTIATE_METHOD, request.method)));
+        }
+        let id = request.id.unwrap_or(Value::Null);
+        let params = request.params.unwrap_or(Value::Null);
+
+        let format = SerializationFormat::negotiate(&offered(&params, "formats"), &config.formats);
+        let Some(format) = format else {
+            let error = JsonRpcError::invalid_params("No serialization format in common");
+            let response = JsonRpcResponse::error(id, error.clone());
+            transport.write_frame(&serde_json::to_vec(&response)?).await?;
+            return Err(Error::JsonRpc(error));
+        };
+        let compression = CompressionAlgorithm::negotiate(&offered(&params, "compression"), &config.compression.algorithms);
+
+        let response = JsonRpcResponse::success(id, json!({ "format": format, "compression": compression }));
+        transport.write_frame(&serde_json::to_vec(&response)?).await?;
+        transport.format = format;
+        transport.compression = compression;
+        Ok(transport)
+    }
+
+    fn new(stream: S, config: &NegotiatedConfig) -> Self {
+        Self {
+            stream,
+            format: SerializationFormat::Json,
+            compression: CompressionAlgorithm::None,
+            threshold: config.compression.threshold,
+            level: config.compression.level,
+            stats: CompressionStats::default(),
+            buffer: Vec::new(),
+            max_message_size: config.connection_limits.max_message_size,
+            closed: false,
+            messages_sent: 0,
+            messages_received: 0,
+        }
+    }
+
+    /// Set the maximum frame size in both directions
+    pub fn with_max_message_size(mut self, max_message_size: usize) -> Self {
+        self.max_message_size = max_message_size;
+        self
+    }
+
+    /// The agreed format
+    pub fn format(&self) -> SerializationFormat {
+        self.format
+    }
+
+    /// The agreed compression
+    pub fn compression(&self) -> CompressionAlgorithm {
+        self.compression
+    }
+
+    /// Compression statistics of the frames sent so far
+    pub fn compression_stats(&self) -> &CompressionStats {
+        &self.stats
+    }
+
+    /// Compress a serialized message as agreed, behind its flag byte
+    fn pack(&mut self, message: Vec<u8>) -> Result<Vec<u8>> {
+        if self.compression == CompressionAlgorithm::None {
+            return Ok(message);
+        }
+
+        let before = message.len();
+        let mut frame = vec![FRAME_RAW];
+        if before >= self.threshold {
+            let compressed = self.compression.compress(&message, self.level)?;
+            if compressed.len() < before {
+                frame[0] = FRAME_COMPRESSED;
+                frame.extend_from_slice(&compressed);
+            }
+        }
+        if frame[0] == FRAME_RAW {
+            frame.extend_from_slice(&message);
+        }
+        self.stats.record(before, frame.len(), frame[0] == FRAME_COMPRESSED);
+        Ok(frame)
+    }
+
+    /// Undo `pack`
+    fn unpack(&self, frame: Vec<u8>) -> Result<Vec<u8>> {
+        if self.compression == CompressionAlgorithm::None {
+            return Ok(frame);
+        }
+
+        match frame.first() {
+            Some(&FRAME_RAW) => Ok(frame[1..].to_vec()),
+            Some(&FRAME_COMPRESSED) => self.compression.decompress(&frame[1..], self.max_message_size),
+            Some(flag) => Err(Error::transport(format!("Unknown frame flag {}", flag))),
+            None => Err(Error::transport("Empty frame")),
+        }
+    }
+
+    async fn write_frame(&mut self, frame: &[u8]) -> Result<()> {
+        if frame.len() > self.max_message_size {
+            return Err(Error::validation(format!(
+                "Message of {} bytes exceeds the {} byte limit", frame.len(), self.max_message_size
+            )));
+        }
+        let length = u32::try_from(frame.len())
+            .map_err(|_| Error::validation("Message too large for a length prefix"))?;
+
+        // One write per frame, so Nagle's algorithm cannot hold the body
+        // back behind the length prefix
+        let mut buffer = Vec::with_capacity(4 + frame.len());
+        buffer.extend_from_slice(&length.to_be_bytes());
+        buffer.extend_from_slice(frame);
+        self.stream.write_all(&buffer).await?;
+        self.stream.flush().await?;
+        Ok(())
+    }
+
+    /// Read one frame; cancel safe, since partial reads stay in the buffer
+    async fn read_frame(&mut self) -> Result<Vec<u8>> {
+        loop {
+            if self.buffer.len() >= 4 {
+                let length = u32::from_be_bytes([self.buffer[0], self.buffer[1], self.buffer[2], self.buffer[3]]) as usize;
+                if length > self.max_message_size {
+                    return Err(Error::validation(format!(
+                        "Message of {} bytes exceeds the {} byte limit", length, self.max_message_size
+                    )));
+                }
+                if self.buffer.len() >= 4 + length {
+                    let frame = self.buffer[4..4 + length].to_vec();
+                    self.buffer.drain(..4 + length);
+                    return Ok(frame);
+                }
+            }
+
+            self.buffer.reserve(8 * 1024);
+            if self.stream.read_buf(&mut self.buffer).await? == 0 {
+                return Err(Error::connection("Stream closed"));
+            }
+        }
+    }
+}
+
+/// Parse a list of names from the handshake, skipping unknown ones
+/// rather than failing the handshake
+fn offered<T: serde::de::DeserializeOwned>(params: &Value, key: &str) -> Vec<T> {
+    params.get(key)
+        .and_then(Value::as_array)
+        .map(|names| names.iter().filter_map(|name| serde_json::from_value(name.clone()).ok()).collect())
+        .unwrap_or_default()
+}
+
+#[async_trait]
+impl<S> Transport for NegotiatedTransport<S>
+where
+    S: AsyncRead + AsyncWrite + Send + Sync + Unpin + 'static,
+{
+    async fn send(&mut self, message: &str) -> Result<()> {
+        if self.closed {
+            return Err(Error::connection("Transport closed"));
+        }
+
+        let message = if self.format.is_binary() {
+            self.format.encode(&serde_json::from_str::<Value>(message)?)?
+        } else {
+            message.as_bytes().to_vec()
+        };
+        let frame = self.pack(message)?;
+        self.write_frame(&frame).await?;
+        self.messages_sent += 1;
+        Ok(())
+    }
+
+    async fn receive(&mut self) -> Result<String> {
+        if self.closed {
+            return Err(Error::connection("Transport closed"));
+        }
+
+        let frame = self.read_frame().await?;
+        let frame = self.unpack(frame)?;
+        let message = if self.format.is_binary() {
+            serde_json::to_string(&self.format.decode::<Value>(&frame)?)?
+        } else {
+            String::from_utf8(frame)?
+        };
+        self.messages_received += 1;
+        Ok(message)
+    }
+
+    async fn close(&mut self) -> Result<()> {
+        if !self.closed {
+            self.closed = true;
+            self.stream.shutdown().await?;
+        }
+        Ok(())
+    }
+
+    fn metadata(&self) -> HashMap<String, Value> {
+        let mut metadata = HashMap::new();
+        metadata.insert("format".to_string(), self.format.name().into());
+        metadata.insert("content_type".to_string(), self.format.content_type().into());
+        metadata.insert("compression".to_string(), self.compression.name().into());
+        metadata.insert("compression_ratio".to_string(), self.stats.ratio().into());
+        metadata.insert("messages_sent".to_string(), self.messages_sent.into());
+        metadata.insert("messages_received".to_string(), self.messages_received.into());
+        metadata
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::JsonRpcClient;
+    use tokio::io::DuplexStream;
+
+    type Negotiated = Result<NegotiatedTransport<DuplexStream>>;
+
+    async fn handshake(client: NegotiatedConfig, server: NegotiatedConfig) -> (Negotiated, Negotiated) {
+        let (client_io, server_io) = tokio::io::duplex(64 * 1024);
+        let server = tokio::spawn(NegotiatedTransport::accept_with(server_io, server));
+        let client = NegotiatedTransport::connect_with(client_io, client).await;
+        (client, server.await.unwrap())
+    }
+
+    fn formats(formats: &[SerializationFormat]) -> NegotiatedConfig {
+        NegotiatedConfig::default().with_formats(formats.iter().copied())
+    }
+
+    /// Echo every request's params back
+    fn echo(mut server: NegotiatedTransport<DuplexStream>) {
+        tokio::spawn(async move {
+            while let Ok(message) = server.receive().await {
+                let request: JsonRpcRequest = serde_json::from_str(&message).unwrap();
+                let response = JsonRpcResponse::success(request.id.unwrap(), request.params.unwrap());
+                server.send(&serde_json::to_string(&response).unwrap()).await.unwrap();
+            }
+        });
+    }
+
+    #[tokio::test]
+    async fn test_negotiation() {
+        use SerializationFormat::*;
+
+        let (client, server) = handshake(formats(&[MessagePack, Cbor, Json]), formats(&[Json, Cbor, MessagePack])).await;
+        let expected = SerializationFormat::supported()[0];
+        assert_eq!(client.unwrap().format(), expected);
+        assert_eq!(server.unwrap().format(), expected);
+
+        // Only JSON in common
+        let (client, server) = handshake(formats(&[MessagePack, Json]), formats(&[Json])).await;
+        assert_eq!(client.unwrap().format(), Json);
+        assert_eq!(server.unwrap().format(), Json);
+
+        // Nothing in common: both ends fail
+        if Cbor.is_supported() {
+            let (client, server) = handshake(formats(&[Json]), formats(&[Cbor])).await;
+            assert!(matches!(client, Err(Error::JsonRpc(_))));
+            assert!(server.is_err());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_calls_over_negotiated_format() {
+        use SerializationFormat::*;
+
+        let (client, server) = handshake(NegotiatedConfig::default(), formats(&[Cbor, MessagePack, Json])).await;
+        echo(server.unwrap());
+        let client = JsonRpcClient::new(client.unwrap());
+
+        let params = json!({ "text": "héllo", "n": [1, -2, 3.5], "nested": { "ok": true, "none": null } });
+        let echoed: Value = client.call("echo", params.clone()).await.unwrap();
+        assert_eq!(echoed, params);
+    }
+
+    #[tokio::test]
+    async fn test_compression() {
+        let algorithms: Vec<CompressionAlgorithm> = [CompressionAlgorithm::Zstd, CompressionAlgorithm::Deflate]
+            .into_iter()
+            .filter(|algorithm| algorithm.is_supported())
+            .collect();
+        let expected = algorithms.first().copied().unwrap_or(CompressionAlgorithm::None);
+        let compression = CompressionConfig::default().with_algorithms(algorithms).with_threshold(256);
+        let config = NegotiatedConfig::default().with_compression(compression);
+
+        // A peer that does not compress keeps it off
+        let (client, server) = handshake(config.clone(), NegotiatedConfig::default()).await;
+        assert_eq!(client.unwrap().compression(), CompressionAlgorithm::None);
+        assert_eq!(server.unwrap().compression(), CompressionAlgorithm::None);
+
+        let (client, server) = handshake(config.clone(), config).await;
+        let mut client = client.unwrap();
+        assert_eq!(client.compression(), expected);
+        echo(server.unwrap());
+
+        // One message under the threshold, one well over it
+        for text in ["short".to_string(), "repetitive ".repeat(200)] {
+            let request = JsonRpcRequest::with_id("echo", Some(json!(text)), json!(1));
+            client.send(&serde_json::to_string(&request).unwrap()).await.unwrap();
+            let response: JsonRpcResponse = serde_json::from_str(&client.receive().await.unwrap()).unwrap();
+            assert_eq!(response.result, Some(json!(text)));
+        }
+
+        let stats = client.compression_stats().clone();
+        if expected == CompressionAlgorithm::None {
+            assert_eq!(stats, CompressionStats::default());
+        } else {
+            assert_eq!((stats.compressed_frames, stats.uncompressed_frames), (1, 1));
+            assert!(stats.ratio() < 0.5);
+            assert!(client.metadata()["compression_ratio"].as_f64().unwrap() < 0.5);
+        }
+    }
+}
@@ -0,0 +1,567 @@
+//! Automatic reconnection for client connections
+//!
+//! [`ReconnectingConnection`] owns a transport created by a
+//! [`TransportConnector`] and re-creates it whenever it drops, waiting
+//! between attempts according to a [`RetryPolicy`] (exponential backoff
+//! with jitter). Requests sent but not yet answered when the connection
+//! drops are either sent again on the new connection or answered locally
+//! with an error response, depending on the [`ReplayPolicy`].
+//!
+//! Observers can follow the connection through [`ConnectionEvent`]s.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use std::time::Duration;
+use async_trait::async_trait;
+use futures::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+use crate::core::error::{Error, JsonRpcError, Result, RetryPolicy};
+use crate::core::traits::{Connection, Transport};
+use crate::core::types::JsonRpcResponse;
+use super::abstraction::ConnectionState;
+
+/// Creates a fresh transport each time the connection is (re-)established
+pub type TransportConnector = Arc<dyn Fn() -> BoxFuture<'static, Result<Box<dyn Transport>>> + Send + Sync>;
+
+/// What to do with unanswered requests when the connection drops
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ReplayPolicy {
+    /// Send them again once reconnected; only safe for idempotent methods
+    Replay,
+    /// Answer them with an error response
+    Fail,
+}
+
+/// Reconnection configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReconnectConfig {
+    /// Backoff between connection attempts; `max_attempts` bounds each
+    /// reconnection cycle
+    pub retry_policy: RetryPolicy,
+    /// Handling of requests in flight when the connection drops
+    pub replay_policy: ReplayPolicy,
+}
+
+impl Default for ReconnectConfig {
+    fn default() -> Self {
+        Self {
+            retry_policy: RetryPolicy::exponential_backoff(10),
+            replay_policy: ReplayPolicy::Fail,
+        }
+    }
+}
+
+impl ReconnectConfig {
+    /// Set the backoff policy
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// Set the handling of requests in flight
+    pub fn with_replay_policy(mut self, replay_policy: ReplayPolicy) -> Self {
+        self.replay_policy = replay_policy;
+        self
+    }
+}
+
+/// Connection state changes published by a [`ReconnectingConnection`]
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConnectionEvent {
+    /// A connection attempt is starting (0-based)
+    Connecting { attempt: u32 },
+    /// The transport is established
+    Connected { attempt: u32 },
+    /// The transport dropped or was closed
+    Disconnected { reason: String },
+    /// An attempt failed; the next one starts after `delay`
+    Reconnecting { attempt: u32, delay: Duration },
+    /// Every attempt of the cycle failed; the connection stays down until
+    /// `connect` is called again
+    Failed { reason: String },
+    /// Requests in flight were sent again (`Replay`) or failed (`Fail`)
+    PendingRequests { policy: ReplayPolicy, count: usize },
+}
+
+/// A client connection that re-establishes its transport when it drops
+pub struct ReconnectingConnection {
+    /// Connection ID
+    id: String,
+    /// Transport factory
+    connector: TransportConnector,
+    /// Reconnection configuration
+    config: ReconnectConfig,
+    /// Current transport, `None` while disconnected
+    transport: Option<Box<dyn Transport>>,
+    /// Connection state
+    state: ConnectionState,
+    /// Requests sent and not yet answered, in sending order
+    pending: Vec<(String, String)>,
+    /// Error responses for failed requests, handed out by `receive`
+    failed: VecDeque<String>,
+    /// Event channel
+    events: broadcast::Sender<ConnectionEvent>,
+    /// Successful reconnections after the first connection
+    reconnects: u64,
+    /// Last error
+    last_error: Option<Error>,
+}
+
+impl ReconnectingConnection {
+    /// Create a disconnected connection using `connector` for every attempt
+    pub fn new<F>(connector: F, config: ReconnectConfig) -> Self
+    where
+        F: Fn() -> BoxFuture<'static, Result<Box<dyn Transport>>> + Send + Sync + 'static,
+    {
+        let (events, _) = broadcast::channel(64);
+        Self {
+            id: Uuid::new_v4().to_string(),
+            connector: Arc::new(connector),
+            config,
+            transport: None,
+            state: ConnectionState::Disconnected,
+            pending: Vec::new(),
+            failed: VecDeque::new(),
+            events,
+            reconnects: 0,
+            last_error: None,
+        }
+    }
+
+    /// Create and connect
+    pub async fn connect_with<F>(connector: F, config: ReconnectConfig) -> Result<Self>
+    where
+        F: Fn() -> BoxFuture<'static, Result<Box<dyn Transport>>> + Send + Sync + 'static,
+    {
+        let mut connection = Self::new(connector, config);
+        connection.connect().await?;
+        Ok(connection)
+    }
+
+    /// Subscribe to connection state changes
+    pub fn subscribe(&self) -> broadcast::Receiver<ConnectionEvent> {
+        self.events.subscribe()
+    }
+
+    /// Number of requests awaiting a response
+    pub fn pending_requests(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Number of successful reconnections
+    pub fn reconnects(&self) -> u64 {
+        self.reconnects
+    }
+
+    fn emit(&self, event: ConnectionEvent) {
+        // No subscribers is fine
+        let _ = self.events.send(event);
+    }
+
+    /// Run one connection cycle: attempt until connected or out of attempts
+    async fn establish(&mut self) -> Result<()> {
+        let policy = self.config.retry_policy.clone();
+        let mut attempt = 0;
+        self.state = ConnectionState::Connecting;
+
+        loop {
+            self.emit(ConnectionEvent::Connecting { attempt });
+            match (self.connector)().await {
+                Ok(transport) => {
+                    self.transport = Some(transport);
+                    self.state = ConnectionState::Connected;
+                    self.emit(ConnectionEvent::Connected { attempt });
+                    return Ok(());
+                }
+                Err(e) => {
+                    tracing::debug!("Connection attempt {} failed: {}", attempt, e);
+                    attempt += 1;
+                    if !e.is_retryable() || !policy.should_retry(attempt) {
+                        let reason = e.to_string();
+                        self.state = ConnectionState::Error(reason.clone());
+                        self.emit(ConnectionEvent::Failed { reason });
+                        self.last_error = Some(Error::connection(e.to_string()));
+                        return Err(e);
+                    }
+
+                    let delay = policy.delay_for_attempt(attempt - 1);
+                    self.emit(ConnectionEvent::Reconnecting { attempt, delay });
+                    tokio::time::sleep(delay).await;
+                }
+            }
+        }
+    }
+
+    /// Replace a dropped transport and deal with the requests it carried
+    async fn reconnect(&mut self, reason: String) -> Result<()> {
+        if let Some(mut transport) = self.transport.take() {
+            let _ = transport.close().await;
+        }
+        self.last_error = Some(Error::connection(reason.clone()));
+        self.emit(ConnectionEvent::Disconnected { reason });
+
+        if let Err(e) = self.establish().await {
+            self.fail_pending();
+            return Err(e);
+        }
+        self.reconnects += 1;
+
+        match self.config.replay_policy {
+            ReplayPolicy::Fail => self.fail_pending(),
+            ReplayPolicy::Replay if !self.pending.is_empty() => {
+                self.emit(ConnectionEvent::PendingRequests {
+                    policy: ReplayPolicy::Replay,
+                    count: self.pending.len(),
+                });
+                let transport = self.transport.as_mut().expect("just connected");
+                for (_, message) in &self.pending {
+                    transport.send(message).await?;
+                }
+            }
+            ReplayPolicy::Replay => {}
+        }
+        Ok(())
+    }
+
+    fn fail_pending(&mut self) {
+        if self.pending.is_empty() {
+            return;
+        }
+
+        self.emit(ConnectionEvent::PendingRequests {
+            policy: ReplayPolicy::Fail,
+            count: self.pending.len(),
+        });
+        for (_, message) in self.pending.drain(..) {
+            for id in request_ids(&message) {
+                let error = JsonRpcError::internal_error("Connection lost before the response arrived");
+                if let Ok(response) = serde_json::to_string(&JsonRpcResponse::error(id, error)) {
+                    self.failed.push_back(response);
+                }
+            }
+        }
+    }
+
+    /// Fail fast once a cycle gave up or the connection was closed
+    async fn ensure_connected(&mut self) -> Result<()> {
+        match self.state {
+            ConnectionState::Connected if self.transport.is_some() => Ok(()),
+            ConnectionState::Error(ref reason) => {
+                Err(Error::connection(format!("Reconnection failed: {}", reason)))
+            }
+            ConnectionState::Disconnected => Err(Error::connection("Connection closed")),
+            // A reconnection cancelled midway resumes here
+            _ => self.reconnect("Reconnection interrupted".to_string()).await,
+        }
+    }
+
+    fn track(&mut self, message: &str) {
+        let ids = request_ids(message);
+        if !ids.is_empty() {
+            let key = ids.iter().map(Value::to_string).collect::<Vec<_>>().join(",");
+            self.pending.push((key, message.to_string()));
+        }
+    }
+
+    fn settle(&mut self, message: &str) {
+        let Ok(value) = serde_json::from_str::<Value>(message) else {
+            return;
+        };
+        let items = match value {
+            Value::Array(items) => items,
+            single => vec![single],
+        };
+
+        for item in items {
+            if item.get("method").is_some() {
+                continue;
+            }
+            if let Some(id) = item.get("id") {
+                let id = id.to_string();
+                // A batch is settled as soon as any of its responses arrives,
+                // since servers answer a batch in one message
+                self.pending.retain(|(key, _)| !key.split(',').any(|k| k == id));
+            }
+        }
+    }
+}
+
+/// Ids of the requests in a message, single or batch
+fn request_ids(message: &str) -> Vec<Value> {
+    let Ok(value) = serde_json::from_str::<Value>(message) else {
+        return Vec::new();
+    };
+    let items = match value {
+        Value::Array(items) => items,
+        single => vec![single],
+    };
+
+    items.into_iter()
+        .filter(|item| item.get("method").is_some())
+        .filter_map(|item| item.get("id").filter(|id| !id.is_null()).cloned())
+        .collect()
+}
+
+#[async_trait]
+impl Connection for ReconnectingConnection {
+    async fn connect(&mut self) -> Result<()> {
+        if self.is_connected() {
+            return Ok(());
+        }
+        self.establish().await
+    }
+
+    async fn disconnect(&mut self) -> Result<()> {
+        let result = match self.transport.take() {
+            Some(mut transport) => transport.close().await,
+            None => Ok(()),
+        };
+        self.pending.clear();
+        self.failed.clear();
+        self.state = ConnectionState::Disconnected;
+        self.emit(ConnectionEvent::Disconnected { reason: "Closed by client".to_string() });
+        result
+    }
+
+    fn is_connected(&self) -> bool {
+        matches!(self.state, ConnectionState::Connected)
+    }
+
+    fn is_closed(&self) -> bool {
+        matches!(self.state, ConnectionState::Disconnected)
+    }
+
+    fn connection_info(&self) -> HashMap<String, Value> {
+        let mut info = self.transport.as_ref()
+            .map(|transport| transport.metadata())
+            .unwrap_or_default();
+        info.insert("id".to_string(), self.id.clone().into());
+        info.insert("state".to_string(), format!("{:?}", self.state).into());
+        info.insert("reconnects".to_string(), self.reconnects.into());
+        info.insert("pending_requests".to_string(), self.pending.len().into());
+        info.insert("replay_policy".to_string(), format!("{:?}", self.config.replay_policy).into());
+        info
+    }
+
+    fn last_error(&self) -> Option<&Error> {
+        self.last_error.as_ref()
+    }
+}
+
+#[async_trait]
+impl Transport for ReconnectingConnection {
+    async fn send(&mut self, message: &str) -> Result<()> {
+        self.ensure_connected().await?;
+
+        let transport = self.transport.as_mut().expect("connected");
+        match transport.send(message).await {
+            Ok(()) => {
+                self.track(message);
+                Ok(())
+            }
+            Err(e) if e.is_retryable() => {
+                // The message never made it out; send it on the new transport
+                self.reconnect(e.to_string()).await?;
+                self.transport.as_mut().expect("reconnected").send(message).await?;
+                self.track(message);
+                Ok(())
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    async fn receive(&mut self) -> Result<String> {
+        loop {
+            if let Some(response) = self.failed.pop_front() {
+                return Ok(response);
+            }
+            self.ensure_connected().await?;
+
+            let transport = self.transport.as_mut().expect("connected");
+            match transport.receive().await {
+                Ok(message) => {
+                    self.settle(&message);
+                    return Ok(message);
+                }
+                Err(e) if e.is_retryable() => {
+                    // A failed cycle still leaves failed responses to hand out
+                    if let Err(e) = self.reconnect(e.to_string()).await {
+                        return self.failed.pop_front().ok_or(e);
+                    }
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    async fn close(&mut self) -> Result<()> {
+        self.disconnect().await
+    }
+
+    fn is_bidirectional(&self) -> bool {
+        self.transport.as_ref().is_none_or(|transport| transport.is_bidirectional())
+    }
+
+    fn metadata(&self) -> HashMap<String, Value> {
+        self.connection_info()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::JsonRpcClient;
+    use futures::FutureExt;
+    use serde_json::json;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tokio::sync::mpsc;
+
+    /// One end of an in-memory link; dropping either end breaks it
+    struct Link {
+        tx: mpsc::UnboundedSender<String>,
+        rx: mpsc::UnboundedReceiver<String>,
+    }
+
+    fn link() -> (Link, Link) {
+        let (a_tx, a_rx) = mpsc::unbounded_channel();
+        let (b_tx, b_rx) = mpsc::unbounded_channel();
+        (Link { tx: a_tx, rx: b_rx }, Link { tx: b_tx, rx: a_rx })
+    }
+
+    #[async_trait]
+    impl Transport for Link {
+        async fn send(&mut self, message: &str) -> Result<()> {
+            self.tx.send(message.to_string()).map_err(|_| Error::connection("link broken"))
+        }
+
+        async fn receive(&mut self) -> Result<String> {
+            self.rx.recv().await.ok_or_else(|| Error::connection("link broken"))
+        }
+
+        async fn close(&mut self) -> Result<()> {
+            self.rx.close();
+            Ok(())
+        }
+    }
+
+    /// Connector handing the server end of every new link to the test;
+    /// the first `refusals` attempts fail
+    fn connector(refusals: usize) -> (TransportConnector, mpsc::UnboundedReceiver<Link>) {
+        let (servers_tx, servers) = mpsc::unbounded_channel();
+        let attempts = Arc::new(AtomicUsize::new(0));
+        let connect = move || {
+            let servers_tx = servers_tx.clone();
+            let attempt = attempts.fetch_add(1, Ordering::SeqCst);
+            async move {
+                if attempt < refusals {
+                    return Err(Error::connection("connection refused"));
+                }
+                let (client, server) = link();
+                let _ = servers_tx.send(server);
+                Ok(Box::new(client) as Box<dyn Transport>)
+            }
+            .boxed()
+        };
+        (Arc::new(connect), servers)
+    }
+
+    fn fast_retries(max_attempts: u32) -> RetryPolicy {
+        RetryPolicy::new(max_attempts, Duration::from_millis(5)).with_jitter_factor(0.0)
+    }
+
+    fn answer(request: &str, result: Value) -> String {
+        let id = serde_json::from_str::<Value>(request).unwrap()["id"].clone();
+        serde_json::to_string(&JsonRpcResponse::success(id, result)).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_backoff_and_events() {
+        let (connect, mut servers) = connector(2);
+        let mut connection = ReconnectingConnection::new(move || connect(), ReconnectConfig::default().with_retry_policy(fast_retries(5)));
+        let mut events = connection.subscribe();
+
+        connection.connect().await.unwrap();
+        assert!(connection.is_connected());
+        let mut server = servers.recv().await.unwrap();
+
+        let mut seen = Vec::new();
+        while let Ok(event) = events.try_recv() {
+            seen.push(event);
+        }
+        assert_eq!(seen, vec![
+            ConnectionEvent::Connecting { attempt: 0 },
+            ConnectionEvent::Reconnecting { attempt: 1, delay: Duration::from_millis(5) },
+            ConnectionEvent::Connecting { attempt: 1 },
+            ConnectionEvent::Reconnecting { attempt: 2, delay: Duration::from_millis(10) },
+            ConnectionEvent::Connecting { attempt: 2 },
+            ConnectionEvent::Connected { attempt: 2 },
+        ]);
+
+        // Out of attempts: the connection gives up and stays down
+        let (connect, _servers) = connector(usize::MAX);
+        let mut doomed = ReconnectingConnection::new(move || connect(), ReconnectConfig::default().with_retry_policy(fast_retries(2)));
+        assert!(doomed.connect().await.is_err());
+        assert!(doomed.send("{}").await.is_err());
+
+        connection.send("ping").await.unwrap();
+        assert_eq!(server.receive().await.unwrap(), "ping");
+    }
+
+    #[tokio::test]
+    async fn test_replay_pending_requests() {
+        let (connect, mut servers) = connector(0);
+        let config = ReconnectConfig::default()
+            .with_retry_policy(fast_retries(3))
+            .with_replay_policy(ReplayPolicy::Replay);
+        let connection = ReconnectingConnection::connect_with(move || connect(), config).await.unwrap();
+        let client = Arc::new(JsonRpcClient::new(connection));
+
+        let call = tokio::spawn({
+            let client = Arc::clone(&client);
+            async move { client.call::<_, Value>("echo", json!("hi")).await }
+        });
+
+        // The first server drops the request on the floor
+        let mut server = servers.recv().await.unwrap();
+        server.receive().await.unwrap();
+        drop(server);
+
+        // The second one gets it again and answers
+        let mut server = servers.recv().await.unwrap();
+        let request = server.receive().await.unwrap();
+        server.send(&answer(&request, json!("hi"))).await.unwrap();
+        assert_eq!(call.await.unwrap().unwrap(), json!("hi"));
+    }
+
+    #[tokio::test]
+    async fn test_fail_pending_requests() {
+        let (connect, mut servers) = connector(0);
+        let config = ReconnectConfig::default().with_retry_policy(fast_retries(3));
+        let connection = ReconnectingConnection::connect_with(move || connect(), config).await.unwrap();
+        let client = Arc::new(JsonRpcClient::new(connection));
+
+        let call = tokio::spawn({
+            let client = Arc::clone(&client);
+            async move { client.call::<_, Value>("slow", json!(null)).await }
+        });
+        let mut server = servers.recv().await.unwrap();
+        server.receive().await.unwrap();
+        drop(server);
+
+        // The lost request fails, and the client keeps working afterwards
+        assert!(matches!(call.await.unwrap(), Err(Error::JsonRpc(_))));
+        let call = tokio::spawn({
+            let client = Arc::clone(&client);
+            async move { client.call::<_, Value>("echo", json!(1)).await }
+        });
+        let mut server = servers.recv().await.unwrap();
+        let request = server.receive().await.unwrap();
+        assert!(request.contains("echo"));
+        server.send(&answer(&request, json!(1))).await.unwrap();
+        assert_eq!(call.await.unwrap().unwrap(), json!(1));
+    }
+}
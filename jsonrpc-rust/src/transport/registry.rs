@@ -3,6 +3,20 @@
 //! This module provides a registry system for managing different transport
 //! implementations, allowing dynamic selection and creation of transports
 //! based on protocol type or URI scheme.
+//!
+//! [`TransportRegistry::connect`] and [`TransportRegistry::listen`] pick
+//! the implementation by the scheme of a URI:
+//!
+//! | Scheme | `connect` | `listen` |
+//! |---|---|---|
+//! | `tcp://host:port` | [`NegotiatedTransport`] over TCP | TCP listener with the same handshake |
+//! | `ws://`, `wss://` | [`WebSocketTransport`](super::websocket::WebSocketTransport) (`websocket`) | [`WebSocketServer`](super::websocket::WebSocketServer) (`websocket`) |
+//! | `http://`, `https://` | [`HttpTransport`](super::http::HttpTransport) (`http`) | |
+//! | `stdio:` | [`StdioTransport`](super::stdio::StdioTransport) (`stdio`) | the process's own stdio, once |
+//! | `mock:` | [`MockTransport`](super::mock::MockTransport) | |
+//!
+//! Other schemes, or replacements for these, are added with
+//! [`TransportRegistry::register_scheme`].
 
 use std::collections::HashMap;
 use std::sync::Arc;
@@ -17,6 +31,7 @@ use super::abstraction::{
     TransportConfig, TimeoutConfig, RetryConfig, ConnectionLimits,
 };
 use super::{Protocol, tcp::TcpConfig, mock::MockConfig};
+use super::negotiated::{NegotiatedConfig, NegotiatedTransport};
 
 /// Transport type identifier
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -27,6 +42,8 @@ pub enum TransportType {
     WebSocket,
     /// HTTP transport
     Http,
+    /// Stdio transport
+    Stdio,
     /// Mock transport for testing
     Mock,
     /// Custom transport type
@@ -39,6 +56,7 @@ impl From<Protocol> for TransportType {
             Protocol::Tcp => TransportType::Tcp,
             Protocol::WebSocket => TransportType::WebSocket,
             Protocol::Http => TransportType::Http,
+            Protocol::Stdio => TransportType::Stdio,
             Protocol::Mock => TransportType::Mock,
         }
     }
@@ -50,6 +68,7 @@ impl std::fmt::Display for TransportType {
             TransportType::Tcp => write!(f, "tcp"),
             TransportType::WebSocket => write!(f, "websocket"),
             TransportType::Http => write!(f, "http"),
+            TransportType::Stdio => write!(f, "stdio"),
             TransportType::Mock => write!(f, "mock"),
             TransportType::Custom(name) => write!(f, "{}", name),
         }
@@ -64,6 +83,7 @@ impl std::str::FromStr for TransportType {
             "tcp" => Ok(TransportType::Tcp),
             "websocket" | "ws" => Ok(TransportType::WebSocket),
             "http" | "https" => Ok(TransportType::Http),
+            "stdio" => Ok(TransportType::Stdio),
             "mock" => Ok(TransportType::Mock),
             custom => Ok(TransportType::Custom(custom.to_string())),
         }
@@ -152,12 +172,38 @@ impl TransportConfig for RegistryConfig {
     }
 }
 
+/// Creates transports for the URIs of a scheme
+#[async_trait]
+pub trait SchemeHandler: Send + Sync {
+    /// Connect to `uri`
+    async fn connect(&self, uri: &Url, config: &RegistryConfig) -> Result<Box<dyn Transport>>;
+
+    /// Listen on `uri`; not every scheme can
+    async fn listen(&self, uri: &Url, _config: &RegistryConfig) -> Result<Box<dyn TransportListener>> {
+        Err(Error::configuration(format!("Cannot listen on {} URIs", uri.scheme())))
+    }
+}
+
+/// Accepts the connections made to a listening endpoint
+#[async_trait]
+pub trait TransportListener: Send {
+    /// Wait for the next connection and the address it came from
+    async fn accept(&mut self) -> Result<(Box<dyn Transport>, String)>;
+
+    /// Address the listener is bound to, if it has one
+    fn local_addr(&self) -> Option<std::net::SocketAddr> {
+        None
+    }
+}
+
 /// Transport registry for managing multiple transport types
 pub struct TransportRegistry {
     /// Registry configuration
     config: RegistryConfig,
     /// Transport instances cache
     transports: Arc<RwLock<HashMap<String, Box<dyn Transport>>>>,
+    /// Handlers of URI schemes, built in and registered
+    schemes: Arc<RwLock<HashMap<String, Arc<dyn SchemeHandler>>>>,
     /// Statistics
     stats: Arc<RwLock<RegistryStats>>,
 }
@@ -183,6 +229,7 @@ impl TransportRegistry {
         Ok(Self {
             config,
             transports: Arc::new(RwLock::new(HashMap::new())),
+            schemes: Arc::new(RwLock::new(builtin_schemes())),
             stats: Arc::new(RwLock::new(RegistryStats::default())),
         })
     }
@@ -197,6 +244,8 @@ impl TransportRegistry {
         vec![
             TransportType::Tcp,
             TransportType::Mock,
+            #[cfg(feature = "stdio")]
+            TransportType::Stdio,
             // Add more as they become available
         ]
     }
@@ -204,6 +253,7 @@ impl TransportRegistry {
     /// Check if a transport type is supported
     pub async fn is_supported(&self, transport_type: &TransportType) -> bool {
         matches!(transport_type, TransportType::Tcp | TransportType::Mock)
+            || (cfg!(feature = "stdio") && *transport_type == TransportType::Stdio)
     }
     
     /// Create a transport instance by type
@@ -237,6 +287,21 @@ impl TransportRegistry {
                 let transport = crate::transport::mock::MockTransport::new(mock_config).await?;
                 Ok(Box::new(transport))
             }
+            #[cfg(feature = "stdio")]
+            TransportType::Stdio => {
+                let stdio_config = if let Some(config) = config {
+                    serde_json::from_value(config)
+                        .map_err(|e| Error::Configuration {
+                            message: format!("Invalid Stdio config: {}", e),
+                            source: Some(Box::new(e)),
+                        })?
+                } else {
+                    crate::transport::stdio::StdioConfig::default()
+                };
+                
+                let transport = crate::transport::stdio::StdioTransport::new(stdio_config)?;
+                Ok(Box::new(transport))
+            }
             _ => Err(Error::Configuration {
                 message: format!("Transport type {} not supported", transport_type),
                 source: None,
@@ -267,6 +332,54 @@ impl TransportRegistry {
         self.create_transport(transport_type, Some(config)).await
     }
     
+    /// Connect to a URI with the transport its scheme selects
+    pub async fn connect(&self, uri: &str) -> Result<Box<dyn Transport>> {
+        let (parsed, handler) = self.scheme_handler(uri).await?;
+        let result = handler.connect(&parsed, &self.config).await;
+        self.record_creation(&parsed, result.is_ok()).await;
+        result
+    }
+    
+    /// Listen on a URI with the transport its scheme selects
+    pub async fn listen(&self, uri: &str) -> Result<Box<dyn TransportListener>> {
+        let (parsed, handler) = self.scheme_handler(uri).await?;
+        handler.listen(&parsed, &self.config).await
+    }
+    
+    /// Handle the URIs of `scheme` with `handler`, replacing any handler
+    /// it had, built in or not
+    pub async fn register_scheme(&self, scheme: impl Into<String>, handler: Arc<dyn SchemeHandler>) {
+        self.schemes.write().await.insert(scheme.into().to_lowercase(), handler);
+    }
+    
+    /// Schemes [`connect`](Self::connect) and [`listen`](Self::listen)
+    /// accept, sorted
+    pub async fn schemes(&self) -> Vec<String> {
+        let mut schemes: Vec<String> = self.schemes.read().await.keys().cloned().collect();
+        schemes.sort();
+        schemes
+    }
+    
+    async fn scheme_handler(&self, uri: &str) -> Result<(Url, Arc<dyn SchemeHandler>)> {
+        let parsed = Url::parse(uri)
+            .map_err(|e| Error::configuration(format!("Invalid URI {}: {}", uri, e)))?;
+        let handler = self.schemes.read().await.get(parsed.scheme()).cloned()
+            .ok_or_else(|| Error::configuration(format!("No transport registered for scheme '{}'", parsed.scheme())))?;
+        Ok((parsed, handler))
+    }
+    
+    async fn record_creation(&self, uri: &Url, created: bool) {
+        let mut stats = self.stats.write().await;
+        if created {
+            stats.created_instances += 1;
+            let transport_type = self.scheme_to_transport_type(uri.scheme())
+                .unwrap_or_else(|_| TransportType::Custom(uri.scheme().to_string()));
+            *stats.usage_counts.entry(transport_type).or_insert(0) += 1;
+        } else {
+            stats.creation_failures += 1;
+        }
+    }
+    
     /// Get or create a cached transport instance
     pub async fn get_or_create_transport(&self, key: &str, transport_type: TransportType, config: Option<serde_json::Value>) -> Result<()> {
         let transports = self.transports.read().await;
@@ -314,6 +427,7 @@ impl TransportRegistry {
             "tcp" => Ok(TransportType::Tcp),
             "ws" | "websocket" => Ok(TransportType::WebSocket),
             "http" | "https" => Ok(TransportType::Http),
+            "stdio" => Ok(TransportType::Stdio),
             "mock" => Ok(TransportType::Mock),
             unknown => {
                 if self.config.auto_select {
@@ -363,6 +477,18 @@ impl TransportRegistry {
                 config.insert("protocol".to_string(), "http".into());
                 config.insert("secure".to_string(), (uri.scheme() == "https").into());
             }
+            TransportType::Stdio => {
+                // Accept `stdio:?framing=content-length` style framing names
+                let framing = match config.get("framing").and_then(|v| v.as_str()) {
+                    Some("content-length") | Some("lsp") => Some("ContentLength"),
+                    Some("line") | Some("newline") => Some("LineDelimited"),
+                    _ => None,
+                };
+                if let Some(framing) = framing {
+                    config.insert("framing".to_string(), framing.into());
+                }
+                config.insert("protocol".to_string(), "stdio".into());
+            }
             TransportType::Mock => {
                 // Add Mock-specific configuration
                 config.insert("protocol".to_string(), "mock".into());
@@ -377,6 +503,195 @@ impl TransportRegistry {
     }
 }
 
+/// Handlers of the schemes every registry starts with
+fn builtin_schemes() -> HashMap<String, Arc<dyn SchemeHandler>> {
+    let mut schemes: HashMap<String, Arc<dyn SchemeHandler>> = HashMap::new();
+    #[cfg(feature = "tcp")]
+    schemes.insert("tcp".to_string(), Arc::new(TcpScheme));
+    #[cfg(feature = "websocket")]
+    for scheme in ["ws", "wss"] {
+        schemes.insert(scheme.to_string(), Arc::new(WebSocketScheme));
+    }
+    #[cfg(feature = "http")]
+    for scheme in ["http", "https"] {
+        schemes.insert(scheme.to_string(), Arc::new(HttpScheme));
+    }
+    #[cfg(feature = "stdio")]
+    schemes.insert("stdio".to_string(), Arc::new(StdioScheme));
+    schemes.insert("mock".to_string(), Arc::new(MockScheme));
+    schemes
+}
+
+/// Host and port of a URI, the port defaulting to the scheme's own
+fn host_port(uri: &Url) -> Result<(String, u16)> {
+    let host = uri.host_str()
+        .ok_or_else(|| Error::configuration(format!("No host in {}", uri)))?;
+    let port = uri.port_or_known_default()
+        .ok_or_else(|| Error::configuration(format!("No port in {}", uri)))?;
+    Ok((host.to_string(), port))
+}
+
+/// `tcp://host:port`: the negotiated framing [`JsonRpcServer`](crate::convenience::JsonRpcServer) speaks
+#[cfg(feature = "tcp")]
+struct TcpScheme;
+
+#[cfg(feature = "tcp")]
+impl TcpScheme {
+    fn negotiated_config(config: &RegistryConfig) -> NegotiatedConfig {
+        NegotiatedConfig {
+            timeouts: config.timeouts.clone(),
+            retry_config: config.retry_config.clone(),
+            connection_limits: config.connection_limits.clone(),
+            ..NegotiatedConfig::default()
+        }
+    }
+}
+
+#[cfg(feature = "tcp")]
+#[async_trait]
+impl SchemeHandler for TcpScheme {
+    async fn connect(&self, uri: &Url, config: &RegistryConfig) -> Result<Box<dyn Transport>> {
+        let address = host_port(uri)?;
+        let timeout = config.timeouts.connect_timeout;
+        let stream = tokio::time::timeout(timeout, tokio::net::TcpStream::connect(&address)).await
+            .map_err(|_| Error::timeout(format!("connecting to {}", uri), timeout))?
+            .map_err(|e| Error::connection(format!("Failed to connect to {}: {}", uri, e)))?;
+        let _ = stream.set_nodelay(true);
+        Ok(Box::new(NegotiatedTransport::connect_with(stream, Self::negotiated_config(config)).await?))
+    }
+
+    async fn listen(&self, uri: &Url, config: &RegistryConfig) -> Result<Box<dyn TransportListener>> {
+        let address = host_port(uri)?;
+        let listener = tokio::net::TcpListener::bind(&address).await
+            .map_err(|e| Error::transport(format!("Failed to bind to {}: {}", uri, e)))?;
+        Ok(Box::new(TcpTransportListener {
+            listener,
+            config: Self::negotiated_config(config),
+        }))
+    }
+}
+
+#[cfg(feature = "tcp")]
+struct TcpTransportListener {
+    listener: tokio::net::TcpListener,
+    config: NegotiatedConfig,
+}
+
+#[cfg(feature = "tcp")]
+#[async_trait]
+impl TransportListener for TcpTransportListener {
+    async fn accept(&mut self) -> Result<(Box<dyn Transport>, String)> {
+        let (stream, peer) = self.listener.accept().await
+            .map_err(|e| Error::transport(format!("Failed to accept connection: {}", e)))?;
+        let _ = stream.set_nodelay(true);
+        let transport = NegotiatedTransport::accept_with(stream, self.config.clone()).await?;
+        Ok((Box::new(transport), peer.to_string()))
+    }
+
+    fn local_addr(&self) -> Option<std::net::SocketAddr> {
+        self.listener.local_addr().ok()
+    }
+}
+
+/// `ws://` and `wss://`
+#[cfg(feature = "websocket")]
+struct WebSocketScheme;
+
+#[cfg(feature = "websocket")]
+#[async_trait]
+impl SchemeHandler for WebSocketScheme {
+    async fn connect(&self, uri: &Url, config: &RegistryConfig) -> Result<Box<dyn Transport>> {
+        let mut ws_config = super::websocket::WebSocketConfig::client(uri.as_str());
+        ws_config.timeouts = config.timeouts.clone();
+        Ok(Box::new(super::websocket::WebSocketTransport::connect(ws_config).await?))
+    }
+
+    async fn listen(&self, uri: &Url, config: &RegistryConfig) -> Result<Box<dyn TransportListener>> {
+        let address = tokio::net::lookup_host(host_port(uri)?).await?.next()
+            .ok_or_else(|| Error::configuration(format!("No address for {}", uri)))?;
+        let mut ws_config = super::websocket::WebSocketConfig::server(address);
+        ws_config.timeouts = config.timeouts.clone();
+        Ok(Box::new(super::websocket::WebSocketServer::bind(ws_config).await?))
+    }
+}
+
+#[cfg(feature = "websocket")]
+#[async_trait]
+impl TransportListener for super::websocket::WebSocketServer {
+    async fn accept(&mut self) -> Result<(Box<dyn Transport>, String)> {
+        let transport = super::websocket::WebSocketServer::accept(self).await?;
+        let peer = transport.connection().remote_addr()
+            .map(|addr| addr.to_string())
+            .unwrap_or_default();
+        Ok((Box::new(transport), peer))
+    }
+
+    fn local_addr(&self) -> Option<std::net::SocketAddr> {
+        super::websocket::WebSocketServer::local_addr(self).ok()
+    }
+}
+
+/// `http://` and `https://`, client side only: HTTP servers answer with a
+/// router, see [`HttpServer`](super::http::HttpServer)
+#[cfg(feature = "http")]
+struct HttpScheme;
+
+#[cfg(feature = "http")]
+#[async_trait]
+impl SchemeHandler for HttpScheme {
+    async fn connect(&self, uri: &Url, config: &RegistryConfig) -> Result<Box<dyn Transport>> {
+        let mut http_config = super::http::HttpConfig::client(uri.as_str());
+        http_config.timeouts = config.timeouts.clone();
+        Ok(Box::new(super::http::HttpTransport::connect(http_config).await?))
+    }
+}
+
+/// `stdio:`, framed as `?framing=` says
+#[cfg(feature = "stdio")]
+struct StdioScheme;
+
+#[cfg(feature = "stdio")]
+#[async_trait]
+impl SchemeHandler for StdioScheme {
+    async fn connect(&self, uri: &Url, _config: &RegistryConfig) -> Result<Box<dyn Transport>> {
+        let config = StdioTransportFactory.parse_uri(uri.as_str())?;
+        Ok(Box::new(super::stdio::StdioTransport::new(config)?))
+    }
+
+    async fn listen(&self, uri: &Url, config: &RegistryConfig) -> Result<Box<dyn TransportListener>> {
+        Ok(Box::new(StdioListener {
+            transport: Some(self.connect(uri, config).await?),
+        }))
+    }
+}
+
+/// Accepts the process's own stdio once, then nothing
+#[cfg(feature = "stdio")]
+struct StdioListener {
+    transport: Option<Box<dyn Transport>>,
+}
+
+#[cfg(feature = "stdio")]
+#[async_trait]
+impl TransportListener for StdioListener {
+    async fn accept(&mut self) -> Result<(Box<dyn Transport>, String)> {
+        match self.transport.take() {
+            Some(transport) => Ok((transport, "stdio".to_string())),
+            None => std::future::pending().await,
+        }
+    }
+}
+
+/// `mock:`
+struct MockScheme;
+
+#[async_trait]
+impl SchemeHandler for MockScheme {
+    async fn connect(&self, _uri: &Url, _config: &RegistryConfig) -> Result<Box<dyn Transport>> {
+        Ok(Box::new(crate::transport::mock::MockTransport::new(MockConfig::default()).await?))
+    }
+}
+
 /// Concrete factory implementations
 pub struct TcpTransportFactory;
 
@@ -419,6 +734,49 @@ impl TransportFactory for TcpTransportFactory {
     }
 }
 
+#[cfg(feature = "stdio")]
+pub struct StdioTransportFactory;
+
+#[cfg(feature = "stdio")]
+#[async_trait]
+impl TransportFactory for StdioTransportFactory {
+    type Transport = crate::transport::stdio::StdioTransport;
+    type Config = crate::transport::stdio::StdioConfig;
+    
+    async fn create(&self, config: Self::Config) -> Result<Self::Transport> {
+        crate::transport::stdio::StdioTransport::new(config)
+    }
+    
+    fn transport_type(&self) -> TransportType {
+        TransportType::Stdio
+    }
+    
+    fn default_config(&self) -> Self::Config {
+        Self::Config::default()
+    }
+    
+    fn parse_uri(&self, uri: &str) -> Result<Self::Config> {
+        let parsed = Url::parse(uri)
+            .map_err(|e| Error::Configuration {
+                message: format!("Invalid stdio URI {}: {}", uri, e),
+                source: Some(Box::new(e)),
+            })?;
+        
+        let mut config = Self::Config::default();
+        for (key, value) in parsed.query_pairs() {
+            if key == "framing" {
+                config.framing = match value.as_ref() {
+                    "content-length" | "lsp" => super::abstraction::FramingType::ContentLength,
+                    "line" | "newline" => super::abstraction::FramingType::LineDelimited,
+                    other => return Err(Error::configuration(format!("Unknown stdio framing: {}", other))),
+                };
+            }
+        }
+        
+        Ok(config)
+    }
+}
+
 pub struct MockTransportFactory;
 
 #[async_trait]
@@ -447,6 +805,7 @@ impl TransportFactory for MockTransportFactory {
 /// Builder for creating a configured transport registry
 pub struct RegistryBuilder {
     config: RegistryConfig,
+    schemes: Vec<(String, Arc<dyn SchemeHandler>)>,
 }
 
 impl Default for RegistryBuilder {
@@ -460,6 +819,7 @@ impl RegistryBuilder {
     pub fn new() -> Self {
         Self {
             config: RegistryConfig::default(),
+            schemes: Vec::new(),
         }
     }
     
@@ -499,15 +859,26 @@ impl RegistryBuilder {
         self
     }
     
+    /// Handle the URIs of a scheme
+    pub fn with_scheme(mut self, scheme: impl Into<String>, handler: Arc<dyn SchemeHandler>) -> Self {
+        self.schemes.push((scheme.into(), handler));
+        self
+    }
+    
     /// Build the registry
     pub async fn build(self) -> Result<TransportRegistry> {
-        TransportRegistry::new(self.config)
+        let registry = TransportRegistry::new(self.config)?;
+        for (scheme, handler) in self.schemes {
+            registry.register_scheme(scheme, handler).await;
+        }
+        Ok(registry)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::time::Duration;
     
     #[tokio::test]
     async fn test_transport_type_conversion() {
@@ -547,6 +918,23 @@ mod tests {
         assert!(!registry.is_supported(&TransportType::WebSocket).await);
     }
     
+    #[cfg(feature = "stdio")]
+    #[tokio::test]
+    async fn test_stdio_registration() {
+        let registry = TransportRegistry::default().unwrap();
+        
+        assert!(registry.list_transport_types().await.contains(&TransportType::Stdio));
+        assert!(registry.is_supported(&TransportType::Stdio).await);
+        assert_eq!(registry.scheme_to_transport_type("stdio").unwrap(), TransportType::Stdio);
+        
+        let transport = registry.create_from_uri("stdio:?framing=content-length").await.unwrap();
+        assert_eq!(transport.metadata()["framing"], "ContentLength");
+        
+        let config = StdioTransportFactory.parse_uri("stdio:?framing=line").unwrap();
+        assert_eq!(config.framing, super::super::abstraction::FramingType::LineDelimited);
+        assert!(StdioTransportFactory.parse_uri("stdio:?framing=xml").is_err());
+    }
+    
     #[tokio::test]
     async fn test_uri_scheme_mapping() {
         let registry = TransportRegistry::default().unwrap();
@@ -584,4 +972,47 @@ mod tests {
         assert_eq!(stats.created_instances, 0);
         assert_eq!(stats.creation_failures, 0);
     }
-} 
\ No newline at end of file
+    
+    #[tokio::test]
+    async fn test_connect_and_listen_by_scheme() {
+        let registry = TransportRegistry::default().unwrap();
+        assert!(registry.schemes().await.contains(&"tcp".to_string()));
+        
+        let mut listener = registry.listen("tcp://127.0.0.1:0").await.unwrap();
+        let uri = format!("tcp://{}", listener.local_addr().unwrap());
+        let (accepted, client) = tokio::join!(listener.accept(), registry.connect(&uri));
+        let (mut server, peer) = accepted.unwrap();
+        let mut client = client.unwrap();
+        assert!(peer.starts_with("127.0.0.1:"));
+        
+        client.send(r#"{"jsonrpc":"2.0","method":"ping","id":1}"#).await.unwrap();
+        assert!(server.receive().await.unwrap().contains("ping"));
+        assert_eq!(registry.stats().await.usage_counts[&TransportType::Tcp], 1);
+        
+        assert!(registry.connect("nope://host:1").await.is_err());
+        assert!(registry.listen("mock:").await.is_err());
+    }
+    
+    /// Answers every `custom:` URI with a mock transport
+    struct CustomScheme;
+    
+    #[async_trait]
+    impl SchemeHandler for CustomScheme {
+        async fn connect(&self, _uri: &Url, _config: &RegistryConfig) -> Result<Box<dyn Transport>> {
+            Ok(Box::new(crate::transport::mock::MockTransport::new(MockConfig::default()).await?))
+        }
+    }
+    
+    #[tokio::test]
+    async fn test_custom_scheme() {
+        let registry = RegistryBuilder::new()
+            .with_scheme("Custom", Arc::new(CustomScheme))
+            .build()
+            .await
+            .unwrap();
+        
+        assert!(registry.connect("custom://anywhere").await.is_ok());
+        let stats = registry.stats().await;
+        assert_eq!(stats.usage_counts[&TransportType::Custom("custom".to_string())], 1);
+    }
+}
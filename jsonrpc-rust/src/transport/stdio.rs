@@ -0,0 +1,383 @@
+//! Stdio transport implementation for JSON-RPC
+//!
+//! This module lets a service run as a subprocess that talks JSON-RPC over
+//! its standard input and output, the way editors drive language servers
+//! and plugin hosts drive their plugins. Messages are framed either one per
+//! line or with LSP-style `Content-Length` headers.
+//!
+//! Reads are buffered inside the transport, so `receive` can be cancelled
+//! (for example by the [`JsonRpcClient`](crate::protocol::JsonRpcClient)
+//! driver) without losing part of a message.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use uuid::Uuid;
+
+use crate::core::error::{Error, Result};
+use crate::core::session::Session;
+use crate::core::traits::Transport;
+use crate::core::types::ServiceContext;
+use crate::protocol::MethodRouter;
+use super::abstraction::{
+    TransportConfig, TimeoutConfig, RetryConfig, ConnectionLimits, FramingType,
+};
+
+/// Longest `Content-Length` header block accepted
+const MAX_HEADER_SIZE: usize = 8 * 1024;
+
+/// Stdio transport configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StdioConfig {
+    /// Message framing, `LineDelimited` or `ContentLength`
+    pub framing: FramingType,
+    /// Timeout configuration
+    pub timeouts: TimeoutConfig,
+    /// Retry configuration
+    pub retry_config: RetryConfig,
+    /// Connection limits; `max_message_size` caps both directions
+    pub connection_limits: ConnectionLimits,
+}
+
+impl Default for StdioConfig {
+    fn default() -> Self {
+        Self {
+            framing: FramingType::LineDelimited,
+            timeouts: TimeoutConfig::default(),
+            retry_config: RetryConfig::default(),
+            connection_limits: ConnectionLimits::default(),
+        }
+    }
+}
+
+impl StdioConfig {
+    /// Configuration using LSP-style `Content-Length` framing
+    pub fn content_length() -> Self {
+        Self::default().with_framing(FramingType::ContentLength)
+    }
+
+    /// Set the message framing
+    pub fn with_framing(mut self, framing: FramingType) -> Self {
+        self.framing = framing;
+        self
+    }
+
+    /// Set the maximum message size
+    pub fn with_max_message_size(mut self, max_message_size: usize) -> Self {
+        self.connection_limits.max_message_size = max_message_size;
+        self
+    }
+}
+
+impl TransportConfig for StdioConfig {
+    fn validate(&self) -> Result<()> {
+        if !matches!(self.framing, FramingType::LineDelimited | FramingType::ContentLength) {
+            return Err(Error::configuration(format!("Unsupported stdio framing: {:?}", self.framing)));
+        }
+
+        if self.connection_limits.max_message_size == 0 {
+            return Err(Error::configuration("Max message size cannot be zero"));
+        }
+
+        Ok(())
+    }
+
+    fn timeouts(&self) -> TimeoutConfig {
+        self.timeouts.clone()
+    }
+
+    fn retry_config(&self) -> RetryConfig {
+        self.retry_config.clone()
+    }
+
+    fn connection_limits(&self) -> ConnectionLimits {
+        self.connection_limits.clone()
+    }
+}
+
+type Reader = Box<dyn AsyncRead + Send + Sync + Unpin>;
+type Writer = Box<dyn AsyncWrite + Send + Sync + Unpin>;
+
+/// Stdio transport implementation
+pub struct StdioTransport {
+    /// Transport ID
+    id: String,
+    /// Transport configuration
+    config: StdioConfig,
+    /// Input stream, stdin unless built with `from_io`
+    reader: Reader,
+    /// Output stream, stdout unless built with `from_io`
+    writer: Writer,
+    /// Bytes read but not yet framed into a message
+    buffer: Vec<u8>,
+    /// Whether the transport has been closed
+    closed: bool,
+    /// Messages sent on this transport
+    messages_sent: u64,
+    /// Messages received on this transport
+    messages_received: u64,
+}
+
+impl StdioTransport {
+    /// Transport over the process's stdin and stdout
+    pub fn new(config: StdioConfig) -> Result<Self> {
+        Self::from_io(tokio::io::stdin(), tokio::io::stdout(), config)
+    }
+
+    /// Transport over arbitrary streams, such as a child process's pipes
+    pub fn from_io<R, W>(reader: R, writer: W, config: StdioConfig) -> Result<Self>
+    where
+        R: AsyncRead + Send + Sync + Unpin + 'static,
+        W: AsyncWrite + Send + Sync + Unpin + 'static,
+    {
+        config.validate()?;
+        Ok(Self {
+            id: Uuid::new_v4().to_string(),
+            config,
+            reader: Box::new(reader),
+            writer: Box::new(writer),
+            buffer: Vec::new(),
+            closed: false,
+            messages_sent: 0,
+            messages_received: 0,
+        })
+    }
+
+    /// Answer incoming requests with `router` until the input ends
+    ///
+    /// This is the main loop of a service run as a subprocess. Reaching
+    /// the end of the input is a normal shutdown and returns `Ok`.
+    pub async fn serve(mut self, router: Arc<MethodRouter>) -> Result<()> {
+        let session = Session::new("stdio".into());
+        while let Some(message) = self.next_message().await? {
+            let context = ServiceContext::new(Uuid::new_v4().to_string())
+                .with_metadata("transport", "stdio".into())
+                .with_session(session.clone());
+            if let Some(response) = router.dispatch_message(&message, &context).await {
+                self.send(&response).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Read the next message, `None` at the end of the input
+    async fn next_message(&mut self) -> Result<Option<String>> {
+        if self.closed {
+            return Err(Error::connection("Stdio transport closed"));
+        }
+
+        loop {
+            if let Some(message) = self.take_frame()? {
+                self.messages_received += 1;
+                return Ok(Some(message));
+            }
+
+            // `read_buf` is cancel safe: bytes land in the buffer or not at all
+            self.buffer.reserve(8 * 1024);
+            if self.reader.read_buf(&mut self.buffer).await? == 0 {
+                if !self.buffer.iter().all(u8::is_ascii_whitespace) {
+                    tracing::warn!("Stdio input ended inside a message");
+                }
+                return Ok(None);
+            }
+        }
+    }
+
+    /// Split one complete message off the front of the buffer
+    fn take_frame(&mut self) -> Result<Option<String>> {
+        let limit = self.config.connection_limits.max_message_size;
+        let frame = match self.config.framing {
+            FramingType::ContentLength => {
+                let Some(header_end) = self.buffer.windows(4).position(|window| window == b"\r\n\r\n") else {
+                    if self.buffer.len() > MAX_HEADER_SIZE {
+                        return Err(Error::transport("Content-Length header block too large"));
+                    }
+                    return Ok(None);
+                };
+
+                let length = content_length(&self.buffer[..header_end])?;
+                if length > limit {
+                    return Err(Error::validation(format!(
+                        "Message of {} bytes exceeds the {} byte limit", length, limit
+                    )));
+                }
+                let body_start = header_end + 4;
+                if self.buffer.len() < body_start + length {
+                    return Ok(None);
+                }
+
+                let frame: Vec<u8> = self.buffer.drain(..body_start + length).collect();
+                frame[body_start..].to_vec()
+            }
+            _ => loop {
+                let Some(newline) = self.buffer.iter().position(|&byte| byte == b'\n') else {
+                    if self.buffer.len() > limit {
+                        return Err(Error::validation(format!("Line exceeds the {} byte limit", limit)));
+                    }
+                    return Ok(None);
+                };
+
+                let mut line: Vec<u8> = self.buffer.drain(..=newline).collect();
+                line.pop();
+                if line.last() == Some(&b'\r') {
+                    line.pop();
+                }
+                if line.len() > limit {
+                    return Err(Error::validation(format!("Line exceeds the {} byte limit", limit)));
+                }
+                // Blank lines between messages are tolerated
+                if !line.iter().all(u8::is_ascii_whitespace) {
+                    break line;
+                }
+            },
+        };
+
+        String::from_utf8(frame)
+            .map(Some)
+            .map_err(|e| Error::transport(format!("Invalid UTF-8 in stdio message: {}", e)))
+    }
+}
+
+/// Parse the `Content-Length` value out of a header block
+fn content_length(headers: &[u8]) -> Result<usize> {
+    let headers = std::str::from_utf8(headers)
+        .map_err(|e| Error::transport(format!("Invalid UTF-8 in message headers: {}", e)))?;
+
+    headers.split("\r\n")
+        .filter_map(|line| line.split_once(':'))
+        .find(|(name, _)| name.trim().eq_ignore_ascii_case("content-length"))
+        .ok_or_else(|| Error::transport("Missing Content-Length header"))?
+        .1.trim().parse()
+        .map_err(|e| Error::transport(format!("Invalid Content-Length header: {}", e)))
+}
+
+#[async_trait]
+impl Transport for StdioTransport {
+    async fn send(&mut self, message: &str) -> Result<()> {
+        if self.closed {
+            return Err(Error::connection("Stdio transport closed"));
+        }
+
+        let limit = self.config.connection_limits.max_message_size;
+        if message.len() > limit {
+            return Err(Error::validation(format!(
+                "Message of {} bytes exceeds the {} byte limit", message.len(), limit
+            )));
+        }
+
+        match self.config.framing {
+            FramingType::ContentLength => {
+                let header = format!("Content-Length: {}\r\n\r\n", message.len());
+                self.writer.write_all(header.as_bytes()).await?;
+                self.writer.write_all(message.as_bytes()).await?;
+            }
+            _ => {
+                if message.contains('\n') {
+                    return Err(Error::validation("Line-delimited messages cannot contain newlines"));
+                }
+                self.writer.write_all(message.as_bytes()).await?;
+                self.writer.write_all(b"\n").await?;
+            }
+        }
+        self.writer.flush().await?;
+        self.messages_sent += 1;
+        Ok(())
+    }
+
+    async fn receive(&mut self) -> Result<String> {
+        self.next_message().await?
+            .ok_or_else(|| Error::connection("Stdio input closed"))
+    }
+
+    async fn close(&mut self) -> Result<()> {
+        if !self.closed {
+            self.closed = true;
+            self.writer.shutdown().await?;
+        }
+        Ok(())
+    }
+
+    fn metadata(&self) -> HashMap<String, serde_json::Value> {
+        let mut metadata = HashMap::new();
+        metadata.insert("id".to_string(), self.id.clone().into());
+        metadata.insert("protocol".to_string(), "stdio".into());
+        metadata.insert("framing".to_string(), format!("{:?}", self.config.framing).into());
+        metadata.insert("messages_sent".to_string(), self.messages_sent.into());
+        metadata.insert("messages_received".to_string(), self.messages_received.into());
+        metadata
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::traits::MethodHandler;
+    use crate::core::types::{JsonRpcRequest, JsonRpcResponse};
+    use crate::protocol::JsonRpcClient;
+    use serde_json::{json, Value};
+
+    struct EchoHandler;
+
+    #[async_trait]
+    impl MethodHandler for EchoHandler {
+        async fn handle_method(&self, request: &JsonRpcRequest, _context: &ServiceContext) -> Result<JsonRpcResponse> {
+            Ok(JsonRpcResponse::success(Value::Null, request.params.clone().unwrap_or(Value::Null)))
+        }
+
+        fn supported_methods(&self) -> Vec<String> {
+            vec!["echo".to_string()]
+        }
+    }
+
+    #[tokio::test]
+    async fn test_line_framing() {
+        let (input, mut feed) = tokio::io::duplex(1024);
+        let mut transport = StdioTransport::from_io(input, tokio::io::sink(), StdioConfig::default().with_max_message_size(16)).unwrap();
+
+        feed.write_all(b"\n{\"a\":1}\r\n  \n{\"b\"").await.unwrap();
+        assert_eq!(transport.receive().await.unwrap(), r#"{"a":1}"#);
+        feed.write_all(b":2}\n").await.unwrap();
+        assert_eq!(transport.receive().await.unwrap(), r#"{"b":2}"#);
+
+        assert!(transport.send("{\n}").await.is_err());
+        feed.write_all(&[b'x'; 32]).await.unwrap();
+        assert!(matches!(transport.receive().await, Err(Error::Validation { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_content_length_framing() {
+        let (input, mut feed) = tokio::io::duplex(1024);
+        let mut transport = StdioTransport::from_io(input, tokio::io::sink(), StdioConfig::content_length()).unwrap();
+
+        feed.write_all(b"Content-Type: application/json\r\ncontent-length: 7\r\n\r\n{\"a\":1}Content-Length: 7\r\n\r\n").await.unwrap();
+        assert_eq!(transport.receive().await.unwrap(), r#"{"a":1}"#);
+        feed.write_all(b"{\"b\":2}").await.unwrap();
+        assert_eq!(transport.receive().await.unwrap(), r#"{"b":2}"#);
+
+        drop(feed);
+        assert!(transport.receive().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_serve_as_subprocess() {
+        let (client_io, server_io) = tokio::io::duplex(4096);
+        let (server_read, server_write) = tokio::io::split(server_io);
+        let (client_read, client_write) = tokio::io::split(client_io);
+
+        let router = Arc::new(MethodRouter::new().with_handler(Arc::new(EchoHandler)).unwrap());
+        let server = StdioTransport::from_io(server_read, server_write, StdioConfig::content_length()).unwrap();
+        let service = tokio::spawn(server.serve(router));
+
+        let transport = StdioTransport::from_io(client_read, client_write, StdioConfig::content_length()).unwrap();
+        let client = JsonRpcClient::new(transport);
+        let echoed: Value = client.call("echo", json!({ "hello": "world" })).await.unwrap();
+        assert_eq!(echoed, json!({ "hello": "world" }));
+
+        // Closing our end is end-of-input for the service
+        client.close().await.unwrap();
+        service.await.unwrap().unwrap();
+    }
+}
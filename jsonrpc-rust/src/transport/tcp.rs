@@ -2,16 +2,19 @@
 //! 
 //! This module provides a TCP-based transport for JSON-RPC communication
 //! with support for connection pooling, message framing, and automatic
-//! reconnection.
+//! reconnection. With the `tls` feature, connections can be encrypted
+//! (and mutually authenticated) by setting [`TcpConfig::tls`].
 
 use std::collections::HashMap;
 use std::net::SocketAddr;
+use std::pin::Pin;
 use std::sync::Arc;
+use std::task::{Context, Poll};
 use std::time::{Duration, Instant};
 use async_trait::async_trait;
 use futures::{SinkExt, StreamExt};
 use serde::{Deserialize, Serialize};
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, ReadBuf};
 use tokio::net::{TcpListener, TcpStream};
 use tokio::sync::{mpsc, RwLock, Mutex};
 use tokio_util::codec::{Framed, LengthDelimitedCodec, LinesCodec};
@@ -25,6 +28,8 @@ use super::abstraction::{
     TimeoutConfig, RetryConfig, ConnectionLimits, FramingType,
     DefaultMessageCodec,
 };
+#[cfg(feature = "tls")]
+use super::tls::TlsConfig;
 
 /// TCP transport implementation
 pub struct TcpTransport {
@@ -38,6 +43,9 @@ pub struct TcpTransport {
     stats: Arc<RwLock<TransportStats>>,
     /// Active connections
     connections: Arc<RwLock<HashMap<String, Arc<RwLock<TcpConnection>>>>>,
+    /// TLS acceptor for server mode, built once from `config.tls`
+    #[cfg(feature = "tls")]
+    tls_acceptor: Option<tokio_rustls::TlsAcceptor>,
 }
 
 /// TCP transport configuration
@@ -59,6 +67,10 @@ pub struct TcpConfig {
     pub no_delay: bool,
     /// Keep-alive settings
     pub keep_alive: Option<Duration>,
+    /// TLS settings, `None` for plain TCP
+    #[cfg(feature = "tls")]
+    #[serde(default)]
+    pub tls: Option<TlsConfig>,
 }
 
 impl Default for TcpConfig {
@@ -72,6 +84,8 @@ impl Default for TcpConfig {
             framing: FramingType::LengthPrefixed,
             no_delay: true,
             keep_alive: Some(Duration::from_secs(60)),
+            #[cfg(feature = "tls")]
+            tls: None,
         }
     }
 }
@@ -92,6 +106,11 @@ impl TransportConfig for TcpConfig {
             });
         }
         
+        #[cfg(feature = "tls")]
+        if let Some(ref tls) = self.tls {
+            tls.validate()?;
+        }
+        
         Ok(())
     }
     
@@ -108,12 +127,74 @@ impl TransportConfig for TcpConfig {
     }
 }
 
+/// A TCP stream, encrypted or not
+pub enum MaybeTlsStream {
+    /// Plain TCP
+    Plain(TcpStream),
+    /// TCP wrapped in TLS
+    #[cfg(feature = "tls")]
+    Tls(Box<tokio_rustls::TlsStream<TcpStream>>),
+}
+
+impl MaybeTlsStream {
+    /// Whether the stream is encrypted
+    pub fn is_tls(&self) -> bool {
+        !matches!(self, MaybeTlsStream::Plain(_))
+    }
+    
+    /// ALPN protocol agreed during the TLS handshake
+    pub fn alpn_protocol(&self) -> Option<String> {
+        match self {
+            MaybeTlsStream::Plain(_) => None,
+            #[cfg(feature = "tls")]
+            MaybeTlsStream::Tls(stream) => stream.get_ref().1.alpn_protocol()
+                .map(|protocol| String::from_utf8_lossy(protocol).into_owned()),
+        }
+    }
+}
+
+impl AsyncRead for MaybeTlsStream {
+    fn poll_read(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<std::io::Result<()>> {
+        match self.get_mut() {
+            MaybeTlsStream::Plain(stream) => Pin::new(stream).poll_read(cx, buf),
+            #[cfg(feature = "tls")]
+            MaybeTlsStream::Tls(stream) => Pin::new(stream.as_mut()).poll_read(cx, buf),
+        }
+    }
+}
+
+impl AsyncWrite for MaybeTlsStream {
+    fn poll_write(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<std::io::Result<usize>> {
+        match self.get_mut() {
+            MaybeTlsStream::Plain(stream) => Pin::new(stream).poll_write(cx, buf),
+            #[cfg(feature = "tls")]
+            MaybeTlsStream::Tls(stream) => Pin::new(stream.as_mut()).poll_write(cx, buf),
+        }
+    }
+    
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        match self.get_mut() {
+            MaybeTlsStream::Plain(stream) => Pin::new(stream).poll_flush(cx),
+            #[cfg(feature = "tls")]
+            MaybeTlsStream::Tls(stream) => Pin::new(stream.as_mut()).poll_flush(cx),
+        }
+    }
+    
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        match self.get_mut() {
+            MaybeTlsStream::Plain(stream) => Pin::new(stream).poll_shutdown(cx),
+            #[cfg(feature = "tls")]
+            MaybeTlsStream::Tls(stream) => Pin::new(stream.as_mut()).poll_shutdown(cx),
+        }
+    }
+}
+
 /// TCP connection implementation
 pub struct TcpConnection {
     /// Connection ID
     id: String,
-    /// TCP stream
-    stream: Option<TcpStream>,
+    /// TCP stream, possibly wrapped in TLS
+    stream: Option<MaybeTlsStream>,
     /// Remote address
     remote_addr: Option<SocketAddr>,
     /// Local address
@@ -152,9 +233,28 @@ impl TcpConnection {
     
     /// Create from existing TCP stream
     pub async fn from_stream(stream: TcpStream) -> Result<Self> {
-        let id = Uuid::new_v4().to_string();
         let remote_addr = stream.peer_addr().ok();
         let local_addr = stream.local_addr().ok();
+        Ok(Self::from_parts(MaybeTlsStream::Plain(stream), remote_addr, local_addr))
+    }
+    
+    /// Complete the server side of a TLS handshake on an accepted stream
+    #[cfg(feature = "tls")]
+    pub async fn from_tls_stream(stream: TcpStream, acceptor: &tokio_rustls::TlsAcceptor) -> Result<Self> {
+        let remote_addr = stream.peer_addr().ok();
+        let local_addr = stream.local_addr().ok();
+        let stream = acceptor.accept(stream).await
+            .map_err(|e| Error::Transport {
+                message: format!("TLS handshake with {:?} failed: {}", remote_addr, e),
+                source: Some(Box::new(e)),
+            })?;
+        
+        let stream = MaybeTlsStream::Tls(Box::new(tokio_rustls::TlsStream::Server(stream)));
+        Ok(Self::from_parts(stream, remote_addr, local_addr))
+    }
+    
+    fn from_parts(stream: MaybeTlsStream, remote_addr: Option<SocketAddr>, local_addr: Option<SocketAddr>) -> Self {
+        let id = Uuid::new_v4().to_string();
         let now = chrono::Utc::now();
         
         let mut connection = Self::new(id.clone());
@@ -173,7 +273,7 @@ impl TcpConnection {
             messages_received: 0,
         };
         
-        Ok(connection)
+        connection
     }
     
     /// Connect to a remote address
@@ -202,6 +302,24 @@ impl TcpConnection {
         
         self.remote_addr = stream.peer_addr().ok();
         self.local_addr = stream.local_addr().ok();
+        
+        #[cfg(feature = "tls")]
+        let stream = match config.tls {
+            Some(ref tls) => {
+                let handshake = tls.connector()?.connect(tls.server_name_for(addr)?, stream);
+                let stream = tokio::time::timeout(config.timeouts.connect_timeout, handshake).await
+                    .map_err(|_| Error::timeout(format!("TLS handshake with {}", addr), config.timeouts.connect_timeout))?
+                    .map_err(|e| Error::Transport {
+                        message: format!("TLS handshake with {} failed: {}", addr, e),
+                        source: Some(Box::new(e)),
+                    })?;
+                MaybeTlsStream::Tls(Box::new(tokio_rustls::TlsStream::Client(stream)))
+            }
+            None => MaybeTlsStream::Plain(stream),
+        };
+        #[cfg(not(feature = "tls"))]
+        let stream = MaybeTlsStream::Plain(stream);
+        
         self.stream = Some(stream);
         self.state = ConnectionState::Connected;
         self.info.state = ConnectionState::Connected;
@@ -293,6 +411,13 @@ impl Connection for TcpConnection {
         info.insert("protocol".to_string(), "tcp".into());
         info.insert("state".to_string(), format!("{:?}", self.state).into());
         
+        if let Some(ref stream) = self.stream {
+            info.insert("tls".to_string(), stream.is_tls().into());
+            if let Some(protocol) = stream.alpn_protocol() {
+                info.insert("alpn_protocol".to_string(), protocol.into());
+            }
+        }
+        
         if let Some(addr) = self.remote_addr {
             info.insert("remote_addr".to_string(), addr.to_string().into());
         }
@@ -403,12 +528,20 @@ impl TcpTransport {
         let stats = Arc::new(RwLock::new(TransportStats::default()));
         let connections = Arc::new(RwLock::new(HashMap::new()));
         
+        #[cfg(feature = "tls")]
+        let tls_acceptor = match config.tls {
+            Some(ref tls) if config.bind_address.is_some() => Some(tls.acceptor()?),
+            _ => None,
+        };
+        
         Ok(Self {
             config,
             connection_manager,
             codec,
             stats,
             connections,
+            #[cfg(feature = "tls")]
+            tls_acceptor,
         })
     }
     
@@ -453,6 +586,16 @@ impl TcpTransport {
                 source: Some(Box::new(e)),
             })?;
         
+        #[cfg(feature = "tls")]
+        let connection = match self.tls_acceptor {
+            Some(ref acceptor) => {
+                let handshake = TcpConnection::from_tls_stream(stream, acceptor);
+                tokio::time::timeout(self.config.timeouts.connect_timeout, handshake).await
+                    .map_err(|_| Error::timeout(format!("TLS handshake with {}", addr), self.config.timeouts.connect_timeout))??
+            }
+            None => TcpConnection::from_stream(stream).await?,
+        };
+        #[cfg(not(feature = "tls"))]
         let connection = TcpConnection::from_stream(stream).await?;
         let connection_id = connection.id.clone();
         
@@ -623,6 +766,8 @@ impl Transport for TcpTransport {
         metadata.insert("protocol".to_string(), "tcp".into());
         metadata.insert("framing".to_string(), format!("{:?}", self.config.framing).into());
         metadata.insert("no_delay".to_string(), self.config.no_delay.into());
+        #[cfg(feature = "tls")]
+        metadata.insert("tls".to_string(), self.config.tls.is_some().into());
         
         if let Some(addr) = self.config.bind_address {
             metadata.insert("bind_address".to_string(), addr.to_string().into());
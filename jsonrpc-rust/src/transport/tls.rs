@@ -0,0 +1,314 @@
+//! TLS support for the TCP transport
+//!
+//! This module builds rustls client and server configurations from a
+//! [`TlsConfig`], which the TCP transport uses to wrap its streams.
+//! Certificates and keys are read from PEM files. A server can also
+//! require client certificates signed by a given CA (mutual TLS).
+
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use serde::{Deserialize, Serialize};
+use tokio_rustls::rustls::{self, pki_types};
+use tokio_rustls::{TlsAcceptor, TlsConnector};
+
+use crate::core::error::{Error, Result};
+
+/// TLS configuration for a TCP client or server
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TlsConfig {
+    /// PEM certificate chain presented to the peer; required for servers,
+    /// and for clients taking part in mutual TLS
+    pub cert_path: Option<PathBuf>,
+    /// PEM private key matching `cert_path`
+    pub key_path: Option<PathBuf>,
+    /// PEM CA bundle: clients verify the server against it (the webpki
+    /// roots are used when unset), servers verify client certificates
+    pub ca_path: Option<PathBuf>,
+    /// Reject clients that do not present a certificate signed by `ca_path`
+    pub require_client_auth: bool,
+    /// Name sent as SNI and checked against the server certificate;
+    /// defaults to the server IP address
+    pub server_name: Option<String>,
+    /// ALPN protocols, in order of preference
+    pub alpn_protocols: Vec<String>,
+}
+
+impl TlsConfig {
+    /// Server configuration presenting the given certificate chain and key
+    pub fn server(cert_path: impl Into<PathBuf>, key_path: impl Into<PathBuf>) -> Self {
+        Self {
+            cert_path: Some(cert_path.into()),
+            key_path: Some(key_path.into()),
+            ..Self::default()
+        }
+    }
+
+    /// Client configuration trusting the given CA bundle
+    pub fn client(ca_path: impl Into<PathBuf>) -> Self {
+        Self {
+            ca_path: Some(ca_path.into()),
+            ..Self::default()
+        }
+    }
+
+    /// Present a certificate chain and key (client side of mutual TLS)
+    pub fn with_identity(mut self, cert_path: impl Into<PathBuf>, key_path: impl Into<PathBuf>) -> Self {
+        self.cert_path = Some(cert_path.into());
+        self.key_path = Some(key_path.into());
+        self
+    }
+
+    /// Require client certificates signed by `ca_path` (server side of mutual TLS)
+    pub fn with_client_auth(mut self, ca_path: impl Into<PathBuf>) -> Self {
+        self.ca_path = Some(ca_path.into());
+        self.require_client_auth = true;
+        self
+    }
+
+    /// Set the SNI name
+    pub fn with_server_name(mut self, server_name: impl Into<String>) -> Self {
+        self.server_name = Some(server_name.into());
+        self
+    }
+
+    /// Set the ALPN protocols
+    pub fn with_alpn_protocols<I, S>(mut self, protocols: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.alpn_protocols = protocols.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Check the configuration is complete
+    pub fn validate(&self) -> Result<()> {
+        if self.cert_path.is_some() != self.key_path.is_some() {
+            return Err(Error::configuration("TLS cert_path and key_path must be set together"));
+        }
+
+        if self.require_client_auth && self.ca_path.is_none() {
+            return Err(Error::configuration("TLS client authentication requires ca_path"));
+        }
+
+        if self.alpn_protocols.iter().any(|protocol| protocol.is_empty() || protocol.len() > 255) {
+            return Err(Error::configuration("ALPN protocol names must be 1 to 255 bytes"));
+        }
+
+        Ok(())
+    }
+
+    /// Build the connector used by TCP clients
+    pub fn connector(&self) -> Result<TlsConnector> {
+        self.validate()?;
+
+        let roots = match self.ca_path {
+            Some(ref path) => root_store(path)?,
+            None => rustls::RootCertStore {
+                roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+            },
+        };
+        let builder = rustls::ClientConfig::builder_with_provider(provider())
+            .with_safe_default_protocol_versions()
+            .map_err(tls_error)?
+            .with_root_certificates(roots);
+
+        let mut config = match (&self.cert_path, &self.key_path) {
+            (Some(cert), Some(key)) => builder
+                .with_client_auth_cert(load_certs(cert)?, load_key(key)?)
+                .map_err(tls_error)?,
+            _ => builder.with_no_client_auth(),
+        };
+        config.alpn_protocols = self.alpn();
+
+        Ok(TlsConnector::from(Arc::new(config)))
+    }
+
+    /// Build the acceptor used by TCP servers
+    pub fn acceptor(&self) -> Result<TlsAcceptor> {
+        self.validate()?;
+
+        let (Some(cert), Some(key)) = (&self.cert_path, &self.key_path) else {
+            return Err(Error::configuration("TLS server requires cert_path and key_path"));
+        };
+        let builder = rustls::ServerConfig::builder_with_provider(provider())
+            .with_safe_default_protocol_versions()
+            .map_err(tls_error)?;
+
+        let builder = match self.ca_path {
+            Some(ref ca) => {
+                let verifier = rustls::server::WebPkiClientVerifier::builder_with_provider(
+                    Arc::new(root_store(ca)?),
+                    provider(),
+                );
+                let verifier = if self.require_client_auth {
+                    verifier
+                } else {
+                    verifier.allow_unauthenticated()
+                };
+                builder.with_client_cert_verifier(verifier.build().map_err(tls_error)?)
+            }
+            None => builder.with_no_client_auth(),
+        };
+
+        let mut config = builder
+            .with_single_cert(load_certs(cert)?, load_key(key)?)
+            .map_err(tls_error)?;
+        config.alpn_protocols = self.alpn();
+
+        Ok(TlsAcceptor::from(Arc::new(config)))
+    }
+
+    /// Name to verify the server as, given the address being connected to
+    pub(crate) fn server_name_for(&self, addr: std::net::SocketAddr) -> Result<pki_types::ServerName<'static>> {
+        match self.server_name {
+            Some(ref name) => pki_types::ServerName::try_from(name.clone())
+                .map_err(|e| Error::configuration(format!("Invalid TLS server name {}: {}", name, e))),
+            None => Ok(pki_types::ServerName::IpAddress(addr.ip().into())),
+        }
+    }
+
+    fn alpn(&self) -> Vec<Vec<u8>> {
+        self.alpn_protocols.iter().map(|protocol| protocol.as_bytes().to_vec()).collect()
+    }
+}
+
+fn provider() -> Arc<rustls::crypto::CryptoProvider> {
+    Arc::new(rustls::crypto::ring::default_provider())
+}
+
+fn tls_error(e: impl std::fmt::Display) -> Error {
+    Error::configuration(format!("Invalid TLS configuration: {}", e))
+}
+
+fn open(path: &Path) -> Result<BufReader<std::fs::File>> {
+    std::fs::File::open(path)
+        .map(BufReader::new)
+        .map_err(|e| Error::configuration(format!("Failed to open {}: {}", path.display(), e)))
+}
+
+fn load_certs(path: &Path) -> Result<Vec<pki_types::CertificateDer<'static>>> {
+    let certs = rustls_pemfile::certs(&mut open(path)?)
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .map_err(|e| Error::configuration(format!("Invalid certificate in {}: {}", path.display(), e)))?;
+    if certs.is_empty() {
+        return Err(Error::configuration(format!("No certificates found in {}", path.display())));
+    }
+    Ok(certs)
+}
+
+fn load_key(path: &Path) -> Result<pki_types::PrivateKeyDer<'static>> {
+    rustls_pemfile::private_key(&mut open(path)?)
+        .map_err(|e| Error::configuration(format!("Invalid private key in {}: {}", path.display(), e)))?
+        .ok_or_else(|| Error::configuration(format!("No private key found in {}", path.display())))
+}
+
+fn root_store(path: &Path) -> Result<rustls::RootCertStore> {
+    let mut roots = rustls::RootCertStore::empty();
+    let (_, ignored) = roots.add_parsable_certificates(load_certs(path)?);
+    if roots.is_empty() {
+        return Err(Error::configuration(format!("No usable CA certificates in {}", path.display())));
+    }
+    if ignored > 0 {
+        tracing::warn!("Ignored {} unparsable CA certificates in {}", ignored, path.display());
+    }
+    Ok(roots)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::traits::Connection;
+    use crate::transport::tcp::{TcpConfig, TcpConnection};
+    use std::net::SocketAddr;
+    use tokio::net::TcpListener;
+    use tokio::task::JoinHandle;
+
+    fn cert(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/certs").join(name)
+    }
+
+    fn server_tls() -> TlsConfig {
+        TlsConfig::server(cert("server.pem"), cert("server.key"))
+            .with_client_auth(cert("ca.pem"))
+            .with_alpn_protocols(["jsonrpc/2.0"])
+    }
+
+    fn client_tls() -> TlsConfig {
+        TlsConfig::client(cert("ca.pem"))
+            .with_identity(cert("client.pem"), cert("client.key"))
+            .with_server_name("localhost")
+            .with_alpn_protocols(["jsonrpc/2.0"])
+    }
+
+    /// Accept one connection and complete the server side of the handshake
+    async fn tls_server() -> (SocketAddr, JoinHandle<Result<TcpConnection>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let acceptor = server_tls().acceptor().unwrap();
+        let accepted = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await?;
+            TcpConnection::from_tls_stream(stream, &acceptor).await
+        });
+        (addr, accepted)
+    }
+
+    async fn connect(addr: SocketAddr, tls: TlsConfig) -> Result<TcpConnection> {
+        let config = TcpConfig {
+            tls: Some(tls),
+            ..TcpConfig::default()
+        };
+        let mut connection = TcpConnection::new("client".to_string());
+        connection.connect_to(addr, &config).await?;
+        Ok(connection)
+    }
+
+    #[test]
+    fn test_tls_config_validation() {
+        assert!(server_tls().validate().is_ok());
+        assert!(TlsConfig { require_client_auth: true, ..TlsConfig::default() }.validate().is_err());
+        assert!(TlsConfig { cert_path: Some(cert("server.pem")), ..TlsConfig::default() }.validate().is_err());
+        assert!(TlsConfig::default().with_alpn_protocols([""]).validate().is_err());
+        assert!(TlsConfig::default().acceptor().is_err());
+        assert!(TlsConfig::server(cert("missing.pem"), cert("server.key")).acceptor().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_mutual_tls_round_trip() {
+        let (addr, accepted) = tls_server().await;
+        let mut client = connect(addr, client_tls()).await.unwrap();
+        let mut server = accepted.await.unwrap().unwrap();
+
+        client.send_data(b"ping\n").await.unwrap();
+        let mut buffer = [0u8; 16];
+        let read = server.receive_data(&mut buffer).await.unwrap();
+        assert_eq!(&buffer[..read], b"ping\n");
+
+        for info in [client.connection_info(), server.connection_info()] {
+            assert_eq!(info["tls"], true);
+            assert_eq!(info["alpn_protocol"], "jsonrpc/2.0");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_peer_verification() {
+        // Without a client certificate the server rejects the handshake
+        let (addr, accepted) = tls_server().await;
+        let anonymous = TlsConfig::client(cert("ca.pem")).with_server_name("localhost");
+        let _ = connect(addr, anonymous).await;
+        assert!(accepted.await.unwrap().is_err());
+
+        // The server certificate is not valid for other names
+        let (addr, _accepted) = tls_server().await;
+        assert!(connect(addr, client_tls().with_server_name("example.com")).await.is_err());
+
+        // Nor is it trusted without the test CA
+        let (addr, _accepted) = tls_server().await;
+        let untrusted = TlsConfig::default()
+            .with_identity(cert("client.pem"), cert("client.key"))
+            .with_server_name("localhost");
+        assert!(connect(addr, untrusted).await.is_err());
+    }
+}
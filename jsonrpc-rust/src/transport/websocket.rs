@@ -0,0 +1,704 @@
+//! WebSocket transport implementation for JSON-RPC
+//!
+//! This module provides a WebSocket transport built on tokio-tungstenite.
+//! Each JSON-RPC message travels as one text frame. A connection is driven
+//! by a background task that answers pings, sends keepalive pings of its
+//! own and performs the close handshake, so the peer is detected as gone
+//! when it stops answering even if the TCP connection stays open.
+//!
+//! [`WebSocketTransport`] is the client side; [`WebSocketServer`] accepts
+//! connections and can serve a [`MethodRouter`] on each of them.
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use async_trait::async_trait;
+use futures::{SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::TcpListener;
+use tokio::sync::{mpsc, oneshot};
+use tokio::time::Instant;
+use tokio_tungstenite::tungstenite::protocol::WebSocketConfig as ProtocolConfig;
+use tokio_tungstenite::tungstenite::Message as Frame;
+use tokio_tungstenite::WebSocketStream;
+use uuid::Uuid;
+
+use crate::core::error::{Error, Result};
+use crate::core::session::Session;
+use crate::core::traits::{Transport, Connection};
+use crate::core::types::ServiceContext;
+use crate::protocol::MethodRouter;
+use super::abstraction::{
+    TransportConfig, ConnectionState, TimeoutConfig, RetryConfig, ConnectionLimits,
+};
+
+/// WebSocket transport configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebSocketConfig {
+    /// Server URL for client connections (`ws://host:port/path`)
+    pub url: Option<String>,
+    /// Bind address for server mode
+    pub bind_address: Option<SocketAddr>,
+    /// Timeout configuration
+    pub timeouts: TimeoutConfig,
+    /// Retry configuration
+    pub retry_config: RetryConfig,
+    /// Connection limits; `max_message_size` also caps frame size
+    pub connection_limits: ConnectionLimits,
+    /// Interval between keepalive pings, `None` to disable them
+    pub ping_interval: Option<Duration>,
+    /// Time to wait for the peer to answer a keepalive ping
+    pub pong_timeout: Duration,
+    /// Time to wait for the peer to answer a close frame
+    pub close_timeout: Duration,
+}
+
+impl Default for WebSocketConfig {
+    fn default() -> Self {
+        Self {
+            url: None,
+            bind_address: None,
+            timeouts: TimeoutConfig::default(),
+            retry_config: RetryConfig::default(),
+            connection_limits: ConnectionLimits::default(),
+            ping_interval: Some(Duration::from_secs(30)),
+            pong_timeout: Duration::from_secs(10),
+            close_timeout: Duration::from_secs(5),
+        }
+    }
+}
+
+impl WebSocketConfig {
+    /// Configuration for a client connecting to `url`
+    pub fn client(url: impl Into<String>) -> Self {
+        Self {
+            url: Some(url.into()),
+            ..Self::default()
+        }
+    }
+
+    /// Configuration for a server listening on `bind_address`
+    pub fn server(bind_address: SocketAddr) -> Self {
+        Self {
+            bind_address: Some(bind_address),
+            ..Self::default()
+        }
+    }
+
+    /// Set the keepalive ping interval
+    pub fn with_ping_interval(mut self, interval: Option<Duration>) -> Self {
+        self.ping_interval = interval;
+        self
+    }
+
+    /// Set the time to wait for a keepalive pong
+    pub fn with_pong_timeout(mut self, timeout: Duration) -> Self {
+        self.pong_timeout = timeout;
+        self
+    }
+
+    /// Set the maximum message and frame size
+    pub fn with_max_message_size(mut self, max_message_size: usize) -> Self {
+        self.connection_limits.max_message_size = max_message_size;
+        self
+    }
+
+    fn protocol_config(&self) -> ProtocolConfig {
+        let max_size = self.connection_limits.max_message_size;
+        ProtocolConfig {
+            max_message_size: Some(max_size),
+            max_frame_size: Some(max_size),
+            ..ProtocolConfig::default()
+        }
+    }
+}
+
+impl TransportConfig for WebSocketConfig {
+    fn validate(&self) -> Result<()> {
+        if let Some(ref url) = self.url {
+            let parsed = url::Url::parse(url)
+                .map_err(|e| Error::configuration(format!("Invalid WebSocket URL {}: {}", url, e)))?;
+            if !matches!(parsed.scheme(), "ws" | "wss") {
+                return Err(Error::configuration(format!("Unsupported WebSocket URL scheme: {}", parsed.scheme())));
+            }
+        }
+
+        if self.timeouts.connect_timeout.is_zero() {
+            return Err(Error::configuration("Connect timeout cannot be zero"));
+        }
+
+        if self.connection_limits.max_message_size == 0 {
+            return Err(Error::configuration("Max message size cannot be zero"));
+        }
+
+        if self.ping_interval.is_some_and(|interval| interval.is_zero()) {
+            return Err(Error::configuration("Ping interval cannot be zero"));
+        }
+
+        Ok(())
+    }
+
+    fn timeouts(&self) -> TimeoutConfig {
+        self.timeouts.clone()
+    }
+
+    fn retry_config(&self) -> RetryConfig {
+        self.retry_config.clone()
+    }
+
+    fn connection_limits(&self) -> ConnectionLimits {
+        self.connection_limits.clone()
+    }
+}
+
+/// Requests from a connection to its driver task
+enum Command {
+    Send(String, oneshot::Sender<Result<()>>),
+    Ping(oneshot::Sender<Result<()>>),
+    Close(oneshot::Sender<Result<()>>),
+}
+
+/// WebSocket connection implementation
+pub struct WebSocketConnection {
+    /// Connection ID
+    id: String,
+    /// Connection configuration
+    config: WebSocketConfig,
+    /// Remote address, when known
+    remote_addr: Option<SocketAddr>,
+    /// Connection state
+    state: ConnectionState,
+    /// Channel to the driver task
+    commands: Option<mpsc::UnboundedSender<Command>>,
+    /// Text messages received by the driver task
+    incoming: Option<mpsc::UnboundedReceiver<Result<String>>>,
+    /// Messages sent on this connection
+    messages_sent: u64,
+    /// Messages received on this connection
+    messages_received: u64,
+    /// Last error
+    last_error: Option<Error>,
+}
+
+impl WebSocketConnection {
+    /// Create a disconnected client connection
+    pub fn new(config: WebSocketConfig) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            config,
+            remote_addr: None,
+            state: ConnectionState::Disconnected,
+            commands: None,
+            incoming: None,
+            messages_sent: 0,
+            messages_received: 0,
+            last_error: None,
+        }
+    }
+
+    /// Take over an established WebSocket stream
+    pub fn from_stream<S>(stream: WebSocketStream<S>, config: WebSocketConfig, remote_addr: Option<SocketAddr>) -> Self
+    where
+        S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    {
+        let mut connection = Self::new(config);
+        connection.remote_addr = remote_addr;
+        connection.start(stream);
+        connection
+    }
+
+    /// Connection ID
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Remote address, when known
+    pub fn remote_addr(&self) -> Option<SocketAddr> {
+        self.remote_addr
+    }
+
+    /// Send a text message
+    pub async fn send_text(&mut self, message: &str) -> Result<()> {
+        let limit = self.config.connection_limits.max_message_size;
+        if message.len() > limit {
+            return Err(Error::validation(format!(
+                "Message of {} bytes exceeds the {} byte limit", message.len(), limit
+            )));
+        }
+
+        self.request(|ack| Command::Send(message.to_string(), ack)).await?;
+        self.messages_sent += 1;
+        Ok(())
+    }
+
+    /// Receive the next text message
+    pub async fn receive_text(&mut self) -> Result<String> {
+        let incoming = self.incoming.as_mut()
+            .ok_or_else(|| Error::connection("WebSocket connection not established"))?;
+
+        match incoming.recv().await {
+            Some(Ok(message)) => {
+                self.messages_received += 1;
+                Ok(message)
+            }
+            Some(Err(e)) => {
+                self.last_error = Some(Error::connection(e.to_string()));
+                self.state = ConnectionState::Error(e.to_string());
+                Err(e)
+            }
+            None => {
+                self.mark_closed();
+                Err(Error::connection("WebSocket connection closed"))
+            }
+        }
+    }
+
+    fn start<S>(&mut self, stream: WebSocketStream<S>)
+    where
+        S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    {
+        let (commands, command_rx) = mpsc::unbounded_channel();
+        let (incoming_tx, incoming) = mpsc::unbounded_channel();
+        tokio::spawn(drive(stream, command_rx, incoming_tx, self.config.clone()));
+
+        self.commands = Some(commands);
+        self.incoming = Some(incoming);
+        self.state = ConnectionState::Connected;
+    }
+
+    async fn request(&mut self, command: impl FnOnce(oneshot::Sender<Result<()>>) -> Command) -> Result<()> {
+        let commands = self.commands.as_ref()
+            .ok_or_else(|| Error::connection("WebSocket connection not established"))?;
+
+        let (ack, done) = oneshot::channel();
+        if commands.send(command(ack)).is_err() {
+            self.mark_closed();
+            return Err(Error::connection("WebSocket connection closed"));
+        }
+        done.await.unwrap_or_else(|_| Err(Error::connection("WebSocket connection closed")))
+    }
+
+    fn mark_closed(&mut self) {
+        self.commands = None;
+        if !matches!(self.state, ConnectionState::Error(_)) {
+            self.state = ConnectionState::Disconnected;
+        }
+    }
+}
+
+#[async_trait]
+impl Connection for WebSocketConnection {
+    async fn connect(&mut self) -> Result<()> {
+        if self.is_connected() {
+            return Ok(());
+        }
+
+        self.config.validate()?;
+        let url = self.config.url.clone()
+            .ok_or_else(|| Error::configuration("No WebSocket URL configured"))?;
+        self.state = ConnectionState::Connecting;
+
+        let connect = tokio_tungstenite::connect_async_with_config(
+            url.as_str(),
+            Some(self.config.protocol_config()),
+            true,
+        );
+        let (stream, _) = match tokio::time::timeout(self.config.timeouts.connect_timeout, connect).await {
+            Ok(Ok(connected)) => connected,
+            Ok(Err(e)) => {
+                let message = format!("Failed to connect to {}: {}", url, e);
+                self.state = ConnectionState::Error(message.clone());
+                return Err(Error::connection(message));
+            }
+            Err(_) => {
+                self.state = ConnectionState::Error(format!("Connection timeout to {}", url));
+                return Err(Error::timeout(format!("connect to {}", url), self.config.timeouts.connect_timeout));
+            }
+        };
+
+        if let tokio_tungstenite::MaybeTlsStream::Plain(ref tcp) = stream.get_ref() {
+            self.remote_addr = tcp.peer_addr().ok();
+        }
+        self.start(stream);
+        Ok(())
+    }
+
+    async fn disconnect(&mut self) -> Result<()> {
+        if self.commands.is_none() {
+            return Ok(());
+        }
+
+        self.state = ConnectionState::Disconnecting;
+        let result = self.request(Command::Close).await;
+        self.commands = None;
+        self.incoming = None;
+        self.state = ConnectionState::Disconnected;
+        result
+    }
+
+    fn is_connected(&self) -> bool {
+        matches!(self.state, ConnectionState::Connected)
+    }
+
+    fn is_closed(&self) -> bool {
+        matches!(self.state, ConnectionState::Disconnected)
+    }
+
+    fn connection_info(&self) -> HashMap<String, serde_json::Value> {
+        let mut info = HashMap::new();
+        info.insert("id".to_string(), self.id.clone().into());
+        info.insert("protocol".to_string(), "websocket".into());
+        info.insert("state".to_string(), format!("{:?}", self.state).into());
+
+        if let Some(ref url) = self.config.url {
+            info.insert("url".to_string(), url.clone().into());
+        }
+
+        if let Some(addr) = self.remote_addr {
+            info.insert("remote_addr".to_string(), addr.to_string().into());
+        }
+
+        info.insert("messages_sent".to_string(), self.messages_sent.into());
+        info.insert("messages_received".to_string(), self.messages_received.into());
+
+        info
+    }
+
+    fn last_error(&self) -> Option<&Error> {
+        self.last_error.as_ref()
+    }
+
+    async fn ping(&mut self) -> Result<()> {
+        self.request(Command::Ping).await
+    }
+}
+
+/// Own the socket: send what the connection asks for, hand received text
+/// frames over and keep the connection alive
+async fn drive<S>(
+    mut socket: WebSocketStream<S>,
+    mut commands: mpsc::UnboundedReceiver<Command>,
+    incoming: mpsc::UnboundedSender<Result<String>>,
+    config: WebSocketConfig,
+) where
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    let mut keepalive = config.ping_interval.map(|period| tokio::time::interval_at(Instant::now() + period, period));
+    let mut pong_deadline: Option<Instant> = None;
+
+    loop {
+        tokio::select! {
+            command = commands.recv() => match command {
+                Some(Command::Send(message, ack)) => {
+                    let _ = ack.send(socket.send(Frame::Text(message)).await.map_err(protocol_error));
+                }
+                Some(Command::Ping(ack)) => {
+                    let _ = ack.send(socket.send(Frame::Ping(Vec::new())).await.map_err(protocol_error));
+                }
+                Some(Command::Close(ack)) => {
+                    let _ = ack.send(close(&mut socket, config.close_timeout).await);
+                    return;
+                }
+                None => {
+                    let _ = close(&mut socket, config.close_timeout).await;
+                    return;
+                }
+            },
+            frame = socket.next() => {
+                // Any frame proves the peer is alive
+                pong_deadline = None;
+                match frame {
+                    Some(Ok(Frame::Text(text))) => {
+                        let _ = incoming.send(Ok(text));
+                    }
+                    Some(Ok(Frame::Binary(data))) => {
+                        let message = String::from_utf8(data)
+                            .map_err(|_| Error::transport("Received a binary frame that is not UTF-8"));
+                        let _ = incoming.send(message);
+                    }
+                    Some(Ok(Frame::Close(frame))) => {
+                        tracing::debug!("WebSocket closed by peer: {:?}", frame);
+                        // Let tungstenite flush its reply to the close frame
+                        while let Some(Ok(_)) = socket.next().await {}
+                        return;
+                    }
+                    Some(Ok(_)) => {}
+                    Some(Err(e)) => {
+                        let _ = incoming.send(Err(protocol_error(e)));
+                        return;
+                    }
+                    None => return,
+                }
+            },
+            _ = async { keepalive.as_mut().unwrap().tick().await }, if keepalive.is_some() && pong_deadline.is_none() => {
+                if let Err(e) = socket.send(Frame::Ping(Vec::new())).await {
+                    let _ = incoming.send(Err(protocol_error(e)));
+                    return;
+                }
+                pong_deadline = Some(Instant::now() + config.pong_timeout);
+            },
+            _ = async { tokio::time::sleep_until(pong_deadline.unwrap()).await }, if pong_deadline.is_some() => {
+                let _ = incoming.send(Err(Error::timeout("WebSocket keepalive", config.pong_timeout)));
+                return;
+            },
+        }
+    }
+}
+
+/// Send a close frame and wait for the peer to answer it
+async fn close<S>(socket: &mut WebSocketStream<S>, timeout: Duration) -> Result<()>
+where
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    socket.close(None).await.map_err(protocol_error)?;
+    let handshake = async {
+        while let Some(frame) = socket.next().await {
+            if matches!(frame, Ok(Frame::Close(_)) | Err(_)) {
+                break;
+            }
+        }
+    };
+    tokio::time::timeout(timeout, handshake).await
+        .map_err(|_| Error::timeout("WebSocket close handshake", timeout))
+}
+
+fn protocol_error(e: tokio_tungstenite::tungstenite::Error) -> Error {
+    Error::transport(format!("WebSocket error: {}", e))
+}
+
+/// WebSocket transport implementation (client side of one connection)
+pub struct WebSocketTransport {
+    /// The underlying connection
+    connection: WebSocketConnection,
+}
+
+impl WebSocketTransport {
+    /// Connect to a WebSocket server
+    pub async fn connect(config: WebSocketConfig) -> Result<Self> {
+        let mut connection = WebSocketConnection::new(config);
+        connection.connect().await?;
+        Ok(Self { connection })
+    }
+
+    /// Connect to a WebSocket URL with the default configuration
+    pub async fn client(url: impl Into<String>) -> Result<Self> {
+        Self::connect(WebSocketConfig::client(url)).await
+    }
+
+    /// Wrap an established connection
+    pub fn from_connection(connection: WebSocketConnection) -> Self {
+        Self { connection }
+    }
+
+    /// The underlying connection
+    pub fn connection(&self) -> &WebSocketConnection {
+        &self.connection
+    }
+
+    /// The underlying connection, mutably
+    pub fn connection_mut(&mut self) -> &mut WebSocketConnection {
+        &mut self.connection
+    }
+}
+
+#[async_trait]
+impl Transport for WebSocketTransport {
+    async fn send(&mut self, message: &str) -> Result<()> {
+        self.connection.send_text(message).await
+    }
+
+    async fn receive(&mut self) -> Result<String> {
+        self.connection.receive_text().await
+    }
+
+    async fn close(&mut self) -> Result<()> {
+        self.connection.disconnect().await
+    }
+
+    fn is_bidirectional(&self) -> bool {
+        true
+    }
+
+    fn metadata(&self) -> HashMap<String, serde_json::Value> {
+        let mut metadata = self.connection.connection_info();
+        metadata.insert(
+            "max_message_size".to_string(),
+            self.connection.config.connection_limits.max_message_size.into(),
+        );
+        metadata
+    }
+}
+
+/// WebSocket server accepting JSON-RPC connections
+pub struct WebSocketServer {
+    /// Server configuration
+    config: WebSocketConfig,
+    /// Bound listener
+    listener: TcpListener,
+}
+
+impl WebSocketServer {
+    /// Bind to the configured address
+    pub async fn bind(config: WebSocketConfig) -> Result<Self> {
+        config.validate()?;
+        let bind_addr = config.bind_address
+            .ok_or_else(|| Error::configuration("No bind address configured for server mode"))?;
+
+        let listener = TcpListener::bind(bind_addr).await
+            .map_err(|e| Error::transport(format!("Failed to bind to {}: {}", bind_addr, e)))?;
+        tracing::info!("WebSocket transport listening on {}", bind_addr);
+
+        Ok(Self { config, listener })
+    }
+
+    /// Address the server is listening on
+    pub fn local_addr(&self) -> Result<SocketAddr> {
+        Ok(self.listener.local_addr()?)
+    }
+
+    /// Accept the next connection and complete its handshake
+    pub async fn accept(&self) -> Result<WebSocketTransport> {
+        let (stream, addr) = self.listener.accept().await
+            .map_err(|e| Error::transport(format!("Failed to accept connection: {}", e)))?;
+
+        let handshake = tokio_tungstenite::accept_async_with_config(stream, Some(self.config.protocol_config()));
+        let socket = tokio::time::timeout(self.config.timeouts.connect_timeout, handshake).await
+            .map_err(|_| Error::timeout(format!("WebSocket handshake with {}", addr), self.config.timeouts.connect_timeout))?
+            .map_err(|e| Error::transport(format!("WebSocket handshake with {} failed: {}", addr, e)))?;
+
+        let connection = WebSocketConnection::from_stream(socket, self.config.clone(), Some(addr));
+        tracing::debug!("Accepted WebSocket connection {} from {}", connection.id(), addr);
+        Ok(WebSocketTransport::from_connection(connection))
+    }
+
+    /// Accept connections forever, answering their requests with `router`
+    ///
+    /// Each connection is served by its own task. Failed handshakes are
+    /// logged and do not stop the server.
+    pub async fn serve(self, router: Arc<MethodRouter>) -> Result<()> {
+        loop {
+            match self.accept().await {
+                Ok(transport) => {
+                    tokio::spawn(serve_connection(transport, Arc::clone(&router)));
+                }
+                Err(e) => tracing::warn!("Rejected WebSocket connection: {}", e),
+            }
+        }
+    }
+}
+
+/// Answer the requests of one connection until it closes
+async fn serve_connection(mut transport: WebSocketTransport, router: Arc<MethodRouter>) {
+    let connection_id = transport.connection().id().to_string();
+    let session = Session::new(connection_id.clone().into());
+    loop {
+        let message = match transport.receive().await {
+            Ok(message) => message,
+            Err(e) => {
+                tracing::debug!("WebSocket connection {} ended: {}", connection_id, e);
+                break;
+            }
+        };
+
+        let context = ServiceContext::new(Uuid::new_v4().to_string())
+            .with_metadata("connection_id", connection_id.clone().into())
+            .with_session(session.clone());
+        if let Some(response) = router.dispatch_message(&message, &context).await {
+            if let Err(e) = transport.send(&response).await {
+                tracing::debug!("Failed to answer on WebSocket connection {}: {}", connection_id, e);
+                break;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::traits::MethodHandler;
+    use crate::core::types::{JsonRpcRequest, JsonRpcResponse};
+    use crate::protocol::JsonRpcClient;
+    use serde_json::{json, Value};
+
+    struct EchoHandler;
+
+    #[async_trait]
+    impl MethodHandler for EchoHandler {
+        async fn handle_method(&self, request: &JsonRpcRequest, _context: &ServiceContext) -> Result<JsonRpcResponse> {
+            Ok(JsonRpcResponse::success(Value::Null, request.params.clone().unwrap_or(Value::Null)))
+        }
+
+        fn supported_methods(&self) -> Vec<String> {
+            vec!["echo".to_string()]
+        }
+    }
+
+    async fn echo_server() -> String {
+        let server = WebSocketServer::bind(WebSocketConfig::server("127.0.0.1:0".parse().unwrap()))
+            .await
+            .unwrap();
+        let url = format!("ws://{}", server.local_addr().unwrap());
+        let router = Arc::new(MethodRouter::new().with_handler(Arc::new(EchoHandler)).unwrap());
+        tokio::spawn(server.serve(router));
+        url
+    }
+
+    #[test]
+    fn test_websocket_config() {
+        assert!(WebSocketConfig::client("ws://localhost:8080/rpc").validate().is_ok());
+        assert!(WebSocketConfig::client("http://localhost:8080").validate().is_err());
+        assert!(WebSocketConfig::default().with_max_message_size(0).validate().is_err());
+        assert!(WebSocketConfig::default().with_ping_interval(Some(Duration::ZERO)).validate().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_client_server_round_trip() {
+        let url = echo_server().await;
+        let config = WebSocketConfig::client(url)
+            .with_ping_interval(Some(Duration::from_millis(20)))
+            .with_max_message_size(1024);
+        let client = JsonRpcClient::new(WebSocketTransport::connect(config).await.unwrap());
+
+        let echoed: Value = client.call("echo", json!({ "hello": "world" })).await.unwrap();
+        assert_eq!(echoed, json!({ "hello": "world" }));
+
+        // Keepalive pings are answered, so the connection survives idle periods
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        let echoed: Vec<u32> = client.call("echo", vec![1, 2, 3]).await.unwrap();
+        assert_eq!(echoed, vec![1, 2, 3]);
+
+        let oversized = client.call::<_, Value>("echo", "x".repeat(2048)).await.unwrap_err();
+        assert!(matches!(oversized, Error::Validation { .. }));
+
+        client.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_close_handshake_and_dead_peer_detection() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            // First peer closes properly; the second never reads again
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut socket = tokio_tungstenite::accept_async(stream).await.unwrap();
+            while let Some(Ok(_)) = socket.next().await {}
+            let (stream, _) = listener.accept().await.unwrap();
+            tokio_tungstenite::accept_async(stream).await.unwrap()
+        });
+
+        let mut transport = WebSocketTransport::client(url.clone()).await.unwrap();
+        transport.close().await.unwrap();
+        assert!(transport.connection().is_closed());
+        assert!(transport.send("{}").await.is_err());
+
+        let config = WebSocketConfig::client(url)
+            .with_ping_interval(Some(Duration::from_millis(20)))
+            .with_pong_timeout(Duration::from_millis(50));
+        let mut transport = WebSocketTransport::connect(config).await.unwrap();
+        let _silent_peer = server.await.unwrap();
+        assert!(matches!(transport.receive().await, Err(Error::Timeout { .. })));
+        assert!(transport.connection().last_error().is_some());
+    }
+}
@@ -0,0 +1,42 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+use trn_rust::{find_matching_trns, Trn, TrnIndex};
+
+/// A registry of 100k tools across 1000 scopes
+fn registry() -> Vec<String> {
+    (0..100_000)
+        .map(|i| format!("trn:user:user{}:tool:api{}:v1.{}", i % 1000, i / 1000, i % 7))
+        .collect()
+}
+
+fn bench_index_build(c: &mut Criterion) {
+    let trns = registry();
+
+    c.bench_function("index_build_100k", |b| {
+        b.iter(|| trns.iter().map(|trn| Trn::parse(trn).unwrap()).collect::<TrnIndex>())
+    });
+}
+
+fn bench_index_queries(c: &mut Criterion) {
+    let trns = registry();
+    let index: TrnIndex = trns.iter().map(|trn| Trn::parse(trn).unwrap()).collect();
+    let base = Trn::parse("trn:user:user42:tool:api42:v1.0").unwrap();
+    let mut group = c.benchmark_group("index_queries_100k");
+
+    group.bench_function("by_scope", |b| {
+        b.iter(|| index.by_scope("user", "user42").count())
+    });
+    group.bench_function("find_matching", |b| {
+        b.iter(|| index.find_matching("trn:user:user42:tool:*:*").unwrap().len())
+    });
+    group.bench_function("latest_version", |b| {
+        b.iter(|| index.latest_version(&base))
+    });
+    group.bench_function("linear_find_matching_trns", |b| {
+        b.iter(|| find_matching_trns(&trns, "trn:user:user42:tool:*:*").len())
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_index_build, bench_index_queries);
+criterion_main!(benches);
@@ -1,5 +1,5 @@
 use criterion::{criterion_group, criterion_main, Criterion, BenchmarkId};
-use trn_rust::Trn;
+use trn_rust::{Trn, TrnRef};
 
 fn bench_parse_simple_trn(c: &mut Criterion) {
     let trn_str = "trn:user:alice:tool:myapi:v1.0";
@@ -160,6 +160,33 @@ fn bench_parse_memory_usage(c: &mut Criterion) {
     });
 }
 
+fn bench_parse_owned_vs_borrowed(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parse_owned_vs_borrowed");
+    
+    // More distinct TRNs than the validation cache holds, as on a busy bus
+    let trn_strings: Vec<String> = (0..5000)
+        .map(|i| format!("trn:user:user{}:tool:api{}:v1.0", i, i))
+        .collect();
+    
+    group.bench_function("owned", |b| {
+        b.iter(|| {
+            for trn_str in &trn_strings {
+                let _ = Trn::parse(trn_str);
+            }
+        })
+    });
+    
+    group.bench_function("borrowed", |b| {
+        b.iter(|| {
+            for trn_str in &trn_strings {
+                let _ = TrnRef::parse(trn_str);
+            }
+        })
+    });
+    
+    group.finish();
+}
+
 criterion_group!(
     benches,
     bench_parse_simple_trn,
@@ -170,7 +197,8 @@ criterion_group!(
     bench_parse_different_lengths,
     bench_parse_vs_constructor,
     bench_parse_error_cases,
-    bench_parse_memory_usage
+    bench_parse_memory_usage,
+    bench_parse_owned_vs_borrowed
 );
 
 criterion_main!(benches); 
\ No newline at end of file
@@ -216,6 +216,24 @@ fn bench_validate_naming_conventions(c: &mut Criterion) {
     });
 }
 
+fn bench_validate_batch_report(c: &mut Criterion) {
+    let mut group = c.benchmark_group("validate_batch_report");
+    let trn_strings: Vec<String> = (0..100_000)
+        .map(|i| if i % 100 == 0 { format!("invalid:{}", i) } else { format!("trn:user:user{}:tool:api{}:v1.0", i, i) })
+        .collect();
+
+    group.bench_function("sequential", |b| {
+        b.iter(|| trn_rust::validate_batch(&trn_strings))
+    });
+
+    #[cfg(feature = "rayon")]
+    group.bench_function("parallel", |b| {
+        b.iter(|| trn_rust::validate_batch_parallel(&trn_strings))
+    });
+
+    group.finish();
+}
+
 criterion_group!(
     benches,
     bench_validate_single_trn,
@@ -229,6 +247,7 @@ criterion_group!(
     bench_validate_component_lengths,
     bench_validate_mixed_valid_invalid,
     bench_validation_with_caching,
+    bench_validate_batch_report,
     bench_validate_naming_conventions
 );
 
@@ -0,0 +1,360 @@
+//! Access control over TRNs
+//!
+//! A [`Policy`] holds allow and deny rules, each a TRN pattern. A TRN is
+//! allowed when an allow rule matches it and no deny rule does: deny rules
+//! take precedence, and a TRN no rule matches is denied.
+//!
+//! Policies compose two ways. [`Policy::merge`] pools the rules of two
+//! policies into one, so either may allow and either may deny. A
+//! [`PolicyStack`] layers policies instead, and allows a TRN only when every
+//! layer allows it, as when a user's grants are bounded by their
+//! organisation's.
+//!
+//! [`Policy::explain`] tells which rule decided, for audit logs and error
+//! messages:
+//!
+//! ```rust
+//! use trn_rust::{Policy, TrnRef};
+//!
+//! let policy = Policy::new(["trn:user:alice:*:*:*"], ["trn:user:alice:dataset:private-*:*"])?
+//!     .named("alice");
+//!
+//! let trn = TrnRef::parse("trn:user:alice:dataset:private-keys:v1.0")?;
+//! let explanation = policy.explain(&trn);
+//! assert!(!explanation.allowed);
+//! assert_eq!(
+//!     explanation.to_string(),
+//!     "denied by deny rule 'trn:user:alice:dataset:private-*:*' of policy 'alice'"
+//! );
+//! # Ok::<(), trn_rust::TrnError>(())
+//! ```
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::TrnResult;
+use crate::pattern_set::TrnPatternSet;
+use crate::types::{TrnComponents, TrnRef};
+
+/// Effect of a rule
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Effect {
+    /// Grants access
+    Allow,
+    /// Refuses access, whatever allows it
+    Deny,
+}
+
+impl fmt::Display for Effect {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Allow => write!(f, "allow"),
+            Self::Deny => write!(f, "deny"),
+        }
+    }
+}
+
+/// A rule of a policy
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Rule {
+    /// Effect of the rule
+    pub effect: Effect,
+    /// TRN pattern the rule applies to
+    pub pattern: String,
+}
+
+/// Outcome of evaluating a TRN, with the rules behind it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Explanation {
+    /// Whether access is allowed
+    pub allowed: bool,
+    /// Name of the deciding policy, if it has one
+    pub policy: Option<String>,
+    /// Rule that decided, or `None` when no rule matched
+    pub rule: Option<Rule>,
+    /// Every rule of the deciding policy matching the TRN, deny rules first
+    pub matched: Vec<Rule>,
+}
+
+impl fmt::Display for Explanation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.rule {
+            Some(rule) if self.allowed => write!(f, "allowed by {} rule '{}'", rule.effect, rule.pattern)?,
+            Some(rule) => write!(f, "denied by {} rule '{}'", rule.effect, rule.pattern)?,
+            None => write!(f, "denied: no rule matches")?,
+        }
+        if let Some(policy) = &self.policy {
+            let joiner = if self.rule.is_some() { " of" } else { " in" };
+            write!(f, "{joiner} policy '{policy}'")?;
+        }
+        Ok(())
+    }
+}
+
+/// Allow and deny rules over TRN patterns, with deny taking precedence
+///
+/// Serializes as `{ "name": ..., "allow": [...], "deny": [...] }`, and the
+/// patterns are checked when deserializing.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(try_from = "PolicySpec", into = "PolicySpec")]
+pub struct Policy {
+    name: Option<String>,
+    allow: TrnPatternSet,
+    deny: TrnPatternSet,
+}
+
+/// Serialized form of a policy
+#[derive(Serialize, Deserialize)]
+struct PolicySpec {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+    #[serde(default)]
+    allow: Vec<String>,
+    #[serde(default)]
+    deny: Vec<String>,
+}
+
+impl TryFrom<PolicySpec> for Policy {
+    type Error = crate::error::TrnError;
+
+    fn try_from(spec: PolicySpec) -> TrnResult<Self> {
+        let policy = Self::new(spec.allow, spec.deny)?;
+        Ok(match spec.name {
+            Some(name) => policy.named(name),
+            None => policy,
+        })
+    }
+}
+
+impl From<Policy> for PolicySpec {
+    fn from(policy: Policy) -> Self {
+        Self {
+            name: policy.name,
+            allow: policy.allow.patterns().to_vec(),
+            deny: policy.deny.patterns().to_vec(),
+        }
+    }
+}
+
+impl Policy {
+    /// Create a policy from allow and deny patterns
+    pub fn new<A, D, S, T>(allow: A, deny: D) -> TrnResult<Self>
+    where
+        A: IntoIterator<Item = S>,
+        D: IntoIterator<Item = T>,
+        S: AsRef<str>,
+        T: AsRef<str>,
+    {
+        Ok(Self {
+            name: None,
+            allow: TrnPatternSet::new(allow)?,
+            deny: TrnPatternSet::new(deny)?,
+        })
+    }
+
+    /// Create a policy denying everything
+    pub fn deny_all() -> Self {
+        Self::default()
+    }
+
+    /// Name the policy, for explanations
+    pub fn named(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// Get the policy name
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Add an allow rule
+    pub fn allow(mut self, pattern: &str) -> TrnResult<Self> {
+        self.allow.add_pattern(pattern)?;
+        Ok(self)
+    }
+
+    /// Add a deny rule
+    pub fn deny(mut self, pattern: &str) -> TrnResult<Self> {
+        self.deny.add_pattern(pattern)?;
+        Ok(self)
+    }
+
+    /// Get the allow patterns
+    pub fn allow_patterns(&self) -> &[String] {
+        self.allow.patterns()
+    }
+
+    /// Get the deny patterns
+    pub fn deny_patterns(&self) -> &[String] {
+        self.deny.patterns()
+    }
+
+    /// Pool the rules of `other` into this policy
+    ///
+    /// The merged policy allows what either allows, unless either denies it.
+    /// It keeps this policy's name.
+    pub fn merge(mut self, other: &Self) -> Self {
+        for pattern in other.allow.patterns() {
+            // Already parsed once, so cannot fail
+            let _ = self.allow.add_pattern(pattern);
+        }
+        for pattern in other.deny.patterns() {
+            let _ = self.deny.add_pattern(pattern);
+        }
+        self
+    }
+
+    /// Check if a TRN is allowed
+    pub fn is_allowed<'t>(&self, trn: impl Into<TrnComponents<'t>>) -> bool {
+        let trn = trn.into();
+        !self.deny.matches_any(trn) && self.allow.matches_any(trn)
+    }
+
+    /// Check if a TRN string is allowed; invalid TRNs are denied
+    pub fn is_allowed_str(&self, trn: &str) -> bool {
+        TrnRef::parse(trn).is_ok_and(|trn| self.is_allowed(&trn))
+    }
+
+    /// Evaluate a TRN and tell which rule decided
+    pub fn explain<'t>(&self, trn: impl Into<TrnComponents<'t>>) -> Explanation {
+        let trn = trn.into();
+        let matched: Vec<Rule> = matching_rules(&self.deny, Effect::Deny, trn)
+            .chain(matching_rules(&self.allow, Effect::Allow, trn))
+            .collect();
+        Explanation {
+            allowed: matched.first().is_some_and(|rule| rule.effect == Effect::Allow),
+            policy: self.name.clone(),
+            rule: matched.first().cloned(),
+            matched,
+        }
+    }
+}
+
+fn matching_rules<'s>(
+    set: &'s TrnPatternSet,
+    effect: Effect,
+    trn: TrnComponents<'_>,
+) -> impl Iterator<Item = Rule> + 's {
+    set.which_match(trn).into_iter().map(move |index| Rule {
+        effect,
+        pattern: set.patterns()[index].clone(),
+    })
+}
+
+/// Policies layered so that a TRN must be allowed by each of them
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct PolicyStack {
+    layers: Vec<Policy>,
+}
+
+impl PolicyStack {
+    /// Create a stack from layers, outermost first
+    pub fn new(layers: impl IntoIterator<Item = Policy>) -> Self {
+        Self { layers: layers.into_iter().collect() }
+    }
+
+    /// Add an inner layer
+    pub fn push(mut self, policy: Policy) -> Self {
+        self.layers.push(policy);
+        self
+    }
+
+    /// Get the layers, outermost first
+    pub fn layers(&self) -> &[Policy] {
+        &self.layers
+    }
+
+    /// Check if every layer allows a TRN; an empty stack allows nothing
+    pub fn is_allowed<'t>(&self, trn: impl Into<TrnComponents<'t>>) -> bool {
+        let trn = trn.into();
+        !self.layers.is_empty() && self.layers.iter().all(|policy| policy.is_allowed(trn))
+    }
+
+    /// Evaluate a TRN, explaining with the outermost layer denying it, or
+    /// with the innermost layer when all allow it
+    pub fn explain<'t>(&self, trn: impl Into<TrnComponents<'t>>) -> Explanation {
+        let trn = trn.into();
+        let mut last = None;
+        for policy in &self.layers {
+            let explanation = policy.explain(trn);
+            if !explanation.allowed {
+                return explanation;
+            }
+            last = Some(explanation);
+        }
+        last.unwrap_or_else(|| Policy::deny_all().explain(trn))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Trn;
+
+    fn trn(s: &str) -> Trn {
+        Trn::parse(s).unwrap()
+    }
+
+    #[test]
+    fn test_deny_takes_precedence() {
+        let policy = Policy::new(
+            ["trn:user:alice:*:*:*", "trn:*:*:tool:*:*"],
+            ["trn:user:alice:dataset:private-*:*", "trn:*:*:tool:*:v0.*"],
+        )
+        .unwrap();
+
+        assert!(policy.is_allowed(&trn("trn:user:alice:model:bert:v1.0")));
+        assert!(policy.is_allowed(&trn("trn:user:bob:tool:fetch:v1.0")));
+        assert!(!policy.is_allowed(&trn("trn:user:alice:dataset:private-keys:v1.0")));
+        assert!(!policy.is_allowed(&trn("trn:user:alice:tool:fetch:v0.9")));
+        assert!(!policy.is_allowed(&trn("trn:org:acme:model:bert:v1.0")));
+        assert!(!policy.is_allowed_str("not-a-trn"));
+        assert!(!Policy::deny_all().is_allowed(&trn("trn:user:alice:model:bert:v1.0")));
+
+        let explanation = policy.explain(&trn("trn:user:alice:tool:fetch:v0.9"));
+        assert_eq!(explanation.rule.unwrap().pattern, "trn:*:*:tool:*:v0.*");
+        assert_eq!(explanation.matched.len(), 3);
+        assert_eq!(explanation.matched[2].effect, Effect::Allow);
+
+        let explanation = policy.explain(&trn("trn:user:bob:tool:fetch:v1.0"));
+        assert_eq!(explanation.to_string(), "allowed by allow rule 'trn:*:*:tool:*:*'");
+        let explanation = policy.named("p").explain(&trn("trn:org:acme:model:bert:v1.0"));
+        assert_eq!(explanation.to_string(), "denied: no rule matches in policy 'p'");
+    }
+
+    #[test]
+    fn test_composition() {
+        let org = Policy::new(["trn:user:*:*:*:*"], ["trn:*:*:dataset:*:*"]).unwrap().named("org");
+        let alice = Policy::new(["trn:user:alice:*:*:*"], Vec::<String>::new()).unwrap().named("alice");
+
+        let merged = alice.clone().merge(&org);
+        assert_eq!(merged.name(), Some("alice"));
+        assert!(merged.is_allowed(&trn("trn:user:bob:tool:fetch:v1.0")));
+        assert!(!merged.is_allowed(&trn("trn:user:alice:dataset:crawl:v1.0")));
+
+        let stack = PolicyStack::new([org]).push(alice);
+        assert!(stack.is_allowed(&trn("trn:user:alice:tool:fetch:v1.0")));
+        assert!(!stack.is_allowed(&trn("trn:user:bob:tool:fetch:v1.0")));
+        assert_eq!(stack.explain(&trn("trn:user:bob:tool:fetch:v1.0")).policy.as_deref(), Some("alice"));
+        assert_eq!(stack.explain(&trn("trn:user:alice:dataset:crawl:v1.0")).policy.as_deref(), Some("org"));
+        assert!(!PolicyStack::default().is_allowed(&trn("trn:user:alice:tool:fetch:v1.0")));
+    }
+
+    #[test]
+    fn test_serde_round_trip() {
+        let policy: Policy = serde_json::from_str(
+            r#"{ "name": "ops", "allow": ["trn:org:acme:*:*:*"], "deny": ["trn:org:acme:dataset:private-*:*"] }"#,
+        )
+        .unwrap();
+        assert_eq!(policy.name(), Some("ops"));
+        assert_eq!(policy.deny_patterns(), ["trn:org:acme:dataset:private-*:*"]);
+
+        let json = serde_json::to_value(&policy).unwrap();
+        assert_eq!(json["allow"][0], "trn:org:acme:*:*:*");
+        assert!(serde_json::from_str::<Policy>(r#"{ "allow": ["trn:org"] }"#).is_err());
+    }
+}
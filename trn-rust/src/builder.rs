@@ -1,10 +1,13 @@
 //! TRN builder pattern implementation
 //!
 //! This module provides a fluent builder interface for constructing TRN objects
-//! with validation, convenience methods, and type safety for the simplified 6-component format.
+//! with validation, convenience methods, and type safety for the simplified 6-component format,
+//! and a builder deriving related TRNs from a parent TRN.
 
 use crate::error::{TrnError, TrnResult};
 use crate::types::{Platform, ResourceType, Trn};
+use crate::utils::{increment_version, SemanticVersion, VersionComponent};
+use crate::version::parse_partial;
 
 /// Fluent builder for TRN construction
 #[derive(Debug, Default, Clone)]
@@ -267,6 +270,172 @@ impl TrnBuilder {
     }
 }
 
+/// Separator between a resource ID and a sub-resource name
+const SUB_RESOURCE_SEPARATOR: char = '.';
+
+/// Builder for TRNs related to a parent TRN, created by [`Trn::derive`]
+///
+/// Derived TRNs stay in the parent's platform and scope, which the builder
+/// offers no way to change; the resource type, resource ID and version
+/// start as the parent's. Receivers can check a TRN claimed to be derived
+/// with [`Trn::is_derived_from`] and [`Trn::is_child_of`].
+///
+/// # Examples
+///
+/// ```rust
+/// use trn_rust::{Trn, VersionComponent};
+///
+/// let parent = Trn::parse("trn:user:alice:tool:weather-api:v1.2")?;
+///
+/// let child = parent.derive().child("forecast").build()?;
+/// assert_eq!(child.to_string(), "trn:user:alice:tool:weather-api.forecast:v1.2");
+/// assert!(child.is_child_of(&parent));
+///
+/// let next = parent.derive().bump(VersionComponent::Minor).build()?;
+/// assert_eq!(next.version(), "v1.3");
+///
+/// let model = parent.derive().resource_type("model").resource_id("forecaster").build()?;
+/// assert_eq!(model.to_string(), "trn:user:alice:model:forecaster:v1.2");
+/// # Ok::<(), trn_rust::TrnError>(())
+/// ```
+#[derive(Debug)]
+pub struct DerivedTrnBuilder {
+    parent: Trn,
+    resource_type: String,
+    resource_id: String,
+    version: String,
+    /// First error from a setter, reported by `build`
+    error: Option<TrnError>,
+}
+
+impl DerivedTrnBuilder {
+    /// Start deriving from `parent`
+    pub fn new(parent: &Trn) -> Self {
+        Self {
+            parent: parent.clone(),
+            resource_type: parent.resource_type().to_string(),
+            resource_id: parent.resource_id().to_string(),
+            version: parent.version().to_string(),
+            error: None,
+        }
+    }
+
+    /// Set the resource type
+    pub fn resource_type<S: Into<String>>(mut self, resource_type: S) -> Self {
+        self.resource_type = resource_type.into();
+        self
+    }
+
+    /// Set the resource type using enum
+    pub fn resource_type_enum(mut self, resource_type: ResourceType) -> Self {
+        self.resource_type = resource_type.to_string();
+        self
+    }
+
+    /// Set the resource ID, for a sibling resource
+    pub fn resource_id<S: Into<String>>(mut self, resource_id: S) -> Self {
+        self.resource_id = resource_id.into();
+        self
+    }
+
+    /// Name a sub-resource of the current resource ID, as
+    /// `weather-api.forecast` for `forecast` under `weather-api`
+    pub fn child(mut self, name: &str) -> Self {
+        if name.is_empty() || name.contains(SUB_RESOURCE_SEPARATOR) {
+            self.fail(TrnError::builder_invalid_field(
+                "resource_id".to_string(),
+                format!("Sub-resource name '{name}' must be non-empty and not contain '{SUB_RESOURCE_SEPARATOR}'"),
+            ));
+            return self;
+        }
+        self.resource_id = format!("{}{SUB_RESOURCE_SEPARATOR}{name}", self.resource_id);
+        self
+    }
+
+    /// Set the version
+    pub fn version<S: Into<String>>(mut self, version: S) -> Self {
+        self.version = version.into();
+        self
+    }
+
+    /// Increment the current version, keeping its `v` prefix and, where
+    /// possible, its number count: `v1.2` bumps to `v1.3` or `v2.0`
+    pub fn bump(mut self, component: VersionComponent) -> Self {
+        match bump_version(&self.version, component) {
+            Ok(version) => self.version = version,
+            Err(error) => self.fail(error),
+        }
+        self
+    }
+
+    /// Increment the major version
+    pub fn bump_major(self) -> Self {
+        self.bump(VersionComponent::Major)
+    }
+
+    /// Increment the minor version
+    pub fn bump_minor(self) -> Self {
+        self.bump(VersionComponent::Minor)
+    }
+
+    /// Increment the patch version
+    pub fn bump_patch(self) -> Self {
+        self.bump(VersionComponent::Patch)
+    }
+
+    /// Build and validate the derived TRN
+    pub fn build(self) -> TrnResult<Trn> {
+        if let Some(error) = self.error {
+            return Err(error);
+        }
+        Trn::new(
+            self.parent.platform(),
+            self.parent.scope(),
+            self.resource_type,
+            self.resource_id,
+            self.version,
+        )
+    }
+
+    /// Build and return as string
+    pub fn build_string(self) -> TrnResult<String> {
+        self.build().map(|trn| trn.to_string())
+    }
+
+    fn fail(&mut self, error: TrnError) {
+        self.error.get_or_insert(error);
+    }
+}
+
+/// Increment a TRN version, keeping its style
+fn bump_version(version: &str, component: VersionComponent) -> TrnResult<String> {
+    let (prefix, number) = version.strip_prefix('v').map_or(("", version), |number| ("v", number));
+    let Some((parsed, given)) = parse_partial(number) else {
+        return Err(TrnError::version(
+            format!("Cannot bump non-semantic version '{version}'"),
+            version.to_string(),
+            String::new(),
+            "bump".to_string(),
+        ));
+    };
+    let bumped = SemanticVersion::parse(&increment_version(&parsed.to_string(), component)?)?;
+
+    // Drop trailing zeros the original version did not spell out
+    let needed = if bumped.patch != 0 || bumped.prerelease.is_some() || bumped.build.is_some() {
+        3
+    } else if bumped.minor != 0 {
+        2
+    } else {
+        1
+    };
+    let bumped = match given.max(needed) {
+        1 => bumped.major.to_string(),
+        2 => format!("{}.{}", bumped.major, bumped.minor),
+        _ => bumped.to_string(),
+    };
+    Ok(format!("{prefix}{bumped}"))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -368,4 +537,38 @@ mod tests {
 
         assert_eq!(trn_string, "trn:user:alice:tool:getUserById:v1.0");
     }
-} 
\ No newline at end of file
+
+    #[test]
+    fn test_derive() {
+        let parent = Trn::parse("trn:user:alice:tool:weather-api:v1.2").unwrap();
+
+        let child = parent.derive().child("forecast").child("daily").build().unwrap();
+        assert_eq!(child.resource_id(), "weather-api.forecast.daily");
+        assert!(child.is_child_of(&parent) && child.is_derived_from(&parent));
+        assert!(!parent.is_child_of(&parent));
+        assert!(parent.derive().child("").build().is_err());
+        assert!(parent.derive().child("a.b").build().is_err());
+
+        let sibling = parent.derive().resource_id("weather-apis").build().unwrap();
+        assert!(!sibling.is_child_of(&parent) && sibling.is_derived_from(&parent));
+        let other = Trn::parse("trn:user:bob:tool:weather-api.forecast:v1.2").unwrap();
+        assert!(!other.is_child_of(&parent) && !other.is_derived_from(&parent));
+
+        assert!(parent.derive().resource_type("gadget").build().is_err());
+    }
+
+    #[test]
+    fn test_version_bumps() {
+        let bump = |version: &str, component| bump_version(version, component).unwrap();
+        assert_eq!(bump("v1.2", VersionComponent::Minor), "v1.3");
+        assert_eq!(bump("v1.2", VersionComponent::Major), "v2.0");
+        assert_eq!(bump("v1.2", VersionComponent::Patch), "v1.2.1");
+        assert_eq!(bump("v1", VersionComponent::Major), "v2");
+        assert_eq!(bump("1.2.3", VersionComponent::Minor), "1.3.0");
+        assert!(bump_version("latest", VersionComponent::Minor).is_err());
+
+        let parent = Trn::parse("trn:user:alice:tool:weather-api:latest").unwrap();
+        assert!(parent.derive().bump_minor().build().is_err());
+        assert_eq!(parent.derive().version("v1.0").bump_major().build().unwrap().version(), "v2.0");
+    }
+}
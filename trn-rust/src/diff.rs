@@ -0,0 +1,286 @@
+//! Comparing TRNs
+//!
+//! [`Trn::diff`] lists the components two TRNs differ in and classifies the
+//! version change between them, for upgrade planners comparing a deployed
+//! TRN with a requested one:
+//!
+//! ```rust
+//! use trn_rust::{Trn, TrnComponent, VersionChange, VersionLevel};
+//!
+//! let deployed = Trn::parse("trn:user:alice:tool:weather-api:v1.2")?;
+//! let requested = Trn::parse("trn:user:alice:tool:weather-api:v1.4.1")?;
+//!
+//! let diff = deployed.diff(&requested);
+//! assert_eq!(diff.changed_components(), vec![TrnComponent::Version]);
+//! assert_eq!(diff.version_change, VersionChange::Upgrade { level: VersionLevel::Minor, breaking: false });
+//! assert!(deployed.is_compatible_upgrade(&requested));
+//! # Ok::<(), trn_rust::TrnError>(())
+//! ```
+//!
+//! An upgrade is compatible when it stays on the same resource and the new
+//! version meets a caret requirement on the old one: same major version, or
+//! for `0.x` versions the same minor version, as in [`crate::VersionRequirement`].
+
+use std::cmp::Ordering;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::types::Trn;
+use crate::version::{parse_partial, VersionRequirement};
+
+/// A component of a TRN
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TrnComponent {
+    /// Platform
+    Platform,
+    /// Scope
+    Scope,
+    /// Resource type
+    ResourceType,
+    /// Resource ID
+    ResourceId,
+    /// Version
+    Version,
+}
+
+impl TrnComponent {
+    /// Get component name as string
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Platform => "platform",
+            Self::Scope => "scope",
+            Self::ResourceType => "resource_type",
+            Self::ResourceId => "resource_id",
+            Self::Version => "version",
+        }
+    }
+}
+
+impl fmt::Display for TrnComponent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// A component differing between two TRNs
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ComponentChange {
+    /// Component that differs
+    pub component: TrnComponent,
+    /// Value in the first TRN
+    pub from: String,
+    /// Value in the second TRN
+    pub to: String,
+}
+
+/// Most significant part of a version that went up
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VersionLevel {
+    /// Major version, as `1.4.0` to `2.0.0`
+    Major,
+    /// Minor version, as `1.4.0` to `1.5.0`
+    Minor,
+    /// Patch version, as `1.4.0` to `1.4.1`
+    Patch,
+    /// Only the prerelease, as `2.0.0-alpha` to `2.0.0-beta` or `2.0.0`
+    Prerelease,
+}
+
+impl fmt::Display for VersionLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Major => write!(f, "major"),
+            Self::Minor => write!(f, "minor"),
+            Self::Patch => write!(f, "patch"),
+            Self::Prerelease => write!(f, "prerelease"),
+        }
+    }
+}
+
+/// Change between the versions of two TRNs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "kind")]
+pub enum VersionChange {
+    /// Same version, possibly spelled differently, as `v1.0` and `v1.0.0`
+    Unchanged,
+    /// Newer version
+    Upgrade {
+        /// Most significant part that went up
+        level: VersionLevel,
+        /// Whether semantic versioning allows the change to break users
+        breaking: bool,
+    },
+    /// Older version
+    Downgrade,
+    /// A version is not semantic, such as `latest`, and they differ
+    Incomparable,
+}
+
+/// Differences between two TRNs
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TrnDiff {
+    /// Differing components, in TRN order
+    pub changes: Vec<ComponentChange>,
+    /// Change between the versions
+    pub version_change: VersionChange,
+}
+
+impl TrnDiff {
+    /// Compare two TRNs
+    pub fn new(from: &Trn, to: &Trn) -> Self {
+        let (a, b) = (from.components(), to.components());
+        let changes = [
+            (TrnComponent::Platform, a.platform, b.platform),
+            (TrnComponent::Scope, a.scope, b.scope),
+            (TrnComponent::ResourceType, a.resource_type, b.resource_type),
+            (TrnComponent::ResourceId, a.resource_id, b.resource_id),
+            (TrnComponent::Version, a.version, b.version),
+        ]
+        .into_iter()
+        .filter(|(_, from, to)| from != to)
+        .map(|(component, from, to)| ComponentChange {
+            component,
+            from: from.to_string(),
+            to: to.to_string(),
+        })
+        .collect();
+
+        Self {
+            changes,
+            version_change: version_change(a.version, b.version),
+        }
+    }
+
+    /// Check if the TRNs are identical
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// Get the differing components, in TRN order
+    pub fn changed_components(&self) -> Vec<TrnComponent> {
+        self.changes.iter().map(|change| change.component).collect()
+    }
+
+    /// Check if a component differs
+    pub fn changed(&self, component: TrnComponent) -> bool {
+        self.changes.iter().any(|change| change.component == component)
+    }
+
+    /// Check if both TRNs name the same resource, whatever their versions
+    pub fn same_resource(&self) -> bool {
+        self.changes.iter().all(|change| change.component == TrnComponent::Version)
+    }
+
+    /// Check if moving from the first TRN to the second is a compatible
+    /// upgrade: same resource, and a version that is the same or a
+    /// non-breaking upgrade
+    pub fn is_compatible_upgrade(&self) -> bool {
+        self.same_resource()
+            && matches!(
+                self.version_change,
+                VersionChange::Unchanged | VersionChange::Upgrade { breaking: false, .. }
+            )
+    }
+}
+
+impl fmt::Display for TrnDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return write!(f, "identical");
+        }
+        for (i, change) in self.changes.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}: {} -> {}", change.component, change.from, change.to)?;
+            if change.component == TrnComponent::Version {
+                match self.version_change {
+                    VersionChange::Upgrade { level, breaking } => {
+                        write!(f, " ({level} upgrade{})", if breaking { ", breaking" } else { "" })?;
+                    }
+                    VersionChange::Downgrade => write!(f, " (downgrade)")?,
+                    VersionChange::Unchanged | VersionChange::Incomparable => {}
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn version_change(from: &str, to: &str) -> VersionChange {
+    let (Some((a, _)), Some((b, _))) = (parse_partial(from), parse_partial(to)) else {
+        return if from == to { VersionChange::Unchanged } else { VersionChange::Incomparable };
+    };
+    match a.cmp(&b) {
+        Ordering::Equal => VersionChange::Unchanged,
+        Ordering::Greater => VersionChange::Downgrade,
+        Ordering::Less => {
+            let level = if a.major != b.major {
+                VersionLevel::Major
+            } else if a.minor != b.minor {
+                VersionLevel::Minor
+            } else if a.patch != b.patch {
+                VersionLevel::Patch
+            } else {
+                VersionLevel::Prerelease
+            };
+            // `^from`, with prereleases of later versions counting as breaking
+            let breaking = !VersionRequirement::parse(&format!("^{from}"))
+                .is_ok_and(|requirement| requirement.matches(&b));
+            VersionChange::Upgrade { level, breaking }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn diff(from: &str, to: &str) -> TrnDiff {
+        Trn::parse(from).unwrap().diff(&Trn::parse(to).unwrap())
+    }
+
+    fn change(from: &str, to: &str) -> VersionChange {
+        diff(&format!("trn:user:alice:tool:api:{from}"), &format!("trn:user:alice:tool:api:{to}")).version_change
+    }
+
+    #[test]
+    fn test_version_changes() {
+        let upgrade = |level, breaking| VersionChange::Upgrade { level, breaking };
+        assert_eq!(change("v1.0", "v1.0.0"), VersionChange::Unchanged);
+        assert_eq!(change("v1.2", "v1.2.5"), upgrade(VersionLevel::Patch, false));
+        assert_eq!(change("v1.2", "v1.9"), upgrade(VersionLevel::Minor, false));
+        assert_eq!(change("v1.9", "v2.0"), upgrade(VersionLevel::Major, true));
+        assert_eq!(change("v0.2", "v0.3"), upgrade(VersionLevel::Minor, true));
+        assert_eq!(change("v0.2.1", "v0.2.4"), upgrade(VersionLevel::Patch, false));
+        assert_eq!(change("v1.0.0-alpha", "v1.0.0"), upgrade(VersionLevel::Prerelease, false));
+        assert_eq!(change("v1.0.0", "v1.1.0-beta"), upgrade(VersionLevel::Minor, true));
+        assert_eq!(change("v2.0", "v1.9"), VersionChange::Downgrade);
+        assert_eq!(change("latest", "v1.0"), VersionChange::Incomparable);
+        assert_eq!(change("latest", "latest"), VersionChange::Unchanged);
+    }
+
+    #[test]
+    fn test_diff() {
+        let d = diff("trn:user:alice:tool:api:v1.0", "trn:org:acme:tool:api:v1.1");
+        assert_eq!(d.changed_components(), vec![TrnComponent::Platform, TrnComponent::Scope, TrnComponent::Version]);
+        assert!(d.changed(TrnComponent::Scope) && !d.changed(TrnComponent::ResourceId));
+        assert!(!d.same_resource());
+        assert!(!d.is_compatible_upgrade());
+        assert_eq!(
+            d.to_string(),
+            "platform: user -> org, scope: alice -> acme, version: v1.0 -> v1.1 (minor upgrade)"
+        );
+
+        let d = diff("trn:user:alice:tool:api:v1.0", "trn:user:alice:tool:api:v1.0");
+        assert!(d.is_empty() && d.is_compatible_upgrade());
+        assert_eq!(d.to_string(), "identical");
+
+        let json = serde_json::to_value(diff("trn:user:alice:tool:api:v1.0", "trn:user:alice:tool:api:v2.0")).unwrap();
+        assert_eq!(json["changes"][0]["component"], "version");
+        assert_eq!(json["version_change"], serde_json::json!({ "kind": "upgrade", "level": "major", "breaking": true }));
+    }
+}
@@ -0,0 +1,173 @@
+//! Binary encoding of TRNs for storage keys
+//!
+//! A TRN encodes as a format tag followed by its five components, each
+//! ended by a zero byte:
+//!
+//! ```text
+//! 0x01 user 0x00 alice 0x00 tool 0x00 weather-api 0x00 v1.0 0x00
+//! ```
+//!
+//! No component contains a zero byte, and zero sorts below every character
+//! they may hold, so byte order of the encodings is the order of TRNs
+//! compared component by component. All TRNs under a platform, scope or
+//! resource share the encoding of those leading components, which
+//! [`key_prefix`] builds for range and prefix scans.
+
+use crate::error::{TrnError, TrnResult};
+use crate::types::{Trn, TrnComponents};
+
+/// Tag of the current encoding
+const FORMAT_V1: u8 = 0x01;
+
+/// Ends each component
+const TERMINATOR: u8 = 0x00;
+
+/// Number of encoded components
+const COMPONENTS: usize = 5;
+
+/// Encode TRN components
+pub(crate) fn encode(components: TrnComponents<'_>) -> Vec<u8> {
+    let parts = [
+        components.platform,
+        components.scope,
+        components.resource_type,
+        components.resource_id,
+        components.version,
+    ];
+    let mut bytes = Vec::with_capacity(1 + parts.iter().map(|part| part.len() + 1).sum::<usize>());
+    bytes.push(FORMAT_V1);
+    for part in parts {
+        bytes.extend_from_slice(part.as_bytes());
+        bytes.push(TERMINATOR);
+    }
+    bytes
+}
+
+/// Decode and validate an encoded TRN
+pub(crate) fn decode(bytes: &[u8]) -> TrnResult<Trn> {
+    let invalid = |message: &str| TrnError::format(format!("Invalid TRN encoding: {message}"), None);
+
+    let Some((&tag, body)) = bytes.split_first() else {
+        return Err(invalid("empty input"));
+    };
+    if tag != FORMAT_V1 {
+        return Err(invalid(&format!("unknown format tag {tag:#04x}")));
+    }
+    let Some(body) = body.strip_suffix(&[TERMINATOR]) else {
+        return Err(invalid("unterminated component"));
+    };
+
+    let mut parts = [""; COMPONENTS];
+    let mut split = body.split(|&byte| byte == TERMINATOR);
+    for part in &mut parts {
+        let bytes = split.next().ok_or_else(|| invalid("too few components"))?;
+        *part = std::str::from_utf8(bytes).map_err(|_| invalid("component is not UTF-8"))?;
+    }
+    if split.next().is_some() {
+        return Err(invalid("too many components"));
+    }
+
+    let [platform, scope, resource_type, resource_id, version] = parts;
+    Trn::new(platform, scope, resource_type, resource_id, version)
+}
+
+/// Encode the leading components of a TRN, for scanning every TRN under them
+///
+/// `["user", "alice"]` gives the prefix of every encoded TRN on the `user`
+/// platform in the `alice` scope. Only whole components match: the prefix
+/// for scope `alice` does not cover scope `alice2`.
+///
+/// # Examples
+///
+/// ```rust
+/// use trn_rust::{key_prefix, Trn};
+///
+/// let prefix = key_prefix(&["user", "alice"])?;
+/// let trn = Trn::parse("trn:user:alice:tool:weather-api:v1.0")?;
+/// assert!(trn.to_bytes().starts_with(&prefix));
+///
+/// let other = Trn::parse("trn:user:alice2:tool:weather-api:v1.0")?;
+/// assert!(!other.to_bytes().starts_with(&prefix));
+/// # Ok::<(), trn_rust::TrnError>(())
+/// ```
+pub fn key_prefix(components: &[&str]) -> TrnResult<Vec<u8>> {
+    if components.len() > COMPONENTS {
+        return Err(TrnError::format(
+            format!("A TRN has {COMPONENTS} components, got {}", components.len()),
+            None,
+        ));
+    }
+    if let Some(part) = components.iter().find(|part| part.is_empty() || part.contains(['\0', ':'])) {
+        return Err(TrnError::format(format!("Invalid component '{part}' in key prefix"), None));
+    }
+
+    let mut bytes = vec![FORMAT_V1];
+    for part in components {
+        bytes.extend_from_slice(part.as_bytes());
+        bytes.push(TERMINATOR);
+    }
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let trn = Trn::parse("trn:user:alice:tool:weather-api:v1.0").unwrap();
+        let bytes = trn.to_bytes();
+        assert_eq!(bytes, b"\x01user\0alice\0tool\0weather-api\0v1.0\0");
+        assert_eq!(Trn::from_bytes(&bytes).unwrap(), trn);
+
+        for invalid in [
+            &b""[..],
+            b"\x02user\0alice\0tool\0weather-api\0v1.0\0",
+            b"\x01user\0alice\0tool\0weather-api\0v1.0",
+            b"\x01user\0alice\0tool\0weather-api\0",
+            b"\x01user\0alice\0tool\0weather-api\0v1.0\0extra\0",
+            b"\x01user\0alice\0tool\0weather-api\0\xff\0",
+            b"\x01user\0alice\0gadget\0weather-api\0v1.0\0",
+        ] {
+            assert!(Trn::from_bytes(invalid).is_err(), "{invalid:?}");
+        }
+    }
+
+    #[test]
+    fn test_order_is_preserved() {
+        let mut trns: Vec<Trn> = [
+            "trn:user:alice:tool:weather-api:v1.0",
+            "trn:user:alice-b:tool:weather-api:v1.0",
+            "trn:user:alice:tool:weather:v1.0",
+            "trn:org:acme:model:bert:v2.0",
+            "trn:user:alice2:dataset:crawl:v1.0",
+            "trn:user:alice:model:bert:v1.0",
+            "trn:user:al:tool:fetch:v1.0",
+        ]
+        .iter()
+        .map(|s| Trn::parse(s).unwrap())
+        .collect();
+
+        let mut encoded: Vec<Vec<u8>> = trns.iter().map(Trn::to_bytes).collect();
+        encoded.sort();
+        trns.sort_by(|a, b| {
+            let key = |trn: &Trn| {
+                let c = trn.components();
+                [c.platform, c.scope, c.resource_type, c.resource_id, c.version].map(str::to_string)
+            };
+            key(a).cmp(&key(b))
+        });
+        let decoded: Vec<Trn> = encoded.iter().map(|bytes| Trn::from_bytes(bytes).unwrap()).collect();
+        assert_eq!(decoded, trns);
+
+        // The TRNs of a scope are contiguous under its prefix
+        let prefix = key_prefix(&["user", "alice"]).unwrap();
+        let scoped: Vec<usize> = (0..encoded.len()).filter(|&i| encoded[i].starts_with(&prefix)).collect();
+        assert_eq!(scoped.len(), 3);
+        assert_eq!(scoped.last().unwrap() - scoped[0], 2);
+
+        assert_eq!(key_prefix(&[]).unwrap(), [FORMAT_V1]);
+        assert!(key_prefix(&["user", ""]).is_err());
+        assert!(key_prefix(&["a", "b", "c", "d", "e", "f"]).is_err());
+    }
+}
@@ -0,0 +1,207 @@
+//! Normalization of user-supplied identifiers
+//!
+//! TRN components are ASCII. Names typed by users are not: the same
+//! resource may arrive as `Café Menu`, `cafe\u{301} menu`, `Caf%C3%A9%20Menu`
+//! or `ＣＡＦＥ`, depending on locale, keyboard and transport.
+//! [`IdentifierOptions::normalize`] turns such names into a single valid
+//! component, so TRNs built from them compare and match deterministically:
+//!
+//! ```rust
+//! use trn_rust::{display_identifier, CaseFolding, IdentifierOptions, NonAsciiPolicy};
+//!
+//! let options = IdentifierOptions {
+//!     non_ascii: NonAsciiPolicy::Punycode,
+//!     case: CaseFolding::Lowercase,
+//! };
+//! let id = options.normalize("Café Menu")?;
+//! assert_eq!(id, "xn--caf-menu-d1a");
+//! assert_eq!(options.normalize("cafe\u{301} menu")?, id);
+//! assert_eq!(options.normalize("Caf%C3%A9%20Menu")?, id);
+//! assert_eq!(display_identifier(&id), "café-menu");
+//!
+//! // The default policy only accepts ASCII names
+//! assert!(IdentifierOptions::default().normalize("Café").is_err());
+//! # Ok::<(), trn_rust::TrnError>(())
+//! ```
+//!
+//! Normalization percent-decodes the input, trims it, applies Unicode NFKC
+//! (so full-width and composed forms agree), optionally lowercases,
+//! replaces whitespace runs with `-` and, under
+//! [`NonAsciiPolicy::Punycode`], encodes remaining non-ASCII names as
+//! `xn--` punycode. Lowercasing uses the locale-independent Unicode
+//! mapping, never the rules of the current locale. Normalizing an
+//! identifier twice gives the same result.
+
+use std::borrow::Cow;
+
+use percent_encoding::percent_decode_str;
+use serde::{Deserialize, Serialize};
+use unicode_normalization::UnicodeNormalization;
+
+use crate::diff::TrnComponent;
+use crate::error::{TrnError, TrnResult};
+use crate::validation::validate_component_value;
+
+/// Prefix of punycode-encoded identifiers
+const PUNYCODE_PREFIX: &str = "xn--";
+
+/// What to do with names that are not ASCII after normalization
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum NonAsciiPolicy {
+    /// Reject them
+    #[default]
+    Reject,
+    /// Encode them as `xn--` punycode
+    Punycode,
+}
+
+/// Case rule applied to names
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum CaseFolding {
+    /// Keep the case, so `Report` and `report` are different identifiers
+    #[default]
+    Preserve,
+    /// Lowercase, so `Report` and `report` are the same identifier
+    Lowercase,
+}
+
+/// How user-supplied names become TRN components
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct IdentifierOptions {
+    /// Handling of non-ASCII names
+    pub non_ascii: NonAsciiPolicy,
+    /// Case rule
+    pub case: CaseFolding,
+}
+
+impl IdentifierOptions {
+    /// Normalize a name into a resource ID
+    pub fn normalize(&self, name: &str) -> TrnResult<String> {
+        self.normalize_component(TrnComponent::ResourceId, name)
+    }
+
+    /// Normalize a name into the given component
+    pub fn normalize_component(&self, component: TrnComponent, name: &str) -> TrnResult<String> {
+        let decoded = percent_decode_str(name).decode_utf8().map_err(|e| {
+            TrnError::format(format!("{component} '{name}' is not UTF-8 when decoded: {e}"), None)
+        })?;
+        let mut value: String = decoded.trim().nfkc().collect();
+
+        // Already-encoded names are decoded, so both forms normalize alike
+        if self.non_ascii == NonAsciiPolicy::Punycode {
+            if let Some(unicode) = decode_punycode(&value) {
+                value = unicode.nfkc().collect();
+            }
+        }
+        if self.case == CaseFolding::Lowercase {
+            // Lowercasing can leave unnormalized sequences, such as for 'İ'
+            value = value.to_lowercase().nfkc().collect();
+        }
+        let mut value = value.split_whitespace().collect::<Vec<_>>().join("-");
+
+        if !value.is_ascii() {
+            if self.non_ascii == NonAsciiPolicy::Reject {
+                return Err(TrnError::validation(
+                    format!("{component} '{value}' contains non-ASCII characters"),
+                    "non_ascii_identifier".to_string(),
+                    None,
+                ));
+            }
+            let encoded = idna::punycode::encode_str(&value).ok_or_else(|| {
+                TrnError::format(format!("{component} '{value}' cannot be encoded as punycode"), None)
+            })?;
+            value = format!("{PUNYCODE_PREFIX}{encoded}");
+        }
+
+        validate_component_value(component, &value)?;
+        Ok(value)
+    }
+
+    /// Check whether two names normalize to the same resource ID
+    ///
+    /// Names that do not normalize are never the same.
+    pub fn same_identifier(&self, a: &str, b: &str) -> bool {
+        matches!((self.normalize(a), self.normalize(b)), (Ok(a), Ok(b)) if a == b)
+    }
+}
+
+/// Get the readable form of an identifier, decoding `xn--` punycode
+pub fn display_identifier(id: &str) -> Cow<'_, str> {
+    decode_punycode(id).map_or(Cow::Borrowed(id), Cow::Owned)
+}
+
+fn decode_punycode(id: &str) -> Option<String> {
+    let encoded = id
+        .get(..PUNYCODE_PREFIX.len())
+        .filter(|prefix| prefix.eq_ignore_ascii_case(PUNYCODE_PREFIX))
+        .map(|_| &id[PUNYCODE_PREFIX.len()..])?;
+    idna::punycode::decode_to_string(encoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PORTABLE: IdentifierOptions = IdentifierOptions {
+        non_ascii: NonAsciiPolicy::Punycode,
+        case: CaseFolding::Lowercase,
+    };
+
+    #[test]
+    fn test_ascii_names() {
+        let options = IdentifierOptions::default();
+        assert_eq!(options.normalize("weather-api").unwrap(), "weather-api");
+        assert_eq!(options.normalize("  Weather API ").unwrap(), "Weather-API");
+        assert_eq!(options.normalize("weather%2Dapi").unwrap(), "weather-api");
+        // Full-width forms are compatibility-equivalent to ASCII
+        assert_eq!(options.normalize("ＷＥＡＴＨＥＲ").unwrap(), "WEATHER");
+
+        for invalid in ["", "   ", "weather/api", "-weather", "null", "%ff", &"a".repeat(65)] {
+            assert!(options.normalize(invalid).is_err(), "{invalid}");
+        }
+        assert!(PORTABLE.normalize("NULL").is_err());
+    }
+
+    #[test]
+    fn test_non_ascii_policy() {
+        let err = IdentifierOptions::default().normalize("café").unwrap_err();
+        assert!(err.to_string().contains("non-ASCII"));
+
+        let options = IdentifierOptions {
+            non_ascii: NonAsciiPolicy::Punycode,
+            ..IdentifierOptions::default()
+        };
+        assert_eq!(options.normalize("café").unwrap(), "xn--caf-dma");
+        assert_eq!(options.normalize("Café").unwrap(), "xn--Caf-dma");
+        assert_eq!(options.normalize("天气").unwrap(), "xn--rss235b");
+        assert_eq!(display_identifier("xn--rss235b"), "天气");
+        assert_eq!(display_identifier("weather-api"), "weather-api");
+        assert_eq!(display_identifier("xn--"), "");
+
+        // Scopes do not allow '.', which punycode never produces
+        assert_eq!(options.normalize_component(TrnComponent::Scope, "équipe").unwrap(), "xn--quipe-9ra");
+        assert!(options.normalize(&"é".repeat(60)).is_err());
+    }
+
+    #[test]
+    fn test_equivalent_forms() {
+        // Composed, decomposed, percent-encoded, already encoded and upper case
+        let forms = ["Café Menu", "cafe\u{301}   menu", "Caf%C3%A9%20Menu", "xn--caf-menu-d1a", "CAFÉ MENU"];
+        for form in forms {
+            assert_eq!(PORTABLE.normalize(form).unwrap(), "xn--caf-menu-d1a", "{form}");
+            assert!(PORTABLE.same_identifier(form, forms[0]));
+        }
+        assert!(!IdentifierOptions::default().same_identifier("Report", "report"));
+        assert!(PORTABLE.same_identifier("Report", "report"));
+        assert!(!PORTABLE.same_identifier("weather/api", "weather/api"));
+
+        // Locale-independent lowercasing
+        assert_eq!(PORTABLE.normalize("İSTANBUL").unwrap(), PORTABLE.normalize("i\u{307}stanbul").unwrap());
+
+        // Normalizing is idempotent
+        for name in ["Straße", "ÅNGSTRÖM", "東京 タワー", "plain-name"] {
+            let once = PORTABLE.normalize(name).unwrap();
+            assert_eq!(PORTABLE.normalize(&once).unwrap(), once, "{name}");
+        }
+    }
+}
@@ -0,0 +1,325 @@
+//! Indexed TRN collections
+//!
+//! A [`TrnIndex`] keeps TRNs ordered by their [binary
+//! encoding](crate::Trn::to_bytes), so every TRN under a platform, scope,
+//! resource type or resource ID is one contiguous range. Prefix lookups,
+//! pattern queries with leading exact components and version listings
+//! visit only that range, where the `group_*` and `filter_*` utilities scan
+//! and reparse the whole collection on every call:
+//!
+//! ```rust
+//! use trn_rust::{Trn, TrnIndex};
+//!
+//! let index: TrnIndex = [
+//!     "trn:user:alice:tool:weather-api:v1.0",
+//!     "trn:user:alice:tool:weather-api:v1.10",
+//!     "trn:user:alice:tool:weather-api:v1.2",
+//!     "trn:user:alice:model:bert:v2.0",
+//!     "trn:user:bob:tool:weather-api:v1.0",
+//! ]
+//! .iter()
+//! .map(|trn| Trn::parse(trn))
+//! .collect::<Result<_, _>>()?;
+//!
+//! assert_eq!(index.with_prefix(&["user", "alice"])?.count(), 4);
+//! assert_eq!(index.find_matching("trn:user:*:tool:*:*")?.len(), 4);
+//!
+//! let base = Trn::parse("trn:user:alice:tool:weather-api:v1.0")?;
+//! let versions: Vec<&str> = index.versions(&base).iter().map(|trn| trn.version()).collect();
+//! assert_eq!(versions, ["v1.0", "v1.2", "v1.10"]);
+//! assert_eq!(index.latest_version(&base).unwrap().version(), "v1.10");
+//! # Ok::<(), trn_rust::TrnError>(())
+//! ```
+
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::ops::Bound;
+
+use crate::diff::TrnComponent;
+use crate::encoding::{encode, key_prefix};
+use crate::error::TrnResult;
+use crate::pattern::parse_pattern_components;
+use crate::pattern_set::TrnPatternSet;
+use crate::types::{Trn, TrnComponents};
+use crate::version::parse_partial;
+
+/// Ordered, deduplicated collection of TRNs with prefix lookups
+#[derive(Debug, Clone, Default)]
+pub struct TrnIndex {
+    entries: BTreeMap<Vec<u8>, Trn>,
+}
+
+impl TrnIndex {
+    /// Create an empty index
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a TRN, returning false if it was already present
+    pub fn insert(&mut self, trn: Trn) -> bool {
+        let key = encode(trn.components());
+        if self.entries.contains_key(&key) {
+            return false;
+        }
+        self.entries.insert(key, trn);
+        true
+    }
+
+    /// Parse and add a TRN, returning false if it was already present
+    pub fn insert_str(&mut self, trn: &str) -> TrnResult<bool> {
+        Trn::parse(trn).map(|trn| self.insert(trn))
+    }
+
+    /// Remove a TRN, returning whether it was present
+    pub fn remove<'t>(&mut self, trn: impl Into<TrnComponents<'t>>) -> bool {
+        self.entries.remove(&encode(trn.into())).is_some()
+    }
+
+    /// Check if the index holds a TRN
+    pub fn contains<'t>(&self, trn: impl Into<TrnComponents<'t>>) -> bool {
+        self.entries.contains_key(&encode(trn.into()))
+    }
+
+    /// Get TRN count
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Check whether the index has no TRNs
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Iterate over all TRNs, ordered component by component
+    pub fn iter(&self) -> impl Iterator<Item = &Trn> + '_ {
+        self.entries.values()
+    }
+
+    /// Iterate over the TRNs whose leading components are `components`,
+    /// such as `["user", "alice"]` for everything in one scope
+    ///
+    /// Only whole components match, as with [`key_prefix`].
+    pub fn with_prefix(&self, components: &[&str]) -> TrnResult<impl Iterator<Item = &Trn> + '_> {
+        Ok(self.range(key_prefix(components)?))
+    }
+
+    /// Iterate over the TRNs of a platform
+    pub fn by_platform<'a>(&'a self, platform: &str) -> impl Iterator<Item = &'a Trn> + 'a {
+        self.range_or_empty(&[platform])
+    }
+
+    /// Iterate over the TRNs of a scope
+    pub fn by_scope<'a>(&'a self, platform: &str, scope: &str) -> impl Iterator<Item = &'a Trn> + 'a {
+        self.range_or_empty(&[platform, scope])
+    }
+
+    /// Iterate over the TRNs of a resource type in a scope
+    pub fn by_resource_type<'a>(
+        &'a self,
+        platform: &str,
+        scope: &str,
+        resource_type: &str,
+    ) -> impl Iterator<Item = &'a Trn> + 'a {
+        self.range_or_empty(&[platform, scope, resource_type])
+    }
+
+    /// Find the TRNs matching a pattern, in index order
+    ///
+    /// Leading components without wildcards narrow the search to their
+    /// range; the rest are matched as in [`TrnPatternSet`].
+    pub fn find_matching(&self, pattern: &str) -> TrnResult<Vec<&Trn>> {
+        let components = parse_pattern_components(pattern)?;
+        let set = TrnPatternSet::new([pattern])?;
+        let exact: Vec<&str> = [
+            &components.platform,
+            &components.scope,
+            &components.resource_type,
+            &components.resource_id,
+            &components.version,
+        ]
+        .into_iter()
+        .map_while(|component| component.as_deref().filter(|value| !value.contains('*')))
+        .collect();
+
+        Ok(self.range_or_empty(&exact).filter(|trn| set.matches_any(*trn)).collect())
+    }
+
+    /// List the versions of a resource, ignoring the version of `base`
+    ///
+    /// Semantic versions come first, lowest to highest; other versions,
+    /// such as `latest`, follow in byte order.
+    pub fn versions<'t>(&self, base: impl Into<TrnComponents<'t>>) -> Vec<&Trn> {
+        let base = base.into();
+        let mut versions: Vec<&Trn> = self
+            .range_or_empty(&[base.platform, base.scope, base.resource_type, base.resource_id])
+            .collect();
+        versions.sort_by(|a, b| compare_versions(a.version(), b.version()));
+        versions
+    }
+
+    /// Get the highest semantic version of a resource
+    pub fn latest_version<'t>(&self, base: impl Into<TrnComponents<'t>>) -> Option<&Trn> {
+        let base = base.into();
+        self.range_or_empty(&[base.platform, base.scope, base.resource_type, base.resource_id])
+            .filter_map(|trn| parse_partial(trn.version()).map(|(version, _)| (version, trn)))
+            .max_by(|(a, _), (b, _)| a.cmp(b))
+            .map(|(_, trn)| trn)
+    }
+
+    /// Group the TRNs by the value of a component
+    pub fn group_by(&self, component: TrnComponent) -> BTreeMap<&str, Vec<&Trn>> {
+        let mut groups: BTreeMap<&str, Vec<&Trn>> = BTreeMap::new();
+        for trn in self.iter() {
+            let value = match component {
+                TrnComponent::Platform => trn.platform(),
+                TrnComponent::Scope => trn.scope(),
+                TrnComponent::ResourceType => trn.resource_type(),
+                TrnComponent::ResourceId => trn.resource_id(),
+                TrnComponent::Version => trn.version(),
+            };
+            groups.entry(value).or_default().push(trn);
+        }
+        groups
+    }
+
+    fn range(&self, prefix: Vec<u8>) -> impl Iterator<Item = &Trn> + '_ {
+        self.entries
+            .range::<[u8], _>((Bound::Included(prefix.as_slice()), Bound::Unbounded))
+            .take_while(move |(key, _)| key.starts_with(&prefix))
+            .map(|(_, trn)| trn)
+    }
+
+    /// Range for components that may not form a valid prefix, which then
+    /// match nothing
+    fn range_or_empty(&self, components: &[&str]) -> impl Iterator<Item = &Trn> + '_ {
+        key_prefix(components)
+            .ok()
+            .into_iter()
+            .flat_map(|prefix| self.range(prefix))
+    }
+}
+
+impl FromIterator<Trn> for TrnIndex {
+    fn from_iter<I: IntoIterator<Item = Trn>>(trns: I) -> Self {
+        let mut index = Self::new();
+        index.extend(trns);
+        index
+    }
+}
+
+impl Extend<Trn> for TrnIndex {
+    fn extend<I: IntoIterator<Item = Trn>>(&mut self, trns: I) {
+        for trn in trns {
+            self.insert(trn);
+        }
+    }
+}
+
+impl<'a> IntoIterator for &'a TrnIndex {
+    type Item = &'a Trn;
+    type IntoIter = std::collections::btree_map::Values<'a, Vec<u8>, Trn>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.values()
+    }
+}
+
+fn compare_versions(a: &str, b: &str) -> Ordering {
+    match (parse_partial(a), parse_partial(b)) {
+        (Some((a, _)), Some((b, _))) => a.cmp(&b),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => a.cmp(b),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn index(trns: &[&str]) -> TrnIndex {
+        trns.iter().map(|trn| Trn::parse(trn).unwrap()).collect()
+    }
+
+    #[test]
+    fn test_insert_and_remove() {
+        let mut index = TrnIndex::new();
+        assert!(index.insert_str("trn:user:alice:tool:fetch:v1.0").unwrap());
+        assert!(!index.insert_str("trn:user:alice:tool:fetch:v1.0").unwrap());
+        assert!(index.insert_str("trn:user:alice:gadget:fetch:v1.0").is_err());
+        assert_eq!(index.len(), 1);
+
+        let trn = Trn::parse("trn:user:alice:tool:fetch:v1.0").unwrap();
+        assert!(index.contains(&trn));
+        assert!(index.remove(&trn));
+        assert!(!index.remove(&trn));
+        assert!(index.is_empty());
+    }
+
+    #[test]
+    fn test_prefix_lookups() {
+        let index = index(&[
+            "trn:user:alice:tool:fetch:v1.0",
+            "trn:user:alice:tool:fetch:v2.0",
+            "trn:user:alice2:tool:fetch:v1.0",
+            "trn:user:alice:model:bert:v1.0",
+            "trn:org:acme:tool:fetch:v1.0",
+        ]);
+
+        assert_eq!(index.by_platform("user").count(), 4);
+        assert_eq!(index.by_scope("user", "alice").count(), 3);
+        assert_eq!(index.by_resource_type("user", "alice", "tool").count(), 2);
+        assert_eq!(index.with_prefix(&["user", "alice", "tool", "fetch", "v2.0"]).unwrap().count(), 1);
+        assert_eq!(index.with_prefix(&[]).unwrap().count(), 5);
+        assert!(index.with_prefix(&["user", ""]).is_err());
+        assert_eq!(index.by_scope("user", "").count(), 0);
+        assert_eq!(index.by_platform("nope").count(), 0);
+
+        let groups = index.group_by(TrnComponent::ResourceType);
+        assert_eq!(groups["tool"].len(), 4);
+        assert_eq!(groups["model"].len(), 1);
+    }
+
+    #[test]
+    fn test_find_matching() {
+        let index = index(&[
+            "trn:user:alice:tool:weather-api:v1.0",
+            "trn:user:alice:tool:weather-map:v1.0",
+            "trn:user:alice:tool:fetch:v1.0",
+            "trn:user:bob:tool:weather-api:v1.0",
+            "trn:org:acme:model:weather-api:v1.0",
+        ]);
+
+        let found = |pattern| {
+            index.find_matching(pattern).unwrap().iter().map(ToString::to_string).collect::<Vec<_>>()
+        };
+        assert_eq!(
+            found("trn:user:alice:tool:weather-*:*"),
+            ["trn:user:alice:tool:weather-api:v1.0", "trn:user:alice:tool:weather-map:v1.0"]
+        );
+        assert_eq!(found("trn:*:*:*:weather-api:*").len(), 3);
+        assert_eq!(found("trn:user:*:tool:weather-api:v1.0").len(), 2);
+        assert!(found("trn:user:carol:*:*:*").is_empty());
+        assert!(index.find_matching("trn:user:alice").is_err());
+    }
+
+    #[test]
+    fn test_versions() {
+        let index = index(&[
+            "trn:user:alice:tool:fetch:latest",
+            "trn:user:alice:tool:fetch:v2.0",
+            "trn:user:alice:tool:fetch:v1.10",
+            "trn:user:alice:tool:fetch:v1.9.3",
+            "trn:user:alice:tool:fetch-all:v9.0",
+        ]);
+
+        let base = Trn::parse("trn:user:alice:tool:fetch:v1.0").unwrap();
+        let versions: Vec<&str> = index.versions(&base).iter().map(|trn| trn.version()).collect();
+        assert_eq!(versions, ["v1.9.3", "v1.10", "v2.0", "latest"]);
+        assert_eq!(index.latest_version(&base).unwrap().version(), "v2.0");
+
+        let missing = Trn::parse("trn:user:alice:tool:store:v1.0").unwrap();
+        assert!(index.versions(&missing).is_empty());
+        assert!(index.latest_version(&missing).is_none());
+    }
+}
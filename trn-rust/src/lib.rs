@@ -11,7 +11,7 @@
 //! - Parsing and validating TRN strings
 //! - Converting between TRN and URL formats
 //! - Pattern matching and filtering
-//! - Version comparison and management
+//! - Version comparison, management and resolution against requirements
 //! - Builder pattern for TRN construction
 //!
 //! ## TRN Format
@@ -92,36 +92,71 @@ mod error;
 mod types;
 
 // Main functionality modules
+mod acl;
 mod builder;
+mod diff;
+mod encoding;
+mod identifier;
+mod index;
+mod naming;
+mod params;
 mod parsing;
 mod pattern;
+mod pattern_set;
+mod pipeline;
+pub mod serde_trn;
+#[cfg(feature = "signing")]
+mod signing;
+mod suggest;
 mod url;
 mod utils;
 mod validation;
+mod version;
 
 // Re-export public API
-pub use builder::TrnBuilder;
+pub use acl::{Effect, Explanation, Policy, PolicyStack, Rule};
+pub use builder::{DerivedTrnBuilder, TrnBuilder};
+pub use diff::{ComponentChange, TrnComponent, TrnDiff, VersionChange, VersionLevel};
+pub use encoding::key_prefix;
+pub use identifier::{display_identifier, CaseFolding, IdentifierOptions, NonAsciiPolicy};
+pub use index::TrnIndex;
+pub use naming::{DefaultNamingPolicy, NamingPolicy, NamingRules, NamingRulesBuilder};
+pub use params::TrnParams;
+pub use suggest::{suggest_similar, suggest_similar_with, Suggestion, SuggestionWeights};
 pub use error::{TrnError, TrnResult};
-pub use types::{Platform, ResourceType, Trn, TrnComponents};
+pub use types::{Platform, ResourceType, Trn, TrnComponents, TrnRef};
 
 // Re-export utility functions
 pub use utils::*;
 
 // Re-export URL conversion functions
-pub use url::url_to_trn;
+pub use url::{url_to_trn, UrlEncoding, UrlScheme, UrlSchemeBuilder};
 
 // Re-export validation functions
 pub use validation::{
-    is_valid_trn, validate_trn_string, validate_trn_struct, validate_multiple_trns,
+    is_valid_trn, validate_trn_string, validate_trn_struct, validate_trn_struct_with, validate_multiple_trns,
     generate_validation_report, check_component_format, validate_naming_conventions,
-    validate_performance_batch, ValidationCache, ValidationCacheStats, ValidationStats,
-    ValidationReport
+    validate_performance_batch, validate_batch, ValidationCache, ValidationCacheConfig, ValidationCacheStats, ValidationStats,
+    ValidationReport, BatchItemError, BatchValidationReport
 };
+#[cfg(feature = "rayon")]
+#[cfg_attr(docsrs, doc(cfg(feature = "rayon")))]
+pub use validation::validate_batch_parallel;
 
 // Note: Validate trait is defined in this module, not re-exported
 
 // Re-export pattern matching
 pub use pattern::{find_matching_trns, TrnMatcher};
+pub use pattern_set::TrnPatternSet;
+pub use pipeline::{TrnPipeline, TrnPipelineBuilder, TrnPipelineIter, TrnPipelineResult};
+
+// Re-export version resolution
+pub use version::{resolve_version, resolve_version_with, PrereleasePolicy, VersionRequirement};
+
+// Re-export signing
+#[cfg(feature = "signing")]
+#[cfg_attr(docsrs, doc(cfg(feature = "signing")))]
+pub use signing::{sign, verify, SignatureAlgorithm, SignedTrn, SigningKey, VerifyingKey};
 
 // Feature-gated modules (commented out for now - implement as needed)
 // #[cfg(feature = "cli")]
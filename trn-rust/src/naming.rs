@@ -0,0 +1,265 @@
+//! Naming-convention policies
+//!
+//! The built-in validation accepts any TRN of the right format. Tenants
+//! with stricter conventions express them as a [`NamingPolicy`], applied on
+//! top of the built-in rules with
+//! [`validate_trn_struct_with`](crate::validate_trn_struct_with) or
+//! [`Trn::validate_with`](crate::Trn::validate_with).
+//!
+//! [`NamingRules`] covers the usual conventions:
+//!
+//! ```rust
+//! use trn_rust::{NamingRules, Trn, TrnComponent};
+//!
+//! let rules = NamingRules::builder()
+//!     .allow_platforms(["org", "user"])
+//!     .scope_pattern(r"^acme(-[a-z]+)?$")
+//!     .reserved_resource_ids(["admin", "root"])
+//!     .max_length(TrnComponent::ResourceId, 24)
+//!     .build()?;
+//!
+//! assert!(Trn::parse("trn:org:acme-labs:tool:weather-api:v1.0")?.validate_with(&rules).is_ok());
+//! assert!(Trn::parse("trn:org:globex:tool:weather-api:v1.0")?.validate_with(&rules).is_err());
+//! assert!(Trn::parse("trn:org:acme:tool:admin:v1.0")?.validate_with(&rules).is_err());
+//! # Ok::<(), trn_rust::TrnError>(())
+//! ```
+//!
+//! Other rules implement the trait directly; any
+//! `Fn(TrnComponents<'_>) -> TrnResult<()>` closure is a policy too.
+
+use std::collections::HashSet;
+
+use regex::Regex;
+
+use crate::diff::TrnComponent;
+use crate::error::{TrnError, TrnResult};
+use crate::types::TrnComponents;
+
+/// Naming conventions a TRN must follow beyond the built-in rules
+pub trait NamingPolicy: Send + Sync {
+    /// Check a TRN's components against the policy
+    fn check(&self, trn: TrnComponents<'_>) -> TrnResult<()>;
+}
+
+impl<F> NamingPolicy for F
+where
+    F: Fn(TrnComponents<'_>) -> TrnResult<()> + Send + Sync,
+{
+    fn check(&self, trn: TrnComponents<'_>) -> TrnResult<()> {
+        self(trn)
+    }
+}
+
+/// Policy with no conventions beyond the built-in rules
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DefaultNamingPolicy;
+
+impl NamingPolicy for DefaultNamingPolicy {
+    fn check(&self, _trn: TrnComponents<'_>) -> TrnResult<()> {
+        Ok(())
+    }
+}
+
+/// Configurable naming conventions, built with [`NamingRules::builder`]
+#[derive(Debug, Clone, Default)]
+pub struct NamingRules {
+    allowed_platforms: Option<HashSet<String>>,
+    scope_patterns: Vec<Regex>,
+    reserved_resource_ids: HashSet<String>,
+    max_lengths: Vec<(TrnComponent, usize)>,
+}
+
+impl NamingRules {
+    /// Create a builder with no rules
+    pub fn builder() -> NamingRulesBuilder {
+        NamingRulesBuilder::default()
+    }
+}
+
+impl NamingPolicy for NamingRules {
+    fn check(&self, trn: TrnComponents<'_>) -> TrnResult<()> {
+        let input = || Some(trn_string(trn));
+
+        if let Some(allowed) = &self.allowed_platforms {
+            if !allowed.contains(trn.platform) {
+                return Err(TrnError::validation(
+                    format!("Platform '{}' is not allowed by the naming policy", trn.platform),
+                    "naming_platform".to_string(),
+                    input(),
+                ));
+            }
+        }
+
+        if !self.scope_patterns.is_empty()
+            && !self.scope_patterns.iter().any(|pattern| pattern.is_match(trn.scope))
+        {
+            return Err(TrnError::validation(
+                format!("Scope '{}' does not match the naming policy", trn.scope),
+                "naming_scope".to_string(),
+                input(),
+            ));
+        }
+
+        if self.reserved_resource_ids.contains(trn.resource_id) {
+            return Err(TrnError::reserved_word(trn.resource_id, "resource_id", input()));
+        }
+
+        for &(component, max_length) in &self.max_lengths {
+            let value = component_value(trn, component);
+            if value.len() > max_length {
+                return Err(TrnError::length(
+                    format!("{component} is longer than the naming policy allows"),
+                    value.len(),
+                    max_length,
+                    input(),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Builder for [`NamingRules`]
+#[derive(Debug, Clone, Default)]
+pub struct NamingRulesBuilder {
+    allowed_platforms: Option<HashSet<String>>,
+    scope_patterns: Vec<String>,
+    reserved_resource_ids: HashSet<String>,
+    max_lengths: Vec<(TrnComponent, usize)>,
+}
+
+impl NamingRulesBuilder {
+    /// Allow a platform; once any is allowed, others are rejected
+    pub fn allow_platform<S: Into<String>>(mut self, platform: S) -> Self {
+        self.allowed_platforms.get_or_insert_with(HashSet::new).insert(platform.into());
+        self
+    }
+
+    /// Allow several platforms
+    pub fn allow_platforms<I, S>(self, platforms: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        platforms.into_iter().fold(self, Self::allow_platform)
+    }
+
+    /// Require scopes to match a regex; with several, any may match
+    pub fn scope_pattern<S: Into<String>>(mut self, pattern: S) -> Self {
+        self.scope_patterns.push(pattern.into());
+        self
+    }
+
+    /// Reserve a resource ID
+    pub fn reserved_resource_id<S: Into<String>>(mut self, resource_id: S) -> Self {
+        self.reserved_resource_ids.insert(resource_id.into());
+        self
+    }
+
+    /// Reserve several resource IDs
+    pub fn reserved_resource_ids<I, S>(self, resource_ids: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        resource_ids.into_iter().fold(self, Self::reserved_resource_id)
+    }
+
+    /// Limit the length of a component, below the built-in maximum
+    pub fn max_length(mut self, component: TrnComponent, max_length: usize) -> Self {
+        self.max_lengths.retain(|(existing, _)| *existing != component);
+        self.max_lengths.push((component, max_length));
+        self
+    }
+
+    /// Build the rules, compiling the scope patterns
+    pub fn build(self) -> TrnResult<NamingRules> {
+        let scope_patterns = self.scope_patterns
+            .iter()
+            .map(|pattern| {
+                Regex::new(pattern).map_err(|e| {
+                    TrnError::builder_invalid_field("scope_pattern".to_string(), format!("Invalid regex '{pattern}': {e}"))
+                })
+            })
+            .collect::<TrnResult<_>>()?;
+        Ok(NamingRules {
+            allowed_platforms: self.allowed_platforms,
+            scope_patterns,
+            reserved_resource_ids: self.reserved_resource_ids,
+            max_lengths: self.max_lengths,
+        })
+    }
+}
+
+fn component_value(trn: TrnComponents<'_>, component: TrnComponent) -> &str {
+    match component {
+        TrnComponent::Platform => trn.platform,
+        TrnComponent::Scope => trn.scope,
+        TrnComponent::ResourceType => trn.resource_type,
+        TrnComponent::ResourceId => trn.resource_id,
+        TrnComponent::Version => trn.version,
+    }
+}
+
+fn trn_string(trn: TrnComponents<'_>) -> String {
+    format!(
+        "trn:{}:{}:{}:{}:{}",
+        trn.platform, trn.scope, trn.resource_type, trn.resource_id, trn.version
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Trn;
+    use crate::validation::validate_trn_struct_with;
+
+    fn check(policy: &dyn NamingPolicy, trn: &str) -> TrnResult<()> {
+        validate_trn_struct_with(&Trn::parse(trn).unwrap(), policy)
+    }
+
+    #[test]
+    fn test_naming_rules() {
+        let rules = NamingRules::builder()
+            .allow_platform("org")
+            .scope_pattern("^acme$")
+            .scope_pattern("^acme-[a-z]+$")
+            .reserved_resource_id("admin")
+            .max_length(TrnComponent::ResourceId, 8)
+            .max_length(TrnComponent::Version, 4)
+            .build()
+            .unwrap();
+
+        assert!(check(&rules, "trn:org:acme:tool:fetch:v1.0").is_ok());
+        assert!(check(&rules, "trn:org:acme-labs:tool:fetch:v1.0").is_ok());
+        assert!(matches!(check(&rules, "trn:user:acme:tool:fetch:v1.0"), Err(TrnError::Validation { .. })));
+        assert!(check(&rules, "trn:org:acme2:tool:fetch:v1.0").is_err());
+        assert!(matches!(check(&rules, "trn:org:acme:tool:admin:v1.0"), Err(TrnError::ReservedWord { .. })));
+        assert!(matches!(
+            check(&rules, "trn:org:acme:tool:weather-api:v1.0"),
+            Err(TrnError::Length { max_length: 8, .. })
+        ));
+        assert!(check(&rules, "trn:org:acme:tool:fetch:v1.0.1").is_err());
+
+        assert!(NamingRules::builder().scope_pattern("(").build().is_err());
+        assert!(check(&NamingRules::default(), "trn:user:bob:tool:weather-api:v1.0").is_ok());
+    }
+
+    #[test]
+    fn test_custom_policies() {
+        let lowercase = |trn: TrnComponents<'_>| {
+            if trn.resource_id.chars().any(|c| c.is_ascii_uppercase()) {
+                return Err(TrnError::validation(
+                    "Resource IDs must be lowercase".to_string(),
+                    "lowercase".to_string(),
+                    None,
+                ));
+            }
+            Ok(())
+        };
+        assert!(check(&lowercase, "trn:user:alice:tool:fetch:v1.0").is_ok());
+        assert!(check(&lowercase, "trn:user:alice:tool:getUserById:v1.0").is_err());
+        assert!(check(&DefaultNamingPolicy, "trn:user:alice:tool:getUserById:v1.0").is_ok());
+    }
+}
@@ -0,0 +1,244 @@
+//! Metadata parameters on TRNs
+//!
+//! A TRN may carry a query-like suffix of metadata, such as
+//! `trn:user:alice:tool:weather-api:v1.0?env=prod&region=us-east`. The
+//! parameters are not part of the grammar, so [`Trn::parse`] rejects them;
+//! [`Trn::parse_with_params`] and `str::parse` accept them and the TRN keeps
+//! them on round-trip:
+//!
+//! ```rust
+//! use trn_rust::Trn;
+//!
+//! let trn = Trn::parse_with_params("trn:user:alice:tool:weather-api:v1.0?region=us-east&env=prod")?;
+//! assert_eq!(trn.params().get("env"), Some("prod"));
+//! assert_eq!(trn.to_string(), "trn:user:alice:tool:weather-api:v1.0?env=prod&region=us-east");
+//!
+//! let replicas = trn.with_param("replicas", "3")?;
+//! assert_eq!(replicas.params().get_parsed::<u32>("replicas"), Some(Ok(3)));
+//! # Ok::<(), trn_rust::TrnError>(())
+//! ```
+//!
+//! Parameters are kept sorted by key. Keys use the resource ID characters;
+//! values are percent-encoded when written. Validation, pattern matching,
+//! URLs and binary keys all use the TRN without its parameters.
+//!
+//! [`Trn::parse`]: crate::Trn::parse
+//! [`Trn::parse_with_params`]: crate::Trn::parse_with_params
+
+use std::collections::btree_map;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, CONTROLS};
+
+use crate::error::{TrnError, TrnResult};
+
+/// Starts the parameters of a TRN
+pub(crate) const PARAMS_SEPARATOR: char = '?';
+
+/// Characters encoded in parameter values
+const VALUE_ENCODE_SET: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'%')
+    .add(b'&')
+    .add(b'+')
+    .add(b'=')
+    .add(b'?');
+
+/// Metadata parameters of a TRN, sorted by key
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct TrnParams(BTreeMap<String, String>);
+
+impl TrnParams {
+    /// Create empty parameters
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parse parameters from a query string, without the leading `?`
+    pub fn parse(query: &str) -> TrnResult<Self> {
+        let invalid = |message: String| TrnError::format(message, Some(query.to_string()));
+
+        let mut params = Self::new();
+        for pair in query.split('&') {
+            let (key, value) = pair
+                .split_once('=')
+                .ok_or_else(|| invalid(format!("Parameter '{pair}' has no value")))?;
+            check_key(key)?;
+            let value = percent_decode_str(value)
+                .decode_utf8()
+                .map_err(|e| invalid(format!("Parameter '{key}' is not UTF-8: {e}")))?;
+            if params.0.insert(key.to_string(), value.into_owned()).is_some() {
+                return Err(invalid(format!("Duplicate parameter '{key}'")));
+            }
+        }
+        Ok(params)
+    }
+
+    /// Get a parameter
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).map(String::as_str)
+    }
+
+    /// Get a parameter parsed as `T`, such as a number or a flag
+    pub fn get_parsed<T: FromStr>(&self, key: &str) -> Option<Result<T, T::Err>> {
+        self.get(key).map(str::parse)
+    }
+
+    /// Check if a parameter is set
+    pub fn contains(&self, key: &str) -> bool {
+        self.0.contains_key(key)
+    }
+
+    /// Set a parameter, returning its previous value
+    pub fn insert(&mut self, key: impl Into<String>, value: impl Into<String>) -> TrnResult<Option<String>> {
+        let key = key.into();
+        check_key(&key)?;
+        Ok(self.0.insert(key, value.into()))
+    }
+
+    /// Remove a parameter, returning its value
+    pub fn remove(&mut self, key: &str) -> Option<String> {
+        self.0.remove(key)
+    }
+
+    /// Iterate over the parameters, sorted by key
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> + '_ {
+        self.0.iter().map(|(key, value)| (key.as_str(), value.as_str()))
+    }
+
+    /// Get parameter count
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Check whether there are no parameters
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl fmt::Display for TrnParams {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, (key, value)) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str("&")?;
+            }
+            write!(f, "{key}={}", utf8_percent_encode(value, VALUE_ENCODE_SET))?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for TrnParams {
+    type Err = TrnError;
+
+    fn from_str(s: &str) -> TrnResult<Self> {
+        Self::parse(s)
+    }
+}
+
+impl<'a> IntoIterator for &'a TrnParams {
+    type Item = (&'a String, &'a String);
+    type IntoIter = btree_map::Iter<'a, String, String>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+impl From<TrnParams> for BTreeMap<String, String> {
+    fn from(params: TrnParams) -> Self {
+        params.0
+    }
+}
+
+impl TryFrom<BTreeMap<String, String>> for TrnParams {
+    type Error = TrnError;
+
+    fn try_from(params: BTreeMap<String, String>) -> TrnResult<Self> {
+        params.keys().try_for_each(|key| check_key(key))?;
+        Ok(Self(params))
+    }
+}
+
+/// Split a TRN string into the TRN and its parameters, if any
+pub(crate) fn split_params(input: &str) -> TrnResult<(&str, TrnParams)> {
+    match input.split_once(PARAMS_SEPARATOR) {
+        None => Ok((input, TrnParams::new())),
+        Some((trn, "")) => Err(TrnError::format("Empty parameter list", Some(trn.to_string()))),
+        Some((trn, query)) => Ok((trn, TrnParams::parse(query)?)),
+    }
+}
+
+fn check_key(key: &str) -> TrnResult<()> {
+    let valid = key
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphanumeric())
+        && key.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '-'));
+    if valid {
+        Ok(())
+    } else {
+        Err(TrnError::validation(
+            format!("Invalid parameter name '{key}'"),
+            "param_name".to_string(),
+            None,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Trn;
+
+    #[test]
+    fn test_params_parsing() {
+        let params = TrnParams::parse("region=us%20east&env=prod&debug=true").unwrap();
+        assert_eq!(params.len(), 3);
+        assert_eq!(params.get("region"), Some("us east"));
+        assert_eq!(params.get_parsed::<bool>("debug"), Some(Ok(true)));
+        assert!(params.get_parsed::<u32>("env").unwrap().is_err());
+        assert_eq!(params.to_string(), "debug=true&env=prod&region=us%20east");
+        assert_eq!(TrnParams::parse(&params.to_string()).unwrap(), params);
+
+        for invalid in ["", "env", "env=a&env=b", "=prod", "e nv=prod", "-env=prod", "env=%ff"] {
+            assert!(TrnParams::parse(invalid).is_err(), "{invalid}");
+        }
+
+        let mut params = TrnParams::new();
+        assert_eq!(params.insert("note", "a&b=c?#").unwrap(), None);
+        assert!(params.insert("bad key", "x").is_err());
+        assert_eq!(TrnParams::parse(&params.to_string()).unwrap().get("note"), Some("a&b=c?#"));
+    }
+
+    #[test]
+    fn test_trn_params() {
+        let input = "trn:user:alice:tool:weather-api:v1.0?env=prod&region=us-east";
+        assert!(Trn::parse(input).is_err());
+
+        let trn = Trn::parse_with_params(input).unwrap();
+        assert_eq!(trn.to_string(), input);
+        assert_eq!(trn.params().get("region"), Some("us-east"));
+        assert!(trn.is_valid());
+        assert!(trn.matches_pattern("trn:user:alice:tool:*:*"));
+        assert_eq!(trn.without_params().to_string(), "trn:user:alice:tool:weather-api:v1.0");
+        assert_ne!(trn, trn.without_params());
+
+        // `FromStr` and serde keep the parameters; binary keys drop them
+        assert_eq!(trn.to_string().parse::<Trn>().unwrap(), trn);
+        assert_eq!(Trn::from_bytes(&trn.to_bytes()).unwrap(), trn.without_params());
+        assert_eq!(trn.to_bytes(), trn.without_params().to_bytes());
+        let json = serde_json::to_value(&trn).unwrap();
+        assert_eq!(json, input);
+        assert_eq!(serde_json::from_value::<Trn>(json).unwrap(), trn);
+
+        assert!(Trn::parse_with_params("trn:user:alice:tool:weather-api:v1.0").unwrap().params().is_empty());
+        assert!(Trn::parse_with_params("trn:user:alice:tool:weather-api:v1.0?").is_err());
+        assert!(Trn::parse_with_params("trn:user:alice:gadget:weather-api:v1.0?env=prod").is_err());
+    }
+}
@@ -8,7 +8,7 @@ use percent_encoding::percent_decode_str;
 
 use crate::constants::*;
 use crate::error::{TrnError, TrnResult};
-use crate::types::{Trn, TrnComponents};
+use crate::types::{Trn, TrnComponents, TrnRef};
 
 /// Parse TRN string into a TRN object
 pub fn parse_trn(input: &str) -> TrnResult<Trn> {
@@ -111,18 +111,16 @@ pub fn parse_trn(input: &str) -> TrnResult<Trn> {
 /// Parse TRN components from a string (zero-copy)
 pub fn parse_trn_components(input: &str) -> TrnResult<TrnComponents<'_>> {
     // Split TRN by colons - expect exactly 6 parts for simplified structure
-    let parts: Vec<&str> = input.split(':').collect();
-    
-    if parts.len() != TRN_FIXED_COMPONENT_COUNT {
+    let Some(parts) = split_components(input) else {
         return Err(TrnError::format(
             format!(
                 "TRN must have exactly {} components (trn:platform:scope:resource_type:resource_id:version), found {}",
                 TRN_FIXED_COMPONENT_COUNT,
-                parts.len()
+                input.split(':').count()
             ),
             Some(input.to_string()),
         ));
-    }
+    };
     
     if parts[0] != "trn" {
         return Err(TrnError::format(
@@ -188,6 +186,24 @@ pub fn parse_trn_components(input: &str) -> TrnResult<TrnComponents<'_>> {
     })
 }
 
+/// Parse and validate a TRN string into a borrowed view, without allocating
+pub fn parse_trn_ref(input: &str) -> TrnResult<TrnRef<'_>> {
+    // Same checks as `Trn::parse`, minus the validation cache
+    crate::validation::validate_trn_str(input)?;
+    let components = parse_trn_components(input)?;
+    Ok(TrnRef::from_parts(input, components))
+}
+
+/// Split a TRN at its colons, if it has exactly the fixed component count
+fn split_components(input: &str) -> Option<[&str; TRN_FIXED_COMPONENT_COUNT]> {
+    let mut parts = [""; TRN_FIXED_COMPONENT_COUNT];
+    let mut split = input.split(':');
+    for part in &mut parts {
+        *part = split.next()?;
+    }
+    split.next().is_none().then_some(parts)
+}
+
 /// Extract normalized components
 #[allow(dead_code)]
 pub fn extract_components(input: &str) -> TrnResult<(String, String, String, String, String)> {
@@ -26,12 +26,12 @@ struct CompiledPattern {
 
 /// Pattern components with wildcards
 #[derive(Debug, Clone)]
-struct PatternComponents {
-    platform: Option<String>,
-    scope: Option<String>,
-    resource_type: Option<String>,
-    resource_id: Option<String>,
-    version: Option<String>,
+pub(crate) struct PatternComponents {
+    pub(crate) platform: Option<String>,
+    pub(crate) scope: Option<String>,
+    pub(crate) resource_type: Option<String>,
+    pub(crate) resource_id: Option<String>,
+    pub(crate) version: Option<String>,
 }
 
 impl TrnMatcher {
@@ -152,7 +152,7 @@ fn compile_pattern(pattern: &str) -> TrnResult<CompiledPattern> {
 }
 
 /// Parse pattern into components
-fn parse_pattern_components(pattern: &str) -> TrnResult<PatternComponents> {
+pub(crate) fn parse_pattern_components(pattern: &str) -> TrnResult<PatternComponents> {
     if !pattern.starts_with("trn:") {
         return Err(TrnError::pattern(
             "Pattern must start with 'trn:'",
@@ -0,0 +1,265 @@
+//! Precompiled pattern sets for bulk matching
+//!
+//! A [`TrnPatternSet`] compiles many TRN patterns once into a trie keyed by
+//! component, so that checking a TRN against all of them looks each
+//! component up once instead of running every pattern's regex. Exact
+//! components are hash lookups; `*` components and components with a `*`
+//! inside, such as `alice-*`, are followed alongside.
+//!
+//! Sets match parsed TRNs, whose components are already valid, so a `*`
+//! component matches any value there.
+
+use std::collections::HashMap;
+
+use crate::error::TrnResult;
+use crate::pattern::parse_pattern_components;
+use crate::types::{Trn, TrnComponents, TrnRef};
+
+/// Number of components a pattern matches, after the `trn` prefix
+const DEPTH: usize = 5;
+
+/// Trie node for one component position
+#[derive(Debug, Clone, Default)]
+struct Node {
+    /// Children for exact component values
+    exact: HashMap<String, Node>,
+    /// Child for `*`
+    any: Option<Box<Node>>,
+    /// Children for components with a `*` inside
+    globs: Vec<(String, Node)>,
+    /// Patterns ending here, at the last level
+    patterns: Vec<usize>,
+}
+
+impl Node {
+    fn child(&mut self, component: Option<&str>) -> &mut Node {
+        match component {
+            None => self.any.get_or_insert_with(Default::default),
+            Some(glob) if glob.contains('*') => {
+                let index = match self.globs.iter().position(|(existing, _)| existing == glob) {
+                    Some(index) => index,
+                    None => {
+                        self.globs.push((glob.to_string(), Node::default()));
+                        self.globs.len() - 1
+                    }
+                };
+                &mut self.globs[index].1
+            }
+            Some(exact) => self.exact.entry(exact.to_string()).or_default(),
+        }
+    }
+
+    /// Visit the patterns matching `components`; stops once `visit` returns true
+    fn walk(&self, components: &[&str], visit: &mut impl FnMut(&[usize]) -> bool) -> bool {
+        let Some((component, rest)) = components.split_first() else {
+            return visit(&self.patterns);
+        };
+        if let Some(node) = self.exact.get(*component) {
+            if node.walk(rest, visit) {
+                return true;
+            }
+        }
+        if let Some(node) = &self.any {
+            if node.walk(rest, visit) {
+                return true;
+            }
+        }
+        self.globs.iter()
+            .filter(|(glob, _)| glob_matches(glob, component))
+            .any(|(_, node)| node.walk(rest, visit))
+    }
+}
+
+/// Set of TRN patterns compiled for matching many TRNs
+///
+/// # Examples
+///
+/// ```rust
+/// use trn_rust::{TrnPatternSet, TrnRef};
+///
+/// let set = TrnPatternSet::new([
+///     "trn:user:alice:*:*:*",
+///     "trn:*:*:tool:weather-*:*",
+///     "trn:org:acme:model:*:*",
+/// ])?;
+///
+/// let trn = TrnRef::parse("trn:user:alice:tool:weather-api:v1.0")?;
+/// assert!(set.matches_any(&trn));
+/// assert_eq!(set.which_match(&trn), vec![0, 1]);
+/// # Ok::<(), trn_rust::TrnError>(())
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct TrnPatternSet {
+    root: Node,
+    patterns: Vec<String>,
+}
+
+impl TrnPatternSet {
+    /// Compile a set from patterns, numbered in order from 0
+    pub fn new<I, S>(patterns: I) -> TrnResult<Self>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut set = Self::empty();
+        for pattern in patterns {
+            set.add_pattern(pattern.as_ref())?;
+        }
+        Ok(set)
+    }
+
+    /// Create an empty set
+    pub fn empty() -> Self {
+        Self::default()
+    }
+
+    /// Add a pattern to the set, returning its index
+    pub fn add_pattern(&mut self, pattern: &str) -> TrnResult<usize> {
+        let components = parse_pattern_components(pattern)?;
+        let index = self.patterns.len();
+        let leaf = [
+            components.platform.as_deref(),
+            components.scope.as_deref(),
+            components.resource_type.as_deref(),
+            components.resource_id.as_deref(),
+            components.version.as_deref(),
+        ]
+        .into_iter()
+        .fold(&mut self.root, |node, component| node.child(component));
+        leaf.patterns.push(index);
+        self.patterns.push(pattern.to_string());
+        Ok(index)
+    }
+
+    /// Get the pattern with the given index
+    pub fn pattern(&self, index: usize) -> Option<&str> {
+        self.patterns.get(index).map(String::as_str)
+    }
+
+    /// Get all patterns, in index order
+    pub fn patterns(&self) -> &[String] {
+        &self.patterns
+    }
+
+    /// Get pattern count
+    pub fn len(&self) -> usize {
+        self.patterns.len()
+    }
+
+    /// Check whether the set has no patterns
+    pub fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+
+    /// Check if a TRN matches any pattern of the set
+    pub fn matches_any<'t>(&self, trn: impl Into<TrnComponents<'t>>) -> bool {
+        let components = components(trn.into());
+        self.root.walk(&components, &mut |patterns| !patterns.is_empty())
+    }
+
+    /// Get the indexes of the patterns a TRN matches, in ascending order
+    pub fn which_match<'t>(&self, trn: impl Into<TrnComponents<'t>>) -> Vec<usize> {
+        let components = components(trn.into());
+        let mut matched = Vec::new();
+        self.root.walk(&components, &mut |patterns| {
+            matched.extend_from_slice(patterns);
+            false
+        });
+        matched.sort_unstable();
+        matched
+    }
+
+    /// Check if a TRN string matches any pattern; invalid TRNs match none
+    pub fn matches_str(&self, trn: &str) -> bool {
+        TrnRef::parse(trn).is_ok_and(|trn| self.matches_any(&trn))
+    }
+}
+
+impl<'a> From<&'a Trn> for TrnComponents<'a> {
+    fn from(trn: &'a Trn) -> Self {
+        trn.components()
+    }
+}
+
+impl<'a> From<&TrnRef<'a>> for TrnComponents<'a> {
+    fn from(trn: &TrnRef<'a>) -> Self {
+        trn.components()
+    }
+}
+
+fn components(trn: TrnComponents<'_>) -> [&str; DEPTH] {
+    [trn.platform, trn.scope, trn.resource_type, trn.resource_id, trn.version]
+}
+
+/// Match a component against a pattern whose `*`s stand for any run of
+/// characters
+fn glob_matches(glob: &str, value: &str) -> bool {
+    let mut segments = glob.split('*');
+    let first = segments.next().unwrap_or_default();
+    let Some(mut rest) = value.strip_prefix(first) else {
+        return false;
+    };
+    let mut segments = segments.peekable();
+    while let Some(segment) = segments.next() {
+        if segments.peek().is_none() {
+            // The last segment anchors at the end
+            return rest.ends_with(segment);
+        }
+        match rest.find(segment) {
+            Some(at) => rest = &rest[at + segment.len()..],
+            None => return false,
+        }
+    }
+    rest.is_empty()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pattern::TrnMatcher;
+
+    #[test]
+    fn test_glob_matches() {
+        assert!(glob_matches("alice-*", "alice-smith"));
+        assert!(glob_matches("alice-*", "alice-"));
+        assert!(!glob_matches("alice-*", "bob-smith"));
+        assert!(glob_matches("*-api", "weather-api"));
+        assert!(glob_matches("v1.*-*", "v1.0-beta"));
+        assert!(!glob_matches("v1.*-*", "v1.0"));
+        assert!(!glob_matches("a*a", "a"));
+    }
+
+    #[test]
+    fn test_set_agrees_with_matcher() {
+        let patterns = [
+            "trn:user:alice:*:*:*",
+            "trn:*:*:tool:*:*",
+            "trn:user:*:tool:weather-*:v1.*",
+            "trn:org:acme:model:*:*",
+            "trn:user:alice:tool:myapi:v1.0",
+            "trn:*:*:*:*:latest",
+        ];
+        let trns = [
+            "trn:user:alice:tool:myapi:v1.0",
+            "trn:user:bob:tool:weather-api:v1.2",
+            "trn:user:bob:tool:weather-api:v2.0",
+            "trn:org:acme:model:bert:latest",
+            "trn:org:other:dataset:crawl:v3.0",
+        ];
+
+        let set = TrnPatternSet::new(patterns).unwrap();
+        assert_eq!(set.len(), patterns.len());
+        for trn in trns {
+            let expected: Vec<usize> = (0..patterns.len())
+                .filter(|&index| TrnMatcher::new(patterns[index]).unwrap().matches(trn))
+                .collect();
+            let parsed = Trn::parse(trn).unwrap();
+            assert_eq!(set.which_match(&parsed), expected, "{trn}");
+            assert_eq!(set.matches_any(&TrnRef::parse(trn).unwrap()), !expected.is_empty(), "{trn}");
+        }
+
+        assert!(!set.matches_str("not-a-trn"));
+        assert!(TrnPatternSet::empty().which_match(&Trn::parse(trns[0]).unwrap()).is_empty());
+        assert!(TrnPatternSet::new(["trn:user:alice"]).is_err());
+    }
+}
@@ -0,0 +1,443 @@
+//! Bulk transformation pipelines
+//!
+//! Registry migrations usually filter a set of TRNs, rewrite a component or
+//! two, drop the duplicates this creates and write the result out in order.
+//! A [`TrnPipeline`] chains those steps once and runs them over any
+//! iterator of TRNs:
+//!
+//! ```rust
+//! use trn_rust::{TrnPipeline, TrnSortCriteria};
+//!
+//! let pipeline = TrnPipeline::builder()
+//!     .filter("trn:user:alice:*:*:*")
+//!     .exclude("trn:*:*:*:*:*-beta")
+//!     .platform("org")
+//!     .version("v2.0")
+//!     .dedupe()
+//!     .sort_by(TrnSortCriteria::InstanceId)
+//!     .build()?;
+//!
+//! let result = pipeline.run_strs([
+//!     "trn:user:alice:tool:weather-api:v1.0",
+//!     "trn:user:alice:tool:weather-api:v1.1",
+//!     "trn:user:alice:model:bert:v1.0",
+//!     "trn:user:alice:tool:weather-api:v1.0-beta",
+//!     "trn:user:bob:tool:search:v1.0",
+//!     "not a trn",
+//! ]);
+//! let migrated: Vec<String> = result.trns.iter().map(|trn| trn.to_string()).collect();
+//! assert_eq!(migrated, ["trn:org:alice:model:bert:v2.0", "trn:org:alice:tool:weather-api:v2.0"]);
+//! assert_eq!(result.failures.len(), 1);
+//! # Ok::<(), trn_rust::TrnError>(())
+//! ```
+//!
+//! Steps run in the order they were added, except sorting, which always
+//! comes last. [`TrnPipeline::iter`] runs lazily, one TRN at a time, unless
+//! the pipeline sorts.
+
+use std::collections::HashSet;
+use std::fmt;
+use std::sync::Arc;
+
+use crate::builder::TrnBuilder;
+use crate::diff::TrnComponent;
+use crate::error::{TrnError, TrnResult};
+use crate::pattern_set::TrnPatternSet;
+use crate::types::Trn;
+use crate::utils::{compare_trns, TrnSortCriteria};
+
+type Predicate = Arc<dyn Fn(&Trn) -> bool + Send + Sync>;
+type Transform = Arc<dyn Fn(Trn) -> TrnResult<Trn> + Send + Sync>;
+
+/// A step of a pipeline
+#[derive(Clone)]
+enum Stage<P> {
+    /// Keep TRNs matching any pattern
+    Filter(P),
+    /// Drop TRNs matching any pattern
+    Exclude(P),
+    /// Keep TRNs for which the predicate holds
+    Retain(Predicate),
+    /// Replace a component
+    Set(TrnComponent, String),
+    /// Rewrite the TRN
+    Map(Transform),
+    /// Drop TRNs already seen at this step
+    Dedupe,
+}
+
+impl<P: fmt::Debug> fmt::Debug for Stage<P> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Filter(patterns) => f.debug_tuple("Filter").field(patterns).finish(),
+            Self::Exclude(patterns) => f.debug_tuple("Exclude").field(patterns).finish(),
+            Self::Retain(_) => f.write_str("Retain(..)"),
+            Self::Set(component, value) => f.debug_tuple("Set").field(component).field(value).finish(),
+            Self::Map(_) => f.write_str("Map(..)"),
+            Self::Dedupe => f.write_str("Dedupe"),
+        }
+    }
+}
+
+/// A chain of filters and transforms over TRNs, built with
+/// [`TrnPipeline::builder`]
+#[derive(Debug, Clone)]
+pub struct TrnPipeline {
+    stages: Vec<Stage<TrnPatternSet>>,
+    sort: Option<TrnSortCriteria>,
+}
+
+/// Output of [`TrnPipeline::run`]
+#[derive(Debug, Default)]
+pub struct TrnPipelineResult {
+    /// TRNs that made it through the pipeline
+    pub trns: Vec<Trn>,
+    /// Inputs that failed to parse or to transform
+    pub failures: Vec<TrnError>,
+}
+
+impl TrnPipeline {
+    /// Create a builder with no steps
+    pub fn builder() -> TrnPipelineBuilder {
+        TrnPipelineBuilder::default()
+    }
+
+    /// Run the pipeline over TRNs, yielding each result as it is ready
+    ///
+    /// A sorting pipeline reads the whole input before yielding; its
+    /// failures follow the sorted TRNs.
+    pub fn iter<I>(&self, input: I) -> TrnPipelineIter<'_, I::IntoIter>
+    where
+        I: IntoIterator<Item = Trn>,
+    {
+        TrnPipelineIter {
+            pipeline: self,
+            input: input.into_iter(),
+            seen: vec![HashSet::new(); self.stages.len()],
+            sorted: None,
+        }
+    }
+
+    /// Run the pipeline over TRNs, collecting the results
+    pub fn run<I>(&self, input: I) -> TrnPipelineResult
+    where
+        I: IntoIterator<Item = Trn>,
+    {
+        let mut result = TrnPipelineResult::default();
+        for item in self.iter(input) {
+            match item {
+                Ok(trn) => result.trns.push(trn),
+                Err(err) => result.failures.push(err),
+            }
+        }
+        result
+    }
+
+    /// Parse TRN strings, keeping their parameters, and run the pipeline
+    /// over them
+    pub fn run_strs<I, S>(&self, input: I) -> TrnPipelineResult
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut failures = Vec::new();
+        let trns = input
+            .into_iter()
+            .filter_map(|s| Trn::parse_with_params(s.as_ref()).map_err(|err| failures.push(err)).ok())
+            .collect::<Vec<_>>();
+        let mut result = self.run(trns);
+        failures.append(&mut result.failures);
+        result.failures = failures;
+        result
+    }
+
+    /// Pass one TRN through the steps; `None` when it is dropped
+    fn process(&self, mut trn: Trn, seen: &mut [HashSet<Trn>]) -> Option<TrnResult<Trn>> {
+        for (stage, seen) in self.stages.iter().zip(seen) {
+            match stage {
+                Stage::Filter(patterns) => {
+                    if !patterns.matches_any(&trn) {
+                        return None;
+                    }
+                }
+                Stage::Exclude(patterns) => {
+                    if patterns.matches_any(&trn) {
+                        return None;
+                    }
+                }
+                Stage::Retain(predicate) => {
+                    if !predicate(&trn) {
+                        return None;
+                    }
+                }
+                Stage::Set(component, value) => match set_component(&trn, *component, value) {
+                    Ok(updated) => trn = updated,
+                    Err(err) => return Some(Err(err)),
+                },
+                Stage::Map(transform) => match transform(trn) {
+                    Ok(updated) => trn = updated,
+                    Err(err) => return Some(Err(err)),
+                },
+                Stage::Dedupe => {
+                    if !seen.insert(trn.clone()) {
+                        return None;
+                    }
+                }
+            }
+        }
+        Some(Ok(trn))
+    }
+}
+
+/// Replace one component, keeping the parameters
+fn set_component(trn: &Trn, component: TrnComponent, value: &str) -> TrnResult<Trn> {
+    let builder = TrnBuilder::from_trn(trn);
+    let builder = match component {
+        TrnComponent::Platform => builder.platform(value),
+        TrnComponent::Scope => builder.scope(value),
+        TrnComponent::ResourceType => builder.resource_type(value),
+        TrnComponent::ResourceId => builder.resource_id(value),
+        TrnComponent::Version => builder.version(value),
+    };
+    Ok(builder.build()?.with_params(trn.params().clone()))
+}
+
+/// Lazy run of a [`TrnPipeline`], from [`TrnPipeline::iter`]
+pub struct TrnPipelineIter<'p, I> {
+    pipeline: &'p TrnPipeline,
+    input: I,
+    seen: Vec<HashSet<Trn>>,
+    sorted: Option<std::vec::IntoIter<TrnResult<Trn>>>,
+}
+
+impl<I: Iterator<Item = Trn>> TrnPipelineIter<'_, I> {
+    fn next_unsorted(&mut self) -> Option<TrnResult<Trn>> {
+        self.input
+            .by_ref()
+            .find_map(|trn| self.pipeline.process(trn, &mut self.seen))
+    }
+}
+
+impl<I: Iterator<Item = Trn>> Iterator for TrnPipelineIter<'_, I> {
+    type Item = TrnResult<Trn>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let Some(sort_by) = self.pipeline.sort else {
+            return self.next_unsorted();
+        };
+        if self.sorted.is_none() {
+            let (mut trns, failures): (Vec<_>, Vec<_>) =
+                std::iter::from_fn(|| self.next_unsorted()).partition(Result::is_ok);
+            trns.sort_by(|a, b| match (a, b) {
+                (Ok(a), Ok(b)) => compare_trns(a, b, sort_by),
+                _ => unreachable!("partitioned out"),
+            });
+            trns.extend(failures);
+            self.sorted = Some(trns.into_iter());
+        }
+        self.sorted.as_mut()?.next()
+    }
+}
+
+/// Builder for [`TrnPipeline`]
+#[derive(Debug, Clone, Default)]
+pub struct TrnPipelineBuilder {
+    stages: Vec<Stage<Vec<String>>>,
+    sort: Option<TrnSortCriteria>,
+}
+
+impl TrnPipelineBuilder {
+    /// Keep only TRNs matching a pattern
+    pub fn filter<S: Into<String>>(self, pattern: S) -> Self {
+        self.filter_any([pattern])
+    }
+
+    /// Keep only TRNs matching any of several patterns
+    pub fn filter_any<I, S>(mut self, patterns: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.stages.push(Stage::Filter(patterns.into_iter().map(Into::into).collect()));
+        self
+    }
+
+    /// Drop TRNs matching a pattern
+    pub fn exclude<S: Into<String>>(mut self, pattern: S) -> Self {
+        self.stages.push(Stage::Exclude(vec![pattern.into()]));
+        self
+    }
+
+    /// Keep only TRNs for which the predicate holds
+    pub fn retain<F>(mut self, predicate: F) -> Self
+    where
+        F: Fn(&Trn) -> bool + Send + Sync + 'static,
+    {
+        self.stages.push(Stage::Retain(Arc::new(predicate)));
+        self
+    }
+
+    /// Replace a component; TRNs for which the result is invalid fail
+    pub fn set<S: Into<String>>(mut self, component: TrnComponent, value: S) -> Self {
+        self.stages.push(Stage::Set(component, value.into()));
+        self
+    }
+
+    /// Move TRNs to another platform
+    pub fn platform<S: Into<String>>(self, platform: S) -> Self {
+        self.set(TrnComponent::Platform, platform)
+    }
+
+    /// Replace the version
+    pub fn version<S: Into<String>>(self, version: S) -> Self {
+        self.set(TrnComponent::Version, version)
+    }
+
+    /// Rewrite TRNs with a function; errors are reported as failures
+    pub fn map<F>(mut self, transform: F) -> Self
+    where
+        F: Fn(Trn) -> TrnResult<Trn> + Send + Sync + 'static,
+    {
+        self.stages.push(Stage::Map(Arc::new(transform)));
+        self
+    }
+
+    /// Drop TRNs equal to one already passed at this point, keeping the first
+    pub fn dedupe(mut self) -> Self {
+        self.stages.push(Stage::Dedupe);
+        self
+    }
+
+    /// Sort the output, after every other step; ties keep the input order
+    pub fn sort_by(mut self, sort_by: TrnSortCriteria) -> Self {
+        self.sort = Some(sort_by);
+        self
+    }
+
+    /// Build the pipeline, compiling the patterns
+    pub fn build(self) -> TrnResult<TrnPipeline> {
+        let compile = |patterns: Vec<String>| {
+            TrnPatternSet::new(&patterns).map_err(|e| {
+                TrnError::builder_invalid_field("pattern".to_string(), format!("Invalid pattern in {patterns:?}: {e}"))
+            })
+        };
+        let stages = self
+            .stages
+            .into_iter()
+            .map(|stage| {
+                Ok(match stage {
+                    Stage::Filter(patterns) => Stage::Filter(compile(patterns)?),
+                    Stage::Exclude(patterns) => Stage::Exclude(compile(patterns)?),
+                    Stage::Retain(predicate) => Stage::Retain(predicate),
+                    Stage::Set(component, value) => Stage::Set(component, value),
+                    Stage::Map(transform) => Stage::Map(transform),
+                    Stage::Dedupe => Stage::Dedupe,
+                })
+            })
+            .collect::<TrnResult<_>>()?;
+        Ok(TrnPipeline {
+            stages,
+            sort: self.sort,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trns(inputs: &[&str]) -> Vec<Trn> {
+        inputs.iter().map(|s| Trn::parse_with_params(s).unwrap()).collect()
+    }
+
+    fn strings(trns: &[Trn]) -> Vec<String> {
+        trns.iter().map(Trn::to_string).collect()
+    }
+
+    #[test]
+    fn test_pipeline_steps() {
+        let input = trns(&[
+            "trn:user:alice:tool:weather-api:v1.0",
+            "trn:user:alice:tool:weather-api:v1.1?env=prod",
+            "trn:user:alice:tool:search:v1.0",
+            "trn:user:bob:tool:search:v1.0",
+        ]);
+
+        // An empty pipeline passes everything through
+        let pipeline = TrnPipeline::builder().build().unwrap();
+        assert_eq!(pipeline.run(input.clone()).trns, input);
+
+        // Steps run in order: dedupe before the version change sees no duplicates
+        let versions = &input[..2].iter().map(Trn::without_params).collect::<Vec<_>>();
+        let pipeline = TrnPipeline::builder().dedupe().version("v2.0").build().unwrap();
+        assert_eq!(pipeline.run(versions.clone()).trns.len(), 2);
+        let pipeline = TrnPipeline::builder().version("v2.0").dedupe().build().unwrap();
+        assert_eq!(pipeline.run(versions.clone()).trns.len(), 1);
+
+        let pipeline = TrnPipeline::builder()
+            .filter_any(["trn:user:alice:*:*:*", "trn:*:*:*:search:*"])
+            .retain(|trn| trn.resource_id() != "search" || trn.scope() == "bob")
+            .set(TrnComponent::Scope, "team")
+            .version("v2.0")
+            .dedupe()
+            .build()
+            .unwrap();
+        assert_eq!(
+            strings(&pipeline.run(input.clone()).trns),
+            [
+                "trn:user:team:tool:weather-api:v2.0",
+                "trn:user:team:tool:weather-api:v2.0?env=prod",
+                "trn:user:team:tool:search:v2.0",
+            ]
+        );
+
+        // The iterator is lazy
+        let pipeline = TrnPipeline::builder().map(|trn| panic!("ran on {trn}")).build().unwrap();
+        let _unused = pipeline.iter(input);
+    }
+
+    #[test]
+    fn test_pipeline_failures_and_sorting() {
+        let pipeline = TrnPipeline::builder()
+            .map(|trn| match trn.resource_id() {
+                "broken" => Err(TrnError::format("broken resource", None)),
+                _ => Ok(trn),
+            })
+            .platform("Invalid Platform")
+            .build()
+            .unwrap();
+        let result = pipeline.run(trns(&["trn:user:alice:tool:broken:v1.0", "trn:user:alice:tool:ok:v1.0"]));
+        assert!(result.trns.is_empty());
+        assert_eq!(result.failures.len(), 2);
+
+        let pipeline = TrnPipeline::builder()
+            .map(|trn| match trn.resource_id() {
+                "broken" => Err(TrnError::format("broken resource", None)),
+                _ => Ok(trn),
+            })
+            .sort_by(TrnSortCriteria::Version)
+            .build()
+            .unwrap();
+        let input = trns(&[
+            "trn:user:alice:tool:a:1.10.0",
+            "trn:user:alice:tool:broken:1.0.0",
+            "trn:user:alice:tool:b:1.2.0",
+            "trn:user:alice:tool:c:1.2.0",
+        ]);
+        let output: Vec<_> = pipeline.iter(input).collect();
+        assert_eq!(output.len(), 4);
+        let sorted: Vec<String> = output[..3].iter().map(|r| r.as_ref().unwrap().to_string()).collect();
+        assert_eq!(
+            sorted,
+            [
+                "trn:user:alice:tool:b:1.2.0",
+                "trn:user:alice:tool:c:1.2.0",
+                "trn:user:alice:tool:a:1.10.0",
+            ]
+        );
+        assert!(output[3].is_err());
+
+        assert!(TrnPipeline::builder().filter("trn:user").build().is_err());
+        assert!(TrnPipeline::builder().exclude("trn:user:alice:*:*:*").build().is_ok());
+    }
+}
@@ -0,0 +1,255 @@
+//! Serde support for TRNs
+//!
+//! [`Trn`] serializes as its string form, with any metadata parameters,
+//! and validates when deserialized, so a struct embedding a `Trn` rejects
+//! malformed values at the API boundary. The struct form older versions wrote, with one field per
+//! component, is still read and validated the same way.
+//!
+//! The modules here plug into `#[serde(with = ...)]` for the other cases:
+//!
+//! ```rust
+//! use serde::Deserialize;
+//! use trn_rust::Trn;
+//!
+//! #[derive(Deserialize)]
+//! struct Request {
+//!     /// Validated on deserialize
+//!     tool: Trn,
+//!     /// Kept as a string, but still validated
+//!     #[serde(with = "trn_rust::serde_trn::string")]
+//!     caller: String,
+//!     /// Accepted as long as it has the TRN shape
+//!     #[serde(with = "trn_rust::serde_trn::lenient")]
+//!     legacy: Trn,
+//! }
+//!
+//! let request: Request = serde_json::from_str(r#"{
+//!     "tool": "trn:user:alice:tool:weather-api:v1.0",
+//!     "caller": "trn:org:acme:service:gateway:v2.0",
+//!     "legacy": "trn:old:x:thing:y:z"
+//! }"#)?;
+//! assert_eq!(request.tool.resource_id(), "weather-api");
+//!
+//! let invalid = r#"{ "tool": "trn:user:alice", "caller": "", "legacy": "" }"#;
+//! assert!(serde_json::from_str::<Request>(invalid).is_err());
+//! # Ok::<(), serde_json::Error>(())
+//! ```
+//!
+//! With the `schemars` feature, [`Trn`] implements `JsonSchema` as a string
+//! with the TRN pattern; annotate `string` fields with
+//! `#[schemars(with = "Trn")]` to get the same schema.
+
+use std::fmt;
+
+use serde::de::{self, MapAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::params::split_params;
+use crate::parsing::parse_trn_components;
+use crate::types::Trn;
+
+impl Serialize for Trn {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Trn {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(TrnVisitor { lenient: false })
+    }
+}
+
+/// Component fields of the struct form
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TrnFields {
+    platform: String,
+    scope: String,
+    resource_type: String,
+    resource_id: String,
+    version: String,
+}
+
+struct TrnVisitor {
+    /// Only check the TRN shape, not the validation rules
+    lenient: bool,
+}
+
+impl<'de> Visitor<'de> for TrnVisitor {
+    type Value = Trn;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "a TRN string")
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<Trn, E> {
+        let trn = if self.lenient {
+            split_params(value).and_then(|(trn, params)| {
+                parse_trn_components(trn).map(|components| components.to_owned().with_params(params))
+            })
+        } else {
+            Trn::parse_with_params(value)
+        };
+        trn.map_err(E::custom)
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<Trn, A::Error> {
+        let fields = TrnFields::deserialize(de::value::MapAccessDeserializer::new(map))?;
+        let trn = crate::types::TrnComponents::new(
+            &fields.platform,
+            &fields.scope,
+            &fields.resource_type,
+            &fields.resource_id,
+            &fields.version,
+        )
+        .to_owned();
+        if self.lenient {
+            // Same shape check as a lenient string
+            return parse_trn_components(&trn.to_string())
+                .map(|_| trn)
+                .map_err(de::Error::custom);
+        }
+        trn.validate().map(|()| trn).map_err(de::Error::custom)
+    }
+}
+
+/// (De)serialize a [`Trn`], only checking its shape when deserializing
+///
+/// For TRNs minted under older or foreign rules, such as a platform or
+/// resource type this version does not support. The value still needs the
+/// `trn:` prefix and five non-empty components.
+pub mod lenient {
+    use serde::{Deserializer, Serialize, Serializer};
+
+    use super::TrnVisitor;
+    use crate::types::Trn;
+
+    /// Serialize a TRN as its string form
+    pub fn serialize<S: Serializer>(trn: &Trn, serializer: S) -> Result<S::Ok, S::Error> {
+        trn.serialize(serializer)
+    }
+
+    /// Deserialize a TRN, only checking its shape
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Trn, D::Error> {
+        deserializer.deserialize_any(TrnVisitor { lenient: true })
+    }
+}
+
+/// (De)serialize a `String` field holding a TRN, validating it
+pub mod string {
+    use serde::{de, Deserialize, Deserializer, Serializer};
+
+    /// Serialize the string as is
+    pub fn serialize<S: Serializer>(trn: &str, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(trn)
+    }
+
+    /// Deserialize a string, rejecting it unless it is a valid TRN
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+        let trn = String::deserialize(deserializer)?;
+        crate::validation::validate_trn_str(&trn).map_err(de::Error::custom)?;
+        Ok(trn)
+    }
+}
+
+#[cfg(feature = "schemars")]
+impl schemars::JsonSchema for Trn {
+    fn schema_name() -> String {
+        "Trn".to_string()
+    }
+
+    fn json_schema(_: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+        use schemars::schema::{InstanceType, Metadata, SchemaObject, StringValidation};
+
+        SchemaObject {
+            instance_type: Some(InstanceType::String.into()),
+            format: Some("trn".to_string()),
+            metadata: Some(Box::new(Metadata {
+                description: Some("Tool Resource Name: trn:platform:scope:resource_type:resource_id:version".to_string()),
+                examples: vec!["trn:user:alice:tool:weather-api:v1.0".into()],
+                ..Default::default()
+            })),
+            string: Some(Box::new(StringValidation {
+                max_length: u32::try_from(crate::constants::TRN_MAX_LENGTH).ok(),
+                min_length: u32::try_from(crate::constants::TRN_MIN_LENGTH).ok(),
+                pattern: Some(crate::constants::TRN_REGEX.as_str().to_string()),
+            })),
+            ..Default::default()
+        }
+        .into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[derive(Debug, Serialize, Deserialize)]
+    struct Holder {
+        trn: Trn,
+        #[serde(with = "lenient")]
+        lenient: Trn,
+        #[serde(with = "string")]
+        string: String,
+    }
+
+    #[test]
+    fn test_trn_serde() {
+        let trn = Trn::parse("trn:user:alice:tool:myapi:v1.0").unwrap();
+        assert_eq!(serde_json::to_value(&trn).unwrap(), json!("trn:user:alice:tool:myapi:v1.0"));
+        assert_eq!(serde_json::from_value::<Trn>(json!("trn:user:alice:tool:myapi:v1.0")).unwrap(), trn);
+
+        // The struct form is still read, and validated
+        let fields = json!({
+            "platform": "user", "scope": "alice", "resource_type": "tool",
+            "resource_id": "myapi", "version": "v1.0",
+        });
+        assert_eq!(serde_json::from_value::<Trn>(fields).unwrap(), trn);
+        let fields = json!({
+            "platform": "user", "scope": "alice", "resource_type": "gadget",
+            "resource_id": "myapi", "version": "v1.0",
+        });
+        assert!(serde_json::from_value::<Trn>(fields).is_err());
+
+        for invalid in [json!("trn:user:alice"), json!("trn:user:alice:gadget:myapi:v1.0"), json!(42)] {
+            assert!(serde_json::from_value::<Trn>(invalid).is_err());
+        }
+    }
+
+    #[test]
+    fn test_with_helpers() {
+        let holder: Holder = serde_json::from_value(json!({
+            "trn": "trn:user:alice:tool:myapi:v1.0",
+            "lenient": "trn:legacy:alice:gadget:myapi:v1.0",
+            "string": "trn:org:acme:model:bert:v2.0",
+        }))
+        .unwrap();
+        assert_eq!(holder.lenient.platform(), "legacy");
+        assert_eq!(serde_json::to_value(&holder).unwrap()["lenient"], "trn:legacy:alice:gadget:myapi:v1.0");
+
+        // Lenient still needs the TRN shape, and strings must be valid
+        assert!(serde_json::from_value::<Holder>(json!({
+            "trn": "trn:user:alice:tool:myapi:v1.0",
+            "lenient": "trn:legacy:alice",
+            "string": "trn:org:acme:model:bert:v2.0",
+        }))
+        .is_err());
+        assert!(serde_json::from_value::<Holder>(json!({
+            "trn": "trn:user:alice:tool:myapi:v1.0",
+            "lenient": "trn:legacy:alice:gadget:myapi:v1.0",
+            "string": "not a trn",
+        }))
+        .is_err());
+    }
+
+    #[cfg(feature = "schemars")]
+    #[test]
+    fn test_json_schema() {
+        let schema = serde_json::to_value(schemars::schema_for!(Trn)).unwrap();
+        assert_eq!(schema["type"], "string");
+        assert_eq!(schema["format"], "trn");
+        assert!(schema["pattern"].as_str().unwrap().starts_with("^trn:"));
+    }
+}
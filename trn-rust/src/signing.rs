@@ -0,0 +1,410 @@
+//! Signed TRNs
+//!
+//! A control plane that mints TRNs can sign them, so that services handed a
+//! TRN later can check it was minted there without looking it up. A
+//! [`SignedTrn`] carries the signature after a `#`, which no TRN contains:
+//!
+//! ```text
+//! trn:user:alice:tool:weather-api:v1.0#hs256.<base64url signature>
+//! ```
+//!
+//! HMAC-SHA256 signing needs the `signing` feature and shares one secret
+//! between minting and verifying. Ed25519 signing needs the `ed25519`
+//! feature, and lets verifiers hold only the public key.
+//!
+//! ```rust
+//! use trn_rust::{sign, verify, SigningKey, Trn};
+//!
+//! let key = SigningKey::hmac(b"control-plane-secret".to_vec());
+//! let trn = Trn::parse("trn:user:alice:tool:weather-api:v1.0")?;
+//!
+//! let signed = sign(&trn, &key).to_string();
+//! assert_eq!(verify(&signed, &key.verifying_key())?, trn);
+//!
+//! let other = SigningKey::hmac(b"another-secret".to_vec());
+//! assert!(verify(&signed, &other.verifying_key()).is_err());
+//! # Ok::<(), trn_rust::TrnError>(())
+//! ```
+
+use std::fmt;
+use std::str::FromStr;
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+
+use crate::error::{TrnError, TrnResult};
+use crate::types::Trn;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Separator between a TRN and its signature
+const SEPARATOR: char = '#';
+
+/// Signature algorithm
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SignatureAlgorithm {
+    /// HMAC with SHA-256
+    HmacSha256,
+    /// Ed25519
+    #[cfg(feature = "ed25519")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "ed25519")))]
+    Ed25519,
+}
+
+impl SignatureAlgorithm {
+    /// Get algorithm name as used in signed TRNs
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::HmacSha256 => "hs256",
+            #[cfg(feature = "ed25519")]
+            Self::Ed25519 => "ed25519",
+        }
+    }
+}
+
+impl fmt::Display for SignatureAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for SignatureAlgorithm {
+    type Err = TrnError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "hs256" => Ok(Self::HmacSha256),
+            #[cfg(feature = "ed25519")]
+            "ed25519" => Ok(Self::Ed25519),
+            _ => Err(TrnError::format(
+                format!("Unsupported signature algorithm '{s}'"),
+                None,
+            )),
+        }
+    }
+}
+
+/// Key for signing TRNs
+#[derive(Clone)]
+pub enum SigningKey {
+    /// HMAC-SHA256 secret
+    Hmac(Vec<u8>),
+    /// Ed25519 private key
+    #[cfg(feature = "ed25519")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "ed25519")))]
+    Ed25519(ed25519_dalek::SigningKey),
+}
+
+impl SigningKey {
+    /// Create an HMAC-SHA256 key from a shared secret
+    pub fn hmac(secret: impl Into<Vec<u8>>) -> Self {
+        Self::Hmac(secret.into())
+    }
+
+    /// Create an Ed25519 key from its 32-byte seed
+    #[cfg(feature = "ed25519")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "ed25519")))]
+    pub fn ed25519(seed: &[u8; 32]) -> Self {
+        Self::Ed25519(ed25519_dalek::SigningKey::from_bytes(seed))
+    }
+
+    /// Get the algorithm the key signs with
+    pub fn algorithm(&self) -> SignatureAlgorithm {
+        match self {
+            Self::Hmac(_) => SignatureAlgorithm::HmacSha256,
+            #[cfg(feature = "ed25519")]
+            Self::Ed25519(_) => SignatureAlgorithm::Ed25519,
+        }
+    }
+
+    /// Get the key verifying this key's signatures
+    pub fn verifying_key(&self) -> VerifyingKey {
+        match self {
+            Self::Hmac(secret) => VerifyingKey::Hmac(secret.clone()),
+            #[cfg(feature = "ed25519")]
+            Self::Ed25519(key) => VerifyingKey::Ed25519(key.verifying_key()),
+        }
+    }
+
+    fn sign(&self, message: &[u8]) -> Vec<u8> {
+        match self {
+            Self::Hmac(secret) => {
+                let mut mac = hmac_for(secret);
+                mac.update(message);
+                mac.finalize().into_bytes().to_vec()
+            }
+            #[cfg(feature = "ed25519")]
+            Self::Ed25519(key) => {
+                use ed25519_dalek::Signer;
+                key.sign(message).to_bytes().to_vec()
+            }
+        }
+    }
+}
+
+impl fmt::Debug for SigningKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Never print key material
+        write!(f, "SigningKey({})", self.algorithm())
+    }
+}
+
+/// Key for verifying signed TRNs
+#[derive(Clone)]
+pub enum VerifyingKey {
+    /// HMAC-SHA256 secret
+    Hmac(Vec<u8>),
+    /// Ed25519 public key
+    #[cfg(feature = "ed25519")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "ed25519")))]
+    Ed25519(ed25519_dalek::VerifyingKey),
+}
+
+impl VerifyingKey {
+    /// Create an HMAC-SHA256 key from a shared secret
+    pub fn hmac(secret: impl Into<Vec<u8>>) -> Self {
+        Self::Hmac(secret.into())
+    }
+
+    /// Create an Ed25519 key from a 32-byte public key
+    #[cfg(feature = "ed25519")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "ed25519")))]
+    pub fn ed25519(public_key: &[u8; 32]) -> TrnResult<Self> {
+        ed25519_dalek::VerifyingKey::from_bytes(public_key)
+            .map(Self::Ed25519)
+            .map_err(|e| TrnError::format(format!("Invalid Ed25519 public key: {e}"), None))
+    }
+
+    /// Get the algorithm the key verifies
+    pub fn algorithm(&self) -> SignatureAlgorithm {
+        match self {
+            Self::Hmac(_) => SignatureAlgorithm::HmacSha256,
+            #[cfg(feature = "ed25519")]
+            Self::Ed25519(_) => SignatureAlgorithm::Ed25519,
+        }
+    }
+
+    fn verify(&self, message: &[u8], signature: &[u8]) -> bool {
+        match self {
+            Self::Hmac(secret) => {
+                let mut mac = hmac_for(secret);
+                mac.update(message);
+                mac.verify_slice(signature).is_ok()
+            }
+            #[cfg(feature = "ed25519")]
+            Self::Ed25519(key) => ed25519_dalek::Signature::from_slice(signature)
+                .is_ok_and(|signature| key.verify_strict(message, &signature).is_ok()),
+        }
+    }
+}
+
+impl fmt::Debug for VerifyingKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "VerifyingKey({})", self.algorithm())
+    }
+}
+
+/// A TRN with a signature over it
+///
+/// Displays and serializes as `<trn>#<algorithm>.<base64url signature>`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct SignedTrn {
+    trn: Trn,
+    algorithm: SignatureAlgorithm,
+    signature: Vec<u8>,
+}
+
+impl SignedTrn {
+    /// Parse a signed TRN string, without verifying it
+    pub fn parse(input: &str) -> TrnResult<Self> {
+        let (trn, signature) = input
+            .rsplit_once(SEPARATOR)
+            .ok_or_else(|| TrnError::format("Signed TRN has no signature", Some(input.to_string())))?;
+        let (algorithm, signature) = signature.split_once('.').ok_or_else(|| {
+            TrnError::format("Signature must be '<algorithm>.<signature>'", Some(input.to_string()))
+        })?;
+        let signature = URL_SAFE_NO_PAD
+            .decode(signature)
+            .map_err(|e| TrnError::format(format!("Invalid signature encoding: {e}"), Some(input.to_string())))?;
+        Ok(Self {
+            trn: Trn::parse_with_params(trn)?,
+            algorithm: algorithm.parse()?,
+            signature,
+        })
+    }
+
+    /// Get the TRN, whether or not the signature verifies
+    pub fn trn_unverified(&self) -> &Trn {
+        &self.trn
+    }
+
+    /// Get the signature algorithm
+    pub fn algorithm(&self) -> SignatureAlgorithm {
+        self.algorithm
+    }
+
+    /// Get the raw signature bytes
+    pub fn signature(&self) -> &[u8] {
+        &self.signature
+    }
+
+    /// Verify the signature with `key`, returning the TRN if it holds
+    pub fn verify(&self, key: &VerifyingKey) -> TrnResult<&Trn> {
+        let trn = || Some(self.trn.to_string());
+        if key.algorithm() != self.algorithm {
+            return Err(TrnError::hash(
+                "Signature algorithm does not match the key",
+                Some(key.algorithm().to_string()),
+                Some(self.algorithm.to_string()),
+                trn(),
+            ));
+        }
+        if !key.verify(&message(&self.trn, self.algorithm), &self.signature) {
+            return Err(TrnError::hash("Signature does not verify", None, None, trn()));
+        }
+        Ok(&self.trn)
+    }
+
+    /// Strip the signature
+    pub fn into_unverified(self) -> Trn {
+        self.trn
+    }
+}
+
+impl fmt::Display for SignedTrn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}{SEPARATOR}{}.{}",
+            self.trn,
+            self.algorithm,
+            URL_SAFE_NO_PAD.encode(&self.signature)
+        )
+    }
+}
+
+impl FromStr for SignedTrn {
+    type Err = TrnError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl TryFrom<String> for SignedTrn {
+    type Error = TrnError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        Self::parse(&s)
+    }
+}
+
+impl From<SignedTrn> for String {
+    fn from(signed: SignedTrn) -> Self {
+        signed.to_string()
+    }
+}
+
+/// Sign a TRN
+pub fn sign(trn: &Trn, key: &SigningKey) -> SignedTrn {
+    let algorithm = key.algorithm();
+    SignedTrn {
+        trn: trn.clone(),
+        algorithm,
+        signature: key.sign(&message(trn, algorithm)),
+    }
+}
+
+/// Parse a signed TRN string and verify its signature, returning the TRN
+pub fn verify(signed: &str, key: &VerifyingKey) -> TrnResult<Trn> {
+    SignedTrn::parse(signed)?.verify(key).cloned()
+}
+
+/// Bytes a signature covers; naming the algorithm keeps a signature from
+/// being replayed under another one
+fn message(trn: &Trn, algorithm: SignatureAlgorithm) -> Vec<u8> {
+    format!("trn-signature:v1:{algorithm}:{trn}").into_bytes()
+}
+
+fn hmac_for(secret: &[u8]) -> HmacSha256 {
+    HmacSha256::new_from_slice(secret).expect("HMAC takes keys of any length")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trn() -> Trn {
+        Trn::parse("trn:user:alice:tool:weather-api:v1.0").unwrap()
+    }
+
+    #[test]
+    fn test_hmac_sign_verify() {
+        let key = SigningKey::hmac(b"secret".to_vec());
+        let signed = sign(&trn(), &key);
+        assert_eq!(signed.algorithm(), SignatureAlgorithm::HmacSha256);
+        assert_eq!(signed.signature().len(), 32);
+
+        let text = signed.to_string();
+        assert!(text.starts_with("trn:user:alice:tool:weather-api:v1.0#hs256."));
+        assert_eq!(SignedTrn::parse(&text).unwrap(), signed);
+        assert_eq!(verify(&text, &VerifyingKey::hmac(b"secret".to_vec())).unwrap(), trn());
+        assert!(matches!(
+            verify(&text, &VerifyingKey::hmac(b"other".to_vec())),
+            Err(TrnError::Hash { .. })
+        ));
+
+        // A signature does not carry over to another TRN
+        let tampered = text.replacen("alice", "mallory", 1);
+        assert!(verify(&tampered, &key.verifying_key()).is_err());
+
+        // Metadata parameters are signed too
+        let with_params = trn().with_param("env", "prod").unwrap();
+        let text_with_params = sign(&with_params, &key).to_string();
+        assert_eq!(verify(&text_with_params, &key.verifying_key()).unwrap(), with_params);
+        let tampered = text_with_params.replacen("env=prod", "env=dev", 1);
+        assert!(verify(&tampered, &key.verifying_key()).is_err());
+
+        let json = serde_json::to_string(&signed).unwrap();
+        assert_eq!(json, format!("\"{text}\""));
+        assert_eq!(serde_json::from_str::<SignedTrn>(&json).unwrap(), signed);
+    }
+
+    #[test]
+    fn test_malformed_signed_trns() {
+        let key = VerifyingKey::hmac(b"secret".to_vec());
+        for input in [
+            "trn:user:alice:tool:weather-api:v1.0",
+            "trn:user:alice:tool:weather-api:v1.0#hs256",
+            "trn:user:alice:tool:weather-api:v1.0#md5.AAAA",
+            "trn:user:alice:tool:weather-api:v1.0#hs256.!!",
+            "trn:user:alice#hs256.AAAA",
+        ] {
+            assert!(matches!(verify(input, &key), Err(TrnError::Format { .. })), "{input}");
+        }
+        assert_eq!(format!("{key:?}"), "VerifyingKey(hs256)");
+    }
+
+    #[cfg(feature = "ed25519")]
+    #[test]
+    fn test_ed25519_sign_verify() {
+        let key = SigningKey::ed25519(&[7; 32]);
+        let signed = sign(&trn(), &key).to_string();
+        assert!(signed.contains("#ed25519."));
+
+        let public = match key.verifying_key() {
+            VerifyingKey::Ed25519(public) => public.to_bytes(),
+            VerifyingKey::Hmac(_) => unreachable!(),
+        };
+        assert_eq!(verify(&signed, &VerifyingKey::ed25519(&public).unwrap()).unwrap(), trn());
+        assert!(verify(&signed, &SigningKey::ed25519(&[8; 32]).verifying_key()).is_err());
+
+        // Keys only verify their own algorithm
+        let error = verify(&signed, &VerifyingKey::hmac(b"secret".to_vec())).unwrap_err();
+        assert!(error.to_string().contains("does not match"));
+    }
+}
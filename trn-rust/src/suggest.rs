@@ -0,0 +1,212 @@
+//! "Did you mean" suggestions for mistyped TRNs
+//!
+//! [`suggest_similar`] ranks known TRNs by a component-aware edit distance
+//! to an invalid or unknown one, so CLIs and APIs can answer with the
+//! closest match:
+//!
+//! ```rust
+//! use trn_rust::suggest_similar;
+//!
+//! let known = [
+//!     "trn:user:alice:tool:getUserById:v1.0",
+//!     "trn:user:alice:tool:getUserByName:v1.0",
+//!     "trn:user:bob:tool:getUserById:v1.0",
+//! ];
+//! let suggestions = suggest_similar("trn:user:alice:tool:getUsrById:v1.0", &known, 2.0);
+//! assert_eq!(suggestions[0].trn, "trn:user:alice:tool:getUserById:v1.0");
+//! assert_eq!(suggestions[0].to_string(), "did you mean trn:user:alice:tool:getUserById:v1.0?");
+//! ```
+//!
+//! When both strings have the TRN shape, the edit distance of each
+//! component is scaled by its weight in [`SuggestionWeights`]; by default a
+//! version mismatch costs half as much as a mismatch elsewhere, since the
+//! wrong version of the right resource is usually what was meant. Strings
+//! without the TRN shape are compared whole.
+
+use std::cmp::Ordering;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::diff::TrnComponent;
+use crate::parsing::parse_trn_components;
+
+/// Cost of one edit in each component
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SuggestionWeights {
+    /// Platform weight
+    pub platform: f64,
+    /// Scope weight
+    pub scope: f64,
+    /// Resource type weight
+    pub resource_type: f64,
+    /// Resource ID weight
+    pub resource_id: f64,
+    /// Version weight
+    pub version: f64,
+}
+
+impl SuggestionWeights {
+    /// Get the weight of a component
+    pub fn weight(&self, component: TrnComponent) -> f64 {
+        match component {
+            TrnComponent::Platform => self.platform,
+            TrnComponent::Scope => self.scope,
+            TrnComponent::ResourceType => self.resource_type,
+            TrnComponent::ResourceId => self.resource_id,
+            TrnComponent::Version => self.version,
+        }
+    }
+}
+
+impl Default for SuggestionWeights {
+    fn default() -> Self {
+        Self {
+            platform: 1.0,
+            scope: 1.0,
+            resource_type: 1.0,
+            resource_id: 1.0,
+            version: 0.5,
+        }
+    }
+}
+
+/// A candidate TRN close to the input
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Suggestion {
+    /// Suggested TRN
+    pub trn: String,
+    /// Weighted edit distance from the input
+    pub distance: f64,
+    /// Components that differ from the input; empty when either side does
+    /// not have the TRN shape
+    pub changed: Vec<TrnComponent>,
+}
+
+impl fmt::Display for Suggestion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "did you mean {}?", self.trn)
+    }
+}
+
+/// Find the candidates within `max_distance` of the input, closest first
+///
+/// Ties keep the order of `candidates`. Uses the default
+/// [`SuggestionWeights`].
+pub fn suggest_similar<S: AsRef<str>>(input: &str, candidates: &[S], max_distance: f64) -> Vec<Suggestion> {
+    suggest_similar_with(input, candidates, max_distance, &SuggestionWeights::default())
+}
+
+/// Find the candidates within `max_distance` of the input with custom weights
+pub fn suggest_similar_with<S: AsRef<str>>(
+    input: &str,
+    candidates: &[S],
+    max_distance: f64,
+    weights: &SuggestionWeights,
+) -> Vec<Suggestion> {
+    let mut suggestions: Vec<Suggestion> = candidates
+        .iter()
+        .map(AsRef::as_ref)
+        .filter(|candidate| *candidate != input)
+        .map(|candidate| distance(input, candidate, weights))
+        .filter(|suggestion| suggestion.distance <= max_distance)
+        .collect();
+    // Stable, so ties keep the candidate order
+    suggestions.sort_by(|a, b| a.distance.partial_cmp(&b.distance).unwrap_or(Ordering::Equal));
+    suggestions
+}
+
+fn distance(input: &str, candidate: &str, weights: &SuggestionWeights) -> Suggestion {
+    let (Ok(a), Ok(b)) = (parse_trn_components(input), parse_trn_components(candidate)) else {
+        return Suggestion {
+            trn: candidate.to_string(),
+            distance: levenshtein(input, candidate) as f64,
+            changed: Vec::new(),
+        };
+    };
+
+    let mut distance = 0.0;
+    let mut changed = Vec::new();
+    for (component, from, to) in [
+        (TrnComponent::Platform, a.platform, b.platform),
+        (TrnComponent::Scope, a.scope, b.scope),
+        (TrnComponent::ResourceType, a.resource_type, b.resource_type),
+        (TrnComponent::ResourceId, a.resource_id, b.resource_id),
+        (TrnComponent::Version, a.version, b.version),
+    ] {
+        if from != to {
+            distance += levenshtein(from, to) as f64 * weights.weight(component);
+            changed.push(component);
+        }
+    }
+    Suggestion {
+        trn: candidate.to_string(),
+        distance,
+        changed,
+    }
+}
+
+/// Edit distance counting insertions, deletions and substitutions of chars
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, &cb) in b.iter().enumerate() {
+            let above = row[j + 1];
+            row[j + 1] = (above + 1).min(row[j] + 1).min(diagonal + usize::from(ca != cb));
+            diagonal = above;
+        }
+    }
+    row[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_levenshtein() {
+        assert_eq!(levenshtein("", ""), 0);
+        assert_eq!(levenshtein("kitten", "sitting"), 3);
+        assert_eq!(levenshtein("getUserById", "getUsrById"), 1);
+        assert_eq!(levenshtein("", "abc"), 3);
+    }
+
+    #[test]
+    fn test_suggest_similar() {
+        let known = [
+            "trn:user:alice:tool:getUserById:v1.0",
+            "trn:user:alice:tool:getUserById:v1.1",
+            "trn:user:alice:tool:getUserByName:v1.0",
+            "trn:org:acme:model:bert:v2.0",
+        ];
+
+        // A version mismatch costs less than a resource ID mismatch
+        let suggestions = suggest_similar("trn:user:alice:tool:getUserById:v1.2", &known, 10.0);
+        assert_eq!(suggestions[0].trn, "trn:user:alice:tool:getUserById:v1.0");
+        assert_eq!(suggestions[0].distance, 0.5);
+        assert_eq!(suggestions[0].changed, vec![TrnComponent::Version]);
+        assert_eq!(suggestions[1].trn, "trn:user:alice:tool:getUserById:v1.1");
+        assert!(suggestions.iter().all(|s| !s.trn.contains("bert")));
+
+        let suggestions = suggest_similar("trn:user:alice:tool:getUsrById:v1.0", &known, 2.0);
+        assert_eq!(suggestions.len(), 2);
+        assert_eq!(suggestions[0].changed, vec![TrnComponent::ResourceId]);
+
+        // The input itself is not suggested
+        assert!(suggest_similar(known[3], &known, 0.0).is_empty());
+
+        // Inputs without the TRN shape are compared whole
+        let suggestions = suggest_similar("trn:org:acme:model:bert", &known, 5.0);
+        assert_eq!(suggestions.len(), 1);
+        assert_eq!(suggestions[0].distance, 5.0);
+        assert!(suggestions[0].changed.is_empty());
+
+        let weights = SuggestionWeights { version: 4.0, ..SuggestionWeights::default() };
+        let suggestions = suggest_similar_with("trn:user:alice:tool:getUserByI:v1.1", &known, 10.0, &weights);
+        assert_eq!(suggestions[0].trn, "trn:user:alice:tool:getUserById:v1.1");
+        assert_eq!(suggestions[1].trn, "trn:user:alice:tool:getUserById:v1.0");
+    }
+}
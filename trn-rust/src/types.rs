@@ -5,6 +5,7 @@
 
 use crate::constants::*;
 use crate::error::{TrnError, TrnResult};
+use crate::params::TrnParams;
 use serde::{Deserialize, Serialize};
 use std::fmt;
 use std::str::FromStr;
@@ -128,7 +129,7 @@ impl FromStr for ResourceType {
 }
 
 /// TRN components structure for zero-copy parsing
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct TrnComponents<'a> {
     /// Platform identifier
     pub platform: &'a str,
@@ -168,12 +169,133 @@ impl<'a> TrnComponents<'a> {
             resource_type: self.resource_type.to_string(),
             resource_id: self.resource_id.to_string(),
             version: self.version.to_string(),
+            params: TrnParams::new(),
         }
     }
 }
 
+/// Borrowed view of a validated TRN string
+///
+/// Parses and validates like [`Trn::parse`] but borrows its components
+/// from the input instead of allocating them, for hot paths that parse many
+/// TRNs and keep few. Convert with [`Trn::from`] to keep one.
+///
+/// # Examples
+///
+/// ```rust
+/// use trn_rust::{Trn, TrnRef};
+///
+/// let input = "trn:user:alice:tool:weather:v1.0";
+/// let trn = TrnRef::parse(input)?;
+/// assert_eq!(trn.resource_id(), "weather");
+/// assert!(trn.matches_pattern("trn:user:*:tool:*:*"));
+/// assert_eq!(Trn::from(trn), Trn::parse(input)?);
+/// # Ok::<(), trn_rust::TrnError>(())
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TrnRef<'a> {
+    input: &'a str,
+    platform: &'a str,
+    scope: &'a str,
+    resource_type: &'a str,
+    resource_id: &'a str,
+    version: &'a str,
+}
+
+impl<'a> TrnRef<'a> {
+    /// Parse a TRN string without allocating
+    pub fn parse(input: &'a str) -> TrnResult<Self> {
+        crate::parsing::parse_trn_ref(input)
+    }
+
+    /// Wrap an input already split into its components
+    pub(crate) fn from_parts(input: &'a str, components: TrnComponents<'a>) -> Self {
+        Self {
+            input,
+            platform: components.platform,
+            scope: components.scope,
+            resource_type: components.resource_type,
+            resource_id: components.resource_id,
+            version: components.version,
+        }
+    }
+
+    /// Get the whole TRN string
+    pub fn as_str(&self) -> &'a str {
+        self.input
+    }
+
+    /// Get the platform
+    pub fn platform(&self) -> &'a str {
+        self.platform
+    }
+
+    /// Get the scope
+    pub fn scope(&self) -> &'a str {
+        self.scope
+    }
+
+    /// Get the resource type
+    pub fn resource_type(&self) -> &'a str {
+        self.resource_type
+    }
+
+    /// Get the resource ID
+    pub fn resource_id(&self) -> &'a str {
+        self.resource_id
+    }
+
+    /// Get the version
+    pub fn version(&self) -> &'a str {
+        self.version
+    }
+
+    /// Get components as borrowed structure
+    pub fn components(&self) -> TrnComponents<'a> {
+        TrnComponents::new(self.platform, self.scope, self.resource_type, self.resource_id, self.version)
+    }
+
+    /// Encode as an order-preserving binary key, as [`Trn::to_bytes`]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        crate::encoding::encode(self.components())
+    }
+
+    /// Check if this TRN matches a pattern
+    pub fn matches_pattern(&self, pattern: &str) -> bool {
+        crate::pattern::matches_pattern(self.input, pattern)
+    }
+
+    /// Check if this TRN is compatible with another TRN
+    pub fn is_compatible_with(&self, other: &TrnRef<'_>) -> bool {
+        self.platform == other.platform
+            && self.scope == other.scope
+            && self.resource_type == other.resource_type
+            && self.resource_id == other.resource_id
+    }
+}
+
+impl fmt::Display for TrnRef<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.input)
+    }
+}
+
+impl AsRef<str> for TrnRef<'_> {
+    fn as_ref(&self) -> &str {
+        self.input
+    }
+}
+
+impl PartialEq<Trn> for TrnRef<'_> {
+    fn eq(&self, other: &Trn) -> bool {
+        self.components() == other.components()
+    }
+}
+
 /// Main TRN structure (owned variant)
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+///
+/// Serializes as its string form; see [`serde_trn`](crate::serde_trn).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Trn {
     /// Platform identifier
     platform: String,
@@ -185,6 +307,8 @@ pub struct Trn {
     resource_id: String,
     /// Version identifier
     version: String,
+    /// Metadata parameters, see [`TrnParams`]
+    params: TrnParams,
 }
 
 impl Trn {
@@ -202,6 +326,7 @@ impl Trn {
             resource_type: resource_type.into(),
             resource_id: resource_id.into(),
             version: version.into(),
+            params: TrnParams::new(),
         };
         
         trn.validate()?;
@@ -213,6 +338,13 @@ impl Trn {
         crate::parsing::parse_trn(input)
     }
 
+    /// Parse a TRN string that may end with metadata parameters, as in
+    /// `trn:user:alice:tool:weather-api:v1.0?env=prod`
+    pub fn parse_with_params(input: &str) -> TrnResult<Self> {
+        let (trn, params) = crate::params::split_params(input)?;
+        Ok(Self::parse(trn)?.with_params(params))
+    }
+
     /// Create TRN from components
     pub fn from_components(components: TrnComponents<'_>) -> TrnResult<Self> {
         let trn = components.to_owned();
@@ -225,6 +357,11 @@ impl Trn {
         crate::validation::validate_trn_struct(self)
     }
 
+    /// Validate this TRN, then check it against a naming policy
+    pub fn validate_with(&self, policy: &dyn crate::NamingPolicy) -> TrnResult<()> {
+        crate::validation::validate_trn_struct_with(self, policy)
+    }
+
     /// Check if this TRN is valid
     pub fn is_valid(&self) -> bool {
         self.validate().is_ok()
@@ -256,9 +393,41 @@ impl Trn {
         &self.version
     }
 
+    /// Get the metadata parameters
+    pub fn params(&self) -> &TrnParams {
+        &self.params
+    }
+
+    /// Get the metadata parameters for modification
+    pub fn params_mut(&mut self) -> &mut TrnParams {
+        &mut self.params
+    }
+
+    /// Replace the metadata parameters
+    pub fn with_params(mut self, params: TrnParams) -> Self {
+        self.params = params;
+        self
+    }
+
+    /// Set a metadata parameter
+    pub fn with_param(mut self, key: impl Into<String>, value: impl Into<String>) -> TrnResult<Self> {
+        self.params.insert(key, value)?;
+        Ok(self)
+    }
+
+    /// Get a copy without metadata parameters
+    pub fn without_params(&self) -> Self {
+        self.clone().with_params(TrnParams::new())
+    }
+
     // Conversion methods
-    /// Convert to string representation
+    /// Convert to string representation, with any metadata parameters
     pub fn to_string(&self) -> String {
+        format!("{self}")
+    }
+
+    /// Get the TRN string without metadata parameters
+    pub(crate) fn base_string(&self) -> String {
         format!(
             "trn:{}:{}:{}:{}:{}",
             self.platform,
@@ -269,6 +438,20 @@ impl Trn {
         )
     }
 
+    /// Encode as an order-preserving binary key
+    ///
+    /// See [`key_prefix`](crate::key_prefix) for the encoding. The key names
+    /// the TRN without its metadata parameters, so
+    /// [`from_bytes`](Self::from_bytes) gives back [`without_params`](Self::without_params).
+    pub fn to_bytes(&self) -> Vec<u8> {
+        crate::encoding::encode(self.components())
+    }
+
+    /// Decode and validate a binary key from [`to_bytes`](Self::to_bytes)
+    pub fn from_bytes(bytes: &[u8]) -> TrnResult<Self> {
+        crate::encoding::decode(bytes)
+    }
+
     /// Convert to URL format
     pub fn to_url(&self) -> TrnResult<String> {
         crate::url::trn_to_url(self)
@@ -279,6 +462,11 @@ impl Trn {
         crate::url::trn_to_http_url(self, base)
     }
 
+    /// Convert to a URL of a custom scheme
+    pub fn to_url_with(&self, scheme: &crate::url::UrlScheme) -> TrnResult<String> {
+        scheme.to_url(self)
+    }
+
     // Manipulation methods
     /// Get the base TRN (without version)
     pub fn base_trn(&self) -> Self {
@@ -288,12 +476,13 @@ impl Trn {
             resource_type: self.resource_type.clone(),
             resource_id: self.resource_id.clone(),
             version: "*".to_string(),
+            params: TrnParams::new(),
         }
     }
 
     /// Check if this TRN matches a pattern
     pub fn matches_pattern(&self, pattern: &str) -> bool {
-        crate::pattern::matches_pattern(&self.to_string(), pattern)
+        crate::pattern::matches_pattern(&self.base_string(), pattern)
     }
 
     /// Check if this TRN is compatible with another TRN
@@ -304,6 +493,37 @@ impl Trn {
             && self.resource_id == other.resource_id
     }
 
+    /// Start deriving a related TRN in the same platform and scope
+    pub fn derive(&self) -> crate::DerivedTrnBuilder {
+        crate::DerivedTrnBuilder::new(self)
+    }
+
+    /// Check if this TRN is in the same platform and scope as `parent`
+    pub fn is_derived_from(&self, parent: &Self) -> bool {
+        self.platform == parent.platform && self.scope == parent.scope
+    }
+
+    /// Check if this TRN names a sub-resource of `parent`, as
+    /// `weather-api.forecast` is of `weather-api`
+    pub fn is_child_of(&self, parent: &Self) -> bool {
+        self.is_derived_from(parent)
+            && self.resource_type == parent.resource_type
+            && self.resource_id
+                .strip_prefix(parent.resource_id.as_str())
+                .is_some_and(|rest| rest.len() > 1 && rest.starts_with('.'))
+    }
+
+    /// Compare with another TRN, listing the components that differ
+    pub fn diff(&self, other: &Self) -> crate::TrnDiff {
+        crate::TrnDiff::new(self, other)
+    }
+
+    /// Check if moving from this TRN to `other` is a compatible upgrade of
+    /// the same resource, by semantic versioning rules
+    pub fn is_compatible_upgrade(&self, other: &Self) -> bool {
+        self.diff(other).is_compatible_upgrade()
+    }
+
     // Mutable operations
     /// Set the scope
     pub fn set_scope(&mut self, scope: String) {
@@ -331,6 +551,11 @@ impl Trn {
         crate::url::url_to_trn(url)
     }
 
+    /// Parse TRN from a URL of a custom scheme
+    pub fn from_url_with(url: &str, scheme: &crate::url::UrlScheme) -> TrnResult<Self> {
+        scheme.url_to_trn(url)
+    }
+
     /// Serialize to JSON string
     pub fn to_json(&self) -> Result<String, serde_json::Error> {
         serde_json::to_string(self)
@@ -364,15 +589,26 @@ impl fmt::Display for Trn {
             self.resource_type,
             self.resource_id,
             self.version
-        )
+        )?;
+        if !self.params.is_empty() {
+            write!(f, "{}{}", crate::params::PARAMS_SEPARATOR, self.params)?;
+        }
+        Ok(())
     }
 }
 
+/// Parses like [`Trn::parse_with_params`], so `to_string` round-trips
 impl FromStr for Trn {
     type Err = TrnError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Self::parse(s)
+        Self::parse_with_params(s)
+    }
+}
+
+impl From<TrnRef<'_>> for Trn {
+    fn from(trn: TrnRef<'_>) -> Self {
+        trn.components().to_owned()
     }
 }
 
@@ -3,9 +3,10 @@
 //! This module provides bidirectional conversion between TRN strings and URL formats,
 //! including trn:// URLs and HTTP URLs for web-based access.
 
-use percent_encoding::{utf8_percent_encode, percent_decode_str, CONTROLS, AsciiSet};
+use percent_encoding::{utf8_percent_encode, percent_decode_str, CONTROLS, NON_ALPHANUMERIC, AsciiSet};
 use url::Url;
 
+use crate::diff::TrnComponent;
 use crate::error::{TrnError, TrnResult};
 use crate::types::{Trn, TrnComponents};
 
@@ -350,6 +351,346 @@ pub fn validate_url(url: &str) -> UrlValidationResult {
     }
 }
 
+/// Percent-encoding applied to TRN components in a [`UrlScheme`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UrlEncoding {
+    /// Encode only characters with a meaning in URLs, keeping `-`, `.`,
+    /// `_`, `~` and `:` readable
+    #[default]
+    Component,
+    /// Encode everything but ASCII letters, digits, `-`, `.`, `_` and `~`
+    Strict,
+}
+
+impl UrlEncoding {
+    const fn ascii_set(self) -> &'static AsciiSet {
+        match self {
+            Self::Component => TRN_COMPONENT_ENCODE_SET,
+            Self::Strict => STRICT_ENCODE_SET,
+        }
+    }
+}
+
+/// Everything but RFC 3986 unreserved characters
+const STRICT_ENCODE_SET: &AsciiSet = &NON_ALPHANUMERIC.remove(b'-').remove(b'.').remove(b'_').remove(b'~');
+
+/// Where a TRN component goes in the URLs of a [`UrlScheme`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Slot {
+    /// A literal path segment
+    Literal(String),
+    /// A path segment holding a component, with literal text around it
+    Component {
+        component: TrnComponent,
+        prefix: String,
+        suffix: String,
+    },
+}
+
+/// How TRNs map to the URLs of a deployment, in both directions
+///
+/// A scheme is a base URL, a path template with one `{component}`
+/// placeholder per segment, and query parameters for the components the
+/// template leaves out. Every component must appear exactly once.
+///
+/// # Examples
+///
+/// ```rust
+/// use trn_rust::{Trn, TrnComponent, UrlScheme};
+///
+/// let scheme = UrlScheme::builder("https://api.example.com/v2/")
+///     .path_template("/{platform}/{scope}/{resource_type}s/{resource_id}")
+///     .query_param("version", TrnComponent::Version)
+///     .build()?;
+///
+/// let trn = Trn::parse("trn:user:alice:tool:weather-api:v1.0")?;
+/// let url = trn.to_url_with(&scheme)?;
+/// assert_eq!(url, "https://api.example.com/v2/user/alice/tools/weather-api?version=v1.0");
+/// assert_eq!(scheme.url_to_trn(&url)?, trn);
+/// # Ok::<(), trn_rust::TrnError>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct UrlScheme {
+    base: Url,
+    base_segments: Vec<String>,
+    path: Vec<Slot>,
+    query: Vec<(String, TrnComponent)>,
+    encoding: UrlEncoding,
+}
+
+impl UrlScheme {
+    /// Path template of [`trn_to_http_url`]
+    pub const DEFAULT_PATH_TEMPLATE: &'static str = "/trn/{platform}/{scope}/{resource_type}/{resource_id}/{version}";
+
+    /// Create a builder for URLs under a base URL
+    pub fn builder<S: Into<String>>(base_url: S) -> UrlSchemeBuilder {
+        UrlSchemeBuilder {
+            base_url: base_url.into(),
+            path_template: Self::DEFAULT_PATH_TEMPLATE.to_string(),
+            query: Vec::new(),
+            encoding: UrlEncoding::default(),
+        }
+    }
+
+    /// Convert a TRN to a URL of this scheme
+    pub fn to_url(&self, trn: &Trn) -> TrnResult<String> {
+        let components = trn.components();
+        let set = self.encoding.ascii_set();
+
+        let mut path = String::new();
+        for segment in &self.base_segments {
+            path.push('/');
+            path.push_str(segment);
+        }
+        for slot in &self.path {
+            path.push('/');
+            match slot {
+                Slot::Literal(literal) => path.push_str(&utf8_percent_encode(literal, set).to_string()),
+                Slot::Component { component, prefix, suffix } => {
+                    let value = component_value(components, *component);
+                    path.push_str(&utf8_percent_encode(prefix, set).to_string());
+                    path.push_str(&utf8_percent_encode(value, set).to_string());
+                    path.push_str(&utf8_percent_encode(suffix, set).to_string());
+                }
+            }
+        }
+
+        let mut url = self.base.clone();
+        url.set_path(&path);
+        if self.query.is_empty() {
+            url.set_query(None);
+        } else {
+            let query: Vec<String> = self.query
+                .iter()
+                .map(|(name, component)| {
+                    format!(
+                        "{}={}",
+                        utf8_percent_encode(name, set),
+                        utf8_percent_encode(component_value(components, *component), set)
+                    )
+                })
+                .collect();
+            url.set_query(Some(&query.join("&")));
+        }
+        url.set_fragment(None);
+        Ok(url.to_string())
+    }
+
+    /// Convert a URL of this scheme back to a TRN, validating it
+    ///
+    /// The URL must have the scheme, host and port of the base URL and a
+    /// path matching the template; query parameters the scheme does not
+    /// use are ignored.
+    pub fn url_to_trn(&self, url: &str) -> TrnResult<Trn> {
+        let invalid = |message: String| TrnError::url(message, Some(url.to_string()));
+
+        let parsed = Url::parse(url).map_err(|e| invalid(format!("Invalid URL: {e}")))?;
+        if parsed.scheme() != self.base.scheme()
+            || parsed.host_str() != self.base.host_str()
+            || parsed.port_or_known_default() != self.base.port_or_known_default()
+        {
+            return Err(invalid(format!("URL is not under {}", self.base)));
+        }
+
+        let segments: Vec<&str> = parsed
+            .path_segments()
+            .map(|segments| segments.filter(|segment| !segment.is_empty()).collect())
+            .unwrap_or_default();
+        let Some(rest) = segments
+            .strip_prefix(self.base_segments.iter().map(String::as_str).collect::<Vec<_>>().as_slice())
+        else {
+            return Err(invalid(format!("URL is not under {}", self.base)));
+        };
+        if rest.len() != self.path.len() {
+            return Err(invalid(format!(
+                "URL path has {} segments after the base, the scheme expects {}",
+                rest.len(),
+                self.path.len()
+            )));
+        }
+
+        let mut values: [Option<String>; 5] = Default::default();
+        for (slot, segment) in self.path.iter().zip(rest) {
+            let segment = url_decode_component(segment)
+                .map_err(|e| invalid(format!("Failed to decode URL components: {e}")))?;
+            match slot {
+                Slot::Literal(literal) => {
+                    if segment != *literal {
+                        return Err(invalid(format!("Expected path segment '{literal}', found '{segment}'")));
+                    }
+                }
+                Slot::Component { component, prefix, suffix } => {
+                    let value = segment
+                        .strip_prefix(prefix.as_str())
+                        .and_then(|value| value.strip_suffix(suffix.as_str()))
+                        .filter(|value| !value.is_empty())
+                        .ok_or_else(|| invalid(format!("Path segment '{segment}' does not hold the {component}")))?;
+                    values[component_index(*component)] = Some(value.to_string());
+                }
+            }
+        }
+        for (name, component) in &self.query {
+            let value = query_value(&parsed, name)
+                .map_err(|e| invalid(format!("Failed to decode URL components: {e}")))?
+                .ok_or_else(|| invalid(format!("Missing query parameter '{name}'")))?;
+            values[component_index(*component)] = Some(value);
+        }
+
+        let [platform, scope, resource_type, resource_id, version] = values.map(Option::unwrap_or_default);
+        Trn::new(platform, scope, resource_type, resource_id, version)
+    }
+
+    /// Check if a URL belongs to this scheme and holds a valid TRN
+    pub fn matches(&self, url: &str) -> bool {
+        self.url_to_trn(url).is_ok()
+    }
+}
+
+/// Builder for [`UrlScheme`]
+#[derive(Debug, Clone)]
+pub struct UrlSchemeBuilder {
+    base_url: String,
+    path_template: String,
+    query: Vec<(String, TrnComponent)>,
+    encoding: UrlEncoding,
+}
+
+impl UrlSchemeBuilder {
+    /// Set the path template, relative to the base URL
+    ///
+    /// Each `/`-separated segment is literal text or holds one
+    /// `{platform}`, `{scope}`, `{resource_type}`, `{resource_id}` or
+    /// `{version}` placeholder, optionally with literal text around it.
+    pub fn path_template<S: Into<String>>(mut self, template: S) -> Self {
+        self.path_template = template.into();
+        self
+    }
+
+    /// Carry a component in a query parameter instead of the path
+    pub fn query_param<S: Into<String>>(mut self, name: S, component: TrnComponent) -> Self {
+        self.query.push((name.into(), component));
+        self
+    }
+
+    /// Set the percent-encoding of components
+    pub fn encoding(mut self, encoding: UrlEncoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
+
+    /// Build the scheme, checking that every component appears exactly once
+    pub fn build(self) -> TrnResult<UrlScheme> {
+        let invalid = |field: &str, message: String| TrnError::builder_invalid_field(field.to_string(), message);
+
+        let base = Url::parse(&self.base_url)
+            .map_err(|e| invalid("base_url", format!("Invalid base URL '{}': {e}", self.base_url)))?;
+        let base_segments: Vec<String> = base
+            .path_segments()
+            .ok_or_else(|| invalid("base_url", format!("'{}' cannot be a base URL", self.base_url)))?
+            .filter(|segment| !segment.is_empty())
+            .map(str::to_string)
+            .collect();
+
+        let path = self.path_template
+            .split('/')
+            .filter(|segment| !segment.is_empty())
+            .map(parse_slot)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|message| invalid("path_template", message))?;
+
+        let placed = path
+            .iter()
+            .filter_map(|slot| match slot {
+                Slot::Component { component, .. } => Some(*component),
+                Slot::Literal(_) => None,
+            })
+            .chain(self.query.iter().map(|(_, component)| *component));
+        let mut counts = [0usize; 5];
+        for component in placed {
+            counts[component_index(component)] += 1;
+        }
+        for component in COMPONENTS {
+            match counts[component_index(component)] {
+                1 => {}
+                0 => return Err(invalid("path_template", format!("The scheme does not place the {component}"))),
+                _ => return Err(invalid("path_template", format!("The scheme places the {component} more than once"))),
+            }
+        }
+        if let Some((name, _)) = self.query.iter().find(|(name, _)| name.is_empty()) {
+            return Err(invalid("query_param", format!("Invalid query parameter name '{name}'")));
+        }
+
+        Ok(UrlScheme {
+            base,
+            base_segments,
+            path,
+            query: self.query,
+            encoding: self.encoding,
+        })
+    }
+}
+
+/// Components in TRN order
+const COMPONENTS: [TrnComponent; 5] = [
+    TrnComponent::Platform,
+    TrnComponent::Scope,
+    TrnComponent::ResourceType,
+    TrnComponent::ResourceId,
+    TrnComponent::Version,
+];
+
+fn component_index(component: TrnComponent) -> usize {
+    COMPONENTS.iter().position(|&c| c == component).unwrap_or_default()
+}
+
+fn component_value(components: TrnComponents<'_>, component: TrnComponent) -> &str {
+    match component {
+        TrnComponent::Platform => components.platform,
+        TrnComponent::Scope => components.scope,
+        TrnComponent::ResourceType => components.resource_type,
+        TrnComponent::ResourceId => components.resource_id,
+        TrnComponent::Version => components.version,
+    }
+}
+
+/// Parse a template segment
+fn parse_slot(segment: &str) -> Result<Slot, String> {
+    let Some(open) = segment.find('{') else {
+        if segment.contains('}') {
+            return Err(format!("Unbalanced braces in template segment '{segment}'"));
+        }
+        return Ok(Slot::Literal(segment.to_string()));
+    };
+    let close = segment[open..]
+        .find('}')
+        .map(|close| open + close)
+        .ok_or_else(|| format!("Unbalanced braces in template segment '{segment}'"))?;
+    let (prefix, name, suffix) = (&segment[..open], &segment[open + 1..close], &segment[close + 1..]);
+    if prefix.contains('}') || suffix.contains(['{', '}']) {
+        return Err(format!("Template segment '{segment}' holds more than one placeholder"));
+    }
+    let component = COMPONENTS
+        .into_iter()
+        .find(|component| component.as_str() == name)
+        .ok_or_else(|| format!("Unknown placeholder '{{{name}}}'"))?;
+    Ok(Slot::Component {
+        component,
+        prefix: prefix.to_string(),
+        suffix: suffix.to_string(),
+    })
+}
+
+/// Get a decoded query parameter, as encoded by [`UrlScheme::to_url`]
+fn query_value(url: &Url, name: &str) -> Result<Option<String>, std::str::Utf8Error> {
+    for pair in url.query().unwrap_or_default().split('&') {
+        let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+        if url_decode_component(key)? == name {
+            return url_decode_component(value).map(Some);
+        }
+    }
+    Ok(None)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -444,4 +785,71 @@ mod tests {
         
         assert_eq!(trn_url, back_to_trn_url);
     }
-} 
\ No newline at end of file
+
+    #[test]
+    fn test_url_scheme_round_trip() {
+        let trn = Trn::parse("trn:org:acme:model:bert-base:v2.0").unwrap();
+
+        // The default template is the trn_to_http_url layout
+        let scheme = UrlScheme::builder("https://api.example.com/").build().unwrap();
+        let url = trn.to_url_with(&scheme).unwrap();
+        assert_eq!(url, trn_to_http_url(&trn, "https://api.example.com/").unwrap());
+        assert_eq!(Trn::from_url_with(&url, &scheme).unwrap(), trn);
+
+        let scheme = UrlScheme::builder("registry://models.internal:8443/api")
+            .path_template("{platform}/team-{scope}/{resource_type}s/{resource_id}")
+            .query_param("v", TrnComponent::Version)
+            .encoding(UrlEncoding::Strict)
+            .build()
+            .unwrap();
+        let trn = Trn::parse("trn:org:acme:model:bert_base:v2.0-rc.1").unwrap();
+        let url = scheme.to_url(&trn).unwrap();
+        assert_eq!(url, "registry://models.internal:8443/api/org/team-acme/models/bert_base?v=v2.0-rc.1");
+        assert_eq!(scheme.url_to_trn(&url).unwrap(), trn);
+        assert_eq!(scheme.url_to_trn(&format!("{url}&extra=1")).unwrap(), trn);
+        assert!(scheme.matches(&url));
+
+        for invalid in [
+            "registry://other:8443/api/org/team-acme/models/bert_base?v=v2.0",
+            "registry://models.internal:8443/v1/org/team-acme/models/bert_base?v=v2.0",
+            "registry://models.internal:8443/api/org/team-acme/models/bert_base",
+            "registry://models.internal:8443/api/org/team-acme/models?v=v2.0",
+            "registry://models.internal:8443/api/org/team-acme/gadgets/bert_base?v=v2.0",
+        ] {
+            assert!(matches!(scheme.url_to_trn(invalid), Err(TrnError::Url { .. } | TrnError::Validation { .. })), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn test_url_scheme_encoding() {
+        let trn = Trn::parse("trn:user:alice:tool:get-user:v1.0").unwrap();
+        let url = |encoding| {
+            UrlScheme::builder("https://api.example.com")
+                .path_template("/ns:{scope}/{platform}/{resource_type}/{resource_id}/{version}")
+                .encoding(encoding)
+                .build()
+                .unwrap()
+                .to_url(&trn)
+                .unwrap()
+        };
+        assert_eq!(url(UrlEncoding::Component), "https://api.example.com/ns:alice/user/tool/get-user/v1.0");
+        assert_eq!(url(UrlEncoding::Strict), "https://api.example.com/ns%3Aalice/user/tool/get-user/v1.0");
+    }
+
+    #[test]
+    fn test_url_scheme_build_errors() {
+        let build = |template: &str| UrlScheme::builder("https://api.example.com/").path_template(template).build();
+        assert!(build("/{platform}/{scope}/{resource_type}/{resource_id}").is_err());
+        assert!(build("/{platform}/{scope}/{resource_type}/{resource_id}/{version}/{version}").is_err());
+        assert!(build("/{platform}-{scope}/{resource_type}/{resource_id}/{version}").is_err());
+        assert!(build("/{platform}{scope}/{resource_type}/{resource_id}/{version}").is_err());
+        assert!(build("/{platform}/{scope}/{resource_type}/{resource_id}/{versions}").is_err());
+        assert!(build("/{platform/{scope}/{resource_type}/{resource_id}/{version}").is_err());
+        assert!(UrlScheme::builder("https://api.example.com/")
+            .query_param("version", TrnComponent::Version)
+            .build()
+            .is_err());
+        assert!(UrlScheme::builder("not a url").build().is_err());
+        assert!(UrlScheme::builder("mailto:ops@example.com").build().is_err());
+    }
+}
@@ -2,6 +2,8 @@
 //!
 //! This module provides various utility functions for TRN operations,
 //! including version comparison, statistics, and convenience helpers.
+//! Jobs chaining several of the filters and transforms here are better
+//! written as a [`TrnPipeline`](crate::TrnPipeline).
 
 use std::collections::HashMap;
 use std::cmp::Ordering;
@@ -547,7 +549,15 @@ pub fn convert_trn_format(trn: &str, format: TrnFormat) -> TrnResult<String> {
             ))
         }
         TrnFormat::Json => {
-            serde_json::to_string_pretty(&parsed_trn)
+            let components = parsed_trn.components();
+            let json = serde_json::json!({
+                "platform": components.platform,
+                "scope": components.scope,
+                "resource_type": components.resource_type,
+                "resource_id": components.resource_id,
+                "version": components.version,
+            });
+            serde_json::to_string_pretty(&json)
                 .map_err(|e| TrnError::format(format!("JSON serialization error: {}", e), Some(trn.to_string())))
         }
     }
@@ -587,23 +597,28 @@ pub fn sort_trns(trns: &mut [String], sort_by: TrnSortCriteria) {
         let trn_b = Trn::parse(b);
         
         match (trn_a, trn_b) {
-            (Ok(ta), Ok(tb)) => match sort_by {
-                TrnSortCriteria::Platform => ta.platform().cmp(tb.platform()),
-                TrnSortCriteria::ResourceType => ta.resource_type().cmp(tb.resource_type()),
-                TrnSortCriteria::InstanceId => ta.resource_id().cmp(tb.resource_id()),
-                TrnSortCriteria::Version => {
-                    match (SemanticVersion::parse(ta.version()), SemanticVersion::parse(tb.version())) {
-                        (Ok(v1), Ok(v2)) => v1.cmp(&v2),
-                        _ => ta.version().cmp(tb.version()),
-                    }
-                }
-                TrnSortCriteria::Length => ta.to_string().len().cmp(&tb.to_string().len()),
-            },
+            (Ok(ta), Ok(tb)) => compare_trns(&ta, &tb, sort_by),
             _ => a.cmp(b), // Fallback to string comparison
         }
     });
 }
 
+/// Compare two TRNs by a sorting criterion
+pub(crate) fn compare_trns(a: &Trn, b: &Trn, sort_by: TrnSortCriteria) -> Ordering {
+    match sort_by {
+        TrnSortCriteria::Platform => a.platform().cmp(b.platform()),
+        TrnSortCriteria::ResourceType => a.resource_type().cmp(b.resource_type()),
+        TrnSortCriteria::InstanceId => a.resource_id().cmp(b.resource_id()),
+        TrnSortCriteria::Version => {
+            match (SemanticVersion::parse(a.version()), SemanticVersion::parse(b.version())) {
+                (Ok(v1), Ok(v2)) => v1.cmp(&v2),
+                _ => a.version().cmp(b.version()),
+            }
+        }
+        TrnSortCriteria::Length => a.to_string().len().cmp(&b.to_string().len()),
+    }
+}
+
 /// TRN sorting criteria
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum TrnSortCriteria {
@@ -745,9 +760,10 @@ pub fn get_error_context(error: &TrnError) -> String {
 /// Get fix suggestions for common errors
 pub fn get_fix_suggestions(error: &TrnError) -> Vec<String> {
     match error.code() {
-        100 => vec!["Check TRN format: trn:platform:resource_type:type:instance_id:version".to_string()],
-        101 => vec!["Ensure all required components are present".to_string()],
-        102 => vec!["Check component character restrictions (alphanumeric, hyphens, underscores)".to_string()],
+        -32000 => vec!["Check TRN format: trn:platform:scope:resource_type:resource_id:version".to_string()],
+        -32001 => vec!["Ensure all required components are present and supported".to_string()],
+        -32002 => vec!["Check component lengths and character restrictions (alphanumeric, hyphens, underscores)".to_string()],
+        -32040 | -32041 => vec!["Use a supported platform and resource type".to_string()],
         _ => vec!["Check TRN documentation for valid formats".to_string()],
     }
 }
@@ -5,115 +5,286 @@
 
 use dashmap::DashMap;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
 use crate::constants::*;
+use crate::diff::TrnComponent;
 use crate::error::{TrnError, TrnResult};
+use crate::naming::{DefaultNamingPolicy, NamingPolicy};
 use crate::types::Trn;
 
 /// Validation cache for performance optimization
+///
+/// Holds at most `max_size` results, each for at most `ttl`. When full, it
+/// drops expired entries, then the least recently used quarter. Clones
+/// share the same entries and counters, so one cache can be handed to
+/// every thread on a hot path:
+///
+/// ```rust
+/// use std::time::Duration;
+/// use trn_rust::{ValidationCache, ValidationCacheConfig};
+///
+/// let cache = ValidationCache::with_config(ValidationCacheConfig {
+///     max_size: 10_000,
+///     ttl: Duration::from_secs(60),
+/// });
+/// let handle = cache.clone();
+/// std::thread::spawn(move || handle.validate("trn:user:alice:tool:weather-api:v1.0")).join().unwrap()?;
+///
+/// assert!(cache.validate("trn:user:alice:tool:weather-api:v1.0").is_ok());
+/// let stats = cache.stats();
+/// assert_eq!((stats.hits, stats.misses), (1, 1));
+/// # Ok::<(), trn_rust::TrnError>(())
+/// ```
 #[derive(Debug, Clone)]
 pub struct ValidationCache {
-    cache: Arc<DashMap<String, CacheEntry>>,
-    max_size: usize,
-    ttl: Duration,
+    inner: Arc<CacheInner>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug)]
+struct CacheInner {
+    entries: DashMap<String, CacheEntry>,
+    config: ValidationCacheConfig,
+    /// Logical clock ordering accesses, for LRU eviction
+    clock: AtomicU64,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    evictions: AtomicU64,
+    expirations: AtomicU64,
+}
+
+#[derive(Debug)]
 struct CacheEntry {
     result: bool,
     timestamp: Instant,
+    last_used: AtomicU64,
+}
+
+/// Size and lifetime limits of a [`ValidationCache`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ValidationCacheConfig {
+    /// Maximum number of entries; 0 disables caching
+    pub max_size: usize,
+    /// How long a result stays valid
+    pub ttl: Duration,
+}
+
+impl Default for ValidationCacheConfig {
+    fn default() -> Self {
+        Self {
+            max_size: VALIDATION_CACHE_SIZE,
+            ttl: Duration::from_secs(VALIDATION_CACHE_TTL_SECONDS),
+        }
+    }
 }
 
 impl ValidationCache {
     /// Create a new validation cache
     pub fn new(max_size: usize, ttl_seconds: u64) -> Self {
-        Self {
-            cache: Arc::new(DashMap::new()),
+        Self::with_config(ValidationCacheConfig {
             max_size,
             ttl: Duration::from_secs(ttl_seconds),
+        })
+    }
+
+    /// Create a validation cache with the given limits
+    pub fn with_config(config: ValidationCacheConfig) -> Self {
+        Self {
+            inner: Arc::new(CacheInner {
+                entries: DashMap::new(),
+                config,
+                clock: AtomicU64::new(0),
+                hits: AtomicU64::new(0),
+                misses: AtomicU64::new(0),
+                evictions: AtomicU64::new(0),
+                expirations: AtomicU64::new(0),
+            }),
+        }
+    }
+
+    /// Get a handle to the cache used by [`validate_trn_string`]
+    pub fn global() -> Self {
+        VALIDATION_CACHE.clone()
+    }
+
+    /// Get the cache limits
+    pub fn config(&self) -> ValidationCacheConfig {
+        self.inner.config
+    }
+
+    /// Validate a TRN string, reusing a cached result when there is one
+    ///
+    /// A cached failure is reported as a generic validation error, without
+    /// the details of the original one.
+    pub fn validate(&self, input: &str) -> TrnResult<()> {
+        if let Some(cached_result) = self.get(input) {
+            return if cached_result {
+                Ok(())
+            } else {
+                Err(TrnError::validation("TRN is invalid (cached)".to_string(), "cached".to_string(), Some(input.to_string())))
+            };
         }
+
+        let result = validate_trn_string_impl(input);
+        self.insert(input.to_string(), result.is_ok());
+        result
     }
 
     /// Get cached validation result
     pub fn get(&self, key: &str) -> Option<bool> {
-        if let Some(entry) = self.cache.get(key) {
-            if entry.timestamp.elapsed() < self.ttl {
+        let inner = &self.inner;
+        if let Some(entry) = inner.entries.get(key) {
+            if entry.timestamp.elapsed() < inner.config.ttl {
+                entry.last_used.store(inner.tick(), Ordering::Relaxed);
+                inner.hits.fetch_add(1, Ordering::Relaxed);
                 return Some(entry.result);
-            } else {
-                // Entry expired, remove it
-                self.cache.remove(key);
+            }
+            // Entry expired, remove it
+            drop(entry);
+            if inner.entries.remove(key).is_some() {
+                inner.expirations.fetch_add(1, Ordering::Relaxed);
             }
         }
+        inner.misses.fetch_add(1, Ordering::Relaxed);
         None
     }
 
     /// Insert validation result into cache
     pub fn insert(&self, key: String, result: bool) {
-        // If cache is full, remove oldest entries
-        if self.cache.len() >= self.max_size {
+        let inner = &self.inner;
+        if inner.config.max_size == 0 {
+            return;
+        }
+        if inner.entries.len() >= inner.config.max_size && !inner.entries.contains_key(&key) {
             self.cleanup_expired();
-            
-            if self.cache.len() >= self.max_size {
-                // Remove some entries to make space
-                let keys_to_remove: Vec<String> = self.cache
-                    .iter()
-                    .take(self.max_size / 4)
-                    .map(|entry| entry.key().clone())
-                    .collect();
-                
-                for key in keys_to_remove {
-                    self.cache.remove(&key);
-                }
-            }
+            self.evict_least_recently_used();
         }
 
-        self.cache.insert(key, CacheEntry {
+        inner.entries.insert(key, CacheEntry {
             result,
             timestamp: Instant::now(),
+            last_used: AtomicU64::new(inner.tick()),
         });
     }
 
     /// Remove expired entries
     fn cleanup_expired(&self) {
+        let inner = &self.inner;
         let now = Instant::now();
-        let expired_keys: Vec<String> = self.cache
+        let expired_keys: Vec<String> = inner.entries
             .iter()
-            .filter(|entry| now.duration_since(entry.timestamp) >= self.ttl)
+            .filter(|entry| now.duration_since(entry.timestamp) >= inner.config.ttl)
             .map(|entry| entry.key().clone())
             .collect();
 
         for key in expired_keys {
-            self.cache.remove(&key);
+            if inner.entries.remove(&key).is_some() {
+                inner.expirations.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// Drop the least recently used entries until a quarter of the cache
+    /// is free, if it is still full
+    fn evict_least_recently_used(&self) {
+        let inner = &self.inner;
+        let max_size = inner.config.max_size;
+        let len = inner.entries.len();
+        if len < max_size {
+            return;
+        }
+
+        let target = max_size - (max_size / 4).max(1);
+        let mut by_age: Vec<(u64, String)> = inner.entries
+            .iter()
+            .map(|entry| (entry.last_used.load(Ordering::Relaxed), entry.key().clone()))
+            .collect();
+        let count = by_age.len().saturating_sub(target);
+        if count == 0 {
+            return;
+        }
+        by_age.select_nth_unstable_by_key(count - 1, |(last_used, _)| *last_used);
+
+        for (_, key) in by_age.drain(..count) {
+            if inner.entries.remove(&key).is_some() {
+                inner.evictions.fetch_add(1, Ordering::Relaxed);
+            }
         }
     }
 
+    /// Get the number of cached results, including expired ones not yet
+    /// removed
+    pub fn len(&self) -> usize {
+        self.inner.entries.len()
+    }
+
+    /// Check whether the cache holds no results
+    pub fn is_empty(&self) -> bool {
+        self.inner.entries.is_empty()
+    }
+
     /// Clear all cached entries
     pub fn clear(&self) {
-        self.cache.clear();
+        self.inner.entries.clear();
+    }
+
+    /// Reset the hit, miss and eviction counters
+    pub fn reset_stats(&self) {
+        let inner = &self.inner;
+        for counter in [&inner.hits, &inner.misses, &inner.evictions, &inner.expirations] {
+            counter.store(0, Ordering::Relaxed);
+        }
     }
 
     /// Get cache statistics
     pub fn stats(&self) -> ValidationCacheStats {
+        let inner = &self.inner;
+        let hits = inner.hits.load(Ordering::Relaxed);
+        let misses = inner.misses.load(Ordering::Relaxed);
+        let lookups = hits + misses;
         ValidationCacheStats {
-            total_entries: self.cache.len(),
-            hit_rate: 0.0, // Would need additional tracking
+            total_entries: inner.entries.len(),
+            max_size: inner.config.max_size,
+            hit_rate: if lookups > 0 { hits as f64 / lookups as f64 } else { 0.0 },
+            hits,
+            misses,
+            evictions: inner.evictions.load(Ordering::Relaxed),
+            expirations: inner.expirations.load(Ordering::Relaxed),
         }
     }
 }
 
+impl CacheInner {
+    fn tick(&self) -> u64 {
+        self.clock.fetch_add(1, Ordering::Relaxed)
+    }
+}
+
 /// Statistics for validation cache performance
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub struct ValidationCacheStats {
     /// Total number of cache entries
     pub total_entries: usize,
+    /// Maximum number of entries
+    pub max_size: usize,
     /// Cache hit rate as a percentage (0.0 to 1.0)
     pub hit_rate: f64,
+    /// Lookups answered from the cache
+    pub hits: u64,
+    /// Lookups not in the cache, or expired
+    pub misses: u64,
+    /// Entries dropped to make room
+    pub evictions: u64,
+    /// Entries dropped after their TTL
+    pub expirations: u64,
 }
 
 /// Global validation cache instance
 static VALIDATION_CACHE: once_cell::sync::Lazy<ValidationCache> = once_cell::sync::Lazy::new(|| {
-    ValidationCache::new(VALIDATION_CACHE_SIZE, VALIDATION_CACHE_TTL_SECONDS)
+    ValidationCache::with_config(ValidationCacheConfig::default())
 });
 
 /// Validation statistics
@@ -135,23 +306,10 @@ pub fn is_valid_trn(input: &str) -> bool {
 }
 
 /// Validate a TRN string with detailed error information
+///
+/// Results are cached in [`ValidationCache::global`].
 pub fn validate_trn_string(input: &str) -> TrnResult<()> {
-    // Check cache first
-    if let Some(cached_result) = VALIDATION_CACHE.get(input) {
-        return if cached_result {
-            Ok(())
-        } else {
-            Err(TrnError::validation("TRN is invalid (cached)".to_string(), "cached".to_string(), Some(input.to_string())))
-        };
-    }
-
-    // Perform validation
-    let result = validate_trn_string_impl(input);
-    
-    // Cache the result
-    VALIDATION_CACHE.insert(input.to_string(), result.is_ok());
-    
-    result
+    VALIDATION_CACHE.validate(input)
 }
 
 /// Internal validation implementation
@@ -171,10 +329,22 @@ fn validate_trn_string_impl(input: &str) -> TrnResult<()> {
     Ok(())
 }
 
+/// Validate a TRN string without going through the cache, which would
+/// allocate a key for every new string
+pub(crate) fn validate_trn_str(input: &str) -> TrnResult<()> {
+    validate_trn_string_impl(input)
+}
+
 /// Validate TRN structure (for already parsed TRN objects)
 pub fn validate_trn_struct(trn: &Trn) -> TrnResult<()> {
-    let trn_string = trn.to_string();
-    validate_trn_string(&trn_string)
+    validate_trn_struct_with(trn, &DefaultNamingPolicy)
+}
+
+/// Validate TRN structure, then check it against a naming policy
+pub fn validate_trn_struct_with(trn: &Trn, policy: &dyn NamingPolicy) -> TrnResult<()> {
+    let trn_string = trn.base_string();
+    validate_trn_string(&trn_string)?;
+    policy.check(trn.components())
 }
 
 /// Validate basic TRN format
@@ -260,6 +430,18 @@ fn validate_components(input: &str) -> TrnResult<()> {
     Ok(())
 }
 
+/// Validate a single component value outside of a TRN
+pub(crate) fn validate_component_value(component: TrnComponent, value: &str) -> TrnResult<()> {
+    let (regex, max_length) = match component {
+        TrnComponent::Platform => (&*PLATFORM_REGEX, PLATFORM_MAX_LENGTH),
+        TrnComponent::Scope => (&*SCOPE_REGEX, SCOPE_MAX_LENGTH),
+        TrnComponent::ResourceType => (&*RESOURCE_TYPE_REGEX, RESOURCE_TYPE_MAX_LENGTH),
+        TrnComponent::ResourceId => (&*RESOURCE_ID_REGEX, RESOURCE_ID_MAX_LENGTH),
+        TrnComponent::Version => (&*VERSION_REGEX, VERSION_MAX_LENGTH),
+    };
+    validate_component(value, component.as_str(), regex, max_length)
+}
+
 /// Validate a single component
 fn validate_component(
     value: &str,
@@ -459,6 +641,108 @@ pub fn generate_validation_report(trns: &[String]) -> ValidationReport {
     }
 }
 
+/// Failure of one TRN in a batch validation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchItemError {
+    /// Position of the TRN in the batch
+    pub index: usize,
+    /// The invalid TRN string
+    pub trn: String,
+    /// Error code, as [`TrnError::code`]
+    pub code: i32,
+    /// Error message
+    pub message: String,
+    /// How the TRN might be fixed
+    pub suggestions: Vec<String>,
+}
+
+impl BatchItemError {
+    fn new(index: usize, trn: &str, error: &TrnError) -> Self {
+        Self {
+            index,
+            trn: trn.to_string(),
+            code: error.code(),
+            message: error.to_string(),
+            suggestions: crate::utils::get_fix_suggestions(error),
+        }
+    }
+}
+
+/// Per-item validation report for a batch of TRNs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchValidationReport {
+    /// Total number of TRNs validated
+    pub total: usize,
+    /// Number of valid TRNs
+    pub valid: usize,
+    /// Number of invalid TRNs
+    pub invalid: usize,
+    /// Failures, in batch order
+    pub failures: Vec<BatchItemError>,
+    /// Number of failures per error code
+    pub errors_by_code: BTreeMap<i32, usize>,
+    /// Performance statistics
+    pub stats: ValidationStats,
+}
+
+impl BatchValidationReport {
+    fn new(total: usize, failures: Vec<BatchItemError>, duration: Duration) -> Self {
+        let mut errors_by_code = BTreeMap::new();
+        for failure in &failures {
+            *errors_by_code.entry(failure.code).or_insert(0) += 1;
+        }
+        let seconds = duration.as_secs_f64();
+        Self {
+            total,
+            valid: total - failures.len(),
+            invalid: failures.len(),
+            failures,
+            errors_by_code,
+            stats: ValidationStats {
+                duration_ms: u64::try_from(duration.as_millis()).unwrap_or(u64::MAX),
+                // Batches bypass the cache, which would only churn
+                cache_hits: 0,
+                cache_misses: 0,
+                rate_per_second: if seconds > 0.0 { total as f64 / seconds } else { 0.0 },
+            },
+        }
+    }
+}
+
+/// Validate a batch of TRNs, reporting each failure with its index, error
+/// code and fix suggestions
+pub fn validate_batch<S: AsRef<str>>(trns: &[S]) -> BatchValidationReport {
+    let start = Instant::now();
+    let failures = trns.iter()
+        .enumerate()
+        .filter_map(|(index, trn)| validate_item(index, trn.as_ref()))
+        .collect();
+    BatchValidationReport::new(trns.len(), failures, start.elapsed())
+}
+
+/// Validate a batch of TRNs across the rayon thread pool, with the same
+/// report as [`validate_batch`]
+#[cfg(feature = "rayon")]
+#[cfg_attr(docsrs, doc(cfg(feature = "rayon")))]
+pub fn validate_batch_parallel<S: AsRef<str> + Sync>(trns: &[S]) -> BatchValidationReport {
+    use rayon::prelude::*;
+
+    let start = Instant::now();
+    let failures = trns.par_iter()
+        .enumerate()
+        // Items are cheap; splitting finer only adds scheduling overhead
+        .with_min_len(1024)
+        .filter_map(|(index, trn)| validate_item(index, trn.as_ref()))
+        .collect();
+    BatchValidationReport::new(trns.len(), failures, start.elapsed())
+}
+
+fn validate_item(index: usize, trn: &str) -> Option<BatchItemError> {
+    validate_trn_string_impl(trn)
+        .err()
+        .map(|error| BatchItemError::new(index, trn, &error))
+}
+
 /// Check if TRN components are well-formed
 pub fn check_component_format(components: &crate::types::TrnComponents<'_>) -> Vec<String> {
     let mut issues = Vec::new();
@@ -492,14 +776,16 @@ pub fn check_component_format(components: &crate::types::TrnComponents<'_>) -> V
 }
 
 /// Validate TRN compliance with naming conventions
-pub fn validate_naming_conventions(_trn: &Trn) -> TrnResult<()> {
+///
+/// Only the default conventions apply; see [`NamingPolicy`] for stricter ones.
+pub fn validate_naming_conventions(trn: &Trn) -> TrnResult<()> {
     // We now allow both uppercase and lowercase in platform, scope, and resource type
     // Only enforce basic format validation through regex patterns
     
     // Platforms, scopes, and resource types can be uppercase or lowercase
     // The regex validation handles character restrictions
     
-    Ok(())
+    DefaultNamingPolicy.check(trn.components())
 }
 
 /// Performance validation for high-throughput scenarios
@@ -568,6 +854,77 @@ mod tests {
         assert_eq!(cache.get("nonexistent"), None);
     }
 
+    #[test]
+    fn test_validation_cache_eviction() {
+        let cache = ValidationCache::new(4, 60);
+        for key in ["a", "b", "c", "d"] {
+            cache.insert(key.to_string(), true);
+        }
+        // "a" becomes the most recently used, so "b" is evicted first
+        assert_eq!(cache.get("a"), Some(true));
+        cache.insert("e".to_string(), true);
+        assert_eq!(cache.len(), 4);
+        assert_eq!(cache.get("b"), None);
+        assert_eq!(cache.get("a"), Some(true));
+
+        // Replacing an entry evicts nothing
+        cache.insert("e".to_string(), false);
+        assert_eq!(cache.get("e"), Some(false));
+
+        let stats = cache.stats();
+        assert_eq!(stats.evictions, 1);
+        assert_eq!((stats.hits, stats.misses), (3, 1));
+        assert!((stats.hit_rate - 0.75).abs() < f64::EPSILON);
+
+        cache.reset_stats();
+        assert_eq!(cache.stats().hits, 0);
+
+        let disabled = ValidationCache::new(0, 60);
+        disabled.insert("a".to_string(), true);
+        assert!(disabled.is_empty());
+    }
+
+    #[test]
+    fn test_validation_cache_expiry() {
+        let cache = ValidationCache::with_config(ValidationCacheConfig {
+            max_size: 2,
+            ttl: Duration::ZERO,
+        });
+        cache.insert("a".to_string(), true);
+        cache.insert("b".to_string(), true);
+        // Making room drops expired entries rather than evicting
+        cache.insert("c".to_string(), true);
+        assert_eq!(cache.len(), 1);
+        assert_eq!(cache.get("c"), None);
+        assert!(cache.is_empty());
+
+        let stats = cache.stats();
+        assert_eq!(stats.expirations, 3);
+        assert_eq!(stats.evictions, 0);
+    }
+
+    #[test]
+    fn test_validation_cache_shared() {
+        let cache = ValidationCache::new(100, 60);
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                let cache = cache.clone();
+                std::thread::spawn(move || {
+                    assert!(cache.validate("trn:user:alice:tool:myapi:v1.0").is_ok());
+                    assert!(cache.validate("trn:user:alice:gadget:myapi:v1.0").is_err());
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        let stats = cache.stats();
+        assert_eq!(stats.total_entries, 2);
+        assert_eq!(stats.hits + stats.misses, 8);
+        assert!(stats.misses >= 2);
+    }
+
     #[test]
     fn test_batch_validation() {
         let trns = vec![
@@ -590,4 +947,34 @@ mod tests {
         let trn = Trn::new("USER", "alice", "tool", "myapi", "v1.0").unwrap();
         assert!(validate_naming_conventions(&trn).is_ok());
     }
-} 
\ No newline at end of file
+
+    #[test]
+    fn test_batch_report() {
+        let trns = [
+            "trn:user:alice:tool:myapi:v1.0",
+            "invalid:format",
+            "trn:org:company:model:bert:v2.1",
+            "trn:user:alice:gadget:myapi:v1.0",
+            "",
+        ];
+        let report = validate_batch(&trns);
+        assert_eq!((report.total, report.valid, report.invalid), (5, 2, 3));
+        let indexes: Vec<usize> = report.failures.iter().map(|failure| failure.index).collect();
+        assert_eq!(indexes, [1, 3, 4]);
+        assert_eq!(report.errors_by_code.values().sum::<usize>(), 3);
+        assert!(report.failures.iter().all(|failure| !failure.suggestions.is_empty()));
+        assert_eq!(report.failures[0].trn, "invalid:format");
+
+        #[cfg(feature = "rayon")]
+        {
+            let trns: Vec<String> = (0..2000)
+                .map(|i| if i % 7 == 0 { format!("bad:{i}") } else { format!("trn:user:u{i}:tool:api:v1.0") })
+                .collect();
+            let parallel = validate_batch_parallel(&trns);
+            let sequential = validate_batch(&trns);
+            assert_eq!(parallel.invalid, sequential.invalid);
+            let indexes = |report: &BatchValidationReport| report.failures.iter().map(|f| f.index).collect::<Vec<_>>();
+            assert_eq!(indexes(&parallel), indexes(&sequential));
+        }
+    }
+}
@@ -0,0 +1,369 @@
+//! Version requirements and resolution
+//!
+//! This module picks the best version of a resource among a set of TRNs,
+//! given a requirement in the usual semantic versioning syntax:
+//!
+//! | Requirement | Matches |
+//! |---|---|
+//! | `^1.2`, `1.2` | `>=1.2.0, <2.0.0` |
+//! | `^0.2` | `>=0.2.0, <0.3.0` |
+//! | `~1.2` | `>=1.2.0, <1.3.0` |
+//! | `=1.2` | `>=1.2.0, <1.3.0` |
+//! | `>1.2` | `>=1.3.0` |
+//! | `>=2.0,<3.0` | both comparators |
+//! | `*` | anything |
+//!
+//! Versions are compared with [`SemanticVersion`], a missing minor or patch
+//! number counting as zero, so `v1.0` is read as `1.0.0`. Aliases such as
+//! `latest` match no requirement. Whether prereleases match is set by a
+//! [`PrereleasePolicy`].
+
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::error::{TrnError, TrnResult};
+use crate::pattern::TrnMatcher;
+use crate::types::Trn;
+use crate::utils::SemanticVersion;
+
+/// Which prerelease versions a requirement matches
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PrereleasePolicy {
+    /// No prerelease ever matches
+    Exclude,
+    /// A prerelease matches only if a comparator of the requirement names
+    /// a prerelease of the same `major.minor.patch`, as in Cargo and npm
+    #[default]
+    Requested,
+    /// Prereleases match like any other version
+    Include,
+}
+
+/// Comparison operator of a comparator
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Exact,
+    Greater,
+    GreaterEq,
+    Less,
+    LessEq,
+    Tilde,
+    Caret,
+}
+
+/// One comparator of a requirement, such as `>=1.2`
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Comparator {
+    op: Op,
+    /// Version given, missing numbers as zero
+    version: SemanticVersion,
+    /// Numbers given, from 1 (`1`) to 3 (`1.2.3`)
+    given: usize,
+}
+
+impl Comparator {
+    fn parse(input: &str) -> Option<Self> {
+        let input = input.trim();
+        let (op, rest) = [
+            (">=", Op::GreaterEq),
+            ("<=", Op::LessEq),
+            ("==", Op::Exact),
+            ("=", Op::Exact),
+            (">", Op::Greater),
+            ("<", Op::Less),
+            ("~", Op::Tilde),
+            ("^", Op::Caret),
+        ]
+        .into_iter()
+        .find_map(|(symbol, op)| input.strip_prefix(symbol).map(|rest| (op, rest)))
+        .unwrap_or((Op::Caret, input));
+
+        let (version, given) = parse_partial(rest.trim())?;
+        Some(Self { op, version, given })
+    }
+
+    /// The first version past the numbers given: `1.3.0` for `1.2`
+    const fn next(&self) -> SemanticVersion {
+        let v = &self.version;
+        match self.given {
+            1 => release(v.major + 1, 0, 0),
+            2 => release(v.major, v.minor + 1, 0),
+            _ => release(v.major, v.minor, v.patch + 1),
+        }
+    }
+
+    fn matches(&self, version: &SemanticVersion) -> bool {
+        let v = &self.version;
+        match self.op {
+            Op::Exact if self.given == 3 => version.cmp(v) == Ordering::Equal,
+            Op::Exact => version >= v && *version < self.next(),
+            Op::Greater if self.given == 3 => version > v,
+            Op::Greater => *version >= self.next(),
+            Op::GreaterEq => version >= v,
+            Op::Less => version < v,
+            Op::LessEq if self.given == 3 => version <= v,
+            Op::LessEq => *version < self.next(),
+            Op::Tilde => {
+                let upper = match self.given {
+                    1 => release(v.major + 1, 0, 0),
+                    _ => release(v.major, v.minor + 1, 0),
+                };
+                version >= v && *version < upper
+            }
+            Op::Caret => {
+                // The leftmost non-zero number given may not change
+                let upper = match (v.major, v.minor, self.given) {
+                    (0, _, 1) => release(1, 0, 0),
+                    (0, 0, 2) => release(0, 1, 0),
+                    (0, 0, _) => release(0, 0, v.patch + 1),
+                    (0, minor, _) => release(0, minor + 1, 0),
+                    (major, _, _) => release(major + 1, 0, 0),
+                };
+                version >= v && *version < upper
+            }
+        }
+    }
+
+    /// Whether this comparator names a prerelease of `version`'s release
+    fn requests_prerelease_of(&self, version: &SemanticVersion) -> bool {
+        self.version.prerelease.is_some()
+            && (self.version.major, self.version.minor, self.version.patch)
+                == (version.major, version.minor, version.patch)
+    }
+}
+
+/// A version requirement, such as `^1.2` or `>=2.0,<3.0`
+///
+/// # Examples
+///
+/// ```rust
+/// use trn_rust::{SemanticVersion, VersionRequirement};
+///
+/// let requirement = VersionRequirement::parse(">=2.0, <3.0")?;
+/// assert!(requirement.matches(&SemanticVersion::parse("2.4.1")?));
+/// assert!(!requirement.matches(&SemanticVersion::parse("3.0.0")?));
+/// # Ok::<(), trn_rust::TrnError>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VersionRequirement {
+    original: String,
+    /// All must match; none for `*`
+    comparators: Vec<Comparator>,
+}
+
+impl VersionRequirement {
+    /// Parse a requirement made of comma-separated comparators
+    pub fn parse(requirement: &str) -> TrnResult<Self> {
+        let invalid = || TrnError::version("Invalid version requirement", requirement, "", "");
+        let trimmed = requirement.trim();
+        if trimmed.is_empty() {
+            return Err(invalid());
+        }
+
+        let comparators = if trimmed == "*" {
+            Vec::new()
+        } else {
+            trimmed
+                .split(',')
+                .map(|comparator| Comparator::parse(comparator).ok_or_else(invalid))
+                .collect::<TrnResult<Vec<_>>>()?
+        };
+
+        Ok(Self {
+            original: trimmed.to_string(),
+            comparators,
+        })
+    }
+
+    /// Check whether `version` meets the requirement, with the default
+    /// [`PrereleasePolicy`]
+    pub fn matches(&self, version: &SemanticVersion) -> bool {
+        self.matches_with(version, PrereleasePolicy::default())
+    }
+
+    /// Check whether `version` meets the requirement under `policy`
+    pub fn matches_with(&self, version: &SemanticVersion, policy: PrereleasePolicy) -> bool {
+        if version.prerelease.is_some() {
+            let allowed = match policy {
+                PrereleasePolicy::Exclude => false,
+                PrereleasePolicy::Requested => self.comparators.iter()
+                    .any(|comparator| comparator.requests_prerelease_of(version)),
+                PrereleasePolicy::Include => true,
+            };
+            if !allowed {
+                return false;
+            }
+        }
+        self.comparators.iter().all(|comparator| comparator.matches(version))
+    }
+}
+
+impl FromStr for VersionRequirement {
+    type Err = TrnError;
+
+    fn from_str(s: &str) -> TrnResult<Self> {
+        Self::parse(s)
+    }
+}
+
+impl fmt::Display for VersionRequirement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.original)
+    }
+}
+
+/// Pick the highest version among `trns` matching `base_pattern` that
+/// meets `requirement`, with the default [`PrereleasePolicy`]
+///
+/// `base_pattern` is a TRN pattern; without a version component, any
+/// version is taken. Returns `None` if no TRN qualifies, or if the pattern
+/// or requirement is invalid.
+///
+/// # Examples
+///
+/// ```rust
+/// use trn_rust::resolve_version;
+///
+/// let trns = vec![
+///     "trn:user:alice:tool:weather:v1.2.0".to_string(),
+///     "trn:user:alice:tool:weather:v1.4.2".to_string(),
+///     "trn:user:alice:tool:weather:v2.0.0".to_string(),
+/// ];
+/// let best = resolve_version(&trns, "trn:user:alice:tool:weather", "^1.2").unwrap();
+/// assert_eq!(best.version(), "v1.4.2");
+/// ```
+pub fn resolve_version(trns: &[String], base_pattern: &str, requirement: &str) -> Option<Trn> {
+    let requirement = VersionRequirement::parse(requirement).ok()?;
+    resolve_version_with(trns, base_pattern, &requirement, PrereleasePolicy::default())
+}
+
+/// Pick the highest version among `trns` matching `base_pattern` that
+/// meets `requirement` under `policy`; the first listed wins a tie
+pub fn resolve_version_with(
+    trns: &[String],
+    base_pattern: &str,
+    requirement: &VersionRequirement,
+    policy: PrereleasePolicy,
+) -> Option<Trn> {
+    let matcher = match base_pattern.split(':').count() {
+        5 => TrnMatcher::new(&format!("{base_pattern}:*")),
+        _ => TrnMatcher::new(base_pattern),
+    }
+    .ok()?;
+
+    let mut best: Option<(SemanticVersion, Trn)> = None;
+    for trn in trns.iter().filter(|trn| matcher.matches(trn)) {
+        let Ok(trn) = Trn::parse(trn) else { continue };
+        let Some((version, _)) = parse_partial(trn.version()) else { continue };
+        if !requirement.matches_with(&version, policy) {
+            continue;
+        }
+        if best.as_ref().is_none_or(|(current, _)| version > *current) {
+            best = Some((version, trn));
+        }
+    }
+    best.map(|(_, trn)| trn)
+}
+
+/// Parse a version of one to three numbers, with the count given
+pub(crate) fn parse_partial(version: &str) -> Option<(SemanticVersion, usize)> {
+    if let Ok(version) = SemanticVersion::parse(version) {
+        return Some((version, 3));
+    }
+    let numbers = version.strip_prefix('v').unwrap_or(version)
+        .split('.')
+        .map(|number| number.parse::<u32>().ok())
+        .collect::<Option<Vec<_>>>()?;
+    match numbers[..] {
+        [major] => Some((release(major, 0, 0), 1)),
+        [major, minor] => Some((release(major, minor, 0), 2)),
+        _ => None,
+    }
+}
+
+const fn release(major: u32, minor: u32, patch: u32) -> SemanticVersion {
+    SemanticVersion {
+        major,
+        minor,
+        patch,
+        prerelease: None,
+        build: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn version(version: &str) -> SemanticVersion {
+        SemanticVersion::parse(version).unwrap()
+    }
+
+    #[test]
+    fn test_requirement_matching() {
+        let cases = [
+            ("^1.2", "1.9.0", true),
+            ("^1.2", "2.0.0", false),
+            ("1.2", "1.1.9", false),
+            ("^0.2", "0.3.0", false),
+            ("^0.0.3", "0.0.4", false),
+            ("~1.2", "1.2.7", true),
+            ("~1.2", "1.3.0", false),
+            ("=1.2", "1.2.5", true),
+            (">1.2", "1.2.9", false),
+            (">1.2", "1.3.0", true),
+            ("<=1.2", "1.2.9", true),
+            (">=2.0,<3.0", "2.5.0", true),
+            (">=2.0, <3.0", "3.0.0", false),
+            ("*", "0.0.1", true),
+        ];
+        for (requirement, candidate, expected) in cases {
+            let parsed = VersionRequirement::parse(requirement).unwrap();
+            assert_eq!(parsed.matches(&version(candidate)), expected, "{requirement} against {candidate}");
+        }
+
+        assert!(VersionRequirement::parse("").is_err());
+        assert!(VersionRequirement::parse(">=two").is_err());
+        assert!(VersionRequirement::parse("^1.2,").is_err());
+    }
+
+    #[test]
+    fn test_prerelease_policies() {
+        let beta = version("1.3.0-beta");
+        let plain = VersionRequirement::parse("^1.2").unwrap();
+        let requested = VersionRequirement::parse(">=1.3.0-alpha").unwrap();
+
+        assert!(!plain.matches(&beta));
+        assert!(plain.matches_with(&beta, PrereleasePolicy::Include));
+        assert!(requested.matches(&beta));
+        assert!(!requested.matches_with(&beta, PrereleasePolicy::Exclude));
+        assert!(!requested.matches(&version("1.4.0-beta")));
+    }
+
+    #[test]
+    fn test_resolve_version() {
+        let trns: Vec<String> = [
+            "trn:user:alice:tool:weather:v1.0",
+            "trn:user:alice:tool:weather:v1.4.2",
+            "trn:user:alice:tool:weather:v1.5.0-rc.1",
+            "trn:user:alice:tool:weather:v2.1",
+            "trn:user:alice:tool:weather:latest",
+            "trn:user:bob:tool:weather:v1.9.0",
+        ]
+        .iter()
+        .map(ToString::to_string)
+        .collect();
+
+        let resolve = |pattern, requirement| resolve_version(&trns, pattern, requirement).map(|trn| trn.to_string());
+        assert_eq!(resolve("trn:user:alice:tool:weather", "^1.0").as_deref(), Some("trn:user:alice:tool:weather:v1.4.2"));
+        assert_eq!(resolve("trn:user:alice:tool:weather:*", ">=2.0,<3.0").as_deref(), Some("trn:user:alice:tool:weather:v2.1"));
+        assert_eq!(resolve("trn:user:*:tool:weather", "~1.9").as_deref(), Some("trn:user:bob:tool:weather:v1.9.0"));
+        assert_eq!(resolve("trn:user:alice:tool:weather", "^3"), None);
+        assert_eq!(resolve("trn:user:alice:tool:weather", "not a requirement"), None);
+
+        let requirement = VersionRequirement::parse("^1.0").unwrap();
+        let best = resolve_version_with(&trns, "trn:user:alice:tool:weather", &requirement, PrereleasePolicy::Include);
+        assert_eq!(best.unwrap().version(), "v1.5.0-rc.1");
+    }
+}
@@ -221,4 +221,36 @@ fn test_parse_performance() {
     let duration = start.elapsed();
     // Should be fast - less than 100ms for 1000 parses
     assert!(duration.as_millis() < 100, "Parsing too slow: {:?}", duration);
-} 
\ No newline at end of file
+}
+
+#[test]
+fn test_borrowed_parsing_matches_owned() {
+    let cases = [
+        "trn:user:alice:tool:myapi:v1.0",
+        "trn:aiplatform:system:dataset:training:latest",
+        "trn:org:company:model:bert:v2.1",
+        "trn:user:alice:tool",
+        "trn:user::tool:myapi:v1.0",
+        "urn:user:alice:tool:myapi:v1.0",
+        "trn:user:a:tool:myapi:v1.0",
+    ];
+
+    for input in cases {
+        match (TrnRef::parse(input), Trn::parse(input)) {
+            (Ok(borrowed), Ok(owned)) => {
+                assert_eq!(borrowed, owned);
+                assert_eq!(borrowed.as_str(), input);
+                assert_eq!(borrowed.to_string(), owned.to_string());
+                assert_eq!(Trn::from(borrowed), owned);
+            }
+            (Err(_), Err(_)) => {}
+            (borrowed, owned) => panic!("{input}: borrowed {borrowed:?}, owned {owned:?}"),
+        }
+    }
+
+    let trn = TrnRef::parse("trn:user:alice:tool:myapi:v1.0").unwrap();
+    assert_eq!(trn.resource_type(), "tool");
+    assert!(trn.matches_pattern("trn:user:alice:*:*:*"));
+    assert!(TrnMatcher::new("trn:*:*:tool:*:*").unwrap().matches(trn.as_str()));
+    assert!(trn.is_compatible_with(&TrnRef::parse("trn:user:alice:tool:myapi:v2.0").unwrap()));
+}
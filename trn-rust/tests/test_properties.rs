@@ -0,0 +1,179 @@
+//! Property-based and differential tests
+//!
+//! Generated TRNs exercise the parser, validator, pattern matchers and URL
+//! conversions against each other, checking that the different entry
+//! points agree on every input instead of only on hand-picked examples.
+
+use proptest::prelude::*;
+use trn_rust::{
+    is_valid_trn, url_to_trn, validate_trn_string, Trn, TrnMatcher, TrnPatternSet, TrnRef,
+    UrlScheme,
+};
+
+const RESERVED: [&str; 4] = ["trn", "null", "undefined", "void"];
+
+const RESOURCE_TYPES: [&str; 8] = [
+    "tool", "model", "dataset", "pipeline", "workflow", "service", "api", "custom-type",
+];
+
+fn not_reserved(value: &str) -> bool {
+    !RESERVED.contains(&value)
+}
+
+fn platform() -> impl Strategy<Value = String> {
+    prop_oneof![
+        Just("user".to_string()),
+        Just("org".to_string()),
+        Just("aiplatform".to_string()),
+        "[a-z][a-z0-9-]{1,15}".prop_filter("reserved", |value| not_reserved(value)),
+    ]
+}
+
+fn scope() -> impl Strategy<Value = String> {
+    "[a-zA-Z0-9][a-zA-Z0-9_-]{1,20}".prop_filter("reserved", |value| not_reserved(value))
+}
+
+fn resource_type() -> impl Strategy<Value = String> {
+    prop::sample::select(RESOURCE_TYPES.to_vec()).prop_map(str::to_string)
+}
+
+fn resource_id() -> impl Strategy<Value = String> {
+    "[a-zA-Z0-9][a-zA-Z0-9_.-]{0,30}".prop_filter("reserved", |value| not_reserved(value))
+}
+
+fn version() -> impl Strategy<Value = String> {
+    prop_oneof![
+        (0u32..20, 0u32..20).prop_map(|(major, minor)| format!("v{major}.{minor}")),
+        (0u32..20, 0u32..20, 0u32..20).prop_map(|(major, minor, patch)| format!("{major}.{minor}.{patch}")),
+        Just("latest".to_string()),
+        "[a-zA-Z0-9][a-zA-Z0-9.-]{0,15}".prop_filter("reserved", |value| not_reserved(value)),
+    ]
+}
+
+/// Components of a valid TRN
+fn components() -> impl Strategy<Value = [String; 5]> {
+    (platform(), scope(), resource_type(), resource_id(), version()).prop_map(|(p, s, t, i, v)| [p, s, t, i, v])
+}
+
+fn valid_trn() -> impl Strategy<Value = String> {
+    components().prop_map(|parts| format!("trn:{}", parts.join(":")))
+}
+
+/// Strings close to valid TRNs, most of them invalid
+fn mutated_trn() -> impl Strategy<Value = String> {
+    let edits = prop_oneof![
+        // Insert a character
+        (any::<prop::sample::Index>(), prop::sample::select(vec![':', ' ', '*', '/', '@', '_', '.', '-', 'é', '\0']))
+            .prop_map(|(at, c)| Edit::Insert(at, c)),
+        // Delete a character
+        any::<prop::sample::Index>().prop_map(Edit::Delete),
+        // Empty, reserve or uppercase a component
+        (0usize..6, prop::sample::select(vec!["", "null", "trn", "TRN", "x", "*"]))
+            .prop_map(|(component, value)| Edit::Replace(component, value)),
+    ];
+    (valid_trn(), edits).prop_map(|(trn, edit)| edit.apply(&trn))
+}
+
+#[derive(Debug, Clone)]
+enum Edit {
+    Insert(prop::sample::Index, char),
+    Delete(prop::sample::Index),
+    Replace(usize, &'static str),
+}
+
+impl Edit {
+    fn apply(&self, trn: &str) -> String {
+        let mut chars: Vec<char> = trn.chars().collect();
+        match self {
+            Self::Insert(at, c) => chars.insert(at.index(chars.len() + 1), *c),
+            Self::Delete(at) => {
+                chars.remove(at.index(chars.len()));
+            }
+            Self::Replace(component, value) => {
+                let mut parts: Vec<&str> = trn.split(':').collect();
+                parts[*component] = value;
+                return parts.join(":");
+            }
+        }
+        chars.into_iter().collect()
+    }
+}
+
+/// How a pattern component is derived from a TRN component
+#[derive(Debug, Clone, Copy)]
+enum Wildcard {
+    /// Keep the component
+    Exact,
+    /// Replace it with `*`
+    Any,
+    /// Keep its first character, followed by `*`
+    Prefix,
+}
+
+fn wildcards() -> impl Strategy<Value = [Wildcard; 5]> {
+    let wildcard = prop_oneof![Just(Wildcard::Exact), Just(Wildcard::Any), Just(Wildcard::Prefix)];
+    [wildcard.clone(), wildcard.clone(), wildcard.clone(), wildcard.clone(), wildcard]
+}
+
+/// A pattern for `trn`, with its components replaced as in `wildcards`
+fn pattern(trn: &str, wildcards: [Wildcard; 5]) -> String {
+    let parts: Vec<&str> = trn.split(':').collect();
+    let components = parts[1..].iter().zip(wildcards).map(|(part, wildcard)| match wildcard {
+        Wildcard::Exact => (*part).to_string(),
+        Wildcard::Any => "*".to_string(),
+        Wildcard::Prefix => format!("{}*", &part[..1]),
+    });
+    std::iter::once("trn".to_string()).chain(components).collect::<Vec<_>>().join(":")
+}
+
+proptest! {
+    #[test]
+    fn valid_trns_round_trip(trn in valid_trn()) {
+        let parsed = Trn::parse(&trn).unwrap();
+        prop_assert_eq!(parsed.to_string(), trn.clone());
+        prop_assert_eq!(Trn::parse(&parsed.to_string()).unwrap(), parsed.clone());
+        prop_assert_eq!(TrnRef::parse(&trn).unwrap().to_owned(), parsed.clone());
+        prop_assert_eq!(Trn::from_bytes(&parsed.to_bytes()).unwrap(), parsed.clone());
+        prop_assert!(is_valid_trn(&trn));
+        prop_assert!(parsed.validate().is_ok());
+    }
+
+    #[test]
+    fn parsers_agree_on_near_misses(input in prop_oneof![mutated_trn(), ".{0,40}"]) {
+        let parsed = Trn::parse(&input);
+        prop_assert_eq!(parsed.is_ok(), validate_trn_string(&input).is_ok(), "{}", input);
+        prop_assert_eq!(parsed.is_ok(), is_valid_trn(&input), "{}", input);
+        prop_assert_eq!(parsed.is_ok(), TrnRef::parse(&input).is_ok(), "{}", input);
+        if let Ok(trn) = parsed {
+            prop_assert_eq!(trn.to_string(), input);
+        }
+    }
+
+    #[test]
+    fn pattern_matchers_agree(trn in valid_trn(), other in valid_trn(), wildcards in wildcards()) {
+        // A pattern derived from the TRN itself matches it
+        let own = pattern(&trn, wildcards);
+        prop_assert!(TrnRef::parse(&trn).unwrap().matches_pattern(&own), "{} !~ {}", trn, own);
+        prop_assert!(TrnMatcher::new(&own).unwrap().matches(&trn));
+        prop_assert!(TrnPatternSet::new([&own]).unwrap().matches_str(&trn));
+
+        // Every matcher gives the same answer for another TRN's pattern
+        let foreign = pattern(&other, wildcards);
+        let expected = TrnRef::parse(&trn).unwrap().matches_pattern(&foreign);
+        prop_assert_eq!(TrnMatcher::new(&foreign).unwrap().matches(&trn), expected, "{} ~ {}", trn, foreign);
+        prop_assert_eq!(Trn::parse(&trn).unwrap().matches_pattern(&foreign), expected);
+        prop_assert_eq!(TrnPatternSet::new([&foreign]).unwrap().matches_str(&trn), expected, "{} ~ {}", trn, foreign);
+    }
+
+    #[test]
+    fn urls_round_trip(trn in valid_trn()) {
+        let trn = Trn::parse(&trn).unwrap();
+        prop_assert_eq!(url_to_trn(&trn.to_url().unwrap()).unwrap(), trn.clone());
+        prop_assert_eq!(Trn::from_url(&trn.to_url().unwrap()).unwrap(), trn.clone());
+
+        let scheme = UrlScheme::builder("https://api.example.com/").build().unwrap();
+        let url = trn.to_http_url("https://api.example.com/").unwrap();
+        prop_assert_eq!(trn.to_url_with(&scheme).unwrap(), url.clone());
+        prop_assert_eq!(scheme.url_to_trn(&url).unwrap(), trn);
+    }
+}